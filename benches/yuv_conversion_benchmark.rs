@@ -1,6 +1,8 @@
 //! Benchmark YUV to RGB conversion (SIMD vs scalar)
 
-use zenavif::yuv_convert::{YuvMatrix, YuvRange, yuv420_to_rgb8};
+use zenavif::yuv_convert::{
+    YuvCoefficients, YuvMatrix, YuvRange, coefficients, yuv420_to_rgb8, yuv420_to_rgb8_strip,
+};
 use zenbench::criterion_compat::*;
 use zenbench::{criterion_group, criterion_main};
 
@@ -56,5 +58,174 @@ fn bench_yuv420_conversion(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_yuv420_conversion);
+/// Batch of 64×64 thumbnails in mixed colour spaces: per-call setup is a
+/// visible share of the work at this size. `derive` vs `cached` isolates
+/// the coefficient setup that the cache removes from every call.
+fn bench_thumbnail_setup(c: &mut Criterion) {
+    const SIZE: usize = 64;
+    let spaces = [
+        (YuvMatrix::Bt601, YuvRange::Limited),
+        (YuvMatrix::Bt709, YuvRange::Full),
+        (YuvMatrix::Bt2020, YuvRange::Limited),
+    ];
+    let y_plane = vec![128u8; SIZE * SIZE];
+    let uv_plane = vec![128u8; (SIZE / 2) * (SIZE / 2)];
+
+    let mut group = c.benchmark_group("thumbnail_64x64");
+    group.throughput(Throughput::Elements(spaces.len() as u64));
+
+    group.bench_function("coefficients_derive", |b| {
+        b.iter(|| {
+            for (matrix, range) in spaces {
+                black_box(YuvCoefficients::derive(
+                    black_box(matrix),
+                    black_box(range),
+                    8,
+                ));
+            }
+        });
+    });
+    group.bench_function("coefficients_cached", |b| {
+        b.iter(|| {
+            for (matrix, range) in spaces {
+                black_box(coefficients(black_box(matrix), black_box(range), 8));
+            }
+        });
+    });
+    group.bench_function("convert_full_frame", |b| {
+        b.iter(|| {
+            for (matrix, range) in spaces {
+                black_box(yuv420_to_rgb8(
+                    &y_plane,
+                    SIZE,
+                    &uv_plane,
+                    SIZE / 2,
+                    &uv_plane,
+                    SIZE / 2,
+                    SIZE,
+                    SIZE,
+                    range,
+                    matrix,
+                ));
+            }
+        });
+    });
+    // Strip conversion pays setup once per strip rather than per image.
+    group.bench_function("convert_8_row_strips", |b| {
+        let mut out = vec![rgb::RGB8::default(); SIZE * 8];
+        b.iter(|| {
+            for (matrix, range) in spaces {
+                for y_start in (0..SIZE).step_by(8) {
+                    yuv420_to_rgb8_strip(
+                        &y_plane,
+                        SIZE,
+                        &uv_plane,
+                        SIZE / 2,
+                        &uv_plane,
+                        SIZE / 2,
+                        SIZE,
+                        SIZE,
+                        y_start,
+                        8,
+                        range,
+                        matrix,
+                        &mut out,
+                    );
+                }
+                black_box(&out);
+            }
+        });
+    });
+
+    group.finish();
+}
+
+/// AVX2 against AVX-512BW for the integer converters at 1080p. The AVX-512
+/// rows only appear when built with `--features avx512` on a CPU that has
+/// it; the gap is largest where 512-bit ops don't downclock (Ice Lake
+/// server, Sapphire Rapids, Zen 4).
+fn bench_integer_tiers(c: &mut Criterion) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        use archmage::prelude::*;
+        use zenavif::yuv_convert_fast::yuv420_to_rgb8_fast;
+        use zenavif::yuv_convert_libyuv_simd::yuv420_to_rgb8_simd;
+
+        let (width, height) = (1920, 1080);
+        let y_plane: Vec<u8> = (0..width * height).map(|i| (i * 7 % 256) as u8).collect();
+        let cw = width / 2;
+        let u_plane: Vec<u8> = (0..cw * height / 2).map(|i| (i * 13 % 256) as u8).collect();
+        let v_plane: Vec<u8> = (0..cw * height / 2).map(|i| (i * 5 % 256) as u8).collect();
+
+        let mut group = c.benchmark_group("yuv420_integer_1080p");
+        group.throughput(Throughput::Elements((width * height) as u64));
+
+        if let Some(token) = Desktop64::summon() {
+            group.bench_function("fast_avx2", |b| {
+                b.iter(|| {
+                    yuv420_to_rgb8_fast(
+                        token, &y_plane, width, &u_plane, cw, &v_plane, cw, width, height,
+                    )
+                });
+            });
+            group.bench_function("libyuv_avx2", |b| {
+                b.iter(|| {
+                    yuv420_to_rgb8_simd(
+                        token,
+                        &y_plane,
+                        width,
+                        &u_plane,
+                        cw,
+                        &v_plane,
+                        cw,
+                        width,
+                        height,
+                        YuvRange::Full,
+                        YuvMatrix::Bt709,
+                    )
+                });
+            });
+        }
+
+        #[cfg(feature = "avx512")]
+        if let Some(token) = X64V4Token::summon() {
+            group.bench_function("fast_avx512", |b| {
+                b.iter(|| {
+                    zenavif::yuv_convert_fast::yuv420_to_rgb8_fast_avx512(
+                        token, &y_plane, width, &u_plane, cw, &v_plane, cw, width, height,
+                    )
+                });
+            });
+            // Dispatches to AVX-512 on this CPU.
+            group.bench_function("libyuv_avx512", |b| {
+                b.iter(|| {
+                    zenavif::yuv_convert_libyuv::yuv420_to_rgb8(
+                        &y_plane,
+                        width,
+                        &u_plane,
+                        cw,
+                        &v_plane,
+                        cw,
+                        width,
+                        height,
+                        YuvRange::Full,
+                        YuvMatrix::Bt709,
+                    )
+                });
+            });
+        } else {
+            eprintln!("✗ AVX-512 not available - skipping AVX-512 tiers");
+        }
+
+        group.finish();
+    }
+    let _ = c;
+}
+
+criterion_group!(
+    benches,
+    bench_yuv420_conversion,
+    bench_thumbnail_setup,
+    bench_integer_tiers
+);
 criterion_main!(benches);
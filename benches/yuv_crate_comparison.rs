@@ -71,5 +71,65 @@ fn bench_comparison(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_comparison);
+fn bench_comparison_10bit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("yuv420_10bit_comparison");
+
+    let width: usize = 1920;
+    let height: usize = 1080;
+    let cw = width.div_ceil(2);
+    let y_plane: Vec<u16> = (0..width * height).map(|i| (i % 1024) as u16).collect();
+    let u_plane = vec![400u16; cw * height.div_ceil(2)];
+    let v_plane = vec![600u16; cw * height.div_ceil(2)];
+    let mut rgb = vec![0u16; width * height * 3];
+
+    group.throughput(Throughput::Elements((width * height) as u64));
+
+    group.bench_function("zenavif_simd", |b| {
+        b.iter(|| {
+            zenavif::yuv_convert_16bit::yuv_to_rgb16(
+                black_box(&y_plane),
+                width,
+                black_box(&u_plane),
+                cw,
+                black_box(&v_plane),
+                cw,
+                width,
+                height,
+                (1, 1),
+                10,
+                OurYuvRange::Limited,
+                OurYuvMatrix::Bt709,
+                3,
+                &mut rgb,
+            )
+        });
+    });
+
+    group.bench_function("yuv_crate", |b| {
+        let yuv_image = YuvPlanarImage {
+            y_plane: &y_plane,
+            y_stride: width as u32,
+            u_plane: &u_plane,
+            u_stride: cw as u32,
+            v_plane: &v_plane,
+            v_stride: cw as u32,
+            width: width as u32,
+            height: height as u32,
+        };
+        b.iter(|| {
+            yuv::i010_to_rgb10(
+                black_box(&yuv_image),
+                black_box(&mut rgb),
+                width as u32 * 3,
+                YuvRange::Limited,
+                YuvStandardMatrix::Bt709,
+            )
+            .unwrap();
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_comparison, bench_comparison_10bit);
 criterion_main!(benches);
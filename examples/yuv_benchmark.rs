@@ -1,10 +1,9 @@
 //! Quick comparison: Our SIMD vs yuv crate
 
-use archmage::prelude::*;
 use std::time::Instant;
 use yuv::{YuvPlanarImage, YuvRange, YuvStandardMatrix, yuv420_to_rgb};
 use zenavif::yuv_convert::{YuvMatrix as OurYuvMatrix, YuvRange as OurYuvRange, yuv420_to_rgb8};
-use zenavif::yuv_convert_fast::yuv420_to_rgb8_fast;
+use zenavif::yuv_convert_fast::yuv420_to_rgb8_fast_auto;
 
 fn main() {
     let width = 1920;
@@ -56,45 +55,41 @@ fn main() {
     let our_avg = our_time.as_micros() as f64 / iterations as f64 / 1000.0;
 
     // Warmup fast version
-    if let Some(token) = Desktop64::summon() {
-        for _ in 0..10 {
-            let _ = yuv420_to_rgb8_fast(
-                token,
-                &y_plane,
-                width,
-                &u_plane,
-                width.div_ceil(2),
-                &v_plane,
-                width.div_ceil(2),
-                width,
-                height,
-            );
-        }
-
-        // Benchmark fast integer version
-        let start = Instant::now();
-        for _ in 0..iterations {
-            let _ = yuv420_to_rgb8_fast(
-                token,
-                &y_plane,
-                width,
-                &u_plane,
-                width.div_ceil(2),
-                &v_plane,
-                width.div_ceil(2),
-                width,
-                height,
-            );
-        }
-        let fast_time = start.elapsed();
-        let fast_avg = fast_time.as_micros() as f64 / iterations as f64 / 1000.0;
+    for _ in 0..10 {
+        let _ = yuv420_to_rgb8_fast_auto(
+            &y_plane,
+            width,
+            &u_plane,
+            width.div_ceil(2),
+            &v_plane,
+            width.div_ceil(2),
+            width,
+            height,
+        );
+    }
 
-        println!(
-            "  zenavif FAST:      {:>8.2} ms  ({:>6.1} Mpixels/s)",
-            fast_avg,
-            (width * height) as f64 / fast_avg / 1000.0
+    // Benchmark fast integer version (AVX2, NEON or scalar)
+    let start = Instant::now();
+    for _ in 0..iterations {
+        let _ = yuv420_to_rgb8_fast_auto(
+            &y_plane,
+            width,
+            &u_plane,
+            width.div_ceil(2),
+            &v_plane,
+            width.div_ceil(2),
+            width,
+            height,
         );
     }
+    let fast_time = start.elapsed();
+    let fast_avg = fast_time.as_micros() as f64 / iterations as f64 / 1000.0;
+
+    println!(
+        "  zenavif FAST:      {:>8.2} ms  ({:>6.1} Mpixels/s)",
+        fast_avg,
+        (width * height) as f64 / fast_avg / 1000.0
+    );
 
     // Warmup yuv crate
     let yuv_image = YuvPlanarImage {
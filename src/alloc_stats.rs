@@ -0,0 +1,204 @@
+//! Allocation accounting per decode stage.
+//!
+//! [`CountingAllocator`] wraps another global allocator and attributes every
+//! allocation to the decode stage running at the time. Install it as the
+//! `#[global_allocator]` of a test binary and compare [`snapshot`]s taken
+//! around a decode to catch per-row or per-pixel `Vec` churn.
+//!
+//! The current stage is process-wide: allocations made by decoder worker
+//! threads count toward the stage that started the work, and concurrent
+//! decodes blur together. Run measured decodes one at a time.
+//!
+//! Only available with the `alloc-stats` feature. Without it, stage markers
+//! compile to nothing.
+
+#[cfg(feature = "alloc-stats")]
+use core::alloc::{GlobalAlloc, Layout};
+#[cfg(feature = "alloc-stats")]
+use core::sync::atomic::{AtomicI64, AtomicU8, AtomicU64, Ordering};
+#[cfg(feature = "alloc-stats")]
+use std::alloc::System;
+
+/// Decode stage that allocations are attributed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Stage {
+    /// Container parsing.
+    Parse = 1,
+    /// AV1 decoding of color and alpha frames.
+    Decode = 2,
+    /// YUV→RGB conversion and alpha merging.
+    Convert = 3,
+}
+
+/// Restores the previous stage when dropped.
+pub(crate) struct StageGuard {
+    #[cfg(feature = "alloc-stats")]
+    previous: u8,
+}
+
+/// Attribute allocations to `stage` until the guard is dropped.
+#[inline]
+pub(crate) fn enter(stage: Stage) -> StageGuard {
+    #[cfg(feature = "alloc-stats")]
+    {
+        StageGuard {
+            previous: CURRENT.swap(stage as u8, Ordering::Relaxed),
+        }
+    }
+    #[cfg(not(feature = "alloc-stats"))]
+    {
+        let _ = stage;
+        StageGuard {}
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+impl Drop for StageGuard {
+    fn drop(&mut self) {
+        CURRENT.store(self.previous, Ordering::Relaxed);
+    }
+}
+
+/// Slot 0 collects allocations made outside any stage.
+#[cfg(feature = "alloc-stats")]
+const SLOTS: usize = 4;
+
+#[cfg(feature = "alloc-stats")]
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+#[cfg(feature = "alloc-stats")]
+static ALLOCATIONS: [AtomicU64; SLOTS] = [const { AtomicU64::new(0) }; SLOTS];
+#[cfg(feature = "alloc-stats")]
+static BYTES: [AtomicU64; SLOTS] = [const { AtomicU64::new(0) }; SLOTS];
+#[cfg(feature = "alloc-stats")]
+static LIVE_BYTES: AtomicI64 = AtomicI64::new(0);
+
+/// Allocation counts for one stage.
+#[cfg(feature = "alloc-stats")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StageStats {
+    /// Number of allocations, including reallocations.
+    pub allocations: u64,
+    /// Bytes requested by those allocations.
+    pub bytes: u64,
+}
+
+/// Cumulative allocation counters, as returned by [`snapshot`].
+#[cfg(feature = "alloc-stats")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocStats {
+    /// Container parsing.
+    pub parse: StageStats,
+    /// AV1 decoding of color and alpha frames.
+    pub decode: StageStats,
+    /// YUV→RGB conversion and alpha merging.
+    pub convert: StageStats,
+    /// Everything outside a decode stage.
+    pub other: StageStats,
+    /// Bytes allocated and not yet freed, across all stages.
+    pub live_bytes: i64,
+}
+
+#[cfg(feature = "alloc-stats")]
+impl AllocStats {
+    /// Counters accumulated since `earlier` was taken.
+    pub fn since(&self, earlier: &AllocStats) -> AllocStats {
+        let diff = |a: StageStats, b: StageStats| StageStats {
+            allocations: a.allocations - b.allocations,
+            bytes: a.bytes - b.bytes,
+        };
+        AllocStats {
+            parse: diff(self.parse, earlier.parse),
+            decode: diff(self.decode, earlier.decode),
+            convert: diff(self.convert, earlier.convert),
+            other: diff(self.other, earlier.other),
+            live_bytes: self.live_bytes - earlier.live_bytes,
+        }
+    }
+}
+
+/// Read the current counters.
+///
+/// Counters only move while a [`CountingAllocator`] is the global allocator.
+#[cfg(feature = "alloc-stats")]
+pub fn snapshot() -> AllocStats {
+    let slot = |i: usize| StageStats {
+        allocations: ALLOCATIONS[i].load(Ordering::Relaxed),
+        bytes: BYTES[i].load(Ordering::Relaxed),
+    };
+    AllocStats {
+        other: slot(0),
+        parse: slot(Stage::Parse as usize),
+        decode: slot(Stage::Decode as usize),
+        convert: slot(Stage::Convert as usize),
+        live_bytes: LIVE_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+/// Global allocator adapter that feeds [`snapshot`].
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOC: zenavif::alloc_stats::CountingAllocator =
+///     zenavif::alloc_stats::CountingAllocator::new(std::alloc::System);
+/// ```
+#[cfg(feature = "alloc-stats")]
+#[derive(Debug, Default)]
+pub struct CountingAllocator<A = System> {
+    inner: A,
+}
+
+#[cfg(feature = "alloc-stats")]
+impl<A> CountingAllocator<A> {
+    /// Wrap `inner`, which does the actual allocating.
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+}
+
+#[cfg(feature = "alloc-stats")]
+fn record(size: usize) {
+    let slot = CURRENT.load(Ordering::Relaxed) as usize;
+    ALLOCATIONS[slot].fetch_add(1, Ordering::Relaxed);
+    BYTES[slot].fetch_add(size as u64, Ordering::Relaxed);
+}
+
+// SAFETY: every call is forwarded unchanged to `inner`; the counters are
+// lock-free atomics and never allocate.
+#[cfg(feature = "alloc-stats")]
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: forwarded with the caller's layout.
+        let ptr = unsafe { self.inner.alloc(layout) };
+        if !ptr.is_null() {
+            record(layout.size());
+            LIVE_BYTES.fetch_add(layout.size() as i64, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: forwarded with the caller's layout.
+        let ptr = unsafe { self.inner.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            record(layout.size());
+            LIVE_BYTES.fetch_add(layout.size() as i64, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: `ptr` came from `inner` with this layout.
+        unsafe { self.inner.dealloc(ptr, layout) };
+        LIVE_BYTES.fetch_sub(layout.size() as i64, Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // SAFETY: `ptr` came from `inner` with this layout.
+        let new_ptr = unsafe { self.inner.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            record(new_size);
+            LIVE_BYTES.fetch_add(new_size as i64 - layout.size() as i64, Ordering::Relaxed);
+        }
+        new_ptr
+    }
+}
@@ -0,0 +1,246 @@
+//! Audio track access for AVIF image sequences.
+//!
+//! AVIS files are ISOBMFF movies and may carry a sound track next to the
+//! color and alpha tracks. zenavif doesn't decode audio, but players can
+//! pull the raw samples out here and hand them to an audio decoder instead
+//! of silently dropping them.
+
+use crate::bmff::{boxes, child, malformed, read_u8, read_u16, read_u32, read_u64, table};
+use crate::error::Result;
+
+/// An audio track extracted from an AVIF image sequence.
+#[derive(Debug, Clone)]
+pub struct AudioTrack {
+    /// Sample entry fourcc, e.g. `*b"mp4a"` or `*b"Opus"`.
+    pub codec: [u8; 4],
+    /// Channel count from the audio sample entry.
+    pub channel_count: u16,
+    /// Sample rate in Hz from the audio sample entry.
+    pub sample_rate: u32,
+    /// Media timescale (ticks per second) for [`AudioSample::duration`].
+    pub timescale: u32,
+    /// Sample entry payload after the box header, including codec
+    /// configuration boxes such as `esds` or `dOps`.
+    pub sample_entry: Vec<u8>,
+    /// Compressed samples in decode order.
+    pub samples: Vec<AudioSample>,
+}
+
+/// One compressed audio sample.
+#[derive(Debug, Clone)]
+pub struct AudioSample {
+    /// Raw sample bytes as stored in `mdat`.
+    pub data: Vec<u8>,
+    /// Duration in [`AudioTrack::timescale`] ticks.
+    pub duration: u32,
+}
+
+/// Whether the file contains an audio (`soun`) track.
+///
+/// Only walks the `moov` box headers; sample tables are not read.
+pub fn has_audio(data: &[u8]) -> bool {
+    find_audio_trak(data).ok().flatten().is_some()
+}
+
+/// Extract the first audio track of an AVIF image sequence.
+///
+/// Returns `Ok(None)` for stills and sequences without a sound track.
+pub fn extract_audio_track(data: &[u8]) -> Result<Option<AudioTrack>> {
+    let Some(trak) = find_audio_trak(data)? else {
+        return Ok(None);
+    };
+    let mdia = child(trak, b"mdia")?.ok_or_else(malformed)?;
+    let mdhd = child(mdia, b"mdhd")?.ok_or_else(malformed)?;
+    let timescale = if read_u8(mdhd, 0)? == 1 {
+        read_u32(mdhd, 20)?
+    } else {
+        read_u32(mdhd, 12)?
+    };
+    let minf = child(mdia, b"minf")?.ok_or_else(malformed)?;
+    let stbl = child(minf, b"stbl")?.ok_or_else(malformed)?;
+
+    // stsd: first sample entry is the codec
+    let stsd = child(stbl, b"stsd")?.ok_or_else(malformed)?;
+    let (codec, entry) = boxes(stsd.get(8..).ok_or_else(malformed)?)
+        .next()
+        .ok_or_else(malformed)??;
+    // AudioSampleEntry: 8 bytes SampleEntry, 8 reserved, channelcount,
+    // samplesize, 4 reserved, 16.16 samplerate
+    let channel_count = read_u16(entry, 16)?;
+    let sample_rate = read_u32(entry, 24)? >> 16;
+
+    let sizes = sample_sizes(child(stbl, b"stsz")?.ok_or_else(malformed)?)?;
+    let durations = sample_durations(child(stbl, b"stts")?.ok_or_else(malformed)?, sizes.len())?;
+    let chunk_offsets = match child(stbl, b"stco")? {
+        Some(stco) => table(stco, 4)?
+            .map(|e| read_u32(e, 0).map(u64::from))
+            .collect::<Result<Vec<_>>>()?,
+        None => table(child(stbl, b"co64")?.ok_or_else(malformed)?, 8)?
+            .map(|e| read_u64(e, 0))
+            .collect::<Result<Vec<_>>>()?,
+    };
+    let stsc = table(child(stbl, b"stsc")?.ok_or_else(malformed)?, 12)?
+        .map(|e| Ok((read_u32(e, 0)?, read_u32(e, 4)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut samples = Vec::with_capacity(sizes.len());
+    let mut remaining = sizes.iter().zip(&durations);
+    for (chunk_index, &chunk_offset) in chunk_offsets.iter().enumerate() {
+        let chunk_number = chunk_index as u32 + 1;
+        let per_chunk = stsc
+            .iter()
+            .take_while(|&&(first_chunk, _)| first_chunk <= chunk_number)
+            .last()
+            .map_or(0, |&(_, count)| count);
+        let mut offset = usize::try_from(chunk_offset).map_err(|_| malformed())?;
+        for _ in 0..per_chunk {
+            let Some((&size, &duration)) = remaining.next() else {
+                break;
+            };
+            let end = offset.checked_add(size as usize).ok_or_else(malformed)?;
+            let bytes = data.get(offset..end).ok_or_else(malformed)?;
+            samples.push(AudioSample {
+                data: bytes.to_vec(),
+                duration,
+            });
+            offset = end;
+        }
+    }
+
+    Ok(Some(AudioTrack {
+        codec,
+        channel_count,
+        sample_rate,
+        timescale,
+        sample_entry: entry.to_vec(),
+        samples,
+    }))
+}
+
+/// Payload of the first `trak` whose handler is `soun`.
+fn find_audio_trak(data: &[u8]) -> Result<Option<&[u8]>> {
+    let Some(moov) = child(data, b"moov")? else {
+        return Ok(None);
+    };
+    for item in boxes(moov) {
+        let (kind, trak) = item?;
+        if &kind != b"trak" {
+            continue;
+        }
+        let handler = child(trak, b"mdia")?
+            .map(|mdia| child(mdia, b"hdlr"))
+            .transpose()?
+            .flatten();
+        // FullBox header, pre_defined, then handler_type
+        if let Some(hdlr) = handler
+            && hdlr.get(8..12) == Some(&b"soun"[..])
+        {
+            return Ok(Some(trak));
+        }
+    }
+    Ok(None)
+}
+
+/// `stsz`: per-sample sizes, expanding a constant size.
+fn sample_sizes(stsz: &[u8]) -> Result<Vec<u32>> {
+    let constant = read_u32(stsz, 4)?;
+    let count = read_u32(stsz, 8)? as usize;
+    if constant != 0 {
+        return Ok(vec![constant; count]);
+    }
+    let entries = stsz.get(12..).ok_or_else(malformed)?;
+    if entries.len() / 4 < count {
+        return Err(malformed());
+    }
+    (0..count).map(|i| read_u32(entries, i * 4)).collect()
+}
+
+/// `stts`: run-length decoded sample durations.
+fn sample_durations(stts: &[u8], sample_count: usize) -> Result<Vec<u32>> {
+    let mut durations = Vec::with_capacity(sample_count);
+    for entry in table(stts, 8)? {
+        let run = read_u32(entry, 0)? as usize;
+        let delta = read_u32(entry, 4)?;
+        let run = run.min(sample_count - durations.len());
+        durations.extend(std::iter::repeat_n(delta, run));
+    }
+    // Samples past the table keep a zero duration
+    durations.resize(sample_count, 0);
+    Ok(durations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bx(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn full(kind: &[u8; 4], words: &[u32]) -> Vec<u8> {
+        let payload: Vec<u8> = [0u32]
+            .iter()
+            .chain(words)
+            .flat_map(|w| w.to_be_bytes())
+            .collect();
+        bx(kind, &payload)
+    }
+
+    /// A movie with one `soun` track of three samples in two chunks,
+    /// followed by `mdat`. `mdat_offset` is where the sample bytes start.
+    fn movie(mdat_offset: u32) -> Vec<u8> {
+        let mut entry = vec![0u8; 28];
+        entry[16..18].copy_from_slice(&2u16.to_be_bytes());
+        entry[24..28].copy_from_slice(&(48000u32 << 16).to_be_bytes());
+        let mut stsd = vec![0u8; 4];
+        stsd.extend_from_slice(&1u32.to_be_bytes());
+        stsd.extend(bx(b"Opus", &entry));
+        let stbl = [
+            bx(b"stsd", &stsd),
+            full(b"stts", &[1, 3, 960]),
+            full(b"stsc", &[1, 1, 2, 1]),
+            full(b"stsz", &[0, 3, 2, 3, 1]),
+            full(b"stco", &[2, mdat_offset, mdat_offset + 5]),
+        ]
+        .concat();
+        let mdia = [
+            full(b"mdhd", &[0, 0, 48000, 2880]),
+            full(b"hdlr", &[0, u32::from_be_bytes(*b"soun"), 0, 0, 0]),
+            bx(b"minf", &bx(b"stbl", &stbl)),
+        ]
+        .concat();
+        bx(b"moov", &bx(b"trak", &bx(b"mdia", &mdia)))
+    }
+
+    #[test]
+    fn extracts_samples_from_chunks() {
+        let probe = movie(0);
+        let mut data = movie(probe.len() as u32 + 8);
+        data.extend(bx(b"mdat", &[1, 1, 2, 2, 2, 3]));
+
+        assert!(has_audio(&data));
+        let track = extract_audio_track(&data).unwrap().unwrap();
+        assert_eq!(&track.codec, b"Opus");
+        assert_eq!(track.channel_count, 2);
+        assert_eq!(track.sample_rate, 48000);
+        assert_eq!(track.timescale, 48000);
+        let samples: Vec<_> = track.samples.iter().map(|s| s.data.clone()).collect();
+        assert_eq!(samples, [vec![1, 1], vec![2, 2, 2], vec![3]]);
+        assert!(track.samples.iter().all(|s| s.duration == 960));
+    }
+
+    #[test]
+    fn no_audio_without_sound_track() {
+        assert!(!has_audio(&bx(b"ftyp", b"avifmif1")));
+        assert!(extract_audio_track(&[]).unwrap().is_none());
+    }
+
+    #[test]
+    fn out_of_range_chunk_is_an_error() {
+        let data = movie(1 << 20);
+        assert!(extract_audio_track(&data).is_err());
+    }
+}
@@ -0,0 +1,1205 @@
+//! Minimal AV1 OBU header parser.
+//!
+//! Walks the OBUs of an AV1 sample and parses the sequence header and the
+//! uncompressed header of intra frames far enough to read the in-loop filter
+//! parameters the encoder chose: deblocking levels, CDEF strengths and loop
+//! restoration types. Tile data is never touched.
+//!
+//! Inter frames depend on reference-frame state that this parser doesn't
+//! track, so their headers are skipped. Section numbers refer to the AV1
+//! bitstream specification.
+
+const OBU_SEQUENCE_HEADER: u8 = 1;
+const OBU_FRAME_HEADER: u8 = 3;
+const OBU_FRAME: u8 = 6;
+
+const KEY_FRAME: u32 = 0;
+const INTRA_ONLY_FRAME: u32 = 2;
+const SWITCH_FRAME: u32 = 3;
+
+const SELECT_SCREEN_CONTENT_TOOLS: u32 = 2;
+const SELECT_INTEGER_MV: u32 = 2;
+
+const SEG_FEATURE_BITS: [u32; 8] = [8, 6, 6, 6, 6, 3, 0, 0];
+const SEG_FEATURE_SIGNED: [bool; 8] = [true, true, true, true, true, false, false, false];
+
+/// Parsing failed: truncated data or a header using syntax we don't handle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct HeaderError;
+
+type Result<T> = core::result::Result<T, HeaderError>;
+
+/// Loop restoration filter signalled for one plane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestorationType {
+    /// Restoration disabled.
+    None,
+    /// Wiener filter for every unit.
+    Wiener,
+    /// Self-guided filter for every unit.
+    SelfGuided,
+    /// Chosen per restoration unit.
+    Switchable,
+}
+
+/// In-loop filter parameters the encoder chose for one intra frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameFilterStats {
+    /// Base quantizer index (0-255).
+    pub base_q_idx: u8,
+    /// Effective quantizer index of each active segment, when segmentation
+    /// adjusts it; empty otherwise.
+    pub segment_q_idx: Vec<u8>,
+    /// Every segment is coded losslessly; all filters are then off.
+    pub lossless: bool,
+    /// Deblocking levels (0-63): luma vertical, luma horizontal, Cb, Cr.
+    pub deblock_level: [u8; 4],
+    /// Deblocking sharpness (0-7).
+    pub deblock_sharpness: u8,
+    /// CDEF settings, or `None` when CDEF is disabled for the frame.
+    pub cdef: Option<CdefStats>,
+    /// Loop restoration type for Y, Cb, Cr.
+    pub restoration: [RestorationType; 3],
+    /// Loop restoration unit size in samples for Y, Cb, Cr (0 = unused).
+    pub restoration_unit_size: [u16; 3],
+}
+
+/// CDEF settings for one frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CdefStats {
+    /// Filter damping (3-6).
+    pub damping: u8,
+    /// Number of bits used to pick a preset per 64x64 block.
+    pub bits: u8,
+    /// Luma `(primary, secondary)` strengths, one pair per preset.
+    pub y_strengths: Vec<(u8, u8)>,
+    /// Chroma `(primary, secondary)` strengths; empty for monochrome.
+    pub uv_strengths: Vec<(u8, u8)>,
+}
+
+/// The subset of the sequence header needed to parse frame headers.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SequenceHeader {
+    pub seq_profile: u8,
+    pub seq_level_idx_0: u8,
+    pub seq_tier_0: bool,
+    pub high_bitdepth: bool,
+    pub twelve_bit: bool,
+    pub mono_chrome: bool,
+    pub chroma_sample_position: u8,
+    /// CICP from `color_config`, 2 (unspecified) when not described.
+    pub color_primaries: u8,
+    pub transfer_characteristics: u8,
+    pub matrix_coefficients: u8,
+    pub full_range: bool,
+    pub max_frame_width: u32,
+    pub max_frame_height: u32,
+    /// The complete sequence header OBU, header included, for `av1C`.
+    pub obu: Vec<u8>,
+    reduced_still_picture_header: bool,
+    decoder_model_info_present: bool,
+    equal_picture_interval: bool,
+    buffer_removal_time_length: u32,
+    frame_presentation_time_length: u32,
+    operating_points: Vec<OperatingPoint>,
+    frame_width_bits: u32,
+    frame_height_bits: u32,
+    frame_id_numbers_present: bool,
+    frame_id_length: u32,
+    use_128x128_superblock: bool,
+    force_screen_content_tools: u32,
+    force_integer_mv: u32,
+    order_hint_bits: u32,
+    enable_order_hint: bool,
+    enable_superres: bool,
+    enable_cdef: bool,
+    enable_restoration: bool,
+    num_planes: u32,
+    pub subsampling_x: bool,
+    pub subsampling_y: bool,
+    separate_uv_delta_q: bool,
+    film_grain_params_present: bool,
+}
+
+impl SequenceHeader {
+    /// A header for a `width`×`height` stream with everything else default.
+    #[cfg(test)]
+    pub(crate) fn with_size(width: u32, height: u32) -> Self {
+        Self {
+            max_frame_width: width,
+            max_frame_height: height,
+            ..Self::default()
+        }
+    }
+
+    /// `AV1CodecConfigurationRecord` (AV1-ISOBMFF §2.3) carrying this
+    /// sequence header as its only config OBU.
+    pub(crate) fn av1c(&self) -> Vec<u8> {
+        let flags = ((self.seq_tier_0 as u8) << 7)
+            | ((self.high_bitdepth as u8) << 6)
+            | ((self.twelve_bit as u8) << 5)
+            | ((self.mono_chrome as u8) << 4)
+            | ((self.subsampling_x as u8) << 3)
+            | ((self.subsampling_y as u8) << 2)
+            | self.chroma_sample_position;
+        let mut out = vec![
+            0x81, // marker + version 1
+            (self.seq_profile << 5) | self.seq_level_idx_0,
+            flags,
+            0, // no initial_presentation_delay
+        ];
+        out.extend_from_slice(&self.obu);
+        out
+    }
+
+    /// Index of the operating point that decodes the fewest spatial layers.
+    ///
+    /// `None` unless some operating point leaves out a spatial layer that
+    /// another one decodes. Among equals the highest index wins, since lower
+    /// indices conventionally carry more.
+    pub(crate) fn lowest_resolution_operating_point(&self) -> Option<u8> {
+        let spatial_layers = |op: &OperatingPoint| ((op.idc >> 8) & 0xf).count_ones();
+        let points = || {
+            self.operating_points
+                .iter()
+                .enumerate()
+                .filter(|(_, op)| op.idc != 0)
+        };
+        let most = points().map(|(_, op)| spatial_layers(op)).max()?;
+        let (index, op) = points().rev().min_by_key(|(_, op)| spatial_layers(op))?;
+        (spatial_layers(op) < most).then_some(index as u8)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct OperatingPoint {
+    idc: u32,
+    decoder_model_present: bool,
+}
+
+/// Parse the filter parameters of every intra frame header in `data`.
+///
+/// `seq` carries the sequence header between calls, since animation samples
+/// after the first usually omit it. Frame headers seen before any sequence
+/// header are skipped, as are inter frames and `show_existing_frame` headers.
+pub(crate) fn parse_frame_filters(
+    data: &[u8],
+    seq: &mut Option<SequenceHeader>,
+) -> Result<Vec<FrameFilterStats>> {
+    let mut frames = Vec::new();
+    for obu in obus(data) {
+        let obu = obu?;
+        match obu.obu_type {
+            OBU_SEQUENCE_HEADER => *seq = Some(parse_sequence_header(&obu)?),
+            OBU_FRAME_HEADER | OBU_FRAME => {
+                if let Some(seq) = seq.as_ref()
+                    && let Some(header) = parse_frame_header(&obu, seq)?
+                {
+                    frames.push(header.filters);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(frames)
+}
+
+/// Whether `data` starts a random access point: its first frame header
+/// codes a shown key frame.
+///
+/// Updates `seq` like [`parse_frame_filters`]. Samples without a frame
+/// header, or seen before any sequence header, are not sync samples.
+pub(crate) fn is_sync_sample(data: &[u8], seq: &mut Option<SequenceHeader>) -> Result<bool> {
+    for obu in obus(data) {
+        let obu = obu?;
+        match obu.obu_type {
+            OBU_SEQUENCE_HEADER => *seq = Some(parse_sequence_header(&obu)?),
+            OBU_FRAME_HEADER | OBU_FRAME => {
+                let Some(seq) = seq.as_ref() else {
+                    return Ok(false);
+                };
+                if seq.reduced_still_picture_header {
+                    return Ok(true);
+                }
+                let mut r = BitReader::new(obu.payload);
+                let show_existing_frame = r.f(1)? == 1;
+                let frame_type = r.f(2)?;
+                let show_frame = r.f(1)? == 1;
+                return Ok(!show_existing_frame && frame_type == KEY_FRAME && show_frame);
+            }
+            _ => {}
+        }
+    }
+    Ok(false)
+}
+
+/// Overwrite the film grain seed of every intra frame in `data` that
+/// applies grain, returning how many were rewritten.
+///
+/// The seed is a fixed-width field, so the sample keeps its size and every
+/// other bit. Updates `seq` like [`parse_frame_filters`]; inter frames and
+/// frames seen before any sequence header keep their seed.
+pub(crate) fn override_grain_seed(
+    data: &mut [u8],
+    seq: &mut Option<SequenceHeader>,
+    seed: u16,
+) -> Result<usize> {
+    let mut seed_bits = Vec::new();
+    let mut obu_start = 0;
+    for obu in obus(data) {
+        let obu = obu?;
+        match obu.obu_type {
+            OBU_SEQUENCE_HEADER => *seq = Some(parse_sequence_header(&obu)?),
+            OBU_FRAME_HEADER | OBU_FRAME => {
+                if let Some(seq) = seq.as_ref()
+                    && let Some(header) = parse_frame_header(&obu, seq)?
+                    && let Some(bit) = header.grain_seed_bit
+                {
+                    let payload_start = obu_start + obu.raw.len() - obu.payload.len();
+                    seed_bits.push(payload_start * 8 + bit);
+                }
+            }
+            _ => {}
+        }
+        obu_start += obu.raw.len();
+    }
+    for &start in &seed_bits {
+        for i in 0..16 {
+            let (byte, shift) = ((start + i) / 8, 7 - (start + i) % 8);
+            let bit = ((seed >> (15 - i)) & 1) as u8;
+            data[byte] = (data[byte] & !(1 << shift)) | (bit << shift);
+        }
+    }
+    Ok(seed_bits.len())
+}
+
+/// What [`parse_frame_header`] reads from an intra frame header.
+struct IntraFrameHeader {
+    filters: FrameFilterStats,
+    /// Bit offset of `grain_seed` in the OBU payload, when the frame
+    /// applies film grain.
+    grain_seed_bit: Option<usize>,
+}
+
+/// One OBU: its type, layer ids, payload, and the raw bytes it spans.
+struct Obu<'a> {
+    obu_type: u8,
+    temporal_id: u32,
+    spatial_id: u32,
+    payload: &'a [u8],
+    raw: &'a [u8],
+}
+
+/// Iterate the OBUs of a low-overhead bitstream sample (§5.3).
+fn obus(data: &[u8]) -> impl Iterator<Item = Result<Obu<'_>>> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        if pos >= data.len() {
+            return None;
+        }
+        let obu = read_obu(&data[pos..]);
+        match &obu {
+            Ok(obu) => pos += obu.raw.len(),
+            Err(_) => pos = data.len(),
+        }
+        Some(obu)
+    })
+}
+
+fn read_obu(data: &[u8]) -> Result<Obu<'_>> {
+    let mut r = BitReader::new(data);
+    r.f(1)?; // obu_forbidden_bit
+    let obu_type = r.f(4)? as u8;
+    let has_extension = r.f(1)? == 1;
+    let has_size = r.f(1)? == 1;
+    r.f(1)?; // obu_reserved_1bit
+    let (temporal_id, spatial_id) = if has_extension {
+        let t = r.f(3)?;
+        let s = r.f(2)?;
+        r.f(3)?;
+        (t, s)
+    } else {
+        (0, 0)
+    };
+    let header_len = r.byte_pos();
+    let (size, size_len) = if has_size {
+        leb128(&data[header_len..])?
+    } else {
+        (data.len() - header_len, 0)
+    };
+    let start = header_len + size_len;
+    let end = start.checked_add(size).ok_or(HeaderError)?;
+    Ok(Obu {
+        obu_type,
+        temporal_id,
+        spatial_id,
+        payload: data.get(start..end).ok_or(HeaderError)?,
+        raw: &data[..end],
+    })
+}
+
+/// §5.5.1
+fn parse_sequence_header(obu: &Obu) -> Result<SequenceHeader> {
+    let mut r = BitReader::new(obu.payload);
+    let mut seq = SequenceHeader {
+        obu: obu.raw.to_vec(),
+        ..SequenceHeader::default()
+    };
+
+    let seq_profile = r.f(3)?;
+    seq.seq_profile = seq_profile as u8;
+    r.f(1)?; // still_picture
+    seq.reduced_still_picture_header = r.f(1)? == 1;
+    if seq.reduced_still_picture_header {
+        seq.seq_level_idx_0 = r.f(5)? as u8;
+        seq.operating_points.push(OperatingPoint::default());
+    } else {
+        let timing_info_present = r.f(1)? == 1;
+        if timing_info_present {
+            r.f(32)?; // num_units_in_display_tick
+            r.f(32)?; // time_scale
+            seq.equal_picture_interval = r.f(1)? == 1;
+            if seq.equal_picture_interval {
+                r.uvlc()?;
+            }
+            seq.decoder_model_info_present = r.f(1)? == 1;
+        }
+        let mut buffer_delay_length = 0;
+        if seq.decoder_model_info_present {
+            buffer_delay_length = r.f(5)? + 1;
+            r.f(32)?; // num_units_in_decoding_tick
+            seq.buffer_removal_time_length = r.f(5)? + 1;
+            seq.frame_presentation_time_length = r.f(5)? + 1;
+        }
+        let initial_display_delay_present = r.f(1)? == 1;
+        let count = r.f(5)? + 1;
+        for _ in 0..count {
+            let idc = r.f(12)?;
+            let seq_level_idx = r.f(5)?;
+            let seq_tier = seq_level_idx > 7 && r.f(1)? == 1;
+            if seq.operating_points.is_empty() {
+                seq.seq_level_idx_0 = seq_level_idx as u8;
+                seq.seq_tier_0 = seq_tier;
+            }
+            let mut decoder_model_present = false;
+            if seq.decoder_model_info_present {
+                decoder_model_present = r.f(1)? == 1;
+                if decoder_model_present {
+                    r.f(buffer_delay_length)?; // decoder_buffer_delay
+                    r.f(buffer_delay_length)?; // encoder_buffer_delay
+                    r.f(1)?; // low_delay_mode_flag
+                }
+            }
+            if initial_display_delay_present && r.f(1)? == 1 {
+                r.f(4)?; // initial_display_delay_minus_1
+            }
+            seq.operating_points.push(OperatingPoint {
+                idc,
+                decoder_model_present,
+            });
+        }
+    }
+
+    seq.frame_width_bits = r.f(4)? + 1;
+    seq.frame_height_bits = r.f(4)? + 1;
+    seq.max_frame_width = r.f(seq.frame_width_bits)? + 1;
+    seq.max_frame_height = r.f(seq.frame_height_bits)? + 1;
+    if !seq.reduced_still_picture_header {
+        seq.frame_id_numbers_present = r.f(1)? == 1;
+    }
+    if seq.frame_id_numbers_present {
+        let delta_frame_id_length = r.f(4)? + 2;
+        let additional_frame_id_length = r.f(3)? + 1;
+        seq.frame_id_length = delta_frame_id_length + additional_frame_id_length;
+    }
+    seq.use_128x128_superblock = r.f(1)? == 1;
+    r.f(1)?; // enable_filter_intra
+    r.f(1)?; // enable_intra_edge_filter
+
+    seq.force_screen_content_tools = SELECT_SCREEN_CONTENT_TOOLS;
+    seq.force_integer_mv = SELECT_INTEGER_MV;
+    if !seq.reduced_still_picture_header {
+        r.f(1)?; // enable_interintra_compound
+        r.f(1)?; // enable_masked_compound
+        r.f(1)?; // enable_warped_motion
+        r.f(1)?; // enable_dual_filter
+        seq.enable_order_hint = r.f(1)? == 1;
+        if seq.enable_order_hint {
+            r.f(1)?; // enable_jnt_comp
+            r.f(1)?; // enable_ref_frame_mvs
+        }
+        if r.f(1)? == 0 {
+            // !seq_choose_screen_content_tools
+            seq.force_screen_content_tools = r.f(1)?;
+        }
+        if seq.force_screen_content_tools > 0 {
+            if r.f(1)? == 0 {
+                // !seq_choose_integer_mv
+                seq.force_integer_mv = r.f(1)?;
+            }
+        } else {
+            seq.force_integer_mv = SELECT_INTEGER_MV;
+        }
+        if seq.enable_order_hint {
+            seq.order_hint_bits = r.f(3)? + 1;
+        }
+    }
+    seq.enable_superres = r.f(1)? == 1;
+    seq.enable_cdef = r.f(1)? == 1;
+    seq.enable_restoration = r.f(1)? == 1;
+    parse_color_config(&mut r, seq_profile, &mut seq)?;
+    seq.film_grain_params_present = r.f(1)? == 1;
+    Ok(seq)
+}
+
+/// §5.5.2
+fn parse_color_config(r: &mut BitReader, seq_profile: u32, seq: &mut SequenceHeader) -> Result<()> {
+    seq.high_bitdepth = r.f(1)? == 1;
+    seq.twelve_bit = seq_profile == 2 && seq.high_bitdepth && r.f(1)? == 1;
+    seq.mono_chrome = seq_profile != 1 && r.f(1)? == 1;
+    seq.num_planes = if seq.mono_chrome { 1 } else { 3 };
+
+    let (mut cp, mut tc, mut mc) = (2, 2, 2);
+    if r.f(1)? == 1 {
+        cp = r.f(8)?;
+        tc = r.f(8)?;
+        mc = r.f(8)?;
+    }
+    seq.color_primaries = cp as u8;
+    seq.transfer_characteristics = tc as u8;
+    seq.matrix_coefficients = mc as u8;
+    if seq.mono_chrome {
+        seq.full_range = r.f(1)? == 1;
+        seq.subsampling_x = true;
+        seq.subsampling_y = true;
+        return Ok(());
+    }
+    if cp == 1 && tc == 13 && mc == 0 {
+        // sRGB identity: 4:4:4, full range, nothing more to read here
+        seq.full_range = true;
+    } else {
+        seq.full_range = r.f(1)? == 1;
+        match seq_profile {
+            0 => {
+                seq.subsampling_x = true;
+                seq.subsampling_y = true;
+            }
+            1 => {}
+            _ => {
+                if seq.twelve_bit {
+                    seq.subsampling_x = r.f(1)? == 1;
+                    seq.subsampling_y = seq.subsampling_x && r.f(1)? == 1;
+                } else {
+                    seq.subsampling_x = true;
+                }
+            }
+        }
+        if seq.subsampling_x && seq.subsampling_y {
+            seq.chroma_sample_position = r.f(2)? as u8;
+        }
+    }
+    seq.separate_uv_delta_q = r.f(1)? == 1;
+    Ok(())
+}
+
+/// §5.9.2, up to and including `lr_params()`, then on to the film grain
+/// seed. Returns `None` for headers that don't code a new intra frame.
+fn parse_frame_header(obu: &Obu, seq: &SequenceHeader) -> Result<Option<IntraFrameHeader>> {
+    let mut r = BitReader::new(obu.payload);
+
+    let frame_type;
+    let show_frame;
+    let mut showable_frame = false;
+    let error_resilient_mode;
+    if seq.reduced_still_picture_header {
+        frame_type = KEY_FRAME;
+        show_frame = true;
+        error_resilient_mode = true;
+    } else {
+        if r.f(1)? == 1 {
+            // show_existing_frame
+            return Ok(None);
+        }
+        frame_type = r.f(2)?;
+        if frame_type != KEY_FRAME && frame_type != INTRA_ONLY_FRAME {
+            return Ok(None);
+        }
+        show_frame = r.f(1)? == 1;
+        if show_frame && seq.decoder_model_info_present && !seq.equal_picture_interval {
+            r.f(seq.frame_presentation_time_length)?;
+        }
+        if !show_frame {
+            showable_frame = r.f(1)? == 1;
+        }
+        error_resilient_mode =
+            frame_type == SWITCH_FRAME || (frame_type == KEY_FRAME && show_frame) || r.f(1)? == 1;
+    }
+
+    let disable_cdf_update = r.f(1)? == 1;
+    let allow_screen_content_tools =
+        if seq.force_screen_content_tools == SELECT_SCREEN_CONTENT_TOOLS {
+            r.f(1)? == 1
+        } else {
+            seq.force_screen_content_tools == 1
+        };
+    if allow_screen_content_tools && seq.force_integer_mv == SELECT_INTEGER_MV {
+        r.f(1)?; // force_integer_mv, implied 1 for intra frames anyway
+    }
+    if seq.frame_id_numbers_present {
+        r.f(seq.frame_id_length)?; // current_frame_id
+    }
+    let frame_size_override = !seq.reduced_still_picture_header && r.f(1)? == 1;
+    r.f(seq.order_hint_bits)?; // order_hint
+    // primary_ref_frame is PRIMARY_REF_NONE for intra frames.
+
+    if seq.decoder_model_info_present && r.f(1)? == 1 {
+        // buffer_removal_time_present_flag
+        for op in &seq.operating_points {
+            if op.decoder_model_present {
+                let in_temporal = (op.idc >> obu.temporal_id) & 1 == 1;
+                let in_spatial = (op.idc >> (obu.spatial_id + 8)) & 1 == 1;
+                if op.idc == 0 || (in_temporal && in_spatial) {
+                    r.f(seq.buffer_removal_time_length)?;
+                }
+            }
+        }
+    }
+
+    let refresh_frame_flags = if frame_type == KEY_FRAME && show_frame {
+        0xFF
+    } else {
+        r.f(8)?
+    };
+    if refresh_frame_flags != 0xFF && error_resilient_mode && seq.enable_order_hint {
+        for _ in 0..8 {
+            r.f(seq.order_hint_bits)?; // ref_order_hint[i]
+        }
+    }
+
+    // frame_size(), superres_params(), render_size()
+    let (upscaled_width, frame_height) = if frame_size_override {
+        (
+            r.f(seq.frame_width_bits)? + 1,
+            r.f(seq.frame_height_bits)? + 1,
+        )
+    } else {
+        (seq.max_frame_width, seq.max_frame_height)
+    };
+    let mut frame_width = upscaled_width;
+    if seq.enable_superres && r.f(1)? == 1 {
+        let denom = r.f(3)? + 9;
+        frame_width = (upscaled_width * 8 + denom / 2) / denom;
+    }
+    if r.f(1)? == 1 {
+        r.f(16)?; // render_width_minus_1
+        r.f(16)?; // render_height_minus_1
+    }
+    let allow_intrabc = allow_screen_content_tools && upscaled_width == frame_width && r.f(1)? == 1;
+
+    if !(seq.reduced_still_picture_header || disable_cdf_update) {
+        r.f(1)?; // disable_frame_end_update_cdf
+    }
+
+    skip_tile_info(&mut r, seq, frame_width, frame_height)?;
+
+    // quantization_params()
+    let base_q_idx = r.f(8)? as u8;
+    let mut dc_ac_deltas_zero = read_delta_q(&mut r)? == 0;
+    if seq.num_planes > 1 {
+        let diff_uv_delta = seq.separate_uv_delta_q && r.f(1)? == 1;
+        dc_ac_deltas_zero &= read_delta_q(&mut r)? == 0;
+        dc_ac_deltas_zero &= read_delta_q(&mut r)? == 0;
+        if diff_uv_delta {
+            dc_ac_deltas_zero &= read_delta_q(&mut r)? == 0;
+            dc_ac_deltas_zero &= read_delta_q(&mut r)? == 0;
+        }
+    }
+    if r.f(1)? == 1 {
+        // using_qmatrix
+        r.f(4)?;
+        r.f(4)?;
+        if seq.separate_uv_delta_q {
+            r.f(4)?;
+        }
+    }
+
+    // segmentation_params(); intra frames always update the data
+    let mut seg_qidx = [base_q_idx as i32; 8];
+    let mut last_active_segment = None;
+    let mut uses_alt_q = false;
+    if r.f(1)? == 1 {
+        for (segment, qidx) in seg_qidx.iter_mut().enumerate() {
+            for (j, (&bits, &signed)) in
+                SEG_FEATURE_BITS.iter().zip(&SEG_FEATURE_SIGNED).enumerate()
+            {
+                if r.f(1)? == 0 {
+                    continue;
+                }
+                last_active_segment = Some(segment);
+                let value = if signed {
+                    r.su(bits + 1)?
+                } else {
+                    r.f(bits)? as i32
+                };
+                if j == 0 {
+                    // SEG_LVL_ALT_Q
+                    *qidx = (base_q_idx as i32 + value.clamp(-255, 255)).clamp(0, 255);
+                    uses_alt_q = true;
+                }
+            }
+        }
+    }
+
+    // delta_q_params(), delta_lf_params()
+    let delta_q_present = base_q_idx > 0 && r.f(1)? == 1;
+    if delta_q_present {
+        r.f(2)?; // delta_q_res
+        if !allow_intrabc && r.f(1)? == 1 {
+            // delta_lf_present
+            r.f(2)?; // delta_lf_res
+            r.f(1)?; // delta_lf_multi
+        }
+    }
+
+    let segment_q_idx = match last_active_segment {
+        Some(last) if uses_alt_q => seg_qidx[..=last].iter().map(|&q| q as u8).collect(),
+        _ => Vec::new(),
+    };
+    let coded_lossless = dc_ac_deltas_zero && seg_qidx.iter().all(|&q| q == 0);
+    let all_lossless = coded_lossless && frame_width == upscaled_width;
+
+    // loop_filter_params()
+    let mut loop_filter_level = [0u8; 4];
+    let mut loop_filter_sharpness = 0;
+    if !coded_lossless && !allow_intrabc {
+        loop_filter_level[0] = r.f(6)? as u8;
+        loop_filter_level[1] = r.f(6)? as u8;
+        if seq.num_planes > 1 && (loop_filter_level[0] != 0 || loop_filter_level[1] != 0) {
+            loop_filter_level[2] = r.f(6)? as u8;
+            loop_filter_level[3] = r.f(6)? as u8;
+        }
+        loop_filter_sharpness = r.f(3)? as u8;
+        if r.f(1)? == 1 && r.f(1)? == 1 {
+            // loop_filter_delta_enabled && loop_filter_delta_update
+            for _ in 0..8 + 2 {
+                if r.f(1)? == 1 {
+                    r.su(7)?;
+                }
+            }
+        }
+    }
+
+    // cdef_params()
+    let cdef = if coded_lossless || allow_intrabc || !seq.enable_cdef {
+        None
+    } else {
+        let damping = r.f(2)? as u8 + 3;
+        let bits = r.f(2)? as u8;
+        let mut y_strengths = Vec::with_capacity(1 << bits);
+        let mut uv_strengths = Vec::new();
+        for _ in 0..1 << bits {
+            y_strengths.push(read_cdef_strength(&mut r)?);
+            if seq.num_planes > 1 {
+                uv_strengths.push(read_cdef_strength(&mut r)?);
+            }
+        }
+        Some(CdefStats {
+            damping,
+            bits,
+            y_strengths,
+            uv_strengths,
+        })
+    };
+
+    // lr_params()
+    let mut restoration = [RestorationType::None; 3];
+    let mut restoration_unit_size = [0u16; 3];
+    if !all_lossless && !allow_intrabc && seq.enable_restoration {
+        let mut uses_chroma_lr = false;
+        for (plane, ty) in restoration
+            .iter_mut()
+            .enumerate()
+            .take(seq.num_planes as usize)
+        {
+            *ty = match r.f(2)? {
+                0 => RestorationType::None,
+                1 => RestorationType::Switchable,
+                2 => RestorationType::Wiener,
+                _ => RestorationType::SelfGuided,
+            };
+            uses_chroma_lr |= plane > 0 && *ty != RestorationType::None;
+        }
+        if restoration.iter().any(|&t| t != RestorationType::None) {
+            let mut shift = r.f(1)?;
+            if seq.use_128x128_superblock {
+                shift += 1;
+            } else if shift == 1 {
+                shift += r.f(1)?;
+            }
+            let luma_size = 256u16 >> (2 - shift);
+            let uv_shift = if seq.subsampling_x && seq.subsampling_y && uses_chroma_lr {
+                r.f(1)?
+            } else {
+                0
+            };
+            for (plane, size) in restoration_unit_size.iter_mut().enumerate() {
+                if restoration[plane] != RestorationType::None {
+                    *size = if plane == 0 {
+                        luma_size
+                    } else {
+                        luma_size >> uv_shift
+                    };
+                }
+            }
+        }
+    }
+
+    // Only the grain seed needs anything past lr_params(), so a header cut
+    // short after it still yields the filter parameters.
+    let applies_grain = seq.film_grain_params_present && (show_frame || showable_frame);
+    let grain_seed_bit = skip_to_grain_seed(&mut r, coded_lossless, applies_grain).unwrap_or(None);
+
+    Ok(Some(IntraFrameHeader {
+        filters: FrameFilterStats {
+            base_q_idx,
+            segment_q_idx,
+            lossless: coded_lossless,
+            deblock_level: loop_filter_level,
+            deblock_sharpness: loop_filter_sharpness,
+            cdef,
+            restoration,
+            restoration_unit_size,
+        },
+        grain_seed_bit,
+    }))
+}
+
+/// From after `lr_params()` of an intra frame to `grain_seed`, returning
+/// its bit offset if the frame applies grain. Intra frames code no
+/// reference mode, skip mode, warped or global motion in between.
+fn skip_to_grain_seed(
+    r: &mut BitReader,
+    coded_lossless: bool,
+    applies_grain: bool,
+) -> Result<Option<usize>> {
+    if !coded_lossless {
+        r.f(1)?; // tx_mode_select
+    }
+    r.f(1)?; // reduced_tx_set
+    if applies_grain && r.f(1)? == 1 {
+        // apply_grain
+        let seed_bit = r.bit_pos;
+        r.f(16)?; // grain_seed
+        return Ok(Some(seed_bit));
+    }
+    Ok(None)
+}
+
+/// §5.9.15; only advances past the syntax.
+fn skip_tile_info(
+    r: &mut BitReader,
+    seq: &SequenceHeader,
+    frame_width: u32,
+    frame_height: u32,
+) -> Result<()> {
+    let mi_cols = 2 * frame_width.div_ceil(8);
+    let mi_rows = 2 * frame_height.div_ceil(8);
+    let (sb_shift, sb_size) = if seq.use_128x128_superblock {
+        (5, 7)
+    } else {
+        (4, 6)
+    };
+    let sb_cols = (mi_cols + (1 << sb_shift) - 1) >> sb_shift;
+    let sb_rows = (mi_rows + (1 << sb_shift) - 1) >> sb_shift;
+    let max_tile_width_sb = 4096 >> sb_size;
+    let mut max_tile_area_sb = (4096 * 2304) >> (2 * sb_size);
+    let min_log2_tile_cols = tile_log2(max_tile_width_sb, sb_cols);
+    let max_log2_tile_cols = tile_log2(1, sb_cols.min(64));
+    let max_log2_tile_rows = tile_log2(1, sb_rows.min(64));
+    let min_log2_tiles = min_log2_tile_cols.max(tile_log2(max_tile_area_sb, sb_rows * sb_cols));
+
+    let (tile_cols_log2, tile_rows_log2);
+    if r.f(1)? == 1 {
+        // uniform_tile_spacing_flag
+        let mut cols_log2 = min_log2_tile_cols;
+        while cols_log2 < max_log2_tile_cols && r.f(1)? == 1 {
+            cols_log2 += 1;
+        }
+        let mut rows_log2 = min_log2_tiles.saturating_sub(cols_log2);
+        while rows_log2 < max_log2_tile_rows && r.f(1)? == 1 {
+            rows_log2 += 1;
+        }
+        tile_cols_log2 = cols_log2;
+        tile_rows_log2 = rows_log2;
+    } else {
+        let mut widest_tile_sb = 0;
+        let mut start_sb = 0;
+        let mut tile_cols = 0;
+        while start_sb < sb_cols {
+            let max_width = (sb_cols - start_sb).min(max_tile_width_sb);
+            let size_sb = r.ns(max_width)? + 1;
+            widest_tile_sb = widest_tile_sb.max(size_sb);
+            start_sb += size_sb;
+            tile_cols += 1;
+        }
+        max_tile_area_sb = if min_log2_tiles > 0 {
+            (sb_rows * sb_cols) >> (min_log2_tiles + 1)
+        } else {
+            sb_rows * sb_cols
+        };
+        let max_tile_height_sb = (max_tile_area_sb / widest_tile_sb).max(1);
+        let mut start_sb = 0;
+        let mut tile_rows = 0;
+        while start_sb < sb_rows {
+            let max_height = (sb_rows - start_sb).min(max_tile_height_sb);
+            start_sb += r.ns(max_height)? + 1;
+            tile_rows += 1;
+        }
+        tile_cols_log2 = tile_log2(1, tile_cols);
+        tile_rows_log2 = tile_log2(1, tile_rows);
+    }
+    if tile_cols_log2 > 0 || tile_rows_log2 > 0 {
+        r.f(tile_cols_log2 + tile_rows_log2)?; // context_update_tile_id
+        r.f(2)?; // tile_size_bytes_minus_1
+    }
+    Ok(())
+}
+
+fn tile_log2(blk_size: u32, target: u32) -> u32 {
+    let mut k = 0;
+    while (blk_size << k) < target {
+        k += 1;
+    }
+    k
+}
+
+fn read_delta_q(r: &mut BitReader) -> Result<i32> {
+    if r.f(1)? == 1 { r.su(7) } else { Ok(0) }
+}
+
+/// Secondary strength 3 is coded for an actual strength of 4.
+fn read_cdef_strength(r: &mut BitReader) -> Result<(u8, u8)> {
+    let primary = r.f(4)? as u8;
+    let secondary = r.f(2)? as u8;
+    Ok((primary, if secondary == 3 { 4 } else { secondary }))
+}
+
+/// Returns `(value, bytes consumed)`.
+fn leb128(data: &[u8]) -> Result<(usize, usize)> {
+    let mut value = 0usize;
+    for (i, &byte) in data.iter().take(8).enumerate() {
+        value |= ((byte & 0x7F) as usize) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    Err(HeaderError)
+}
+
+/// MSB-first bit reader (§4.10).
+struct BitReader<'a> {
+    data: &'a [u8],
+    bit_pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, bit_pos: 0 }
+    }
+
+    fn byte_pos(&self) -> usize {
+        self.bit_pos.div_ceil(8)
+    }
+
+    fn bit(&mut self) -> Result<u32> {
+        let byte = *self.data.get(self.bit_pos / 8).ok_or(HeaderError)?;
+        let bit = (byte >> (7 - self.bit_pos % 8)) & 1;
+        self.bit_pos += 1;
+        Ok(bit as u32)
+    }
+
+    /// `f(n)`, for `n <= 32`.
+    fn f(&mut self, n: u32) -> Result<u32> {
+        let mut value = 0u64;
+        for _ in 0..n {
+            value = (value << 1) | self.bit()? as u64;
+        }
+        Ok(value as u32)
+    }
+
+    fn su(&mut self, n: u32) -> Result<i32> {
+        let value = self.f(n)? as i32;
+        let sign_mask = 1 << (n - 1);
+        Ok(if value & sign_mask != 0 {
+            value - 2 * sign_mask
+        } else {
+            value
+        })
+    }
+
+    fn ns(&mut self, n: u32) -> Result<u32> {
+        let w = 32 - n.leading_zeros();
+        let m = (1 << w) - n;
+        let v = self.f(w - 1)?;
+        if v < m {
+            return Ok(v);
+        }
+        Ok((v << 1) - m + self.f(1)?)
+    }
+
+    fn uvlc(&mut self) -> Result<u32> {
+        let mut leading_zeros = 0;
+        while self.bit()? == 0 {
+            leading_zeros += 1;
+            if leading_zeros >= 32 {
+                return Err(HeaderError);
+            }
+        }
+        Ok(self
+            .f(leading_zeros)?
+            .wrapping_add((1 << leading_zeros) - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        bits: usize,
+    }
+
+    impl BitWriter {
+        fn put(&mut self, n: u32, value: u32) -> &mut Self {
+            for i in (0..n).rev() {
+                if self.bits.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                let bit = ((value >> i) & 1) as u8;
+                *self.bytes.last_mut().unwrap() |= bit << (7 - self.bits % 8);
+                self.bits += 1;
+            }
+            self
+        }
+    }
+
+    fn obu(obu_type: u8, payload: &[u8]) -> Vec<u8> {
+        assert!(payload.len() < 128);
+        let mut out = vec![(obu_type << 3) | 0x02, payload.len() as u8];
+        out.extend_from_slice(payload);
+        out
+    }
+
+    /// Reduced still-picture header: profile 0, 64x64, 8-bit 4:2:0, CDEF and
+    /// loop restoration enabled.
+    fn sequence_header() -> Vec<u8> {
+        let mut w = BitWriter::default();
+        w.put(3, 0).put(1, 1).put(1, 1).put(5, 0); // profile, still, reduced, level
+        w.put(4, 5).put(4, 5).put(6, 63).put(6, 63); // 64x64
+        w.put(1, 0).put(1, 0).put(1, 0); // 64x64 SB, filter intra, edge filter
+        w.put(1, 0).put(1, 1).put(1, 1); // superres, cdef, restoration
+        w.put(1, 0).put(1, 0).put(1, 0); // 8-bit, not mono, no CICP
+        w.put(1, 0).put(2, 0).put(1, 0); // range, chroma position, uv delta q
+        w.put(1, 0); // film grain
+        obu(OBU_SEQUENCE_HEADER, &w.bytes)
+    }
+
+    /// Frame header through the quantizer params, for the reduced sequence header.
+    fn frame_header_prefix(base_q_idx: u32) -> BitWriter {
+        let mut w = BitWriter::default();
+        w.put(1, 0).put(1, 0); // disable_cdf_update, screen content tools
+        w.put(1, 0); // render size; reduced headers imply disable_frame_end_update_cdf
+        w.put(1, 1); // uniform tile spacing, single tile
+        w.put(8, base_q_idx).put(1, 0).put(1, 0).put(1, 0); // no delta q
+        w.put(1, 0); // qmatrix
+        w
+    }
+
+    #[test]
+    fn parses_filter_params_of_key_frame() {
+        let mut w = frame_header_prefix(100);
+        w.put(1, 0).put(1, 0); // segmentation, delta_q_present
+        w.put(6, 10).put(6, 12).put(6, 5).put(6, 6).put(3, 2); // deblock
+        w.put(1, 1).put(1, 0); // delta enabled, no update
+        w.put(2, 1).put(2, 1); // cdef damping 4, 2 presets
+        w.put(4, 4).put(2, 3).put(4, 2).put(2, 1);
+        w.put(4, 6).put(2, 0).put(4, 0).put(2, 0);
+        w.put(2, 2).put(2, 0).put(2, 3); // Wiener, none, self-guided
+        w.put(1, 1).put(1, 0).put(1, 1); // 128 luma units, halved for chroma
+
+        let mut data = sequence_header();
+        data.extend(obu(OBU_FRAME_HEADER, &w.bytes));
+        let mut seq = None;
+        let frames = parse_frame_filters(&data, &mut seq).unwrap();
+
+        assert_eq!(
+            frames,
+            [FrameFilterStats {
+                base_q_idx: 100,
+                segment_q_idx: Vec::new(),
+                lossless: false,
+                deblock_level: [10, 12, 5, 6],
+                deblock_sharpness: 2,
+                cdef: Some(CdefStats {
+                    damping: 4,
+                    bits: 1,
+                    y_strengths: vec![(4, 4), (6, 0)],
+                    uv_strengths: vec![(2, 1), (0, 0)],
+                }),
+                restoration: [
+                    RestorationType::Wiener,
+                    RestorationType::None,
+                    RestorationType::SelfGuided,
+                ],
+                restoration_unit_size: [128, 0, 64],
+            }]
+        );
+    }
+
+    #[test]
+    fn lossless_frame_disables_filters() {
+        let mut w = frame_header_prefix(0);
+        w.put(1, 0); // segmentation
+        let mut data = sequence_header();
+        data.extend(obu(OBU_FRAME, &w.bytes));
+        let frames = parse_frame_filters(&data, &mut None).unwrap();
+
+        assert_eq!(frames.len(), 1);
+        assert!(frames[0].lossless);
+        assert_eq!(frames[0].deblock_level, [0; 4]);
+        assert_eq!(frames[0].cdef, None);
+        assert_eq!(frames[0].restoration, [RestorationType::None; 3]);
+    }
+
+    #[test]
+    fn segment_quantizers_follow_alt_q() {
+        let mut w = frame_header_prefix(100);
+        w.put(1, 1); // segmentation_enabled
+        for segment in 0..8 {
+            for feature in 0..8 {
+                match (segment, feature) {
+                    (0, 0) => w.put(1, 1).put(9, (-20i32 as u32) & 0x1FF),
+                    (2, 0) => w.put(1, 1).put(9, 30),
+                    _ => w.put(1, 0),
+                };
+            }
+        }
+        w.put(1, 0); // delta_q_present
+        w.put(6, 0).put(6, 0).put(3, 0).put(1, 0); // no deblocking
+        w.put(2, 0)
+            .put(2, 0)
+            .put(4, 0)
+            .put(2, 0)
+            .put(4, 0)
+            .put(2, 0); // cdef
+        w.put(2, 0).put(2, 0).put(2, 0); // no restoration
+
+        let mut data = sequence_header();
+        data.extend(obu(OBU_FRAME_HEADER, &w.bytes));
+        let frames = parse_frame_filters(&data, &mut None).unwrap();
+
+        assert_eq!(frames[0].segment_q_idx, [80, 100, 130]);
+        assert!(!frames[0].lossless);
+    }
+
+    #[test]
+    fn frame_without_sequence_header_is_skipped() {
+        let mut w = frame_header_prefix(0);
+        w.put(1, 0); // segmentation
+        let data = obu(OBU_FRAME_HEADER, &w.bytes);
+        assert!(parse_frame_filters(&data, &mut None).unwrap().is_empty());
+
+        // A sequence header from an earlier sample is reused.
+        let mut seq = None;
+        parse_frame_filters(&sequence_header(), &mut seq).unwrap();
+        assert_eq!(parse_frame_filters(&data, &mut seq).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn grain_seed_is_overwritten_in_place() {
+        let mut seq = sequence_header();
+        // film_grain_params_present is payload bit 43, the last one written.
+        seq[2 + 5] |= 0x10;
+
+        let mut w = frame_header_prefix(0);
+        w.put(1, 0); // segmentation
+        w.put(1, 0); // reduced_tx_set
+        w.put(1, 1).put(16, 0x1234); // apply_grain, grain_seed
+        w.put(1, 1).put(3, 5); // update_grain, further grain params
+        let mut data = seq;
+        data.extend(obu(OBU_FRAME_HEADER, &w.bytes));
+        let original = data.clone();
+
+        assert_eq!(
+            override_grain_seed(&mut data, &mut None, 0xBEEF).unwrap(),
+            1
+        );
+        assert_eq!(data.len(), original.len());
+        let differing_bits: u32 = data
+            .iter()
+            .zip(&original)
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum();
+        assert_eq!(differing_bits, (0x1234u16 ^ 0xBEEF).count_ones());
+
+        // Written where the parser reads it back.
+        assert_eq!(
+            override_grain_seed(&mut data, &mut None, 0x1234).unwrap(),
+            1
+        );
+        assert_eq!(data, original);
+
+        // Without film grain in the sequence header nothing is touched.
+        let mut plain = sequence_header();
+        plain.extend(obu(OBU_FRAME_HEADER, &w.bytes));
+        assert_eq!(override_grain_seed(&mut plain, &mut None, 7).unwrap(), 0);
+    }
+
+    #[test]
+    fn av1c_mirrors_sequence_header() {
+        let header = sequence_header();
+        let mut seq = None;
+        assert!(!is_sync_sample(&header, &mut seq).unwrap());
+        let seq = seq.unwrap();
+        assert_eq!((seq.max_frame_width, seq.max_frame_height), (64, 64));
+
+        let av1c = seq.av1c();
+        // profile 0, level 0, 8-bit 4:2:0
+        assert_eq!(av1c[..4], [0x81, 0x00, 0b0000_1100, 0]);
+        assert_eq!(av1c[4..], header[..]);
+    }
+
+    #[test]
+    fn reduced_still_frame_is_sync() {
+        let mut data = sequence_header();
+        data.extend(obu(OBU_FRAME_HEADER, &frame_header_prefix(0).bytes));
+        assert!(is_sync_sample(&data, &mut None).unwrap());
+    }
+
+    #[test]
+    fn truncated_header_is_an_error() {
+        let mut data = sequence_header();
+        data.extend(obu(OBU_FRAME_HEADER, &[0]));
+        assert_eq!(parse_frame_filters(&data, &mut None), Err(HeaderError));
+    }
+
+    #[test]
+    fn lowest_resolution_operating_point_has_fewest_spatial_layers() {
+        let point = |idc| OperatingPoint {
+            idc,
+            decoder_model_present: false,
+        };
+        let mut seq = SequenceHeader {
+            operating_points: vec![point(0x301), point(0x101), point(0x101)],
+            ..SequenceHeader::default()
+        };
+        assert_eq!(seq.lowest_resolution_operating_point(), Some(2));
+
+        seq.operating_points = vec![point(0x103), point(0x101)];
+        assert_eq!(seq.lowest_resolution_operating_point(), None);
+        seq.operating_points = vec![point(0)];
+        assert_eq!(seq.lowest_resolution_operating_point(), None);
+    }
+}
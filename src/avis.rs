@@ -0,0 +1,419 @@
+//! Author AVIF image sequences from already-encoded AV1.
+//!
+//! For pipelines that encode with an external AV1 encoder (SVT-AV1, aomenc)
+//! and only need the container. The temporal units are stored as-is in an
+//! `avis` image sequence track; the first one doubles as the primary image
+//! item so still-image readers show the first frame.
+
+use crate::av1_header::{self, SequenceHeader};
+use crate::error::{Error, Result};
+use crate::image::{ColorPrimaries, ColorRange, MatrixCoefficients, TransferCharacteristics};
+use crate::mp4::{
+    TIMESCALE, UNITY_MATRIX, put_u16, put_u32, write_box, write_dinf, write_full_box, write_stsd,
+};
+use whereat::at;
+
+const TRACK_ID: u32 = 1;
+const PRIMARY_ITEM_ID: u16 = 1;
+
+/// Container settings for [`from_av1_stream`].
+#[derive(Debug, Clone, Default)]
+pub struct MuxConfig {
+    pub(crate) loop_count: u32,
+    pub(crate) color: Option<ColorDescription>,
+}
+
+/// CICP signalled in `colr` (`nclx`) boxes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ColorDescription {
+    primaries: ColorPrimaries,
+    transfer: TransferCharacteristics,
+    matrix: MatrixCoefficients,
+    range: ColorRange,
+}
+
+impl MuxConfig {
+    /// Create a mux configuration with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of times the animation plays (0 = infinite, the default).
+    pub fn loop_count(mut self, count: u32) -> Self {
+        self.loop_count = count;
+        self
+    }
+
+    /// Signal CICP color information in the container.
+    ///
+    /// Should match the values the encoder wrote into the sequence header.
+    /// Without it, readers fall back to the sequence header's color config.
+    pub fn color(
+        mut self,
+        primaries: ColorPrimaries,
+        transfer: TransferCharacteristics,
+        matrix: MatrixCoefficients,
+        range: ColorRange,
+    ) -> Self {
+        self.color = Some(ColorDescription {
+            primaries,
+            transfer,
+            matrix,
+            range,
+        });
+        self
+    }
+}
+
+/// Mux pre-encoded AV1 temporal units into an animated AVIF.
+///
+/// Each entry of `obu_frames` is one temporal unit in low-overhead
+/// bitstream format (the OBUs of one frame, without IVF framing) together
+/// with its display duration in milliseconds. The first unit must be a key
+/// frame carrying the sequence header. Nothing is decoded or re-encoded.
+pub fn from_av1_stream(obu_frames: &[(&[u8], u32)], config: &MuxConfig) -> Result<Vec<u8>> {
+    if obu_frames.is_empty() {
+        return Err(at!(Error::Unsupported("no frames to mux")));
+    }
+    let mut seq = None;
+    let mut sync = Vec::with_capacity(obu_frames.len());
+    for (data, _) in obu_frames {
+        sync.push(
+            av1_header::is_sync_sample(data, &mut seq)
+                .map_err(|_| at!(Error::Unsupported("malformed AV1 frame header")))?,
+        );
+    }
+    let seq = seq.ok_or_else(|| at!(Error::Unsupported("no AV1 sequence header in stream")))?;
+    if !sync[0] {
+        return Err(at!(Error::Unsupported("first frame is not a key frame")));
+    }
+    let payload_len: usize = obu_frames.iter().map(|(data, _)| data.len()).sum();
+    if payload_len > u32::MAX as usize - 4096 {
+        return Err(at!(Error::Unsupported(
+            "AV1 stream too large for 32-bit offsets"
+        )));
+    }
+
+    Ok(write_avis(&seq, obu_frames, &sync, config))
+}
+
+fn write_avis(
+    seq: &SequenceHeader,
+    frames: &[(&[u8], u32)],
+    sync: &[bool],
+    config: &MuxConfig,
+) -> Vec<u8> {
+    let payload_len: usize = frames.iter().map(|(data, _)| data.len()).sum();
+    let mut out = Vec::with_capacity(payload_len + 2048);
+
+    write_box(&mut out, b"ftyp", |b| {
+        b.extend_from_slice(b"avis");
+        put_u32(b, 0);
+        for brand in [b"avif", b"avis", b"msf1", b"iso8", b"mif1", b"miaf"] {
+            b.extend_from_slice(brand);
+        }
+    });
+    let item_offset_pos = write_meta(&mut out, seq, frames[0].0.len() as u32, config);
+    let chunk_offset_pos = write_moov(&mut out, seq, frames, sync, config);
+
+    // Both the primary item and the single chunk start at the mdat payload.
+    let data_offset = (out.len() + 8) as u32;
+    for pos in [item_offset_pos, chunk_offset_pos] {
+        out[pos..pos + 4].copy_from_slice(&data_offset.to_be_bytes());
+    }
+    write_box(&mut out, b"mdat", |b| {
+        for (data, _) in frames {
+            b.extend_from_slice(data);
+        }
+    });
+    out
+}
+
+/// Primary image item for still-image readers. Returns the position of the
+/// item's extent offset, patched once the mdat position is known.
+fn write_meta(out: &mut Vec<u8>, seq: &SequenceHeader, item_len: u32, config: &MuxConfig) -> usize {
+    let mut offset_pos = 0;
+    write_full_box(out, b"meta", 0, 0, |b| {
+        write_hdlr(b);
+        write_full_box(b, b"pitm", 0, 0, |b| put_u16(b, PRIMARY_ITEM_ID));
+        write_full_box(b, b"iloc", 0, 0, |b| {
+            b.push(0x44); // offset_size = 4, length_size = 4
+            b.push(0); // base_offset_size = 0
+            put_u16(b, 1);
+            put_u16(b, PRIMARY_ITEM_ID);
+            put_u16(b, 0); // data_reference_index
+            put_u16(b, 1); // extent_count
+            offset_pos = b.len();
+            put_u32(b, 0);
+            put_u32(b, item_len);
+        });
+        write_full_box(b, b"iinf", 0, 0, |b| {
+            put_u16(b, 1);
+            write_full_box(b, b"infe", 2, 0, |b| {
+                put_u16(b, PRIMARY_ITEM_ID);
+                put_u16(b, 0); // item_protection_index
+                b.extend_from_slice(b"av01");
+                b.push(0); // item_name
+            });
+        });
+        write_box(b, b"iprp", |b| {
+            write_box(b, b"ipco", |b| {
+                write_full_box(b, b"ispe", 0, 0, |b| {
+                    put_u32(b, seq.max_frame_width);
+                    put_u32(b, seq.max_frame_height);
+                });
+                write_full_box(b, b"pixi", 0, 0, |b| {
+                    let channels = if seq.mono_chrome { 1 } else { 3 };
+                    b.push(channels);
+                    (0..channels).for_each(|_| b.push(bit_depth(seq)));
+                });
+                write_box(b, b"av1C", |b| b.extend_from_slice(&seq.av1c()));
+                if let Some(color) = config.color {
+                    write_colr(b, color);
+                }
+            });
+            write_full_box(b, b"ipma", 0, 0, |b| {
+                put_u32(b, 1);
+                put_u16(b, PRIMARY_ITEM_ID);
+                // 1-based property indices; av1C is essential
+                let mut associations = vec![1, 2, 0x80 | 3];
+                if config.color.is_some() {
+                    associations.push(4);
+                }
+                b.push(associations.len() as u8);
+                b.extend_from_slice(&associations);
+            });
+        });
+    });
+    offset_pos
+}
+
+/// Image sequence track. Returns the position of the chunk offset.
+fn write_moov(
+    out: &mut Vec<u8>,
+    seq: &SequenceHeader,
+    frames: &[(&[u8], u32)],
+    sync: &[bool],
+    config: &MuxConfig,
+) -> usize {
+    let media_duration = frames
+        .iter()
+        .fold(0u32, |sum, &(_, duration)| sum.saturating_add(duration));
+    // AVIF §7.6: the edit list repeats the media; the track duration says
+    // how often, with all ones meaning indefinitely.
+    let track_duration = match config.loop_count {
+        0 => u32::MAX,
+        n => media_duration.saturating_mul(n).min(u32::MAX - 1),
+    };
+    let (width, height) = (seq.max_frame_width, seq.max_frame_height);
+    let mut offset_pos = 0;
+    write_box(out, b"moov", |b| {
+        write_full_box(b, b"mvhd", 0, 0, |b| {
+            put_u32(b, 0); // creation_time
+            put_u32(b, 0); // modification_time
+            put_u32(b, TIMESCALE);
+            put_u32(b, track_duration);
+            put_u32(b, 0x0001_0000); // rate 1.0
+            put_u16(b, 0x0100); // volume 1.0
+            b.extend_from_slice(&[0; 10]);
+            UNITY_MATRIX.iter().for_each(|&v| put_u32(b, v));
+            b.extend_from_slice(&[0; 24]); // pre_defined
+            put_u32(b, TRACK_ID + 1); // next_track_ID
+        });
+        write_box(b, b"trak", |b| {
+            // flags: track_enabled | track_in_movie
+            write_full_box(b, b"tkhd", 0, 3, |b| {
+                put_u32(b, 0);
+                put_u32(b, 0);
+                put_u32(b, TRACK_ID);
+                put_u32(b, 0);
+                put_u32(b, track_duration);
+                b.extend_from_slice(&[0; 16]); // reserved, layer, group, volume
+                UNITY_MATRIX.iter().for_each(|&v| put_u32(b, v));
+                put_u32(b, width << 16);
+                put_u32(b, height << 16);
+            });
+            write_box(b, b"edts", |b| {
+                // flags: repeat the edit list
+                let flags = (config.loop_count != 1) as u32;
+                write_full_box(b, b"elst", 0, flags, |b| {
+                    put_u32(b, 1);
+                    put_u32(b, media_duration); // segment_duration
+                    put_u32(b, 0); // media_time
+                    put_u32(b, 0x0001_0000); // media_rate 1.0
+                });
+            });
+            write_box(b, b"mdia", |b| {
+                write_full_box(b, b"mdhd", 0, 0, |b| {
+                    put_u32(b, 0);
+                    put_u32(b, 0);
+                    put_u32(b, TIMESCALE);
+                    put_u32(b, media_duration);
+                    put_u16(b, 0x55C4); // language "und"
+                    put_u16(b, 0);
+                });
+                write_hdlr(b);
+                write_box(b, b"minf", |b| {
+                    write_full_box(b, b"vmhd", 0, 1, |b| b.extend_from_slice(&[0; 8]));
+                    write_dinf(b);
+                    offset_pos = write_stbl(b, seq, frames, sync, config);
+                });
+            });
+        });
+    });
+    offset_pos
+}
+
+/// Sample table with every sample in a single chunk. Returns the position
+/// of the chunk offset.
+fn write_stbl(
+    out: &mut Vec<u8>,
+    seq: &SequenceHeader,
+    frames: &[(&[u8], u32)],
+    sync: &[bool],
+    config: &MuxConfig,
+) -> usize {
+    let mut offset_pos = 0;
+    write_box(out, b"stbl", |b| {
+        write_stsd(b, seq, |b| {
+            if let Some(color) = config.color {
+                write_colr(b, color);
+            }
+        });
+        write_full_box(b, b"stts", 0, 0, |b| {
+            let mut runs: Vec<(u32, u32)> = Vec::new();
+            for &(_, duration) in frames {
+                match runs.last_mut() {
+                    Some((count, d)) if *d == duration => *count += 1,
+                    _ => runs.push((1, duration)),
+                }
+            }
+            put_u32(b, runs.len() as u32);
+            for (count, duration) in runs {
+                put_u32(b, count);
+                put_u32(b, duration);
+            }
+        });
+        if sync.iter().any(|&s| !s) {
+            write_full_box(b, b"stss", 0, 0, |b| {
+                put_u32(b, sync.iter().filter(|&&s| s).count() as u32);
+                for (i, _) in sync.iter().enumerate().filter(|(_, s)| **s) {
+                    put_u32(b, i as u32 + 1);
+                }
+            });
+        }
+        write_full_box(b, b"stsc", 0, 0, |b| {
+            put_u32(b, 1);
+            put_u32(b, 1); // first_chunk
+            put_u32(b, frames.len() as u32); // samples_per_chunk
+            put_u32(b, 1); // sample_description_index
+        });
+        write_full_box(b, b"stsz", 0, 0, |b| {
+            put_u32(b, 0);
+            put_u32(b, frames.len() as u32);
+            for (data, _) in frames {
+                put_u32(b, data.len() as u32);
+            }
+        });
+        write_full_box(b, b"stco", 0, 0, |b| {
+            put_u32(b, 1);
+            offset_pos = b.len();
+            put_u32(b, 0);
+        });
+    });
+    offset_pos
+}
+
+fn write_hdlr(out: &mut Vec<u8>) {
+    write_full_box(out, b"hdlr", 0, 0, |b| {
+        put_u32(b, 0);
+        b.extend_from_slice(b"pict");
+        b.extend_from_slice(&[0; 12]);
+        b.push(0); // name
+    });
+}
+
+fn write_colr(out: &mut Vec<u8>, color: ColorDescription) {
+    write_box(out, b"colr", |b| {
+        b.extend_from_slice(b"nclx");
+        put_u16(b, color.primaries.0 as u16);
+        put_u16(b, color.transfer.0 as u16);
+        put_u16(b, color.matrix.0 as u16);
+        b.push(((color.range == ColorRange::Full) as u8) << 7);
+    });
+}
+
+fn bit_depth(seq: &SequenceHeader) -> u8 {
+    match (seq.high_bitdepth, seq.twelve_bit) {
+        (true, true) => 12,
+        (true, false) => 10,
+        _ => 8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find_box<'a>(data: &'a [u8], kind: &[u8; 4]) -> &'a [u8] {
+        let pos = data.windows(4).position(|w| w == kind).unwrap() - 4;
+        let size = u32::from_be_bytes(data[pos..pos + 4].try_into().unwrap()) as usize;
+        &data[pos + 8..pos + size]
+    }
+
+    fn mux(frames: &[(&[u8], u32)], sync: &[bool], config: &MuxConfig) -> Vec<u8> {
+        let seq = SequenceHeader::with_size(64, 48);
+        write_avis(&seq, frames, sync, config)
+    }
+
+    #[test]
+    fn empty_stream_is_rejected() {
+        let err = from_av1_stream(&[], &MuxConfig::new()).unwrap_err();
+        assert!(matches!(err.error(), Error::Unsupported(_)));
+    }
+
+    #[test]
+    fn item_and_chunk_point_at_mdat_payload() {
+        let frames: [(&[u8], u32); 2] = [(&[1, 2, 3], 100), (&[4, 5], 100)];
+        let avis = mux(&frames, &[true, false], &MuxConfig::new());
+
+        let mdat = avis.windows(4).position(|w| w == b"mdat").unwrap() + 4;
+        assert_eq!(avis[mdat..], [1, 2, 3, 4, 5]);
+
+        let stco = find_box(&avis, b"stco");
+        let chunk = u32::from_be_bytes(stco[8..12].try_into().unwrap()) as usize;
+        assert_eq!(chunk, mdat);
+        let iloc = find_box(&avis, b"iloc");
+        let item = u32::from_be_bytes(iloc[14..18].try_into().unwrap()) as usize;
+        let item_len = u32::from_be_bytes(iloc[18..22].try_into().unwrap());
+        assert_eq!((item, item_len), (mdat, 3));
+    }
+
+    #[test]
+    fn durations_are_run_length_coded() {
+        let frames: [(&[u8], u32); 3] = [(&[1], 40), (&[2], 40), (&[3], 80)];
+        let avis = mux(&frames, &[true; 3], &MuxConfig::new());
+
+        let stts = find_box(&avis, b"stts");
+        let words: Vec<u32> = stts[4..]
+            .chunks(4)
+            .map(|c| u32::from_be_bytes(c.try_into().unwrap()))
+            .collect();
+        assert_eq!(words, [2, 2, 40, 1, 80]);
+        // all key frames: no sync sample table
+        assert!(!avis.windows(4).any(|w| w == b"stss"));
+    }
+
+    #[test]
+    fn loop_count_sets_track_duration() {
+        let frames: [(&[u8], u32); 2] = [(&[1], 50), (&[2], 50)];
+        let tkhd_duration = |config: &MuxConfig| {
+            let avis = mux(&frames, &[true; 2], config);
+            let tkhd = find_box(&avis, b"tkhd");
+            u32::from_be_bytes(tkhd[20..24].try_into().unwrap())
+        };
+        assert_eq!(tkhd_duration(&MuxConfig::new()), u32::MAX);
+        assert_eq!(tkhd_duration(&MuxConfig::new().loop_count(1)), 100);
+        assert_eq!(tkhd_duration(&MuxConfig::new().loop_count(3)), 300);
+    }
+}
@@ -0,0 +1,404 @@
+//! Walk a directory tree or ZIP archive of AVIF files.
+//!
+//! [`walk`] yields one [`WalkEntry`] per `.avif`/`.avifs` file, probed but
+//! not decoded. Each file is read once to probe its headers and then
+//! dropped; [`WalkEntry::decode`] reads it again on demand, so auditing a
+//! large corpus holds at most one file in memory at a time.
+//!
+//! ZIP archives are read through their central directory. Stored and
+//! deflated entries are supported; ZIP64 and encrypted archives are not.
+//!
+//! # Example
+//!
+//! ```no_run
+//! for entry in zenavif::batch::walk("corpus.zip").unwrap() {
+//!     let entry = entry.unwrap();
+//!     match &entry.info {
+//!         Ok(info) => println!("{}: {}x{}", entry.name, info.width, info.height),
+//!         Err(e) => println!("{}: {e}", entry.name),
+//!     }
+//! }
+//! ```
+
+use crate::config::DecoderConfig;
+use crate::error::{Error, Result};
+use crate::image::{DecodedImage, ImageInfo};
+use enough::Stop;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use whereat::at;
+
+/// Walk `path`, yielding every AVIF file below it.
+///
+/// `path` may be a directory, walked recursively in name order without
+/// following symlinked subdirectories, a ZIP
+/// archive (by `.zip` extension), or a single file. Files are picked by
+/// their `.avif` or `.avifs` extension.
+pub fn walk(path: impl AsRef<Path>) -> Result<Walk> {
+    let path = path.as_ref();
+    let inner = if path.is_dir() {
+        Inner::Dir {
+            root: path.to_path_buf(),
+            pending: vec![path.to_path_buf()],
+        }
+    } else if has_extension(path, &["zip"]) {
+        let archive = Arc::new(path.to_path_buf());
+        let mut file = File::open(path).map_err(|e| at!(Error::Io(e)))?;
+        let entries = read_central_directory(&mut file)?;
+        Inner::Zip {
+            archive,
+            entries: entries.into_iter(),
+        }
+    } else {
+        let root = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        Inner::Dir {
+            root,
+            pending: vec![path.to_path_buf()],
+        }
+    };
+    Ok(Walk { inner })
+}
+
+/// Iterator returned by [`walk`].
+///
+/// Errors reading a directory or archive come back as `Err` items; a file
+/// that fails to probe still yields an entry, with the error in
+/// [`WalkEntry::info`].
+#[derive(Debug)]
+pub struct Walk {
+    inner: Inner,
+}
+
+#[derive(Debug)]
+enum Inner {
+    Dir {
+        root: PathBuf,
+        /// Paths still to visit, the next one last.
+        pending: Vec<PathBuf>,
+    },
+    Zip {
+        archive: Arc<PathBuf>,
+        entries: std::vec::IntoIter<ZipEntry>,
+    },
+}
+
+impl Iterator for Walk {
+    type Item = Result<WalkEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            Inner::Dir { root, pending } => loop {
+                let path = pending.pop()?;
+                // Symlinked directories are skipped, so links can't loop.
+                let is_dir = if path == *root {
+                    path.is_dir()
+                } else {
+                    std::fs::symlink_metadata(&path).is_ok_and(|m| m.is_dir())
+                };
+                if is_dir {
+                    let mut children = match std::fs::read_dir(&path).and_then(|dir| {
+                        dir.map(|e| e.map(|e| e.path()))
+                            .collect::<io::Result<Vec<_>>>()
+                    }) {
+                        Ok(children) => children,
+                        Err(e) => return Some(Err(at!(Error::Io(e)))),
+                    };
+                    children.sort_unstable_by(|a, b| b.cmp(a));
+                    pending.extend(children);
+                } else if has_extension(&path, &["avif", "avifs"]) {
+                    let name = path
+                        .strip_prefix(&*root)
+                        .unwrap_or(&path)
+                        .components()
+                        .map(|c| c.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    return Some(Ok(WalkEntry::probe(name, Source::File(path))));
+                }
+            },
+            Inner::Zip { archive, entries } => {
+                let entry = entries.next()?;
+                let name = entry.name.clone();
+                let source = Source::Zip {
+                    archive: Arc::clone(archive),
+                    entry,
+                };
+                Some(Ok(WalkEntry::probe(name, source)))
+            }
+        }
+    }
+}
+
+/// One AVIF file found by [`walk`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct WalkEntry {
+    /// Path relative to the walked directory, or the name inside the
+    /// archive, with `/` separators.
+    pub name: String,
+    /// Header metadata, or why the file couldn't be probed.
+    pub info: Result<ImageInfo>,
+    source: Source,
+}
+
+#[derive(Debug)]
+enum Source {
+    File(PathBuf),
+    Zip {
+        archive: Arc<PathBuf>,
+        entry: ZipEntry,
+    },
+}
+
+impl WalkEntry {
+    fn probe(name: String, source: Source) -> Self {
+        let info = source.read().and_then(|data| crate::probe(&data));
+        Self { name, info, source }
+    }
+
+    /// Read the file's bytes again.
+    pub fn read(&self) -> Result<Vec<u8>> {
+        self.source.read()
+    }
+
+    /// Read and decode the file.
+    pub fn decode(
+        &self,
+        config: &DecoderConfig,
+        stop: &(impl Stop + ?Sized),
+    ) -> Result<DecodedImage> {
+        crate::decode_image_with(&self.read()?, config, stop)
+    }
+}
+
+impl Source {
+    fn read(&self) -> Result<Vec<u8>> {
+        match self {
+            Source::File(path) => std::fs::read(path).map_err(|e| at!(Error::Io(e))),
+            Source::Zip { archive, entry } => {
+                let mut file = File::open(&**archive).map_err(|e| at!(Error::Io(e)))?;
+                entry.read(&mut file)
+            }
+        }
+    }
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| extensions.iter().any(|x| e.eq_ignore_ascii_case(x)))
+}
+
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const CENTRAL_SIGNATURE: u32 = 0x0201_4b50;
+const LOCAL_SIGNATURE: u32 = 0x0403_4b50;
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATE: u16 = 8;
+
+/// An archive member, located through the central directory.
+#[derive(Debug, Clone)]
+struct ZipEntry {
+    name: String,
+    method: u16,
+    compressed_size: u64,
+    uncompressed_size: u64,
+    local_header_offset: u64,
+}
+
+fn invalid(msg: &'static str) -> whereat::At<Error> {
+    at!(Error::Io(io::Error::new(io::ErrorKind::InvalidData, msg)))
+}
+
+fn le_u16(b: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([b[at], b[at + 1]])
+}
+
+fn le_u32(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]])
+}
+
+/// Read `len` bytes at `offset` of a file `file_len` bytes long.
+///
+/// Offsets and sizes come from the archive's own headers, so a range past
+/// the end of the file is rejected before anything is allocated for it.
+fn read_at(file: &mut (impl Read + Seek), file_len: u64, offset: u64, len: u64) -> Result<Vec<u8>> {
+    if offset.checked_add(len).is_none_or(|end| end > file_len) {
+        return Err(invalid("ZIP record extends past the end of the file"));
+    }
+    let len = usize::try_from(len).map_err(|_| at!(Error::OutOfMemory))?;
+    let mut buf = Vec::new();
+    buf.try_reserve_exact(len)
+        .map_err(|_| at!(Error::OutOfMemory))?;
+    buf.resize(len, 0);
+    file.seek(SeekFrom::Start(offset))
+        .and_then(|_| file.read_exact(&mut buf))
+        .map_err(|e| at!(Error::Io(e)))?;
+    Ok(buf)
+}
+
+/// List the AVIF members of a ZIP archive.
+fn read_central_directory(file: &mut (impl Read + Seek)) -> Result<Vec<ZipEntry>> {
+    // The end-of-central-directory record is 22 bytes plus a comment of up
+    // to 64 KiB, so it sits within the last 65 557 bytes.
+    let len = file.seek(SeekFrom::End(0)).map_err(|e| at!(Error::Io(e)))?;
+    let tail_len = len.min(22 + 0xffff);
+    let tail = read_at(file, len, len - tail_len, tail_len)?;
+    let eocd = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| le_u32(&tail, i) == EOCD_SIGNATURE)
+        .ok_or_else(|| invalid("no ZIP end of central directory record"))?;
+    let eocd = &tail[eocd..];
+    let count = le_u16(eocd, 10);
+    let size = le_u32(eocd, 12);
+    let offset = le_u32(eocd, 16);
+    if count == 0xffff || size == u32::MAX || offset == u32::MAX {
+        return Err(at!(Error::Unsupported("ZIP64 archives")));
+    }
+
+    let dir = read_at(file, len, offset.into(), size.into())?;
+
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    for _ in 0..count {
+        let header = dir
+            .get(pos..pos + 46)
+            .filter(|h| le_u32(h, 0) == CENTRAL_SIGNATURE)
+            .ok_or_else(|| invalid("truncated ZIP central directory"))?;
+        let flags = le_u16(header, 8);
+        let method = le_u16(header, 10);
+        let compressed_size = le_u32(header, 20);
+        let uncompressed_size = le_u32(header, 24);
+        let name_len = le_u16(header, 28) as usize;
+        let extra_len = le_u16(header, 30) as usize;
+        let comment_len = le_u16(header, 32) as usize;
+        let local_header_offset = le_u32(header, 42);
+        let name = dir
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or_else(|| invalid("truncated ZIP central directory"))?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        pos += 46 + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') || !has_extension(Path::new(&name), &["avif", "avifs"]) {
+            continue;
+        }
+        if flags & 1 != 0 {
+            return Err(at!(Error::Unsupported("encrypted ZIP entries")));
+        }
+        entries.push(ZipEntry {
+            name,
+            method,
+            compressed_size: compressed_size.into(),
+            uncompressed_size: uncompressed_size.into(),
+            local_header_offset: local_header_offset.into(),
+        });
+    }
+    Ok(entries)
+}
+
+impl ZipEntry {
+    fn read(&self, file: &mut (impl Read + Seek)) -> Result<Vec<u8>> {
+        // Name and extra field lengths in the local header may differ from
+        // the central directory's.
+        let file_len = file.seek(SeekFrom::End(0)).map_err(|e| at!(Error::Io(e)))?;
+        let header = read_at(file, file_len, self.local_header_offset, 30)?;
+        if le_u32(&header, 0) != LOCAL_SIGNATURE {
+            return Err(invalid("bad ZIP local header"));
+        }
+        let skip = u64::from(le_u16(&header, 26)) + u64::from(le_u16(&header, 28));
+        let data = read_at(
+            file,
+            file_len,
+            self.local_header_offset + 30 + skip,
+            self.compressed_size,
+        )?;
+
+        match self.method {
+            METHOD_STORED => Ok(data),
+            METHOD_DEFLATE => miniz_oxide::inflate::decompress_to_vec_with_limit(
+                &data,
+                self.uncompressed_size as usize,
+            )
+            .map_err(|_| invalid("corrupt deflate stream in ZIP entry")),
+            _ => Err(at!(Error::Unsupported("ZIP compression method"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A two-member archive: `a.avif` stored, `b.txt` skipped.
+    fn archive() -> Vec<u8> {
+        let mut zip = Vec::new();
+        let mut central = Vec::new();
+        for (name, data) in [("a.avif", &b"avif bytes"[..]), ("b.txt", b"text")] {
+            let offset = zip.len() as u32;
+            let fields = |out: &mut Vec<u8>| {
+                out.extend_from_slice(&0u16.to_le_bytes()); // flags
+                out.extend_from_slice(&METHOD_STORED.to_le_bytes());
+                out.extend_from_slice(&[0; 8]); // time, date, crc
+                out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                out.extend_from_slice(&0u16.to_le_bytes()); // extra
+            };
+            zip.extend_from_slice(&LOCAL_SIGNATURE.to_le_bytes());
+            zip.extend_from_slice(&20u16.to_le_bytes());
+            fields(&mut zip);
+            zip.extend_from_slice(name.as_bytes());
+            zip.extend_from_slice(data);
+
+            central.extend_from_slice(&CENTRAL_SIGNATURE.to_le_bytes());
+            central.extend_from_slice(&[20, 0, 20, 0]);
+            fields(&mut central);
+            central.extend_from_slice(&[0; 10]); // comment, disk, attributes
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let offset = zip.len() as u32;
+        zip.extend_from_slice(&central);
+        zip.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+        zip.extend_from_slice(&[0; 4]);
+        zip.extend_from_slice(&2u16.to_le_bytes());
+        zip.extend_from_slice(&2u16.to_le_bytes());
+        zip.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        zip.extend_from_slice(&offset.to_le_bytes());
+        zip.extend_from_slice(&0u16.to_le_bytes());
+        zip
+    }
+
+    #[test]
+    fn central_directory_lists_avif_members() {
+        let mut cursor = io::Cursor::new(archive());
+        let entries = read_central_directory(&mut cursor).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "a.avif");
+        assert_eq!(entries[0].read(&mut cursor).unwrap(), b"avif bytes");
+    }
+
+    #[test]
+    fn lying_sizes_fail_without_allocating() {
+        let zip = archive();
+        let eocd = zip.len() - 22;
+
+        // Central directory size field
+        let mut lying = zip.clone();
+        lying[eocd + 12..eocd + 16].copy_from_slice(&0xffff_fff0u32.to_le_bytes());
+        assert!(read_central_directory(&mut io::Cursor::new(lying)).is_err());
+
+        // Member size field
+        let mut cursor = io::Cursor::new(zip);
+        let mut entry = read_central_directory(&mut cursor).unwrap().remove(0);
+        entry.compressed_size = 0xffff_fff0;
+        assert!(entry.read(&mut cursor).is_err());
+    }
+
+    #[test]
+    fn rejects_data_without_a_directory() {
+        let mut cursor = io::Cursor::new(vec![0u8; 100]);
+        assert!(read_central_directory(&mut cursor).is_err());
+    }
+}
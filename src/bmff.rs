@@ -0,0 +1,605 @@
+//! Minimal ISOBMFF box reading.
+//!
+//! Shared by audio track extraction, streaming decode, metadata remuxing and
+//! the checks on item properties that zenavif-parse does not expose. Only
+//! box headers and the few tables those need are read; everything else is
+//! left to the container parser.
+
+use crate::error::{Error, Result};
+use whereat::at;
+
+/// File offset just past the last byte of item data that `iloc` places in
+/// the file, given the payload of a `meta` box.
+///
+/// `Ok(None)` means some extent runs to the end of the file. Data stored in
+/// `idat` or in other items lives inside `meta` and does not count; a `meta`
+/// without `iloc` gives `Some(0)`.
+pub(crate) fn item_data_end(meta: &[u8]) -> Result<Option<u64>> {
+    let meta = meta.get(4..).ok_or_else(malformed)?;
+    let Some(iloc) = child(meta, b"iloc")? else {
+        return Ok(Some(0));
+    };
+    let mut end = 0u64;
+    for item in item_locations(iloc)?.items {
+        if item.construction_method != 0 {
+            continue;
+        }
+        for (_, offset, length) in item.extents {
+            if length == 0 {
+                return Ok(None);
+            }
+            let extent_end = item
+                .base_offset
+                .checked_add(offset)
+                .and_then(|n| n.checked_add(length))
+                .ok_or_else(malformed)?;
+            end = end.max(extent_end);
+        }
+    }
+    Ok(Some(end))
+}
+
+/// Contents of an `iloc` box.
+#[derive(Debug, Clone)]
+pub(crate) struct ItemLocations {
+    /// Width in bytes of each extent's `extent_index` (0 when absent).
+    #[cfg_attr(not(feature = "encode"), allow(dead_code))]
+    pub index_size: usize,
+    pub items: Vec<ItemLocation>,
+}
+
+/// One `iloc` entry.
+#[derive(Debug, Clone)]
+pub(crate) struct ItemLocation {
+    pub item_id: u32,
+    /// 0 = file offset, 1 = `idat` offset, 2 = item offset.
+    pub construction_method: u8,
+    #[cfg_attr(not(feature = "encode"), allow(dead_code))]
+    pub data_reference_index: u16,
+    pub base_offset: u64,
+    /// `(extent_index, extent_offset, extent_length)`
+    pub extents: Vec<(u64, u64, u64)>,
+}
+
+/// Parse the payload of an `iloc` box (versions 0 to 2).
+pub(crate) fn item_locations(iloc: &[u8]) -> Result<ItemLocations> {
+    let version = read_u8(iloc, 0)?;
+    let sizes = read_u8(iloc, 4)?;
+    let (offset_size, length_size) = ((sizes >> 4) as usize, (sizes & 15) as usize);
+    let sizes = read_u8(iloc, 5)?;
+    let base_offset_size = (sizes >> 4) as usize;
+    let index_size = if version >= 1 {
+        (sizes & 15) as usize
+    } else {
+        0
+    };
+    let mut pos = 6;
+    let item_count = if version < 2 {
+        pos += 2;
+        read_u16(iloc, pos - 2)? as u32
+    } else {
+        pos += 4;
+        read_u32(iloc, pos - 4)?
+    };
+
+    let mut items = Vec::new();
+    for _ in 0..item_count {
+        let item_id = if version < 2 {
+            pos += 2;
+            u32::from(read_u16(iloc, pos - 2)?)
+        } else {
+            pos += 4;
+            read_u32(iloc, pos - 4)?
+        };
+        let construction_method = if version >= 1 {
+            pos += 2;
+            (read_u16(iloc, pos - 2)? & 15) as u8
+        } else {
+            0
+        };
+        let data_reference_index = read_u16(iloc, pos)?;
+        pos += 2;
+        let base_offset = read_uint(iloc, pos, base_offset_size)?;
+        pos += base_offset_size;
+        let extent_count = read_u16(iloc, pos)?;
+        pos += 2;
+        let mut extents = Vec::with_capacity(extent_count as usize);
+        for _ in 0..extent_count {
+            let index = read_uint(iloc, pos, index_size)?;
+            pos += index_size;
+            let offset = read_uint(iloc, pos, offset_size)?;
+            pos += offset_size;
+            let length = read_uint(iloc, pos, length_size)?;
+            pos += length_size;
+            extents.push((index, offset, length));
+        }
+        items.push(ItemLocation {
+            item_id,
+            construction_method,
+            data_reference_index,
+            base_offset,
+            extents,
+        });
+    }
+    Ok(ItemLocations { index_size, items })
+}
+
+/// Bits per channel from the `pixi` property of the primary item.
+///
+/// Returns `Ok(None)` when the file has no `meta` box or the primary item
+/// carries no `pixi`.
+pub(crate) fn primary_item_pixi(data: &[u8]) -> Result<Option<Vec<u8>>> {
+    let Some(pixi) = primary_item_property(data, b"pixi")? else {
+        return Ok(None);
+    };
+    let channels = read_u8(pixi, 4)? as usize;
+    let bits = pixi.get(5..5 + channels).ok_or_else(malformed)?;
+    Ok(Some(bits.to_vec()))
+}
+
+/// The T.35 message of the primary item's `it35` property.
+///
+/// Returns `Ok(None)` when the file has no `meta` box or the primary item
+/// carries no `it35`.
+pub(crate) fn primary_item_it35(data: &[u8]) -> Result<Option<Vec<u8>>> {
+    let Some(it35) = primary_item_property(data, b"it35")? else {
+        return Ok(None);
+    };
+    Ok(Some(it35.get(4..).ok_or_else(malformed)?.to_vec()))
+}
+
+/// Data of the first auxiliary image of the primary item whose `auxC`
+/// type is one of `aux_types`.
+///
+/// Auxiliary images point at the primary item with an `auxl` reference.
+/// Returns `Ok(None)` when there is no such item, or when its data lives in
+/// another item (construction method 2).
+pub(crate) fn primary_item_auxiliary(data: &[u8], aux_types: &[&str]) -> Result<Option<Vec<u8>>> {
+    let Some(meta) = child(data, b"meta")? else {
+        return Ok(None);
+    };
+    let meta = meta.get(4..).ok_or_else(malformed)?;
+    let (Some(pitm), Some(iref), Some(iprp), Some(iloc)) = (
+        child(meta, b"pitm")?,
+        child(meta, b"iref")?,
+        child(meta, b"iprp")?,
+        child(meta, b"iloc")?,
+    ) else {
+        return Ok(None);
+    };
+    let primary = primary_item_id(pitm)?;
+    let ipco = child(iprp, b"ipco")?.ok_or_else(malformed)?;
+    let properties = boxes(ipco).collect::<Result<Vec<_>>>()?;
+
+    for item in references_to(iref, b"auxl", primary)? {
+        let is_wanted = item_property_indices(iprp, item)?.into_iter().any(|index| {
+            let Some((_, payload)) = index
+                .checked_sub(1)
+                .and_then(|i| properties.get(i))
+                .filter(|(kind, _)| kind == b"auxC")
+            else {
+                return false;
+            };
+            let urn = payload.get(4..).unwrap_or_default();
+            let urn = urn.split(|&b| b == 0).next().unwrap_or_default();
+            aux_types.iter().any(|t| t.as_bytes() == urn)
+        });
+        if !is_wanted {
+            continue;
+        }
+        return item_data(data, meta, iloc, item);
+    }
+    Ok(None)
+}
+
+/// Layout of the primary item when it is a `grid`, from the ImageGrid
+/// descriptor in the item's data (HEIF §6.6.2.3).
+///
+/// Returns `Ok(None)` when the primary item is not a grid, its data lives
+/// in another item, or it has more than 255 rows or columns.
+pub(crate) fn primary_item_grid(data: &[u8]) -> Result<Option<zenavif_parse::GridConfig>> {
+    let Some(meta) = child(data, b"meta")? else {
+        return Ok(None);
+    };
+    let meta = meta.get(4..).ok_or_else(malformed)?;
+    let (Some(pitm), Some(iinf), Some(iloc)) = (
+        child(meta, b"pitm")?,
+        child(meta, b"iinf")?,
+        child(meta, b"iloc")?,
+    ) else {
+        return Ok(None);
+    };
+    let primary = primary_item_id(pitm)?;
+    if item_type(iinf, primary)? != Some(*b"grid") {
+        return Ok(None);
+    }
+    let Some(grid) = item_data(data, meta, iloc, primary)? else {
+        return Ok(None);
+    };
+    // version, flags (bit 0: 32-bit output size), rows and columns minus one
+    let (Some(rows), Some(columns)) = (
+        read_u8(&grid, 2)?.checked_add(1),
+        read_u8(&grid, 3)?.checked_add(1),
+    ) else {
+        return Ok(None);
+    };
+    let (output_width, output_height) = if read_u8(&grid, 1)? & 1 == 0 {
+        (
+            u32::from(read_u16(&grid, 4)?),
+            u32::from(read_u16(&grid, 6)?),
+        )
+    } else {
+        (read_u32(&grid, 4)?, read_u32(&grid, 8)?)
+    };
+    Ok(Some(zenavif_parse::GridConfig {
+        rows,
+        columns,
+        output_width,
+        output_height,
+    }))
+}
+
+/// Item type of `item` in the payload of an `iinf` box, if it has an
+/// `infe` of version 2 or later.
+fn item_type(iinf: &[u8], item: u32) -> Result<Option<[u8; 4]>> {
+    let entries = if read_u8(iinf, 0)? == 0 { 6 } else { 8 };
+    for entry in boxes(iinf.get(entries..).ok_or_else(malformed)?) {
+        let (kind, infe) = entry?;
+        if &kind != b"infe" {
+            continue;
+        }
+        let version = read_u8(infe, 0)?;
+        if version < 2 {
+            continue;
+        }
+        let (id, pos) = if version == 2 {
+            (u32::from(read_u16(infe, 4)?), 8)
+        } else {
+            (read_u32(infe, 4)?, 10)
+        };
+        if id == item {
+            let kind = infe.get(pos..pos + 4).ok_or_else(malformed)?;
+            return Ok(Some(kind.try_into().unwrap()));
+        }
+    }
+    Ok(None)
+}
+
+/// Data of `item` as `iloc` places it, given the file and the payload of
+/// its `meta` box after the version and flags.
+///
+/// Returns `Ok(None)` when `item` has no location or its data lives in
+/// another item (construction method 2).
+fn item_data(data: &[u8], meta: &[u8], iloc: &[u8], item: u32) -> Result<Option<Vec<u8>>> {
+    let locations = item_locations(iloc)?;
+    let Some(location) = locations.items.iter().find(|l| l.item_id == item) else {
+        return Ok(None);
+    };
+    let source = match location.construction_method {
+        0 => data,
+        1 => child(meta, b"idat")?.ok_or_else(malformed)?,
+        _ => return Ok(None),
+    };
+    let mut out = Vec::new();
+    for &(_, offset, length) in &location.extents {
+        let start = location
+            .base_offset
+            .checked_add(offset)
+            .and_then(|n| usize::try_from(n).ok())
+            .ok_or_else(malformed)?;
+        let end = match length {
+            0 => source.len(),
+            n => usize::try_from(n)
+                .ok()
+                .and_then(|n| start.checked_add(n))
+                .ok_or_else(malformed)?,
+        };
+        out.extend_from_slice(source.get(start..end).ok_or_else(malformed)?);
+    }
+    Ok(Some(out))
+}
+
+/// Items with a `kind` reference to `to` in the payload of an `iref` box.
+pub(crate) fn references_to(iref: &[u8], kind: &[u8; 4], to: u32) -> Result<Vec<u32>> {
+    let wide = read_u8(iref, 0)? != 0;
+    let id_len = if wide { 4 } else { 2 };
+    let read_id = |data: &[u8], pos: usize| {
+        if wide {
+            read_u32(data, pos)
+        } else {
+            read_u16(data, pos).map(u32::from)
+        }
+    };
+    let mut items = Vec::new();
+    for reference in boxes(iref.get(4..).ok_or_else(malformed)?) {
+        let (found, payload) = reference?;
+        if &found != kind {
+            continue;
+        }
+        let from = read_id(payload, 0)?;
+        let count = read_u16(payload, id_len)? as usize;
+        for i in 0..count {
+            if read_id(payload, id_len + 2 + i * id_len)? == to {
+                items.push(from);
+            }
+        }
+    }
+    Ok(items)
+}
+
+/// Payload of the first `kind` property associated with the primary item.
+///
+/// Follows `pitm` → `ipma` → `ipco`.
+fn primary_item_property<'a>(data: &'a [u8], kind: &[u8; 4]) -> Result<Option<&'a [u8]>> {
+    let Some(meta) = child(data, b"meta")? else {
+        return Ok(None);
+    };
+    let meta = meta.get(4..).ok_or_else(malformed)?;
+    let Some(pitm) = child(meta, b"pitm")? else {
+        return Ok(None);
+    };
+    let primary = primary_item_id(pitm)?;
+    let Some(iprp) = child(meta, b"iprp")? else {
+        return Ok(None);
+    };
+    let ipco = child(iprp, b"ipco")?.ok_or_else(malformed)?;
+    let properties = boxes(ipco).collect::<Result<Vec<_>>>()?;
+
+    for index in item_property_indices(iprp, primary)? {
+        // Property indices are 1-based; 0 means "no property".
+        let Some(&(found, payload)) = index.checked_sub(1).and_then(|i| properties.get(i)) else {
+            continue;
+        };
+        if &found == kind {
+            return Ok(Some(payload));
+        }
+    }
+    Ok(None)
+}
+
+/// Item ID from the payload of a `pitm` box.
+pub(crate) fn primary_item_id(pitm: &[u8]) -> Result<u32> {
+    if read_u8(pitm, 0)? == 0 {
+        Ok(u32::from(read_u16(pitm, 4)?))
+    } else {
+        read_u32(pitm, 4)
+    }
+}
+
+/// `ipco` indices associated with `item` across all `ipma` boxes.
+pub(crate) fn item_property_indices(iprp: &[u8], item: u32) -> Result<Vec<usize>> {
+    let mut indices = Vec::new();
+    for entry in boxes(iprp) {
+        let (kind, ipma) = entry?;
+        if &kind != b"ipma" {
+            continue;
+        }
+        let version = read_u8(ipma, 0)?;
+        let wide_index = read_u8(ipma, 3)? & 1 != 0;
+        let count = read_u32(ipma, 4)?;
+        let mut pos = 8;
+        for _ in 0..count {
+            let id = if version < 1 {
+                pos += 2;
+                u32::from(read_u16(ipma, pos - 2)?)
+            } else {
+                pos += 4;
+                read_u32(ipma, pos - 4)?
+            };
+            let associations = read_u8(ipma, pos)? as usize;
+            pos += 1;
+            for _ in 0..associations {
+                // The top bit flags the property as essential.
+                let index = if wide_index {
+                    pos += 2;
+                    (read_u16(ipma, pos - 2)? & 0x7fff) as usize
+                } else {
+                    pos += 1;
+                    (read_u8(ipma, pos - 1)? & 0x7f) as usize
+                };
+                if id == item {
+                    indices.push(index);
+                }
+            }
+        }
+    }
+    Ok(indices)
+}
+
+/// Entries of a FullBox table laid out as `entry_count` then fixed-size rows.
+pub(crate) fn table(payload: &[u8], entry_len: usize) -> Result<impl Iterator<Item = &[u8]>> {
+    let count = read_u32(payload, 4)? as usize;
+    let entries = payload.get(8..).ok_or_else(malformed)?;
+    if entries.len() / entry_len < count {
+        return Err(malformed());
+    }
+    Ok(entries.chunks_exact(entry_len).take(count))
+}
+
+/// Payload of the first child box of type `kind`.
+pub(crate) fn child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Result<Option<&'a [u8]>> {
+    for item in boxes(data) {
+        let (k, payload) = item?;
+        if &k == kind {
+            return Ok(Some(payload));
+        }
+    }
+    Ok(None)
+}
+
+/// Iterate `(type, payload)` for the boxes in `data`.
+pub(crate) fn boxes(data: &[u8]) -> impl Iterator<Item = Result<([u8; 4], &[u8])>> {
+    box_spans(data).map(move |span| span.map(|s| (s.kind, &data[s.payload..s.end])))
+}
+
+/// Where a box sits within the buffer it was read from.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BoxSpan {
+    pub kind: [u8; 4],
+    /// Offset of the box header.
+    #[cfg_attr(not(feature = "encode"), allow(dead_code))]
+    pub start: usize,
+    /// Offset of the payload.
+    pub payload: usize,
+    /// Offset just past the box.
+    pub end: usize,
+}
+
+/// Iterate the positions of the boxes in `data`.
+pub(crate) fn box_spans(data: &[u8]) -> impl Iterator<Item = Result<BoxSpan>> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let current = &data[pos..];
+        if current.is_empty() {
+            return None;
+        }
+        let parsed = (|| {
+            let size = read_u32(current, 0)? as u64;
+            let kind: [u8; 4] = current.get(4..8).ok_or_else(malformed)?.try_into().unwrap();
+            let (header, size) = match size {
+                0 => (8, current.len() as u64),
+                1 => (16, read_u64(current, 8)?),
+                n => (8, n),
+            };
+            let size = usize::try_from(size).map_err(|_| malformed())?;
+            if size < header || size > current.len() {
+                return Err(malformed());
+            }
+            Ok((kind, header, size))
+        })();
+        match parsed {
+            Ok((kind, header, size)) => {
+                let span = BoxSpan {
+                    kind,
+                    start: pos,
+                    payload: pos + header,
+                    end: pos + size,
+                };
+                pos = span.end;
+                Some(Ok(span))
+            }
+            Err(e) => {
+                pos = data.len();
+                Some(Err(e))
+            }
+        }
+    })
+}
+
+pub(crate) fn malformed() -> whereat::At<Error> {
+    at!(Error::Parse(zenavif_parse::Error::UnexpectedEOF))
+}
+
+pub(crate) fn read_u8(data: &[u8], pos: usize) -> Result<u8> {
+    data.get(pos).copied().ok_or_else(malformed)
+}
+
+pub(crate) fn read_u16(data: &[u8], pos: usize) -> Result<u16> {
+    let bytes = data.get(pos..pos + 2).ok_or_else(malformed)?;
+    Ok(u16::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+pub(crate) fn read_u32(data: &[u8], pos: usize) -> Result<u32> {
+    let bytes = data.get(pos..pos + 4).ok_or_else(malformed)?;
+    Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+/// Big-endian unsigned integer of `size` bytes (0, 4 or 8, as in `iloc`).
+fn read_uint(data: &[u8], pos: usize, size: usize) -> Result<u64> {
+    match size {
+        0 => Ok(0),
+        4 => read_u32(data, pos).map(u64::from),
+        8 => read_u64(data, pos),
+        _ => Err(malformed()),
+    }
+}
+
+pub(crate) fn read_u64(data: &[u8], pos: usize) -> Result<u64> {
+    let bytes = data.get(pos..pos + 8).ok_or_else(malformed)?;
+    Ok(u64::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bx(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    /// `meta` with primary item 2; item 1 has a 1-channel 8-bit `pixi`,
+    /// item 2 a 3-channel 10-bit one.
+    fn meta(primary_pixi: bool) -> Vec<u8> {
+        let ipco = [
+            bx(b"pixi", &[0, 0, 0, 0, 1, 8]),
+            bx(b"ispe", &[0; 12]),
+            bx(b"pixi", &[0, 0, 0, 0, 3, 10, 10, 10]),
+        ]
+        .concat();
+        let item2: &[u8] = if primary_pixi {
+            &[0, 2, 2, 0x82, 0x03]
+        } else {
+            &[0, 2, 1, 0x82]
+        };
+        let ipma = [&[0, 0, 0, 0, 0, 0, 0, 2, 0, 1, 1, 0x81][..], item2].concat();
+        let iprp = [bx(b"ipco", &ipco), bx(b"ipma", &ipma)].concat();
+        let body = [
+            vec![0, 0, 0, 0],
+            bx(b"pitm", &[0, 0, 0, 0, 0, 2]),
+            bx(b"iprp", &iprp),
+        ]
+        .concat();
+        [bx(b"ftyp", b"avifmif1"), bx(b"meta", &body)].concat()
+    }
+
+    #[test]
+    fn finds_pixi_of_primary_item() {
+        assert_eq!(
+            primary_item_pixi(&meta(true)).unwrap(),
+            Some(vec![10, 10, 10])
+        );
+        assert_eq!(primary_item_pixi(&meta(false)).unwrap(), None);
+        assert_eq!(primary_item_pixi(&bx(b"ftyp", b"avif")).unwrap(), None);
+    }
+
+    /// `iloc` version 1 with 4-byte offsets and lengths and a 4-byte base
+    /// offset: item 1 in the file at 100 + [0, 50), item 2 in `idat`.
+    fn meta_with_iloc(item1_length: u32) -> Vec<u8> {
+        let mut iloc = vec![1, 0, 0, 0, 0x44, 0x40, 0, 2];
+        // item 1: file offset, one extent
+        iloc.extend_from_slice(&[0, 1, 0, 0, 0, 0]);
+        iloc.extend_from_slice(&100u32.to_be_bytes());
+        iloc.extend_from_slice(&[0, 1]);
+        iloc.extend_from_slice(&0u32.to_be_bytes());
+        iloc.extend_from_slice(&item1_length.to_be_bytes());
+        // item 2: idat (construction method 1), far-away offset ignored
+        iloc.extend_from_slice(&[0, 2, 0, 1, 0, 0]);
+        iloc.extend_from_slice(&0u32.to_be_bytes());
+        iloc.extend_from_slice(&[0, 1]);
+        iloc.extend_from_slice(&9000u32.to_be_bytes());
+        iloc.extend_from_slice(&4u32.to_be_bytes());
+        [vec![0, 0, 0, 0], bx(b"iloc", &iloc)].concat()
+    }
+
+    #[test]
+    fn item_data_end_follows_file_extents() {
+        assert_eq!(item_data_end(&meta_with_iloc(50)).unwrap(), Some(150));
+        // A zero length runs to the end of the file.
+        assert_eq!(item_data_end(&meta_with_iloc(0)).unwrap(), None);
+        assert_eq!(item_data_end(&[0, 0, 0, 0]).unwrap(), Some(0));
+        let truncated = meta_with_iloc(50);
+        assert!(item_data_end(&truncated[..truncated.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn truncated_pixi_is_an_error() {
+        let mut data = meta(true);
+        // Claim four channels in the primary pixi.
+        let pos = data.windows(4).rposition(|w| w == b"pixi").unwrap();
+        data[pos + 8] = 4;
+        assert!(primary_item_pixi(&data).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Stable hashing for configuration cache keys.
+//!
+//! [`DecoderConfig::cache_key`](crate::DecoderConfig::cache_key) and
+//! `EncoderConfig::cache_key` feed their fields through [`KeyHasher`] as
+//! fixed-width little-endian values, so keys don't depend on the platform,
+//! the Rust version or `Hash` impls. Each key starts with a domain string
+//! carrying a version number, which is bumped whenever a change to the
+//! encoding would otherwise map old and new settings to the same key.
+
+/// 64-bit FNV-1a over an explicit byte encoding.
+pub(crate) struct KeyHasher(u64);
+
+impl KeyHasher {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    /// Start a key for `domain`, e.g. `b"zenavif-decoder-v1"`.
+    pub(crate) fn new(domain: &[u8]) -> Self {
+        Self(Self::OFFSET).bytes(domain)
+    }
+
+    fn write(mut self, bytes: &[u8]) -> Self {
+        for &b in bytes {
+            self.0 ^= u64::from(b);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+        self
+    }
+
+    pub(crate) fn u8(self, v: u8) -> Self {
+        self.write(&[v])
+    }
+
+    pub(crate) fn u16(self, v: u16) -> Self {
+        self.write(&v.to_le_bytes())
+    }
+
+    pub(crate) fn u32(self, v: u32) -> Self {
+        self.write(&v.to_le_bytes())
+    }
+
+    pub(crate) fn u64(self, v: u64) -> Self {
+        self.write(&v.to_le_bytes())
+    }
+
+    pub(crate) fn bool(self, v: bool) -> Self {
+        self.u8(v.into())
+    }
+
+    /// Hash the bit pattern, so `-0.0` and `0.0` differ.
+    pub(crate) fn f32(self, v: f32) -> Self {
+        self.u32(v.to_bits())
+    }
+
+    /// Length-prefixed, so adjacent byte strings can't run together.
+    pub(crate) fn bytes(self, v: &[u8]) -> Self {
+        self.u64(v.len() as u64).write(v)
+    }
+
+    /// A presence tag, then `some(self, value)` for `Some`.
+    pub(crate) fn option<T>(self, v: Option<T>, some: impl FnOnce(Self, T) -> Self) -> Self {
+        match v {
+            Some(v) => some(self.u8(1), v),
+            None => self.u8(0),
+        }
+    }
+
+    pub(crate) fn finish(self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DecoderConfig;
+
+    fn fnv1a(bytes: &[u8]) -> u64 {
+        KeyHasher(KeyHasher::OFFSET).write(bytes).finish()
+    }
+
+    #[test]
+    fn matches_fnv1a_reference_vectors() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn byte_strings_are_prefix_free() {
+        let split = KeyHasher::new(b"t").bytes(b"ab").bytes(b"c").finish();
+        let moved = KeyHasher::new(b"t").bytes(b"a").bytes(b"bc").finish();
+        assert_ne!(split, moved);
+    }
+
+    #[test]
+    fn decoder_key_tracks_output_settings_only() {
+        let base = DecoderConfig::new().cache_key();
+        assert_eq!(base, DecoderConfig::new().cache_key());
+        assert_eq!(base, DecoderConfig::new().threads(7).cache_key());
+        assert_eq!(
+            base,
+            DecoderConfig::new().frame_size_limit(1 << 20).cache_key()
+        );
+        assert_ne!(base, DecoderConfig::new().apply_grain(false).cache_key());
+        assert_ne!(base, DecoderConfig::new().deband(0.5).cache_key());
+        assert_ne!(
+            DecoderConfig::new().crop(0, 0, 8, 16).cache_key(),
+            DecoderConfig::new().crop(0, 0, 16, 8).cache_key()
+        );
+    }
+
+    #[cfg(feature = "encode")]
+    #[test]
+    fn encoder_key_tracks_output_settings_only() {
+        use crate::EncoderConfig;
+
+        let base = EncoderConfig::new().cache_key();
+        assert_eq!(base, EncoderConfig::new().threads(Some(3)).cache_key());
+        assert_ne!(base, EncoderConfig::new().quality(80.0).cache_key());
+        assert_ne!(base, EncoderConfig::new().exif(vec![0; 4]).cache_key());
+        assert_ne!(
+            EncoderConfig::new().xmp(vec![1]).cache_key(),
+            EncoderConfig::new().icc_profile(vec![1]).cache_key()
+        );
+    }
+}
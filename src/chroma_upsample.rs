@@ -0,0 +1,216 @@
+//! Chroma upsampling to 4:4:4 for [`DecoderConfig::chroma_upsampling`].
+//!
+//! The built-in converters each bring their own chroma filter: bilinear
+//! for 8-bit 4:2:0, replication for 8-bit 4:2:2 and the 10/12-bit kernels.
+//! When a filter is chosen explicitly, the subsampled planes are brought to
+//! luma size here first and the 4:4:4 converters do the rest, so every bit
+//! depth and path upsamples the same way.
+//!
+//! [`DecoderConfig::chroma_upsampling`]: crate::DecoderConfig::chroma_upsampling
+
+use crate::config::ChromaUpsampling;
+use crate::error::{Error, Result};
+use crate::image::ChromaSamplePosition;
+use whereat::at;
+
+/// A plane sample: `u8` or a 10/12/16-bit `u16`.
+pub(crate) trait Sample: Copy {
+    fn to_f32(self) -> f32;
+    /// Round and clamp to `[0, max]`.
+    fn from_f32(v: f32, max: f32) -> Self;
+}
+
+impl Sample for u8 {
+    fn to_f32(self) -> f32 {
+        f32::from(self)
+    }
+
+    fn from_f32(v: f32, max: f32) -> Self {
+        (v + 0.5).clamp(0.0, max) as u8
+    }
+}
+
+impl Sample for u16 {
+    fn to_f32(self) -> f32 {
+        f32::from(self)
+    }
+
+    fn from_f32(v: f32, max: f32) -> Self {
+        (v + 0.5).clamp(0.0, max) as u16
+    }
+}
+
+/// Up to four source positions and weights for one output position.
+type Taps = [(usize, f32); 4];
+
+/// Filter taps along one axis for `out_len` outputs from `in_len` inputs.
+///
+/// `coord(i)` maps output `i` to a (fractional) input position; `None`
+/// means the axis isn't subsampled and samples pass straight through.
+fn axis_taps(
+    out_len: usize,
+    in_len: usize,
+    filter: ChromaUpsampling,
+    coord: Option<fn(usize) -> f32>,
+) -> Vec<Taps> {
+    let last = in_len.saturating_sub(1);
+    (0..out_len)
+        .map(|i| {
+            let Some(coord) = coord else {
+                return [(i.min(last), 1.0), (0, 0.0), (0, 0.0), (0, 0.0)];
+            };
+            if filter == ChromaUpsampling::Nearest {
+                return [((i / 2).min(last), 1.0), (0, 0.0), (0, 0.0), (0, 0.0)];
+            }
+            let c = coord(i).clamp(0.0, last as f32);
+            let i0 = c.floor() as usize;
+            let t = c - i0 as f32;
+            let at = |k: isize| (i0 as isize + k).clamp(0, last as isize) as usize;
+            match filter {
+                ChromaUpsampling::CatmullRom => {
+                    let (t2, t3) = (t * t, t * t * t);
+                    [
+                        (at(-1), (-t3 + 2.0 * t2 - t) * 0.5),
+                        (at(0), (3.0 * t3 - 5.0 * t2 + 2.0) * 0.5),
+                        (at(1), (-3.0 * t3 + 4.0 * t2 + t) * 0.5),
+                        (at(2), (t3 - t2) * 0.5),
+                    ]
+                }
+                _ => [(at(0), 1.0 - t), (at(1), t), (0, 0.0), (0, 0.0)],
+            }
+        })
+        .collect()
+}
+
+/// Chroma position of luma sample `x` when chroma sits midway between two
+/// luma samples.
+fn centered(x: usize) -> f32 {
+    (x as f32 + 0.5) * 0.5 - 0.5
+}
+
+/// Chroma position of luma sample `x` when chroma sits on the even ones.
+fn cosited(x: usize) -> f32 {
+    x as f32 * 0.5
+}
+
+/// Upsample one chroma plane to `width` × `height`.
+///
+/// `plane` holds `chroma_width` × `chroma_height` samples `stride` apart.
+/// `subsampled` says which axes are halved: `(true, true)` for 4:2:0,
+/// `(true, false)` for 4:2:2.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn upsample_plane<T: Sample>(
+    plane: &[T],
+    stride: usize,
+    (chroma_width, chroma_height): (usize, usize),
+    (width, height): (usize, usize),
+    subsampled: (bool, bool),
+    siting: ChromaSamplePosition,
+    filter: ChromaUpsampling,
+    max: f32,
+) -> Result<Vec<T>> {
+    if chroma_width == 0 || chroma_height == 0 {
+        return Err(at!(Error::Decode {
+            code: -1,
+            msg: "empty chroma plane",
+        }));
+    }
+    if plane.len() < stride * (chroma_height - 1) + chroma_width {
+        return Err(at!(Error::Decode {
+            code: -1,
+            msg: "plane is shorter than the image",
+        }));
+    }
+    // Same siting as the 8-bit 4:2:0 converter; 4:2:2 chroma is cosited.
+    let x_coord: fn(usize) -> f32 = match (subsampled, siting) {
+        ((true, true), ChromaSamplePosition::Unknown) => centered,
+        _ => cosited,
+    };
+    let y_coord: fn(usize) -> f32 = match siting {
+        ChromaSamplePosition::Colocated => cosited,
+        _ => centered,
+    };
+    let x_taps = axis_taps(width, chroma_width, filter, subsampled.0.then_some(x_coord));
+    let y_taps = axis_taps(
+        height,
+        chroma_height,
+        filter,
+        subsampled.1.then_some(y_coord),
+    );
+
+    // Horizontal pass into f32 rows, then vertical into the output.
+    let mut rows = Vec::new();
+    rows.try_reserve_exact(width * chroma_height)
+        .map_err(|_| at!(Error::OutOfMemory))?;
+    for row in plane.chunks(stride).take(chroma_height) {
+        rows.extend(
+            x_taps
+                .iter()
+                .map(|taps| taps.iter().map(|&(i, w)| row[i].to_f32() * w).sum::<f32>()),
+        );
+    }
+
+    let mut out = Vec::new();
+    out.try_reserve_exact(width * height)
+        .map_err(|_| at!(Error::OutOfMemory))?;
+    for taps in &y_taps {
+        out.extend((0..width).map(|x| {
+            let v = taps
+                .iter()
+                .map(|&(i, w)| rows[i * width + x] * w)
+                .sum::<f32>();
+            T::from_f32(v, max)
+        }));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upsample(plane: &[u8], chroma: (usize, usize), filter: ChromaUpsampling) -> Vec<u8> {
+        let luma = (chroma.0 * 2, chroma.1 * 2);
+        upsample_plane(
+            plane,
+            chroma.0,
+            chroma,
+            luma,
+            (true, true),
+            ChromaSamplePosition::Colocated,
+            filter,
+            255.0,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn flat_planes_stay_flat() {
+        for filter in [
+            ChromaUpsampling::Nearest,
+            ChromaUpsampling::Bilinear,
+            ChromaUpsampling::CatmullRom,
+        ] {
+            assert!(upsample(&[77; 12], (4, 3), filter).iter().all(|&v| v == 77));
+        }
+    }
+
+    #[test]
+    fn filters_differ_between_samples() {
+        let row = [0, 0, 200, 200];
+        let first_row = |filter| upsample(&row, (4, 1), filter)[..8].to_vec();
+        assert_eq!(
+            first_row(ChromaUpsampling::Nearest),
+            [0, 0, 0, 0, 200, 200, 200, 200]
+        );
+        assert_eq!(
+            first_row(ChromaUpsampling::Bilinear),
+            [0, 0, 0, 100, 200, 200, 200, 200]
+        );
+        // Catmull-Rom overshoots next to the edge; clamped at 0.
+        let cubic = first_row(ChromaUpsampling::CatmullRom);
+        assert_eq!(cubic[3], 100);
+        assert_eq!(cubic[2], 0);
+        assert!(cubic[5] > 200);
+    }
+}
@@ -0,0 +1,151 @@
+//! ICC color management at decode time.
+//!
+//! With [`DecoderConfig::color_manage`](crate::DecoderConfig::color_manage),
+//! RGB output of images carrying an ICC profile is converted to the
+//! destination profile with moxcms, right after YUV→RGB conversion.
+
+use crate::config::Destination;
+use crate::error::{Error, Result};
+use crate::image::{ColorPrimaries, ImageInfo, TransferCharacteristics};
+use moxcms::{CmsError, ColorProfile, Layout, TransformOptions};
+use whereat::at;
+use zenpixels::{PixelBuffer, PixelDescriptor};
+
+/// Source and destination profiles of a decoder's color conversion.
+pub(crate) struct ColorTransform {
+    source: ColorProfile,
+    destination: ColorProfile,
+    /// ICC profile reported for converted images; `None` for sRGB, which
+    /// CICP describes.
+    destination_icc: Option<Vec<u8>>,
+}
+
+impl ColorTransform {
+    /// Profiles for converting from the embedded `icc` to `destination`.
+    pub(crate) fn new(icc: &[u8], destination: &Destination) -> Result<Self> {
+        let source = ColorProfile::new_from_slice(icc).map_err(cms_error)?;
+        let (destination, destination_icc) = match destination {
+            Destination::Srgb => (ColorProfile::new_srgb(), None),
+            Destination::Icc(icc) => (
+                ColorProfile::new_from_slice(icc).map_err(cms_error)?,
+                Some(icc.to_vec()),
+            ),
+        };
+        Ok(Self {
+            source,
+            destination,
+            destination_icc,
+        })
+    }
+
+    /// Whether [`apply`](Self::apply) converts images of `pixels`' layout.
+    pub(crate) fn converts(&self, pixels: &PixelBuffer) -> bool {
+        transform_layout(pixels).is_some()
+    }
+
+    /// Convert 8/16-bit RGB or RGBA `pixels` in place. Other layouts, such
+    /// as gray, are left alone.
+    pub(crate) fn apply(&self, pixels: &mut PixelBuffer) -> Result<()> {
+        let Some((layout, wide)) = transform_layout(pixels) else {
+            return Ok(());
+        };
+        let desc = pixels.descriptor();
+        let options = TransformOptions::default();
+        let (width, height) = (pixels.width(), pixels.height());
+        let bytes = width as usize * desc.bytes_per_pixel();
+        let mut image = pixels.as_slice_mut();
+
+        // Rows go through scratch copies: the transforms are not in place,
+        // and 16-bit rows need not be aligned for `u16`.
+        if wide {
+            let transform = self
+                .source
+                .create_transform_16bit(layout, &self.destination, layout, options)
+                .map_err(cms_error)?;
+            let (mut src, mut dst) = (Vec::new(), Vec::new());
+            for y in 0..height {
+                let row = &mut image.row_mut(y)[..bytes];
+                src.clear();
+                src.extend(
+                    row.chunks_exact(2)
+                        .map(|b| u16::from_ne_bytes([b[0], b[1]])),
+                );
+                dst.resize(src.len(), 0);
+                transform.transform(&src, &mut dst).map_err(cms_error)?;
+                for (out, v) in row.chunks_exact_mut(2).zip(&dst) {
+                    out.copy_from_slice(&v.to_ne_bytes());
+                }
+            }
+        } else {
+            let transform = self
+                .source
+                .create_transform_8bit(layout, &self.destination, layout, options)
+                .map_err(cms_error)?;
+            let mut src = Vec::with_capacity(bytes);
+            for y in 0..height {
+                let row = &mut image.row_mut(y)[..bytes];
+                src.clear();
+                src.extend_from_slice(row);
+                transform.transform(&src, row).map_err(cms_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rewrite the color description of `info` to the destination.
+    pub(crate) fn describe(&self, info: &mut ImageInfo) {
+        match &self.destination_icc {
+            Some(icc) => info.icc_profile = Some(icc.clone()),
+            None => {
+                info.icc_profile = None;
+                info.color_primaries = ColorPrimaries::BT709;
+                info.transfer_characteristics = TransferCharacteristics::SRGB;
+            }
+        }
+    }
+}
+
+/// moxcms layout of `pixels` and whether its samples are 16-bit.
+fn transform_layout(pixels: &PixelBuffer) -> Option<(Layout, bool)> {
+    let desc = pixels.descriptor();
+    [
+        (PixelDescriptor::RGB8, Layout::Rgb, false),
+        (PixelDescriptor::RGBA8, Layout::Rgba, false),
+        (PixelDescriptor::RGB16, Layout::Rgb, true),
+        (PixelDescriptor::RGBA16, Layout::Rgba, true),
+    ]
+    .into_iter()
+    .find(|&(layout, ..)| desc.layout_compatible(layout))
+    .map(|(_, layout, wide)| (layout, wide))
+}
+
+fn cms_error(e: CmsError) -> whereat::At<Error> {
+    at!(Error::ColorManagement(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rgb::Rgb;
+
+    #[test]
+    fn srgb_to_srgb_keeps_pixels() {
+        let icc = ColorProfile::new_srgb().encode().unwrap();
+        let transform = ColorTransform::new(&icc, &Destination::Srgb).unwrap();
+        let px: Vec<Rgb<u8>> = (0..16u8).map(|v| Rgb::new(v * 16, 255 - v, 128)).collect();
+        let mut image: PixelBuffer = PixelBuffer::from_pixels(px.clone(), 4, 4).unwrap().into();
+        transform.apply(&mut image).unwrap();
+        let out = image.try_as_imgref::<Rgb<u8>>().unwrap();
+        for (a, b) in out.buf().iter().zip(&px) {
+            assert!(a.r.abs_diff(b.r) <= 1 && a.g.abs_diff(b.g) <= 1 && a.b.abs_diff(b.b) <= 1);
+        }
+
+        let mut info = ImageInfo {
+            icc_profile: Some(icc),
+            ..ImageInfo::default()
+        };
+        transform.describe(&mut info);
+        assert_eq!(info.icc_profile, None);
+        assert_eq!(info.transfer_characteristics, TransferCharacteristics::SRGB);
+    }
+}
@@ -109,12 +109,33 @@ impl AvifEncoderConfig {
     }
 
     /// Set encode quality (0.0 = worst, 100.0 = lossless).
+    ///
+    /// This is AVIF's native quality scale, forwarded unchanged. For a value
+    /// that means the same as a JPEG quality, use
+    /// [`with_calibrated_quality`](Self::with_calibrated_quality).
     #[must_use]
     pub fn with_quality(mut self, quality: f32) -> Self {
         self.inner = self.inner.quality(quality);
         self
     }
 
+    /// Set encode quality on the libjpeg-turbo scale (0.0-100.0).
+    ///
+    /// The value is mapped through a calibration table so that, on a
+    /// typical photographic corpus, the output scores the same median
+    /// SSIMULACRA2 as a libjpeg-turbo JPEG at that quality. "Quality 80"
+    /// thus looks alike across codecs in a zencodec pipeline; it encodes
+    /// at native AVIF quality 66.6. This is what
+    /// [`with_generic_quality`](zencodec::encode::EncoderConfig::with_generic_quality)
+    /// does.
+    #[must_use]
+    pub fn with_calibrated_quality(mut self, quality: f32) -> Self {
+        let clamped = quality.clamp(0.0, 100.0);
+        self.trait_quality = Some(clamped);
+        self.inner = self.inner.quality(calibrated_avif_quality(clamped));
+        self
+    }
+
     /// Set encode effort/speed (0 = slowest/best, 10 = fastest).
     #[must_use]
     pub fn with_effort_u32(mut self, effort: u32) -> Self {
@@ -415,12 +436,8 @@ impl zencodec::encode::EncoderConfig for AvifEncoderConfig {
         self.trait_effort
     }
 
-    fn with_generic_quality(mut self, quality: f32) -> Self {
-        let clamped = quality.clamp(0.0, 100.0);
-        self.trait_quality = Some(clamped);
-        let native = calibrated_avif_quality(clamped);
-        self.inner = self.inner.quality(native);
-        self
+    fn with_generic_quality(self, quality: f32) -> Self {
+        self.with_calibrated_quality(quality)
     }
 
     fn generic_quality(&self) -> Option<f32> {
@@ -553,11 +570,12 @@ impl zencodec::encode::EncodeJob for AvifEncodeJob {
     fn encoder(self) -> Result<AvifEncoder, At<Error>> {
         let mut config = self.config.inner.clone();
         // Apply CICP color metadata from Metadata
+        // The matrix describes the source's coding, not ours; the encoder's
+        // signal format picks its own.
         if let Some(cicp) = self.cicp {
             config = config
                 .color_primaries(cicp.color_primaries)
-                .transfer_characteristics(cicp.transfer_characteristics)
-                .matrix_coefficients(cicp.matrix_coefficients);
+                .transfer_characteristics(cicp.transfer_characteristics);
         }
         // Apply HDR metadata from Metadata
         if let Some(cll) = self.content_light_level {
@@ -611,6 +629,10 @@ impl zencodec::encode::EncodeJob for AvifEncodeJob {
             }
             // threads == 0 only from future unknown variants; leave default
         }
+        // Let the encoder trade threads for memory before refusing the job.
+        if let Some(mem) = self.limits.max_memory_bytes {
+            config = config.max_memory_bytes(mem.min(usize::MAX as u64) as usize);
+        }
         // Apply encode policy: suppress metadata the policy disallows.
         let exif = match self.policy {
             Some(ref p) if !p.resolve_exif(true) => None,
@@ -647,11 +669,12 @@ impl zencodec::encode::EncodeJob for AvifEncodeJob {
     fn animation_frame_encoder(self) -> Result<AvifAnimationFrameEncoder, At<Error>> {
         let mut config = self.config.inner.clone();
         // Apply CICP color metadata
+        // The matrix describes the source's coding, not ours; the encoder's
+        // signal format picks its own.
         if let Some(cicp) = self.cicp {
             config = config
                 .color_primaries(cicp.color_primaries)
-                .transfer_characteristics(cicp.transfer_characteristics)
-                .matrix_coefficients(cicp.matrix_coefficients);
+                .transfer_characteristics(cicp.transfer_characteristics);
         }
         // Apply HDR metadata
         if let Some(cll) = self.content_light_level {
@@ -698,6 +721,9 @@ impl zencodec::encode::EncodeJob for AvifEncodeJob {
                 config = config.threads(Some(threads as usize));
             }
         }
+        if let Some(mem) = self.limits.max_memory_bytes {
+            config = config.max_memory_bytes(mem.min(usize::MAX as u64) as usize);
+        }
         // Apply metadata
         let policy = self.policy.as_ref();
         if let Some(exif) = self.exif
@@ -935,10 +961,8 @@ impl AvifEncoder {
         let cfg = self.build_config();
         let stop = self.stop_token();
         let raw = pixels.contiguous_bytes();
-        // Gray → RGB for encoding (AVIF encoder expects color planes)
-        let rgb: Vec<Rgb<u8>> = raw.iter().map(|&g| Rgb { r: g, g, b: g }).collect();
-        let img = imgref::ImgVec::new(rgb, w, h);
-        let result = crate::encode_rgb8(img.as_ref(), &cfg, stop)?;
+        let luma = raw.iter().map(|&g| u16::from(g));
+        let result = crate::mono_encode::encode_luma(w, h, luma, 8, &cfg, stop)?;
         self.make_output(result.avif_file)
     }
 
@@ -997,23 +1021,21 @@ impl AvifEncoder {
     }
 
     fn do_encode_gray_f32(self, pixels: PixelSlice<'_>) -> Result<EncodeOutput, At<Error>> {
-        use linear_srgb::default::linear_to_srgb_u8;
+        use linear_srgb::default::linear_to_srgb;
         let w = pixels.width() as usize;
         let h = pixels.rows() as usize;
         self.check_limits(w, h, 4)?;
-        let cfg = self.build_config();
+        // Samples are sRGB-encoded below, so declare sRGB rather than the
+        // linear transfer of the input descriptor. 10 bits keep float
+        // gradients from banding; the stream is monochrome like Gray8's.
+        let cfg = self.build_config().transfer_characteristics(13);
         let stop = self.stop_token();
         let raw = pixels.contiguous_bytes();
-        let rgb: Vec<Rgb<u8>> = raw
-            .chunks_exact(4)
-            .map(|c| {
-                let v = f32::from_le_bytes([c[0], c[1], c[2], c[3]]);
-                let s = linear_to_srgb_u8(v.clamp(0.0, 1.0));
-                Rgb { r: s, g: s, b: s }
-            })
-            .collect();
-        let img = imgref::ImgVec::new(rgb, w, h);
-        let result = crate::encode_rgb8(img.as_ref(), &cfg, stop)?;
+        let luma = raw.chunks_exact(4).map(|c| {
+            let v = f32::from_le_bytes([c[0], c[1], c[2], c[3]]);
+            (linear_to_srgb(v.clamp(0.0, 1.0)) * 1023.0).round() as u16
+        });
+        let result = crate::mono_encode::encode_luma(w, h, luma, 10, &cfg, stop)?;
         self.make_output(result.avif_file)
     }
 
@@ -1577,6 +1599,10 @@ impl AvifDecoderConfig {
     }
 
     /// Convenience: decode into a pre-allocated RGB8 buffer.
+    ///
+    /// 10/12-bit sources lose precision here, which shows as
+    /// `source_color.bit_depth > 8` on the returned info. They are truncated to 8 bits, or dithered with
+    /// [`DecoderConfig::dither`](crate::DecoderConfig::dither).
     pub fn decode_into_rgb8(
         &self,
         data: &[u8],
@@ -1584,7 +1610,7 @@ impl AvifDecoderConfig {
     ) -> Result<ImageInfo, At<Error>> {
         let output = self.decode(data)?;
         let info = output.info().clone();
-        let src = output.into_buffer().to_rgb8();
+        let src = crate::convert::reduce_to_8bit(output.into_buffer(), self.inner.dither).to_rgb8();
         let src_ref = src.as_imgref();
         let w = dst.width().min(src_ref.width());
         let h = dst.height().min(src_ref.height());
@@ -1607,6 +1633,10 @@ impl AvifDecoderConfig {
     }
 
     /// Convenience: decode into a pre-allocated RGBA8 buffer.
+    ///
+    /// 10/12-bit sources lose precision here, which shows as
+    /// `source_color.bit_depth > 8` on the returned info. They are truncated to 8 bits, or dithered with
+    /// [`DecoderConfig::dither`](crate::DecoderConfig::dither).
     pub fn decode_into_rgba8(
         &self,
         data: &[u8],
@@ -1614,7 +1644,8 @@ impl AvifDecoderConfig {
     ) -> Result<ImageInfo, At<Error>> {
         let output = self.decode(data)?;
         let info = output.info().clone();
-        let src = output.into_buffer().to_rgba8();
+        let src =
+            crate::convert::reduce_to_8bit(output.into_buffer(), self.inner.dither).to_rgba8();
         let src_ref = src.as_imgref();
         let w = dst.width().min(src_ref.width());
         let h = dst.height().min(src_ref.height());
@@ -1636,117 +1667,112 @@ impl AvifDecoderConfig {
         Ok(info)
     }
 
-    /// Convenience: decode into a pre-allocated RGB f32 buffer.
+    /// Decode to linear-light RGBA, undoing the image's own transfer
+    /// function (sRGB, BT.709, PQ, HLG, ...) from its CICP code points.
+    ///
+    /// Returns the pixels, the image info and the CICP color primaries.
+    fn decode_linear(
+        &self,
+        data: &[u8],
+    ) -> crate::error::Result<(imgref::ImgVec<Rgba<f32>>, ImageInfo, u8)> {
+        let mut decoder = crate::ManagedAvifDecoder::new(data, &self.inner)?;
+        let (pixels, native_info) = decoder.decode_full(&enough::Unstoppable)?;
+        let linear = crate::transfer::linear_rgba(
+            &pixels,
+            native_info.transfer_characteristics.0,
+            decoder.output_is_video_range(&native_info),
+        );
+        Ok((
+            linear,
+            convert_native_info(&native_info),
+            native_info.color_primaries.0,
+        ))
+    }
+
+    /// Convenience: decode into a pre-allocated linear-light RGB f32 buffer.
+    ///
+    /// The transfer function is taken from the image's CICP; values stay in
+    /// the image's own primaries. PQ content maps 1.0 to 10 000 cd/m².
+    /// Limited-range sources expand to full range here even with
+    /// [`OutputRange::PreserveLimited`](crate::OutputRange); with
+    /// [`RangeClipping::PreserveOvershoot`](crate::RangeClipping) their
+    /// sub-blacks come out below 0.0 and super-whites above reference white.
     pub fn decode_into_rgb_f32(
         &self,
         data: &[u8],
         mut dst: imgref::ImgRefMut<'_, Rgb<f32>>,
     ) -> Result<ImageInfo, At<Error>> {
-        use linear_srgb::default::srgb_u8_to_linear;
-        let output = self.decode(data)?;
-        let info = output.info().clone();
-        let src = output.into_buffer().to_rgb8();
-        let src_ref = src.as_imgref();
-        let w = dst.width().min(src_ref.width());
-        let h = dst.height().min(src_ref.height());
-        for y in 0..h {
-            let src_row = src_ref.rows().nth(y).ok_or_else(|| {
-                at!(Error::Decode {
-                    code: -1,
-                    msg: "Source row index out of bounds",
-                })
-            })?;
-            let dst_row = &mut dst.rows_mut().nth(y).ok_or_else(|| {
-                at!(Error::Decode {
-                    code: -1,
-                    msg: "Destination row index out of bounds",
-                })
-            })?[..w];
-            for (i, px) in src_row[..w].iter().enumerate() {
-                dst_row[i] = Rgb {
-                    r: srgb_u8_to_linear(px.r),
-                    g: srgb_u8_to_linear(px.g),
-                    b: srgb_u8_to_linear(px.b),
+        let (src, info, _) = self.decode_linear(data)?;
+        let w = dst.width().min(src.width());
+        for (src_row, dst_row) in src.rows().zip(dst.rows_mut()) {
+            for (d, s) in dst_row[..w].iter_mut().zip(&src_row[..w]) {
+                *d = Rgb {
+                    r: s.r,
+                    g: s.g,
+                    b: s.b,
                 };
             }
         }
         Ok(info)
     }
 
-    /// Convenience: decode into a pre-allocated RGBA f32 buffer.
+    /// Convenience: decode into a pre-allocated linear-light RGBA f32 buffer.
+    ///
+    /// See [`decode_into_rgb_f32`](Self::decode_into_rgb_f32). Alpha is
+    /// straight and scaled to `[0, 1]`.
     pub fn decode_into_rgba_f32(
         &self,
         data: &[u8],
         mut dst: imgref::ImgRefMut<'_, Rgba<f32>>,
     ) -> Result<ImageInfo, At<Error>> {
-        use linear_srgb::default::srgb_u8_to_linear;
-        let output = self.decode(data)?;
-        let info = output.info().clone();
-        let src = output.into_buffer().to_rgba8();
-        let src_ref = src.as_imgref();
-        let w = dst.width().min(src_ref.width());
-        let h = dst.height().min(src_ref.height());
-        for y in 0..h {
-            let src_row = src_ref.rows().nth(y).ok_or_else(|| {
-                at!(Error::Decode {
-                    code: -1,
-                    msg: "Source row index out of bounds",
-                })
-            })?;
-            let dst_row = &mut dst.rows_mut().nth(y).ok_or_else(|| {
-                at!(Error::Decode {
-                    code: -1,
-                    msg: "Destination row index out of bounds",
-                })
-            })?[..w];
-            for (i, px) in src_row[..w].iter().enumerate() {
-                dst_row[i] = Rgba {
-                    r: srgb_u8_to_linear(px.r),
-                    g: srgb_u8_to_linear(px.g),
-                    b: srgb_u8_to_linear(px.b),
-                    a: px.a as f32 / 255.0,
-                };
-            }
+        let (src, info, _) = self.decode_linear(data)?;
+        let w = dst.width().min(src.width());
+        for (src_row, dst_row) in src.rows().zip(dst.rows_mut()) {
+            dst_row[..w].copy_from_slice(&src_row[..w]);
         }
         Ok(info)
     }
 
-    /// Convenience: decode into a pre-allocated Gray f32 buffer.
+    /// Convenience: decode into a pre-allocated linear-light RGBA `f16` buffer.
+    ///
+    /// The values of [`decode_into_rgba_f32`](Self::decode_into_rgba_f32) at
+    /// half the memory, laid out for OpenEXR `HALF` channels. `f16` keeps
+    /// about 11 significant bits, more than any 10/12-bit source carries.
+    #[cfg(feature = "f16")]
+    pub fn decode_into_rgba_f16(
+        &self,
+        data: &[u8],
+        mut dst: imgref::ImgRefMut<'_, Rgba<half::f16>>,
+    ) -> Result<ImageInfo, At<Error>> {
+        let mut decoder = crate::ManagedAvifDecoder::new(data, &self.inner)?;
+        let (pixels, native_info) = decoder.decode_full(&enough::Unstoppable)?;
+        let src = crate::transfer::linear_rgba_as(
+            &pixels,
+            native_info.transfer_characteristics.0,
+            decoder.output_is_video_range(&native_info),
+            half::f16::from_f32,
+        );
+        let w = dst.width().min(src.width());
+        for (src_row, dst_row) in src.rows().zip(dst.rows_mut()) {
+            dst_row[..w].copy_from_slice(&src_row[..w]);
+        }
+        Ok(convert_native_info(&native_info))
+    }
+
+    /// Convenience: decode into a pre-allocated linear-light Gray f32 buffer.
+    ///
+    /// Luminance is weighted for the image's CICP color primaries.
     pub fn decode_into_gray_f32(
         &self,
         data: &[u8],
         mut dst: imgref::ImgRefMut<'_, rgb::Gray<f32>>,
     ) -> Result<ImageInfo, At<Error>> {
-        use linear_srgb::default::srgb_u8_to_linear;
-        let output = self.decode(data)?;
-        let info = output.info().clone();
-        let src = output.into_buffer().to_rgb8();
-        let src_ref = src.as_imgref();
-        let w = dst.width().min(src_ref.width());
-        let h = dst.height().min(src_ref.height());
-        // BT.709 luma coefficients in linear light
-        let (kr, kb) =
-            crate::yuv_convert::matrix_coefficients(crate::yuv_convert::YuvMatrix::Bt709);
-        let kg = 1.0 - kr - kb;
-        for y in 0..h {
-            let src_row = src_ref.rows().nth(y).ok_or_else(|| {
-                at!(Error::Decode {
-                    code: -1,
-                    msg: "Source row index out of bounds",
-                })
-            })?;
-            let dst_row = &mut dst.rows_mut().nth(y).ok_or_else(|| {
-                at!(Error::Decode {
-                    code: -1,
-                    msg: "Destination row index out of bounds",
-                })
-            })?[..w];
-            for (i, px) in src_row[..w].iter().enumerate() {
-                let r = srgb_u8_to_linear(px.r);
-                let g = srgb_u8_to_linear(px.g);
-                let b = srgb_u8_to_linear(px.b);
-                let luma = kr * r + kg * g + kb * b;
-                dst_row[i] = rgb::Gray(luma);
+        let (src, info, primaries) = self.decode_linear(data)?;
+        let [kr, kg, kb] = crate::transfer::luminance_weights(primaries);
+        let w = dst.width().min(src.width());
+        for (src_row, dst_row) in src.rows().zip(dst.rows_mut()) {
+            for (d, s) in dst_row[..w].iter_mut().zip(&src_row[..w]) {
+                *d = rgb::Gray(kr * s.r + kg * s.g + kb * s.b);
             }
         }
         Ok(info)
@@ -1847,6 +1873,7 @@ impl AvifDecodeJob {
         // Forward resource limits to the container parser.
         if let Some(mem) = self.limits.max_memory_bytes {
             cfg.parser_peak_memory_limit = Some(mem);
+            cfg = cfg.max_memory_bytes(mem);
         }
         if let Some(px) = self.limits.max_pixels {
             // Convert pixels to megapixels (round up to avoid zero).
@@ -1885,7 +1912,10 @@ impl AvifDecodeJob {
         Ok(())
     }
 
-    /// Check decoded image dimensions and estimated memory against limits.
+    /// Check decoded image dimensions against limits.
+    ///
+    /// Memory is checked earlier, when the decoder is created: the effective
+    /// config carries `max_memory_bytes` into the decoder's estimate.
     fn check_decode_limits(&self, info: &crate::image::ImageInfo) -> Result<(), At<Error>> {
         self.limits
             .check_dimensions(info.width, info.height)
@@ -1895,18 +1925,6 @@ impl AvifDecodeJob {
                     height: info.height,
                 })
             })?;
-        // Estimate output memory: width * height * max_bpp (4 bytes for RGBA8, 8 for RGBA16)
-        let bpp: u64 = if info.bit_depth > 8 {
-            if info.has_alpha { 8 } else { 6 }
-        } else if info.has_alpha {
-            4
-        } else {
-            3
-        };
-        let estimated_mem = info.width as u64 * info.height as u64 * bpp;
-        self.limits
-            .check_memory(estimated_mem)
-            .map_err(|e| at!(Error::ResourceLimit(format!("{e}"))))?;
         Ok(())
     }
 }
@@ -2583,7 +2601,7 @@ pub struct AvifStreamingDecoder {
     output_width: u32,
     output_height: u32,
     /// Grid path: managed decoder for tile-row streaming.
-    decoder: Option<crate::ManagedAvifDecoder>,
+    decoder: Option<crate::ManagedAvifDecoder<'static>>,
     /// Stop token for cancellable grid decoding.
     stop: zencodec::StopToken,
     grid_rows: u32,
@@ -2602,27 +2620,22 @@ pub struct AvifStreamingDecoder {
 impl AvifStreamingDecoder {
     /// Stitch decoded tiles horizontally into `self.strip_buffer`.
     fn stitch_tiles(&mut self, tiles: &[PixelBuffer], strip_h: u32) {
-        let bpp = self.strip_descriptor.bytes_per_pixel();
         let mut strip = PixelBuffer::new(self.output_width, strip_h, self.strip_descriptor);
         {
             let mut sm = strip.as_slice_mut();
-            for py in 0..strip_h {
-                let dst_row = sm.row_mut(py);
-                let mut x_offset = 0usize;
-                for tile in tiles {
-                    let tile_w = tile.width() as usize;
-                    let actual_w =
-                        tile_w.min((self.output_width as usize).saturating_sub(x_offset));
-                    if actual_w == 0 {
-                        continue;
-                    }
-                    let tile_slice = tile.as_slice();
-                    let src = tile_slice.row(py);
-                    let copy_bytes = actual_w * bpp;
-                    let dst_start = x_offset * bpp;
-                    dst_row[dst_start..dst_start + copy_bytes].copy_from_slice(&src[..copy_bytes]);
-                    x_offset += tile_w;
-                }
+            let mut x_offset = 0usize;
+            for tile in tiles {
+                let tile_w = tile.width() as usize;
+                let actual_w = tile_w.min((self.output_width as usize).saturating_sub(x_offset));
+                crate::plane_copy::copy_rect(
+                    &tile.as_slice(),
+                    (0, 0),
+                    &mut sm,
+                    (x_offset, 0),
+                    actual_w,
+                    strip_h as usize,
+                );
+                x_offset += tile_w;
             }
         }
         self.strip_buffer = Some(strip);
@@ -3110,6 +3123,40 @@ mod tests {
         }
     }
 
+    #[cfg(all(feature = "encode", feature = "f16"))]
+    #[test]
+    fn f16_matches_f32_output() {
+        let pixels: Vec<Rgba<f32>> = (0..16 * 16)
+            .map(|i| {
+                let t = i as f32 / 255.0;
+                Rgba::new(t, 0.5, 1.0 - t, 1.0)
+            })
+            .collect();
+        let img = imgref::ImgVec::new(pixels, 16, 16);
+        let output = AvifEncoderConfig::new()
+            .with_quality(100.0)
+            .with_effort_u32(10)
+            .encode_rgba_f32(img.as_ref())
+            .unwrap();
+
+        let dec = AvifDecoderConfig::new();
+        let mut full = imgref::ImgVec::new(vec![Rgba::new(0.0f32, 0.0, 0.0, 0.0); 16 * 16], 16, 16);
+        dec.decode_into_rgba_f32(output.data(), full.as_mut())
+            .unwrap();
+        let zero = half::f16::ZERO;
+        let mut halves =
+            imgref::ImgVec::new(vec![Rgba::new(zero, zero, zero, zero); 16 * 16], 16, 16);
+        dec.decode_into_rgba_f16(output.data(), halves.as_mut())
+            .unwrap();
+
+        for (h, f) in halves.buf().iter().zip(full.buf()) {
+            assert_eq!(h.a.to_f32(), 1.0);
+            for (h, f) in [(h.r, f.r), (h.g, f.g), (h.b, f.b)] {
+                assert_eq!(h, half::f16::from_f32(f), "{f}");
+            }
+        }
+    }
+
     #[cfg(feature = "encode")]
     #[test]
     fn f32_gray_roundtrip() {
@@ -3138,6 +3185,48 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "encode")]
+    #[test]
+    fn f32_gray_encodes_monochrome_srgb_luma() {
+        use rgb::Gray;
+
+        let pixels: Vec<Gray<f32>> = (0..16 * 16).map(|i| Gray(i as f32 / 255.0)).collect();
+        let img = imgref::ImgVec::new(pixels.clone(), 16, 16);
+        let output = AvifEncoderConfig::new()
+            .with_quality(100.0)
+            .with_effort_u32(10)
+            .encode_gray_f32(img.as_ref())
+            .unwrap();
+
+        let decoder =
+            crate::ManagedAvifDecoder::new(output.data(), &crate::DecoderConfig::default())
+                .unwrap();
+        let info = decoder.probe_info().unwrap();
+        assert_eq!(
+            info.transfer_characteristics.0, 13,
+            "samples are sRGB-encoded"
+        );
+        assert_eq!(info.bit_depth, 10);
+        assert_eq!(info.chroma_sampling, crate::ChromaSampling::Monochrome);
+
+        let mut rgb = imgref::ImgVec::new(vec![Rgb::new(0.0f32, 0.0, 0.0); 16 * 16], 16, 16);
+        AvifDecoderConfig::new()
+            .decode_into_rgb_f32(output.data(), rgb.as_mut())
+            .unwrap();
+        for (p, src) in rgb.buf().iter().zip(&pixels) {
+            assert!(
+                (p.r - p.g).abs() < 0.002 && (p.b - p.g).abs() < 0.002,
+                "chroma leaked: {p:?}"
+            );
+            assert!(
+                (p.g - src.value()).abs() < 0.02,
+                "{} vs {}",
+                p.g,
+                src.value()
+            );
+        }
+    }
+
     #[cfg(feature = "encode")]
     #[test]
     fn effort_and_quality_getters() {
@@ -3151,6 +3240,42 @@ mod tests {
         assert_eq!(config.is_lossless(), Some(false));
     }
 
+    #[cfg(feature = "encode")]
+    #[test]
+    fn calibrated_quality_maps_through_table() {
+        let native = |q: f32| {
+            AvifEncoderConfig::new()
+                .with_calibrated_quality(q)
+                .inner
+                .quality
+        };
+        assert_eq!(native(50.0), 50.0);
+        assert!((native(80.0) - 66.6).abs() < 1e-4);
+        // Halfway between the 90 and 92 entries.
+        assert!((native(91.0) - 77.05).abs() < 1e-4);
+        // Clamped to the table ends.
+        assert_eq!(native(0.0), 5.0);
+        assert_eq!(native(100.0), 87.0);
+
+        let mut previous = 0.0;
+        for q in 0..=100 {
+            let n = native(q as f32);
+            assert!(n >= previous, "calibration not monotonic at {q}");
+            previous = n;
+        }
+
+        let config = AvifEncoderConfig::new().with_calibrated_quality(80.0);
+        assert_eq!(
+            zencodec::encode::EncoderConfig::generic_quality(&config),
+            Some(80.0)
+        );
+        // The plain setter stays on the native scale.
+        assert_eq!(
+            AvifEncoderConfig::new().with_quality(80.0).inner.quality,
+            80.0
+        );
+    }
+
     #[cfg(feature = "encode")]
     #[test]
     fn four_layer_encode_flow() {
@@ -4029,7 +4154,7 @@ mod tests {
             .encode_rgb8(img.as_ref())
             .unwrap();
 
-        // Set max_memory to 1 byte — decode should fail with ResourceLimit, not Encode
+        // Set max_memory to 1 byte — decode should fail with ResourceLimitExceeded, not Encode
         let config = AvifDecoderConfig::new();
         let limits = ResourceLimits::none().with_max_memory(1);
         let decoder = config
@@ -4044,8 +4169,11 @@ mod tests {
         assert!(result.is_err(), "expected error from memory limit");
         let err = result.err().unwrap();
         assert!(
-            matches!(err.error(), Error::ResourceLimit(_)),
-            "expected Error::ResourceLimit, got: {}",
+            matches!(
+                err.error(),
+                Error::ResourceLimitExceeded { limit: 1, estimated } if *estimated > 32 * 32 * 3
+            ),
+            "expected Error::ResourceLimitExceeded, got: {}",
             err
         );
     }
@@ -0,0 +1,175 @@
+//! Compositing images before encoding.
+//!
+//! Blending happens on straight (unassociated) alpha as decoding produces
+//! it: samples are premultiplied, combined with the source-over operator,
+//! and divided back out, so fully transparent pixels never bleed their
+//! color into the result. Blending is done on the encoded (gamma) values,
+//! like most imaging tools do.
+
+use crate::error::{Error, Result};
+use crate::plane_copy::sample_layout;
+use whereat::at;
+use zenpixels::PixelBuffer;
+
+/// Draw `overlay` onto `base` with its top-left corner at (`x`, `y`).
+///
+/// Both images may be 8- or 16-bit RGB, RGBA or gray, in any combination;
+/// the result keeps `base`'s layout. Color overlays on a gray base
+/// contribute their BT.709 luma. `opacity` (clamped to `0.0..=1.0`) scales
+/// the overlay's alpha. Parts of the overlay outside `base`, including at
+/// negative offsets, are clipped.
+///
+/// # Example
+///
+/// ```no_run
+/// let mut photo = zenavif::decode(&std::fs::read("photo.avif").unwrap()).unwrap();
+/// let logo = zenavif::decode(&std::fs::read("logo.avif").unwrap()).unwrap();
+/// let x = photo.width() as i32 - logo.width() as i32 - 16;
+/// let y = photo.height() as i32 - logo.height() as i32 - 16;
+/// zenavif::composite::overlay(&mut photo, &logo, x, y, 0.5).unwrap();
+/// ```
+pub fn overlay(
+    base: &mut PixelBuffer,
+    overlay: &PixelBuffer,
+    x: i32,
+    y: i32,
+    opacity: f32,
+) -> Result<()> {
+    let unsupported = || {
+        at!(Error::Unsupported(
+            "overlay supports 8/16-bit RGB, RGBA and gray pixels"
+        ))
+    };
+    let (base_channels, base_size) = sample_layout(base).ok_or_else(unsupported)?;
+    let (top_channels, top_size) = sample_layout(overlay).ok_or_else(unsupported)?;
+    let opacity = opacity.clamp(0.0, 1.0);
+
+    // Overlay coordinates that land inside `base`.
+    let visible = |offset: i32, top: u32, below: u32| {
+        let offset = i64::from(offset);
+        (-offset).max(0)..i64::from(top).min(i64::from(below) - offset)
+    };
+    let cols = visible(x, overlay.width(), base.width());
+    let rows = visible(y, overlay.height(), base.height());
+    if opacity == 0.0 || cols.is_empty() || rows.is_empty() {
+        return Ok(());
+    }
+
+    let (top_px, base_px) = (top_channels * top_size, base_channels * base_size);
+    let src = overlay.as_slice();
+    let mut dst = base.as_slice_mut();
+    for oy in rows {
+        let top_row = src.row(oy as u32);
+        let base_row = dst.row_mut((oy + i64::from(y)) as u32);
+        for ox in cols.clone() {
+            let bx = (ox + i64::from(x)) as usize;
+            let top = read(&top_row[ox as usize * top_px..][..top_px], top_size);
+            let px = &mut base_row[bx * base_px..][..base_px];
+            let below = read(px, base_size);
+            write(px, source_over(below, top, opacity), base_size);
+        }
+    }
+    Ok(())
+}
+
+/// Straight RGBA in `0.0..=1.0` from the samples of one pixel.
+fn read(px: &[u8], size: usize) -> [f32; 4] {
+    let mut samples = px.chunks_exact(size).map(|s| match *s {
+        [v] => f32::from(v) / 255.0,
+        [a, b] => f32::from(u16::from_ne_bytes([a, b])) / 65535.0,
+        _ => 0.0,
+    });
+    let mut next = || samples.next().unwrap_or(1.0);
+    match px.len() / size {
+        1 => {
+            let v = next();
+            [v, v, v, 1.0]
+        }
+        _ => [next(), next(), next(), next()],
+    }
+}
+
+/// Store straight RGBA into the samples of one pixel.
+fn write(px: &mut [u8], [r, g, b, a]: [f32; 4], size: usize) {
+    let luma = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let values = match px.len() / size {
+        1 => [luma, 0.0, 0.0, 0.0],
+        _ => [r, g, b, a],
+    };
+    for (sample, v) in px.chunks_exact_mut(size).zip(values) {
+        let v = v.clamp(0.0, 1.0);
+        if size == 1 {
+            sample[0] = (v * 255.0).round() as u8;
+        } else {
+            sample.copy_from_slice(&((v * 65535.0).round() as u16).to_ne_bytes());
+        }
+    }
+}
+
+/// Porter-Duff source-over of `top` (alpha scaled by `opacity`) onto
+/// `below`, computed on premultiplied values.
+fn source_over(below: [f32; 4], top: [f32; 4], opacity: f32) -> [f32; 4] {
+    let top_alpha = top[3] * opacity;
+    let below_weight = below[3] * (1.0 - top_alpha);
+    let alpha = top_alpha + below_weight;
+    if alpha <= 0.0 {
+        return [0.0; 4];
+    }
+    let mix = |c: usize| (top[c] * top_alpha + below[c] * below_weight) / alpha;
+    [mix(0), mix(1), mix(2), alpha]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rgb::{Gray, Rgb, Rgba};
+
+    fn rgba8(width: u32, height: u32, px: Rgba<u8>) -> PixelBuffer {
+        let pixels = vec![px; (width * height) as usize];
+        PixelBuffer::from_pixels(pixels, width, height)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn half_opacity_blends_and_clips() {
+        let mut base = rgba8(4, 4, Rgba::new(0, 0, 0, 255));
+        let top = rgba8(2, 2, Rgba::new(200, 100, 0, 255));
+        overlay(&mut base, &top, 3, -1, 0.5).unwrap();
+
+        let img = base.try_as_imgref::<Rgba<u8>>().unwrap();
+        assert_eq!(img.buf()[3], Rgba::new(100, 50, 0, 255));
+        assert_eq!(img.buf()[2], Rgba::new(0, 0, 0, 255));
+        assert_eq!(img.buf()[img.stride() + 3], Rgba::new(0, 0, 0, 255));
+    }
+
+    #[test]
+    fn transparent_base_takes_overlay_color() {
+        // Straight-alpha blending would darken red toward the base's black.
+        let mut base = rgba8(1, 1, Rgba::new(0, 0, 0, 0));
+        let top = rgba8(1, 1, Rgba::new(255, 0, 0, 128));
+        overlay(&mut base, &top, 0, 0, 1.0).unwrap();
+
+        let img = base.try_as_imgref::<Rgba<u8>>().unwrap();
+        assert_eq!(img.buf()[0], Rgba::new(255, 0, 0, 128));
+    }
+
+    #[test]
+    fn mixed_depths_and_gray() {
+        let mut base: PixelBuffer = PixelBuffer::from_pixels(vec![Rgb::new(0u16, 0, 0)], 1, 1)
+            .unwrap()
+            .into();
+        let top = rgba8(1, 1, Rgba::new(255, 255, 255, 255));
+        overlay(&mut base, &top, 0, 0, 1.0).unwrap();
+        let img = base.try_as_imgref::<Rgb<u16>>().unwrap();
+        assert_eq!(img.buf()[0], Rgb::new(65535, 65535, 65535));
+
+        let mut gray: PixelBuffer = PixelBuffer::from_pixels(vec![Gray::new(0u8)], 1, 1)
+            .unwrap()
+            .into();
+        let green = rgba8(1, 1, Rgba::new(0, 255, 0, 255));
+        overlay(&mut gray, &green, 0, 0, 1.0).unwrap();
+        let img = gray.try_as_imgref::<Gray<u8>>().unwrap();
+        assert_eq!(img.buf()[0], Gray::new(182));
+    }
+}
@@ -1,5 +1,249 @@
 //! Decoder configuration
 
+use crate::cache_key::KeyHasher;
+use crate::convert::fits_8bit;
+use crate::error::Error;
+use crate::image::{ImageInfo, TransferCharacteristics};
+use crate::plane_copy::Rect;
+use std::fmt;
+use std::sync::Arc;
+use zenpixels::PixelBuffer;
+
+/// Signature of a [`DecoderConfig::post_process`] hook.
+pub type PostProcessFn =
+    dyn Fn(&mut PixelBuffer, &ImageInfo) -> core::result::Result<(), Error> + Send + Sync;
+
+/// Shared handle to a post-processing hook, so configs stay cheap to clone.
+#[derive(Clone)]
+pub(crate) struct PostProcess(pub(crate) Arc<PostProcessFn>);
+
+impl fmt::Debug for PostProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PostProcess(..)")
+    }
+}
+
+/// How limited-range sources handle luma outside the nominal `[16, 235]` range.
+///
+/// Real-world encoders sometimes emit super-whites (Y above 235) and
+/// sub-blacks (Y below 16). By default these are clipped during YUV→RGB
+/// conversion; grading workflows may want to keep them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RangeClipping {
+    /// Clip to the nominal range while expanding limited → full (default).
+    #[default]
+    Clip,
+    /// Keep over-range values in 16-bit output.
+    ///
+    /// Limited-range 10/12-bit sources are converted to RGB that stays in
+    /// video range: nominal black and white land at `16 << 8` and `235 << 8`,
+    /// leaving headroom for super-whites up to `65535` and footroom for
+    /// sub-blacks down to `0`. [`ImageInfo::color_range`](crate::ImageInfo)
+    /// stays [`ColorRange::Limited`](crate::ColorRange) so callers know to
+    /// expand the range themselves. Output reduced to 8 bits keeps the same
+    /// scale, with black and white at `16` and `235`.
+    ///
+    /// Linear-light float output expands the range without clipping, so
+    /// overshoot lands below 0.0 and above 1.0. ICC color management is
+    /// skipped for these images, as a profile describes full-range RGB;
+    /// the returned info then describes the source. YCgCo and identity
+    /// (GBR) sources are always clipped.
+    PreserveOvershoot,
+}
+
+/// Range of the RGB output for limited-range (video range) sources.
+///
+/// Full-range sources always decode to full-range output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputRange {
+    /// Expand to full range: black is `0` and white the sample maximum
+    /// (default).
+    #[default]
+    Full,
+    /// Keep video range, for output headed back into a video encoder.
+    ///
+    /// Black and white land at `16` and `235` in 8-bit output and at
+    /// `16 << 8` and `235 << 8` in 16-bit output; alpha stays full range.
+    /// [`ImageInfo::color_range`](crate::ImageInfo) stays
+    /// [`ColorRange::Limited`](crate::ColorRange) to describe the output.
+    PreserveLimited,
+}
+
+/// Channel order and packing of 8-bit or packed 16-bit output.
+///
+/// [`PixelBuffer`](zenpixels::PixelBuffer) can describe RGB and BGRA
+/// output only; the other layouts are written by
+/// [`ManagedAvifDecoder::decode_into_layout`](crate::ManagedAvifDecoder::decode_into_layout).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputLayout {
+    /// RGB, RGBA or gray in the source's depth (default).
+    #[default]
+    Rgb,
+    /// 8-bit B, G, R, A, as GDI, Direct2D and most GPU swapchains expect.
+    Bgra8,
+    /// 8-bit B, G, R.
+    Bgr8,
+    /// 8-bit A, R, G, B.
+    Argb8,
+    /// 8-bit A, B, G, R.
+    Abgr8,
+    /// One little-endian `u16` per pixel: red in the top 5 bits, then
+    /// 6 bits of green and 5 of blue. Alpha is dropped.
+    Rgb565,
+    /// One little-endian `u16` per pixel: 4 bits each of red, green, blue
+    /// and alpha, red in the top bits.
+    Rgba4444,
+}
+
+impl OutputLayout {
+    /// Bytes per pixel of this layout; `Rgb` depends on the image's alpha.
+    pub fn bytes_per_pixel(self, has_alpha: bool) -> usize {
+        match self {
+            OutputLayout::Rgb if has_alpha => 4,
+            OutputLayout::Rgb | OutputLayout::Bgr8 => 3,
+            OutputLayout::Bgra8 | OutputLayout::Argb8 | OutputLayout::Abgr8 => 4,
+            OutputLayout::Rgb565 | OutputLayout::Rgba4444 => 2,
+        }
+    }
+}
+
+/// Filter that brings 4:2:0 and 4:2:2 chroma up to luma resolution.
+///
+/// See [`DecoderConfig::chroma_upsampling`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChromaUpsampling {
+    /// Replicate each chroma sample. Fastest; blocky colour edges.
+    Nearest,
+    /// Linear interpolation between the two nearest chroma samples,
+    /// honouring the stream's chroma sample position.
+    Bilinear,
+    /// Catmull-Rom cubic over four chroma samples. Sharper colour edges
+    /// than bilinear, with slight ringing.
+    CatmullRom,
+}
+
+/// Which YUV to RGB converters 8-bit sources go through.
+///
+/// See [`DecoderConfig::conversion_backend`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConversionBackend {
+    /// zenavif's own `f32` converters with bilinear, siting-aware 4:2:0
+    /// chroma (default). The most accurate.
+    #[default]
+    Reference,
+    /// libyuv's integer arithmetic, bit for bit, as libavif uses when built
+    /// with libyuv. For reproducing its output; limited-range BT.601 and
+    /// BT.709 only.
+    Exact,
+    /// The `yuv` crate's integer converters. Fastest, with slightly coarser
+    /// rounding and chroma replicated rather than interpolated.
+    Fast,
+}
+
+/// What happens when an animation exceeds [`DecoderConfig::max_frames`],
+/// [`DecoderConfig::max_total_duration_ms`] or
+/// [`DecoderConfig::max_frame_duration_ms`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnimationLimitPolicy {
+    /// Fail with [`Error::ResourceLimit`](crate::Error::ResourceLimit)
+    /// before decoding any frame (default).
+    #[default]
+    Fail,
+    /// Decode only the frames within the limits; the reported frame count
+    /// shrinks to match. Frames over the per-frame maximum are shortened to
+    /// it instead.
+    Truncate,
+}
+
+/// Animation limits taken from a [`DecoderConfig`].
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct AnimationLimits {
+    pub(crate) max_frames: Option<u32>,
+    pub(crate) max_total_duration_ms: Option<u64>,
+    pub(crate) min_frame_duration_ms: u32,
+    pub(crate) max_frame_duration_ms: Option<u32>,
+    pub(crate) policy: AnimationLimitPolicy,
+}
+
+impl AnimationLimits {
+    /// `duration_ms` as reported to callers: raised to the minimum and,
+    /// when truncating, capped at the maximum.
+    pub(crate) fn frame_duration(&self, duration_ms: u32) -> u32 {
+        let duration_ms = duration_ms.max(self.min_frame_duration_ms);
+        match (self.max_frame_duration_ms, self.policy) {
+            (Some(max), AnimationLimitPolicy::Truncate) => duration_ms.min(max),
+            _ => duration_ms,
+        }
+    }
+}
+
+/// What replaces grid tiles that fail to decode under
+/// [`DecoderConfig::error_resilient`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TileFill {
+    /// A solid 8-bit RGBA color (default: opaque black).
+    ///
+    /// Scaled to 16 bits for 16-bit output and reduced to BT.709 luma for
+    /// gray output.
+    Color([u8; 4]),
+    /// The average of the pixels just outside the tile, taken from the
+    /// neighbouring tiles that did decode. Falls back to black when none did.
+    NeighborAverage,
+}
+
+impl Default for TileFill {
+    fn default() -> Self {
+        Self::Color([0, 0, 0, 255])
+    }
+}
+
+/// Destination color space for [`DecoderConfig::color_manage`].
+#[cfg(feature = "cms")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    /// sRGB. Converted images are described by CICP and carry no ICC
+    /// profile.
+    Srgb,
+    /// A caller-provided ICC profile, reported as the converted image's
+    /// profile.
+    Icc(Arc<[u8]>),
+}
+
+/// Sample depth of the RGB output for 10/12-bit sources.
+///
+/// 8-bit sources always decode to 8-bit output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputDepth {
+    /// 8-bit output when the decoded samples sit on 8-bit levels, as they
+    /// do for 8-bit images encoded at 10-bit; 16-bit otherwise.
+    ///
+    /// The check builds a histogram of each sample's distance from the
+    /// nearest 8-bit level. Genuine high-depth content spreads evenly across
+    /// that range, while upconverted 8-bit content clusters near zero. PQ
+    /// and HLG sources always stay 16-bit.
+    Auto,
+    /// Always downscale to 8-bit, like [`DecoderConfig::prefer_8bit`].
+    Force8,
+    /// Keep 16-bit output (default).
+    #[default]
+    Force16,
+}
+
+impl OutputDepth {
+    /// Whether a 16-bit decode of a high-depth source should become 8-bit.
+    pub(crate) fn wants_8bit(self, image: &PixelBuffer, transfer: TransferCharacteristics) -> bool {
+        match self {
+            Self::Force8 => true,
+            Self::Force16 => false,
+            Self::Auto => {
+                transfer != TransferCharacteristics::SMPTE2084
+                    && transfer != TransferCharacteristics::HLG
+                    && fits_8bit(image)
+            }
+        }
+    }
+}
+
 /// Configuration for AVIF decoding
 #[derive(Debug, Clone)]
 pub struct DecoderConfig {
@@ -7,8 +251,16 @@ pub struct DecoderConfig {
     pub(crate) threads: u32,
     /// Whether to apply film grain synthesis
     pub(crate) apply_grain: bool,
+    /// Film grain seed replacing the one in the bitstream.
+    pub(crate) grain_seed_override: Option<u16>,
+    /// Decode on a single thread.
+    pub(crate) deterministic: bool,
     /// Maximum frame size limit in pixels (0 = no limit)
     pub(crate) frame_size_limit: u32,
+    /// Upper bound on the estimated memory of a decode, in bytes.
+    pub(crate) max_memory_bytes: Option<u64>,
+    /// Frame count and duration limits for animations.
+    pub(crate) animation_limits: AnimationLimits,
     /// CPU feature flags mask (bitwise AND with detected features).
     /// Use to disable SIMD paths for testing. Default: all enabled.
     /// x86_64: bit 3 = AVX2, bit 2 = SSE4.1, bit 1 = SSSE3, bit 0 = SSE2
@@ -19,10 +271,49 @@ pub struct DecoderConfig {
     pub(crate) parser_total_megapixels_limit: Option<u32>,
     /// Parser max animation frames (forwarded to zenavif-parse).
     pub(crate) parser_max_animation_frames: Option<u32>,
-    /// When true, 10/12-bit AV1 content is downscaled to 8-bit RGB output.
+    /// Whether 10/12-bit AV1 content is downscaled to 8-bit RGB output.
     /// Most AVIF encoders (including zenravif) default to 10-bit encoding
-    /// even for 8-bit input. This option returns 8-bit output for those files.
-    pub(crate) prefer_8bit: bool,
+    /// even for 8-bit input, so 8-bit output is often all that's needed.
+    pub(crate) output_depth: OutputDepth,
+    /// Ordered dither when reducing 10/12-bit samples to 8-bit output.
+    pub(crate) dither: bool,
+    /// Chroma filter for subsampled sources; `None` keeps each path's own.
+    pub(crate) chroma_upsampling: Option<ChromaUpsampling>,
+    /// Converter family for 8-bit sources.
+    pub(crate) conversion_backend: ConversionBackend,
+    /// Handling of limited-range luma overshoot in 16-bit output.
+    pub(crate) range_clipping: RangeClipping,
+    /// Range of the RGB output for limited-range sources.
+    pub(crate) output_range: OutputRange,
+    /// Channel order of the output.
+    pub(crate) output_layout: OutputLayout,
+    /// Debanding strength, 0.0 (off) to 1.0.
+    pub(crate) deband: f32,
+    /// User hook run on the converted image before it is returned.
+    pub(crate) post_process: Option<PostProcess>,
+    /// Reject files whose `pixi` property contradicts the AV1 stream.
+    pub(crate) strict_pixi: bool,
+    /// Decode monochrome images without alpha to gray instead of RGB.
+    pub(crate) prefer_grayscale: bool,
+    /// Decode the next animation frame while converting the current one.
+    pub(crate) pipelined_animation: bool,
+    /// Size still images are reduced towards, for thumbnails.
+    pub(crate) downscale: Option<(u32, u32)>,
+    /// Region of still images to decode.
+    pub(crate) crop: Option<Rect>,
+    /// Fill grid tiles that fail to decode instead of failing the image.
+    pub(crate) error_resilient: bool,
+    /// Replacement for failed grid tiles.
+    pub(crate) tile_fill: TileFill,
+    /// Operating point of scalable AV1 streams (0..=31).
+    pub(crate) operating_point: u8,
+    /// Output every spatial layer instead of only the highest one.
+    pub(crate) all_layers: bool,
+    /// Decode the operating point with the fewest spatial layers.
+    pub(crate) prefer_lowest_resolution: bool,
+    /// Convert ICC-tagged images to this color space.
+    #[cfg(feature = "cms")]
+    pub(crate) color_manage: Option<Destination>,
 }
 
 impl Default for DecoderConfig {
@@ -33,12 +324,36 @@ impl Default for DecoderConfig {
             // frame threading overhead.
             threads: 0,
             apply_grain: true,
+            grain_seed_override: None,
+            deterministic: false,
             frame_size_limit: 0,
+            max_memory_bytes: None,
+            animation_limits: AnimationLimits::default(),
             cpu_flags_mask: u32::MAX,
             parser_peak_memory_limit: None,
             parser_total_megapixels_limit: None,
             parser_max_animation_frames: None,
-            prefer_8bit: false,
+            output_depth: OutputDepth::Force16,
+            dither: false,
+            chroma_upsampling: None,
+            conversion_backend: ConversionBackend::Reference,
+            range_clipping: RangeClipping::Clip,
+            output_range: OutputRange::Full,
+            output_layout: OutputLayout::Rgb,
+            deband: 0.0,
+            post_process: None,
+            strict_pixi: false,
+            prefer_grayscale: false,
+            pipelined_animation: false,
+            downscale: None,
+            crop: None,
+            error_resilient: false,
+            tile_fill: TileFill::default(),
+            operating_point: 0,
+            all_layers: false,
+            prefer_lowest_resolution: false,
+            #[cfg(feature = "cms")]
+            color_manage: None,
         }
     }
 }
@@ -66,6 +381,30 @@ impl DecoderConfig {
         self
     }
 
+    /// Synthesize film grain from `seed` instead of the bitstream's seed.
+    ///
+    /// Default: unset. Makes the grain pattern of a still image a function
+    /// of the seed alone, e.g. to produce reproducible test imagery from
+    /// differently encoded files. Only intra frames are reseeded, which
+    /// covers every still image and grid tile; inter frames of animations
+    /// keep their own seed. Has no effect when grain is not applied.
+    pub fn grain_seed_override(mut self, seed: u16) -> Self {
+        self.grain_seed_override = Some(seed);
+        self
+    }
+
+    /// Decode on a single thread, for bit-identical output on every run.
+    ///
+    /// Default: `false`. AV1 decoding, film grain synthesis included, is
+    /// specified bit-exactly and grain is seeded per frame from the
+    /// bitstream, so output shouldn't depend on thread count anyway; this
+    /// takes scheduling out of the picture entirely, at the cost of speed.
+    /// Overrides [`threads`](Self::threads) and decodes alpha after color.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
     /// Set maximum frame size limit in total pixels
     ///
     /// If width * height exceeds this limit, decoding will fail.
@@ -75,6 +414,75 @@ impl DecoderConfig {
         self
     }
 
+    /// Limit the estimated peak memory of a decode, in bytes.
+    ///
+    /// Before any AV1 data is decoded, the decoder's YUV frames, the alpha
+    /// plane and the RGB(A) output are sized from the container and
+    /// sequence header. When their sum exceeds the limit, creating the
+    /// decoder fails with [`Error::ResourceLimitExceeded`](crate::Error::ResourceLimitExceeded),
+    /// so files declaring huge dimensions are refused instead of running the
+    /// process out of memory. The estimate is conservative for grids, whose
+    /// tiles are decoded one at a time. Default: no limit.
+    pub fn max_memory_bytes(mut self, bytes: u64) -> Self {
+        self.max_memory_bytes = Some(bytes);
+        self
+    }
+
+    /// Limit animations to `frames` frames.
+    ///
+    /// Guards servers against animation bombs: files declaring thousands of
+    /// frames, each cheap to store but expensive to decode. The limit is
+    /// checked against the container before any frame is decoded; see
+    /// [`animation_limit_policy`](Self::animation_limit_policy) for what
+    /// happens when it is exceeded. Default: no limit.
+    pub fn max_frames(mut self, frames: u32) -> Self {
+        self.animation_limits.max_frames = Some(frames);
+        self
+    }
+
+    /// Limit animations to `ms` milliseconds of playback, summed over one
+    /// loop's frame durations.
+    ///
+    /// Checked like [`max_frames`](Self::max_frames). Default: no limit.
+    pub fn max_total_duration_ms(mut self, ms: u64) -> Self {
+        self.animation_limits.max_total_duration_ms = Some(ms);
+        self
+    }
+
+    /// Report frames shorter than `ms` milliseconds as lasting `ms`.
+    ///
+    /// Files with 0 ms frames, or absurd frame rates, play as fast as the
+    /// player can go; browsers show GIF frames of 10 ms or less for 100 ms
+    /// instead. With a minimum set here, [`DecodedFrame::duration_ms`](crate::DecodedFrame)
+    /// is already sanitized for playback layers. Raised durations count
+    /// towards [`max_total_duration_ms`](Self::max_total_duration_ms).
+    /// Default: 0, durations as stored.
+    pub fn min_frame_duration_ms(mut self, ms: u32) -> Self {
+        self.animation_limits.min_frame_duration_ms = ms;
+        self
+    }
+
+    /// Treat frames lasting longer than `ms` milliseconds as a limit
+    /// violation, e.g. hour-long frames that stall a slideshow.
+    ///
+    /// Under [`AnimationLimitPolicy::Fail`] such a file is refused before
+    /// decoding; under [`AnimationLimitPolicy::Truncate`] the frames are
+    /// reported as lasting `ms`. Default: no limit.
+    pub fn max_frame_duration_ms(mut self, ms: u32) -> Self {
+        self.animation_limits.max_frame_duration_ms = Some(ms);
+        self
+    }
+
+    /// Fail or truncate animations over [`max_frames`](Self::max_frames),
+    /// [`max_total_duration_ms`](Self::max_total_duration_ms) or
+    /// [`max_frame_duration_ms`](Self::max_frame_duration_ms).
+    ///
+    /// Default: [`AnimationLimitPolicy::Fail`].
+    pub fn animation_limit_policy(mut self, policy: AnimationLimitPolicy) -> Self {
+        self.animation_limits.policy = policy;
+        self
+    }
+
     /// Set CPU feature flags mask.
     ///
     /// Controls which SIMD code paths are used by masking detected CPU features.
@@ -98,8 +506,331 @@ impl DecoderConfig {
     ///
     /// Default: `false`. Enable when decoding files encoded at 10-bit from
     /// 8-bit sources and you want 8-bit output without an external conversion.
+    ///
+    /// Shorthand for [`output_depth`](Self::output_depth) with
+    /// [`OutputDepth::Force8`] or [`OutputDepth::Force16`].
     pub fn prefer_8bit(mut self, prefer: bool) -> Self {
-        self.prefer_8bit = prefer;
+        self.output_depth = if prefer {
+            OutputDepth::Force8
+        } else {
+            OutputDepth::Force16
+        };
         self
     }
+
+    /// Choose the output sample depth for 10/12-bit sources.
+    ///
+    /// Default: [`OutputDepth::Force16`]. [`OutputDepth::Auto`] halves output
+    /// memory for the common case of 8-bit images encoded at 10-bit, at the
+    /// cost of one pass over the decoded pixels.
+    pub fn output_depth(mut self, depth: OutputDepth) -> Self {
+        self.output_depth = depth;
+        self
+    }
+
+    /// Dither instead of truncating when 10/12-bit samples become 8-bit
+    /// output.
+    ///
+    /// Applies a 4×4 ordered dither to the color channels, trading the
+    /// contours of plain truncation for fine noise. Samples already on an
+    /// 8-bit level are unchanged. Also dithers the 8-bit to 4/5/6-bit step
+    /// of [`OutputLayout::Rgb565`] and [`OutputLayout::Rgba4444`], where
+    /// banding is far more visible. Default: `false`.
+    /// [`ImageInfo::lossy_conversion`](crate::ImageInfo::lossy_conversion)
+    /// reports when the reduction happened.
+    pub fn dither(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Choose the filter that upsamples 4:2:0 and 4:2:2 chroma.
+    ///
+    /// By default each conversion path keeps its own filter: bilinear for
+    /// 8-bit 4:2:0, sample replication for 8-bit 4:2:2 and 10/12-bit
+    /// sources. Setting a filter applies it to every bit depth and
+    /// subsampling alike, at the cost of an extra pass over the chroma
+    /// planes. 4:4:4 and monochrome sources are unaffected.
+    pub fn chroma_upsampling(mut self, filter: ChromaUpsampling) -> Self {
+        self.chroma_upsampling = Some(filter);
+        self
+    }
+
+    /// Choose the YUV to RGB converters for 8-bit sources.
+    ///
+    /// Default: [`ConversionBackend::Reference`]. [`ConversionBackend::Exact`]
+    /// matches libavif built with libyuv for limited-range BT.601 and BT.709
+    /// sources; other sources keep the reference converters.
+    /// [`ConversionBackend::Fast`] trades a code value or so of accuracy for
+    /// speed. 10/12-bit and monochrome sources are unaffected.
+    pub fn conversion_backend(mut self, backend: ConversionBackend) -> Self {
+        self.conversion_backend = backend;
+        self
+    }
+
+    /// Choose how limited-range super-whites and sub-blacks are handled.
+    ///
+    /// Default: [`RangeClipping::Clip`]. [`RangeClipping::PreserveOvershoot`]
+    /// only affects 10/12-bit limited-range color sources, and turns off
+    /// ICC color management for them.
+    pub fn range_clipping(mut self, clipping: RangeClipping) -> Self {
+        self.range_clipping = clipping;
+        self
+    }
+
+    /// Choose whether limited-range sources decode to full or video range.
+    ///
+    /// Default: [`OutputRange::Full`]. [`OutputRange::PreserveLimited`]
+    /// converts to full range as usual, then compresses the RGB or gray
+    /// samples back into video range, so it costs one extra pass over the
+    /// output. Grid images take the per-tile conversion path.
+    pub fn output_range(mut self, range: OutputRange) -> Self {
+        self.output_range = range;
+        self
+    }
+
+    /// Choose the channel order of the output.
+    ///
+    /// Default: [`OutputLayout::Rgb`]. Any other layout implies 8-bit
+    /// output, reduced as for [`OutputDepth::Force8`], and always carries
+    /// alpha when the layout has it (opaque images get full alpha). The
+    /// packed 16-bit layouts are for framebuffers short on memory. The
+    /// channels are reordered with SIMD shuffles after YUV conversion, so
+    /// callers needn't swizzle the result themselves. Applies to still
+    /// image decodes; animation frames stay RGB.
+    pub fn output_layout(mut self, layout: OutputLayout) -> Self {
+        self.output_layout = layout;
+        self
+    }
+
+    /// Smooth banding in flat gradients of 8-bit output.
+    ///
+    /// `strength` ranges from `0.0` (off, the default) to `1.0`; higher values
+    /// look further for neighbours and tolerate larger steps. Samples whose
+    /// neighbourhood contains an edge are left alone, and a small ordered
+    /// dither breaks up the remaining contours. 16-bit output is unaffected.
+    /// Runs on still images before any [`post_process`](Self::post_process)
+    /// hook.
+    pub fn deband(mut self, strength: f32) -> Self {
+        self.deband = strength.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Reject files whose `pixi` property contradicts the AV1 stream.
+    ///
+    /// Default: `false`. The AV1 bit depth is used for decoding either way;
+    /// lenient decoding reports the declared values in
+    /// [`ImageInfo::declared_bits_per_channel`] and
+    /// [`ImageInfo::bit_depth_mismatch`]. When `true`, a `pixi` whose channel
+    /// count or bit depth disagrees with `av1C` fails decoder construction
+    /// with [`Error::Unsupported`].
+    pub fn strict_pixi(mut self, strict: bool) -> Self {
+        self.strict_pixi = strict;
+        self
+    }
+
+    /// Return monochrome images as gray instead of expanding them to RGB.
+    ///
+    /// Default: `false`. When `true`, 4:0:0 still images without alpha
+    /// decode to `Gray8` or `Gray16` buffers, a third of the RGB size.
+    /// Limited range is expanded to full range as for RGB output, always
+    /// clipping; [`RangeClipping::PreserveOvershoot`] does not apply.
+    /// Images with alpha still decode to RGBA.
+    pub fn prefer_grayscale(mut self, prefer: bool) -> Self {
+        self.prefer_grayscale = prefer;
+        self
+    }
+
+    /// Overlap AV1 decoding and RGB conversion of animation frames.
+    ///
+    /// Default: `false`. When `true`, `decode_animation` decodes frames on a
+    /// helper thread while the calling thread converts the ones already
+    /// decoded, keeping at most two decoded frames in flight. Both sides
+    /// poll the stop token. Worth about 1.5–1.8× throughput on multicore
+    /// machines; single still images are unaffected.
+    pub fn pipelined_animation(mut self, pipelined: bool) -> Self {
+        self.pipelined_animation = pipelined;
+        self
+    }
+
+    /// Box-filter decoded still images down towards `width` × `height`.
+    ///
+    /// Default: unset. This is a resize after decoding, not a cheaper
+    /// decode: AV1 has no reduced-resolution decode, so the full image (and
+    /// every grid tile) is decoded and converted to RGB first. The result is
+    /// then area-averaged down by the largest whole factor that keeps it at
+    /// least `width` × `height`, so a 50 MP photo comes out a few megapixels
+    /// large and only needs a cheap final resize. Film grain synthesis is
+    /// skipped, as it would be averaged away. Images already smaller are
+    /// returned as is. [`ImageInfo`](crate::ImageInfo) keeps the full
+    /// dimensions.
+    pub fn downscale_after_decode(mut self, width: u32, height: u32) -> Self {
+        self.downscale = Some((width, height));
+        self
+    }
+
+    /// Decode only the `width` × `height` region of still images whose
+    /// top-left corner is at (`x`, `y`).
+    ///
+    /// Default: unset. Coordinates refer to the decoded image before
+    /// rotation or mirroring are applied; the region is clipped to it, and
+    /// decoding fails with [`Error::Unsupported`] if nothing is left. Grid
+    /// images decode only the tiles the region touches, so pulling one tile
+    /// out of a gigapixel grid costs one tile decode. The crop happens before
+    /// [`downscale_after_decode`](Self::downscale_after_decode) reduction.
+    /// [`ImageInfo`](crate::ImageInfo) keeps the full dimensions.
+    pub fn crop(mut self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        self.crop = Some(Rect::new(x, y, width, height));
+        self
+    }
+
+    /// Return what could be decoded of grid images with corrupt tiles.
+    ///
+    /// Default: `false`, where one tile that fails to decode fails the
+    /// whole image. When `true`, failed tiles are painted with
+    /// [`tile_fill`](Self::tile_fill) and listed in
+    /// [`ImageInfo::failed_tiles`](crate::ImageInfo::failed_tiles), as
+    /// viewers do for slightly damaged files. Decoding still fails if no
+    /// tile decodes. Images without a grid are unaffected.
+    pub fn error_resilient(mut self, resilient: bool) -> Self {
+        self.error_resilient = resilient;
+        self
+    }
+
+    /// Select the operating point of scalable (layered) AV1 streams.
+    ///
+    /// Default: `0`, by AV1 convention the highest-quality point. The
+    /// operating point decides which spatial and temporal layers are
+    /// decoded; streams with a single operating point ignore it. Values
+    /// above 31 are clamped.
+    pub fn operating_point(mut self, point: u8) -> Self {
+        self.operating_point = point.min(31);
+        self
+    }
+
+    /// Output every spatial layer of the operating point instead of only
+    /// the highest one.
+    ///
+    /// Default: `false`, where the decoder outputs one frame per item and
+    /// that first frame is the image, which is also right for items holding
+    /// a show-existing-frame chain. When `true`, every layer is output and
+    /// the last, highest layer becomes the image.
+    pub fn all_layers(mut self, all: bool) -> Self {
+        self.all_layers = all;
+        self
+    }
+
+    /// Decode the cheapest spatial layer of layered still images, for
+    /// previews.
+    ///
+    /// Default: `false`. When `true` and the primary item's AV1 stream has
+    /// an operating point that leaves out spatial layers, the one with the
+    /// fewest is decoded instead of [`operating_point`](Self::operating_point),
+    /// so the full-resolution layers are never touched and the image comes
+    /// out at the base layer's size. Streams with a single operating point
+    /// decode as usual. Superres-coded frames are always upscaled, as the
+    /// AV1 decoding process requires.
+    pub fn prefer_lowest_resolution(mut self, prefer: bool) -> Self {
+        self.prefer_lowest_resolution = prefer;
+        self
+    }
+
+    /// Convert images that carry an ICC profile to `destination` while
+    /// decoding.
+    ///
+    /// Default: unset, where the profile is only reported in
+    /// [`ImageInfo::icc_profile`](crate::ImageInfo::icc_profile). When set,
+    /// RGB and RGBA output is converted with moxcms right after YUV→RGB
+    /// conversion, and the returned [`ImageInfo`](crate::ImageInfo)
+    /// describes the destination instead of the source. Images tagged with
+    /// CICP alone, and gray output, are left as they are. Decoder
+    /// construction fails with [`Error::ColorManagement`] if either profile
+    /// cannot be used.
+    #[cfg(feature = "cms")]
+    pub fn color_manage(mut self, destination: Destination) -> Self {
+        self.color_manage = Some(destination);
+        self
+    }
+
+    /// Choose what replaces failed tiles under
+    /// [`error_resilient`](Self::error_resilient).
+    ///
+    /// Default: [`TileFill::Color`] with opaque black.
+    pub fn tile_fill(mut self, fill: TileFill) -> Self {
+        self.tile_fill = fill;
+        self
+    }
+
+    /// Run a hook on every decoded still image before it is returned.
+    ///
+    /// The hook sees the final pixels (after YUV→RGB conversion, cropping,
+    /// and alpha) together with the image metadata, and runs inside the
+    /// decoder's cancellation check: the stop token is polled right before
+    /// the hook, and an `Err` from the hook is returned from the decode call.
+    /// Useful for watermarking or debanding without duplicating the decode
+    /// orchestration.
+    pub fn post_process(
+        mut self,
+        hook: impl Fn(&mut PixelBuffer, &ImageInfo) -> core::result::Result<(), Error>
+        + Send
+        + Sync
+        + 'static,
+    ) -> Self {
+        self.post_process = Some(PostProcess(Arc::new(hook)));
+        self
+    }
+
+    /// Stable 64-bit key over every setting that affects the decoded pixels
+    /// and metadata.
+    ///
+    /// Meant for caching decode results by `(input hash, config key)`.
+    /// Threading, resource limits, [`strict_pixi`](Self::strict_pixi) and
+    /// [`pipelined_animation`](Self::pipelined_animation) are left out: they
+    /// decide whether a decode succeeds or how fast it runs, not what it
+    /// returns. A [`post_process`](Self::post_process) hook only contributes
+    /// its presence, since closures can't be hashed; fold an identifier for
+    /// the hook into the key yourself. Keys are equal for equal settings on
+    /// every platform, and stay the same across patch releases; a minor
+    /// release may change them when it adds settings.
+    pub fn cache_key(&self) -> u64 {
+        let limits = &self.animation_limits;
+        let key = KeyHasher::new(b"zenavif-decoder-v1")
+            .bool(self.apply_grain)
+            .option(self.grain_seed_override, KeyHasher::u16)
+            .option(limits.max_frames, KeyHasher::u32)
+            .option(limits.max_total_duration_ms, KeyHasher::u64)
+            .u32(limits.min_frame_duration_ms)
+            .option(limits.max_frame_duration_ms, KeyHasher::u32)
+            .u8(limits.policy as u8)
+            .u8(self.output_depth as u8)
+            .bool(self.dither)
+            .option(self.chroma_upsampling, |h, f| h.u8(f as u8))
+            .u8(self.conversion_backend as u8)
+            .u8(self.range_clipping as u8)
+            .u8(self.output_range as u8)
+            .u8(self.output_layout as u8)
+            .f32(self.deband)
+            .bool(self.post_process.is_some())
+            .bool(self.prefer_grayscale)
+            .option(self.downscale, |h, (w, h2)| h.u32(w).u32(h2))
+            .option(self.crop, |h, r| {
+                h.u32(r.x).u32(r.y).u32(r.width).u32(r.height)
+            })
+            .bool(self.error_resilient)
+            .option(
+                match self.tile_fill {
+                    TileFill::Color(rgba) => Some(rgba),
+                    TileFill::NeighborAverage => None,
+                },
+                |h, rgba| h.bytes(&rgba),
+            )
+            .u8(self.operating_point)
+            .bool(self.all_layers)
+            .bool(self.prefer_lowest_resolution);
+        #[cfg(feature = "cms")]
+        let key = key.option(self.color_manage.as_ref(), |h, dest| match dest {
+            Destination::Srgb => h.u8(0),
+            Destination::Icc(icc) => h.u8(1).bytes(icc),
+        });
+        key.finish()
+    }
 }
@@ -1,11 +1,15 @@
 //! Alpha channel handling, premultiply conversion, and bit depth scaling
 
+use crate::config::OutputLayout;
 use crate::error::{Error, Result};
-use crate::image::ColorRange;
+use crate::image::{ChromaSampling, ColorRange};
+use crate::simd::{scale_samples_to_u16, swizzle4};
+use crate::yuv_convert::{YuvMatrix, YuvRange, coefficients};
 use rgb::prelude::*;
-use rgb::{Rgb, Rgba};
+use rgb::{Gray, Rgb, Rgba};
 use whereat::at;
 use zenpixels::{PixelBuffer, PixelDescriptor};
+use zenpixels_convert::PixelBufferConvertTypedExt as _;
 
 /// Scale a limited-range Y value to full range (8-bit)
 #[inline]
@@ -27,6 +31,69 @@ fn limited_to_full_16(y: u16, bit_depth: u8) -> u16 {
     ((y32.saturating_sub(y_min)) * max_val / y_range).min(max_val) as u16
 }
 
+/// Convert limited-range high bit depth YUV to RGB without clipping overshoot.
+///
+/// Output is 16-bit and stays in video range: nominal black and white map to
+/// `16 << 8` and `235 << 8`, and values outside that range are only clamped
+/// to `[0, 65535]`.
+/// Subsampled chroma is sampled nearest-neighbour, so callers upsample it
+/// first; `u`/`v` are ignored for monochrome.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn yuv_to_rgb16_preserve_overshoot(
+    y: &[u16],
+    y_stride: usize,
+    u: &[u16],
+    u_stride: usize,
+    v: &[u16],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    sampling: ChromaSampling,
+    bit_depth: u8,
+    matrix: YuvMatrix,
+) -> Vec<Rgb<u16>> {
+    let c = coefficients(matrix, YuvRange::Limited, bit_depth);
+    // Widen by a plain shift, not bit replication, so nominal black and
+    // white land exactly on `16 << 8` and `235 << 8`.
+    let widen = f32::from(1u16 << (16 - bit_depth));
+    let half = c.uv_center;
+    // Chroma excursion is 224 codes vs 219 for luma; rescale so the output
+    // keeps luma's video-range scale.
+    let chroma_scale = 219.0 / 224.0;
+    let (vr, ub) = (c.vr, c.ub);
+    let (ug, vg) = (-c.ug, -c.vg);
+    let (ss_x, ss_y) = match sampling {
+        ChromaSampling::Cs420 => (1, 1),
+        ChromaSampling::Cs422 => (1, 0),
+        ChromaSampling::Cs444 | ChromaSampling::Monochrome => (0, 0),
+    };
+
+    let mut out = Vec::with_capacity(width * height);
+    for row in 0..height {
+        let y_row = &y[row * y_stride..][..width];
+        for (col, &luma) in y_row.iter().enumerate() {
+            let yv = luma as f32;
+            let (cb, cr) = if sampling == ChromaSampling::Monochrome {
+                (0.0, 0.0)
+            } else {
+                let ci = col >> ss_x;
+                let cj = row >> ss_y;
+                (
+                    (u[cj * u_stride + ci] as f32 - half) * chroma_scale,
+                    (v[cj * v_stride + ci] as f32 - half) * chroma_scale,
+                )
+            };
+            let clamp = |c: f32| (c * widen).round().clamp(0.0, 65535.0) as u16;
+            out.push(Rgb {
+                r: clamp(yv + vr * cr),
+                g: clamp(yv - ug * cb - vg * cr),
+                b: clamp(yv + ub * cb),
+            });
+        }
+    }
+    out
+}
+
 /// Scale a value from native bit depth to full u16 range using LSB replication.
 ///
 /// For 10-bit: `(v << 6) | (v >> 4)` maps 0→0, 1023→65535
@@ -43,22 +110,108 @@ fn scale_to_u16(v: u16, bit_depth: u8) -> u16 {
     (v << shift) | (v >> (bit_depth - shift))
 }
 
-/// Downscale a 16-bit PixelBuffer to 8-bit by taking the high byte of each channel.
+/// Luma of a monochrome 8-bit frame as gray, expanded to full range.
+///
+/// Limited range maps `[16, 235]` onto `[0, 255]` with rounding, as the RGB
+/// conversion does for each channel of a monochrome frame.
+pub(crate) fn luma_to_gray8<'a>(
+    rows: impl Iterator<Item = &'a [u8]>,
+    width: usize,
+    height: usize,
+    range: ColorRange,
+) -> Result<PixelBuffer> {
+    let mut out = Vec::new();
+    out.try_reserve_exact(width * height)
+        .map_err(|_| at!(Error::OutOfMemory))?;
+    for row in rows.take(height) {
+        let row = row
+            .get(..width)
+            .ok_or_else(|| at!(Error::Unsupported("luma plane is narrower than the image")))?;
+        out.extend(row.iter().map(|&y| {
+            Gray(match range {
+                ColorRange::Full => y,
+                ColorRange::Limited => ((((y as i32 - 16) * 255 + 109) / 219).clamp(0, 255)) as u8,
+            })
+        }));
+    }
+    PixelBuffer::from_pixels(out, width as u32, height as u32)
+        .map(Into::into)
+        .map_err(|_| at!(Error::Unsupported("luma plane is shorter than the image")))
+}
+
+/// Luma of a monochrome 10/12-bit frame as 16-bit gray.
+///
+/// Limited range is expanded at the native depth, then samples are scaled
+/// to the full u16 range like [`scale_pixels_to_u16`].
+pub(crate) fn luma_to_gray16<'a>(
+    rows: impl Iterator<Item = &'a [u16]>,
+    width: usize,
+    height: usize,
+    range: ColorRange,
+    bit_depth: u8,
+) -> Result<PixelBuffer> {
+    let max_val = (1i32 << bit_depth) - 1;
+    let y_min = 16i32 << (bit_depth - 8);
+    let y_range = 219i32 << (bit_depth - 8);
+    let mut out = Vec::new();
+    out.try_reserve_exact(width * height)
+        .map_err(|_| at!(Error::OutOfMemory))?;
+    for row in rows.take(height) {
+        let row = row
+            .get(..width)
+            .ok_or_else(|| at!(Error::Unsupported("luma plane is narrower than the image")))?;
+        out.extend(row.iter().map(|&y| {
+            let y = match range {
+                ColorRange::Full => (y as i32).min(max_val),
+                ColorRange::Limited => {
+                    (((y as i32 - y_min) * max_val + y_range / 2) / y_range).clamp(0, max_val)
+                }
+            };
+            Gray(scale_to_u16(y as u16, bit_depth))
+        }));
+    }
+    PixelBuffer::from_pixels(out, width as u32, height as u32)
+        .map(Into::into)
+        .map_err(|_| at!(Error::Unsupported("luma plane is shorter than the image")))
+}
+
+/// 4×4 Bayer matrix for [`reduce_to_8bit`].
+const BAYER_4X4: [[u32; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Downscale a 16-bit PixelBuffer to 8-bit, optionally with a 4×4 ordered
+/// dither on the color channels.
+///
+/// Converts Rgb16 → Rgb8, Rgba16 → Rgba8 and Gray16 → Gray8; other formats
+/// pass through. Values are assumed to be in full u16 range (0–65535) after
+/// `scale_pixels_to_u16`. Without dithering each channel keeps its high byte.
 ///
-/// Converts Rgb16 → Rgb8 and Rgba16 → Rgba8 in-place (reallocates to a new buffer).
-/// Values are assumed to be in full u16 range (0–65535) after `scale_pixels_to_u16`.
-pub fn downscale_to_8bit(image: PixelBuffer) -> PixelBuffer {
+/// Dithering scales each sample to `[0, 255]` and adds an offset between
+/// 1/32 and 31/32 of a code value before truncating, so samples already on
+/// an 8-bit level keep it and the discarded fraction turns into a pattern
+/// instead of a band. Alpha is always truncated.
+pub(crate) fn reduce_to_8bit(image: PixelBuffer, dither: bool) -> PixelBuffer {
+    let level = |v: u16, x: usize, y: usize| {
+        if dither {
+            let offset = (BAYER_4X4[y & 3][x & 3] * 2 + 1) * 65535 / 32;
+            ((u32::from(v) * 255 + offset) / 65535) as u8
+        } else {
+            (v >> 8) as u8
+        }
+    };
     let desc = image.descriptor();
     let w = image.width();
     let h = image.height();
     if desc.layout_compatible(PixelDescriptor::RGB16) {
         let src = image.try_as_imgref::<Rgb<u16>>().unwrap();
         let out: Vec<Rgb<u8>> = src
-            .pixels()
-            .map(|px| Rgb {
-                r: (px.r >> 8) as u8,
-                g: (px.g >> 8) as u8,
-                b: (px.b >> 8) as u8,
+            .rows()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter().enumerate().map(move |(x, px)| Rgb {
+                    r: level(px.r, x, y),
+                    g: level(px.g, x, y),
+                    b: level(px.b, x, y),
+                })
             })
             .collect();
         PixelBuffer::from_pixels(out, w, h)
@@ -67,12 +220,29 @@ pub fn downscale_to_8bit(image: PixelBuffer) -> PixelBuffer {
     } else if desc.layout_compatible(PixelDescriptor::RGBA16) {
         let src = image.try_as_imgref::<Rgba<u16>>().unwrap();
         let out: Vec<Rgba<u8>> = src
-            .pixels()
-            .map(|px| Rgba {
-                r: (px.r >> 8) as u8,
-                g: (px.g >> 8) as u8,
-                b: (px.b >> 8) as u8,
-                a: (px.a >> 8) as u8,
+            .rows()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter().enumerate().map(move |(x, px)| Rgba {
+                    r: level(px.r, x, y),
+                    g: level(px.g, x, y),
+                    b: level(px.b, x, y),
+                    a: (px.a >> 8) as u8,
+                })
+            })
+            .collect();
+        PixelBuffer::from_pixels(out, w, h)
+            .expect("allocation should succeed for same dimensions")
+            .into()
+    } else if desc.layout_compatible(PixelDescriptor::GRAY16) {
+        let src = image.try_as_imgref::<Gray<u16>>().unwrap();
+        let out: Vec<Gray<u8>> = src
+            .rows()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(x, px)| Gray::new(level(px.value(), x, y)))
             })
             .collect();
         PixelBuffer::from_pixels(out, w, h)
@@ -83,10 +253,165 @@ pub fn downscale_to_8bit(image: PixelBuffer) -> PixelBuffer {
     }
 }
 
+/// Share of color samples that must lie within a quarter 8-bit step of an
+/// 8-bit level for [`fits_8bit`] to accept an image.
+const FITS_8BIT_MIN_SHARE: f32 = 0.9;
+
+/// Whether a 16-bit RGB/RGBA/gray image can drop to 8-bit without visible loss.
+///
+/// Histograms each color sample's distance from the nearest 8-bit level
+/// (`v = n * 257`). Content that was 8-bit before encoding clusters near
+/// zero, with only compression noise spreading it; true high-depth
+/// gradients fill the histogram evenly. Alpha is ignored. Non-16-bit
+/// layouts return `false`.
+pub(crate) fn fits_8bit(image: &PixelBuffer) -> bool {
+    let desc = image.descriptor();
+    // Distance in eighths of an 8-bit step; the largest distance is half a step.
+    let mut histogram = [0u64; 4];
+    let mut add = |v: u16| {
+        let level = (v as u32 + 128) / 257;
+        let distance = (v as i32 - (level * 257) as i32).unsigned_abs();
+        histogram[(distance / 32).min(3) as usize] += 1;
+    };
+    if desc.layout_compatible(PixelDescriptor::RGB16) {
+        let src = image.try_as_imgref::<Rgb<u16>>().unwrap();
+        for px in src.pixels() {
+            add(px.r);
+            add(px.g);
+            add(px.b);
+        }
+    } else if desc.layout_compatible(PixelDescriptor::RGBA16) {
+        let src = image.try_as_imgref::<Rgba<u16>>().unwrap();
+        for px in src.pixels() {
+            add(px.r);
+            add(px.g);
+            add(px.b);
+        }
+    } else if desc.layout_compatible(PixelDescriptor::GRAY16) {
+        let src = image.try_as_imgref::<Gray<u16>>().unwrap();
+        src.pixels().for_each(|px| add(px.value()));
+    } else {
+        return false;
+    }
+
+    let total: u64 = histogram.iter().sum();
+    if total == 0 {
+        return false;
+    }
+    // Bins 0-1 hold samples within a quarter step; evenly spread content
+    // puts about half its samples there.
+    let near = histogram[0] + histogram[1];
+    near as f32 >= total as f32 * FITS_8BIT_MIN_SHARE
+}
+
+/// Shrink `image` by the largest whole factor that keeps it at least
+/// `target_width` × `target_height`, averaging each factor × factor block.
+///
+/// Returns `None` when no factor of 2 or more fits, or for layouts other
+/// than 8/16-bit RGB, RGBA and gray. Blocks cut off by the right or bottom
+/// edge average the samples they cover.
+pub(crate) fn reduce_to_fit(
+    image: &PixelBuffer,
+    target_width: u32,
+    target_height: u32,
+) -> Result<Option<PixelBuffer>> {
+    let factor = (image.width() / target_width.max(1)).min(image.height() / target_height.max(1));
+    if factor < 2 {
+        return Ok(None);
+    }
+    let factor = factor as usize;
+    let desc = image.descriptor();
+    let reduced: PixelBuffer = if desc.layout_compatible(PixelDescriptor::RGB8) {
+        let (out, w, h) = box_reduce::<_, u8>(image.try_as_imgref::<Rgb<u8>>().unwrap(), factor)?;
+        PixelBuffer::from_pixels(out, w, h)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    } else if desc.layout_compatible(PixelDescriptor::RGBA8) {
+        let (out, w, h) = box_reduce::<_, u8>(image.try_as_imgref::<Rgba<u8>>().unwrap(), factor)?;
+        PixelBuffer::from_pixels(out, w, h)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    } else if desc.layout_compatible(PixelDescriptor::GRAY8) {
+        let (out, w, h) = box_reduce::<_, u8>(image.try_as_imgref::<Gray<u8>>().unwrap(), factor)?;
+        PixelBuffer::from_pixels(out, w, h)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    } else if desc.layout_compatible(PixelDescriptor::RGB16) {
+        let (out, w, h) = box_reduce::<_, u16>(image.try_as_imgref::<Rgb<u16>>().unwrap(), factor)?;
+        PixelBuffer::from_pixels(out, w, h)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    } else if desc.layout_compatible(PixelDescriptor::RGBA16) {
+        let (out, w, h) =
+            box_reduce::<_, u16>(image.try_as_imgref::<Rgba<u16>>().unwrap(), factor)?;
+        PixelBuffer::from_pixels(out, w, h)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    } else if desc.layout_compatible(PixelDescriptor::GRAY16) {
+        let (out, w, h) =
+            box_reduce::<_, u16>(image.try_as_imgref::<Gray<u16>>().unwrap(), factor)?;
+        PixelBuffer::from_pixels(out, w, h)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(reduced))
+}
+
+/// Box-filter `src` down by `factor`, treating each pixel `P` as a run of
+/// `C` samples. Returns the pixels and their width and height.
+fn box_reduce<P, C>(src: imgref::ImgRef<'_, P>, factor: usize) -> Result<(Vec<P>, u32, u32)>
+where
+    P: bytemuck::Pod,
+    C: bytemuck::Pod + Into<u64> + TryFrom<u64>,
+{
+    let channels = size_of::<P>() / size_of::<C>();
+    let (width, height) = (src.width(), src.height());
+    let (out_width, out_height) = (width.div_ceil(factor), height.div_ceil(factor));
+    let mut out = Vec::new();
+    out.try_reserve_exact(out_width * out_height)
+        .map_err(|_| at!(Error::OutOfMemory))?;
+    out.resize(out_width * out_height, P::zeroed());
+    let out_samples: &mut [C] = bytemuck::cast_slice_mut(&mut out);
+
+    let mut sums = vec![0u64; out_width * channels];
+    for (out_y, out_row) in out_samples
+        .chunks_exact_mut(out_width * channels)
+        .enumerate()
+    {
+        sums.fill(0);
+        let rows = out_y * factor..((out_y + 1) * factor).min(height);
+        let block_height = rows.len();
+        for row in src.sub_image(0, rows.start, width, block_height).rows() {
+            let row: &[C] = bytemuck::cast_slice(row);
+            for (x, px) in row.chunks_exact(channels).enumerate() {
+                let sums = &mut sums[x / factor * channels..][..channels];
+                for (sum, &v) in sums.iter_mut().zip(px) {
+                    *sum += v.into();
+                }
+            }
+        }
+        for (out_x, (out_px, sums)) in out_row
+            .chunks_exact_mut(channels)
+            .zip(sums.chunks_exact(channels))
+            .enumerate()
+        {
+            let block_width = (width - out_x * factor).min(factor);
+            let count = (block_width * block_height) as u64;
+            for (out, &sum) in out_px.iter_mut().zip(sums) {
+                *out = C::try_from((sum + count / 2) / count)
+                    .unwrap_or_else(|_| unreachable!("an average fits its sample type"));
+            }
+        }
+    }
+    Ok((out, out_width as u32, out_height as u32))
+}
+
 /// Scale all channels in a 16-bit PixelBuffer from native bit depth to full u16 range.
 ///
 /// This converts e.g. 10-bit values (0–1023) to full 16-bit (0–65535) using
-/// LSB replication for exact endpoint mapping.
+/// LSB replication for exact endpoint mapping, vectorized per sample.
 pub fn scale_pixels_to_u16(image: &mut PixelBuffer, bit_depth: u8) {
     if bit_depth >= 16 {
         return;
@@ -94,22 +419,167 @@ pub fn scale_pixels_to_u16(image: &mut PixelBuffer, bit_depth: u8) {
     let desc = image.descriptor();
     if desc.layout_compatible(PixelDescriptor::RGB16) {
         let mut img = image.try_as_imgref_mut::<Rgb<u16>>().unwrap();
+        scale_samples_to_u16(rgb::bytemuck::cast_slice_mut(img.buf_mut()), bit_depth);
+    } else if desc.layout_compatible(PixelDescriptor::RGBA16) {
+        let mut img = image.try_as_imgref_mut::<Rgba<u16>>().unwrap();
+        scale_samples_to_u16(rgb::bytemuck::cast_slice_mut(img.buf_mut()), bit_depth);
+    }
+}
+
+/// Write an 8-bit image to `dst` in `layout`'s channel order.
+///
+/// Rows start `stride` bytes apart. RGB and gray images gain opaque alpha
+/// for the 4-byte layouts; [`OutputLayout::Rgb`] writes RGBA when `image`
+/// has alpha and RGB otherwise. `dither` applies to the packed 16-bit
+/// layouts only.
+pub(crate) fn write_layout(
+    image: &PixelBuffer,
+    layout: OutputLayout,
+    dither: bool,
+    dst: &mut [u8],
+    stride: usize,
+) -> Result<()> {
+    let has_alpha = image.descriptor().layout_compatible(PixelDescriptor::RGBA8);
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let row_bytes = width * layout.bytes_per_pixel(has_alpha);
+    let needed = match height {
+        0 => Some(0),
+        h => stride
+            .checked_mul(h - 1)
+            .and_then(|n| n.checked_add(row_bytes)),
+    };
+    if stride < row_bytes || needed.is_none_or(|n| dst.len() < n) {
+        return Err(at!(Error::Unsupported(
+            "destination too small for the output layout"
+        )));
+    }
+    if row_bytes == 0 || height == 0 {
+        return Ok(());
+    }
+    let dst_rows = dst.chunks_mut(stride).map(|row| &mut row[..row_bytes]);
+
+    let order = match layout {
+        OutputLayout::Rgb if has_alpha => {
+            let rgba = image.to_rgba8();
+            for (src, dst) in rgba.as_imgref().rows().zip(dst_rows) {
+                dst.copy_from_slice(rgb::bytemuck::cast_slice(src));
+            }
+            return Ok(());
+        }
+        OutputLayout::Rgb | OutputLayout::Bgr8 => {
+            let rgb = image.to_rgb8();
+            let bgr = layout == OutputLayout::Bgr8;
+            for (src, dst) in rgb.as_imgref().rows().zip(dst_rows) {
+                for (px, out) in src.iter().zip(dst.chunks_exact_mut(3)) {
+                    let px = if bgr {
+                        [px.b, px.g, px.r]
+                    } else {
+                        [px.r, px.g, px.b]
+                    };
+                    out.copy_from_slice(&px);
+                }
+            }
+            return Ok(());
+        }
+        OutputLayout::Rgb565 | OutputLayout::Rgba4444 => {
+            let rgba = image.to_rgba8();
+            for (y, (src, dst)) in rgba.as_imgref().rows().zip(dst_rows).enumerate() {
+                for (x, (px, out)) in src.iter().zip(dst.chunks_exact_mut(2)).enumerate() {
+                    let threshold = dither.then(|| BAYER_4X4[y & 3][x & 3]);
+                    let [r, g, b] = [px.r, px.g, px.b];
+                    let packed = if layout == OutputLayout::Rgb565 {
+                        (quantize8(r, 5, threshold) << 11)
+                            | (quantize8(g, 6, threshold) << 5)
+                            | quantize8(b, 5, threshold)
+                    } else {
+                        (quantize8(r, 4, threshold) << 12)
+                            | (quantize8(g, 4, threshold) << 8)
+                            | (quantize8(b, 4, threshold) << 4)
+                            | quantize8(px.a, 4, None)
+                    };
+                    out.copy_from_slice(&packed.to_le_bytes());
+                }
+            }
+            return Ok(());
+        }
+        OutputLayout::Bgra8 => [2, 1, 0, 3],
+        OutputLayout::Argb8 => [3, 0, 1, 2],
+        OutputLayout::Abgr8 => [3, 2, 1, 0],
+    };
+    let rgba = image.to_rgba8();
+    for (src, dst) in rgba.as_imgref().rows().zip(dst_rows) {
+        swizzle4(rgb::bytemuck::cast_slice(src), dst, order);
+    }
+    Ok(())
+}
+
+/// Reduce an 8-bit sample to `bits` bits.
+///
+/// With a Bayer `threshold` the sample is scaled to `[0, 2^bits - 1]` and
+/// offset as in [`reduce_to_8bit`]; without one it is truncated.
+fn quantize8(v: u8, bits: u32, threshold: Option<u32>) -> u16 {
+    match threshold {
+        Some(t) => {
+            let max = (1 << bits) - 1;
+            ((u32::from(v) * max + (t * 2 + 1) * 255 / 32) / 255) as u16
+        }
+        None => u16::from(v >> (8 - bits)),
+    }
+}
+
+/// An 8-bit image as a BGRA8 buffer, for [`OutputLayout::Bgra8`].
+pub(crate) fn to_bgra8(image: &PixelBuffer) -> Result<PixelBuffer> {
+    let (width, height) = (image.width(), image.height());
+    let mut bytes = vec![0u8; width as usize * height as usize * 4];
+    write_layout(
+        image,
+        OutputLayout::Bgra8,
+        false,
+        &mut bytes,
+        width as usize * 4,
+    )?;
+    PixelBuffer::from_vec(bytes, width, height, PixelDescriptor::BGRA8_SRGB)
+        .map_err(|_| at!(Error::OutOfMemory))
+}
+
+/// Compress full-range color samples into video range in place.
+///
+/// Black and white map to 16 and 235 in 8-bit buffers and to `16 << 8` and
+/// `235 << 8` in 16-bit ones. Covers RGB, RGBA and gray; alpha is left
+/// untouched.
+pub(crate) fn compress_to_limited_range(image: &mut PixelBuffer) {
+    let limit8 = |v: u8| (16 + (u32::from(v) * 219 + 127) / 255) as u8;
+    let limit16 = |v: u16| ((16 << 8) + (u32::from(v) * (219 << 8) + 32767) / 65535) as u16;
+    let desc = image.descriptor();
+    if desc.layout_compatible(PixelDescriptor::RGB8) {
+        let mut img = image.try_as_imgref_mut::<Rgb<u8>>().unwrap();
         for px in img.buf_mut().iter_mut() {
-            *px = Rgb {
-                r: scale_to_u16(px.r, bit_depth),
-                g: scale_to_u16(px.g, bit_depth),
-                b: scale_to_u16(px.b, bit_depth),
-            };
+            *px = px.map(limit8);
+        }
+    } else if desc.layout_compatible(PixelDescriptor::RGBA8) {
+        let mut img = image.try_as_imgref_mut::<Rgba<u8>>().unwrap();
+        for px in img.buf_mut().iter_mut() {
+            *px = px.map_colors(limit8);
+        }
+    } else if desc.layout_compatible(PixelDescriptor::GRAY8) {
+        let mut img = image.try_as_imgref_mut::<Gray<u8>>().unwrap();
+        for px in img.buf_mut().iter_mut() {
+            *px = Gray::new(limit8(px.value()));
+        }
+    } else if desc.layout_compatible(PixelDescriptor::RGB16) {
+        let mut img = image.try_as_imgref_mut::<Rgb<u16>>().unwrap();
+        for px in img.buf_mut().iter_mut() {
+            *px = px.map(limit16);
         }
     } else if desc.layout_compatible(PixelDescriptor::RGBA16) {
         let mut img = image.try_as_imgref_mut::<Rgba<u16>>().unwrap();
         for px in img.buf_mut().iter_mut() {
-            *px = Rgba {
-                r: scale_to_u16(px.r, bit_depth),
-                g: scale_to_u16(px.g, bit_depth),
-                b: scale_to_u16(px.b, bit_depth),
-                a: scale_to_u16(px.a, bit_depth),
-            };
+            *px = px.map_colors(limit16);
+        }
+    } else if desc.layout_compatible(PixelDescriptor::GRAY16) {
+        let mut img = image.try_as_imgref_mut::<Gray<u16>>().unwrap();
+        for px in img.buf_mut().iter_mut() {
+            *px = Gray::new(limit16(px.value()));
         }
     }
 }
@@ -242,6 +712,78 @@ pub fn unpremultiply16(img_row: &mut [Rgba<u16>]) {
 mod tests {
     use super::*;
 
+    fn rgb16(values: impl Iterator<Item = u16>) -> PixelBuffer {
+        let px: Vec<Rgb<u16>> = values.map(|v| Rgb::new(v, v, v)).collect();
+        let w = px.len() as u32;
+        PixelBuffer::from_pixels(px, w, 1).unwrap().into()
+    }
+
+    #[test]
+    fn luma_to_gray8_expands_limited_range_at_display_size() {
+        // Rows carry one sample of padding past the display width.
+        let rows: [&[u8]; 3] = [&[16, 235, 126, 99], &[0, 255, 16, 99], &[7; 4]];
+        let gray = luma_to_gray8(rows.into_iter(), 3, 2, ColorRange::Limited).unwrap();
+        let out = gray.try_as_imgref::<Gray<u8>>().unwrap();
+        let values: Vec<u8> = out.pixels().map(|p| p.value()).collect();
+        assert_eq!(values, [0, 255, 128, 0, 255, 0]);
+
+        let short: [&[u8]; 1] = [&[1, 2, 3]];
+        assert!(luma_to_gray8(short.into_iter(), 3, 2, ColorRange::Full).is_err());
+    }
+
+    #[test]
+    fn luma_to_gray16_scales_to_full_u16() {
+        let rows: [&[u16]; 1] = [&[64, 940, 502, 1023]];
+        let gray = luma_to_gray16(rows.into_iter(), 4, 1, ColorRange::Limited, 10).unwrap();
+        let out = gray.try_as_imgref::<Gray<u16>>().unwrap();
+        let values: Vec<u16> = out.pixels().map(|p| p.value()).collect();
+        assert_eq!(values, [0, 65535, 32800, 65535]);
+
+        let gray = luma_to_gray16(rows.into_iter(), 4, 1, ColorRange::Full, 10).unwrap();
+        assert!(!fits_8bit(&gray));
+        let gray8 = reduce_to_8bit(gray, false);
+        let out = gray8.try_as_imgref::<Gray<u8>>().unwrap();
+        let values: Vec<u8> = out.pixels().map(|p| p.value()).collect();
+        assert_eq!(values, [16, 235, 125, 255]);
+    }
+
+    #[test]
+    fn dithered_reduction_keeps_levels_and_averages_fractions() {
+        // Half way between 8-bit levels 100 and 101, plus exact level 200.
+        let mid = 100 * 257 + 128;
+        let px: Vec<Gray<u16>> = (0..16).map(|_| Gray(mid)).collect();
+        let image: PixelBuffer = PixelBuffer::from_pixels(px, 4, 4).unwrap().into();
+        let out = reduce_to_8bit(image, true);
+        let values: Vec<u8> = out
+            .try_as_imgref::<Gray<u8>>()
+            .unwrap()
+            .pixels()
+            .map(|p| p.value())
+            .collect();
+        assert_eq!(values.iter().filter(|&&v| v == 101).count(), 8);
+        assert!(values.iter().all(|&v| v == 100 || v == 101));
+
+        let px = vec![Rgba::<u16>::new(200 * 257, 0, 65535, 65535); 16];
+        let image: PixelBuffer = PixelBuffer::from_pixels(px, 4, 4).unwrap().into();
+        let out = reduce_to_8bit(image, true);
+        let out = out.try_as_imgref::<Rgba<u8>>().unwrap();
+        assert!(out.pixels().all(|p| p == Rgba::new(200, 0, 255, 255)));
+    }
+
+    #[test]
+    fn fits_8bit_accepts_upconverted_content() {
+        // 8-bit levels with a little compression noise
+        let image = rgb16((0..=255u16).map(|v| (v * 257).saturating_add(v % 3 * 20)));
+        assert!(fits_8bit(&image));
+    }
+
+    #[test]
+    fn fits_8bit_rejects_fine_gradients() {
+        // Every 10-bit level, scaled to 16-bit the way decoding does
+        let image = rgb16((0..1024u16).map(|v| (v << 6) | (v >> 4)));
+        assert!(!fits_8bit(&image));
+    }
+
     #[test]
     fn limited_to_full_8_no_overflow() {
         // Regression: i16 arithmetic overflowed for y > 144
@@ -290,4 +832,105 @@ mod tests {
         // 16-bit no-op
         assert_eq!(scale_to_u16(12345, 16), 12345);
     }
+
+    #[test]
+    fn preserve_overshoot_keeps_super_white() {
+        // 10-bit limited range: nominal white is 940, 1000 is a super-white
+        let y = [64u16, 940, 1000, 20];
+        let neutral = [512u16; 4];
+        let out = yuv_to_rgb16_preserve_overshoot(
+            &y,
+            4,
+            &neutral,
+            4,
+            &neutral,
+            4,
+            4,
+            1,
+            ChromaSampling::Cs444,
+            10,
+            YuvMatrix::Bt709,
+        );
+        assert_eq!(out[0], Rgb::new(16 << 8, 16 << 8, 16 << 8));
+        assert_eq!(out[1], Rgb::new(235 << 8, 235 << 8, 235 << 8));
+        assert_eq!(out[2], Rgb::new(1000 << 6, 1000 << 6, 1000 << 6));
+        // Sub-black stays below nominal black instead of clipping to it
+        assert_eq!(out[3], Rgb::new(20 << 6, 20 << 6, 20 << 6));
+    }
+
+    #[test]
+    fn reduce_to_fit_averages_whole_blocks_and_edges() {
+        // 5x3 gray, reduced by 2 towards 2x1: 3x2 blocks, the last column
+        // and row partial.
+        let px: Vec<Gray<u8>> = [
+            0, 2, 10, 20, 7, //
+            4, 6, 30, 40, 9, //
+            100, 200, 1, 1, 255,
+        ]
+        .into_iter()
+        .map(Gray)
+        .collect();
+        let image: PixelBuffer = PixelBuffer::from_pixels(px, 5, 3).unwrap().into();
+        let reduced = reduce_to_fit(&image, 2, 1).unwrap().unwrap();
+        assert_eq!((reduced.width(), reduced.height()), (3, 2));
+        let out: Vec<u8> = reduced
+            .try_as_imgref::<Gray<u8>>()
+            .unwrap()
+            .pixels()
+            .map(|px| px.value())
+            .collect();
+        assert_eq!(out, [3, 25, 8, 150, 1, 255]);
+
+        assert!(reduce_to_fit(&image, 3, 2).unwrap().is_none());
+    }
+
+    #[test]
+    fn compress_to_limited_range_keeps_alpha() {
+        let px = vec![Rgba::new(0u8, 128, 255, 7)];
+        let mut image: PixelBuffer = PixelBuffer::from_pixels(px, 1, 1).unwrap().into();
+        compress_to_limited_range(&mut image);
+        let out = image.try_as_imgref::<Rgba<u8>>().unwrap().buf()[0];
+        assert_eq!(out, Rgba::new(16, 126, 235, 7));
+
+        let px = vec![Gray(0u16), Gray(65535)];
+        let mut image: PixelBuffer = PixelBuffer::from_pixels(px, 2, 1).unwrap().into();
+        compress_to_limited_range(&mut image);
+        let out: Vec<u16> = image
+            .try_as_imgref::<Gray<u16>>()
+            .unwrap()
+            .pixels()
+            .map(|px| px.value())
+            .collect();
+        assert_eq!(out, [16 << 8, 235 << 8]);
+    }
+
+    #[test]
+    fn packed_layouts() {
+        let px = vec![Rgba::new(255u8, 0, 255, 128), Rgba::new(8, 4, 16, 255)];
+        let image: PixelBuffer = PixelBuffer::from_pixels(px, 2, 1).unwrap().into();
+        let pack = |layout| {
+            let mut out = [0u8; 4];
+            write_layout(&image, layout, false, &mut out, 4).unwrap();
+            [0, 2].map(|i| u16::from_le_bytes([out[i], out[i + 1]]))
+        };
+        assert_eq!(pack(OutputLayout::Rgb565), [0xf81f, 0x0822]);
+        assert_eq!(pack(OutputLayout::Rgba4444), [0xf0f8, 0x001f]);
+    }
+
+    #[test]
+    fn packed_dither_keeps_mean() {
+        let image: PixelBuffer = PixelBuffer::from_pixels(vec![Rgb::new(130u8, 0, 255); 16], 4, 4)
+            .unwrap()
+            .into();
+        let mut out = [0u8; 32];
+        write_layout(&image, OutputLayout::Rgb565, true, &mut out, 8).unwrap();
+        let packed: Vec<u16> = out
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        // 130 / 255 * 31 = 15.8: three 15s and thirteen 16s.
+        let red: u32 = packed.iter().map(|&p| u32::from(p >> 11)).sum();
+        assert_eq!(red, 253);
+        assert!(packed.iter().all(|&p| p & 0x7e0 == 0 && p & 0x1f == 0x1f));
+    }
 }
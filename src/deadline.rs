@@ -0,0 +1,109 @@
+//! Time-bounded decoding through the [`Stop`] abstraction.
+
+use enough::{Stop, StopReason, Unstoppable};
+use std::time::{Duration, Instant};
+
+/// A [`Stop`] token that fires once a deadline has passed.
+///
+/// Pass it wherever a decode function takes a stop token to bound decoding
+/// time without writing a `Stop` impl. Decoders check the token between
+/// animation frames, grid tiles and conversion strips, so a decode returns
+/// [`Error::Cancelled`](crate::Error::Cancelled) with
+/// [`StopReason::TimedOut`] shortly after the deadline; the decode of a
+/// single AV1 frame is not interrupted. Wrap another token with
+/// [`with_inner`](Self::with_inner) to also honor caller cancellation.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use zenavif::{DeadlineStop, DecoderConfig, decode_with};
+///
+/// let data = std::fs::read("image.avif").unwrap();
+/// let stop = DeadlineStop::after(Duration::from_secs(2));
+/// let pixels = decode_with(&data, &DecoderConfig::new(), &stop).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DeadlineStop<S = Unstoppable> {
+    deadline: Instant,
+    inner: S,
+}
+
+impl DeadlineStop {
+    /// Fire `timeout` from now.
+    pub fn after(timeout: Duration) -> Self {
+        Self::at(Instant::now() + timeout)
+    }
+
+    /// Fire at `deadline`.
+    pub fn at(deadline: Instant) -> Self {
+        Self {
+            deadline,
+            inner: Unstoppable,
+        }
+    }
+
+    /// Keep the deadline and also stop when `inner` does.
+    ///
+    /// `inner`'s reason wins when both have fired.
+    pub fn with_inner<S: Stop>(self, inner: S) -> DeadlineStop<S> {
+        DeadlineStop {
+            deadline: self.deadline,
+            inner,
+        }
+    }
+}
+
+impl<S> DeadlineStop<S> {
+    /// The instant the token fires.
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Time left before the deadline, zero once it has passed.
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+}
+
+impl<S: Stop> Stop for DeadlineStop<S> {
+    fn check(&self) -> Result<(), StopReason> {
+        self.inner.check()?;
+        if Instant::now() >= self.deadline {
+            return Err(StopReason::TimedOut);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Cancelled;
+
+    impl Stop for Cancelled {
+        fn check(&self) -> Result<(), StopReason> {
+            Err(StopReason::Cancelled)
+        }
+    }
+
+    #[test]
+    fn fires_after_the_deadline() {
+        let stop = DeadlineStop::after(Duration::from_secs(3600));
+        assert!(stop.check().is_ok());
+        assert!(stop.remaining() > Duration::from_secs(3500));
+
+        let stop = DeadlineStop::at(Instant::now());
+        assert!(matches!(stop.check(), Err(StopReason::TimedOut)));
+        assert_eq!(stop.remaining(), Duration::ZERO);
+    }
+
+    #[test]
+    fn inner_token_is_checked_first() {
+        let stop = DeadlineStop::at(Instant::now()).with_inner(Cancelled);
+        assert!(matches!(stop.check(), Err(StopReason::Cancelled)));
+        let stop = DeadlineStop::after(Duration::from_secs(3600)).with_inner(Cancelled);
+        assert!(matches!(stop.check(), Err(StopReason::Cancelled)));
+    }
+}
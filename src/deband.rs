@@ -0,0 +1,154 @@
+//! Gradient-aware debanding post-filter for 8-bit output
+//!
+//! Heavily quantized 8-bit AV1 shows contouring in smooth gradients such as
+//! skies. For every colour sample, four neighbours at a strength-dependent
+//! radius are compared against the centre; if all lie within a small
+//! threshold the region is treated as a flat gradient and the sample is
+//! replaced by the neighbour average plus a 4×4 ordered dither. Edges and
+//! texture fail the threshold test and pass through untouched.
+
+use archmage::prelude::*;
+use rgb::{Rgb, Rgba};
+use zenpixels::{PixelBuffer, PixelDescriptor};
+
+/// 4×4 Bayer matrix, values 0..16.
+const BAYER_4X4: [[u16; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Map a `0.0..=1.0` strength to `(radius, threshold)`.
+fn parameters(strength: f32) -> (usize, u8) {
+    let strength = strength.clamp(0.0, 1.0);
+    let radius = 4 + (strength * 12.0) as usize;
+    let threshold = 1 + (strength * 3.0) as u8;
+    (radius, threshold)
+}
+
+/// Deband an RGB8 or RGBA8 buffer in place. Alpha is never modified.
+///
+/// Other layouts (16-bit output already carries enough precision to avoid
+/// visible banding) are left unchanged. `strength <= 0.0` is a no-op.
+pub(crate) fn deband(image: &mut PixelBuffer, strength: f32) {
+    if strength <= 0.0 {
+        return;
+    }
+    let (radius, threshold) = parameters(strength);
+    let desc = image.descriptor();
+    if desc.layout_compatible(PixelDescriptor::RGB8) {
+        let Some(mut img) = image.try_as_imgref_mut::<Rgb<u8>>() else {
+            return;
+        };
+        let (width, height, stride) = (img.width(), img.height(), img.stride());
+        let bytes: &mut [u8] = rgb::bytemuck::cast_slice_mut(img.buf_mut());
+        deband_interleaved(bytes, width, height, stride * 3, 3, radius, threshold);
+    } else if desc.layout_compatible(PixelDescriptor::RGBA8) {
+        let Some(mut img) = image.try_as_imgref_mut::<Rgba<u8>>() else {
+            return;
+        };
+        let (width, height, stride) = (img.width(), img.height(), img.stride());
+        let bytes: &mut [u8] = rgb::bytemuck::cast_slice_mut(img.buf_mut());
+        deband_interleaved(bytes, width, height, stride * 4, 4, radius, threshold);
+    }
+}
+
+/// Filter the first three channels of an interleaved 8-bit buffer.
+fn deband_interleaved(
+    data: &mut [u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    channels: usize,
+    radius: usize,
+    threshold: u8,
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    let src = data.to_vec();
+    deband_rows(
+        &src, data, width, height, stride, channels, radius, threshold,
+    );
+}
+
+#[autoversion]
+#[allow(clippy::too_many_arguments)]
+fn deband_rows(
+    src: &[u8],
+    dst: &mut [u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+    channels: usize,
+    radius: usize,
+    threshold: u8,
+) {
+    for y in 0..height {
+        let up = y.saturating_sub(radius) * stride;
+        let down = (y + radius).min(height - 1) * stride;
+        let row = y * stride;
+        let bayer = &BAYER_4X4[y & 3];
+        for x in 0..width {
+            let left = x.saturating_sub(radius) * channels;
+            let right = (x + radius).min(width - 1) * channels;
+            let col = x * channels;
+            for c in 0..3 {
+                let center = src[row + col + c];
+                let n = [
+                    src[row + left + c],
+                    src[row + right + c],
+                    src[up + col + c],
+                    src[down + col + c],
+                ];
+                if n.iter().all(|&v| v.abs_diff(center) <= threshold) {
+                    let sum: u16 = n.iter().map(|&v| v as u16).sum();
+                    // sum / 4 with the dither adding up to 15/16 of a code value
+                    dst[row + col + c] = ((sum * 4 + bayer[x & 3]) / 16) as u8;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_region_is_unchanged() {
+        let mut data = vec![100u8; 16 * 16 * 3];
+        deband_interleaved(&mut data, 16, 16, 16 * 3, 3, 4, 2);
+        assert!(data.iter().all(|&v| v == 100));
+    }
+
+    #[test]
+    fn edges_are_preserved() {
+        // Hard vertical edge: left half 0, right half 200
+        let mut data: Vec<u8> = (0..16 * 16)
+            .flat_map(|i| if i % 16 < 8 { [0u8; 3] } else { [200u8; 3] })
+            .collect();
+        let before = data.clone();
+        deband_interleaved(&mut data, 16, 16, 16 * 3, 3, 4, 2);
+        assert_eq!(data, before);
+    }
+
+    #[test]
+    fn band_step_is_smoothed() {
+        // A one-code-value step in a smooth gradient gets dithered across.
+        let mut data: Vec<u8> = (0..32 * 8)
+            .flat_map(|i| if i % 32 < 16 { [50u8; 3] } else { [51u8; 3] })
+            .collect();
+        deband_interleaved(&mut data, 32, 8, 32 * 3, 3, 4, 2);
+        let near_step: Vec<u8> = (0..8).map(|y| data[(y * 32 + 16) * 3]).collect();
+        assert!(near_step.iter().all(|&v| v == 50 || v == 51));
+        assert!(near_step.contains(&50) && near_step.contains(&51));
+    }
+
+    #[test]
+    fn alpha_is_untouched() {
+        let mut data: Vec<u8> = (0..8 * 8)
+            .flat_map(|i| [60, 60, 61, (i * 3) as u8])
+            .collect();
+        let alphas: Vec<u8> = data.iter().skip(3).step_by(4).copied().collect();
+        deband_interleaved(&mut data, 8, 8, 8 * 4, 4, 4, 2);
+        let after: Vec<u8> = data.iter().skip(3).step_by(4).copied().collect();
+        assert_eq!(alphas, after);
+    }
+}
@@ -2,13 +2,21 @@
 
 #![allow(unsafe_code)]
 
-use crate::config::DecoderConfig;
-use crate::convert::{add_alpha8, add_alpha16, downscale_to_8bit, scale_pixels_to_u16};
+use crate::config::{DecoderConfig, OutputDepth};
+use crate::convert::{
+    add_alpha8, add_alpha16, luma_to_gray8, luma_to_gray16, reduce_to_8bit, reduce_to_fit,
+    scale_pixels_to_u16,
+};
+use crate::decoder_managed::{
+    check_declared_bits, chroma_sample_position, declared_bits, depth_map, grain_seed_override,
+    hdr10_plus, reseed_grain,
+};
 use crate::error::{Error, Result};
 use crate::image::{
-    ChromaSampling, ColorPrimaries, ColorRange, ImageInfo, MatrixCoefficients,
+    AlphaTransform, ChromaSampling, ColorPrimaries, ColorRange, ImageInfo, MatrixCoefficients,
     TransferCharacteristics,
 };
+use crate::plane_copy::PlaneCopy;
 use enough::Stop;
 use rgb::{Rgb, Rgba};
 use whereat::at;
@@ -73,9 +81,16 @@ impl Rav1dDecoder {
         }
 
         let mut settings = unsafe { settings.assume_init() };
-        settings.n_threads = config.threads as c_int;
-        settings.apply_grain = config.apply_grain as c_int;
+        settings.n_threads = if config.deterministic {
+            1
+        } else {
+            config.threads as c_int
+        };
+        // Grain would be averaged away by the post-decode downscale.
+        settings.apply_grain = (config.apply_grain && config.downscale.is_none()) as c_int;
         settings.frame_size_limit = config.frame_size_limit;
+        settings.operating_point = c_int::from(config.operating_point);
+        settings.all_layers = config.all_layers as c_int;
 
         let mut ctx: Option<Dav1dContext> = None;
 
@@ -526,6 +541,9 @@ impl AvifDecoder {
             &enough::Unstoppable,
         )
         .map_err(|e| at!(Error::Parse(e)))?;
+        if config.strict_pixi {
+            check_declared_bits(declared_bits(data).as_deref(), &parser)?;
+        }
 
         // Extract metadata from the parsed AVIF
         let metadata = parser
@@ -555,6 +573,7 @@ impl AvifDecoder {
             matrix_coefficients: MatrixCoefficients::default(),
             color_range: ColorRange::default(),
             chroma_sampling,
+            chroma_sample_position: chroma_sample_position(&parser),
             icc_profile: None,
             rotation: None,
             mirror: None,
@@ -562,10 +581,15 @@ impl AvifDecoder {
             pixel_aspect_ratio: None,
             content_light_level: None,
             mastering_display: None,
+            hdr10_plus: hdr10_plus(data),
+            declared_bits_per_channel: declared_bits(data),
             exif: None,
             xmp: None,
             gain_map: None,
-            depth_map: None,
+            depth_map: depth_map(data),
+            failed_tiles: Vec::new(),
+            lossy_conversion: false,
+            alpha_transformed: None,
         };
 
         // Check frame size limit
@@ -578,6 +602,8 @@ impl AvifDecoder {
                 }));
             }
         }
+        let grid = crate::decoder_managed::grid_config(&parser, data);
+        crate::decoder_managed::check_memory_limit(&parser, grid.as_ref(), config)?;
 
         Ok(Self {
             parser,
@@ -604,6 +630,7 @@ impl AvifDecoder {
             .parser
             .primary_data()
             .map_err(|e| at!(Error::Parse(e)))?;
+        let primary_data = reseed_grain(&primary_data, grain_seed_override(&self.config));
         let color_picture = decoder.decode(&primary_data)?;
 
         // Check for cancellation after color decode
@@ -614,7 +641,7 @@ impl AvifDecoder {
         let yuv_range = seq_hdr
             .map(|h| to_yuv_range(h.color_range))
             .unwrap_or(YuvRange::Limited);
-        let _color_range = seq_hdr
+        let color_range = seq_hdr
             .map(|h| to_color_range(h.color_range))
             .unwrap_or(ColorRange::Limited);
 
@@ -632,6 +659,14 @@ impl AvifDecoder {
                 .ok_or_else(|| at!(Error::Unsupported("failed to extract YUV planes")))?;
 
             match planes.chroma_sampling() {
+                ChromaSampling::Monochrome if self.config.prefer_grayscale && !has_alpha => {
+                    luma_to_gray8(
+                        planes.y.chunks(planes.width),
+                        planes.width,
+                        planes.height,
+                        color_range,
+                    )?
+                }
                 ChromaSampling::Monochrome => {
                     self.convert_mono8(&planes, yuv_range, matrix, has_alpha)?
                 }
@@ -643,6 +678,15 @@ impl AvifDecoder {
                 .ok_or_else(|| at!(Error::Unsupported("failed to extract YUV planes")))?;
 
             match planes.chroma_sampling() {
+                ChromaSampling::Monochrome if self.config.prefer_grayscale && !has_alpha => {
+                    luma_to_gray16(
+                        planes.y.chunks(planes.width),
+                        planes.width,
+                        planes.height,
+                        color_range,
+                        bit_depth,
+                    )?
+                }
                 ChromaSampling::Monochrome => {
                     self.convert_mono16(&planes, yuv_range, matrix, bit_depth, has_alpha)?
                 }
@@ -668,6 +712,7 @@ impl AvifDecoder {
 
             let alpha_bit_depth = alpha_picture.bit_depth();
             let premultiplied = self.parser.premultiplied_alpha();
+            self.info.alpha_transformed = premultiplied.then_some(AlphaTransform::Unpremultiplied);
 
             if alpha_bit_depth == 8 {
                 let (y_data, width, height, _) = alpha_picture
@@ -704,8 +749,24 @@ impl AvifDecoder {
             scale_pixels_to_u16(&mut image, bit_depth);
         }
 
-        if self.config.prefer_8bit && bit_depth > 8 {
-            image = downscale_to_8bit(image);
+        if bit_depth > 8
+            && self
+                .config
+                .output_depth
+                .wants_8bit(&image, self.info.transfer_characteristics)
+        {
+            image = reduce_to_8bit(image, self.config.dither);
+            self.info.lossy_conversion = self.config.output_depth == OutputDepth::Force8;
+        }
+
+        if let Some(rect) = self.config.crop {
+            image = image.crop(rect)?;
+        }
+
+        if let Some((width, height)) = self.config.downscale
+            && let Some(reduced) = reduce_to_fit(&image, width, height)?
+        {
+            image = reduced;
         }
 
         Ok(image)
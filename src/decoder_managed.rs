@@ -5,19 +5,40 @@
 
 #![deny(unsafe_code)]
 
-use crate::config::DecoderConfig;
-use crate::convert::{add_alpha8, add_alpha16, downscale_to_8bit, scale_pixels_to_u16};
+use crate::alloc_stats::{self, Stage};
+use crate::av1_header;
+#[cfg(feature = "cms")]
+use crate::cms::ColorTransform;
+use crate::config::{
+    AnimationLimitPolicy, AnimationLimits, ChromaUpsampling, ConversionBackend, DecoderConfig,
+    OutputDepth, OutputLayout, OutputRange, PostProcess, RangeClipping, TileFill,
+};
+use crate::convert::{
+    add_alpha8, add_alpha16, compress_to_limited_range, luma_to_gray8, luma_to_gray16,
+    reduce_to_8bit, reduce_to_fit, scale_pixels_to_u16, to_bgra8, write_layout,
+    yuv_to_rgb16_preserve_overshoot,
+};
+use crate::deband::deband;
 use crate::error::{Error, Result};
 use crate::image::{
-    ChromaSampling, ColorPrimaries, ColorRange, DecodedAnimation, DecodedAnimationInfo,
-    DecodedFrame, ImageInfo, MatrixCoefficients, TransferCharacteristics,
+    AlphaTransform, AuxiliaryType, AvifDepthMap, ChromaSamplePosition, ChromaSampling,
+    ColorPrimaries, ColorRange, DecodedAnimation, DecodedAnimationInfo, DecodedFrame,
+    DecodedSemiPlanar, DecodedYuv, ImageInfo, MatrixCoefficients, SemiPlanarOrder,
+    TransferCharacteristics, YuvPlane, pixi_matches,
 };
-use crate::yuv_convert::{self, YuvMatrix as OurYuvMatrix, YuvRange as OurYuvRange};
+use crate::planar::{self, PlanarOptions, PlanarPixels};
+use crate::plane_copy::{PlaneCopy, Rect, border_average, color_pixel, fill_rect, new_buffer};
+use crate::progress::{ProgressEvent, ProgressFn};
+use crate::yuv_convert::{self, YCgCo, YuvMatrix as OurYuvMatrix, YuvRange as OurYuvRange};
+use crate::yuv_convert_16bit;
+use bytemuck::Pod;
 use enough::Stop;
 use rgb::{Rgb, Rgba};
+use std::borrow::Cow;
+use std::sync::{Mutex, PoisonError};
 use whereat::at;
 use yuv::{YuvGrayImage, YuvPlanarImage, YuvRange, YuvStandardMatrix};
-use zenpixels::{PixelBuffer, PixelDescriptor};
+use zenpixels::{ChannelType, PixelBuffer, PixelDescriptor};
 
 // Import managed API from rav1d-safe
 use rav1d_safe::src::managed::{
@@ -71,29 +92,28 @@ fn convert_color_range(range: Rav1dColorRange) -> ColorRange {
 fn to_yuv_matrix(mc: MatrixCoefficients) -> YuvStandardMatrix {
     match mc {
         MatrixCoefficients::BT709 => YuvStandardMatrix::Bt709,
-        MatrixCoefficients::BT601 | MatrixCoefficients::BT470BG | MatrixCoefficients::FCC => {
-            YuvStandardMatrix::Bt601
-        }
+        MatrixCoefficients::BT601 | MatrixCoefficients::BT470BG => YuvStandardMatrix::Bt601,
         MatrixCoefficients::BT2020_NCL | MatrixCoefficients::BT2020_CL => YuvStandardMatrix::Bt2020,
         MatrixCoefficients::SMPTE240 => YuvStandardMatrix::Smpte240,
+        MatrixCoefficients::FCC => YuvStandardMatrix::Fcc,
         _ => YuvStandardMatrix::Bt601,
     }
 }
 
 /// Convert zenavif MatrixCoefficients to our YuvMatrix
-fn to_our_yuv_matrix(mc: MatrixCoefficients) -> OurYuvMatrix {
+pub(crate) fn to_our_yuv_matrix(mc: MatrixCoefficients) -> OurYuvMatrix {
     match mc {
         MatrixCoefficients::BT709 => OurYuvMatrix::Bt709,
-        MatrixCoefficients::BT601 | MatrixCoefficients::BT470BG | MatrixCoefficients::FCC => {
-            OurYuvMatrix::Bt601
-        }
+        MatrixCoefficients::BT601 | MatrixCoefficients::BT470BG => OurYuvMatrix::Bt601,
         MatrixCoefficients::BT2020_NCL | MatrixCoefficients::BT2020_CL => OurYuvMatrix::Bt2020,
+        MatrixCoefficients::SMPTE240 => OurYuvMatrix::Smpte240,
+        MatrixCoefficients::FCC => OurYuvMatrix::Fcc,
         _ => OurYuvMatrix::Bt601, // Default to BT.601 for unknown
     }
 }
 
 /// Convert zenavif ColorRange to our YuvRange
-fn to_our_yuv_range(cr: ColorRange) -> OurYuvRange {
+pub(crate) fn to_our_yuv_range(cr: ColorRange) -> OurYuvRange {
     match cr {
         ColorRange::Limited => OurYuvRange::Limited,
         ColorRange::Full => OurYuvRange::Full,
@@ -108,6 +128,15 @@ fn to_yuv_range(range: ColorRange) -> YuvRange {
     }
 }
 
+/// Horizontal and vertical chroma subsampling shifts of `sampling`.
+fn chroma_shift(sampling: ChromaSampling) -> (u8, u8) {
+    match sampling {
+        ChromaSampling::Cs420 => (1, 1),
+        ChromaSampling::Cs422 => (1, 0),
+        _ => (0, 0),
+    }
+}
+
 /// Convert rav1d-safe PixelLayout to zenavif ChromaSampling
 fn convert_chroma_sampling(layout: PixelLayout) -> ChromaSampling {
     match layout {
@@ -118,50 +147,849 @@ fn convert_chroma_sampling(layout: PixelLayout) -> ChromaSampling {
     }
 }
 
+/// Parse `data` in place and probe its metadata without creating an AV1
+/// decoder.
+pub(crate) fn probe_bytes(data: &[u8]) -> Result<ImageInfo> {
+    let parse_config = zenavif_parse::DecodeConfig::default().lenient(true);
+    let parser = zenavif_parse::AvifParser::from_bytes_with_config(
+        data,
+        &parse_config,
+        &enough::Unstoppable,
+    )
+    .map_err(|e| at!(Error::from(e)))?;
+    probe_parser(
+        &parser,
+        grid_config(&parser, data).as_ref(),
+        declared_bits(data),
+        hdr10_plus(data),
+        depth_map(data),
+    )
+}
+
+/// The operating point of the primary item's AV1 stream with the fewest
+/// spatial layers, for [`DecoderConfig::prefer_lowest_resolution`].
+///
+/// Grid images are judged by their first tile.
+fn lowest_operating_point(parser: &zenavif_parse::AvifParser<'_>) -> Option<u8> {
+    let data = if parser.grid_config().is_some() {
+        parser.tile_data(0).ok()?
+    } else {
+        parser.primary_data().ok()?
+    };
+    let mut seq = None;
+    let _ = av1_header::is_sync_sample(&data, &mut seq);
+    seq?.lowest_resolution_operating_point()
+}
+
+/// The chroma sample position from the primary item's AV1 sequence header,
+/// or [`ChromaSamplePosition::Unknown`] if the header can't be read.
+///
+/// Grid images are judged by their first tile.
+pub(crate) fn chroma_sample_position(
+    parser: &zenavif_parse::AvifParser<'_>,
+) -> ChromaSamplePosition {
+    let data = if parser.grid_config().is_some() {
+        parser.tile_data(0).ok()
+    } else {
+        parser.primary_data().ok()
+    };
+    let mut seq = None;
+    if let Some(data) = data {
+        let _ = av1_header::is_sync_sample(&data, &mut seq);
+    }
+    seq.map_or(ChromaSamplePosition::Unknown, |seq| {
+        ChromaSamplePosition::from_av1(seq.chroma_sample_position)
+    })
+}
+
+/// The primary item's `pixi` bits per channel, read from the raw file since
+/// zenavif-parse does not expose it. A malformed `pixi` counts as absent.
+pub(crate) fn declared_bits(data: &[u8]) -> Option<Vec<u8>> {
+    crate::bmff::primary_item_pixi(data).ok().flatten()
+}
+
+/// Layout of a grid primary item. zenavif-parse only reads a `grid`
+/// property and otherwise divides the grid's `ispe` by a tile's, which
+/// goes wrong when edge tiles are padded, so the ImageGrid descriptor in
+/// the item data wins when it can be read.
+pub(crate) fn grid_config(
+    parser: &zenavif_parse::AvifParser<'_>,
+    data: &[u8],
+) -> Option<zenavif_parse::GridConfig> {
+    let parsed = parser.grid_config()?;
+    let stored = crate::bmff::primary_item_grid(data).ok().flatten();
+    Some(stored.unwrap_or_else(|| parsed.clone()))
+}
+
+/// Paint `holes`, the output areas of grid tiles that failed to decode, as
+/// `fill` asks.
+///
+/// Neighbour averages are all taken before any hole is painted, so they
+/// only ever see decoded pixels. Layouts without a known fill stay black.
+fn fill_failed_tiles(output: &mut PixelBuffer, holes: &[Rect], fill: TileFill) {
+    let pixels: Vec<_> = holes
+        .iter()
+        .map(|&hole| match fill {
+            TileFill::Color(color) => color_pixel(output, color),
+            TileFill::NeighborAverage => {
+                border_average(output, hole, holes).or_else(|| color_pixel(output, [0, 0, 0, 255]))
+            }
+        })
+        .collect();
+    for (&hole, pixel) in holes.iter().zip(pixels) {
+        if let Some(pixel) = pixel {
+            fill_rect(output, hole, &pixel);
+        }
+    }
+}
+
+/// The primary item's HDR10+ T.35 message, read from the raw file since
+/// zenavif-parse does not expose `it35`. A malformed property counts as
+/// absent.
+pub(crate) fn hdr10_plus(data: &[u8]) -> Option<Vec<u8>> {
+    crate::bmff::primary_item_it35(data).ok().flatten()
+}
+
+/// `auxC` types that mark an auxiliary image as a depth map.
+const DEPTH_AUX_TYPES: [&str; 2] = [
+    "urn:mpeg:mpegB:cicp:systems:auxiliary:depth",
+    "urn:mpeg:hevc:2015:auxid:2",
+];
+
+/// The primary item's depth auxiliary image, read from the raw file since
+/// zenavif-parse does not expose it. A malformed reference counts as absent.
+pub(crate) fn depth_map(data: &[u8]) -> Option<AvifDepthMap> {
+    crate::bmff::primary_item_auxiliary(data, &DEPTH_AUX_TYPES)
+        .ok()
+        .flatten()
+        .map(|data| AvifDepthMap { data })
+}
+
+/// Fail if the `pixi` declaration contradicts the parser's `av1C`.
+pub(crate) fn check_declared_bits(
+    declared: Option<&[u8]>,
+    parser: &zenavif_parse::AvifParser<'_>,
+) -> Result<()> {
+    if let (Some(bits), Some(av1)) = (declared, parser.av1_config())
+        && !pixi_matches(bits, av1.bit_depth, av1.monochrome)
+    {
+        return Err(at!(Error::Unsupported(
+            "pixi property does not match the AV1 bit depth or channel count",
+        )));
+    }
+    Ok(())
+}
+
+/// Build [`ImageInfo`] from container and AV1 header metadata alone.
+///
+/// Shared by [`ManagedAvifDecoder::probe_info`], [`crate::probe`] and
+/// [`crate::probe_batch`];
+/// needs no AV1 decoder instance. `declared_bits_per_channel` comes from
+/// [`declared_bits`], `hdr10_plus` from [`hdr10_plus`] and `depth_map` from
+/// [`depth_map`].
+pub(crate) fn probe_parser(
+    parser: &zenavif_parse::AvifParser<'_>,
+    grid: Option<&zenavif_parse::GridConfig>,
+    declared_bits_per_channel: Option<Vec<u8>>,
+    hdr10_plus: Option<Vec<u8>>,
+    depth_map: Option<AvifDepthMap>,
+) -> Result<ImageInfo> {
+    // Get dimensions from grid config or AV1 sequence header
+    let (width, height) = if let Some(grid) = grid {
+        (grid.output_width, grid.output_height)
+    } else {
+        let meta = parser.primary_metadata().map_err(|e| at!(Error::from(e)))?;
+        (meta.max_frame_width.get(), meta.max_frame_height.get())
+    };
+
+    let has_alpha = parser.alpha_metadata().is_some();
+
+    // AV1 config for bit depth
+    let bit_depth = parser.av1_config().map(|c| c.bit_depth).unwrap_or(8);
+
+    // CICP from container (colr box) or AV1 config fallback
+    let (color_primaries, transfer_characteristics, matrix_coefficients, color_range, icc_profile) =
+        match parser.color_info() {
+            Some(zenavif_parse::ColorInformation::Nclx {
+                color_primaries: cp,
+                transfer_characteristics: tc,
+                matrix_coefficients: mc,
+                full_range,
+            }) => (
+                ColorPrimaries(*cp as u8),
+                TransferCharacteristics(*tc as u8),
+                MatrixCoefficients(*mc as u8),
+                if *full_range {
+                    ColorRange::Full
+                } else {
+                    ColorRange::Limited
+                },
+                None,
+            ),
+            Some(zenavif_parse::ColorInformation::IccProfile(icc)) => (
+                ColorPrimaries::BT709,
+                TransferCharacteristics::SRGB,
+                MatrixCoefficients::BT601,
+                ColorRange::Full,
+                Some(icc.clone()),
+            ),
+            None => (
+                ColorPrimaries::BT709,
+                TransferCharacteristics::SRGB,
+                MatrixCoefficients::BT601,
+                ColorRange::Full,
+                None,
+            ),
+        };
+
+    let chroma_sampling = parser
+        .av1_config()
+        .map(|c| {
+            if c.monochrome {
+                ChromaSampling::Monochrome
+            } else if c.chroma_subsampling_x != 0 && c.chroma_subsampling_y != 0 {
+                ChromaSampling::Cs420
+            } else if c.chroma_subsampling_x != 0 {
+                ChromaSampling::Cs422
+            } else {
+                ChromaSampling::Cs444
+            }
+        })
+        .unwrap_or(ChromaSampling::Cs420);
+
+    Ok(ImageInfo {
+        width,
+        height,
+        bit_depth,
+        has_alpha,
+        premultiplied_alpha: parser.premultiplied_alpha(),
+        monochrome: chroma_sampling == ChromaSampling::Monochrome,
+        color_primaries,
+        transfer_characteristics,
+        matrix_coefficients,
+        color_range,
+        chroma_sampling,
+        chroma_sample_position: chroma_sample_position(parser),
+        icc_profile,
+        rotation: parser.rotation().cloned(),
+        mirror: parser.mirror().cloned(),
+        clean_aperture: parser.clean_aperture().cloned(),
+        pixel_aspect_ratio: parser.pixel_aspect_ratio().cloned(),
+        content_light_level: parser.content_light_level().cloned(),
+        mastering_display: parser.mastering_display().cloned(),
+        hdr10_plus,
+        declared_bits_per_channel,
+        exif: parser.exif().and_then(|r| r.ok()).map(|c| c.into_owned()),
+        xmp: parser.xmp().and_then(|r| r.ok()).map(|c| c.into_owned()),
+        gain_map: gain_map_from(parser),
+        // Depth map extraction requires zenavif-parse > 0.4.0 (not yet published).
+        depth_map,
+        failed_tiles: Vec::new(),
+        lossy_conversion: false,
+        alpha_transformed: None,
+    })
+}
+
+/// Bundle gain_map_metadata, gain_map_data, and gain_map_color_info from
+/// the parser into a single [`AvifGainMap`](crate::image::AvifGainMap).
+fn gain_map_from(parser: &zenavif_parse::AvifParser<'_>) -> Option<crate::image::AvifGainMap> {
+    let metadata = parser.gain_map_metadata()?.clone();
+    let data = parser.gain_map_data()?.ok()?.into_owned();
+    let alt_color_info = parser.gain_map_color_info().cloned();
+    Some(crate::image::AvifGainMap {
+        metadata,
+        gain_map_data: data,
+        alt_color_info,
+    })
+}
+
 /// Managed decoder wrapper - 100% safe!
-pub struct ManagedAvifDecoder {
+///
+/// The lifetime is that of the input data: `'static` for decoders from
+/// [`new`](ManagedAvifDecoder::new), which copy it, and the slice's for
+/// [`from_slice`](ManagedAvifDecoder::from_slice), which borrow it.
+pub struct ManagedAvifDecoder<'a> {
     decoder: Rav1dDecoder,
-    parser: zenavif_parse::AvifParser<'static>,
-    prefer_8bit: bool,
+    parser: zenavif_parse::AvifParser<'a>,
+    /// Layout of a grid primary item, from [`grid_config`].
+    grid: Option<zenavif_parse::GridConfig>,
+    output_depth: OutputDepth,
+    range_clipping: RangeClipping,
+    output_range: OutputRange,
+    output_layout: OutputLayout,
+    dither: bool,
+    chroma_upsampling: Option<ChromaUpsampling>,
+    conversion_backend: ConversionBackend,
+    deband: f32,
+    prefer_grayscale: bool,
+    pipelined_animation: bool,
+    downscale: Option<(u32, u32)>,
+    crop: Option<Rect>,
+    error_resilient: bool,
+    tile_fill: TileFill,
+    animation_limits: AnimationLimits,
+    /// Grid tiles that failed in the last decode, under `error_resilient`.
+    failed_tiles: Vec<usize>,
+    /// Conversion from the embedded ICC profile, under `color_manage`.
+    #[cfg(feature = "cms")]
+    color_transform: Option<ColorTransform>,
+    /// Settings `decoder` was created with, for creating more decoders.
+    settings: DecoderSettings,
+    post_process: Option<PostProcess>,
+    progress: Mutex<Option<ProgressFn<'a>>>,
+    reserved: Mutex<ReservedOutput>,
+    declared_bits: Option<Vec<u8>>,
+    chroma_position: ChromaSamplePosition,
+    hdr10_plus: Option<Vec<u8>>,
+    depth_map: Option<AvifDepthMap>,
 }
 
-impl ManagedAvifDecoder {
-    /// Create new decoder with AVIF data and configuration
-    pub fn new(data: &[u8], config: &DecoderConfig) -> Result<Self> {
-        // Use zero-copy AvifParser — primary/alpha data returned as Cow::Borrowed
-        let mut parse_config = zenavif_parse::DecodeConfig::default().lenient(true);
-        // Forward resource limits to the parser when configured.
-        if let Some(mem) = config.parser_peak_memory_limit {
-            parse_config = parse_config.with_peak_memory_limit(mem);
-        }
-        if let Some(mp) = config.parser_total_megapixels_limit {
-            parse_config = parse_config.with_total_megapixels_limit(mp);
-        }
-        if let Some(frames) = config.parser_max_animation_frames {
-            parse_config = parse_config.with_max_animation_frames(frames);
-        }
-        let parser = zenavif_parse::AvifParser::from_owned_with_config(
-            data.to_vec(),
-            &parse_config,
-            &enough::Unstoppable,
-        )
-        .map_err(|e| at!(Error::from(e)))?;
+/// The film grain seed to substitute, when grain is applied at all.
+pub(crate) fn grain_seed_override(config: &DecoderConfig) -> Option<u16> {
+    config
+        .grain_seed_override
+        .filter(|_| config.apply_grain && config.downscale.is_none())
+}
+
+/// `data` with the film grain seed of its intra frames replaced by `seed`.
+pub(crate) fn reseed_grain(data: &[u8], seed: Option<u16>) -> Cow<'_, [u8]> {
+    let Some(seed) = seed else {
+        return Cow::Borrowed(data);
+    };
+    let mut data = data.to_vec();
+    // Headers we can't parse are left for the decoder to judge.
+    let _ = av1_header::override_grain_seed(&mut data, &mut None, seed);
+    Cow::Owned(data)
+}
+
+/// The rav1d settings taken from a [`DecoderConfig`].
+#[derive(Debug, Clone, Copy)]
+struct DecoderSettings {
+    threads: u32,
+    apply_grain: bool,
+    frame_size_limit: u32,
+    operating_point: u8,
+    all_layers: bool,
+    grain_seed: Option<u16>,
+}
+
+impl DecoderSettings {
+    fn new(config: &DecoderConfig) -> Self {
+        Self {
+            threads: if config.deterministic {
+                1
+            } else {
+                config.threads
+            },
+            // Grain would be averaged away by the post-decode downscale.
+            apply_grain: config.apply_grain && config.downscale.is_none(),
+            frame_size_limit: config.frame_size_limit,
+            operating_point: config.operating_point,
+            all_layers: config.all_layers,
+            grain_seed: grain_seed_override(config),
+        }
+    }
+
+    /// Threads this config allows, with 0 (automatic) resolved to the
+    /// number of cores.
+    fn thread_budget(&self) -> u32 {
+        match self.threads {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get() as u32),
+            n => n,
+        }
+    }
+
+    /// Settings for the color decoder of a still with alpha, which leave
+    /// one thread of the budget to the alpha decoder running beside it.
+    fn beside_alpha(self) -> Self {
+        Self {
+            threads: self.thread_budget().saturating_sub(1).max(1),
+            ..self
+        }
+    }
 
+    fn create(self) -> Result<Rav1dDecoder> {
         let mut settings = Settings::default();
-        settings.threads = config.threads;
-        settings.apply_grain = config.apply_grain;
-        settings.frame_size_limit = config.frame_size_limit;
+        settings.threads = self.threads;
+        settings.apply_grain = self.apply_grain;
+        settings.frame_size_limit = self.frame_size_limit;
+        settings.operating_point = self.operating_point;
+        settings.all_layers = self.all_layers;
 
-        let decoder = Rav1dDecoder::with_settings(settings).map_err(|_e| {
+        Rav1dDecoder::with_settings(settings).map_err(|_e| {
             at!(Error::Decode {
                 code: -1,
                 msg: "Failed to create decoder",
             })
+        })
+    }
+}
+
+/// Decoded frames buffered between the decode and convert threads of a
+/// pipelined animation decode.
+const PIPELINE_DEPTH: usize = 2;
+
+/// An animation frame between the decode and convert stages: color, alpha
+/// and duration in milliseconds.
+type DecodedAnimFrame = (Frame, Option<Frame>, u32);
+
+/// Copies of a frame's planes, luma cropped to `luma` and chroma to `chroma`
+/// (width, height).
+fn frame_planes(
+    frame: &Frame,
+    luma: (usize, usize),
+    chroma: (usize, usize),
+) -> Result<(YuvPlane, Option<YuvPlane>, Option<YuvPlane>)> {
+    if let Planes::Depth8(planes) = frame.planes() {
+        let copy = |rows, (w, h)| copy_plane(rows, w, h).map(YuvPlane::U8);
+        let (u_view, v_view) = (planes.u(), planes.v());
+        return Ok((
+            copy(planes.y().rows(), luma)?,
+            u_view
+                .as_ref()
+                .map(|u| copy(u.rows(), chroma))
+                .transpose()?,
+            v_view
+                .as_ref()
+                .map(|v| copy(v.rows(), chroma))
+                .transpose()?,
+        ));
+    }
+    let Planes::Depth16(planes) = frame.planes() else {
+        return Err(at!(Error::Decode {
+            code: -1,
+            msg: "Expected 16-bit planes",
+        }));
+    };
+    let copy = |rows, (w, h)| copy_plane(rows, w, h).map(YuvPlane::U16);
+    let (u_view, v_view) = (planes.u(), planes.v());
+    Ok((
+        copy(planes.y().rows(), luma)?,
+        u_view
+            .as_ref()
+            .map(|u| copy(u.rows(), chroma))
+            .transpose()?,
+        v_view
+            .as_ref()
+            .map(|v| copy(v.rows(), chroma))
+            .transpose()?,
+    ))
+}
+
+/// Copy the top-left `width` × `height` samples of a plane into a compact
+/// buffer.
+fn copy_plane<'a, T: Copy + 'a>(
+    rows: impl Iterator<Item = &'a [T]>,
+    width: usize,
+    height: usize,
+) -> Result<imgref::ImgVec<T>> {
+    let mut buf = Vec::new();
+    buf.try_reserve_exact(width * height)
+        .map_err(|_| at!(Error::OutOfMemory))?;
+    for row in rows.take(height) {
+        let row = row.get(..width).ok_or_else(|| {
+            at!(Error::Decode {
+                code: -1,
+                msg: "plane is narrower than the image",
+            })
         })?;
+        buf.extend_from_slice(row);
+    }
+    if buf.len() != width * height {
+        return Err(at!(Error::Decode {
+            code: -1,
+            msg: "plane is shorter than the image",
+        }));
+    }
+    Ok(imgref::ImgVec::new(buf, width, height))
+}
+
+/// Interleave the top-left `width` × `height` samples of two chroma
+/// planes into one `2 * width` wide plane, `first` before `second`.
+fn interleave_planes<'a, T: Copy + 'a>(
+    first: impl Iterator<Item = &'a [T]>,
+    second: impl Iterator<Item = &'a [T]>,
+    width: usize,
+    height: usize,
+) -> Result<imgref::ImgVec<T>> {
+    let mut buf = Vec::new();
+    buf.try_reserve_exact(2 * width * height)
+        .map_err(|_| at!(Error::OutOfMemory))?;
+    for (a, b) in first.zip(second).take(height) {
+        let (Some(a), Some(b)) = (a.get(..width), b.get(..width)) else {
+            return Err(at!(Error::Decode {
+                code: -1,
+                msg: "plane is narrower than the image",
+            }));
+        };
+        buf.extend(a.iter().zip(b).flat_map(|(&a, &b)| [a, b]));
+    }
+    if buf.len() != 2 * width * height {
+        return Err(at!(Error::Decode {
+            code: -1,
+            msg: "plane is shorter than the image",
+        }));
+    }
+    Ok(imgref::ImgVec::new(buf, 2 * width, height))
+}
+
+/// Bring subsampled `u` and `v` planes (samples, stride) to luma size with
+/// `filter`, or `None` to leave them to the converter's own filter.
+fn upsample_chroma<T: crate::chroma_upsample::Sample>(
+    filter: Option<ChromaUpsampling>,
+    u: (&[T], usize),
+    v: (&[T], usize),
+    sampling: ChromaSampling,
+    info: &ImageInfo,
+) -> Result<Option<[Vec<T>; 2]>> {
+    let subsampled = match sampling {
+        ChromaSampling::Cs420 => (true, true),
+        ChromaSampling::Cs422 => (true, false),
+        _ => return Ok(None),
+    };
+    let Some(filter) = filter else {
+        return Ok(None);
+    };
+    let luma = (info.width as usize, info.height as usize);
+    let chroma = (
+        if subsampled.0 {
+            luma.0.div_ceil(2)
+        } else {
+            luma.0
+        },
+        if subsampled.1 {
+            luma.1.div_ceil(2)
+        } else {
+            luma.1
+        },
+    );
+    let max = ((1u32 << info.bit_depth) - 1) as f32;
+    let upsample = |(plane, stride)| {
+        crate::chroma_upsample::upsample_plane(
+            plane,
+            stride,
+            chroma,
+            luma,
+            subsampled,
+            info.chroma_sample_position,
+            filter,
+            max,
+        )
+    };
+    Ok(Some([upsample(u)?, upsample(v)?]))
+}
+
+/// Convert 8-bit `y`, `u` and `v` planes (samples, stride) with the
+/// integer converters `backend` selects, or `None` to leave them to the
+/// reference converters.
+fn convert_8bit_with_backend(
+    backend: ConversionBackend,
+    y: (&[u8], usize),
+    u: (&[u8], usize),
+    v: (&[u8], usize),
+    sampling: ChromaSampling,
+    info: &ImageInfo,
+    has_alpha: bool,
+) -> Result<Option<PixelBuffer>> {
+    let (width, height) = (info.width as usize, info.height as usize);
+    match backend {
+        ConversionBackend::Reference => Ok(None),
+        ConversionBackend::Exact => {
+            // The libyuv constants here are BT.601 and BT.709 limited range.
+            if info.color_range != ColorRange::Limited {
+                return Ok(None);
+            }
+            let matrix = match info.matrix_coefficients {
+                MatrixCoefficients::BT709 => OurYuvMatrix::Bt709,
+                MatrixCoefficients::BT601
+                | MatrixCoefficients::BT470BG
+                | MatrixCoefficients::UNKNOWN => OurYuvMatrix::Bt601,
+                _ => return Ok(None),
+            };
+            let convert = match sampling {
+                ChromaSampling::Cs420 => crate::yuv_convert_libyuv::yuv420_to_rgb8,
+                ChromaSampling::Cs422 => crate::yuv_convert_libyuv::yuv422_to_rgb8,
+                ChromaSampling::Cs444 => crate::yuv_convert_libyuv::yuv444_to_rgb8,
+                ChromaSampling::Monochrome => return Ok(None),
+            };
+            let Some(rgb) = convert(
+                y.0,
+                y.1,
+                u.0,
+                u.1,
+                v.0,
+                v.1,
+                width,
+                height,
+                to_our_yuv_range(info.color_range),
+                matrix,
+            ) else {
+                return Ok(None);
+            };
+            if !has_alpha {
+                return Ok(Some(PixelBuffer::from_imgvec(rgb).into()));
+            }
+            let rgba: Vec<Rgba<u8>> = rgb
+                .into_buf()
+                .into_iter()
+                .map(|p| p.with_alpha(255))
+                .collect();
+            let image = PixelBuffer::from_pixels(rgba, width as u32, height as u32)
+                .map_err(|_| at!(Error::OutOfMemory))?;
+            Ok(Some(image.into()))
+        }
+        ConversionBackend::Fast => {
+            type Convert = fn(
+                &YuvPlanarImage<u8>,
+                &mut [u8],
+                u32,
+                YuvRange,
+                YuvStandardMatrix,
+            ) -> std::result::Result<(), yuv::YuvError>;
+            let (convert, convert_alpha): (Convert, Convert) = match sampling {
+                ChromaSampling::Cs420 => (yuv::yuv420_to_rgb, yuv::yuv420_to_rgba),
+                ChromaSampling::Cs422 => (yuv::yuv422_to_rgb, yuv::yuv422_to_rgba),
+                ChromaSampling::Cs444 => (yuv::yuv444_to_rgb, yuv::yuv444_to_rgba),
+                ChromaSampling::Monochrome => return Ok(None),
+            };
+            let planar = YuvPlanarImage {
+                y_plane: y.0,
+                y_stride: y.1 as u32,
+                u_plane: u.0,
+                u_stride: u.1 as u32,
+                v_plane: v.0,
+                v_stride: v.1 as u32,
+                width: width as u32,
+                height: height as u32,
+            };
+            let range = to_yuv_range(info.color_range);
+            let matrix = to_yuv_matrix(info.matrix_coefficients);
+            let pixels = width * height;
+            let image = if has_alpha {
+                let mut out = vec![
+                    Rgba {
+                        r: 0u8,
+                        g: 0,
+                        b: 0,
+                        a: 255
+                    };
+                    pixels
+                ];
+                convert_alpha(
+                    &planar,
+                    rgb::bytemuck::cast_slice_mut(&mut out),
+                    width as u32 * 4,
+                    range,
+                    matrix,
+                )
+                .map_err(|e| at!(Error::ColorConversion(e)))?;
+                PixelBuffer::from_pixels(out, width as u32, height as u32)
+                    .map_err(|_| at!(Error::OutOfMemory))?
+                    .into()
+            } else {
+                let mut out = vec![Rgb { r: 0u8, g: 0, b: 0 }; pixels];
+                convert(
+                    &planar,
+                    rgb::bytemuck::cast_slice_mut(&mut out),
+                    width as u32 * 3,
+                    range,
+                    matrix,
+                )
+                .map_err(|e| at!(Error::ColorConversion(e)))?;
+                PixelBuffer::from_pixels(out, width as u32, height as u32)
+                    .map_err(|_| at!(Error::OutOfMemory))?
+                    .into()
+            };
+            Ok(Some(image))
+        }
+    }
+}
+
+/// Output storage set aside by [`ManagedAvifDecoder::preallocate`].
+#[derive(Default)]
+struct ReservedOutput {
+    samples8: Vec<u8>,
+    samples16: Vec<u16>,
+}
+
+/// Reuse `reserved` as a `Vec<P>` of `len` × `fill` when it is large enough
+/// and its layout allows, otherwise allocate.
+fn take_reserved<S: Pod, P: Pod>(reserved: &mut Vec<S>, len: usize, fill: P) -> Vec<P> {
+    if reserved.capacity() * size_of::<S>() >= len * size_of::<P>() {
+        match bytemuck::allocation::try_cast_vec::<S, P>(core::mem::take(reserved)) {
+            Ok(mut out) => {
+                out.clear();
+                out.resize(len, fill);
+                return out;
+            }
+            Err((_, original)) => *reserved = original,
+        }
+    }
+    vec![fill; len]
+}
+
+/// Give `reserved` room for exactly `samples` samples and write to every
+/// page so the first use does not fault.
+fn reserve_touched<S: Pod>(reserved: &mut Vec<S>, samples: usize) -> Result<()> {
+    *reserved = Vec::new();
+    reserved
+        .try_reserve_exact(samples)
+        .map_err(|_| at!(Error::OutOfMemory))?;
+    reserved.resize(samples, S::zeroed());
+    reserved.clear();
+    Ok(())
+}
+
+impl ManagedAvifDecoder<'static> {
+    /// Create new decoder with AVIF data and configuration
+    ///
+    /// The data is copied, so the decoder outlives it. Use
+    /// [`from_slice`](ManagedAvifDecoder::from_slice) to borrow it instead.
+    pub fn new(data: &[u8], config: &DecoderConfig) -> Result<Self> {
+        crate::simd::warn_if_fallback();
+        let parser = {
+            let _stage = alloc_stats::enter(Stage::Parse);
+            zenavif_parse::AvifParser::from_owned_with_config(
+                data.to_vec(),
+                &parse_config(config),
+                &enough::Unstoppable,
+            )
+            .map_err(|e| at!(Error::from(e)))?
+        };
+        ManagedAvifDecoder::with_parser(parser, data, config)
+    }
+}
+
+/// Peak bytes a decode of `parser`'s primary image is expected to need,
+/// or `None` when the dimensions are unknown.
+///
+/// Counts two YUV frames (the reconstruction and the picture handed out,
+/// which is a separate copy once film grain is applied), the alpha plane
+/// likewise, and the RGB(A) output at the configured depth.
+pub(crate) fn estimated_decode_memory(
+    parser: &zenavif_parse::AvifParser<'_>,
+    grid: Option<&zenavif_parse::GridConfig>,
+    config: &DecoderConfig,
+) -> Option<u64> {
+    let (width, height) = if let Some(grid) = grid {
+        (grid.output_width, grid.output_height)
+    } else {
+        let meta = parser.primary_metadata().ok()?;
+        (meta.max_frame_width.get(), meta.max_frame_height.get())
+    };
+    let pixels = u64::from(width) * u64::from(height);
+    let av1 = parser.av1_config();
+    let bit_depth = av1.map_or(8, |c| c.bit_depth);
+    let sample_bytes = if bit_depth > 8 { 2 } else { 1 };
+    // Chroma planes in quarters of the luma plane.
+    let chroma_quarters = match av1 {
+        Some(c) if c.monochrome => 0,
+        Some(c) => 8u64 >> (c.chroma_subsampling_x + c.chroma_subsampling_y),
+        None => 8,
+    };
+    let has_alpha = parser.alpha_metadata().is_some();
+    let yuv = pixels * (4 + chroma_quarters) / 4 * sample_bytes;
+    let alpha = if has_alpha { pixels * sample_bytes } else { 0 };
+    let output_sample_bytes = match config.output_depth {
+        OutputDepth::Force8 => 1,
+        _ => sample_bytes,
+    };
+    let output = pixels * (3 + u64::from(has_alpha)) * output_sample_bytes;
+    Some((yuv + alpha) * 2 + output)
+}
+
+/// Refuse decodes whose estimated memory exceeds
+/// [`DecoderConfig::max_memory_bytes`].
+pub(crate) fn check_memory_limit(
+    parser: &zenavif_parse::AvifParser<'_>,
+    grid: Option<&zenavif_parse::GridConfig>,
+    config: &DecoderConfig,
+) -> Result<()> {
+    let Some(limit) = config.max_memory_bytes else {
+        return Ok(());
+    };
+    match estimated_decode_memory(parser, grid, config) {
+        Some(estimated) if estimated > limit => {
+            Err(at!(Error::ResourceLimitExceeded { estimated, limit }))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Parser settings for a [`DecoderConfig`]: lenient, with the configured
+/// resource limits.
+fn parse_config(config: &DecoderConfig) -> zenavif_parse::DecodeConfig {
+    let mut parse_config = zenavif_parse::DecodeConfig::default().lenient(true);
+    // Forward resource limits to the parser when configured.
+    if let Some(mem) = config.parser_peak_memory_limit {
+        parse_config = parse_config.with_peak_memory_limit(mem);
+    }
+    if let Some(mp) = config.parser_total_megapixels_limit {
+        parse_config = parse_config.with_total_megapixels_limit(mp);
+    }
+    if let Some(frames) = config.parser_max_animation_frames {
+        parse_config = parse_config.with_max_animation_frames(frames);
+    }
+    parse_config
+}
+
+impl<'a> ManagedAvifDecoder<'a> {
+    /// Create a decoder that borrows `data` instead of copying it
+    ///
+    /// Same as [`new`](ManagedAvifDecoder::new), without a second copy of
+    /// the file in memory; primary, alpha and tile data are read from
+    /// `data` directly.
+    pub fn from_slice(data: &'a [u8], config: &DecoderConfig) -> Result<Self> {
+        let parser = {
+            let _stage = alloc_stats::enter(Stage::Parse);
+            zenavif_parse::AvifParser::from_bytes_with_config(
+                data,
+                &parse_config(config),
+                &enough::Unstoppable,
+            )
+            .map_err(|e| at!(Error::from(e)))?
+        };
+        Self::with_parser(parser, data, config)
+    }
+
+    /// Finish construction around a parser of `data`.
+    fn with_parser(
+        parser: zenavif_parse::AvifParser<'a>,
+        data: &[u8],
+        config: &DecoderConfig,
+    ) -> Result<Self> {
+        let mut settings = DecoderSettings::new(config);
+        if config.prefer_lowest_resolution
+            && let Some(point) = lowest_operating_point(&parser)
+        {
+            settings.operating_point = point;
+            settings.all_layers = false;
+        }
+        let grid = grid_config(&parser, data);
+        let still_with_alpha = grid.is_none()
+            && parser.animation_info().is_none()
+            && parser.alpha_metadata().is_some();
+        let decoder = if still_with_alpha {
+            settings.beside_alpha().create()?
+        } else {
+            settings.create()?
+        };
+        let declared_bits = declared_bits(data);
+        if config.strict_pixi {
+            check_declared_bits(declared_bits.as_deref(), &parser)?;
+        }
+
+        #[cfg(feature = "cms")]
+        let color_transform = match (&config.color_manage, parser.color_info()) {
+            (Some(destination), Some(zenavif_parse::ColorInformation::IccProfile(icc))) => {
+                Some(ColorTransform::new(icc, destination)?)
+            }
+            _ => None,
+        };
 
         // Validate dimensions against frame_size_limit before any decode work
         if config.frame_size_limit > 0 {
-            let (width, height) = if let Some(grid) = parser.grid_config() {
+            let (width, height) = if let Some(grid) = &grid {
                 (grid.output_width, grid.output_height)
             } else if let Ok(meta) = parser.primary_metadata() {
                 (meta.max_frame_width.get(), meta.max_frame_height.get())
@@ -173,39 +1001,224 @@ impl ManagedAvifDecoder {
                 return Err(at!(Error::ImageTooLarge { width, height }));
             }
         }
+        check_memory_limit(&parser, grid.as_ref(), config)?;
+        let chroma_position = chroma_sample_position(&parser);
 
         Ok(Self {
             decoder,
             parser,
-            prefer_8bit: config.prefer_8bit,
+            grid,
+            // Reordered layouts are 8-bit only.
+            output_depth: match config.output_layout {
+                OutputLayout::Rgb => config.output_depth,
+                _ => OutputDepth::Force8,
+            },
+            range_clipping: config.range_clipping,
+            output_range: config.output_range,
+            output_layout: config.output_layout,
+            deband: config.deband,
+            prefer_grayscale: config.prefer_grayscale,
+            pipelined_animation: config.pipelined_animation,
+            downscale: config.downscale,
+            crop: config.crop,
+            error_resilient: config.error_resilient,
+            tile_fill: config.tile_fill,
+            animation_limits: config.animation_limits,
+            failed_tiles: Vec::new(),
+            dither: config.dither,
+            chroma_upsampling: config.chroma_upsampling,
+            conversion_backend: config.conversion_backend,
+            #[cfg(feature = "cms")]
+            color_transform,
+            settings,
+            post_process: config.post_process.clone(),
+            progress: Mutex::default(),
+            reserved: Mutex::default(),
+            declared_bits,
+            chroma_position,
+            hdr10_plus: hdr10_plus(data),
+            depth_map: depth_map(data),
         })
     }
 
-    /// Decode a single AV1 frame, handling progressive/multi-layer streams transparently.
+    /// Set aside output storage for images up to `max_width` × `max_height`
+    /// decoded from `bit_depth`-bit sources.
     ///
-    /// If the decoder buffers data internally (returns `Ok(None)`), flushes to retrieve
-    /// the composed frame. Always flushes afterward to reset state, so sequential calls
-    /// (e.g. primary then alpha) work without the caller needing to manage decoder state.
+    /// The memory is allocated and touched now, so the next decode converts
+    /// into it instead of stalling on a large allocation and page faults.
+    /// Meant for interactive viewers that know their largest canvas. The
+    /// reservation covers RGBA output and is consumed by the first decode
+    /// that fits; call again to re-arm it.
+    ///
+    /// Fails with [`Error::OutOfMemory`] if the storage cannot be allocated.
+    pub fn preallocate(&mut self, max_width: u32, max_height: u32, bit_depth: u8) -> Result<()> {
+        // RGBA, rounded to a whole number of both RGB and RGBA pixels.
+        let samples = (max_width as usize)
+            .checked_mul(max_height as usize)
+            .and_then(|n| n.checked_mul(4))
+            .map(|n| n.next_multiple_of(12))
+            .ok_or_else(|| at!(Error::OutOfMemory))?;
+        let reserved = self
+            .reserved
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        if bit_depth > 8 {
+            reserve_touched(&mut reserved.samples16, samples)
+        } else {
+            reserve_touched(&mut reserved.samples8, samples)
+        }
+    }
+
+    /// Output buffer of `len` pixels set to `fill`, built in storage from
+    /// [`Self::preallocate`] when available.
+    fn output_buffer<P: Pod>(&self, len: usize, fill: P) -> Vec<P> {
+        let mut reserved = self.reserved.lock().unwrap_or_else(PoisonError::into_inner);
+        if align_of::<P>() == 1 {
+            take_reserved(&mut reserved.samples8, len, fill)
+        } else {
+            take_reserved(&mut reserved.samples16, len, fill)
+        }
+    }
+
+    /// Report decode stages to `callback` as they start.
+    ///
+    /// Covers the decode calls on this decoder: stills, grid tiles and
+    /// animation frames. Replaces any earlier callback. The callback runs on
+    /// the decoding thread, so keep it cheap, e.g. a channel send.
+    pub fn on_progress(&mut self, callback: impl FnMut(ProgressEvent) + Send + 'a) {
+        *self
+            .progress
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = Some(Box::new(callback));
+    }
+
+    /// Pass `event` to the [`on_progress`](Self::on_progress) callback.
+    fn report(&self, event: ProgressEvent) {
+        let mut progress = self.progress.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(callback) = progress.as_mut() {
+            callback(event);
+        }
+    }
+
+    /// Decode the primary item and, if there is one, its alpha item.
+    ///
+    /// Unless the thread budget is a single thread, alpha decodes on a
+    /// scoped thread with its own single-threaded decoder while the primary
+    /// item decodes on this one, so RGBA stills wait for the slower of the
+    /// two instead of both. The primary decoder was built with the rest of
+    /// the budget, so the two together stay within it.
+    fn decode_color_and_alpha(
+        &mut self,
+        stop: &(impl Stop + ?Sized),
+    ) -> Result<(Frame, Option<Frame>)> {
+        let primary_data = self
+            .parser
+            .primary_data()
+            .map_err(|e| at!(Error::from(e)))?;
+        let primary_data = reseed_grain(&primary_data, self.settings.grain_seed);
+        let alpha_data = self
+            .parser
+            .alpha_data()
+            .transpose()
+            .map_err(|e| at!(Error::from(e)))?;
+
+        self.report(ProgressEvent::PrimaryDecode);
+        let Some(alpha_data) = alpha_data else {
+            let primary_frame = Self::decode_frame(
+                &mut self.decoder,
+                &primary_data,
+                self.settings.all_layers,
+                "Failed to decode primary frame",
+            )?;
+            return Ok((primary_frame, None));
+        };
+
+        if self.settings.thread_budget() == 1 {
+            let primary_frame = Self::decode_frame(
+                &mut self.decoder,
+                &primary_data,
+                self.settings.all_layers,
+                "Failed to decode primary frame",
+            )?;
+            stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+            self.report(ProgressEvent::AlphaDecode);
+            let alpha_frame = Self::decode_frame(
+                &mut self.decoder,
+                &alpha_data,
+                self.settings.all_layers,
+                "Failed to decode alpha frame",
+            )?;
+            return Ok((primary_frame, Some(alpha_frame)));
+        }
+
+        self.report(ProgressEvent::AlphaDecode);
+        let mut alpha_decoder = DecoderSettings {
+            threads: 1,
+            ..self.settings
+        }
+        .create()?;
+        let all_layers = self.settings.all_layers;
+        let decoder = &mut self.decoder;
+        let (primary_frame, alpha_frame) = std::thread::scope(|scope| {
+            let alpha = scope.spawn(|| {
+                Self::decode_frame(
+                    &mut alpha_decoder,
+                    &alpha_data,
+                    all_layers,
+                    "Failed to decode alpha frame",
+                )
+            });
+            let primary = Self::decode_frame(
+                decoder,
+                &primary_data,
+                all_layers,
+                "Failed to decode primary frame",
+            );
+            let alpha = alpha
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            (primary, alpha)
+        });
+        Ok((primary_frame?, Some(alpha_frame?)))
+    }
+
+    /// Decode a single AV1 frame, picking among the layers the decoder
+    /// outputs by [`DecoderConfig::all_layers`].
+    ///
+    /// Without `all_layers` the decoder only outputs the highest spatial
+    /// layer of the operating point, and the first frame out is the result;
+    /// frames after it, such as the rest of a show-existing-frame chain, are
+    /// dropped. With `all_layers` every layer comes out and the last one,
+    /// the highest, is the result. If the decoder buffers the data, a flush
+    /// retrieves the frames. Always flushes afterward to reset state, so
+    /// sequential calls (e.g. primary then alpha) work without the caller
+    /// needing to manage decoder state.
     ///
     /// Takes `decoder` explicitly to avoid borrowing `self` (which would conflict
     /// with borrows of `self.parser` for data access).
     fn decode_frame(
         decoder: &mut Rav1dDecoder,
         data: &[u8],
+        all_layers: bool,
         context: &'static str,
     ) -> Result<Frame> {
-        // Send data and try to get a frame immediately
+        let _stage = alloc_stats::enter(Stage::Decode);
         let frame = match decoder.decode(data) {
-            Ok(Some(frame)) => frame,
-            Ok(None) => {
-                // Progressive/multi-layer: flush to get the composed frame
-                let frames = decoder.flush().map_err(|_e| {
+            Ok(Some(frame)) if !all_layers => frame,
+            Ok(first) => {
+                let flushed = decoder.flush().map_err(|_e| {
                     at!(Error::Decode {
                         code: -1,
                         msg: "Failed to flush decoder",
                     })
                 })?;
-                frames.into_iter().last().ok_or_else(|| {
+                let mut frames = first.into_iter().chain(flushed);
+                let frame = if all_layers {
+                    frames.last()
+                } else {
+                    frames.next()
+                };
+                frame.ok_or_else(|| {
                     at!(Error::Decode {
                         code: -1,
                         msg: context,
@@ -230,75 +1243,264 @@ impl ManagedAvifDecoder {
         stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
 
         // Check if this is a grid image (tiled/multi-frame)
-        if self.parser.grid_config().is_some() {
-            return self.decode_grid(stop);
+        if self.grid.is_some() {
+            let mut pixels = self.decode_grid(stop)?;
+            if self.post_process.is_some() || self.deband > 0.0 || self.downscale.is_some() {
+                let mut info = self.probe_info()?;
+                info.failed_tiles = self.failed_tiles.clone();
+                self.describe_output(&mut info, &pixels);
+                self.finish_image(&mut pixels, &info, stop)?;
+            }
+            return self.apply_layout(pixels);
         }
 
-        let primary_data = self
-            .parser
-            .primary_data()
-            .map_err(|e| at!(Error::from(e)))?;
-        let primary_frame = Self::decode_frame(
-            &mut self.decoder,
-            &primary_data,
-            "Failed to decode primary frame",
-        )?;
+        let (primary_frame, alpha_frame) = self.decode_color_and_alpha(stop)?;
 
         stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
 
-        let alpha_frame = if let Some(alpha_result) = self.parser.alpha_data() {
-            let alpha_data = alpha_result.map_err(|e| at!(Error::from(e)))?;
-            Some(Self::decode_frame(
-                &mut self.decoder,
-                &alpha_data,
-                "Failed to decode alpha frame",
-            )?)
-        } else {
-            None
-        };
-
-        stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
-
-        let (pixels, _info) = self.convert_to_image(primary_frame, alpha_frame, stop)?;
-        Ok(pixels)
+        self.report(ProgressEvent::ColorConversion);
+        let (pixels, info) = self.convert_to_image(primary_frame, alpha_frame, stop)?;
+        let mut pixels = self.crop_output(pixels)?;
+        self.finish_image(&mut pixels, &info, stop)?;
+        self.apply_layout(pixels)
     }
 
     /// Decode the primary image and return both pixels and metadata.
     pub fn decode_full(&mut self, stop: &(impl Stop + ?Sized)) -> Result<(PixelBuffer, ImageInfo)> {
+        let (pixels, info) = self.decode_full_rgb(stop)?;
+        Ok((self.apply_layout(pixels)?, info))
+    }
+
+    /// Decode the primary image into `dst` in the configured
+    /// [`OutputLayout`](crate::OutputLayout), with rows `stride` bytes apart.
+    ///
+    /// Covers every layout, including the ones a [`PixelBuffer`] can't
+    /// describe. `dst` must hold `stride * (height - 1) + width * bpp`
+    /// bytes, where `bpp` is [`OutputLayout::bytes_per_pixel`]. Output is
+    /// 8 bits per channel or packed 16-bit pixels, dithered if
+    /// [`DecoderConfig::dither`] is set; [`OutputLayout::Rgb`] writes RGBA for images with
+    /// alpha and RGB otherwise.
+    pub fn decode_into_layout(
+        &mut self,
+        dst: &mut [u8],
+        stride: usize,
+        stop: &(impl Stop + ?Sized),
+    ) -> Result<ImageInfo> {
+        let (pixels, mut info) = self.decode_full_rgb(stop)?;
+        if pixels.descriptor().channel_type() != ChannelType::U8 {
+            info.lossy_conversion = true;
+        }
+        let pixels = reduce_to_8bit(pixels, self.dither);
+        write_layout(&pixels, self.output_layout, self.dither, dst, stride)?;
+        Ok(info)
+    }
+
+    /// Reorder channels for [`DecoderConfig::output_layout`].
+    fn apply_layout(&self, pixels: PixelBuffer) -> Result<PixelBuffer> {
+        match self.output_layout {
+            OutputLayout::Rgb => Ok(pixels),
+            OutputLayout::Bgra8 => to_bgra8(&pixels),
+            OutputLayout::Bgr8
+            | OutputLayout::Argb8
+            | OutputLayout::Abgr8
+            | OutputLayout::Rgb565
+            | OutputLayout::Rgba4444 => Err(at!(Error::Unsupported(
+                "output layout has no PixelBuffer descriptor; use decode_into_layout"
+            ))),
+        }
+    }
+
+    /// [`decode_full`](Self::decode_full) before the output layout is applied.
+    fn decode_full_rgb(&mut self, stop: &(impl Stop + ?Sized)) -> Result<(PixelBuffer, ImageInfo)> {
         stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
 
-        if self.parser.grid_config().is_some() {
-            let pixels = self.decode_grid(stop)?;
-            let info = self.probe_info()?;
+        if self.grid.is_some() {
+            let mut pixels = self.decode_grid(stop)?;
+            let mut info = self.probe_info()?;
+            info.failed_tiles = self.failed_tiles.clone();
+            self.describe_output(&mut info, &pixels);
+            self.finish_image(&mut pixels, &info, stop)?;
             return Ok((pixels, info));
         }
 
-        let primary_data = self
-            .parser
-            .primary_data()
-            .map_err(|e| at!(Error::from(e)))?;
-        let primary_frame = Self::decode_frame(
-            &mut self.decoder,
-            &primary_data,
-            "Failed to decode primary frame",
-        )?;
+        let (primary_frame, alpha_frame) = self.decode_color_and_alpha(stop)?;
 
         stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
 
-        let alpha_frame = if let Some(alpha_result) = self.parser.alpha_data() {
-            let alpha_data = alpha_result.map_err(|e| at!(Error::from(e)))?;
-            Some(Self::decode_frame(
-                &mut self.decoder,
-                &alpha_data,
-                "Failed to decode alpha frame",
-            )?)
+        self.report(ProgressEvent::ColorConversion);
+        let (pixels, info) = self.convert_to_image(primary_frame, alpha_frame, stop)?;
+        let mut pixels = self.crop_output(pixels)?;
+        self.finish_image(&mut pixels, &info, stop)?;
+        Ok((pixels, info))
+    }
+
+    /// Decode the primary image to its YUV planes, skipping RGB conversion.
+    ///
+    /// For video pipelines and custom color management. Samples keep their
+    /// coded bit depth and range; [`DecodedYuv::info`] carries the CICP and
+    /// chroma sampling needed to interpret them. Planes are copied out at
+    /// display size. Grid images fail with [`Error::Unsupported`].
+    pub fn decode_yuv(&mut self, stop: &(impl Stop + ?Sized)) -> Result<DecodedYuv> {
+        stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+        if self.grid.is_some() {
+            return Err(at!(Error::Unsupported(
+                "YUV output is not supported for grid images",
+            )));
+        }
+
+        let (primary_frame, alpha_frame) = self.decode_color_and_alpha(stop)?;
+
+        let info = self.frame_info(&primary_frame, alpha_frame.is_some());
+        let luma = (info.width as usize, info.height as usize);
+        let chroma = match info.chroma_sampling {
+            ChromaSampling::Cs420 => (luma.0.div_ceil(2), luma.1.div_ceil(2)),
+            ChromaSampling::Cs422 => (luma.0.div_ceil(2), luma.1),
+            ChromaSampling::Cs444 | ChromaSampling::Monochrome => luma,
+        };
+        let (y, u, v) = frame_planes(&primary_frame, luma, chroma)?;
+        let alpha = match &alpha_frame {
+            Some(frame) => Some(frame_planes(frame, luma, (0, 0))?.0),
+            None => None,
+        };
+        Ok(DecodedYuv {
+            y,
+            u,
+            v,
+            alpha,
+            info,
+        })
+    }
+
+    /// Decode a 4:2:0 primary image to a luma plane and an interleaved
+    /// chroma plane (NV12 or NV21), skipping RGB conversion.
+    ///
+    /// Chroma is interleaved straight from the decoder's planes. 10/12-bit
+    /// images give `u16` planes with samples in the low bits, as for
+    /// [`decode_yuv`](Self::decode_yuv). Images with other chroma sampling
+    /// and grid images fail with [`Error::Unsupported`].
+    pub fn decode_semi_planar(
+        &mut self,
+        order: SemiPlanarOrder,
+        stop: &(impl Stop + ?Sized),
+    ) -> Result<DecodedSemiPlanar> {
+        stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+        if self.grid.is_some() {
+            return Err(at!(Error::Unsupported(
+                "NV12/NV21 output is not supported for grid images",
+            )));
+        }
+
+        let (primary_frame, alpha_frame) = self.decode_color_and_alpha(stop)?;
+
+        let info = self.frame_info(&primary_frame, alpha_frame.is_some());
+        if info.chroma_sampling != ChromaSampling::Cs420 {
+            return Err(at!(Error::Unsupported(
+                "NV12/NV21 output requires 4:2:0 chroma",
+            )));
+        }
+        let (width, height) = (info.width as usize, info.height as usize);
+        let chroma = (width.div_ceil(2), height.div_ceil(2));
+        let missing = || {
+            at!(Error::Decode {
+                code: -1,
+                msg: "4:2:0 frame without chroma planes",
+            })
+        };
+        let (y, uv) = if let Planes::Depth8(planes) = primary_frame.planes() {
+            let (u, v) = (
+                planes.u().ok_or_else(missing)?,
+                planes.v().ok_or_else(missing)?,
+            );
+            let (first, second) = match order {
+                SemiPlanarOrder::Nv12 => (u.rows(), v.rows()),
+                SemiPlanarOrder::Nv21 => (v.rows(), u.rows()),
+            };
+            (
+                YuvPlane::U8(copy_plane(planes.y().rows(), width, height)?),
+                YuvPlane::U8(interleave_planes(first, second, chroma.0, chroma.1)?),
+            )
         } else {
-            None
+            let Planes::Depth16(planes) = primary_frame.planes() else {
+                return Err(at!(Error::Decode {
+                    code: -1,
+                    msg: "Expected 16-bit planes",
+                }));
+            };
+            let (u, v) = (
+                planes.u().ok_or_else(missing)?,
+                planes.v().ok_or_else(missing)?,
+            );
+            let (first, second) = match order {
+                SemiPlanarOrder::Nv12 => (u.rows(), v.rows()),
+                SemiPlanarOrder::Nv21 => (v.rows(), u.rows()),
+            };
+            (
+                YuvPlane::U16(copy_plane(planes.y().rows(), width, height)?),
+                YuvPlane::U16(interleave_planes(first, second, chroma.0, chroma.1)?),
+            )
         };
+        let alpha = match &alpha_frame {
+            Some(frame) => Some(frame_planes(frame, (width, height), (0, 0))?.0),
+            None => None,
+        };
+        Ok(DecodedSemiPlanar {
+            y,
+            uv,
+            order,
+            alpha,
+            info,
+        })
+    }
 
+    /// Decode the primary image to separate R, G, B (and alpha) planes.
+    ///
+    /// Converts straight from the YUV planes of [`decode_yuv`](Self::decode_yuv)
+    /// into [`PlanarPixels`], so no interleaved image is built; `options`
+    /// normalization and layout are applied in the same pass. Chroma is
+    /// replicated rather than interpolated. Crop, downscaling, debanding,
+    /// post-processing and color management are not applied. Grid images
+    /// fail with [`Error::Unsupported`].
+    pub fn decode_planar(
+        &mut self,
+        options: &PlanarOptions,
+        stop: &(impl Stop + ?Sized),
+    ) -> Result<PlanarPixels> {
+        let yuv = self.decode_yuv(stop)?;
         stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+        Ok(planar::from_yuv(yuv, options))
+    }
+
+    /// Cut the configured crop region out of a converted still image.
+    fn crop_output(&self, pixels: PixelBuffer) -> Result<PixelBuffer> {
+        match self.crop {
+            Some(rect) => pixels.crop(rect),
+            None => Ok(pixels),
+        }
+    }
 
-        self.convert_to_image(primary_frame, alpha_frame, stop)
+    /// Apply the post-decode downscale, debanding and the post-processing hook,
+    /// if configured.
+    fn finish_image(
+        &self,
+        pixels: &mut PixelBuffer,
+        info: &ImageInfo,
+        stop: &(impl Stop + ?Sized),
+    ) -> Result<()> {
+        if let Some((width, height)) = self.downscale {
+            stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+            if let Some(reduced) = reduce_to_fit(pixels, width, height)? {
+                *pixels = reduced;
+            }
+        }
+        if self.deband > 0.0 {
+            stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+            deband(pixels, self.deband);
+        }
+        if let Some(PostProcess(hook)) = &self.post_process {
+            stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+            hook(pixels, info).map_err(|e| at!(e))?;
+        }
+        Ok(())
     }
 
     /// Decode frames and return a StripConverter for cache-optimal streaming.
@@ -316,28 +1518,7 @@ impl ManagedAvifDecoder {
     ) -> Result<(crate::strip_convert::StripConverter, ImageInfo)> {
         stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
 
-        let primary_data = self
-            .parser
-            .primary_data()
-            .map_err(|e| at!(Error::from(e)))?;
-        let primary_frame = Self::decode_frame(
-            &mut self.decoder,
-            &primary_data,
-            "Failed to decode primary frame",
-        )?;
-
-        stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
-
-        let alpha_frame = if let Some(alpha_result) = self.parser.alpha_data() {
-            let alpha_data = alpha_result.map_err(|e| at!(Error::from(e)))?;
-            Some(Self::decode_frame(
-                &mut self.decoder,
-                &alpha_data,
-                "Failed to decode alpha frame",
-            )?)
-        } else {
-            None
-        };
+        let (primary_frame, alpha_frame) = self.decode_color_and_alpha(stop)?;
 
         stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
 
@@ -354,7 +1535,8 @@ impl ManagedAvifDecoder {
         let can_strip = bit_depth == 8
             && !matches!(chroma_sampling, ChromaSampling::Monochrome)
             && buffer_width == display_width
-            && buffer_height == display_height;
+            && buffer_height == display_height
+            && self.output_range == OutputRange::Full;
 
         let converter = if can_strip {
             let alpha_range = alpha_frame
@@ -372,6 +1554,7 @@ impl ManagedAvifDecoder {
                 primary_frame,
                 alpha_frame,
                 chroma_sampling,
+                info.chroma_sample_position,
                 to_our_yuv_range(info.color_range),
                 to_our_yuv_matrix(info.matrix_coefficients),
                 alpha_range,
@@ -383,7 +1566,8 @@ impl ManagedAvifDecoder {
                 descriptor,
             )
         } else {
-            // Fallback: full conversion for 16-bit, monochrome, or cropped images
+            // Fallback: full conversion for 16-bit, monochrome, cropped or
+            // video-range images
             let (pixels, _) = self.convert_to_image(primary_frame, alpha_frame, stop)?;
             crate::strip_convert::StripConverter::new_from_pixels(pixels)
         };
@@ -391,6 +1575,22 @@ impl ManagedAvifDecoder {
         Ok((converter, info))
     }
 
+    /// Matrix coefficients of the AV1 bitstream, or of the colr box for
+    /// code points rav1d-safe doesn't name (YCgCo among them), which it
+    /// reports as unknown.
+    fn matrix_coefficients(&self, av1: Rav1dMatrixCoefficients) -> MatrixCoefficients {
+        match (convert_matrix(av1), self.parser.color_info()) {
+            (
+                MatrixCoefficients::UNKNOWN,
+                Some(zenavif_parse::ColorInformation::Nclx {
+                    matrix_coefficients: mc,
+                    ..
+                }),
+            ) => MatrixCoefficients(*mc as u8),
+            (mc, _) => mc,
+        }
+    }
+
     /// Build ImageInfo from a decoded primary frame and parser metadata.
     ///
     /// Factored out of `convert_to_image` for reuse by `decode_to_strip_converter`.
@@ -403,7 +1603,7 @@ impl ManagedAvifDecoder {
         let layout = primary.pixel_layout();
 
         let av1_color = primary.color_info();
-        let matrix_coefficients = convert_matrix(av1_color.matrix_coefficients);
+        let matrix_coefficients = self.matrix_coefficients(av1_color.matrix_coefficients);
         let color_range = convert_color_range(av1_color.color_range);
 
         let (color_primaries, transfer_characteristics, icc_profile) =
@@ -441,6 +1641,7 @@ impl ManagedAvifDecoder {
             matrix_coefficients,
             color_range,
             chroma_sampling: convert_chroma_sampling(layout),
+            chroma_sample_position: self.chroma_position,
             icc_profile,
             rotation: self.parser.rotation().cloned(),
             mirror: self.parser.mirror().cloned(),
@@ -448,6 +1649,8 @@ impl ManagedAvifDecoder {
             pixel_aspect_ratio: self.parser.pixel_aspect_ratio().cloned(),
             content_light_level: self.parser.content_light_level().cloned(),
             mastering_display: self.parser.mastering_display().cloned(),
+            hdr10_plus: self.hdr10_plus.clone(),
+            declared_bits_per_channel: self.declared_bits.clone(),
             exif: self
                 .parser
                 .exif()
@@ -459,8 +1662,10 @@ impl ManagedAvifDecoder {
                 .and_then(|r| r.ok())
                 .map(|c| c.into_owned()),
             gain_map: self.extract_gain_map(),
-            // Depth map extraction requires zenavif-parse > 0.4.0 (not yet published).
-            depth_map: None,
+            depth_map: self.depth_map.clone(),
+            failed_tiles: Vec::new(),
+            lossy_conversion: false,
+            alpha_transformed: None,
         })
     }
 
@@ -470,111 +1675,97 @@ impl ManagedAvifDecoder {
     /// dimensions, color info, ICC profile, EXIF, XMP, orientation, and HDR metadata.
     /// Does NOT do full AV1 frame decoding.
     pub fn probe_info(&self) -> Result<ImageInfo> {
-        // Get dimensions from grid config or AV1 sequence header
-        let (width, height) = if let Some(grid) = self.parser.grid_config() {
-            (grid.output_width, grid.output_height)
-        } else {
-            let meta = self
-                .parser
-                .primary_metadata()
-                .map_err(|e| at!(Error::from(e)))?;
-            (meta.max_frame_width.get(), meta.max_frame_height.get())
-        };
+        probe_parser(
+            &self.parser,
+            self.grid.as_ref(),
+            self.declared_bits.clone(),
+            self.hdr10_plus.clone(),
+            self.depth_map.clone(),
+        )
+    }
 
-        let has_alpha = self.parser.alpha_metadata().is_some();
+    /// Grid tiles, as row-major indices, that the last decode filled in
+    /// because they failed to decode.
+    ///
+    /// Always empty unless [`DecoderConfig::error_resilient`] is set.
+    pub fn failed_tiles(&self) -> &[usize] {
+        &self.failed_tiles
+    }
 
-        // AV1 config for bit depth
-        let bit_depth = self.parser.av1_config().map(|c| c.bit_depth).unwrap_or(8);
+    /// Decode an auxiliary image of the primary item as `Gray8` or `Gray16`.
+    ///
+    /// Only the luma plane is kept, at the auxiliary image's own size;
+    /// 10- and 12-bit images are scaled to the full `Gray16` range. Fails
+    /// with [`Error::Unsupported`] if the file has no auxiliary image of
+    /// that kind; [`ImageInfo::has_depth`] tells ahead of time for depth.
+    pub fn decode_auxiliary(
+        &mut self,
+        kind: AuxiliaryType,
+        stop: &(impl Stop + ?Sized),
+    ) -> Result<PixelBuffer> {
+        stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+        let data = match kind {
+            AuxiliaryType::Depth => self.depth_map.as_ref().map(|d| d.data.as_slice()),
+        }
+        .ok_or_else(|| {
+            at!(Error::Unsupported(
+                "image has no auxiliary image of this kind"
+            ))
+        })?;
 
-        // CICP from container (colr box) or AV1 config fallback
-        let (
-            color_primaries,
-            transfer_characteristics,
-            matrix_coefficients,
-            color_range,
-            icc_profile,
-        ) = match self.parser.color_info() {
-            Some(zenavif_parse::ColorInformation::Nclx {
-                color_primaries: cp,
-                transfer_characteristics: tc,
-                matrix_coefficients: mc,
-                full_range,
-            }) => (
-                ColorPrimaries(*cp as u8),
-                TransferCharacteristics(*tc as u8),
-                MatrixCoefficients(*mc as u8),
-                if *full_range {
-                    ColorRange::Full
-                } else {
-                    ColorRange::Limited
-                },
-                None,
-            ),
-            Some(zenavif_parse::ColorInformation::IccProfile(icc)) => (
-                ColorPrimaries::BT709,
-                TransferCharacteristics::SRGB,
-                MatrixCoefficients::BT601,
-                ColorRange::Full,
-                Some(icc.clone()),
-            ),
-            None => (
-                ColorPrimaries::BT709,
-                TransferCharacteristics::SRGB,
-                MatrixCoefficients::BT601,
-                ColorRange::Full,
-                None,
-            ),
-        };
+        let mut decoder = self.settings.create()?;
+        let frame = Self::decode_frame(
+            &mut decoder,
+            data,
+            false,
+            "Failed to decode auxiliary image",
+        )?;
+        Self::luma_plane(&frame)
+    }
 
-        let chroma_sampling = self
+    /// Decode only the alpha item, as `Gray8` or `Gray16`.
+    ///
+    /// The color item is neither decoded nor converted, which makes this the
+    /// cheap way to extract a mask or to check whether the alpha channel is
+    /// actually anything but opaque. Samples are at the alpha item's coded
+    /// size, without `clap` cropping, orientation or premultiplication
+    /// undone; 10- and 12-bit alpha is scaled to the full `Gray16` range.
+    /// Fails with [`Error::Unsupported`] if the image has no alpha, and for
+    /// grid images.
+    pub fn decode_alpha_only(&mut self, stop: &(impl Stop + ?Sized)) -> Result<PixelBuffer> {
+        stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+        if self.grid.is_some() {
+            return Err(at!(Error::Unsupported("alpha-only decode of grid images")));
+        }
+        let alpha_data = self
             .parser
-            .av1_config()
-            .map(|c| {
-                if c.monochrome {
-                    ChromaSampling::Monochrome
-                } else if c.chroma_subsampling_x != 0 && c.chroma_subsampling_y != 0 {
-                    ChromaSampling::Cs420
-                } else if c.chroma_subsampling_x != 0 {
-                    ChromaSampling::Cs422
-                } else {
-                    ChromaSampling::Cs444
-                }
-            })
-            .unwrap_or(ChromaSampling::Cs420);
+            .alpha_data()
+            .transpose()
+            .map_err(|e| at!(Error::from(e)))?
+            .ok_or_else(|| at!(Error::Unsupported("image has no alpha channel")))?;
+        let frame = Self::decode_frame(
+            &mut self.decoder,
+            &alpha_data,
+            self.settings.all_layers,
+            "Failed to decode alpha frame",
+        )?;
+        Self::luma_plane(&frame)
+    }
 
-        Ok(ImageInfo {
-            width,
-            height,
-            bit_depth,
-            has_alpha,
-            premultiplied_alpha: self.parser.premultiplied_alpha(),
-            monochrome: chroma_sampling == ChromaSampling::Monochrome,
-            color_primaries,
-            transfer_characteristics,
-            matrix_coefficients,
-            color_range,
-            chroma_sampling,
-            icc_profile,
-            rotation: self.parser.rotation().cloned(),
-            mirror: self.parser.mirror().cloned(),
-            clean_aperture: self.parser.clean_aperture().cloned(),
-            pixel_aspect_ratio: self.parser.pixel_aspect_ratio().cloned(),
-            content_light_level: self.parser.content_light_level().cloned(),
-            mastering_display: self.parser.mastering_display().cloned(),
-            exif: self
-                .parser
-                .exif()
-                .and_then(|r| r.ok())
-                .map(|c| c.into_owned()),
-            xmp: self
-                .parser
-                .xmp()
-                .and_then(|r| r.ok())
-                .map(|c| c.into_owned()),
-            gain_map: self.extract_gain_map(),
-            // Depth map extraction requires zenavif-parse > 0.4.0 (not yet published).
-            depth_map: None,
-        })
+    /// The luma plane of `frame` at its own size, expanded to full range.
+    fn luma_plane(frame: &Frame) -> Result<PixelBuffer> {
+        let range = convert_color_range(frame.color_info().color_range);
+        let (width, height) = (frame.width() as usize, frame.height() as usize);
+        if let Planes::Depth8(planes) = frame.planes() {
+            return luma_to_gray8(planes.y().rows(), width, height, range);
+        }
+        let Planes::Depth16(planes) = frame.planes() else {
+            return Err(at!(Error::Decode {
+                code: -1,
+                msg: "Expected 16-bit planes",
+            }));
+        };
+        luma_to_gray16(planes.y().rows(), width, height, range, frame.bit_depth())
     }
 
     /// Decode an animated AVIF, returning all frames with timing info.
@@ -610,36 +1801,34 @@ impl ManagedAvifDecoder {
             None
         };
 
-        let frame_count = anim_info.frame_count;
-        let mut frames = Vec::with_capacity(frame_count);
-
-        for i in 0..frame_count {
-            stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
-
-            let frame_ref = self.parser.frame(i).map_err(|e| at!(Error::from(e)))?;
-
-            let primary_frame = Self::decode_anim_frame(
-                &mut self.decoder,
-                &frame_ref.data,
-                "Failed to decode animation frame",
-            )?;
-
-            let alpha_frame = match (&mut alpha_decoder, &frame_ref.alpha_data) {
-                (Some(dec), Some(alpha_data)) => Some(Self::decode_anim_frame(
-                    dec,
-                    alpha_data,
-                    "Failed to decode animation alpha frame",
-                )?),
-                _ => None,
-            };
+        let frame_count = self.limited_frame_count(anim_info.frame_count)?;
+        let frames = if self.pipelined_animation && frame_count > 1 {
+            self.decode_animation_pipelined(frame_count, alpha_decoder, stop)?
+        } else {
+            let mut frames = Vec::with_capacity(frame_count);
+            for i in 0..frame_count {
+                stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+                self.report(ProgressEvent::Frame {
+                    index: i,
+                    count: frame_count,
+                });
+
+                let (primary_frame, alpha_frame, duration_ms) = Self::decode_anim_frames(
+                    &self.parser,
+                    i,
+                    &mut self.decoder,
+                    alpha_decoder.as_mut(),
+                )?;
 
-            let (pixels, _info) = self.convert_to_image(primary_frame, alpha_frame, stop)?;
+                let (pixels, _info) = self.convert_to_image(primary_frame, alpha_frame, stop)?;
 
-            frames.push(DecodedFrame {
-                pixels,
-                duration_ms: frame_ref.duration_ms,
-            });
-        }
+                frames.push(DecodedFrame {
+                    pixels,
+                    duration_ms: self.animation_limits.frame_duration(duration_ms),
+                });
+            }
+            frames
+        };
 
         Ok(DecodedAnimation {
             frames,
@@ -652,6 +1841,142 @@ impl ManagedAvifDecoder {
         })
     }
 
+    /// Number of frames to decode out of `frame_count`, under the
+    /// configured [`AnimationLimits`].
+    ///
+    /// Reads only frame durations from the container, so bombs are refused
+    /// before any decode work.
+    fn limited_frame_count(&self, frame_count: usize) -> Result<usize> {
+        let limits = self.animation_limits;
+        let mut allowed = frame_count;
+        let mut reason = None;
+        if let Some(max) = limits.max_frames
+            && frame_count > max as usize
+        {
+            allowed = max as usize;
+            reason = Some(format!(
+                "animation has {frame_count} frames, limit is {max}"
+            ));
+        }
+        let checks_durations =
+            limits.max_total_duration_ms.is_some() || limits.max_frame_duration_ms.is_some();
+        // Under `Fail` an exceeded frame count already decides the outcome.
+        if checks_durations && (reason.is_none() || limits.policy == AnimationLimitPolicy::Truncate)
+        {
+            let mut total_ms = 0u64;
+            for i in 0..allowed {
+                let raw_ms = self
+                    .parser
+                    .frame(i)
+                    .map_err(|e| at!(Error::from(e)))?
+                    .duration_ms;
+                if let Some(max) = limits.max_frame_duration_ms
+                    && raw_ms > max
+                    && limits.policy == AnimationLimitPolicy::Fail
+                {
+                    reason = Some(format!("frame {i} lasts {raw_ms} ms, limit is {max} ms"));
+                    break;
+                }
+                total_ms += u64::from(limits.frame_duration(raw_ms));
+                if let Some(max_ms) = limits.max_total_duration_ms
+                    && total_ms > max_ms
+                {
+                    reason.get_or_insert_with(|| {
+                        format!(
+                            "animation runs over {max_ms} ms within its first {} frames",
+                            i + 1
+                        )
+                    });
+                    allowed = i;
+                    break;
+                }
+            }
+        }
+        match (reason, limits.policy) {
+            (Some(reason), AnimationLimitPolicy::Fail) => Err(at!(Error::ResourceLimit(reason))),
+            _ => Ok(allowed),
+        }
+    }
+
+    /// Decode animation frames on a helper thread while this one converts
+    /// them to RGB.
+    ///
+    /// The helper uses a decoder of its own, so `self` is only borrowed
+    /// shared. A failure on either side ends both: the helper stops after
+    /// sending its error, and dropping the receiver makes its next send fail.
+    fn decode_animation_pipelined(
+        &self,
+        frame_count: usize,
+        mut alpha_decoder: Option<Rav1dDecoder>,
+        stop: &(impl Stop + ?Sized),
+    ) -> Result<Vec<DecodedFrame>> {
+        let mut decoder = self.settings.create()?;
+        let parser = &self.parser;
+        let (tx, rx) = std::sync::mpsc::sync_channel::<Result<DecodedAnimFrame>>(PIPELINE_DEPTH);
+
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                for i in 0..frame_count {
+                    let decoded = match stop.check() {
+                        Ok(()) => Self::decode_anim_frames(
+                            parser,
+                            i,
+                            &mut decoder,
+                            alpha_decoder.as_mut(),
+                        ),
+                        Err(e) => Err(at!(Error::Cancelled(e))),
+                    };
+                    let failed = decoded.is_err();
+                    if tx.send(decoded).is_err() || failed {
+                        break;
+                    }
+                }
+            });
+
+            // `rx` is moved in here so an early return drops it and unblocks
+            // the helper before the scope joins it.
+            let mut frames = Vec::with_capacity(frame_count);
+            for decoded in rx {
+                let (primary_frame, alpha_frame, duration_ms) = decoded?;
+                self.report(ProgressEvent::Frame {
+                    index: frames.len(),
+                    count: frame_count,
+                });
+                let (pixels, _info) = self.convert_to_image(primary_frame, alpha_frame, stop)?;
+                frames.push(DecodedFrame {
+                    pixels,
+                    duration_ms: self.animation_limits.frame_duration(duration_ms),
+                });
+            }
+            Ok(frames)
+        })
+    }
+
+    /// Decode frame `i` of the color track and, if there is an alpha
+    /// decoder, of the alpha track.
+    fn decode_anim_frames(
+        parser: &zenavif_parse::AvifParser<'_>,
+        i: usize,
+        decoder: &mut Rav1dDecoder,
+        alpha_decoder: Option<&mut Rav1dDecoder>,
+    ) -> Result<DecodedAnimFrame> {
+        let frame_ref = parser.frame(i).map_err(|e| at!(Error::from(e)))?;
+
+        let primary_frame =
+            Self::decode_anim_frame(decoder, &frame_ref.data, "Failed to decode animation frame")?;
+
+        let alpha_frame = match (alpha_decoder, &frame_ref.alpha_data) {
+            (Some(dec), Some(alpha_data)) => Some(Self::decode_anim_frame(
+                dec,
+                alpha_data,
+                "Failed to decode animation alpha frame",
+            )?),
+            _ => None,
+        };
+
+        Ok((primary_frame, alpha_frame, frame_ref.duration_ms))
+    }
+
     /// Decode a single frame within an animation sequence.
     ///
     /// Unlike [`decode_frame`], this does NOT flush the decoder, preserving
@@ -661,6 +1986,7 @@ impl ManagedAvifDecoder {
         data: &[u8],
         context: &'static str,
     ) -> Result<Frame> {
+        let _stage = alloc_stats::enter(Stage::Decode);
         match decoder.decode(data) {
             Ok(Some(frame)) => return Ok(frame),
             Ok(None) => {}
@@ -689,173 +2015,376 @@ impl ManagedAvifDecoder {
 
     /// Decode a grid-based AVIF (tiled image)
     fn decode_grid(&mut self, stop: &(impl Stop + ?Sized)) -> Result<PixelBuffer> {
-        let grid_config = self
-            .parser
-            .grid_config()
-            .ok_or_else(|| {
-                at!(Error::Decode {
-                    code: -1,
-                    msg: "Expected grid config but found none",
-                })
-            })?
-            .clone();
+        let grid_config = self.grid.clone().ok_or_else(|| {
+            at!(Error::Decode {
+                code: -1,
+                msg: "Expected grid config but found none",
+            })
+        })?;
+
+        self.failed_tiles.clear();
+        if let Some(crop) = self.crop {
+            return self.decode_grid_region(&grid_config, crop, stop);
+        }
+
+        let rows = grid_config.rows as usize;
+        let cols = grid_config.columns as usize;
+        let tile_count = self.parser.grid_tile_count();
+        if tile_count == 0 {
+            return Err(at!(Error::Decode {
+                code: -1,
+                msg: "No tiles to stitch",
+            }));
+        }
+        if tile_count != rows * cols {
+            return Err(at!(Error::Decode {
+                code: -1,
+                msg: "Tile count doesn't match grid dimensions",
+            }));
+        }
 
-        // Decode all tiles
-        let mut tile_frames = Vec::new();
-        for i in 0..self.parser.grid_tile_count() {
+        // Tiles are decoded one at a time and written into the output as
+        // they arrive, so only one decoded tile is alive at once.
+        let mut output: Option<PixelBuffer> = None;
+        let mut failed = Vec::new();
+        let mut first_error = None;
+        let (mut tile_width, mut tile_height) = (0, 0);
+        for i in 0..tile_count {
             stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+            self.report(ProgressEvent::Tile {
+                index: i,
+                count: tile_count,
+            });
+
+            let frame = match self.decode_tile(i) {
+                Ok(frame) => frame,
+                Err(e) if self.error_resilient => {
+                    failed.push(i);
+                    first_error.get_or_insert(e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            (tile_width, tile_height) = (frame.width(), frame.height());
+            let pos = (
+                (i % cols) as u32 * tile_width,
+                (i / cols) as u32 * tile_height,
+            );
+            let output_width = if grid_config.output_width > 0 {
+                grid_config.output_width
+            } else {
+                tile_width * cols as u32
+            };
+            let output_height = if grid_config.output_height > 0 {
+                grid_config.output_height
+            } else {
+                tile_height * rows as u32
+            };
+
+            // Color management and range compression run in
+            // `convert_to_image`, so tiles that need them take the slower path.
+            if frame.bit_depth() == 8
+                && !matches!(frame.pixel_layout(), PixelLayout::I400)
+                && !self.color_managed()
+                && self.output_range == OutputRange::Full
+            {
+                let output = match &mut output {
+                    Some(output) => output,
+                    slot => {
+                        let pixels = self.output_buffer(
+                            output_width as usize * output_height as usize,
+                            Rgb { r: 0u8, g: 0, b: 0 },
+                        );
+                        let buffer = PixelBuffer::from_pixels(pixels, output_width, output_height)
+                            .map_err(|_| at!(Error::OutOfMemory))?;
+                        slot.insert(buffer.into())
+                    }
+                };
+                Self::convert_tile_into(&frame, output, pos, self.chroma_position)?;
+            } else {
+                let (tile, _info) = self.convert_to_image(frame, None, stop)?;
+                let output = match &mut output {
+                    Some(output) => output,
+                    slot => slot.insert(new_buffer(output_width, output_height, &tile)?),
+                };
+                output.blit(&tile, pos)?;
+            }
+        }
 
-            let tile_data = self.parser.tile_data(i).map_err(|e| at!(Error::from(e)))?;
-            let frame =
-                Self::decode_frame(&mut self.decoder, &tile_data, "Failed to decode grid tile")?;
+        let Some(mut output) = output else {
+            return Err(first_error.unwrap_or_else(|| {
+                at!(Error::Decode {
+                    code: -1,
+                    msg: "No tiles to stitch",
+                })
+            }));
+        };
+        let holes: Vec<Rect> = failed
+            .iter()
+            .map(|&i| {
+                Rect::new(
+                    (i % cols) as u32 * tile_width,
+                    (i / cols) as u32 * tile_height,
+                    tile_width,
+                    tile_height,
+                )
+                .clip(output.width(), output.height())
+            })
+            .collect();
+        fill_failed_tiles(&mut output, &holes, self.tile_fill);
+        self.failed_tiles = failed;
+        Ok(output)
+    }
 
-            tile_frames.push(frame);
+    /// Decode grid tile `index` into a frame.
+    ///
+    /// A failed decode leaves the decoder flushed, so the next tile starts
+    /// clean.
+    fn decode_tile(&mut self, index: usize) -> Result<Frame> {
+        let tile_data = self
+            .parser
+            .tile_data(index)
+            .map_err(|e| at!(Error::from(e)))?;
+        let tile_data = reseed_grain(&tile_data, self.settings.grain_seed);
+        let frame = Self::decode_frame(
+            &mut self.decoder,
+            &tile_data,
+            self.settings.all_layers,
+            "Failed to decode grid tile",
+        );
+        if frame.is_err() {
+            let _ = self.decoder.flush();
         }
+        frame
+    }
 
-        stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+    /// Convert an 8-bit color tile straight into the RGB8 `output` with its
+    /// top-left corner at `pos`, one row at a time.
+    ///
+    /// The strip converters reproduce the whole-frame conversion of
+    /// [`convert_to_image`](Self::convert_to_image) row for row, so this
+    /// matches converting the tile on its own and copying it over, without
+    /// the tile-sized intermediate. Whatever lies past the output's right
+    /// or bottom edge is dropped. 4:2:0 chroma is placed per `siting`.
+    fn convert_tile_into(
+        frame: &Frame,
+        output: &mut PixelBuffer,
+        pos: (u32, u32),
+        siting: ChromaSamplePosition,
+    ) -> Result<()> {
+        let Planes::Depth8(planes) = frame.planes() else {
+            return Err(at!(Error::Decode {
+                code: -1,
+                msg: "Expected 8-bit planes",
+            }));
+        };
+        let (Some(u_view), Some(v_view)) = (planes.u(), planes.v()) else {
+            return Err(at!(Error::Decode {
+                code: -1,
+                msg: "Missing chroma planes",
+            }));
+        };
+        let y_view = planes.y();
+        let (y_plane, y_stride) = (y_view.as_slice(), y_view.stride());
+        let (u_plane, u_stride) = (u_view.as_slice(), u_view.stride());
+        let (v_plane, v_stride) = (v_view.as_slice(), v_view.stride());
+
+        let color = frame.color_info();
+        let range = to_our_yuv_range(convert_color_range(color.color_range));
+        let matrix = to_our_yuv_matrix(convert_matrix(color.matrix_coefficients));
+        let sampling = convert_chroma_sampling(frame.pixel_layout());
+        let (width, height) = (frame.width() as usize, frame.height() as usize);
+        let (x, y) = (pos.0 as usize, pos.1 as usize);
+        let visible_width = width.min((output.width() as usize).saturating_sub(x));
+        let visible_height = height.min((output.height() as usize).saturating_sub(y));
+
+        // Rows cut off on the right are converted in full, then trimmed,
+        // so chroma at the cut matches the tile converted on its own.
+        let mut scratch = Vec::new();
+        if visible_width < width {
+            scratch
+                .try_reserve_exact(width)
+                .map_err(|_| at!(Error::OutOfMemory))?;
+            scratch.resize(width, Rgb { r: 0u8, g: 0, b: 0 });
+        }
 
-        // Stitch tiles together
-        self.stitch_tiles(tile_frames, &grid_config, stop)
+        let mut dst = output.as_slice_mut();
+        for row in 0..visible_height {
+            let dst_row: &mut [Rgb<u8>] = bytemuck::cast_slice_mut(
+                &mut dst.row_mut((y + row) as u32)[x * 3..(x + visible_width) * 3],
+            );
+            let out = if scratch.is_empty() {
+                &mut *dst_row
+            } else {
+                &mut scratch[..]
+            };
+            match sampling {
+                ChromaSampling::Cs420 => yuv_convert::yuv420_strip(
+                    y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, height, row, 1,
+                    range, matrix, siting, out,
+                ),
+                ChromaSampling::Cs422 => yuv_convert::yuv422_to_rgb8_strip(
+                    y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, row, 1, range,
+                    matrix, out,
+                ),
+                ChromaSampling::Cs444 => yuv_convert::yuv444_to_rgb8_strip(
+                    y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, row, 1, range,
+                    matrix, out,
+                ),
+                ChromaSampling::Monochrome => {
+                    return Err(at!(Error::Decode {
+                        code: -1,
+                        msg: "Monochrome should not reach chroma conversion",
+                    }));
+                }
+            }
+            if !scratch.is_empty() {
+                dst_row.copy_from_slice(&scratch[..visible_width]);
+            }
+        }
+        Ok(())
     }
 
-    /// Stitch decoded tile frames into a single image
-    fn stitch_tiles(
-        &self,
-        tiles: Vec<Frame>,
+    /// Decode only the grid tiles that overlap `crop` and assemble the
+    /// cropped region from them.
+    ///
+    /// Tile size comes from the first tile's sequence header, so tiles
+    /// outside the region are never decoded.
+    fn decode_grid_region(
+        &mut self,
         grid_config: &zenavif_parse::GridConfig,
+        crop: Rect,
         stop: &(impl Stop + ?Sized),
     ) -> Result<PixelBuffer> {
-        if tiles.is_empty() {
+        let rows = grid_config.rows as u32;
+        let cols = grid_config.columns as u32;
+        if self.parser.grid_tile_count() != (rows * cols) as usize || rows == 0 || cols == 0 {
             return Err(at!(Error::Decode {
                 code: -1,
-                msg: "No tiles to stitch",
+                msg: "Tile count doesn't match grid dimensions",
             }));
         }
 
-        let rows = grid_config.rows as usize;
-        let cols = grid_config.columns as usize;
-
-        if tiles.len() != rows * cols {
+        let first_tile = self.parser.tile_data(0).map_err(|e| at!(Error::from(e)))?;
+        let mut seq = None;
+        let _ = av1_header::is_sync_sample(&first_tile, &mut seq);
+        let Some(seq) = seq else {
             return Err(at!(Error::Decode {
                 code: -1,
-                msg: "Tile count doesn't match grid dimensions",
+                msg: "Grid tile has no sequence header",
             }));
-        }
-
-        // Get dimensions from first tile (all tiles should be same size)
-        let tile_width = tiles[0].width() as usize;
-        let tile_height = tiles[0].height() as usize;
+        };
+        let (tile_width, tile_height) = (seq.max_frame_width, seq.max_frame_height);
 
-        // Calculate output dimensions
         let output_width = if grid_config.output_width > 0 {
-            grid_config.output_width as usize
+            grid_config.output_width
         } else {
             tile_width * cols
         };
         let output_height = if grid_config.output_height > 0 {
-            grid_config.output_height as usize
+            grid_config.output_height
         } else {
             tile_height * rows
         };
-
-        // Convert each tile to RGB/RGBA
-        let mut tile_images = Vec::new();
-        for tile in tiles {
-            let (img, _info) = self.convert_to_image(tile, None, stop)?;
-            tile_images.push(img);
+        let crop = crop.clip(output_width, output_height);
+        if crop.width == 0 || crop.height == 0 {
+            return Err(at!(Error::Unsupported(
+                "crop rectangle lies outside the image"
+            )));
         }
 
-        stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
-
-        // Stitch tiles using byte-level row access (format-agnostic)
-        let descriptor = tile_images[0].descriptor();
-        let bpp = descriptor.bytes_per_pixel();
-        let alloc_size = output_width
-            .checked_mul(output_height)
-            .and_then(|n| n.checked_mul(bpp))
-            .ok_or_else(|| at!(Error::OutOfMemory))?;
-        let data = vec![0u8; alloc_size];
-        let mut output =
-            PixelBuffer::from_vec(data, output_width as u32, output_height as u32, descriptor)
-                .map_err(|_| {
-                    at!(Error::Decode {
-                        code: -1,
-                        msg: "failed to create output buffer for grid stitch",
-                    })
-                })?;
-
-        for (tile_idx, tile) in tile_images.iter().enumerate() {
-            let row = tile_idx / cols;
-            let col = tile_idx % cols;
-            let tile_w = tile.width() as usize;
-            let tile_h = tile.height() as usize;
-            let dst_x = col * tile_w;
-            let dst_y = row * tile_h;
-
-            let tile_slice = tile.as_slice();
-            let mut out_slice = output.as_slice_mut();
-            for y in 0..tile_h.min(output_height - dst_y) {
-                let src = tile_slice.row(y as u32);
-                let copy_w = tile_w.min(output_width - dst_x);
-                let copy_bytes = copy_w * bpp;
-                let dst_row = out_slice.row_mut((dst_y + y) as u32);
-                let dst_start = dst_x * bpp;
-                dst_row[dst_start..dst_start + copy_bytes].copy_from_slice(&src[..copy_bytes]);
+        let first_col = crop.x / tile_width;
+        let last_col = ((crop.x + crop.width - 1) / tile_width).min(cols - 1);
+        let first_row = crop.y / tile_height;
+        let last_row = ((crop.y + crop.height - 1) / tile_height).min(rows - 1);
+
+        let mut output: Option<PixelBuffer> = None;
+        let mut failed = Vec::new();
+        let mut holes = Vec::new();
+        let mut first_error = None;
+        let tile_count = ((last_row - first_row + 1) * (last_col - first_col + 1)) as usize;
+        let mut started = 0;
+        for row in first_row..=last_row {
+            for col in first_col..=last_col {
+                stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+                self.report(ProgressEvent::Tile {
+                    index: started,
+                    count: tile_count,
+                });
+                started += 1;
+
+                let index = (row * cols + col) as usize;
+                let (tile_x, tile_y) = (col * tile_width, row * tile_height);
+                let frame = match self.decode_tile(index) {
+                    Ok(frame) => frame,
+                    Err(e) if self.error_resilient => {
+                        // The hole, in output coordinates.
+                        let x0 = tile_x.max(crop.x);
+                        let y0 = tile_y.max(crop.y);
+                        let x1 = (tile_x + tile_width).min(crop.x + crop.width);
+                        let y1 = (tile_y + tile_height).min(crop.y + crop.height);
+                        holes.push(Rect::new(
+                            x0 - crop.x,
+                            y0 - crop.y,
+                            x1.saturating_sub(x0),
+                            y1.saturating_sub(y0),
+                        ));
+                        failed.push(index);
+                        first_error.get_or_insert(e);
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                };
+                let (tile, _info) = self.convert_to_image(frame, None, stop)?;
+
+                // Overlap of this tile with the crop, in image coordinates.
+                let x0 = tile_x.max(crop.x);
+                let y0 = tile_y.max(crop.y);
+                let x1 = (tile_x + tile.width()).min(crop.x + crop.width);
+                let y1 = (tile_y + tile.height()).min(crop.y + crop.height);
+                if x1 <= x0 || y1 <= y0 {
+                    continue;
+                }
+                let part = tile.crop(Rect::new(x0 - tile_x, y0 - tile_y, x1 - x0, y1 - y0))?;
+                let output = match &mut output {
+                    Some(output) => output,
+                    slot => slot.insert(new_buffer(crop.width, crop.height, &tile)?),
+                };
+                output.blit(&part, (x0 - crop.x, y0 - crop.y))?;
             }
         }
 
+        let Some(mut output) = output else {
+            return Err(first_error.unwrap_or_else(|| {
+                at!(Error::Decode {
+                    code: -1,
+                    msg: "No tiles to stitch",
+                })
+            }));
+        };
+        fill_failed_tiles(&mut output, &holes, self.tile_fill);
+        self.failed_tiles = failed;
         Ok(output)
     }
 
-    /// Crop an image to the specified dimensions
-    fn crop_image(image: PixelBuffer, width: usize, height: usize) -> Result<PixelBuffer> {
-        let descriptor = image.descriptor();
-        let bpp = descriptor.bytes_per_pixel();
-        let src_w = image.width() as usize;
-        let src_h = image.height() as usize;
-        let copy_w = width.min(src_w);
-        let copy_bytes = copy_w * bpp;
-
-        let alloc_size = width
-            .checked_mul(height)
-            .and_then(|n| n.checked_mul(bpp))
-            .ok_or_else(|| at!(Error::OutOfMemory))?;
-        let mut data = vec![0u8; alloc_size];
-        let src = image.as_slice();
-        for y in 0..height.min(src_h) {
-            let src_row = src.row(y as u32);
-            let dst_start = y * width * bpp;
-            data[dst_start..dst_start + copy_bytes].copy_from_slice(&src_row[..copy_bytes]);
-        }
-
-        PixelBuffer::from_vec(data, width as u32, height as u32, descriptor).map_err(|_| {
-            at!(Error::Decode {
-                code: -1,
-                msg: "failed to create cropped buffer",
-            })
-        })
-    }
-
-    fn convert_to_image(
-        &self,
-        primary: Frame,
-        alpha: Option<Frame>,
-        stop: &(impl Stop + ?Sized),
-    ) -> Result<(PixelBuffer, ImageInfo)> {
+    /// Metadata of a decoded primary frame, combining the AV1 color config
+    /// with the container properties.
+    fn frame_info(&self, primary: &Frame, has_alpha: bool) -> ImageInfo {
         let width = primary.width() as usize;
         let height = primary.height() as usize;
         let bit_depth = primary.bit_depth();
         let layout = primary.pixel_layout();
 
         let av1_color = primary.color_info();
-        let has_alpha = alpha.is_some();
 
         // CICP precedence (per MIAF ISO 23000-22 Amd 2):
         //   container colr box > AV1 bitstream > AVIF defaults (1/13/6/full)
         //
         // Matrix coefficients and color range always come from AV1 bitstream
         // because they govern YUV→RGB conversion before any ICC profile applies.
-        let matrix_coefficients = convert_matrix(av1_color.matrix_coefficients);
+        // Only code points rav1d-safe can't name fall back to the colr box.
+        let matrix_coefficients = self.matrix_coefficients(av1_color.matrix_coefficients);
         let color_range = convert_color_range(av1_color.color_range);
 
         let (color_primaries, transfer_characteristics, icc_profile) =
@@ -885,7 +2414,7 @@ impl ManagedAvifDecoder {
                 ),
             };
 
-        let info = ImageInfo {
+        ImageInfo {
             width: width as u32,
             height: height as u32,
             bit_depth,
@@ -897,6 +2426,7 @@ impl ManagedAvifDecoder {
             matrix_coefficients,
             color_range,
             chroma_sampling: convert_chroma_sampling(layout),
+            chroma_sample_position: self.chroma_position,
             icc_profile,
             rotation: self.parser.rotation().cloned(),
             mirror: self.parser.mirror().cloned(),
@@ -904,6 +2434,8 @@ impl ManagedAvifDecoder {
             pixel_aspect_ratio: self.parser.pixel_aspect_ratio().cloned(),
             content_light_level: self.parser.content_light_level().cloned(),
             mastering_display: self.parser.mastering_display().cloned(),
+            hdr10_plus: self.hdr10_plus.clone(),
+            declared_bits_per_channel: self.declared_bits.clone(),
             exif: self
                 .parser
                 .exif()
@@ -915,14 +2447,31 @@ impl ManagedAvifDecoder {
                 .and_then(|r| r.ok())
                 .map(|c| c.into_owned()),
             gain_map: self.extract_gain_map(),
-            // Depth map extraction requires zenavif-parse > 0.4.0 (not yet published).
-            depth_map: None,
-        };
+            depth_map: self.depth_map.clone(),
+            failed_tiles: Vec::new(),
+            lossy_conversion: false,
+            alpha_transformed: None,
+        }
+    }
+
+    fn convert_to_image(
+        &self,
+        primary: Frame,
+        alpha: Option<Frame>,
+        stop: &(impl Stop + ?Sized),
+    ) -> Result<(PixelBuffer, ImageInfo)> {
+        let _stage = alloc_stats::enter(Stage::Convert);
+        let bit_depth = primary.bit_depth();
+        let info = self.frame_info(&primary, alpha.is_some());
 
         stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
 
+        let gray = self.prefer_grayscale && alpha.is_none() && info.monochrome;
+        // The overshoot-preserving conversion already lands in video range.
+        let video_range = self.preserves_overshoot(&info);
         let info_clone = info.clone();
         let mut pixels = match bit_depth {
+            8 | 10 | 12 if gray => Self::convert_gray(&primary, &info),
             8 => self.convert_8bit(primary, alpha, info, stop),
             10 | 12 => self.convert_16bit(primary, alpha, info, stop),
             _ => Err(at!(Error::Decode {
@@ -931,11 +2480,101 @@ impl ManagedAvifDecoder {
             })),
         }?;
 
-        if self.prefer_8bit && bit_depth > 8 {
-            pixels = downscale_to_8bit(pixels);
+        let mut info = info_clone;
+        if bit_depth > 8
+            && self
+                .output_depth
+                .wants_8bit(&pixels, info.transfer_characteristics)
+        {
+            pixels = reduce_to_8bit(pixels, self.dither);
+        }
+
+        // ICC profiles describe full-range RGB, so video-range samples are
+        // left unconverted.
+        #[cfg(feature = "cms")]
+        if let Some(transform) = &self.color_transform
+            && !video_range
+        {
+            transform.apply(&mut pixels)?;
+        }
+        if self.output_range == OutputRange::PreserveLimited
+            && info.color_range == ColorRange::Limited
+            && !video_range
+        {
+            compress_to_limited_range(&mut pixels);
+        }
+        self.describe_output(&mut info, &pixels);
+        Ok((pixels, info))
+    }
+
+    /// Describe the color space `pixels` ended up in after color
+    /// management, if any applied, and whether they lost precision.
+    fn describe_output(&self, info: &mut ImageInfo, pixels: &PixelBuffer) {
+        info.lossy_conversion = info.bit_depth > 8
+            && self.output_depth == OutputDepth::Force8
+            && pixels.descriptor().channel_type() == ChannelType::U8;
+        info.alpha_transformed = (info.premultiplied_alpha && pixels.has_alpha())
+            .then_some(AlphaTransform::Unpremultiplied);
+        #[cfg(feature = "cms")]
+        if let Some(transform) = &self.color_transform
+            && transform.converts(pixels)
+            && !self.preserves_overshoot(info)
+        {
+            transform.describe(info);
         }
+    }
+
+    /// Whether `info`'s image converts to 16-bit video range with its
+    /// super-whites and sub-blacks kept, per
+    /// [`RangeClipping::PreserveOvershoot`].
+    fn preserves_overshoot(&self, info: &ImageInfo) -> bool {
+        self.range_clipping == RangeClipping::PreserveOvershoot
+            && info.color_range == ColorRange::Limited
+            && info.bit_depth > 8
+            && !(self.prefer_grayscale && info.monochrome && !info.has_alpha)
+            && YCgCo::from_matrix(info.matrix_coefficients).is_none()
+            && info.matrix_coefficients != MatrixCoefficients::IDENTITY
+    }
 
-        Ok((pixels, info_clone))
+    /// Whether decoded RGB or gray samples of `info`'s image are in video
+    /// range rather than full range.
+    #[cfg_attr(not(feature = "zencodec"), allow(dead_code))]
+    pub(crate) fn output_is_video_range(&self, info: &ImageInfo) -> bool {
+        info.color_range == ColorRange::Limited
+            && (self.output_range == OutputRange::PreserveLimited || self.preserves_overshoot(info))
+    }
+
+    /// Whether decoded images go through an ICC conversion.
+    #[cfg(feature = "cms")]
+    fn color_managed(&self) -> bool {
+        self.color_transform.is_some()
+    }
+
+    #[cfg(not(feature = "cms"))]
+    fn color_managed(&self) -> bool {
+        false
+    }
+
+    /// Convert a monochrome frame to gray at display size, without going
+    /// through RGB.
+    fn convert_gray(primary: &Frame, info: &ImageInfo) -> Result<PixelBuffer> {
+        let (width, height) = (info.width as usize, info.height as usize);
+        if let Planes::Depth8(planes) = primary.planes() {
+            return luma_to_gray8(planes.y().rows(), width, height, info.color_range);
+        }
+        let Planes::Depth16(planes) = primary.planes() else {
+            return Err(at!(Error::Decode {
+                code: -1,
+                msg: "Expected 16-bit planes",
+            }));
+        };
+        luma_to_gray16(
+            planes.y().rows(),
+            width,
+            height,
+            info.color_range,
+            info.bit_depth,
+        )
     }
 
     /// Convert 8-bit frame to RGB using yuv crate bulk conversion (zero-copy)
@@ -953,18 +2592,16 @@ impl ManagedAvifDecoder {
             }));
         };
 
-        // Use buffer dimensions for YUV conversion (actual buffer size)
-        // Then crop to displayed dimensions if needed
-        let buffer_width = planes.y().width();
-        let buffer_height = planes.y().height();
-        let display_width = info.width as usize;
-        let display_height = info.height as usize;
-        let needs_crop = buffer_width != display_width || buffer_height != display_height;
+        // Convert just the displayed region. The planes can be padded past
+        // it; reading them through their strides writes straight into a
+        // display-sized buffer, with no padded intermediate or crop pass.
+        let width = info.width as usize;
+        let height = info.height as usize;
         let has_alpha = alpha.is_some();
         let yuv_range = to_yuv_range(info.color_range);
         let matrix = to_yuv_matrix(info.matrix_coefficients);
-        let buffer_pixel_count = buffer_width
-            .checked_mul(buffer_height)
+        let pixel_count = width
+            .checked_mul(height)
             .ok_or_else(|| at!(Error::OutOfMemory))?;
 
         let mut image = match info.chroma_sampling {
@@ -973,21 +2610,21 @@ impl ManagedAvifDecoder {
                 let gray = YuvGrayImage {
                     y_plane: y_view.as_slice(),
                     y_stride: y_view.stride() as u32,
-                    width: buffer_width as u32,
-                    height: buffer_height as u32,
+                    width: width as u32,
+                    height: height as u32,
                 };
 
                 if has_alpha {
-                    let mut out = vec![
+                    let mut out = self.output_buffer(
+                        pixel_count,
                         Rgba {
                             r: 0u8,
                             g: 0,
                             b: 0,
-                            a: 255
-                        };
-                        buffer_pixel_count
-                    ];
-                    let rgb_stride = buffer_width as u32 * 4;
+                            a: 255,
+                        },
+                    );
+                    let rgb_stride = width as u32 * 4;
                     yuv::yuv400_to_rgba(
                         &gray,
                         rgb::bytemuck::cast_slice_mut(out.as_mut_slice()),
@@ -996,12 +2633,12 @@ impl ManagedAvifDecoder {
                         matrix,
                     )
                     .map_err(|e| at!(Error::ColorConversion(e)))?;
-                    PixelBuffer::from_pixels(out, buffer_width as u32, buffer_height as u32)
+                    PixelBuffer::from_pixels(out, width as u32, height as u32)
                         .map_err(|_| at!(Error::OutOfMemory))?
                         .into()
                 } else {
-                    let mut out = vec![Rgb { r: 0u8, g: 0, b: 0 }; buffer_pixel_count];
-                    let rgb_stride = buffer_width as u32 * 3;
+                    let mut out = self.output_buffer(pixel_count, Rgb { r: 0u8, g: 0, b: 0 });
+                    let rgb_stride = width as u32 * 3;
                     yuv::yuv400_to_rgb(
                         &gray,
                         rgb::bytemuck::cast_slice_mut(out.as_mut_slice()),
@@ -1010,7 +2647,7 @@ impl ManagedAvifDecoder {
                         matrix,
                     )
                     .map_err(|e| at!(Error::ColorConversion(e)))?;
-                    PixelBuffer::from_pixels(out, buffer_width as u32, buffer_height as u32)
+                    PixelBuffer::from_pixels(out, width as u32, height as u32)
                         .map_err(|_| at!(Error::OutOfMemory))?
                         .into()
                 }
@@ -1030,53 +2667,145 @@ impl ManagedAvifDecoder {
                     })
                 })?;
 
-                let planar = YuvPlanarImage {
-                    y_plane: y_view.as_slice(),
-                    y_stride: y_view.stride() as u32,
-                    u_plane: u_view.as_slice(),
-                    u_stride: u_view.stride() as u32,
-                    v_plane: v_view.as_slice(),
-                    v_stride: v_view.stride() as u32,
-                    width: buffer_width as u32,
-                    height: buffer_height as u32,
+                let upsampled = upsample_chroma(
+                    self.chroma_upsampling,
+                    (u_view.as_slice(), u_view.stride()),
+                    (v_view.as_slice(), v_view.stride()),
+                    sampling,
+                    &info,
+                )?;
+                let (u_plane, u_stride, v_plane, v_stride, sampling) = match &upsampled {
+                    Some([u, v]) => (&u[..], width, &v[..], width, ChromaSampling::Cs444),
+                    None => (
+                        u_view.as_slice(),
+                        u_view.stride(),
+                        v_view.as_slice(),
+                        v_view.stride(),
+                        sampling,
+                    ),
                 };
 
-                if has_alpha {
-                    // Decode YUV directly to RGBA to avoid an intermediate RGB
-                    // allocation. Uses yuv crate bilinear functions for 420/422
-                    // (matching our custom YUV module's chroma upsampling quality)
-                    // and standard function for 444 (no upsampling needed).
-                    let mut out = vec![
-                        Rgba {
+                let our_range = to_our_yuv_range(info.color_range);
+                let our_matrix = to_our_yuv_matrix(info.matrix_coefficients);
+                // Identity (GBR) and YCgCo have no Kr/Kb matrix.
+                let ycgco = YCgCo::from_matrix(info.matrix_coefficients);
+                let identity = info.matrix_coefficients == MatrixCoefficients::IDENTITY;
+                if ycgco.is_some() || identity {
+                    let convert = |out: &mut [u8], channels| match ycgco {
+                        Some(variant) => yuv_convert::ycgco_to_rgb(
+                            y_view.as_slice(),
+                            y_view.stride(),
+                            u_plane,
+                            u_stride,
+                            v_plane,
+                            v_stride,
+                            width,
+                            height,
+                            chroma_shift(sampling),
+                            8,
+                            our_range,
+                            variant,
+                            channels,
+                            out,
+                        ),
+                        None => yuv_convert::gbr_to_rgb(
+                            y_view.as_slice(),
+                            y_view.stride(),
+                            u_plane,
+                            u_stride,
+                            v_plane,
+                            v_stride,
+                            width,
+                            height,
+                            chroma_shift(sampling),
+                            8,
+                            our_range,
+                            channels,
+                            out,
+                        ),
+                    };
+                    if has_alpha {
+                        let fill = Rgba {
                             r: 0u8,
                             g: 0,
                             b: 0,
-                            a: 255
+                            a: 255,
                         };
-                        buffer_pixel_count
-                    ];
-                    let rgb_stride = buffer_width as u32 * 4;
-                    match sampling {
-                        ChromaSampling::Cs420 => yuv::yuv420_to_rgba_bilinear(
-                            &planar,
-                            rgb::bytemuck::cast_slice_mut(out.as_mut_slice()),
-                            rgb_stride,
-                            yuv_range,
-                            matrix,
+                        let mut out = self.output_buffer(pixel_count, fill);
+                        convert(rgb::bytemuck::cast_slice_mut(out.as_mut_slice()), 4);
+                        PixelBuffer::from_pixels(out, width as u32, height as u32)
+                            .map_err(|_| at!(Error::OutOfMemory))?
+                            .into()
+                    } else {
+                        let mut out = self.output_buffer(pixel_count, Rgb { r: 0u8, g: 0, b: 0 });
+                        convert(rgb::bytemuck::cast_slice_mut(out.as_mut_slice()), 3);
+                        PixelBuffer::from_pixels(out, width as u32, height as u32)
+                            .map_err(|_| at!(Error::OutOfMemory))?
+                            .into()
+                    }
+                } else if let Some(image) = convert_8bit_with_backend(
+                    self.conversion_backend,
+                    (y_view.as_slice(), y_view.stride()),
+                    (u_plane, u_stride),
+                    (v_plane, v_stride),
+                    sampling,
+                    &info,
+                    has_alpha,
+                )? {
+                    image
+                } else if has_alpha {
+                    // Convert straight to RGBA with opaque alpha, so the alpha
+                    // plane lands in place with no RGB intermediate. Colors
+                    // match the RGB path below, chroma siting included.
+                    let out = self.output_buffer(
+                        pixel_count,
+                        Rgba {
+                            r: 0u8,
+                            g: 0,
+                            b: 0,
+                            a: 255,
+                        },
+                    );
+                    let result = match sampling {
+                        ChromaSampling::Cs420 => yuv_convert::yuv420_to_rgba8_into(
+                            out,
+                            y_view.as_slice(),
+                            y_view.stride(),
+                            u_plane,
+                            u_stride,
+                            v_plane,
+                            v_stride,
+                            width,
+                            height,
+                            our_range,
+                            our_matrix,
+                            info.chroma_sample_position,
                         ),
-                        ChromaSampling::Cs422 => yuv::yuv422_to_rgba_bilinear(
-                            &planar,
-                            rgb::bytemuck::cast_slice_mut(out.as_mut_slice()),
-                            rgb_stride,
-                            yuv_range,
-                            matrix,
+                        ChromaSampling::Cs422 => yuv_convert::yuv422_to_rgba8_into(
+                            out,
+                            y_view.as_slice(),
+                            y_view.stride(),
+                            u_plane,
+                            u_stride,
+                            v_plane,
+                            v_stride,
+                            width,
+                            height,
+                            our_range,
+                            our_matrix,
                         ),
-                        ChromaSampling::Cs444 => yuv::yuv444_to_rgba(
-                            &planar,
-                            rgb::bytemuck::cast_slice_mut(out.as_mut_slice()),
-                            rgb_stride,
-                            yuv_range,
-                            matrix,
+                        ChromaSampling::Cs444 => yuv_convert::yuv444_to_rgba8_into(
+                            out,
+                            y_view.as_slice(),
+                            y_view.stride(),
+                            u_plane,
+                            u_stride,
+                            v_plane,
+                            v_stride,
+                            width,
+                            height,
+                            our_range,
+                            our_matrix,
                         ),
                         ChromaSampling::Monochrome => {
                             return Err(at!(Error::Decode {
@@ -1084,50 +2813,48 @@ impl ManagedAvifDecoder {
                                 msg: "Monochrome should not reach chroma conversion",
                             }));
                         }
-                    }
-                    .map_err(|e| at!(Error::ColorConversion(e)))?;
+                    };
 
-                    PixelBuffer::from_pixels(out, buffer_width as u32, buffer_height as u32)
-                        .map_err(|_| at!(Error::OutOfMemory))?
-                        .into()
+                    PixelBuffer::from_imgvec(result).into()
                 } else {
-                    let our_range = to_our_yuv_range(info.color_range);
-                    let our_matrix = to_our_yuv_matrix(info.matrix_coefficients);
-
                     let result = match sampling {
-                        ChromaSampling::Cs420 => yuv_convert::yuv420_to_rgb8(
+                        ChromaSampling::Cs420 => yuv_convert::yuv420_to_rgb8_into(
+                            self.output_buffer(pixel_count, Rgb { r: 0u8, g: 0, b: 0 }),
                             y_view.as_slice(),
                             y_view.stride(),
-                            u_view.as_slice(),
-                            u_view.stride(),
-                            v_view.as_slice(),
-                            v_view.stride(),
-                            buffer_width,
-                            buffer_height,
+                            u_plane,
+                            u_stride,
+                            v_plane,
+                            v_stride,
+                            width,
+                            height,
                             our_range,
                             our_matrix,
+                            info.chroma_sample_position,
                         ),
-                        ChromaSampling::Cs422 => yuv_convert::yuv422_to_rgb8(
+                        ChromaSampling::Cs422 => yuv_convert::yuv422_to_rgb8_into(
+                            self.output_buffer(pixel_count, Rgb { r: 0u8, g: 0, b: 0 }),
                             y_view.as_slice(),
                             y_view.stride(),
-                            u_view.as_slice(),
-                            u_view.stride(),
-                            v_view.as_slice(),
-                            v_view.stride(),
-                            buffer_width,
-                            buffer_height,
+                            u_plane,
+                            u_stride,
+                            v_plane,
+                            v_stride,
+                            width,
+                            height,
                             our_range,
                             our_matrix,
                         ),
-                        ChromaSampling::Cs444 => yuv_convert::yuv444_to_rgb8(
+                        ChromaSampling::Cs444 => yuv_convert::yuv444_to_rgb8_into(
+                            self.output_buffer(pixel_count, Rgb { r: 0u8, g: 0, b: 0 }),
                             y_view.as_slice(),
                             y_view.stride(),
-                            u_view.as_slice(),
-                            u_view.stride(),
-                            v_view.as_slice(),
-                            v_view.stride(),
-                            buffer_width,
-                            buffer_height,
+                            u_plane,
+                            u_stride,
+                            v_plane,
+                            v_stride,
+                            width,
+                            height,
                             our_range,
                             our_matrix,
                         ),
@@ -1146,11 +2873,6 @@ impl ManagedAvifDecoder {
 
         stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
 
-        // Crop to display dimensions if needed
-        if needs_crop {
-            image = Self::crop_image(image, display_width, display_height)?;
-        }
-
         // Handle alpha channel if present
         if let Some(alpha_frame) = alpha {
             let Planes::Depth8(alpha_planes) = alpha_frame.planes() else {
@@ -1165,8 +2887,8 @@ impl ManagedAvifDecoder {
             add_alpha8(
                 &mut image,
                 alpha_planes.y().rows(),
-                display_width,
-                display_height,
+                width,
+                height,
                 alpha_range,
                 self.parser.premultiplied_alpha(),
             )?;
@@ -1190,41 +2912,97 @@ impl ManagedAvifDecoder {
             }));
         };
 
-        // Use buffer dimensions for YUV conversion (actual buffer size)
-        // Then crop to displayed dimensions if needed
-        let buffer_width = planes.y().width();
-        let buffer_height = planes.y().height();
-        let display_width = info.width as usize;
-        let display_height = info.height as usize;
-        let needs_crop = buffer_width != display_width || buffer_height != display_height;
+        // Convert just the displayed region. The planes can be padded past
+        // it; reading them through their strides writes straight into a
+        // display-sized buffer, with no padded intermediate or crop pass.
+        let width = info.width as usize;
+        let height = info.height as usize;
         let has_alpha = alpha.is_some();
         let yuv_range = to_yuv_range(info.color_range);
         let matrix = to_yuv_matrix(info.matrix_coefficients);
-        let buffer_pixel_count = buffer_width
-            .checked_mul(buffer_height)
+        let pixel_count = width
+            .checked_mul(height)
             .ok_or_else(|| at!(Error::OutOfMemory))?;
+        let ycgco = YCgCo::from_matrix(info.matrix_coefficients);
+        let identity = info.matrix_coefficients == MatrixCoefficients::IDENTITY;
+        let preserve_overshoot = self.preserves_overshoot(&info);
 
         let mut image = match info.chroma_sampling {
+            sampling if preserve_overshoot => {
+                let y_view = planes.y();
+                let (u_view, v_view) = (planes.u(), planes.v());
+                let (u_plane, u_stride) = u_view
+                    .as_ref()
+                    .map_or((&[][..], 0), |u| (u.as_slice(), u.stride()));
+                let (v_plane, v_stride) = v_view
+                    .as_ref()
+                    .map_or((&[][..], 0), |v| (v.as_slice(), v.stride()));
+                // Same default as the clipping converters: bilinear,
+                // siting-aware chroma unless a filter was chosen.
+                let filter = self.chroma_upsampling.unwrap_or(ChromaUpsampling::Bilinear);
+                let upsampled = upsample_chroma(
+                    Some(filter),
+                    (u_plane, u_stride),
+                    (v_plane, v_stride),
+                    sampling,
+                    &info,
+                )?;
+                let (u_plane, u_stride, v_plane, v_stride, sampling) = match &upsampled {
+                    Some([u, v]) => (&u[..], width, &v[..], width, ChromaSampling::Cs444),
+                    None => (u_plane, u_stride, v_plane, v_stride, sampling),
+                };
+                let rgb = yuv_to_rgb16_preserve_overshoot(
+                    y_view.as_slice(),
+                    y_view.stride(),
+                    u_plane,
+                    u_stride,
+                    v_plane,
+                    v_stride,
+                    width,
+                    height,
+                    sampling,
+                    info.bit_depth,
+                    to_our_yuv_matrix(info.matrix_coefficients),
+                );
+                if has_alpha {
+                    let out: Vec<Rgba<u16>> = rgb
+                        .into_iter()
+                        .map(|px| Rgba {
+                            r: px.r,
+                            g: px.g,
+                            b: px.b,
+                            a: 0xFFFF,
+                        })
+                        .collect();
+                    PixelBuffer::from_pixels(out, width as u32, height as u32)
+                        .map_err(|_| at!(Error::OutOfMemory))?
+                        .into()
+                } else {
+                    PixelBuffer::from_pixels(rgb, width as u32, height as u32)
+                        .map_err(|_| at!(Error::OutOfMemory))?
+                        .into()
+                }
+            }
             ChromaSampling::Monochrome => {
                 let y_view = planes.y();
                 let gray = YuvGrayImage {
                     y_plane: y_view.as_slice(),
                     y_stride: y_view.stride() as u32,
-                    width: buffer_width as u32,
-                    height: buffer_height as u32,
+                    width: width as u32,
+                    height: height as u32,
                 };
 
                 if has_alpha {
-                    let mut out = vec![
+                    let mut out = self.output_buffer(
+                        pixel_count,
                         Rgba {
                             r: 0u16,
                             g: 0,
                             b: 0,
-                            a: 0xFFFF
-                        };
-                        buffer_pixel_count
-                    ];
-                    let rgb_stride = buffer_width as u32 * 4;
+                            a: 0xFFFF,
+                        },
+                    );
+                    let rgb_stride = width as u32 * 4;
                     match info.bit_depth {
                         10 => yuv::y010_to_rgba10(
                             &gray,
@@ -1249,19 +3027,19 @@ impl ManagedAvifDecoder {
                         ),
                     }
                     .map_err(|e| at!(Error::ColorConversion(e)))?;
-                    PixelBuffer::from_pixels(out, buffer_width as u32, buffer_height as u32)
+                    PixelBuffer::from_pixels(out, width as u32, height as u32)
                         .map_err(|_| at!(Error::OutOfMemory))?
                         .into()
                 } else {
-                    let mut out = vec![
+                    let mut out = self.output_buffer(
+                        pixel_count,
                         Rgb {
                             r: 0u16,
                             g: 0,
-                            b: 0
-                        };
-                        buffer_pixel_count
-                    ];
-                    let rgb_stride = buffer_width as u32 * 3;
+                            b: 0,
+                        },
+                    );
+                    let rgb_stride = width as u32 * 3;
                     match info.bit_depth {
                         10 => yuv::y010_to_rgb10(
                             &gray,
@@ -1286,7 +3064,115 @@ impl ManagedAvifDecoder {
                         ),
                     }
                     .map_err(|e| at!(Error::ColorConversion(e)))?;
-                    PixelBuffer::from_pixels(out, buffer_width as u32, buffer_height as u32)
+                    PixelBuffer::from_pixels(out, width as u32, height as u32)
+                        .map_err(|_| at!(Error::OutOfMemory))?
+                        .into()
+                }
+            }
+            // SMPTE 240M has no integer constants; the yuv crate covers it
+            // and 16-bit sources.
+            sampling
+                if matches!(info.bit_depth, 10 | 12)
+                    && info.matrix_coefficients != MatrixCoefficients::SMPTE240 =>
+            {
+                let y_view = planes.y();
+                let (Some(u_view), Some(v_view)) = (planes.u(), planes.v()) else {
+                    return Err(at!(Error::Decode {
+                        code: -1,
+                        msg: "Missing chroma planes",
+                    }));
+                };
+                let upsampled = upsample_chroma(
+                    self.chroma_upsampling,
+                    (u_view.as_slice(), u_view.stride()),
+                    (v_view.as_slice(), v_view.stride()),
+                    sampling,
+                    &info,
+                )?;
+                let (u_plane, u_stride, v_plane, v_stride, sampling) = match &upsampled {
+                    Some([u, v]) => (&u[..], width, &v[..], width, ChromaSampling::Cs444),
+                    None => (
+                        u_view.as_slice(),
+                        u_view.stride(),
+                        v_view.as_slice(),
+                        v_view.stride(),
+                        sampling,
+                    ),
+                };
+                let convert = |out: &mut [u16], channels| match ycgco {
+                    Some(variant) => yuv_convert::ycgco_to_rgb(
+                        y_view.as_slice(),
+                        y_view.stride(),
+                        u_plane,
+                        u_stride,
+                        v_plane,
+                        v_stride,
+                        width,
+                        height,
+                        chroma_shift(sampling),
+                        info.bit_depth,
+                        to_our_yuv_range(info.color_range),
+                        variant,
+                        channels,
+                        out,
+                    ),
+                    None if identity => yuv_convert::gbr_to_rgb(
+                        y_view.as_slice(),
+                        y_view.stride(),
+                        u_plane,
+                        u_stride,
+                        v_plane,
+                        v_stride,
+                        width,
+                        height,
+                        chroma_shift(sampling),
+                        info.bit_depth,
+                        to_our_yuv_range(info.color_range),
+                        channels,
+                        out,
+                    ),
+                    None => yuv_convert_16bit::yuv_to_rgb16(
+                        y_view.as_slice(),
+                        y_view.stride(),
+                        u_plane,
+                        u_stride,
+                        v_plane,
+                        v_stride,
+                        width,
+                        height,
+                        chroma_shift(sampling),
+                        info.bit_depth,
+                        to_our_yuv_range(info.color_range),
+                        to_our_yuv_matrix(info.matrix_coefficients),
+                        channels,
+                        out,
+                    ),
+                };
+                if has_alpha {
+                    let mut out = self.output_buffer(
+                        pixel_count,
+                        Rgba {
+                            r: 0u16,
+                            g: 0,
+                            b: 0,
+                            a: 0xFFFF,
+                        },
+                    );
+                    convert(rgb::bytemuck::cast_slice_mut(out.as_mut_slice()), 4);
+                    PixelBuffer::from_pixels(out, width as u32, height as u32)
+                        .map_err(|_| at!(Error::OutOfMemory))?
+                        .into()
+                } else {
+                    let mut out = self.output_buffer(
+                        pixel_count,
+                        Rgb {
+                            r: 0u16,
+                            g: 0,
+                            b: 0,
+                        },
+                    );
+                    convert(rgb::bytemuck::cast_slice_mut(out.as_mut_slice()), 3);
+                    PixelBuffer::from_pixels(out, width as u32, height as u32)
                         .map_err(|_| at!(Error::OutOfMemory))?
                         .into()
                 }
@@ -1306,28 +3192,46 @@ impl ManagedAvifDecoder {
                     })
                 })?;
 
+                let upsampled = upsample_chroma(
+                    self.chroma_upsampling,
+                    (u_view.as_slice(), u_view.stride()),
+                    (v_view.as_slice(), v_view.stride()),
+                    sampling,
+                    &info,
+                )?;
+                let (u_plane, u_stride, v_plane, v_stride, sampling) = match &upsampled {
+                    Some([u, v]) => (&u[..], width, &v[..], width, ChromaSampling::Cs444),
+                    None => (
+                        u_view.as_slice(),
+                        u_view.stride(),
+                        v_view.as_slice(),
+                        v_view.stride(),
+                        sampling,
+                    ),
+                };
+
                 let planar = YuvPlanarImage {
                     y_plane: y_view.as_slice(),
                     y_stride: y_view.stride() as u32,
-                    u_plane: u_view.as_slice(),
-                    u_stride: u_view.stride() as u32,
-                    v_plane: v_view.as_slice(),
-                    v_stride: v_view.stride() as u32,
-                    width: buffer_width as u32,
-                    height: buffer_height as u32,
+                    u_plane,
+                    u_stride: u_stride as u32,
+                    v_plane,
+                    v_stride: v_stride as u32,
+                    width: width as u32,
+                    height: height as u32,
                 };
 
                 if has_alpha {
-                    let mut out = vec![
+                    let mut out = self.output_buffer(
+                        pixel_count,
                         Rgba {
                             r: 0u16,
                             g: 0,
                             b: 0,
-                            a: 0xFFFF
-                        };
-                        buffer_pixel_count
-                    ];
-                    let rgb_stride = buffer_width as u32 * 4;
+                            a: 0xFFFF,
+                        },
+                    );
+                    let rgb_stride = width as u32 * 4;
                     match (info.bit_depth, sampling) {
                         (10, ChromaSampling::Cs420) => yuv::i010_to_rgba10(
                             &planar,
@@ -1400,19 +3304,19 @@ impl ManagedAvifDecoder {
                         }
                     }
                     .map_err(|e| at!(Error::ColorConversion(e)))?;
-                    PixelBuffer::from_pixels(out, buffer_width as u32, buffer_height as u32)
+                    PixelBuffer::from_pixels(out, width as u32, height as u32)
                         .map_err(|_| at!(Error::OutOfMemory))?
                         .into()
                 } else {
-                    let mut out = vec![
+                    let mut out = self.output_buffer(
+                        pixel_count,
                         Rgb {
                             r: 0u16,
                             g: 0,
-                            b: 0
-                        };
-                        buffer_pixel_count
-                    ];
-                    let rgb_stride = buffer_width as u32 * 3;
+                            b: 0,
+                        },
+                    );
+                    let rgb_stride = width as u32 * 3;
                     match (info.bit_depth, sampling) {
                         (10, ChromaSampling::Cs420) => yuv::i010_to_rgb10(
                             &planar,
@@ -1485,7 +3389,7 @@ impl ManagedAvifDecoder {
                         }
                     }
                     .map_err(|e| at!(Error::ColorConversion(e)))?;
-                    PixelBuffer::from_pixels(out, buffer_width as u32, buffer_height as u32)
+                    PixelBuffer::from_pixels(out, width as u32, height as u32)
                         .map_err(|_| at!(Error::OutOfMemory))?
                         .into()
                 }
@@ -1496,11 +3400,9 @@ impl ManagedAvifDecoder {
 
         // Scale from native bit depth (e.g. 0–1023 for 10-bit) to full u16 (0–65535).
         // Must happen before alpha attachment so unpremultiply uses correct 16-bit range.
-        scale_pixels_to_u16(&mut image, info.bit_depth);
-
-        // Crop to display dimensions if needed
-        if needs_crop {
-            image = Self::crop_image(image, display_width, display_height)?;
+        // The overshoot-preserving converter writes 16-bit samples itself.
+        if !preserve_overshoot {
+            scale_pixels_to_u16(&mut image, info.bit_depth);
         }
 
         // Handle alpha channel if present
@@ -1517,8 +3419,8 @@ impl ManagedAvifDecoder {
             add_alpha16(
                 &mut image,
                 alpha_planes.y().rows(),
-                display_width,
-                display_height,
+                width,
+                height,
                 alpha_range,
                 info.bit_depth,
                 self.parser.premultiplied_alpha(),
@@ -1535,30 +3437,20 @@ impl ManagedAvifDecoder {
     }
 
     /// Extract the gain map from the AVIF container, if present.
-    ///
-    /// Bundles gain_map_metadata, gain_map_data, and gain_map_color_info from
-    /// the parser into a single [`AvifGainMap`](crate::image::AvifGainMap).
     fn extract_gain_map(&self) -> Option<crate::image::AvifGainMap> {
-        let metadata = self.parser.gain_map_metadata()?.clone();
-        let data = self.parser.gain_map_data()?.ok()?.into_owned();
-        let alt_color_info = self.parser.gain_map_color_info().cloned();
-        Some(crate::image::AvifGainMap {
-            metadata,
-            gain_map_data: data,
-            alt_color_info,
-        })
+        gain_map_from(&self.parser)
     }
 
     /// Whether this image is a grid (tiled) image.
     #[allow(dead_code)]
     pub(crate) fn is_grid(&self) -> bool {
-        self.parser.grid_config().is_some()
+        self.grid.is_some()
     }
 
     /// Grid configuration, if this is a grid image.
     #[allow(dead_code)]
     pub(crate) fn grid_config(&self) -> Option<zenavif_parse::GridConfig> {
-        self.parser.grid_config().cloned()
+        self.grid.clone()
     }
 
     /// Decode one tile-row of a grid image, returning converted pixel buffers.
@@ -1579,8 +3471,13 @@ impl ManagedAvifDecoder {
                 .parser
                 .tile_data(tile_idx)
                 .map_err(|e| at!(Error::from(e)))?;
-            let frame =
-                Self::decode_frame(&mut self.decoder, &tile_data, "Failed to decode grid tile")?;
+            let tile_data = reseed_grain(&tile_data, self.settings.grain_seed);
+            let frame = Self::decode_frame(
+                &mut self.decoder,
+                &tile_data,
+                self.settings.all_layers,
+                "Failed to decode grid tile",
+            )?;
             let (pixels, _info) = self.convert_to_image(frame, None, stop)?;
             row_tiles.push(pixels);
         }
@@ -1605,7 +3502,7 @@ impl ManagedAvifDecoder {
     ) -> Result<ImageInfo> {
         stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
 
-        if self.parser.grid_config().is_some() {
+        if self.grid.is_some() {
             return self.decode_grid_to_sink(stop, sink);
         }
 
@@ -1615,7 +3512,6 @@ impl ManagedAvifDecoder {
         let height = converter.display_height() as u32;
         let desc = converter.descriptor();
         let strip_h = converter.optimal_strip_height();
-        let bpp = desc.bytes_per_pixel();
 
         sink.begin(width, height, desc)
             .map_err(|e| at!(Error::Encode(e.to_string())))?;
@@ -1643,13 +3539,14 @@ impl ManagedAvifDecoder {
                 .provide_next_buffer(y_offset as u32, h as u32, width, desc)
                 .map_err(|e| at!(Error::Encode(e.to_string())))?;
 
-            let src = strip_pixels.as_slice();
-            let row_bytes = width as usize * bpp;
-            for row in 0..h {
-                let dst_row = sink_buf.row_mut(row as u32);
-                let src_row = src.row(row as u32);
-                dst_row[..row_bytes].copy_from_slice(&src_row[..row_bytes]);
-            }
+            crate::plane_copy::copy_rect(
+                &strip_pixels.as_slice(),
+                (0, 0),
+                &mut sink_buf,
+                (0, 0),
+                width as usize,
+                h,
+            );
 
             y_offset += h;
         }
@@ -1667,16 +3564,12 @@ impl ManagedAvifDecoder {
         stop: &(impl Stop + ?Sized),
         sink: &mut dyn zencodec::decode::DecodeRowSink,
     ) -> Result<ImageInfo> {
-        let grid_config = self
-            .parser
-            .grid_config()
-            .ok_or_else(|| {
-                at!(Error::Decode {
-                    code: -1,
-                    msg: "Expected grid config but found none",
-                })
-            })?
-            .clone();
+        let grid_config = self.grid.clone().ok_or_else(|| {
+            at!(Error::Decode {
+                code: -1,
+                msg: "Expected grid config but found none",
+            })
+        })?;
 
         let grid_rows = grid_config.rows as usize;
         let cols = grid_config.columns as usize;
@@ -1699,9 +3592,11 @@ impl ManagedAvifDecoder {
                     .parser
                     .tile_data(tile_idx)
                     .map_err(|e| at!(Error::from(e)))?;
+                let tile_data = reseed_grain(&tile_data, self.settings.grain_seed);
                 let frame = Self::decode_frame(
                     &mut self.decoder,
                     &tile_data,
+                    self.settings.all_layers,
                     "Failed to decode grid tile",
                 )?;
                 let (pixels, _info) = self.convert_to_image(frame, None, stop)?;
@@ -1710,7 +3605,6 @@ impl ManagedAvifDecoder {
 
             // Get descriptor and tile height from the first tile
             let desc = row_tiles[0].descriptor();
-            let bpp = desc.bytes_per_pixel();
             let tile_h = row_tiles[0].height() as usize;
 
             // Last tile-row may be clipped to output dimensions
@@ -1730,22 +3624,19 @@ impl ManagedAvifDecoder {
             let mut sink_buf = sink
                 .provide_next_buffer(y_offset, strip_h as u32, output_width as u32, desc)
                 .map_err(|e| at!(Error::Encode(e.to_string())))?;
-            for py in 0..strip_h {
-                let dst_row = sink_buf.row_mut(py as u32);
-                let mut x_offset = 0usize;
-                for tile in &row_tiles {
-                    let tile_w = tile.width() as usize;
-                    let actual_w = tile_w.min(output_width.saturating_sub(x_offset));
-                    if actual_w == 0 {
-                        continue;
-                    }
-                    let tile_slice = tile.as_slice();
-                    let src = tile_slice.row(py as u32);
-                    let copy_bytes = actual_w * bpp;
-                    let dst_start = x_offset * bpp;
-                    dst_row[dst_start..dst_start + copy_bytes].copy_from_slice(&src[..copy_bytes]);
-                    x_offset += tile_w;
-                }
+            let mut x_offset = 0usize;
+            for tile in &row_tiles {
+                let tile_w = tile.width() as usize;
+                let actual_w = tile_w.min(output_width.saturating_sub(x_offset));
+                crate::plane_copy::copy_rect(
+                    &tile.as_slice(),
+                    (0, 0),
+                    &mut sink_buf,
+                    (x_offset, 0),
+                    actual_w,
+                    strip_h,
+                );
+                x_offset += tile_w;
             }
 
             y_offset += strip_h as u32;
@@ -1779,7 +3670,7 @@ impl ManagedAvifDecoder {
 /// ```
 pub struct AnimationDecoder {
     /// Underlying decoder (owns parser + color decoder)
-    inner: ManagedAvifDecoder,
+    inner: ManagedAvifDecoder<'static>,
     /// Separate decoder for the alpha track (inter-prediction needs its own state)
     alpha_decoder: Option<Rav1dDecoder>,
     /// Animation metadata
@@ -1801,20 +3692,13 @@ impl AnimationDecoder {
             .ok_or_else(|| at!(Error::Unsupported("not an animated AVIF")))?;
 
         let alpha_decoder = if anim_info.has_alpha {
-            let mut settings = Settings::default();
-            settings.threads = config.threads;
-            Some(Rav1dDecoder::with_settings(settings).map_err(|_e| {
-                at!(Error::Decode {
-                    code: -1,
-                    msg: "Failed to create alpha decoder",
-                })
-            })?)
+            Some(alpha_track_decoder(config.threads)?)
         } else {
             None
         };
 
         let info = DecodedAnimationInfo {
-            frame_count: anim_info.frame_count,
+            frame_count: inner.limited_frame_count(anim_info.frame_count)?,
             loop_count: anim_info.loop_count,
             has_alpha: anim_info.has_alpha,
             timescale: anim_info.timescale,
@@ -1866,7 +3750,10 @@ impl AnimationDecoder {
             .inner
             .convert_to_image(primary_frame, alpha_frame, stop)?;
 
-        let duration_ms = frame_ref.duration_ms;
+        let duration_ms = self
+            .inner
+            .animation_limits
+            .frame_duration(frame_ref.duration_ms);
         self.frame_index += 1;
 
         Ok(Some(DecodedFrame {
@@ -1884,4 +3771,83 @@ impl AnimationDecoder {
     pub fn frame_index(&self) -> usize {
         self.frame_index
     }
+
+    /// Position the decoder so that [`next_frame`](Self::next_frame)
+    /// returns frame `index`.
+    ///
+    /// Decoding restarts from the nearest key frame at or before `index`,
+    /// unless the current position already lies between that key frame and
+    /// `index`. Frames in between are decoded but not color-converted.
+    pub fn seek(&mut self, index: usize, stop: &(impl Stop + ?Sized)) -> Result<()> {
+        if index >= self.info.frame_count {
+            return Err(at!(Error::Unsupported("frame index out of range")));
+        }
+        let keyframe = self.keyframe_at_or_before(index)?;
+        if !(keyframe..=index).contains(&self.frame_index) {
+            self.inner.decoder = self.inner.settings.create()?;
+            if self.alpha_decoder.is_some() {
+                self.alpha_decoder = Some(alpha_track_decoder(self.inner.settings.threads)?);
+            }
+            self.frame_index = keyframe;
+        }
+        while self.frame_index < index {
+            stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+            ManagedAvifDecoder::decode_anim_frames(
+                &self.inner.parser,
+                self.frame_index,
+                &mut self.inner.decoder,
+                self.alpha_decoder.as_mut(),
+            )?;
+            self.frame_index += 1;
+        }
+        Ok(())
+    }
+
+    /// Index of the last frame at or before `index` that starts a random
+    /// access point in both the color and alpha tracks.
+    pub fn keyframe_at_or_before(&self, index: usize) -> Result<usize> {
+        let frame = |i: usize| self.inner.parser.frame(i).map_err(|e| at!(Error::from(e)));
+        // Later key frames may rely on the sequence header of the first.
+        let first = frame(0)?;
+        let (mut color_seq, mut alpha_seq) = (None, None);
+        let _ = av1_header::is_sync_sample(&first.data, &mut color_seq);
+        if let Some(alpha) = &first.alpha_data {
+            let _ = av1_header::is_sync_sample(alpha, &mut alpha_seq);
+        }
+
+        let malformed = |_| {
+            at!(Error::Decode {
+                code: -1,
+                msg: "malformed AV1 frame header",
+            })
+        };
+        for i in (1..=index.min(self.info.frame_count.saturating_sub(1))).rev() {
+            let frame = frame(i)?;
+            let color = av1_header::is_sync_sample(&frame.data, &mut color_seq.clone())
+                .map_err(malformed)?;
+            let alpha = match &frame.alpha_data {
+                Some(alpha) => {
+                    av1_header::is_sync_sample(alpha, &mut alpha_seq.clone()).map_err(malformed)?
+                }
+                None => true,
+            };
+            if color && alpha {
+                return Ok(i);
+            }
+        }
+        Ok(0)
+    }
+}
+
+/// Decoder for an animation's alpha track, which needs its own reference
+/// state.
+fn alpha_track_decoder(threads: u32) -> Result<Rav1dDecoder> {
+    let mut settings = Settings::default();
+    settings.threads = threads;
+    Rav1dDecoder::with_settings(settings).map_err(|_e| {
+        at!(Error::Decode {
+            code: -1,
+            msg: "Failed to create alpha decoder",
+        })
+    })
 }
@@ -21,6 +21,9 @@
 //! }
 //! ```
 
+use crate::av1_header;
+pub use crate::av1_header::{CdefStats, FrameFilterStats, RestorationType};
+
 /// Result of probing an AVIF file.
 #[derive(Debug, Clone)]
 pub struct AvifProbe {
@@ -63,6 +66,9 @@ pub struct AvifProbe {
 #[derive(Debug, Clone)]
 pub struct QualityEstimate {
     /// AV1 base quantizer index (0-255). Lower = higher quality.
+    ///
+    /// From [`estimate_quality`], this is the mean over the active
+    /// segments when segmentation overrides the base quantizer.
     pub quantizer: u8,
     /// Estimated quality on a 0-100 scale. Higher = better.
     ///
@@ -110,6 +116,17 @@ pub enum Recommendation {
     AvoidReencoding,
 }
 
+/// Encoder-side in-loop filter settings, parsed from AV1 frame headers.
+///
+/// Heavy deblocking and CDEF strengths track coarse quantization, so these
+/// give quality heuristics a second signal next to [`QualityEstimate`]
+/// before deciding whether a re-encode is worthwhile.
+#[derive(Debug, Clone)]
+pub struct DecodeStats {
+    /// One entry per color frame; a single entry for still images.
+    pub frames: Vec<Option<FrameFilterStats>>,
+}
+
 /// Errors that can occur during AVIF probing.
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
@@ -122,6 +139,8 @@ pub enum ProbeError {
     Truncated,
     /// Could not find AV1 codec configuration.
     NoAv1Config,
+    /// AV1 sequence or frame headers could not be parsed.
+    InvalidBitstream,
 }
 
 impl core::fmt::Display for ProbeError {
@@ -131,6 +150,7 @@ impl core::fmt::Display for ProbeError {
             Self::NotAvif => write!(f, "not an AVIF file"),
             Self::Truncated => write!(f, "truncated AVIF file"),
             Self::NoAv1Config => write!(f, "no AV1 codec configuration found"),
+            Self::InvalidBitstream => write!(f, "malformed AV1 frame headers"),
         }
     }
 }
@@ -143,20 +163,7 @@ impl std::error::Error for ProbeError {}
 /// estimate quality, and detect lossy/lossless encoding. No pixel decoding is
 /// performed.
 pub fn probe(data: &[u8]) -> Result<AvifProbe, ProbeError> {
-    if data.len() < 12 {
-        return Err(ProbeError::TooShort);
-    }
-
-    // Quick ftyp check before handing off to zenavif-parse
-    if &data[4..8] != b"ftyp" {
-        return Err(ProbeError::NotAvif);
-    }
-
-    let parser = zenavif_parse::AvifParser::from_bytes(data).map_err(|e| match e {
-        zenavif_parse::Error::UnexpectedEOF => ProbeError::Truncated,
-        zenavif_parse::Error::InvalidData(_) => ProbeError::NotAvif,
-        _ => ProbeError::Truncated,
-    })?;
+    let parser = open_parser(data)?;
 
     // Extract container-level metadata
     let has_alpha = parser.alpha_data().is_some();
@@ -262,6 +269,60 @@ pub fn probe(data: &[u8]) -> Result<AvifProbe, ProbeError> {
     })
 }
 
+/// Read the in-loop filter settings the encoder chose for each frame.
+///
+/// Parses AV1 sequence and frame headers only; no tiles are decoded. Stills
+/// yield one entry for the primary item, animations one per color frame.
+/// Entries are `None` for inter frames, whose headers can't be read without
+/// tracking reference state, and for frames that precede the first sequence
+/// header in the track.
+pub fn decode_stats(data: &[u8]) -> Result<DecodeStats, ProbeError> {
+    let parser = open_parser(data)?;
+
+    let mut seq = None;
+    let mut parse_sample = |sample: &[u8]| {
+        av1_header::parse_frame_filters(sample, &mut seq)
+            .map(|frames| frames.into_iter().next())
+            .map_err(|_| ProbeError::InvalidBitstream)
+    };
+
+    let mut frames = Vec::new();
+    if let Some(info) = parser.animation_info() {
+        for i in 0..info.frame_count {
+            let frame = parser.frame(i).map_err(|_| ProbeError::Truncated)?;
+            frames.push(parse_sample(&frame.data)?);
+        }
+    } else {
+        let primary_data = parser.primary_data().map_err(|_| ProbeError::NoAv1Config)?;
+        frames.push(parse_sample(&primary_data)?);
+    }
+
+    Ok(DecodeStats { frames })
+}
+
+/// Estimate the encoding quality of the primary image.
+///
+/// Reads only the first frame header, including per-segment quantizer
+/// overrides, and maps the effective quantizer to a 0-100 quality. Use this
+/// instead of [`probe`] when the quality is all that's needed, e.g. to skip
+/// re-encoding files that are already heavily compressed.
+pub fn estimate_quality(data: &[u8]) -> Result<QualityEstimate, ProbeError> {
+    let parser = open_parser(data)?;
+    let primary_data = parser.primary_data().map_err(|_| ProbeError::NoAv1Config)?;
+    let frame = av1_header::parse_frame_filters(&primary_data, &mut None)
+        .map_err(|_| ProbeError::InvalidBitstream)?
+        .into_iter()
+        .next()
+        .ok_or(ProbeError::NoAv1Config)?;
+
+    let quantizer = effective_quantizer(&frame);
+    Ok(QualityEstimate {
+        quantizer,
+        estimated_quality: qp_to_quality(quantizer),
+        confidence: Confidence::FromFrameHeader,
+    })
+}
+
 impl AvifProbe {
     /// Estimated source quality (0-100), or `None` if not extractable.
     pub fn estimated_quality(&self) -> Option<f32> {
@@ -281,6 +342,34 @@ impl AvifProbe {
 
 // ── Internal helpers ────────────────────────────────────────────────
 
+fn open_parser(data: &[u8]) -> Result<zenavif_parse::AvifParser<'_>, ProbeError> {
+    if data.len() < 12 {
+        return Err(ProbeError::TooShort);
+    }
+
+    // Quick ftyp check before handing off to zenavif-parse
+    if &data[4..8] != b"ftyp" {
+        return Err(ProbeError::NotAvif);
+    }
+
+    zenavif_parse::AvifParser::from_bytes(data).map_err(|e| match e {
+        zenavif_parse::Error::UnexpectedEOF => ProbeError::Truncated,
+        zenavif_parse::Error::InvalidData(_) => ProbeError::NotAvif,
+        _ => ProbeError::Truncated,
+    })
+}
+
+/// Base quantizer, or the rounded mean of the per-segment quantizers when
+/// segmentation overrides it.
+fn effective_quantizer(frame: &FrameFilterStats) -> u8 {
+    if frame.segment_q_idx.is_empty() {
+        return frame.base_q_idx;
+    }
+    let sum: u32 = frame.segment_q_idx.iter().map(|&q| q as u32).sum();
+    let count = frame.segment_q_idx.len() as u32;
+    ((sum + count / 2) / count) as u8
+}
+
 /// Map AV1 quantizer (0-255) to quality (0-100).
 ///
 /// AV1 QP 0 = lossless, QP 255 = worst quality.
@@ -361,6 +450,36 @@ mod tests {
         assert!(err == ProbeError::NotAvif || err == ProbeError::Truncated);
     }
 
+    #[test]
+    fn test_effective_quantizer_averages_segments() {
+        let mut frame = FrameFilterStats {
+            base_q_idx: 100,
+            segment_q_idx: Vec::new(),
+            lossless: false,
+            deblock_level: [0; 4],
+            deblock_sharpness: 0,
+            cdef: None,
+            restoration: [RestorationType::None; 3],
+            restoration_unit_size: [0; 3],
+        };
+        assert_eq!(effective_quantizer(&frame), 100);
+        frame.segment_q_idx = vec![80, 100, 131];
+        assert_eq!(effective_quantizer(&frame), 104);
+    }
+
+    #[test]
+    fn test_estimate_quality_rejects_non_avif() {
+        assert_eq!(estimate_quality(&[]).unwrap_err(), ProbeError::TooShort);
+    }
+
+    #[test]
+    fn test_decode_stats_rejects_non_avif() {
+        assert_eq!(decode_stats(&[0; 4]).unwrap_err(), ProbeError::TooShort);
+        let mut data = vec![0u8; 32];
+        data[4..8].copy_from_slice(b"moov");
+        assert_eq!(decode_stats(&data).unwrap_err(), ProbeError::NotAvif);
+    }
+
     /// Probe all test vectors and check that lossless/QP detection works.
     #[test]
     #[ignore] // requires test vectors: cargo test -- --ignored
@@ -5,13 +5,24 @@
 //! for encoding images.
 
 use crate::Result;
+use crate::cache_key::KeyHasher;
 use crate::error::Error;
+use crate::image::{
+    ChromaSampling, ColorPrimaries, ColorRange, ImageInfo, MatrixCoefficients,
+    TransferCharacteristics,
+};
+use crate::remux::{self, MetadataItem};
+use crate::yuv_convert::{YuvMatrix, matrix_coefficients};
 use almost_enough::Stop;
 use imgref::{ImgRef, ImgVec};
-use rgb::{RGB8, RGBA8, Rgb, Rgba};
+use rgb::{Gray, RGB8, RGBA8, Rgb, Rgba};
 use rgb::{RGB16, RGBA16};
 use whereat::at;
 
+/// T.35 country code (United States), provider code (Samsung), provider
+/// oriented code and application identifier that open an HDR10+ message.
+const HDR10_PLUS_T35_HEADER: [u8; 6] = [0xb5, 0x00, 0x3c, 0x00, 0x01, 0x04];
+
 /// Pre-encoded gain map data for embedding in an AVIF file.
 ///
 /// Contains a pre-encoded AV1 bitstream of the gain map image plus the
@@ -45,6 +56,48 @@ pub struct EncodedImage {
     pub alpha_byte_size: usize,
 }
 
+impl EncodedImage {
+    /// Add EXIF metadata to the finished file without re-encoding.
+    ///
+    /// For pipelines that only know the metadata once the pixels are
+    /// encoded, such as hashes over the encoded image. `exif` is the item
+    /// payload, with the same offset prefix as [`EncoderConfig::exif`].
+    /// The metadata is appended
+    /// in a new `mdat` and the `meta` box is rewritten to point at it; the
+    /// AV1 payloads are not touched. Fails if the file already has EXIF.
+    pub fn with_exif(mut self, exif: &[u8]) -> Result<Self> {
+        self.avif_file = remux::add_metadata_item(&self.avif_file, MetadataItem::Exif(exif))?;
+        Ok(self)
+    }
+
+    /// Add XMP metadata to the finished file without re-encoding.
+    ///
+    /// Works like [`with_exif`](Self::with_exif). Fails if the file already
+    /// has XMP.
+    pub fn with_xmp(mut self, xmp: &[u8]) -> Result<Self> {
+        self.avif_file = remux::add_metadata_item(&self.avif_file, MetadataItem::Xmp(xmp))?;
+        Ok(self)
+    }
+
+    /// Attach HDR10+ dynamic metadata (SMPTE ST 2094-40) to the primary
+    /// item without re-encoding.
+    ///
+    /// `t35` is the complete ITU-T T.35 message as carried in AV1 metadata
+    /// OBUs, starting with the country code. It is stored unchanged in an
+    /// `it35` item property and read back as [`ImageInfo::hdr10_plus`].
+    /// Fails with [`Error::Unsupported`] if the message header does not
+    /// identify HDR10+ or the image already has an `it35` property.
+    pub fn with_hdr10_plus(mut self, t35: &[u8]) -> Result<Self> {
+        if !t35.starts_with(&HDR10_PLUS_T35_HEADER) {
+            return Err(at!(Error::Unsupported(
+                "T.35 message is not HDR10+ (ST 2094-40) metadata",
+            )));
+        }
+        self.avif_file = remux::add_primary_property(&self.avif_file, b"it35", t35)?;
+        Ok(self)
+    }
+}
+
 /// Bit depth for encoding
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum EncodeBitDepth {
@@ -77,8 +130,70 @@ pub enum EncodeAlphaMode {
     UnassociatedDirty,
     /// Premultiplied alpha
     Premultiplied,
+    /// Pick [`UnassociatedClean`](Self::UnassociatedClean) or
+    /// [`UnassociatedDirty`](Self::UnassociatedDirty) per image.
+    ///
+    /// Colors under fully transparent pixels matter when they sit next to
+    /// soft, partially transparent edges: resampling and premultiplying
+    /// consumers blend them into visible pixels there, so they're kept.
+    /// Hard-edged cut-outs such as sprite sheets get the cleaned colors,
+    /// which compress better. Resolved for 8-bit still images; elsewhere
+    /// it behaves as `UnassociatedClean`.
+    Auto,
 }
 
+impl EncodeAlphaMode {
+    /// Resolve [`Auto`](Self::Auto) for an image whose alpha at `(x, y)`
+    /// is `alpha(x, y)`, with `max` fully opaque.
+    pub(crate) fn resolve(
+        self,
+        width: usize,
+        height: usize,
+        max: u16,
+        alpha: impl Fn(usize, usize) -> u16,
+    ) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        // Transparent pixels next to visible ones, and those among them
+        // next to partially transparent ones.
+        let (mut edge, mut soft) = (0u64, 0u64);
+        for y in 0..height {
+            for x in 0..width {
+                if alpha(x, y) != 0 {
+                    continue;
+                }
+                let (mut visible, mut partial) = (false, false);
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for (nx, ny) in neighbours {
+                    if nx < width && ny < height {
+                        let a = alpha(nx, ny);
+                        visible |= a != 0;
+                        partial |= a != 0 && a != max;
+                    }
+                }
+                edge += u64::from(visible);
+                soft += u64::from(partial);
+            }
+        }
+        if edge > 0 && soft * SOFT_EDGE_DENOMINATOR >= edge {
+            Self::UnassociatedDirty
+        } else {
+            Self::UnassociatedClean
+        }
+    }
+}
+
+/// [`EncodeAlphaMode::Auto`] keeps hidden colors once at least one in this
+/// many transparent edge pixels borders soft alpha, so a few stray
+/// antialiased pixels don't decide it.
+const SOFT_EDGE_DENOMINATOR: u64 = 4;
+
 /// Pixel value range for AV1 encoding.
 ///
 /// Full range uses the entire value range (0–255 for 8-bit, 0–1023 for 10-bit).
@@ -164,8 +279,17 @@ pub struct EncoderConfig {
     pub(crate) matrix_coefficients: Option<u8>,
     /// Pixel range: full (0–255/0–1023) or limited/narrow (16–235/64–940)
     pub(crate) pixel_range: Option<EncodePixelRange>,
+    /// Chroma sampling of the coded stream
+    pub(crate) chroma: Option<ChromaSampling>,
     /// Pre-encoded gain map for UltraHDR / ISO 21496-1
     pub(crate) gain_map: Option<GainMapConfig>,
+    /// Upper bound on estimated encoder memory in bytes
+    pub(crate) max_memory_bytes: Option<usize>,
+    /// Longest side encoded as a single frame; larger images become grids
+    pub(crate) grid_threshold: u32,
+    /// Emit an alpha item for opaque RGBA8 input too, as grid tiles of a
+    /// translucent image need
+    pub(crate) keep_alpha: bool,
     /// Enable AV1 quantization matrices (imazen/rav1e fork)
     #[cfg(feature = "encode-imazen")]
     pub(crate) enable_qm: bool,
@@ -205,7 +329,11 @@ impl Default for EncoderConfig {
             transfer_characteristics: None,
             matrix_coefficients: None,
             pixel_range: None,
+            chroma: None,
             gain_map: None,
+            max_memory_bytes: None,
+            grid_threshold: MAX_FRAME_SIDE as u32,
+            keep_alpha: false,
             #[cfg(feature = "encode-imazen")]
             enable_qm: true,
             #[cfg(feature = "encode-imazen")]
@@ -253,7 +381,10 @@ impl EncoderConfig {
 
     /// Set separate quality for the alpha channel
     ///
-    /// If not set, uses the same quality as color.
+    /// If not set, it's picked per image from the alpha content: planes with
+    /// at most two levels (cut-out masks) are coded at 100, which costs
+    /// little for flat areas, and soft or gradient alpha halfway between
+    /// the color quality and 100. Animations get one value for all frames.
     pub fn alpha_quality(mut self, quality: f32) -> Self {
         self.alpha_quality = Some(quality);
         self
@@ -288,6 +419,9 @@ impl EncoderConfig {
     }
 
     /// Embed EXIF metadata in the output
+    ///
+    /// `exif_data` is stored as the Exif item payload: a big-endian u32
+    /// offset to the TIFF header (usually 0), then the EXIF data.
     pub fn exif(mut self, exif_data: Vec<u8>) -> Self {
         self.exif = Some(exif_data);
         self
@@ -351,6 +485,12 @@ impl EncoderConfig {
     /// Set CICP matrix coefficients code point (ITU-T H.273).
     ///
     /// Common values: 0 = Identity/RGB, 1 = BT.709, 6 = BT.601, 9 = BT.2020.
+    ///
+    /// Defaults to BT.601 for [`EncodeColorModel::YCbCr`] and identity for
+    /// [`EncodeColorModel::Rgb`]. Identity must go with the RGB model and
+    /// vice versa. 16-bit stills accept BT.709, BT.601 and BT.2020; other
+    /// inputs are converted by ravif, which only codes BT.601. Unsupported
+    /// combinations fail with [`Error::Unsupported`] before encoding.
     pub fn matrix_coefficients(mut self, mc: u8) -> Self {
         self.matrix_coefficients = Some(mc);
         self
@@ -358,13 +498,96 @@ impl EncoderConfig {
 
     /// Set pixel value range for AV1 encoding.
     ///
-    /// Default is full range. Use limited/narrow range for broadcast content
-    /// that already uses studio levels (16–235 for 8-bit, 64–940 for 10-bit).
+    /// Default is full range. RGB input is always full range; with limited
+    /// range it is scaled into studio levels (16–235 for 8-bit, 64–940 for
+    /// 10-bit) and the stream is signalled as limited, which video pipelines
+    /// often expect.
     pub fn pixel_range(mut self, range: EncodePixelRange) -> Self {
         self.pixel_range = Some(range);
         self
     }
 
+    /// Set the sample range of the encoded stream, as the decoder reports it
+    /// in [`ImageInfo::color_range`].
+    ///
+    /// Same as [`pixel_range`](Self::pixel_range), with the decoder's range
+    /// type, so a transcode can keep the source's range:
+    /// `config.output_range(info.color_range)`.
+    pub fn output_range(self, range: ColorRange) -> Self {
+        self.pixel_range(match range {
+            ColorRange::Full => EncodePixelRange::Full,
+            ColorRange::Limited => EncodePixelRange::Limited,
+        })
+    }
+
+    /// Set the chroma sampling of the encoded stream.
+    ///
+    /// Default is 4:4:4, which keeps colored text and UI edges from
+    /// bleeding. [`ChromaSampling::Cs420`] halves chroma resolution for
+    /// smaller photographic files; it needs a YCbCr matrix, since AV1 forbids
+    /// identity with subsampled chroma. zenravif can't code
+    /// [`Cs422`](ChromaSampling::Cs422), so it fails with
+    /// [`Error::Unsupported`] before encoding instead of silently coding
+    /// 4:4:4. Gray input always codes as
+    /// [`Monochrome`](ChromaSampling::Monochrome) and ignores this setting;
+    /// asking for monochrome from color input fails the same way, since
+    /// dropping chroma is a conversion the caller should choose.
+    pub fn chroma(mut self, sampling: ChromaSampling) -> Self {
+        self.chroma = Some(sampling);
+        self
+    }
+
+    /// Fill unset metadata from a decoded image's [`ImageInfo`].
+    ///
+    /// Copies CICP primaries and transfer characteristics (unless
+    /// unspecified), the ICC profile, EXIF, XMP, rotation, mirror, content
+    /// light level and mastering display. Values already set on `self` win.
+    /// The matrix coefficients describe how the source was coded, not how
+    /// this encode codes it, so they are left to the encoder.
+    pub fn with_image_info(mut self, info: &ImageInfo) -> Self {
+        if info.color_primaries != ColorPrimaries::UNKNOWN {
+            self.color_primaries.get_or_insert(info.color_primaries.0);
+        }
+        if info.transfer_characteristics != TransferCharacteristics::UNKNOWN {
+            self.transfer_characteristics
+                .get_or_insert(info.transfer_characteristics.0);
+        }
+        if self.icc_profile.is_none() {
+            self.icc_profile = info.icc_profile.clone();
+        }
+        if self.exif.is_none() {
+            // ImageInfo holds EXIF without the item's TIFF header offset.
+            self.exif = info
+                .exif
+                .as_ref()
+                .map(|tiff| [&[0u8; 4][..], tiff].concat());
+        }
+        if self.xmp.is_none() {
+            self.xmp = info.xmp.clone();
+        }
+        if let Some(rotation) = &info.rotation {
+            // irot stores quarter turns counter-clockwise.
+            self.rotation.get_or_insert((rotation.angle / 90) as u8);
+        }
+        if let Some(mirror) = &info.mirror {
+            self.mirror.get_or_insert(mirror.axis);
+        }
+        if let Some(cll) = &info.content_light_level {
+            self.content_light_level
+                .get_or_insert((cll.max_content_light_level, cll.max_pic_average_light_level));
+        }
+        if let Some(mdcv) = &info.mastering_display {
+            self.mastering_display
+                .get_or_insert(MasteringDisplayConfig {
+                    primaries: mdcv.primaries,
+                    white_point: mdcv.white_point,
+                    max_luminance: mdcv.max_luminance,
+                    min_luminance: mdcv.min_luminance,
+                });
+        }
+        self
+    }
+
     /// Embed a pre-encoded gain map for UltraHDR / ISO 21496-1.
     ///
     /// The gain map enables SDR/HDR tone mapping: the primary image is the SDR
@@ -393,6 +616,34 @@ impl EncoderConfig {
         self
     }
 
+    /// Limit the estimated peak memory of the encoder, in bytes.
+    ///
+    /// The estimate covers rav1e's source, reconstruction, and reference
+    /// frames plus the state of each tile rav1e splits a frame into, one
+    /// per thread down to a minimum tile size. It is derived from the image
+    /// dimensions (the largest frame's, for an animation), bit depth, alpha,
+    /// speed, and thread count. When the estimate exceeds the
+    /// limit, the thread count is reduced until it fits; if a single thread
+    /// still does not fit, encoding fails with [`Error::ResourceLimit`]
+    /// before any work starts.
+    pub fn max_memory_bytes(mut self, bytes: usize) -> Self {
+        self.max_memory_bytes = Some(bytes);
+        self
+    }
+
+    /// Longest side, in pixels, that [`encode_with`](crate::encode_with)
+    /// codes as a single frame.
+    ///
+    /// Larger images are split into equal tiles of at most this size (and at
+    /// least 64 pixels), each encoded separately and stored as a HEIF image
+    /// grid. Default and maximum: 65535, the AV1 frame limit, so only
+    /// images that could not be encoded otherwise become grids. Lower it to
+    /// keep tiles within the levels hardware decoders support.
+    pub fn grid_threshold(mut self, max_side: u32) -> Self {
+        self.grid_threshold = max_side.min(MAX_FRAME_SIDE as u32);
+        self
+    }
+
     /// Enable/disable AV1 quantization matrices (imazen/rav1e fork).
     ///
     /// QM applies frequency-dependent quantization weights for ~10% BD-rate improvement.
@@ -442,10 +693,64 @@ impl EncoderConfig {
             .with_vaq(true, 0.5)
             .with_still_image_tuning(true)
     }
+
+    /// Stable 64-bit key over every setting that affects the encoded bytes.
+    ///
+    /// Meant for caching encode results by `(input hash, config key)`.
+    /// Thread count and the memory limit are left out, as they don't change
+    /// the output. Keys are equal for equal settings on every platform, and
+    /// stay the same across patch releases; a minor release may change them
+    /// when it adds settings.
+    pub fn cache_key(&self) -> u64 {
+        let key = KeyHasher::new(b"zenavif-encoder-v1")
+            .u8(self.backend as u8)
+            .f32(self.quality)
+            .u8(self.speed)
+            .option(self.alpha_quality, KeyHasher::f32)
+            .u8(self.bit_depth as u8)
+            .u8(self.color_model as u8)
+            .u8(self.alpha_color_mode as u8)
+            .option(self.exif.as_deref(), KeyHasher::bytes)
+            .option(self.xmp.as_deref(), KeyHasher::bytes)
+            .option(self.icc_profile.as_deref(), KeyHasher::bytes)
+            .option(self.rotation, KeyHasher::u8)
+            .option(self.mirror, KeyHasher::u8)
+            .option(self.content_light_level, |h, (cll, fall)| {
+                h.u16(cll).u16(fall)
+            })
+            .option(self.mastering_display.as_ref(), |h, md| {
+                let h = md.primaries.iter().fold(h, |h, &(x, y)| h.u16(x).u16(y));
+                h.u16(md.white_point.0)
+                    .u16(md.white_point.1)
+                    .u32(md.max_luminance)
+                    .u32(md.min_luminance)
+            })
+            .option(self.color_primaries, KeyHasher::u8)
+            .option(self.transfer_characteristics, KeyHasher::u8)
+            .option(self.matrix_coefficients, KeyHasher::u8)
+            .option(self.pixel_range, |h, range| h.u8(range as u8))
+            .option(self.chroma, |h, sampling| h.u8(sampling as u8))
+            .option(self.gain_map.as_ref(), |h, gm| {
+                h.bytes(&gm.av1_data)
+                    .u32(gm.width)
+                    .u32(gm.height)
+                    .u8(gm.bit_depth)
+                    .bytes(&gm.metadata)
+            })
+            .u32(self.grid_threshold);
+        #[cfg(feature = "encode-imazen")]
+        let key = key
+            .bool(self.enable_qm)
+            .bool(self.enable_vaq)
+            .u64(self.vaq_strength.to_bits())
+            .bool(self.tune_still_image)
+            .bool(self.lossless);
+        key.finish()
+    }
 }
 
 /// Convert a CICP color primaries code point to the ravif enum.
-fn cicp_to_color_primaries(cp: u8) -> ravif::ColorPrimaries {
+pub(crate) fn cicp_to_color_primaries(cp: u8) -> ravif::ColorPrimaries {
     match cp {
         1 => ravif::ColorPrimaries::BT709,
         4 => ravif::ColorPrimaries::BT470M,
@@ -463,7 +768,7 @@ fn cicp_to_color_primaries(cp: u8) -> ravif::ColorPrimaries {
 }
 
 /// Convert a CICP transfer characteristics code point to the ravif enum.
-fn cicp_to_transfer_characteristics(tc: u8) -> ravif::TransferCharacteristics {
+pub(crate) fn cicp_to_transfer_characteristics(tc: u8) -> ravif::TransferCharacteristics {
     match tc {
         1 => ravif::TransferCharacteristics::BT709,
         4 => ravif::TransferCharacteristics::BT470M,
@@ -484,9 +789,11 @@ fn cicp_to_transfer_characteristics(tc: u8) -> ravif::TransferCharacteristics {
     }
 }
 
-/// Build a ravif Encoder from our config
 /// Resolve `EncodeBitDepth::Auto` based on whether the input is 8-bit or 16-bit.
-fn resolve_bit_depth(configured: EncodeBitDepth, input_is_16bit: bool) -> ravif::BitDepth {
+pub(crate) fn resolve_bit_depth(
+    configured: EncodeBitDepth,
+    input_is_16bit: bool,
+) -> ravif::BitDepth {
     match configured {
         EncodeBitDepth::Eight => ravif::BitDepth::Eight,
         EncodeBitDepth::Ten => ravif::BitDepth::Ten,
@@ -500,25 +807,403 @@ fn resolve_bit_depth(configured: EncodeBitDepth, input_is_16bit: bool) -> ravif:
     }
 }
 
-fn build_ravif_encoder(
+/// Who turns RGB input into coded AV1 samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Conversion {
+    /// ravif's built-in conversion: BT.601 YCbCr, or identity for RGB.
+    Ravif,
+    /// zenavif converts and hands ravif raw planes.
+    Planes,
+}
+
+/// ravif converts RGB with BT.601 coefficients, or not at all.
+fn ravif_converts(matrix: MatrixCoefficients) -> bool {
+    matches!(
+        matrix,
+        MatrixCoefficients::IDENTITY | MatrixCoefficients::BT470BG | MatrixCoefficients::BT601
+    )
+}
+
+/// How RGB input maps to coded samples: matrix, range, chroma sampling, and
+/// depth, resolved from an [`EncoderConfig`] and validated together before
+/// any encoding starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SignalFormat {
+    pub(crate) matrix: MatrixCoefficients,
+    pub(crate) range: EncodePixelRange,
+    pub(crate) sampling: ChromaSampling,
+    pub(crate) depth: ravif::BitDepth,
+}
+
+impl SignalFormat {
+    fn resolve(
+        config: &EncoderConfig,
+        input_is_16bit: bool,
+        conversion: Conversion,
+    ) -> Result<Self> {
+        let rgb_model = config.color_model == EncodeColorModel::Rgb;
+        let matrix = match config.matrix_coefficients.map(MatrixCoefficients) {
+            None | Some(MatrixCoefficients::UNKNOWN) if rgb_model => MatrixCoefficients::IDENTITY,
+            None | Some(MatrixCoefficients::UNKNOWN) => MatrixCoefficients::BT601,
+            Some(mc) => mc,
+        };
+        if rgb_model != (matrix == MatrixCoefficients::IDENTITY) {
+            return Err(at!(Error::Unsupported(
+                "identity matrix coefficients and EncodeColorModel::Rgb must be used together",
+            )));
+        }
+        let supported = match conversion {
+            Conversion::Ravif => ravif_converts(matrix),
+            Conversion::Planes => matches!(
+                matrix,
+                MatrixCoefficients::IDENTITY
+                    | MatrixCoefficients::BT709
+                    | MatrixCoefficients::BT470BG
+                    | MatrixCoefficients::BT601
+                    | MatrixCoefficients::BT2020_NCL
+            ),
+        };
+        if !supported {
+            return Err(at!(Error::Unsupported(
+                "matrix coefficients not supported by this encode path",
+            )));
+        }
+        let sampling = config.chroma.unwrap_or(ChromaSampling::Cs444);
+        // AV1 forbids the identity matrix with subsampled chroma.
+        if matrix == MatrixCoefficients::IDENTITY && sampling != ChromaSampling::Cs444 {
+            return Err(at!(Error::Unsupported(
+                "identity matrix coefficients require 4:4:4 chroma",
+            )));
+        }
+        if matches!(sampling, ChromaSampling::Cs422 | ChromaSampling::Monochrome) {
+            return Err(at!(Error::Unsupported(
+                "zenravif codes 4:4:4 and 4:2:0 chroma only"
+            )));
+        }
+        Ok(Self {
+            matrix,
+            range: config.pixel_range.unwrap_or_default(),
+            sampling,
+            depth: resolve_bit_depth(config.bit_depth, input_is_16bit),
+        })
+    }
+
+    fn ravif_matrix(&self) -> ravif::MatrixCoefficients {
+        match self.matrix {
+            MatrixCoefficients::IDENTITY => ravif::MatrixCoefficients::Identity,
+            MatrixCoefficients::BT709 => ravif::MatrixCoefficients::BT709,
+            MatrixCoefficients::BT470BG => ravif::MatrixCoefficients::BT470BG,
+            MatrixCoefficients::BT2020_NCL => ravif::MatrixCoefficients::BT2020NCL,
+            _ => ravif::MatrixCoefficients::BT601,
+        }
+    }
+
+    /// Whether ravif's built-in RGB conversion produces this matrix.
+    fn ravif_converts(&self) -> bool {
+        ravif_converts(self.matrix)
+    }
+
+    /// Chroma layout ravif codes; `resolve` has already rejected the
+    /// samplings it cannot produce.
+    fn ravif_subsampling(&self) -> ravif::ChromaSubsampling {
+        match self.sampling {
+            ChromaSampling::Cs420 => ravif::ChromaSubsampling::Yuv420,
+            _ => ravif::ChromaSubsampling::Yuv444,
+        }
+    }
+
+    fn ravif_range(&self) -> ravif::PixelRange {
+        match self.range {
+            EncodePixelRange::Full => ravif::PixelRange::Full,
+            EncodePixelRange::Limited => ravif::PixelRange::Limited,
+        }
+    }
+
+    /// Map a full-range 10-bit RGB pixel to coded planes: `[Y, Cb, Cr]`, or
+    /// `[G, B, R]` for the identity matrix.
+    fn rgb10_to_planes(&self) -> impl Fn([u16; 3]) -> [u16; 3] + use<> {
+        let identity = self.matrix == MatrixCoefficients::IDENTITY;
+        let (kr, kb) = matrix_coefficients(match self.matrix {
+            MatrixCoefficients::BT709 => YuvMatrix::Bt709,
+            MatrixCoefficients::BT2020_NCL => YuvMatrix::Bt2020,
+            _ => YuvMatrix::Bt601,
+        });
+        let kg = 1.0 - kr - kb;
+        let limited = self.range == EncodePixelRange::Limited;
+        move |[r, g, b]| {
+            let (r, g, b) = (r as f32, g as f32, b as f32);
+            // Chroma stays centred on zero until quantisation.
+            let (planes, chroma) = if identity {
+                ([g, b, r], [false; 3])
+            } else {
+                let y = kr * r + kg * g + kb * b;
+                let cb = (b - y) / (2.0 * (1.0 - kb));
+                let cr = (r - y) / (2.0 * (1.0 - kr));
+                ([y, cb, cr], [false, true, true])
+            };
+            let mut out = [0u16; 3];
+            for ((out, v), chroma) in out.iter_mut().zip(planes).zip(chroma) {
+                let v = match (chroma, limited) {
+                    (false, false) => v,
+                    (true, false) => v + 512.0,
+                    (false, true) => 64.0 + v * (876.0 / 1023.0),
+                    (true, true) => 512.0 + v * (896.0 / 1023.0),
+                };
+                *out = v.round().clamp(0.0, 1023.0) as u16;
+            }
+            out
+        }
+    }
+}
+
+/// Frames rav1e keeps resident for a still image: source, reconstruction,
+/// one reference, and the RGB→YUV conversion buffer.
+const STILL_RESIDENT_FRAMES: u64 = 4;
+/// Frames rav1e keeps resident for animation: the still-image set plus the
+/// reference frame pool and lookahead queue.
+const ANIMATION_RESIDENT_FRAMES: u64 = 16;
+/// Fixed per-tile overhead (tile contexts, entropy coder, RDO scratch).
+const PER_TILE_FIXED_BYTES: u64 = 8 << 20;
+/// Per-tile superblock-row buffers, per pixel of tile width.
+const PER_TILE_BYTES_PER_COLUMN: u64 = 64 * 3 * 4;
+
+/// Tile columns and rows rav1e splits a frame into: one tile per thread,
+/// but none smaller than ravif's minimum tile area for `speed`, halving
+/// the longer tile side until there are enough.
+fn encode_tiles(width: usize, height: usize, speed: u8, threads: usize) -> (usize, usize) {
+    // ravif doubles these for its high-quality presets; the smaller sizes
+    // give more tiles and so the larger estimate.
+    let min_tile_size: usize = match speed {
+        0 => 4096,
+        1 => 2048,
+        2 => 1024,
+        3 => 512,
+        4 => 256,
+        _ => 128,
+    };
+    let tiles = threads
+        .min(width * height / (min_tile_size * min_tile_size))
+        .max(1);
+    let (mut cols, mut rows) = (1, 1);
+    while cols * rows < tiles {
+        if height / rows >= width / cols {
+            rows *= 2;
+        } else {
+            cols *= 2;
+        }
+    }
+    (cols, rows)
+}
+
+/// Estimate peak encoder memory for one image (or animation) of this size.
+///
+/// For an animation, `width` and `height` are those of its largest frame.
+fn estimate_encode_memory(
+    width: usize,
+    height: usize,
+    bit_depth: ravif::BitDepth,
+    has_alpha: bool,
+    speed: u8,
+    threads: usize,
+    animated: bool,
+) -> u64 {
+    let bytes_per_sample: u64 = match bit_depth {
+        ravif::BitDepth::Eight => 1,
+        _ => 2,
+    };
+    let pixels = width as u64 * height as u64;
+    // 4:4:4 colour planes plus a monochrome alpha plane
+    let planes = if has_alpha { 4 } else { 3 };
+    let frame_bytes = pixels * planes * bytes_per_sample;
+    let resident = if animated {
+        ANIMATION_RESIDENT_FRAMES
+    } else {
+        STILL_RESIDENT_FRAMES
+    };
+    // Colour and alpha are separate encodes, each tiled for all threads.
+    let (tile_cols, tile_rows) = encode_tiles(width, height, speed, threads);
+    let per_tile =
+        PER_TILE_FIXED_BYTES + width.div_ceil(tile_cols) as u64 * PER_TILE_BYTES_PER_COLUMN;
+    let tiles = (tile_cols * tile_rows) as u64 * if has_alpha { 2 } else { 1 };
+    frame_bytes
+        .saturating_mul(resident)
+        .saturating_add(per_tile.saturating_mul(tiles))
+}
+
+/// Size of the largest animation frame, 0×0 for none.
+///
+/// The memory estimate budgets for it: ravif rejects frames of mixed sizes,
+/// but only after every frame has been converted for it.
+fn largest_frame(sizes: impl Iterator<Item = (usize, usize)>) -> (usize, usize) {
+    sizes.fold((0, 0), |(w, h), (fw, fh)| (w.max(fw), h.max(fh)))
+}
+
+/// Largest frame side rav1e accepts; the AV1 syntax itself allows 65536.
+pub(crate) const MAX_FRAME_SIDE: usize = u16::MAX as usize;
+
+/// Fail before any encoding work on frames that can't be coded as one AV1
+/// frame, instead of deep inside the encoder.
+///
+/// Sizes beyond the largest AV1 level (6.3: 16384×8704, 35.6 MP) are
+/// allowed; they are signalled as unconstrained, which software decoders
+/// handle but some hardware decoders refuse.
+pub(crate) fn check_dimensions(width: usize, height: usize) -> Result<()> {
+    if width == 0 || height == 0 {
+        return Err(at!(Error::Encode(format!(
+            "cannot encode a {width}x{height} image; both sides must be nonzero"
+        ))));
+    }
+    if width > MAX_FRAME_SIDE || height > MAX_FRAME_SIDE {
+        return Err(at!(Error::Encode(format!(
+            "{width}x{height} exceeds the {MAX_FRAME_SIDE}-pixel side limit of a single AV1 \
+             frame; encode it with `encode_with`, which splits it into a grid"
+        ))));
+    }
+    Ok(())
+}
+
+/// Apply `max_memory_bytes`, returning the thread count to encode with.
+///
+/// Reduces the thread count until the estimate fits, and fails if even a
+/// single thread exceeds the limit.
+pub(crate) fn threads_within_memory_limit(
     config: &EncoderConfig,
-    stop: almost_enough::StopToken,
+    width: usize,
+    height: usize,
+    has_alpha: bool,
     input_is_16bit: bool,
-) -> ravif::Encoder<'_> {
+    animated: bool,
+) -> Result<Option<usize>> {
+    let Some(limit) = config.max_memory_bytes else {
+        return Ok(config.threads);
+    };
+    let bit_depth = resolve_bit_depth(config.bit_depth, input_is_16bit);
+    let requested = config.threads.unwrap_or_else(|| {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    });
+    let estimate = |threads| {
+        estimate_encode_memory(
+            width,
+            height,
+            bit_depth,
+            has_alpha,
+            config.speed,
+            threads,
+            animated,
+        )
+    };
+    if estimate(requested) <= limit as u64 {
+        return Ok(config.threads);
+    }
+    match (1..requested).rev().find(|&t| estimate(t) <= limit as u64) {
+        Some(threads) => Ok(Some(threads)),
+        None => Err(at!(Error::ResourceLimit(format!(
+            "estimated encoder memory {} bytes for {width}x{height} exceeds max_memory_bytes {limit}",
+            estimate(1)
+        )))),
+    }
+}
+
+/// How much detail an alpha plane carries, for picking its quality when
+/// [`EncoderConfig::alpha_quality`] is unset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AlphaComplexity {
+    /// At most two distinct levels, e.g. a cut-out mask. Flat areas cost
+    /// next to nothing, so it's coded near-losslessly.
+    Binary,
+    /// Soft edges, gradients or shadows; lossy artifacts show, so it gets
+    /// more bits than color.
+    Gradient,
+}
+
+impl AlphaComplexity {
+    /// Classify alpha samples of any bit depth; stops at the third level.
+    pub(crate) fn classify(alpha: impl IntoIterator<Item = u16>) -> Self {
+        let mut alpha = alpha.into_iter();
+        let Some(first) = alpha.next() else {
+            return Self::Binary;
+        };
+        let mut second = None;
+        for a in alpha {
+            match second {
+                _ if a == first => {}
+                None => second = Some(a),
+                Some(s) if s == a => {}
+                Some(_) => return Self::Gradient,
+            }
+        }
+        Self::Binary
+    }
+
+    /// Alpha quality for this plane given the color quality.
+    pub(crate) fn alpha_quality(self, quality: f32) -> f32 {
+        match self {
+            Self::Binary => 100.0,
+            Self::Gradient => (quality + 100.0) / 2.0,
+        }
+    }
+}
+
+/// Set the alpha quality from the plane's content unless the config
+/// already has one.
+fn with_auto_alpha_quality<'a>(
+    enc: ravif::Encoder<'a>,
+    config: &EncoderConfig,
+    alpha: impl IntoIterator<Item = u16>,
+) -> ravif::Encoder<'a> {
+    if config.alpha_quality.is_some() {
+        return enc;
+    }
+    enc.with_alpha_quality(AlphaComplexity::classify(alpha).alpha_quality(config.quality))
+}
+
+/// Resolve [`EncodeAlphaMode::Auto`] for an 8-bit image.
+fn with_auto_alpha_mode<'a>(
+    enc: ravif::Encoder<'a>,
+    config: &EncoderConfig,
+    img: ImgRef<'_, Rgba<u8>>,
+) -> ravif::Encoder<'a> {
+    if config.alpha_color_mode != EncodeAlphaMode::Auto {
+        return enc;
+    }
+    let mode = config
+        .alpha_color_mode
+        .resolve(img.width(), img.height(), 255, |x, y| img[(x, y)].a.into());
+    enc.with_alpha_color_mode(ravif_alpha_mode(mode))
+}
+
+fn ravif_alpha_mode(mode: EncodeAlphaMode) -> ravif::AlphaColorMode {
+    match mode {
+        EncodeAlphaMode::UnassociatedClean | EncodeAlphaMode::Auto => {
+            ravif::AlphaColorMode::UnassociatedClean
+        }
+        EncodeAlphaMode::UnassociatedDirty => ravif::AlphaColorMode::UnassociatedDirty,
+        EncodeAlphaMode::Premultiplied => ravif::AlphaColorMode::Premultiplied,
+    }
+}
+
+/// Build a ravif Encoder from our config
+fn build_ravif_encoder<'a>(
+    config: &'a EncoderConfig,
+    stop: almost_enough::StopToken,
+    format: &SignalFormat,
+    threads: Option<usize>,
+) -> ravif::Encoder<'a> {
     let mut enc = ravif::Encoder::new()
         .with_quality(config.quality)
         .with_speed(config.speed)
-        .with_bit_depth(resolve_bit_depth(config.bit_depth, input_is_16bit))
-        .with_internal_color_model(match config.color_model {
-            EncodeColorModel::YCbCr => ravif::ColorModel::YCbCr,
-            EncodeColorModel::Rgb => ravif::ColorModel::RGB,
-        })
-        .with_alpha_color_mode(match config.alpha_color_mode {
-            EncodeAlphaMode::UnassociatedClean => ravif::AlphaColorMode::UnassociatedClean,
-            EncodeAlphaMode::UnassociatedDirty => ravif::AlphaColorMode::UnassociatedDirty,
-            EncodeAlphaMode::Premultiplied => ravif::AlphaColorMode::Premultiplied,
+        .with_bit_depth(format.depth)
+        .with_internal_color_model(if format.matrix == MatrixCoefficients::IDENTITY {
+            ravif::ColorModel::RGB
+        } else {
+            ravif::ColorModel::YCbCr
         })
-        .with_num_threads(config.threads);
+        .with_pixel_range(format.ravif_range())
+        .with_chroma_subsampling(format.ravif_subsampling())
+        .with_alpha_color_mode(ravif_alpha_mode(config.alpha_color_mode))
+        .with_num_threads(threads);
 
     if let Some(aq) = config.alpha_quality {
         enc = enc.with_alpha_quality(aq);
@@ -574,12 +1259,6 @@ fn build_ravif_encoder(
     if let Some(tc) = config.transfer_characteristics {
         enc = enc.with_transfer_characteristics(cicp_to_transfer_characteristics(tc));
     }
-    if let Some(pr) = config.pixel_range {
-        enc = enc.with_pixel_range(match pr {
-            EncodePixelRange::Full => ravif::PixelRange::Full,
-            EncodePixelRange::Limited => ravif::PixelRange::Limited,
-        });
-    }
     if let Some(ref gm) = config.gain_map {
         enc = enc.with_gain_map(ravif::GainMapData {
             av1_data: gm.av1_data.clone(),
@@ -608,6 +1287,9 @@ fn build_ravif_encoder(
 
 /// Encode an 8-bit RGB image to AVIF
 ///
+/// Matrix coefficients other than BT.601 and identity are converted by
+/// zenavif rather than ravif, and code 10-bit as in [`encode_rgb16`].
+///
 /// # Arguments
 ///
 /// * `img` - RGB8 image buffer
@@ -619,13 +1301,25 @@ pub fn encode_rgb8(
     stop: almost_enough::StopToken,
 ) -> Result<EncodedImage> {
     stop.check().map_err(|e| at!(Error::from(e)))?;
+    check_dimensions(img.width(), img.height())?;
 
     #[cfg(feature = "encode-svtav1")]
     if config.backend == Av1Backend::Svtav1 {
         return encode_rgb8_svtav1(img, config);
     }
 
-    let enc = build_ravif_encoder(config, stop, false);
+    let format = SignalFormat::resolve(config, false, Conversion::Planes)?;
+    if !format.ravif_converts() {
+        let wide: Vec<Rgb<u16>> = img.pixels().map(widen_rgb8).collect();
+        return encode_rgb16(
+            ImgVec::new(wide, img.width(), img.height()).as_ref(),
+            config,
+            stop,
+        );
+    }
+    let threads =
+        threads_within_memory_limit(config, img.width(), img.height(), false, false, false)?;
+    let enc = build_ravif_encoder(config, stop, &format, threads);
     let result = enc
         .encode_rgb(img)
         .map_err(|e: ravif::Error| at!(Error::Encode(e.to_string())))?;
@@ -636,6 +1330,12 @@ pub fn encode_rgb8(
     })
 }
 
+/// Widen 8-bit samples to the full 16-bit range.
+fn widen_rgb8(p: Rgb<u8>) -> Rgb<u16> {
+    let widen = |v: u8| u16::from(v) * 257;
+    Rgb::new(widen(p.r), widen(p.g), widen(p.b))
+}
+
 /// Encode an 8-bit RGB image using the svtav1-rs backend.
 #[cfg(feature = "encode-svtav1")]
 fn encode_rgb8_svtav1(img: ImgRef<'_, Rgb<u8>>, config: &EncoderConfig) -> Result<EncodedImage> {
@@ -673,7 +1373,7 @@ fn encode_rgb8_svtav1(img: ImgRef<'_, Rgb<u8>>, config: &EncoderConfig) -> Resul
         config.transfer_characteristics,
         config.matrix_coefficients,
     ) {
-        let full_range = config.pixel_range == Some(EncodePixelRange::Full);
+        let full_range = config.pixel_range.unwrap_or_default() == EncodePixelRange::Full;
         enc = enc.with_color_space(cp, tc, mc, full_range);
     }
 
@@ -695,6 +1395,9 @@ fn encode_rgb8_svtav1(img: ImgRef<'_, Rgb<u8>>, config: &EncoderConfig) -> Resul
 
 /// Encode an 8-bit RGBA image to AVIF
 ///
+/// Matrix coefficients other than BT.601 and identity are converted by
+/// zenavif rather than ravif, and code 10-bit as in [`encode_rgba16`].
+///
 /// # Arguments
 ///
 /// * `img` - RGBA8 image buffer
@@ -706,7 +1409,30 @@ pub fn encode_rgba8(
     stop: almost_enough::StopToken,
 ) -> Result<EncodedImage> {
     stop.check().map_err(|e| at!(Error::from(e)))?;
-    let enc = build_ravif_encoder(config, stop, false);
+    check_dimensions(img.width(), img.height())?;
+    let format = SignalFormat::resolve(config, false, Conversion::Planes)?;
+    if !format.ravif_converts() {
+        let wide: Vec<Rgba<u16>> = img
+            .pixels()
+            .map(|p| {
+                let Rgb { r, g, b } = widen_rgb8(Rgb::new(p.r, p.g, p.b));
+                Rgba::new(r, g, b, u16::from(p.a) * 257)
+            })
+            .collect();
+        return encode_rgba16(
+            ImgVec::new(wide, img.width(), img.height()).as_ref(),
+            config,
+            stop,
+        );
+    }
+    let threads =
+        threads_within_memory_limit(config, img.width(), img.height(), true, false, false)?;
+    let enc = build_ravif_encoder(config, stop, &format, threads);
+    if config.keep_alpha && img.pixels().all(|p| p.a == 255) {
+        return encode_opaque_rgba8(enc, img, &format);
+    }
+    let enc = with_auto_alpha_quality(enc, config, img.pixels().map(|p| p.a.into()));
+    let enc = with_auto_alpha_mode(enc, config, img);
     let result = enc
         .encode_rgba(img)
         .map_err(|e: ravif::Error| at!(Error::Encode(e.to_string())))?;
@@ -717,6 +1443,117 @@ pub fn encode_rgba8(
     })
 }
 
+/// Encode opaque 8-bit RGBA with its all-opaque alpha plane, which ravif's
+/// `encode_rgba` would leave out.
+///
+/// Samples are converted exactly as `encode_rgba` converts them, so the
+/// result matches what ravif codes for translucent images.
+fn encode_opaque_rgba8(
+    enc: ravif::Encoder<'_>,
+    img: ImgRef<'_, Rgba<u8>>,
+    format: &SignalFormat,
+) -> Result<EncodedImage> {
+    let (width, height) = (img.width(), img.height());
+    let identity = format.matrix == MatrixCoefficients::IDENTITY;
+    let matrix = if identity {
+        ravif::MatrixCoefficients::Identity
+    } else {
+        ravif::MatrixCoefficients::BT601
+    };
+    let result = if format.depth == ravif::BitDepth::Eight {
+        let planes = img
+            .pixels()
+            .map(move |p| ravif_planes(p.rgb(), 8, identity).map(|v| v as u8));
+        let alpha = std::iter::repeat_n(255u8, width * height);
+        enc.encode_raw_planes_8_bit(
+            width,
+            height,
+            planes,
+            Some(alpha),
+            format.ravif_range(),
+            matrix,
+        )
+    } else {
+        let planes = img
+            .pixels()
+            .map(move |p| ravif_planes(p.rgb(), 10, identity));
+        let alpha = std::iter::repeat_n(1023u16, width * height);
+        enc.encode_raw_planes_10_bit(
+            width,
+            height,
+            planes,
+            Some(alpha),
+            format.ravif_range(),
+            matrix,
+        )
+    }
+    .map_err(|e: ravif::Error| at!(Error::Encode(e.to_string())))?;
+    Ok(EncodedImage {
+        avif_file: result.avif_file,
+        color_byte_size: result.color_byte_size,
+        alpha_byte_size: result.alpha_byte_size,
+    })
+}
+
+/// ravif's conversion of an 8-bit pixel to `depth`-bit samples: BT.601
+/// YCbCr, or G/B/R for the identity matrix.
+fn ravif_planes(px: Rgb<u8>, depth: u8, identity: bool) -> [u16; 3] {
+    if identity {
+        let widen = |v: u8| match depth {
+            8 => u16::from(v),
+            _ => (u16::from(v) << 2) | (u16::from(v) >> 6),
+        };
+        return [widen(px.g), widen(px.b), widen(px.r)];
+    }
+    const BT601: [f32; 3] = [0.2990, 0.5870, 0.1140];
+    let max_value = ((1u32 << depth) - 1) as f32;
+    let scale = max_value / 255.;
+    let shift = (max_value * 0.5).round();
+    let (r, g, b) = (f32::from(px.r), f32::from(px.g), f32::from(px.b));
+    let y = (scale * BT601[2]).mul_add(b, (scale * BT601[0]).mul_add(r, scale * BT601[1] * g));
+    let cb = b.mul_add(scale, -y).mul_add(0.5 / (1. - BT601[2]), shift);
+    let cr = r.mul_add(scale, -y).mul_add(0.5 / (1. - BT601[0]), shift);
+    [y.round() as u16, cb.round() as u16, cr.round() as u16]
+}
+
+/// Encode an 8-bit RGB image with a separate alpha plane to AVIF
+///
+/// For pipelines that keep color and matte separate. The two planes are
+/// combined internally, so the result is identical to [`encode_rgba8`] on the
+/// interleaved image. Use [`EncoderConfig::alpha_quality`] to encode the
+/// alpha plane at a different quality than color.
+///
+/// # Arguments
+///
+/// * `color` - RGB8 image buffer
+/// * `alpha` - Alpha plane, same dimensions as `color`
+/// * `config` - Encoder configuration
+/// * `stop` - Cancellation token (checked pre-encode, forwarded to ravif per-superblock)
+pub fn encode_rgb_with_alpha_plane(
+    color: ImgRef<'_, Rgb<u8>>,
+    alpha: ImgRef<'_, Gray<u8>>,
+    config: &EncoderConfig,
+    stop: almost_enough::StopToken,
+) -> Result<EncodedImage> {
+    if color.width() != alpha.width() || color.height() != alpha.height() {
+        return Err(at!(Error::Unsupported(
+            "alpha plane dimensions must match the color image",
+        )));
+    }
+    let pixels: Vec<Rgba<u8>> = color
+        .pixels()
+        .zip(alpha.pixels())
+        .map(|(c, a)| Rgba {
+            r: c.r,
+            g: c.g,
+            b: c.b,
+            a: a.value(),
+        })
+        .collect();
+    let rgba = ImgVec::new(pixels, color.width(), color.height());
+    encode_rgba8(rgba.as_ref(), config, stop)
+}
+
 /// Encode a 16-bit RGB image to AVIF (10-bit AV1)
 ///
 /// Input values should be in full u16 range (0–65535), in the image's native
@@ -735,31 +1572,32 @@ pub fn encode_rgb16(
 ) -> Result<EncodedImage> {
     use crate::convert::scale_from_u16;
     stop.check().map_err(|e| at!(Error::from(e)))?;
-    let enc = build_ravif_encoder(config, stop, true);
+    check_dimensions(img.width(), img.height())?;
+    let threads =
+        threads_within_memory_limit(config, img.width(), img.height(), false, true, false)?;
+    let format = SignalFormat::resolve(config, true, Conversion::Planes)?;
+    let enc = build_ravif_encoder(config, stop, &format, threads);
     let width = img.width();
     let height = img.height();
+    let to_planes = format.rgb10_to_planes();
     let pixels: Vec<[u16; 3]> = img
         .pixels()
         .map(|p| {
-            [
+            to_planes([
                 scale_from_u16(p.r, 10),
                 scale_from_u16(p.g, 10),
                 scale_from_u16(p.b, 10),
-            ]
+            ])
         })
         .collect();
-    let pixel_range = match config.pixel_range {
-        Some(EncodePixelRange::Limited) => ravif::PixelRange::Limited,
-        _ => ravif::PixelRange::Full,
-    };
     let result = enc
         .encode_raw_planes_10_bit(
             width,
             height,
             pixels,
             None::<std::iter::Empty<u16>>,
-            pixel_range,
-            ravif::MatrixCoefficients::Identity,
+            format.ravif_range(),
+            format.ravif_matrix(),
         )
         .map_err(|e: ravif::Error| at!(Error::Encode(e.to_string())))?;
     Ok(EncodedImage {
@@ -769,6 +1607,47 @@ pub fn encode_rgb16(
     })
 }
 
+/// Encode an 8-bit grayscale image to AVIF
+///
+/// Coded at 8 bits, or at 10 by bit replication when the config asks for
+/// [`EncodeBitDepth::Ten`], so black and white stay exact either way. The
+/// stream is monochrome (4:0:0) AV1 with a single luma plane, and probes as
+/// [`ChromaSampling::Monochrome`] whatever [`EncoderConfig::chroma`] says.
+///
+/// # Arguments
+///
+/// * `img` - Gray8 image buffer
+/// * `config` - Encoder configuration
+/// * `stop` - Cancellation token (checked pre-encode, forwarded to ravif per-superblock)
+pub fn encode_gray8(
+    img: ImgRef<'_, Gray<u8>>,
+    config: &EncoderConfig,
+    stop: almost_enough::StopToken,
+) -> Result<EncodedImage> {
+    let luma = img.pixels().map(|g| u16::from(g.value()));
+    crate::mono_encode::encode_luma(img.width(), img.height(), luma, 8, config, stop)
+}
+
+/// Encode a 16-bit grayscale image to AVIF (10-bit AV1)
+///
+/// Input values should be in full u16 range (0–65535) and are scaled to
+/// 10-bit internally. Like [`encode_gray8`], the stream is monochrome AV1.
+///
+/// # Arguments
+///
+/// * `img` - Gray16 image buffer (0–65535)
+/// * `config` - Encoder configuration
+/// * `stop` - Cancellation token (checked pre-encode, forwarded to ravif per-superblock)
+pub fn encode_gray16(
+    img: ImgRef<'_, Gray<u16>>,
+    config: &EncoderConfig,
+    stop: almost_enough::StopToken,
+) -> Result<EncodedImage> {
+    use crate::convert::scale_from_u16;
+    let luma = img.pixels().map(|g| scale_from_u16(g.value(), 10));
+    crate::mono_encode::encode_luma(img.width(), img.height(), luma, 10, config, stop)
+}
+
 /// Encode a 16-bit RGBA image to AVIF (10-bit AV1)
 ///
 /// Input values should be in full u16 range (0–65535), in the image's native
@@ -787,32 +1666,34 @@ pub fn encode_rgba16(
 ) -> Result<EncodedImage> {
     use crate::convert::scale_from_u16;
     stop.check().map_err(|e| at!(Error::from(e)))?;
-    let enc = build_ravif_encoder(config, stop, true);
+    check_dimensions(img.width(), img.height())?;
+    let threads =
+        threads_within_memory_limit(config, img.width(), img.height(), true, true, false)?;
+    let format = SignalFormat::resolve(config, true, Conversion::Planes)?;
+    let enc = build_ravif_encoder(config, stop, &format, threads);
     let width = img.width();
     let height = img.height();
+    let to_planes = format.rgb10_to_planes();
     let pixels: Vec<[u16; 3]> = img
         .pixels()
         .map(|p| {
-            [
+            to_planes([
                 scale_from_u16(p.r, 10),
                 scale_from_u16(p.g, 10),
                 scale_from_u16(p.b, 10),
-            ]
+            ])
         })
         .collect();
     let alpha: Vec<u16> = img.pixels().map(|p| scale_from_u16(p.a, 10)).collect();
-    let pixel_range = match config.pixel_range {
-        Some(EncodePixelRange::Limited) => ravif::PixelRange::Limited,
-        _ => ravif::PixelRange::Full,
-    };
+    let enc = with_auto_alpha_quality(enc, config, alpha.iter().copied());
     let result = enc
         .encode_raw_planes_10_bit(
             width,
             height,
             pixels,
             Some(alpha),
-            pixel_range,
-            ravif::MatrixCoefficients::Identity,
+            format.ravif_range(),
+            format.ravif_matrix(),
         )
         .map_err(|e: ravif::Error| at!(Error::Encode(e.to_string())))?;
     Ok(EncodedImage {
@@ -867,7 +1748,14 @@ pub fn encode_animation_rgb8(
     stop: almost_enough::StopToken,
 ) -> Result<EncodedAnimation> {
     stop.check().map_err(|e| at!(Error::from(e)))?;
-    let enc = build_ravif_encoder(config, stop, false);
+    let (width, height) =
+        largest_frame(frames.iter().map(|f| (f.pixels.width(), f.pixels.height())));
+    if !frames.is_empty() {
+        check_dimensions(width, height)?;
+    }
+    let threads = threads_within_memory_limit(config, width, height, false, false, true)?;
+    let format = SignalFormat::resolve(config, false, Conversion::Ravif)?;
+    let enc = build_ravif_encoder(config, stop, &format, threads);
 
     let ravif_frames: Vec<ravif::AnimFrame<'_>> = frames
         .iter()
@@ -904,7 +1792,19 @@ pub fn encode_animation_rgba8(
     stop: almost_enough::StopToken,
 ) -> Result<EncodedAnimation> {
     stop.check().map_err(|e| at!(Error::from(e)))?;
-    let enc = build_ravif_encoder(config, stop, false);
+    let (width, height) =
+        largest_frame(frames.iter().map(|f| (f.pixels.width(), f.pixels.height())));
+    if !frames.is_empty() {
+        check_dimensions(width, height)?;
+    }
+    let threads = threads_within_memory_limit(config, width, height, true, false, true)?;
+    let format = SignalFormat::resolve(config, false, Conversion::Ravif)?;
+    let enc = build_ravif_encoder(config, stop, &format, threads);
+    // One alpha quality for the whole sequence, so frames don't flicker.
+    let alpha = frames
+        .iter()
+        .flat_map(|f| f.pixels.pixels().map(|p| p.a.into()));
+    let enc = with_auto_alpha_quality(enc, config, alpha);
 
     let ravif_frames: Vec<ravif::AnimFrameRgba<'_>> = frames
         .iter()
@@ -961,7 +1861,14 @@ pub fn encode_animation_rgb16(
 ) -> Result<EncodedAnimation> {
     use crate::convert::scale_from_u16;
     stop.check().map_err(|e| at!(Error::from(e)))?;
-    let enc = build_ravif_encoder(config, stop, true);
+    let (width, height) =
+        largest_frame(frames.iter().map(|f| (f.pixels.width(), f.pixels.height())));
+    if !frames.is_empty() {
+        check_dimensions(width, height)?;
+    }
+    let threads = threads_within_memory_limit(config, width, height, false, true, true)?;
+    let format = SignalFormat::resolve(config, true, Conversion::Ravif)?;
+    let enc = build_ravif_encoder(config, stop, &format, threads);
 
     // Scale each frame from 0–65535 to 10-bit (0–1023)
     let scaled_frames: Vec<ImgVec<RGB16>> = frames
@@ -1019,7 +1926,14 @@ pub fn encode_animation_rgba16(
 ) -> Result<EncodedAnimation> {
     use crate::convert::scale_from_u16;
     stop.check().map_err(|e| at!(Error::from(e)))?;
-    let enc = build_ravif_encoder(config, stop, true);
+    let (width, height) =
+        largest_frame(frames.iter().map(|f| (f.pixels.width(), f.pixels.height())));
+    if !frames.is_empty() {
+        check_dimensions(width, height)?;
+    }
+    let threads = threads_within_memory_limit(config, width, height, true, true, true)?;
+    let format = SignalFormat::resolve(config, true, Conversion::Ravif)?;
+    let enc = build_ravif_encoder(config, stop, &format, threads);
 
     // Scale each frame from 0–65535 to 10-bit (0–1023)
     let scaled_frames: Vec<ImgVec<RGBA16>> = frames
@@ -1040,6 +1954,9 @@ pub fn encode_animation_rgba16(
         })
         .collect();
 
+    let alpha = scaled_frames.iter().flat_map(|f| f.pixels().map(|p| p.a));
+    let enc = with_auto_alpha_quality(enc, config, alpha);
+
     let ravif_frames: Vec<ravif::AnimFrameRgba16<'_>> = scaled_frames
         .iter()
         .zip(frames.iter())
@@ -1059,3 +1976,284 @@ pub fn encode_animation_rgba16(
         total_duration_ms: result.total_duration_ms,
     })
 }
+
+/// A single 8-bit YCbCr 4:2:0 frame in an animated AVIF sequence
+///
+/// Chroma planes are `ceil(width / 2)` × `ceil(height / 2)`.
+#[derive(Clone, Copy)]
+pub struct AnimationFrameYuv420<'a> {
+    /// Luma plane
+    pub y: ImgRef<'a, u8>,
+    /// Blue-difference chroma plane
+    pub cb: ImgRef<'a, u8>,
+    /// Red-difference chroma plane
+    pub cr: ImgRef<'a, u8>,
+    /// Duration of this frame in milliseconds
+    pub duration_ms: u32,
+}
+
+/// CICP describing the input planes of [`encode_animation_yuv420`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YuvCicp {
+    /// Color primaries, written to the output unless the config sets them
+    pub color_primaries: ColorPrimaries,
+    /// Transfer characteristics, written to the output unless the config
+    /// sets them
+    pub transfer_characteristics: TransferCharacteristics,
+    /// Matrix the planes were derived with: BT.601, BT.709 or BT.2020
+    pub matrix_coefficients: MatrixCoefficients,
+    /// Sample range of the planes
+    pub range: ColorRange,
+}
+
+/// Encode a sequence of 8-bit YCbCr 4:2:0 frames into an animated AVIF
+/// (10-bit AV1)
+///
+/// For video transcodes that already hold decoded YUV frames. zenravif only
+/// accepts RGB animation frames and codes 4:4:4, so each frame is expanded
+/// once to 10-bit RGB, without an 8-bit round trip. Chroma is upsampled by
+/// replication. Primaries and transfer default to `cicp`; the output matrix
+/// follows `config` as for the other encoders.
+///
+/// # Arguments
+///
+/// * `frames` - Sequence of 4:2:0 frames with durations
+/// * `cicp` - Color description of the input planes
+/// * `config` - Encoder configuration (quality, speed, etc.)
+/// * `stop` - Cancellation token (checked before encoding starts)
+pub fn encode_animation_yuv420(
+    frames: &[AnimationFrameYuv420<'_>],
+    cicp: YuvCicp,
+    config: &EncoderConfig,
+    stop: almost_enough::StopToken,
+) -> Result<EncodedAnimation> {
+    stop.check().map_err(|e| at!(Error::from(e)))?;
+    let (width, height) = frames
+        .first()
+        .map_or((0, 0), |f| (f.y.width(), f.y.height()));
+    let chroma_size = (width.div_ceil(2), height.div_ceil(2));
+    for f in frames {
+        if (f.y.width(), f.y.height()) != (width, height)
+            || (f.cb.width(), f.cb.height()) != chroma_size
+            || (f.cr.width(), f.cr.height()) != chroma_size
+        {
+            return Err(at!(Error::Encode(format!(
+                "4:2:0 frame planes must be {width}x{height} luma and {}x{} chroma",
+                chroma_size.0, chroma_size.1
+            ))));
+        }
+    }
+    let matrix = match cicp.matrix_coefficients {
+        MatrixCoefficients::BT709 => YuvMatrix::Bt709,
+        MatrixCoefficients::BT2020_NCL => YuvMatrix::Bt2020,
+        MatrixCoefficients::BT470BG | MatrixCoefficients::BT601 | MatrixCoefficients::UNKNOWN => {
+            YuvMatrix::Bt601
+        }
+        _ => {
+            return Err(at!(Error::Unsupported(
+                "4:2:0 input matrix must be BT.601, BT.709 or BT.2020",
+            )));
+        }
+    };
+
+    let mut config = config.clone();
+    if config.color_primaries.is_none() && cicp.color_primaries != ColorPrimaries::UNKNOWN {
+        config.color_primaries = Some(cicp.color_primaries.0);
+    }
+    if config.transfer_characteristics.is_none()
+        && cicp.transfer_characteristics != TransferCharacteristics::UNKNOWN
+    {
+        config.transfer_characteristics = Some(cicp.transfer_characteristics.0);
+    }
+    if !frames.is_empty() {
+        check_dimensions(width, height)?;
+    }
+    let threads = threads_within_memory_limit(&config, width, height, false, true, true)?;
+    let format = SignalFormat::resolve(&config, true, Conversion::Ravif)?;
+    let enc = build_ravif_encoder(&config, stop, &format, threads);
+
+    let (kr, kb) = matrix_coefficients(matrix);
+    let kg = 1.0 - kr - kb;
+    let (y_offset, y_scale, c_scale) = match cicp.range {
+        ColorRange::Limited => (16.0, 1.0 / 219.0, 1.0 / 224.0),
+        ColorRange::Full => (0.0, 1.0 / 255.0, 1.0 / 255.0),
+    };
+    let to_rgb10 = |y: u8, cb: u8, cr: u8| {
+        let y = (y as f32 - y_offset) * y_scale;
+        let cb = (cb as f32 - 128.0) * c_scale;
+        let cr = (cr as f32 - 128.0) * c_scale;
+        let r = y + 2.0 * (1.0 - kr) * cr;
+        let b = y + 2.0 * (1.0 - kb) * cb;
+        let g = (y - kr * r - kb * b) / kg;
+        let q = |v: f32| (v.clamp(0.0, 1.0) * 1023.0).round() as u16;
+        RGB16 {
+            r: q(r),
+            g: q(g),
+            b: q(b),
+        }
+    };
+    let rgb_frames: Vec<ImgVec<RGB16>> = frames
+        .iter()
+        .map(|f| {
+            let mut rgb = Vec::with_capacity(width * height);
+            // Each chroma row serves two luma rows.
+            let chroma_rows = f.cb.rows().zip(f.cr.rows()).flat_map(|c| [c, c]);
+            for (y_row, (cb_row, cr_row)) in f.y.rows().zip(chroma_rows) {
+                rgb.extend(
+                    y_row
+                        .iter()
+                        .enumerate()
+                        .map(|(x, &y)| to_rgb10(y, cb_row[x / 2], cr_row[x / 2])),
+                );
+            }
+            ImgVec::new(rgb, width, height)
+        })
+        .collect();
+
+    let ravif_frames: Vec<ravif::AnimFrame16<'_>> = rgb_frames
+        .iter()
+        .zip(frames)
+        .map(|(rgb, orig)| ravif::AnimFrame16 {
+            rgb: rgb.as_ref(),
+            duration_ms: orig.duration_ms,
+        })
+        .collect();
+
+    let result = enc
+        .encode_animation_rgb16(&ravif_frames)
+        .map_err(|e: ravif::Error| at!(Error::Encode(e.to_string())))?;
+
+    Ok(EncodedAnimation {
+        avif_file: result.avif_file,
+        frame_count: result.frame_count,
+        total_duration_ms: result.total_duration_ms,
+    })
+}
+
+/// Borrowed YCbCr planes for [`encode_yuv`]
+///
+/// The chroma layout follows from the plane sizes: luma-sized for 4:4:4,
+/// `ceil(width / 2)` wide for 4:2:2, and also `ceil(height / 2)` tall for
+/// 4:2:0. `T` is `u8` for 8-bit samples and `u16` for 10- or 12-bit samples
+/// in the low bits. Under the identity matrix the planes hold G, B and R.
+#[derive(Clone, Copy)]
+pub struct YuvPlanesRef<'a, T = u8> {
+    /// Luma plane
+    pub y: ImgRef<'a, T>,
+    /// Blue-difference chroma plane
+    pub cb: ImgRef<'a, T>,
+    /// Red-difference chroma plane
+    pub cr: ImgRef<'a, T>,
+    /// Sample bit depth: 8, 10 or 12
+    pub bit_depth: u8,
+    /// Color description of the planes
+    pub cicp: YuvCicp,
+}
+
+/// Encode YCbCr planes to AVIF (10-bit AV1) without going through RGB
+///
+/// For transcodes that already hold YCbCr, such as decoded JPEGs: the
+/// samples go to the encoder as they are, so there is no YUV→RGB→YUV round
+/// trip and no rounding loss from one. 8-bit samples widen to 10 bits
+/// exactly; 12-bit samples are rounded to 10. Chroma is coded at the
+/// config's [`chroma`](EncoderConfig::chroma) sampling, 4:4:4 by default:
+/// subsampled planes are replicated up, and 4:2:0 planes coded as 4:2:0 keep
+/// their samples exactly.
+///
+/// The output signals the matrix and range of `planes.cicp`, which must be
+/// identity, BT.601, BT.709 or BT.2020; the config's matrix, range and color
+/// model are ignored. Primaries and transfer default to `planes.cicp` as in
+/// [`encode_animation_yuv420`].
+///
+/// # Arguments
+///
+/// * `planes` - Input planes and their color description
+/// * `config` - Encoder configuration (quality, speed, etc.)
+/// * `stop` - Cancellation token (checked pre-encode, forwarded to ravif per-superblock)
+pub fn encode_yuv<T: Copy + Into<u16>>(
+    planes: YuvPlanesRef<'_, T>,
+    config: &EncoderConfig,
+    stop: almost_enough::StopToken,
+) -> Result<EncodedImage> {
+    stop.check().map_err(|e| at!(Error::from(e)))?;
+    let (width, height) = (planes.y.width(), planes.y.height());
+    check_dimensions(width, height)?;
+    let chroma_size = (planes.cb.width(), planes.cb.height());
+    let shift = |chroma: usize, luma: usize| match chroma {
+        c if c == luma => Some(0),
+        c if c == luma.div_ceil(2) => Some(1),
+        _ => None,
+    };
+    let (Some(x_shift), Some(y_shift)) =
+        (shift(chroma_size.0, width), shift(chroma_size.1, height))
+    else {
+        return Err(at!(Error::Encode(format!(
+            "chroma planes of a {width}x{height} image must be 4:4:4, 4:2:2 or 4:2:0 sized, not {}x{}",
+            chroma_size.0, chroma_size.1
+        ))));
+    };
+    if y_shift > x_shift || (planes.cr.width(), planes.cr.height()) != chroma_size {
+        return Err(at!(Error::Encode(format!(
+            "Cb and Cr planes must both be 4:4:4, 4:2:2 or 4:2:0 sized for {width}x{height}"
+        ))));
+    }
+    let widen: fn(u16) -> u16 = match planes.bit_depth {
+        8 => |v| v.min(255) << 2,
+        10 => |v| v.min(1023),
+        12 => |v| ((v.min(4095) + 2) >> 2).min(1023),
+        _ => {
+            return Err(at!(Error::Unsupported(
+                "YCbCr planes must be 8, 10 or 12 bits"
+            )));
+        }
+    };
+
+    let cicp = planes.cicp;
+    let mut config = config.clone().output_range(cicp.range);
+    config.matrix_coefficients = Some(cicp.matrix_coefficients.0);
+    config.color_model = if cicp.matrix_coefficients == MatrixCoefficients::IDENTITY {
+        EncodeColorModel::Rgb
+    } else {
+        EncodeColorModel::YCbCr
+    };
+    if config.color_primaries.is_none() && cicp.color_primaries != ColorPrimaries::UNKNOWN {
+        config.color_primaries = Some(cicp.color_primaries.0);
+    }
+    if config.transfer_characteristics.is_none()
+        && cicp.transfer_characteristics != TransferCharacteristics::UNKNOWN
+    {
+        config.transfer_characteristics = Some(cicp.transfer_characteristics.0);
+    }
+    let threads = threads_within_memory_limit(&config, width, height, false, true, false)?;
+    let format = SignalFormat::resolve(&config, true, Conversion::Planes)?;
+    let enc = build_ravif_encoder(&config, stop, &format, threads);
+
+    let mut pixels = Vec::new();
+    pixels
+        .try_reserve_exact(width * height)
+        .map_err(|_| at!(Error::OutOfMemory))?;
+    for (row, y_row) in planes.y.rows().enumerate() {
+        let chroma_row = row >> y_shift;
+        let cb_row = &planes.cb.into_buf()[chroma_row * planes.cb.stride()..][..chroma_size.0];
+        let cr_row = &planes.cr.into_buf()[chroma_row * planes.cr.stride()..][..chroma_size.0];
+        pixels.extend(y_row.iter().enumerate().map(|(x, &y)| {
+            let x = x >> x_shift;
+            [y, cb_row[x], cr_row[x]].map(|v| widen(v.into()))
+        }));
+    }
+    let result = enc
+        .encode_raw_planes_10_bit(
+            width,
+            height,
+            pixels,
+            None::<std::iter::Empty<u16>>,
+            format.ravif_range(),
+            format.ravif_matrix(),
+        )
+        .map_err(|e: ravif::Error| at!(Error::Encode(e.to_string())))?;
+    Ok(EncodedImage {
+        avif_file: result.avif_file,
+        color_byte_size: result.color_byte_size,
+        alpha_byte_size: result.alpha_byte_size,
+    })
+}
@@ -23,6 +23,11 @@ pub enum Error {
     #[error("Color conversion error: {0}")]
     ColorConversion(#[from] yuv::YuvError),
 
+    /// ICC color management failed
+    #[cfg(feature = "cms")]
+    #[error("Color management error: {0}")]
+    ColorManagement(String),
+
     /// AV1 encode error
     #[error("AV1 encode error: {0}")]
     Encode(String),
@@ -44,6 +49,18 @@ pub enum Error {
     #[error("Resource limit exceeded: {0}")]
     ResourceLimit(String),
 
+    /// The estimated memory of a decode exceeds
+    /// [`DecoderConfig::max_memory_bytes`](crate::DecoderConfig::max_memory_bytes)
+    #[error(
+        "Resource limit exceeded: decode needs an estimated {estimated} bytes, limit is {limit}"
+    )]
+    ResourceLimitExceeded {
+        /// Estimated peak bytes of the decode
+        estimated: u64,
+        /// Configured limit in bytes
+        limit: u64,
+    },
+
     /// Memory allocation failed
     #[error("Out of memory")]
     OutOfMemory,
@@ -52,6 +69,10 @@ pub enum Error {
     #[error("Operation cancelled: {0:?}")]
     Cancelled(StopReason),
 
+    /// Reading the input failed
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     /// Unsupported codec operation
     #[cfg(feature = "zencodec")]
     #[error(transparent)]
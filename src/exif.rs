@@ -0,0 +1,363 @@
+//! Reading common EXIF fields.
+//!
+//! A small TIFF reader for what image pipelines usually want from EXIF:
+//! display orientation, capture time and GPS position, plus scrubbing the
+//! GPS position for privacy. Anything beyond that needs a full EXIF library.
+//!
+//! All functions take EXIF from the TIFF header onwards, as found in
+//! [`ImageInfo::exif`](crate::ImageInfo::exif). A leading `Exif\0\0` marker
+//! is skipped.
+
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_GPS_IFD: u16 = 0x8825;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+
+const GPS_LATITUDE_REF: u16 = 1;
+const GPS_LATITUDE: u16 = 2;
+const GPS_LONGITUDE_REF: u16 = 3;
+const GPS_LONGITUDE: u16 = 4;
+const GPS_ALTITUDE_REF: u16 = 5;
+const GPS_ALTITUDE: u16 = 6;
+
+/// Position recorded in the GPS IFD.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsPosition {
+    /// Degrees north of the equator; negative is south.
+    pub latitude: f64,
+    /// Degrees east of Greenwich; negative is west.
+    pub longitude: f64,
+    /// Metres above sea level; negative is below. `None` if not recorded.
+    pub altitude: Option<f64>,
+}
+
+/// Read-only view of an EXIF block.
+#[derive(Debug, Clone, Copy)]
+pub struct Exif<'a> {
+    tiff: &'a [u8],
+    big_endian: bool,
+    ifd0: usize,
+}
+
+/// An IFD entry: its field type, value count and where the values start.
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    kind: u16,
+    count: usize,
+    value: usize,
+}
+
+impl<'a> Exif<'a> {
+    /// Parse the TIFF header. Returns `None` if `data` is not EXIF.
+    pub fn new(data: &'a [u8]) -> Option<Self> {
+        let tiff = data.strip_prefix(b"Exif\0\0").unwrap_or(data);
+        let big_endian = match tiff.get(..2)? {
+            b"II" => false,
+            b"MM" => true,
+            _ => return None,
+        };
+        let mut exif = Self {
+            tiff,
+            big_endian,
+            ifd0: 0,
+        };
+        if exif.u16(2)? != 42 {
+            return None;
+        }
+        exif.ifd0 = exif.u32(4)? as usize;
+        exif.u16(exif.ifd0)?;
+        Some(exif)
+    }
+
+    /// The `Orientation` tag, 1 (upright) to 8.
+    ///
+    /// Values 5–8 transpose the image; see the TIFF specification for the
+    /// full table.
+    pub fn orientation(&self) -> Option<u16> {
+        let entry = self.find(self.ifd0, TAG_ORIENTATION)?;
+        let value = self.short(entry, 0)?;
+        (1..=8).contains(&value).then_some(value)
+    }
+
+    /// When the photo was taken, as EXIF's `"YYYY:MM:DD HH:MM:SS"` local
+    /// time.
+    ///
+    /// Reads `DateTimeOriginal`, falling back to the IFD0 `DateTime`.
+    pub fn date_taken(&self) -> Option<&'a str> {
+        self.sub_ifd(TAG_EXIF_IFD)
+            .and_then(|ifd| self.ascii(ifd, TAG_DATE_TIME_ORIGINAL))
+            .or_else(|| self.ascii(self.ifd0, TAG_DATE_TIME))
+    }
+
+    /// Latitude, longitude and altitude from the GPS IFD.
+    pub fn gps(&self) -> Option<GpsPosition> {
+        let ifd = self.sub_ifd(TAG_GPS_IFD)?;
+        let degrees = |tag, ref_tag, negative: u8| {
+            let entry = self.find(ifd, tag)?;
+            let [d, m, s] = [0, 1, 2].map(|i| self.rational(entry, i));
+            let value = d? + m.unwrap_or(0.0) / 60.0 + s.unwrap_or(0.0) / 3600.0;
+            let hemisphere = self.find(ifd, ref_tag).and_then(|e| self.byte(e, 0));
+            Some(if hemisphere == Some(negative) {
+                -value
+            } else {
+                value
+            })
+        };
+        let altitude = self
+            .find(ifd, GPS_ALTITUDE)
+            .and_then(|entry| self.rational(entry, 0))
+            .map(|metres| {
+                let below = self
+                    .find(ifd, GPS_ALTITUDE_REF)
+                    .and_then(|e| self.byte(e, 0));
+                if below == Some(1) { -metres } else { metres }
+            });
+        Some(GpsPosition {
+            latitude: degrees(GPS_LATITUDE, GPS_LATITUDE_REF, b'S')?,
+            longitude: degrees(GPS_LONGITUDE, GPS_LONGITUDE_REF, b'W')?,
+            altitude,
+        })
+    }
+
+    fn find(&self, ifd: usize, tag: u16) -> Option<Entry> {
+        let count = self.u16(ifd)? as usize;
+        (0..count).find_map(|i| {
+            let pos = ifd + 2 + i * 12;
+            (self.u16(pos)? == tag).then(|| self.entry(pos)).flatten()
+        })
+    }
+
+    fn entry(&self, pos: usize) -> Option<Entry> {
+        let kind = self.u16(pos + 2)?;
+        let count = self.u32(pos + 4)? as usize;
+        let len = type_size(kind)?.checked_mul(count)?;
+        let value = if len <= 4 {
+            pos + 8
+        } else {
+            self.u32(pos + 8)? as usize
+        };
+        self.tiff.get(value..value.checked_add(len)?)?;
+        Some(Entry { kind, count, value })
+    }
+
+    /// Offset of the IFD a pointer tag in IFD0 leads to.
+    fn sub_ifd(&self, tag: u16) -> Option<usize> {
+        let entry = self.find(self.ifd0, tag)?;
+        let ifd = self.long(entry, 0)? as usize;
+        self.u16(ifd)?;
+        Some(ifd)
+    }
+
+    fn ascii(&self, ifd: usize, tag: u16) -> Option<&'a str> {
+        let entry = self.find(ifd, tag)?;
+        if entry.kind != 2 {
+            return None;
+        }
+        let bytes = &self.tiff[entry.value..entry.value + entry.count];
+        let text = std::str::from_utf8(bytes).ok()?.trim_end_matches('\0');
+        (!text.is_empty()).then_some(text)
+    }
+
+    fn byte(&self, entry: Entry, index: usize) -> Option<u8> {
+        if !matches!(entry.kind, 1 | 2 | 7) || index >= entry.count {
+            return None;
+        }
+        self.tiff.get(entry.value + index).copied()
+    }
+
+    fn short(&self, entry: Entry, index: usize) -> Option<u16> {
+        (entry.kind == 3 && index < entry.count)
+            .then(|| self.u16(entry.value + index * 2))
+            .flatten()
+    }
+
+    fn long(&self, entry: Entry, index: usize) -> Option<u32> {
+        match entry.kind {
+            _ if index >= entry.count => None,
+            3 => self.u16(entry.value + index * 2).map(u32::from),
+            4 | 13 => self.u32(entry.value + index * 4),
+            _ => None,
+        }
+    }
+
+    fn rational(&self, entry: Entry, index: usize) -> Option<f64> {
+        if entry.kind != 5 || index >= entry.count {
+            return None;
+        }
+        let pos = entry.value + index * 8;
+        let (num, den) = (self.u32(pos)?, self.u32(pos + 4)?);
+        (den != 0).then(|| f64::from(num) / f64::from(den))
+    }
+
+    fn u16(&self, pos: usize) -> Option<u16> {
+        let bytes = self.tiff.get(pos..pos.checked_add(2)?)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    }
+
+    fn u32(&self, pos: usize) -> Option<u32> {
+        let bytes = self.tiff.get(pos..pos.checked_add(4)?)?.try_into().ok()?;
+        Some(if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    }
+}
+
+/// Remove the GPS IFD from `exif` in place. Returns whether there was one.
+///
+/// The GPS pointer is dropped from IFD0 and the GPS IFD with all its values
+/// is zeroed, so the position cannot be recovered. The block keeps its
+/// length, so offsets elsewhere stay valid.
+pub fn strip_gps(exif: &mut [u8]) -> bool {
+    let start = if exif.starts_with(b"Exif\0\0") { 6 } else { 0 };
+    let Some(reader) = Exif::new(exif) else {
+        return false;
+    };
+    let ifd0 = reader.ifd0;
+    let Some(count) = reader.u16(ifd0).map(usize::from) else {
+        return false;
+    };
+    // IFD0's entry table plus its next-IFD offset.
+    let table_end = ifd0 + 2 + count * 12 + 4;
+    if table_end > reader.tiff.len() {
+        return false;
+    }
+    let Some(index) = (0..count).find(|i| reader.u16(ifd0 + 2 + i * 12) == Some(TAG_GPS_IFD))
+    else {
+        return false;
+    };
+
+    // Everything the GPS IFD occupies: its entry table and any values
+    // stored out of line.
+    let mut scrub = Vec::new();
+    if let Some(gps) = reader.sub_ifd(TAG_GPS_IFD) {
+        let entries = reader.u16(gps).map_or(0, usize::from);
+        scrub.push(gps..(gps + 2 + entries * 12 + 4).min(reader.tiff.len()));
+        for i in 0..entries {
+            if let Some(entry) = reader.entry(gps + 2 + i * 12)
+                && entry.value != gps + 2 + i * 12 + 8
+            {
+                let len = type_size(entry.kind).unwrap_or(0) * entry.count;
+                scrub.push(entry.value..entry.value + len);
+            }
+        }
+    }
+    let big_endian = reader.big_endian;
+
+    let tiff = &mut exif[start..];
+    for range in scrub {
+        tiff[range].fill(0);
+    }
+    let entry = ifd0 + 2 + index * 12;
+    tiff.copy_within(entry + 12..table_end, entry);
+    tiff[table_end - 12..table_end].fill(0);
+    let count = (count - 1) as u16;
+    tiff[ifd0..ifd0 + 2].copy_from_slice(&if big_endian {
+        count.to_be_bytes()
+    } else {
+        count.to_le_bytes()
+    });
+    true
+}
+
+/// Bytes per value of a TIFF field type.
+fn type_size(kind: u16) -> Option<usize> {
+    match kind {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 | 13 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Little-endian TIFF with Orientation and DateTime in IFD0, an Exif IFD
+    /// with DateTimeOriginal, and a GPS IFD with position and altitude.
+    fn sample() -> Vec<u8> {
+        let mut t = b"II*\0".to_vec();
+        t.extend_from_slice(&8u32.to_le_bytes());
+        let ifd = |t: &mut Vec<u8>, entries: &[(u16, u16, u32, u32)]| {
+            t.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+            for &(tag, kind, count, value) in entries {
+                t.extend_from_slice(&tag.to_le_bytes());
+                t.extend_from_slice(&kind.to_le_bytes());
+                t.extend_from_slice(&count.to_le_bytes());
+                t.extend_from_slice(&value.to_le_bytes());
+            }
+            t.extend_from_slice(&0u32.to_le_bytes());
+        };
+        let rationals = |t: &mut Vec<u8>, values: &[(u32, u32)]| {
+            for &(num, den) in values {
+                t.extend_from_slice(&num.to_le_bytes());
+                t.extend_from_slice(&den.to_le_bytes());
+            }
+        };
+        let inline = |text: &[u8; 2]| u32::from_le_bytes([text[0], text[1], 0, 0]);
+
+        ifd(
+            &mut t,
+            &[
+                (TAG_ORIENTATION, 3, 1, 6),
+                (TAG_DATE_TIME, 2, 20, 62),
+                (TAG_EXIF_IFD, 4, 1, 82),
+                (TAG_GPS_IFD, 4, 1, 100),
+            ],
+        );
+        t.extend_from_slice(b"2024:01:02 03:04:05\0");
+        ifd(&mut t, &[(TAG_DATE_TIME_ORIGINAL, 2, 20, 218)]);
+        ifd(
+            &mut t,
+            &[
+                (GPS_LATITUDE_REF, 2, 2, inline(b"S\0")),
+                (GPS_LATITUDE, 5, 3, 178),
+                (GPS_LONGITUDE_REF, 2, 2, inline(b"E\0")),
+                (GPS_LONGITUDE, 5, 1, 202),
+                (GPS_ALTITUDE_REF, 1, 1, 1),
+                (GPS_ALTITUDE, 5, 1, 210),
+            ],
+        );
+        rationals(&mut t, &[(33, 1), (30, 1), (36, 1), (151, 2), (12, 4)]);
+        t.extend_from_slice(b"2023:12:31 23:59:59\0");
+        assert_eq!(t.len(), 238);
+        t
+    }
+
+    #[test]
+    fn reads_orientation_date_and_gps() {
+        let data = sample();
+        let exif = Exif::new(&data).unwrap();
+        assert_eq!(exif.orientation(), Some(6));
+        assert_eq!(exif.date_taken(), Some("2023:12:31 23:59:59"));
+        let gps = exif.gps().unwrap();
+        assert!((gps.latitude + 33.51).abs() < 1e-9);
+        assert_eq!(gps.longitude, 75.5);
+        assert_eq!(gps.altitude, Some(-3.0));
+
+        let prefixed = [&b"Exif\0\0"[..], &data].concat();
+        assert_eq!(Exif::new(&prefixed).unwrap().orientation(), Some(6));
+        assert!(Exif::new(b"not exif").is_none());
+    }
+
+    #[test]
+    fn strip_gps_removes_position_and_keeps_the_rest() {
+        let mut data = sample();
+        assert!(strip_gps(&mut data));
+        assert_eq!(data.len(), 238);
+        assert!(data[100..218].iter().all(|&b| b == 0));
+        let exif = Exif::new(&data).unwrap();
+        assert!(exif.gps().is_none());
+        assert_eq!(exif.orientation(), Some(6));
+        assert_eq!(exif.date_taken(), Some("2023:12:31 23:59:59"));
+        assert!(!strip_gps(&mut data));
+    }
+}
@@ -0,0 +1,104 @@
+//! Random access into animations through a cache of decoded frames.
+
+use crate::config::DecoderConfig;
+use crate::decoder_managed::AnimationDecoder;
+use crate::error::{Error, Result};
+use crate::image::{DecodedAnimationInfo, DecodedFrame};
+use enough::Stop;
+use std::collections::VecDeque;
+use whereat::at;
+
+/// Animation decoder for players and editors that jump between frames.
+///
+/// Keeps recently used frames within a byte budget, evicting the least
+/// recently used first. A miss seeks the underlying [`AnimationDecoder`],
+/// which re-decodes from the nearest key frame, so scrubbing back and forth
+/// over a cached range costs nothing and playing forward costs one frame
+/// decode per frame.
+///
+/// # Example
+///
+/// ```no_run
+/// use zenavif::{CachedAnimation, DecoderConfig};
+/// use enough::Unstoppable;
+///
+/// let data = std::fs::read("animation.avif").unwrap();
+/// let mut animation = CachedAnimation::new(&data, &DecoderConfig::default(), 64 << 20).unwrap();
+/// let last = animation.info().frame_count - 1;
+/// for index in [last, 0, last] {
+///     let frame = animation.frame(index, &Unstoppable).unwrap();
+///     println!("frame {index}: {}ms", frame.duration_ms);
+/// }
+/// ```
+pub struct CachedAnimation {
+    decoder: AnimationDecoder,
+    budget: usize,
+    /// Cached frames by index, least recently used first.
+    frames: VecDeque<(usize, DecodedFrame)>,
+    cached_bytes: usize,
+}
+
+impl CachedAnimation {
+    /// Create a cache holding at most `byte_budget` bytes of decoded pixels.
+    ///
+    /// The most recently requested frame is always kept, even if it alone
+    /// exceeds the budget. Returns [`Error::Unsupported`](crate::Error::Unsupported)
+    /// if the file is not animated.
+    pub fn new(data: &[u8], config: &DecoderConfig, byte_budget: usize) -> Result<Self> {
+        Ok(Self {
+            decoder: AnimationDecoder::new(data, config)?,
+            budget: byte_budget,
+            frames: VecDeque::new(),
+            cached_bytes: 0,
+        })
+    }
+
+    /// Animation metadata (frame count, loop count, etc.).
+    pub fn info(&self) -> &DecodedAnimationInfo {
+        self.decoder.info()
+    }
+
+    /// Frame `index`, from the cache or decoded on demand.
+    pub fn frame(&mut self, index: usize, stop: &(impl Stop + ?Sized)) -> Result<&DecodedFrame> {
+        if let Some(pos) = self.frames.iter().position(|(i, _)| *i == index) {
+            let entry = self.frames.remove(pos).unwrap();
+            self.frames.push_back(entry);
+        } else {
+            if self.decoder.frame_index() != index {
+                self.decoder.seek(index, stop)?;
+            }
+            let frame = self
+                .decoder
+                .next_frame(stop)?
+                .ok_or_else(|| at!(Error::Unsupported("frame index out of range")))?;
+            self.cached_bytes += frame_bytes(&frame);
+            self.frames.push_back((index, frame));
+            while self.cached_bytes > self.budget && self.frames.len() > 1 {
+                let (_, evicted) = self.frames.pop_front().unwrap();
+                self.cached_bytes -= frame_bytes(&evicted);
+            }
+        }
+        Ok(&self.frames.back().unwrap().1)
+    }
+
+    /// Number of frames currently cached.
+    pub fn cached_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Bytes of decoded pixels currently cached.
+    pub fn cached_bytes(&self) -> usize {
+        self.cached_bytes
+    }
+
+    /// Drop all cached frames.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.cached_bytes = 0;
+    }
+}
+
+fn frame_bytes(frame: &DecodedFrame) -> usize {
+    let pixels = &frame.pixels;
+    pixels.width() as usize * pixels.height() as usize * pixels.descriptor().bytes_per_pixel()
+}
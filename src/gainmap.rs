@@ -0,0 +1,316 @@
+//! Decoding and applying ISO 21496-1 gain maps.
+//!
+//! A gain-map AVIF (Ultra HDR style) stores a base rendition as the primary
+//! image and a `tmap` derived item holding a gain map image plus metadata
+//! describing how to reach the alternate rendition. [`decode_with_gainmap`]
+//! decodes both images; [`GainMapImage::apply`] combines them for a display
+//! with a given HDR headroom. [`encode_with_gainmap`] writes such files from
+//! a base image and gain map pixels.
+
+use crate::config::DecoderConfig;
+use crate::decode_av1::decode_av1_obu;
+use crate::decoder_managed::ManagedAvifDecoder;
+#[cfg(feature = "encode")]
+use crate::encoder::{EncodeBitDepth, EncodedImage, EncoderConfig};
+use crate::error::{Error, Result};
+use crate::image::{ColorInformation, GainMapMetadata, ImageInfo};
+use enough::Stop;
+use imgref::ImgVec;
+use rgb::{Gray, Rgb, Rgba};
+use whereat::at;
+use zenpixels::{PixelBuffer, PixelDescriptor};
+
+/// A decoded base image together with its decoded gain map.
+#[derive(Debug)]
+pub struct GainMapImage {
+    /// Decoded base rendition (the primary image).
+    pub base: PixelBuffer,
+    /// Metadata of the base image.
+    pub info: ImageInfo,
+    /// Decoded gain map, `Gray8` or `Rgb8`. It may be smaller than `base`.
+    pub gain_map: PixelBuffer,
+    /// ISO 21496-1 gain map metadata.
+    pub metadata: GainMapMetadata,
+    /// Color information for the alternate rendition, if signalled.
+    pub alt_color_info: Option<ColorInformation>,
+}
+
+impl GainMapImage {
+    /// Apply the gain map for a display with `hdr_headroom` stops of
+    /// headroom above SDR white (log2 of peak / SDR white luminance).
+    ///
+    /// Returns linear light with SDR white at 1.0, in the base image's
+    /// primaries. A headroom at or below the base rendition's reproduces
+    /// the base image; at or above the alternate rendition's the full gain
+    /// is applied. The base is linearized with its CICP transfer
+    /// characteristics. Alpha is carried over from the base image.
+    pub fn apply(&self, hdr_headroom: f32) -> Result<ImgVec<Rgba<f32>>> {
+        let md = &self.metadata;
+        let base_headroom = ratio(md.base_hdr_headroom_n as i64, md.base_hdr_headroom_d);
+        let alt_headroom = ratio(
+            md.alternate_hdr_headroom_n as i64,
+            md.alternate_hdr_headroom_d,
+        );
+        let weight = if alt_headroom == base_headroom {
+            0.0
+        } else {
+            ((hdr_headroom - base_headroom) / (alt_headroom - base_headroom)).clamp(0.0, 1.0)
+        };
+
+        let gain = GainSamples::new(&self.gain_map)?;
+        let channel = |c: usize| &md.channels[if md.is_multichannel { c } else { 0 }];
+        // log2 gain per gain map code value, per output channel.
+        let luts: [Vec<f32>; 3] = core::array::from_fn(|c| {
+            let ch = channel(c);
+            let min = ratio(ch.gain_map_min_n as i64, ch.gain_map_min_d);
+            let max = ratio(ch.gain_map_max_n as i64, ch.gain_map_max_d);
+            let gamma = ratio(ch.gamma_n as i64, ch.gamma_d);
+            (0..256)
+                .map(|v| {
+                    let v = (v as f32 / 255.0).powf(1.0 / gamma);
+                    min + (max - min) * v
+                })
+                .collect()
+        });
+        let offsets: [(f32, f32); 3] = core::array::from_fn(|c| {
+            let ch = channel(c);
+            (
+                ratio(ch.base_offset_n as i64, ch.base_offset_d),
+                ratio(ch.alternate_offset_n as i64, ch.alternate_offset_d),
+            )
+        });
+
+        let mut out =
+            crate::transfer::linear_rgba(&self.base, self.info.transfer_characteristics.0, false);
+        let (width, height) = (out.width(), out.height());
+        for (y, row) in out.rows_mut().enumerate() {
+            for (x, px) in row.iter_mut().enumerate() {
+                let codes = gain.sample(x, y, width, height);
+                let mut rgb = [px.r, px.g, px.b];
+                for (c, v) in rgb.iter_mut().enumerate() {
+                    let log2_gain = lerp_lut(&luts[c], codes[c]);
+                    let (base_offset, alt_offset) = offsets[c];
+                    *v = (*v + base_offset) * (log2_gain * weight).exp2() - alt_offset;
+                }
+                [px.r, px.g, px.b] = rgb;
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Decode an AVIF image and its gain map
+///
+/// Fails with [`Error::Unsupported`] if the file carries no gain map.
+///
+/// # Example
+///
+/// ```no_run
+/// use zenavif::DecoderConfig;
+/// use enough::Unstoppable;
+///
+/// let avif_data = std::fs::read("ultrahdr.avif").unwrap();
+/// let image = zenavif::decode_with_gainmap(&avif_data, &DecoderConfig::new(), &Unstoppable)
+///     .unwrap();
+/// // Render for a display with two stops of headroom.
+/// let hdr = image.apply(2.0).unwrap();
+/// ```
+pub fn decode_with_gainmap(
+    data: &[u8],
+    config: &DecoderConfig,
+    stop: &(impl Stop + ?Sized),
+) -> Result<GainMapImage> {
+    let mut decoder = ManagedAvifDecoder::from_slice(data, config)?;
+    let (base, info) = decoder.decode_full(stop)?;
+    let Some(gm) = info.gain_map.clone() else {
+        return Err(at!(Error::Unsupported("image has no gain map")));
+    };
+    stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+
+    let (pixels, width, height, channels) = decode_av1_obu(&gm.gain_map_data)?;
+    let gain_map: PixelBuffer = if channels == 1 {
+        let px: Vec<Gray<u8>> = pixels.into_iter().map(Gray::new).collect();
+        PixelBuffer::from_pixels(px, width, height)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    } else {
+        let px: Vec<Rgb<u8>> = rgb::bytemuck::cast_slice(&pixels).to_vec();
+        PixelBuffer::from_pixels(px, width, height)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    };
+
+    Ok(GainMapImage {
+        base,
+        info,
+        gain_map,
+        metadata: gm.metadata,
+        alt_color_info: gm.alt_color_info,
+    })
+}
+
+/// Encode `base` as an AVIF carrying `gain_map` as its ISO 21496-1 gain map
+///
+/// The gain map (`Gray8` or `Rgb8`, usually smaller than the base) is
+/// encoded as 8-bit AV1 at `config`'s quality and speed, then written with
+/// `metadata` as the `tmap` derived item. The base accepts the pixel formats
+/// of [`encode_with`](crate::encode_with).
+///
+/// # Example
+///
+/// ```no_run
+/// use zenavif::{EncoderConfig, decode_with_gainmap, encode_with_gainmap};
+/// use enough::Unstoppable;
+///
+/// let avif_data = std::fs::read("ultrahdr.avif").unwrap();
+/// let image = decode_with_gainmap(&avif_data, &zenavif::DecoderConfig::new(), &Unstoppable)
+///     .unwrap();
+/// let stop = almost_enough::StopToken::new(Unstoppable);
+/// let encoded = encode_with_gainmap(
+///     &image.base,
+///     &image.gain_map,
+///     &image.metadata,
+///     &EncoderConfig::new().quality(80.0),
+///     stop,
+/// )
+/// .unwrap();
+/// ```
+#[cfg(feature = "encode")]
+pub fn encode_with_gainmap(
+    base: &PixelBuffer,
+    gain_map: &PixelBuffer,
+    metadata: &GainMapMetadata,
+    config: &EncoderConfig,
+    stop: almost_enough::StopToken,
+) -> Result<EncodedImage> {
+    let desc = gain_map.descriptor();
+    let (width, height) = (gain_map.width(), gain_map.height());
+    let pixels: Vec<Rgb<u8>> = if desc.layout_compatible(PixelDescriptor::RGB8) {
+        let img = gain_map.try_as_imgref::<Rgb<u8>>().unwrap();
+        img.pixels().collect()
+    } else if desc.layout_compatible(PixelDescriptor::GRAY8) {
+        let img = gain_map.try_as_imgref::<Gray<u8>>().unwrap();
+        img.pixels()
+            .map(|g| Rgb::new(g.value(), g.value(), g.value()))
+            .collect()
+    } else {
+        return Err(at!(Error::Unsupported("gain map must be Gray8 or Rgb8")));
+    };
+
+    let gain_config = EncoderConfig::new()
+        .quality(config.quality)
+        .speed(config.speed)
+        .threads(config.threads)
+        .bit_depth(EncodeBitDepth::Eight);
+    let encoded = crate::encoder::encode_rgb8(
+        ImgVec::new(pixels, width as usize, height as usize).as_ref(),
+        &gain_config,
+        stop.clone(),
+    )?;
+    let av1_data = zenavif_parse::AvifParser::from_bytes(&encoded.avif_file)
+        .and_then(|parser| parser.primary_data().map(|data| data.into_owned()))
+        .map_err(|e| at!(Error::from(e)))?;
+
+    let config = config
+        .clone()
+        .with_gain_map(av1_data, width, height, 8, tmap_payload(metadata));
+    crate::encode_with(base, &config, stop)
+}
+
+/// ISO 21496-1 binary metadata, as stored in the `tmap` item.
+#[cfg(feature = "encode")]
+fn tmap_payload(md: &GainMapMetadata) -> Vec<u8> {
+    let mut out = vec![0]; // version
+    out.extend_from_slice(&0u16.to_be_bytes()); // minimum_version
+    out.extend_from_slice(&0u16.to_be_bytes()); // writer_version
+    out.push((u8::from(md.is_multichannel) << 7) | (u8::from(md.use_base_colour_space) << 6));
+    out.extend_from_slice(&md.base_hdr_headroom_n.to_be_bytes());
+    out.extend_from_slice(&md.base_hdr_headroom_d.to_be_bytes());
+    out.extend_from_slice(&md.alternate_hdr_headroom_n.to_be_bytes());
+    out.extend_from_slice(&md.alternate_hdr_headroom_d.to_be_bytes());
+    let channels = if md.is_multichannel { 3 } else { 1 };
+    for ch in &md.channels[..channels] {
+        for field in [
+            ch.gain_map_min_n.to_be_bytes(),
+            ch.gain_map_min_d.to_be_bytes(),
+            ch.gain_map_max_n.to_be_bytes(),
+            ch.gain_map_max_d.to_be_bytes(),
+            ch.gamma_n.to_be_bytes(),
+            ch.gamma_d.to_be_bytes(),
+            ch.base_offset_n.to_be_bytes(),
+            ch.base_offset_d.to_be_bytes(),
+            ch.alternate_offset_n.to_be_bytes(),
+            ch.alternate_offset_d.to_be_bytes(),
+        ] {
+            out.extend_from_slice(&field);
+        }
+    }
+    out
+}
+
+fn ratio(n: i64, d: u32) -> f32 {
+    if d == 0 { 0.0 } else { n as f32 / d as f32 }
+}
+
+/// Linear interpolation into a 256-entry table at a fractional code value.
+fn lerp_lut(lut: &[f32], code: f32) -> f32 {
+    let i = (code as usize).min(254);
+    let t = code - i as f32;
+    lut[i] + (lut[i + 1] - lut[i]) * t
+}
+
+/// Gain map samples, bilinearly scaled to the base image size.
+struct GainSamples {
+    data: Vec<u8>,
+    width: usize,
+    height: usize,
+    channels: usize,
+}
+
+impl GainSamples {
+    fn new(gain_map: &PixelBuffer) -> Result<Self> {
+        let desc = gain_map.descriptor();
+        let channels = if desc.layout_compatible(PixelDescriptor::GRAY8) {
+            1
+        } else if desc.layout_compatible(PixelDescriptor::RGB8) {
+            3
+        } else {
+            return Err(at!(Error::Unsupported("gain map must be Gray8 or Rgb8")));
+        };
+        let (width, height) = (gain_map.width() as usize, gain_map.height() as usize);
+        if width == 0 || height == 0 {
+            return Err(at!(Error::Unsupported("gain map is empty")));
+        }
+        let src = gain_map.as_slice();
+        let data = (0..height as u32)
+            .flat_map(|y| &src.row(y)[..width * channels])
+            .copied()
+            .collect();
+        Ok(Self {
+            data,
+            width,
+            height,
+            channels,
+        })
+    }
+
+    /// Code values `[0, 255]` per output channel at base pixel `(x, y)`.
+    fn sample(&self, x: usize, y: usize, base_width: usize, base_height: usize) -> [f32; 3] {
+        // Pixel centres of the base image mapped onto the gain map.
+        let map = |p: usize, base: usize, size: usize| {
+            let f = ((p as f32 + 0.5) * size as f32 / base as f32 - 0.5).max(0.0);
+            let i = (f as usize).min(size - 1);
+            (i, (i + 1).min(size - 1), f - i as f32)
+        };
+        let (x0, x1, tx) = map(x, base_width, self.width);
+        let (y0, y1, ty) = map(y, base_height, self.height);
+        core::array::from_fn(|c| {
+            let c = c.min(self.channels - 1);
+            let at =
+                |x: usize, y: usize| f32::from(self.data[(y * self.width + x) * self.channels + c]);
+            let top = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * tx;
+            let bottom = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * tx;
+            top + (bottom - top) * ty
+        })
+    }
+}
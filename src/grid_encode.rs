@@ -0,0 +1,537 @@
+//! Grid encoding of images too large for one AV1 frame.
+//!
+//! The image is cut into equal tiles, each tile is encoded on its own, and
+//! the tiles' AV1 data is stored as hidden `av01` items that a `grid` item
+//! (HEIF §6.6.2.3) puts back together. Edge tiles are padded by repeating
+//! the last column and row; the grid's output size crops the padding off.
+//! Descriptive properties (`colr`, `clli`, `mdcv`, `irot`, `imir`, ...) are
+//! taken from the first tile's file and moved to the grid item, and Exif and
+//! XMP are added last, as for a single-frame encode.
+
+use crate::bmff::{self, boxes, child, item_property_indices, malformed, primary_item_id};
+use crate::encoder::{AlphaComplexity, EncodedImage, EncoderConfig, MAX_FRAME_SIDE};
+use crate::error::{Error, Result};
+use crate::mp4::{put_u16, put_u32, write_box, write_full_box};
+use crate::plane_copy::new_buffer;
+use crate::remux::{self, MetadataItem};
+use almost_enough::Stop;
+use whereat::at;
+use zenpixels::{PixelBuffer, PixelDescriptor};
+
+/// Smallest tile side MIAF allows in a grid.
+const MIN_TILE_SIDE: u32 = 64;
+/// `rows_minus_one` and `columns_minus_one` are single bytes.
+const MAX_TILES_PER_SIDE: u32 = 256;
+
+/// How an image is split into tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct GridLayout {
+    pub columns: u32,
+    pub rows: u32,
+    pub tile_width: u32,
+    pub tile_height: u32,
+}
+
+/// Tile geometry for a `width` × `height` image whose sides may not exceed
+/// `max_side`, or `None` if it fits in a single frame.
+///
+/// Tiles are as equal as possible, so the padding of the last column and
+/// row stays small.
+pub(crate) fn grid_layout(width: u32, height: u32, max_side: u32) -> Result<Option<GridLayout>> {
+    if width <= max_side && height <= max_side {
+        return Ok(None);
+    }
+    let max_side = max_side.clamp(MIN_TILE_SIDE, MAX_FRAME_SIDE as u32);
+    let split = |size: u32| {
+        let tile = size
+            .div_ceil(size.div_ceil(max_side))
+            .max(MIN_TILE_SIDE.min(size));
+        (size.div_ceil(tile), tile)
+    };
+    let (columns, tile_width) = split(width);
+    let (rows, tile_height) = split(height);
+    if columns > MAX_TILES_PER_SIDE || rows > MAX_TILES_PER_SIDE {
+        return Err(at!(Error::Encode(format!(
+            "{width}x{height} needs a {columns}x{rows} grid of {tile_width}x{tile_height} \
+             tiles; a grid has at most {MAX_TILES_PER_SIDE} tiles per side"
+        ))));
+    }
+    Ok(Some(GridLayout {
+        columns,
+        rows,
+        tile_width,
+        tile_height,
+    }))
+}
+
+/// Encode `image` tile by tile and wrap the tiles in a grid.
+pub(crate) fn encode_grid(
+    image: &PixelBuffer,
+    layout: GridLayout,
+    config: &EncoderConfig,
+    stop: almost_enough::StopToken,
+) -> Result<EncodedImage> {
+    if config.gain_map.is_some() {
+        return Err(at!(Error::Unsupported(
+            "gain maps are not supported for grid-encoded images"
+        )));
+    }
+    let tile_count = (layout.columns * layout.rows) as usize;
+    // Color and alpha grid items plus a hidden item per tile each.
+    if 2 * tile_count + 2 > u16::MAX as usize {
+        return Err(at!(Error::Encode(format!(
+            "a {}x{} grid has too many tiles; raise the grid threshold",
+            layout.columns, layout.rows
+        ))));
+    }
+
+    let mut tile_config = config.clone();
+    tile_config.exif = None;
+    tile_config.xmp = None;
+    tile_config.grid_threshold = MAX_FRAME_SIDE as u32;
+    // Classify the whole image's alpha, so all tiles share one quality.
+    if config.alpha_quality.is_none()
+        && let Some(complexity) = alpha_complexity(image)
+    {
+        tile_config.alpha_quality = Some(complexity.alpha_quality(config.quality));
+    }
+
+    // ravif drops the alpha plane of opaque 8-bit images; every tile of a
+    // translucent image needs one for the alpha grid.
+    tile_config.keep_alpha =
+        image.descriptor().layout_compatible(PixelDescriptor::RGBA8) && !alpha8_is_opaque(image);
+
+    let mut properties = None;
+    let mut color = Vec::with_capacity(tile_count);
+    let mut alpha = Vec::with_capacity(tile_count);
+    let mut color_byte_size = 0;
+    let mut alpha_byte_size = 0;
+    for row in 0..layout.rows {
+        for column in 0..layout.columns {
+            stop.check().map_err(|e| at!(Error::from(e)))?;
+            let tile = tile(
+                image,
+                column * layout.tile_width,
+                row * layout.tile_height,
+                layout,
+            )?;
+            let encoded = crate::encode_with(&tile, &tile_config, stop.clone())?;
+            color_byte_size += encoded.color_byte_size;
+            alpha_byte_size += encoded.alpha_byte_size;
+
+            let parser = zenavif_parse::AvifParser::from_bytes(&encoded.avif_file)
+                .map_err(|e| at!(Error::from(e)))?;
+            color.push(
+                parser
+                    .primary_data()
+                    .map_err(|e| at!(Error::from(e)))?
+                    .into_owned(),
+            );
+            if let Some(data) = parser.alpha_data() {
+                alpha.push(data.map_err(|e| at!(Error::from(e)))?.into_owned());
+            }
+            if properties.is_none() {
+                properties = Some(TileProperties::read(&encoded.avif_file)?);
+            }
+        }
+    }
+    let properties = properties.ok_or_else(malformed)?;
+    if !alpha.is_empty() && (alpha.len() != tile_count || properties.alpha.is_none()) {
+        return Err(at!(Error::Encode(
+            "grid tiles disagree on having an alpha plane".into()
+        )));
+    }
+
+    let mut avif_file = write_grid(
+        (image.width(), image.height()),
+        layout,
+        &properties,
+        &color,
+        &alpha,
+    )?;
+    if let Some(exif) = &config.exif {
+        avif_file = remux::add_metadata_item(&avif_file, MetadataItem::Exif(exif))?;
+    }
+    if let Some(xmp) = &config.xmp {
+        avif_file = remux::add_metadata_item(&avif_file, MetadataItem::Xmp(xmp))?;
+    }
+    Ok(EncodedImage {
+        avif_file,
+        color_byte_size,
+        alpha_byte_size,
+    })
+}
+
+/// The tile at `(x, y)`, padded with copies of the image's last column and
+/// row where it extends past the image.
+fn tile(image: &PixelBuffer, x: u32, y: u32, layout: GridLayout) -> Result<PixelBuffer> {
+    let mut tile = new_buffer(layout.tile_width, layout.tile_height, image)?;
+    let bpp = image.descriptor().bytes_per_pixel();
+    let row_bytes = layout.tile_width as usize * bpp;
+    let inside = (image.width() - x).min(layout.tile_width) as usize * bpp;
+    let start = x as usize * bpp;
+    let src = image.as_slice();
+    let mut dst = tile.as_slice_mut();
+    for row in 0..layout.tile_height {
+        let src_row = &src.row((y + row).min(image.height() - 1))[start..start + inside];
+        let dst_row = &mut dst.row_mut(row)[..row_bytes];
+        dst_row[..inside].copy_from_slice(src_row);
+        let last = &src_row[inside - bpp..];
+        for pixel in dst_row[inside..].chunks_exact_mut(bpp) {
+            pixel.copy_from_slice(last);
+        }
+    }
+    drop(dst);
+    Ok(tile)
+}
+
+fn alpha8_is_opaque(image: &PixelBuffer) -> bool {
+    let pixels = image.as_slice();
+    let row_bytes = image.width() as usize * 4;
+    (0..image.height()).all(|y| {
+        pixels.row(y)[..row_bytes]
+            .chunks_exact(4)
+            .all(|p| p[3] == 255)
+    })
+}
+
+/// Alpha complexity of an RGBA8 or RGBA16 image; `None` for layouts
+/// without alpha.
+fn alpha_complexity(image: &PixelBuffer) -> Option<AlphaComplexity> {
+    let desc = image.descriptor();
+    let pixels = image.as_slice();
+    let width = image.width() as usize;
+    let rows = (0..image.height()).map(|y| pixels.row(y));
+    if desc.layout_compatible(PixelDescriptor::RGBA8) {
+        let alpha = rows.flat_map(|row| row[..width * 4].chunks_exact(4).map(|p| p[3].into()));
+        Some(AlphaComplexity::classify(alpha))
+    } else if desc.layout_compatible(PixelDescriptor::RGBA16) {
+        let alpha = rows.flat_map(|row| {
+            row[..width * 8]
+                .chunks_exact(8)
+                .map(|p| u16::from_ne_bytes([p[6], p[7]]))
+        });
+        Some(AlphaComplexity::classify(alpha))
+    } else {
+        None
+    }
+}
+
+/// A property box: type and payload.
+type Property = ([u8; 4], Vec<u8>);
+
+/// Properties of the first tile's color and alpha items.
+struct TileProperties {
+    color: Vec<Property>,
+    /// Properties of the alpha item, if the tile has one.
+    alpha: Option<Vec<Property>>,
+    /// The color item has a `prem` reference to the alpha item.
+    premultiplied: bool,
+}
+
+impl TileProperties {
+    fn read(file: &[u8]) -> Result<Self> {
+        let meta = child(file, b"meta")?.ok_or_else(malformed)?;
+        let meta = meta.get(4..).ok_or_else(malformed)?;
+        let pitm = child(meta, b"pitm")?.ok_or_else(malformed)?;
+        let iprp = child(meta, b"iprp")?.ok_or_else(malformed)?;
+        let ipco = child(iprp, b"ipco")?.ok_or_else(malformed)?;
+        let all = boxes(ipco).collect::<Result<Vec<_>>>()?;
+        let of_item = |item: u32| -> Result<Vec<Property>> {
+            item_property_indices(iprp, item)?
+                .into_iter()
+                .map(|index| {
+                    let &(kind, payload) = index
+                        .checked_sub(1)
+                        .and_then(|i| all.get(i))
+                        .ok_or_else(malformed)?;
+                    Ok((kind, payload.to_vec()))
+                })
+                .collect()
+        };
+
+        let primary = primary_item_id(pitm)?;
+        let (mut alpha, mut premultiplied) = (None, false);
+        if let Some(iref) = child(meta, b"iref")?
+            && let Some(&item) = bmff::references_to(iref, b"auxl", primary)?.first()
+        {
+            alpha = Some(of_item(item)?);
+            premultiplied = bmff::references_to(iref, b"prem", item)?.contains(&primary);
+        }
+        Ok(Self {
+            color: of_item(primary)?,
+            alpha,
+            premultiplied,
+        })
+    }
+}
+
+/// Property types that describe the coded tiles rather than the image.
+const TILE_ONLY: [&[u8; 4]; 5] = [b"ispe", b"av1C", b"a1lx", b"a1op", b"lsel"];
+
+/// Property types readers must understand to display the item.
+const ESSENTIAL: [&[u8; 4]; 5] = [b"av1C", b"a1op", b"lsel", b"irot", b"imir"];
+
+/// Deduplicated `ipco` contents.
+#[derive(Default)]
+struct PropertyTable(Vec<Property>);
+
+impl PropertyTable {
+    /// `ipma` association byte for `property`: 1-based index plus the
+    /// essential flag.
+    fn associate(&mut self, property: &Property) -> u8 {
+        let index = match self.0.iter().position(|p| p == property) {
+            Some(i) => i,
+            None => {
+                self.0.push(property.clone());
+                self.0.len() - 1
+            }
+        };
+        let essential = if ESSENTIAL.contains(&&property.0) {
+            0x80
+        } else {
+            0
+        };
+        essential | (index + 1) as u8
+    }
+}
+
+fn ispe(width: u32, height: u32) -> Property {
+    let mut payload = vec![0; 4];
+    put_u32(&mut payload, width);
+    put_u32(&mut payload, height);
+    (*b"ispe", payload)
+}
+
+/// One item of the output file.
+struct Item<'a> {
+    kind: &'a [u8; 4],
+    hidden: bool,
+    data: &'a [u8],
+    associations: Vec<u8>,
+}
+
+fn write_grid(
+    (width, height): (u32, u32),
+    layout: GridLayout,
+    properties: &TileProperties,
+    color: &[Vec<u8>],
+    alpha: &[Vec<u8>],
+) -> Result<Vec<u8>> {
+    // ImageGrid: version, flags (bit 0: 32-bit output size), then the
+    // rows and columns minus one.
+    let mut grid = vec![0, 0, (layout.rows - 1) as u8, (layout.columns - 1) as u8];
+    if width > u32::from(u16::MAX) || height > u32::from(u16::MAX) {
+        grid[1] = 1;
+        put_u32(&mut grid, width);
+        put_u32(&mut grid, height);
+    } else {
+        put_u16(&mut grid, width as u16);
+        put_u16(&mut grid, height as u16);
+    }
+
+    let mut table = PropertyTable::default();
+    let tile_ispe = ispe(layout.tile_width, layout.tile_height);
+    let image_ispe = ispe(width, height);
+    let mut items = Vec::with_capacity(2 + color.len() + alpha.len());
+    let grid_props = |table: &mut PropertyTable, props: &[Property]| {
+        std::iter::once(table.associate(&image_ispe))
+            .chain(
+                props
+                    .iter()
+                    .filter(|(kind, _)| !TILE_ONLY.contains(&kind))
+                    .map(|p| table.associate(p)),
+            )
+            .collect::<Vec<_>>()
+    };
+    let tile_props = |table: &mut PropertyTable, props: &[Property]| {
+        std::iter::once(table.associate(&tile_ispe))
+            .chain(
+                props
+                    .iter()
+                    .filter(|(kind, _)| matches!(kind, b"av1C" | b"pixi"))
+                    .map(|p| table.associate(p)),
+            )
+            .collect::<Vec<_>>()
+    };
+
+    items.push(Item {
+        kind: b"grid",
+        hidden: false,
+        data: &grid,
+        associations: grid_props(&mut table, &properties.color),
+    });
+    let color_tiles = tile_props(&mut table, &properties.color);
+    items.extend(color.iter().map(|data| Item {
+        kind: b"av01",
+        hidden: true,
+        data,
+        associations: color_tiles.clone(),
+    }));
+    if !alpha.is_empty()
+        && let Some(alpha_props) = &properties.alpha
+    {
+        items.push(Item {
+            kind: b"grid",
+            hidden: false,
+            data: &grid,
+            associations: grid_props(&mut table, alpha_props),
+        });
+        let alpha_tiles = tile_props(&mut table, alpha_props);
+        items.extend(alpha.iter().map(|data| Item {
+            kind: b"av01",
+            hidden: true,
+            data,
+            associations: alpha_tiles.clone(),
+        }));
+    }
+    if table.0.len() > 0x7f {
+        return Err(at!(Error::Encode(
+            "too many distinct item properties for a grid".into()
+        )));
+    }
+    let data_len: usize = items.iter().map(|item| item.data.len()).sum();
+    if data_len > u32::MAX as usize - 65536 {
+        return Err(at!(Error::Encode(
+            "grid too large for 32-bit offsets".into()
+        )));
+    }
+
+    // Item IDs are 1-based positions in `items`: the color grid, its tiles,
+    // then the alpha grid and its tiles.
+    let tiles = color.len() as u16;
+    let alpha_grid = tiles + 2;
+    let mut out = Vec::with_capacity(data_len + 4096 + items.len() * 64);
+    write_box(&mut out, b"ftyp", |b| {
+        b.extend_from_slice(b"avif");
+        put_u32(b, 0);
+        for brand in [b"avif", b"mif1", b"miaf"] {
+            b.extend_from_slice(brand);
+        }
+    });
+    let mut offset_positions = Vec::with_capacity(items.len());
+    write_full_box(&mut out, b"meta", 0, 0, |b| {
+        write_full_box(b, b"hdlr", 0, 0, |b| {
+            put_u32(b, 0);
+            b.extend_from_slice(b"pict");
+            b.extend_from_slice(&[0; 12]);
+            b.push(0); // name
+        });
+        write_full_box(b, b"pitm", 0, 0, |b| put_u16(b, 1));
+        write_full_box(b, b"iloc", 0, 0, |b| {
+            b.push(0x44); // offset_size = 4, length_size = 4
+            b.push(0); // base_offset_size = 0
+            put_u16(b, items.len() as u16);
+            for (id, item) in (1..).zip(&items) {
+                put_u16(b, id);
+                put_u16(b, 0); // data_reference_index
+                put_u16(b, 1); // extent_count
+                offset_positions.push(b.len());
+                put_u32(b, 0);
+                put_u32(b, item.data.len() as u32);
+            }
+        });
+        write_full_box(b, b"iinf", 0, 0, |b| {
+            put_u16(b, items.len() as u16);
+            for (id, item) in (1..).zip(&items) {
+                // flags bit 0: hidden item
+                write_full_box(b, b"infe", 2, item.hidden as u32, |b| {
+                    put_u16(b, id);
+                    put_u16(b, 0); // item_protection_index
+                    b.extend_from_slice(item.kind);
+                    b.push(0); // item_name
+                });
+            }
+        });
+        write_full_box(b, b"iref", 0, 0, |b| {
+            let mut reference = |kind: &[u8; 4], from: u16, to: &mut dyn Iterator<Item = u16>| {
+                let to: Vec<u16> = to.collect();
+                write_box(b, kind, |b| {
+                    put_u16(b, from);
+                    put_u16(b, to.len() as u16);
+                    to.iter().for_each(|&id| put_u16(b, id));
+                });
+            };
+            reference(b"dimg", 1, &mut (2..=tiles + 1));
+            if items.len() > color.len() + 1 {
+                reference(
+                    b"dimg",
+                    alpha_grid,
+                    &mut (alpha_grid + 1..=alpha_grid + tiles),
+                );
+                reference(b"auxl", alpha_grid, &mut std::iter::once(1));
+                if properties.premultiplied {
+                    reference(b"prem", 1, &mut std::iter::once(alpha_grid));
+                }
+            }
+        });
+        write_box(b, b"iprp", |b| {
+            write_box(b, b"ipco", |b| {
+                for (kind, payload) in &table.0 {
+                    write_box(b, kind, |b| b.extend_from_slice(payload));
+                }
+            });
+            write_full_box(b, b"ipma", 0, 0, |b| {
+                put_u32(b, items.len() as u32);
+                for (id, item) in (1..).zip(&items) {
+                    put_u16(b, id);
+                    b.push(item.associations.len() as u8);
+                    b.extend_from_slice(&item.associations);
+                }
+            });
+        });
+    });
+
+    let mut offset = (out.len() + 8) as u32;
+    for (&pos, item) in offset_positions.iter().zip(&items) {
+        out[pos..pos + 4].copy_from_slice(&offset.to_be_bytes());
+        offset += item.data.len() as u32;
+    }
+    write_box(&mut out, b"mdat", |b| {
+        for item in &items {
+            b.extend_from_slice(item.data);
+        }
+    });
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fitting_images_are_not_split() {
+        assert_eq!(grid_layout(65535, 100, 65535).unwrap(), None);
+        assert_eq!(grid_layout(512, 512, 512).unwrap(), None);
+    }
+
+    #[test]
+    fn tiles_are_balanced() {
+        let layout = grid_layout(70_000, 1000, 65535).unwrap().unwrap();
+        assert_eq!(
+            layout,
+            GridLayout {
+                columns: 2,
+                rows: 1,
+                tile_width: 35_000,
+                tile_height: 1000,
+            }
+        );
+        // 1000 / 3 rounds up; the last tile is padded by one pixel.
+        let layout = grid_layout(1000, 300, 400).unwrap().unwrap();
+        assert_eq!((layout.columns, layout.tile_width), (3, 334));
+        assert_eq!((layout.rows, layout.tile_height), (1, 300));
+    }
+
+    #[test]
+    fn tiles_respect_the_miaf_minimum() {
+        let layout = grid_layout(100, 40, 10).unwrap().unwrap();
+        assert_eq!((layout.columns, layout.tile_width), (2, 64));
+        assert_eq!((layout.rows, layout.tile_height), (1, 40));
+    }
+
+    #[test]
+    fn too_many_tiles_is_an_error() {
+        assert!(grid_layout(64 * 257, 64, 64).is_err());
+    }
+}
@@ -30,6 +30,16 @@ pub struct AvifDepthMap {
     pub data: Vec<u8>,
 }
 
+/// Kind of auxiliary image to decode with
+/// [`ManagedAvifDecoder::decode_auxiliary`](crate::ManagedAvifDecoder::decode_auxiliary).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AuxiliaryType {
+    /// Depth map (`auxC` type `urn:mpeg:mpegB:cicp:systems:auxiliary:depth`
+    /// or the HEVC depth URN).
+    Depth,
+}
+
 /// Chroma subsampling format
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChromaSampling {
@@ -43,6 +53,34 @@ pub enum ChromaSampling {
     Monochrome,
 }
 
+/// Position of subsampled chroma samples relative to luma, from the AV1
+/// sequence header's `chroma_sample_position`.
+///
+/// Only meaningful for 4:2:0 content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChromaSamplePosition {
+    /// Not signaled; treated as centered between luma samples on both axes.
+    #[default]
+    Unknown,
+    /// Co-sited with the left luma column, vertically between two luma
+    /// rows (MPEG-2 style).
+    Vertical,
+    /// Co-sited with the top-left luma sample.
+    Colocated,
+}
+
+impl ChromaSamplePosition {
+    /// Map the AV1 `chroma_sample_position` code; the reserved value 3 maps
+    /// to [`Unknown`](Self::Unknown).
+    pub(crate) fn from_av1(code: u8) -> Self {
+        match code {
+            1 => Self::Vertical,
+            2 => Self::Colocated,
+            _ => Self::Unknown,
+        }
+    }
+}
+
 /// Color primaries
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct ColorPrimaries(pub u8);
@@ -105,6 +143,8 @@ impl MatrixCoefficients {
     pub const CHROMAT_NCL: Self = Self(12);
     pub const CHROMAT_CL: Self = Self(13);
     pub const ICTCP: Self = Self(14);
+    pub const YCGCO_RE: Self = Self(16);
+    pub const YCGCO_RO: Self = Self(17);
 }
 
 /// Color range
@@ -117,6 +157,22 @@ pub enum ColorRange {
     Full,
 }
 
+/// Alpha conversion the decoder applied to the returned pixels; see
+/// [`ImageInfo::alpha_transformed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AlphaTransform {
+    /// The file stores premultiplied color and the pixels hold straight
+    /// color: each color sample `c` of a pixel with alpha `a` strictly
+    /// between 0 and the maximum was divided by it, at the decoded depth.
+    ///
+    /// 8-bit sources: `min(255, (c * 255 + a / 2) / a)`. 10/12-bit sources,
+    /// after scaling to 16 bits: `min(65535, c * 65535 / a)`. Fully
+    /// transparent and fully opaque pixels are left as stored. Multiplying
+    /// back with `(c * a + max / 2) / max` restores the stored samples.
+    Unpremultiplied,
+}
+
 /// Metadata about the decoded image
 #[derive(Debug, Clone)]
 pub struct ImageInfo {
@@ -138,10 +194,17 @@ pub struct ImageInfo {
     pub transfer_characteristics: TransferCharacteristics,
     /// Matrix coefficients
     pub matrix_coefficients: MatrixCoefficients,
-    /// Color range (limited or full)
+    /// Color range (limited or full) signaled by the stream.
+    ///
+    /// Converted RGB and gray output is full range unless
+    /// [`DecoderConfig::output_range`](crate::DecoderConfig::output_range)
+    /// or [`RangeClipping::PreserveOvershoot`](crate::RangeClipping) keeps a
+    /// limited-range source in video range.
     pub color_range: ColorRange,
     /// Chroma subsampling
     pub chroma_sampling: ChromaSampling,
+    /// Chroma sample position signaled in the AV1 sequence header
+    pub chroma_sample_position: ChromaSamplePosition,
     /// ICC color profile from the container's `colr` box, if present
     pub icc_profile: Option<Vec<u8>>,
     /// Image rotation from the container's `irot` property
@@ -156,6 +219,14 @@ pub struct ImageInfo {
     pub content_light_level: Option<ContentLightLevel>,
     /// Mastering display colour volume from the container's `mdcv` property
     pub mastering_display: Option<MasteringDisplayColourVolume>,
+    /// HDR10+ dynamic metadata (SMPTE ST 2094-40) from the primary item's
+    /// `it35` property, as the complete ITU-T T.35 message.
+    pub hdr10_plus: Option<Vec<u8>>,
+    /// Bits per channel declared by the primary item's `pixi` property.
+    ///
+    /// The AV1 stream is authoritative for decoding; compare with
+    /// [`bit_depth`](Self::bit_depth) to spot mislabeled files.
+    pub declared_bits_per_channel: Option<Vec<u8>>,
     /// EXIF metadata (TIFF header onwards, AVIF offset prefix stripped)
     pub exif: Option<Vec<u8>>,
     /// XMP metadata (raw XML)
@@ -172,6 +243,127 @@ pub struct ImageInfo {
     /// auxiliary type (`auxC` property matching the MPEG-B depth URN).
     /// The `data` field is a raw AV1 bitstream that can be decoded separately.
     pub depth_map: Option<AvifDepthMap>,
+    /// Grid tiles, as row-major indices, that failed to decode and were
+    /// filled in.
+    ///
+    /// Only ever non-empty for decodes with
+    /// [`DecoderConfig::error_resilient`](crate::DecoderConfig::error_resilient).
+    pub failed_tiles: Vec<usize>,
+    /// Whether the returned pixels hold less precision than the source:
+    /// 10/12-bit samples reduced to 8-bit output by
+    /// [`OutputDepth::Force8`](crate::OutputDepth::Force8).
+    ///
+    /// [`OutputDepth::Auto`](crate::OutputDepth::Auto) only reduces images
+    /// whose samples already sit on 8-bit levels and leaves this `false`.
+    /// [`DecoderConfig::dither`](crate::DecoderConfig::dither) hides the
+    /// banding the reduction can cause.
+    pub lossy_conversion: bool,
+    /// Alpha conversion applied to the returned pixels, if any, so
+    /// round-trip tools can undo it.
+    ///
+    /// [`premultiplied_alpha`](Self::premultiplied_alpha) says what the file
+    /// stores; this says what decoding did about it.
+    pub alpha_transformed: Option<AlphaTransform>,
+}
+
+/// A decoded still image together with its metadata.
+///
+/// Keeps the CICP tags, ICC profile, orientation and HDR metadata attached
+/// to the pixels they describe. Returned by [`crate::decode_image`]; with the
+/// `encode` feature, [`crate::encode_image`] writes the metadata back out.
+#[derive(Debug)]
+pub struct DecodedImage {
+    /// Decoded pixel data.
+    pub pixels: zenpixels::PixelBuffer,
+    /// Metadata of the decoded image.
+    pub info: ImageInfo,
+}
+
+/// One plane of a [`DecodedYuv`] image.
+#[derive(Debug, Clone)]
+pub enum YuvPlane {
+    /// 8-bit samples.
+    U8(imgref::ImgVec<u8>),
+    /// 10- or 12-bit samples in the low bits of each `u16`.
+    U16(imgref::ImgVec<u16>),
+}
+
+impl YuvPlane {
+    /// Width in samples.
+    pub fn width(&self) -> usize {
+        match self {
+            Self::U8(img) => img.width(),
+            Self::U16(img) => img.width(),
+        }
+    }
+
+    /// Height in rows.
+    pub fn height(&self) -> usize {
+        match self {
+            Self::U8(img) => img.height(),
+            Self::U16(img) => img.height(),
+        }
+    }
+
+    /// Distance between rows, in samples.
+    pub fn stride(&self) -> usize {
+        match self {
+            Self::U8(img) => img.stride(),
+            Self::U16(img) => img.stride(),
+        }
+    }
+}
+
+/// A decoded still image as YUV planes, without RGB conversion.
+///
+/// Returned by [`ManagedAvifDecoder::decode_yuv`](crate::ManagedAvifDecoder::decode_yuv).
+/// Samples are as coded: [`ImageInfo::color_range`] tells limited from full
+/// range and [`ImageInfo::matrix_coefficients`] how to get to RGB.
+#[derive(Debug, Clone)]
+pub struct DecodedYuv {
+    /// Luma plane at display size.
+    pub y: YuvPlane,
+    /// Cb plane, subsampled per [`ImageInfo::chroma_sampling`]; `None` for
+    /// monochrome images.
+    pub u: Option<YuvPlane>,
+    /// Cr plane, same size as `u`.
+    pub v: Option<YuvPlane>,
+    /// Alpha plane at display size, if the image has alpha.
+    pub alpha: Option<YuvPlane>,
+    /// Bit depth, chroma sampling, CICP and the rest of the metadata.
+    pub info: ImageInfo,
+}
+
+/// Order of the chroma samples in [`DecodedSemiPlanar::uv`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SemiPlanarOrder {
+    /// Cb before Cr (NV12; P010 for 10-bit) (default).
+    #[default]
+    Nv12,
+    /// Cr before Cb (NV21).
+    Nv21,
+}
+
+/// A decoded 4:2:0 still image as a luma plane and one interleaved chroma
+/// plane, without RGB conversion.
+///
+/// Returned by
+/// [`ManagedAvifDecoder::decode_semi_planar`](crate::ManagedAvifDecoder::decode_semi_planar),
+/// for handing frames to video encoders, GPUs and compositors that take
+/// NV12/NV21. Samples are as coded, like [`DecodedYuv`].
+#[derive(Debug, Clone)]
+pub struct DecodedSemiPlanar {
+    /// Luma plane at display size.
+    pub y: YuvPlane,
+    /// Chroma pairs in `order`: `2 * ceil(width / 2)` samples by
+    /// `ceil(height / 2)` rows.
+    pub uv: YuvPlane,
+    /// Which chroma sample of each pair comes first.
+    pub order: SemiPlanarOrder,
+    /// Alpha plane at display size, if the image has alpha.
+    pub alpha: Option<YuvPlane>,
+    /// Bit depth, CICP and the rest of the metadata.
+    pub info: ImageInfo,
 }
 
 /// A single decoded frame from an animated AVIF sequence.
@@ -219,6 +411,7 @@ impl Default for ImageInfo {
             matrix_coefficients: MatrixCoefficients::default(),
             color_range: ColorRange::default(),
             chroma_sampling: ChromaSampling::Cs420,
+            chroma_sample_position: ChromaSamplePosition::Unknown,
             icc_profile: None,
             rotation: None,
             mirror: None,
@@ -226,10 +419,57 @@ impl Default for ImageInfo {
             pixel_aspect_ratio: None,
             content_light_level: None,
             mastering_display: None,
+            hdr10_plus: None,
+            declared_bits_per_channel: None,
             exif: None,
             xmp: None,
             gain_map: None,
             depth_map: None,
+            failed_tiles: Vec::new(),
+            lossy_conversion: false,
+            alpha_transformed: None,
         }
     }
 }
+
+impl ImageInfo {
+    /// Whether the file carries a depth auxiliary image.
+    pub fn has_depth(&self) -> bool {
+        self.depth_map.is_some()
+    }
+
+    /// Whether the `pixi` property disagrees with the AV1 stream.
+    ///
+    /// True when [`declared_bits_per_channel`](Self::declared_bits_per_channel)
+    /// has the wrong channel count for a colour or monochrome image, or a
+    /// depth other than [`bit_depth`](Self::bit_depth). Files without `pixi`
+    /// never mismatch.
+    pub fn bit_depth_mismatch(&self) -> bool {
+        self.declared_bits_per_channel
+            .as_deref()
+            .is_some_and(|bits| !pixi_matches(bits, self.bit_depth, self.monochrome))
+    }
+
+    /// Reader for orientation, capture time and GPS in [`exif`](Self::exif).
+    ///
+    /// `None` if there is no EXIF or it lacks a valid TIFF header.
+    #[cfg(feature = "exif")]
+    pub fn parsed_exif(&self) -> Option<crate::exif::Exif<'_>> {
+        self.exif.as_deref().and_then(crate::exif::Exif::new)
+    }
+
+    /// Scrub the GPS position from [`exif`](Self::exif) in place, e.g.
+    /// before re-encoding with [`crate::encode_image`]. Returns whether a
+    /// position was removed.
+    #[cfg(feature = "exif")]
+    pub fn strip_gps(&mut self) -> bool {
+        self.exif.as_deref_mut().is_some_and(crate::exif::strip_gps)
+    }
+}
+
+/// Whether `pixi` bits per channel fit an AV1 stream of `bit_depth` with
+/// one (monochrome) or three channels.
+pub(crate) fn pixi_matches(bits: &[u8], bit_depth: u8, monochrome: bool) -> bool {
+    let channels = if monochrome { 1 } else { 3 };
+    bits.len() == channels && bits.iter().all(|&b| b == bit_depth)
+}
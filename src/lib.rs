@@ -20,7 +20,12 @@
 //!
 //! - **`unsafe-asm`**: Hand-written assembly decoder via C FFI (fastest) — overrides the default safe decoder
 //! - **`encode`**: AVIF encoding via zenravif
+//! - **`exif`**: Orientation, capture time and GPS readers for EXIF metadata
 //! - **`zencodec`**: Integration with [`zencodec`](https://crates.io/crates/zencodec) traits
+//! - **`f16`**: Linear-light half-float RGBA output (implies `zencodec`)
+//! - **`fs`**: [`batch::walk`] over directory trees and ZIP archives of AVIF files
+//! - **`riscv-v`**: RISC-V vector kernels (inline assembly, runtime-detected) for YUV conversion and prediction averaging
+//! - **`alloc-stats`**: Per-stage allocation counters for allocation regression tests
 //!
 //! The default decoder uses rav1d-safe's managed API — completely safe Rust
 //! with zero unsafe code in the entire decode path.
@@ -43,18 +48,45 @@
 //! ```
 
 #![cfg_attr(
-    not(any(feature = "unsafe-asm", feature = "_dev")),
+    not(any(
+        feature = "unsafe-asm",
+        feature = "riscv-v",
+        feature = "_dev",
+        feature = "alloc-stats"
+    )),
     forbid(unsafe_code)
 )]
-#![cfg_attr(feature = "_dev", deny(unsafe_code))]
+#![cfg_attr(
+    any(feature = "riscv-v", feature = "_dev", feature = "alloc-stats"),
+    deny(unsafe_code)
+)]
 
 // Crate info for whereat error tracing (enables at!() macro with GitHub links)
 whereat::define_at_crate_info!();
 
+#[cfg(feature = "alloc-stats")]
+#[allow(unsafe_code)]
+pub mod alloc_stats;
+#[cfg(not(feature = "alloc-stats"))]
+pub(crate) mod alloc_stats;
+mod audio;
+mod av1_header;
+mod avis;
+#[cfg(feature = "fs")]
+pub mod batch;
+mod bmff;
+mod cache_key;
+mod chroma_upsample;
+#[cfg(feature = "cms")]
+mod cms;
 #[cfg(feature = "zencodec")]
 mod codec;
+/// Blending one image onto another, e.g. a watermark before encoding.
+pub mod composite;
 mod config;
 mod convert;
+mod deadline;
+mod deband;
 mod decode_av1;
 #[cfg(feature = "unsafe-asm")]
 mod decoder;
@@ -64,17 +96,55 @@ pub mod detect;
 #[cfg(feature = "encode")]
 mod encoder;
 mod error;
+/// Reading orientation, capture time and GPS from EXIF metadata.
+#[cfg(feature = "exif")]
+pub mod exif;
+mod frame_cache;
+mod gainmap;
+#[cfg(feature = "encode")]
+mod grid_encode;
 mod image;
+#[cfg(feature = "encode")]
+mod mono_encode;
+mod mp4;
+mod planar;
+mod plane_copy;
+mod progress;
+mod raw;
+#[cfg(feature = "encode")]
+mod remux;
+mod session;
 #[cfg(feature = "_dev")]
 pub mod simd;
 #[cfg(not(feature = "_dev"))]
 pub(crate) mod simd;
+/// Merging bursts of frames into one noise-reduced still.
+pub mod stack;
+mod streaming;
 mod strip_convert;
+/// Synthetic test patterns for validating converters and display chains.
+pub mod testimg;
+#[cfg(feature = "encode")]
+mod thumbnail;
+mod transfer;
+#[cfg(feature = "encode")]
+mod trial;
 #[cfg(feature = "_dev")]
 pub mod yuv_convert;
 #[cfg(not(feature = "_dev"))]
 pub(crate) mod yuv_convert;
-#[cfg(all(any(target_arch = "x86_64", target_arch = "aarch64"), feature = "_dev"))]
+#[cfg(feature = "_dev")]
+pub mod yuv_convert_16bit;
+#[cfg(not(feature = "_dev"))]
+pub(crate) mod yuv_convert_16bit;
+#[cfg(all(
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    ),
+    feature = "_dev"
+))]
 #[allow(unsafe_code)]
 pub mod yuv_convert_fast;
 #[cfg(feature = "_dev")]
@@ -85,10 +155,21 @@ pub(crate) mod yuv_convert_libyuv;
 pub mod yuv_convert_libyuv_autovec;
 #[cfg(not(feature = "_dev"))]
 pub(crate) mod yuv_convert_libyuv_autovec;
-#[cfg(all(any(target_arch = "x86_64", target_arch = "aarch64"), feature = "_dev"))]
+#[cfg(all(
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "wasm32"
+    ),
+    feature = "_dev"
+))]
 pub mod yuv_convert_libyuv_simd;
 #[cfg(all(
-    any(target_arch = "x86_64", target_arch = "aarch64"),
+    any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "wasm32"
+    ),
     not(feature = "_dev")
 ))]
 pub(crate) mod yuv_convert_libyuv_simd;
@@ -98,35 +179,77 @@ pub(crate) mod yuv_convert_libyuv_simd;
 #[cfg(feature = "encode")]
 use whereat::at;
 
+pub use audio::{AudioSample, AudioTrack, extract_audio_track, has_audio};
+pub use avis::{MuxConfig, from_av1_stream};
 #[cfg(feature = "zencodec")]
 pub use codec::{
     AvifAnimationFrameDecoder, AvifDecodeJob, AvifDecoder as AvifZenDecoder, AvifDecoderConfig,
 };
 #[cfg(all(feature = "zencodec", feature = "encode"))]
 pub use codec::{AvifAnimationFrameEncoder, AvifEncodeJob, AvifEncoder, AvifEncoderConfig};
-pub use config::DecoderConfig;
+#[cfg(feature = "cms")]
+pub use config::Destination;
+pub use config::{
+    AnimationLimitPolicy, ChromaUpsampling, ConversionBackend, DecoderConfig, OutputDepth,
+    OutputLayout, OutputRange, PostProcessFn, RangeClipping, TileFill,
+};
+pub use deadline::DeadlineStop;
 pub use decode_av1::decode_av1_obu;
 #[cfg(feature = "unsafe-asm")]
 pub use decoder::AvifDecoder;
 pub use decoder_managed::{AnimationDecoder, ManagedAvifDecoder};
 #[cfg(feature = "encode")]
 pub use encoder::{
-    AnimationFrame, AnimationFrame16, AnimationFrameRgba, AnimationFrameRgba16, Av1Backend,
-    EncodeAlphaMode, EncodeBitDepth, EncodeColorModel, EncodePixelRange, EncodedAnimation,
-    EncodedImage, EncoderConfig, GainMapConfig, MasteringDisplayConfig, encode_animation_rgb8,
-    encode_animation_rgb16, encode_animation_rgba8, encode_animation_rgba16, encode_rgb8,
-    encode_rgb16, encode_rgba8, encode_rgba16,
+    AnimationFrame, AnimationFrame16, AnimationFrameRgba, AnimationFrameRgba16,
+    AnimationFrameYuv420, Av1Backend, EncodeAlphaMode, EncodeBitDepth, EncodeColorModel,
+    EncodePixelRange, EncodedAnimation, EncodedImage, EncoderConfig, GainMapConfig,
+    MasteringDisplayConfig, YuvCicp, YuvPlanesRef, encode_animation_rgb8, encode_animation_rgb16,
+    encode_animation_rgba8, encode_animation_rgba16, encode_animation_yuv420, encode_gray8,
+    encode_gray16, encode_rgb_with_alpha_plane, encode_rgb8, encode_rgb16, encode_rgba8,
+    encode_rgba16, encode_yuv,
 };
 pub use enough::{Stop, StopReason, Unstoppable};
 pub use error::{Error, Result};
+pub use frame_cache::CachedAnimation;
+#[cfg(feature = "encode")]
+pub use gainmap::encode_with_gainmap;
+pub use gainmap::{GainMapImage, decode_with_gainmap};
 pub use image::{
-    AvifDepthMap, AvifGainMap, ChromaSampling, CleanAperture, ColorPrimaries, ColorRange,
-    ContentLightLevel, DecodedAnimation, DecodedAnimationInfo, DecodedFrame, GainMapChannel,
-    GainMapMetadata, ImageInfo, ImageMirror, ImageRotation, MasteringDisplayColourVolume,
-    MatrixCoefficients, PixelAspectRatio, TransferCharacteristics,
+    AlphaTransform, AuxiliaryType, AvifDepthMap, AvifGainMap, ChromaSamplePosition, ChromaSampling,
+    CleanAperture, ColorPrimaries, ColorRange, ContentLightLevel, DecodedAnimation,
+    DecodedAnimationInfo, DecodedFrame, DecodedImage, DecodedSemiPlanar, DecodedYuv,
+    GainMapChannel, GainMapMetadata, ImageInfo, ImageMirror, ImageRotation,
+    MasteringDisplayColourVolume, MatrixCoefficients, PixelAspectRatio, SemiPlanarOrder,
+    TransferCharacteristics, YuvPlane,
 };
+pub use mp4::to_av1_mp4;
+pub use planar::{PlanarFormat, PlanarLayout, PlanarOptions, PlanarPixels, PlanarSamples};
+pub use plane_copy::{PlaneCopy, Rect};
+pub use progress::ProgressEvent;
+pub use raw::{PixelBufferExt, RawLayout};
+#[cfg(feature = "encode")]
+pub use remux::{MetadataEdit, rewrite_animation_metadata};
+pub use session::{CancelGroup, DecodeSession};
+pub use simd::{SimdReport, SimdTier, simd_report};
+pub use streaming::{StreamStatus, StreamingAvifDecoder};
+#[cfg(feature = "encode")]
+pub use thumbnail::{ThumbnailOptions, thumbnail};
+#[cfg(feature = "encode")]
+pub use trial::{TRIAL_PROBE_SIDE, TrialResult};
 pub use zenpixels::PixelBuffer;
 
+/// The AV1 decoder behind [`decode`] and [`decode_with`] in this build.
+///
+/// `"rav1d-safe"`, the safe managed decoder, by default; `"rav1d-asm"`,
+/// rav1d with its hand-written assembly, with the `unsafe-asm` feature.
+/// YUV to RGB conversion is chosen separately with
+/// [`DecoderConfig::conversion_backend`] and reported by [`simd_report`].
+pub const CODEC_BACKEND: &str = if cfg!(feature = "unsafe-asm") {
+    "rav1d-asm"
+} else {
+    "rav1d-safe"
+};
+
 /// Decode an AVIF image with default settings
 ///
 /// This is a convenience function that uses default decoder settings
@@ -173,11 +296,185 @@ pub fn decode_with(
 
     #[cfg(not(feature = "unsafe-asm"))]
     {
-        let mut decoder = ManagedAvifDecoder::new(data, config)?;
+        let mut decoder = ManagedAvifDecoder::from_slice(data, config)?;
         decoder.decode(stop)
     }
 }
 
+/// Decode an AVIF image together with its metadata, with default settings
+///
+/// Like [`decode`], but the pixels come back paired with their
+/// [`ImageInfo`], so the colour tags and ICC profile stay attached.
+///
+/// # Example
+///
+/// ```no_run
+/// let avif_data = std::fs::read("image.avif").unwrap();
+/// let image = zenavif::decode_image(&avif_data).unwrap();
+/// println!("primaries: {:?}", image.info.color_primaries);
+/// ```
+pub fn decode_image(data: &[u8]) -> Result<DecodedImage> {
+    decode_image_with(data, &DecoderConfig::default(), &Unstoppable)
+}
+
+/// Decode an AVIF image together with its metadata, with custom settings
+/// and cancellation support
+pub fn decode_image_with(
+    data: &[u8],
+    config: &DecoderConfig,
+    stop: &(impl Stop + ?Sized),
+) -> Result<DecodedImage> {
+    #[cfg(feature = "unsafe-asm")]
+    {
+        let mut decoder = AvifDecoder::new(data, config)?;
+        let pixels = decoder.decode(stop)?;
+        let info = decoder.info().clone();
+        Ok(DecodedImage { pixels, info })
+    }
+
+    #[cfg(not(feature = "unsafe-asm"))]
+    {
+        let mut decoder = ManagedAvifDecoder::from_slice(data, config)?;
+        let (pixels, info) = decoder.decode_full(stop)?;
+        Ok(DecodedImage { pixels, info })
+    }
+}
+
+/// Decode an AVIF image, reporting progress to `progress`
+///
+/// Like [`decode_with`], with each stage passed to `progress` as a
+/// [`ProgressEvent`] when it starts, so a GUI can drive a progress bar for
+/// large grid images. Parsing is reported first; for animations, use
+/// [`ManagedAvifDecoder::on_progress`] with
+/// [`ManagedAvifDecoder::decode_animation`] to get per-frame events.
+///
+/// # Example
+///
+/// ```no_run
+/// use zenavif::{DecoderConfig, ProgressEvent, Unstoppable, decode_with_progress};
+///
+/// let data = std::fs::read("image.avif").unwrap();
+/// let pixels = decode_with_progress(&data, &DecoderConfig::new(), &Unstoppable, |event| {
+///     if let ProgressEvent::Tile { index, count } = event {
+///         println!("tile {} of {count}", index + 1);
+///     }
+/// })
+/// .unwrap();
+/// ```
+pub fn decode_with_progress(
+    data: &[u8],
+    config: &DecoderConfig,
+    stop: &(impl Stop + ?Sized),
+    mut progress: impl FnMut(ProgressEvent) + Send,
+) -> Result<PixelBuffer> {
+    progress(ProgressEvent::Parse);
+    let mut decoder = ManagedAvifDecoder::from_slice(data, config)?;
+    decoder.on_progress(progress);
+    decoder.decode(stop)
+}
+
+/// Decode an AVIF image to separate R, G, B (and alpha) planes
+///
+/// For ML preprocessing that wants tensor input: the samples are converted
+/// straight from YUV without an interleaved intermediate, as 8-bit values
+/// or `f32`, with [`PlanarOptions`] normalization and CHW or HWC layout
+/// applied in the same pass. See [`ManagedAvifDecoder::decode_planar`] for
+/// what is and isn't applied.
+///
+/// # Example
+///
+/// ```no_run
+/// use zenavif::{DecoderConfig, PlanarOptions, Unstoppable, decode_planar};
+///
+/// let data = std::fs::read("image.avif").unwrap();
+/// let options = PlanarOptions::new().normalize([0.485, 0.456, 0.406], [0.229, 0.224, 0.225]);
+/// let tensor = decode_planar(&data, &DecoderConfig::new(), &options, &Unstoppable).unwrap();
+/// let red = tensor.plane_f32(0).unwrap();
+/// ```
+pub fn decode_planar(
+    data: &[u8],
+    config: &DecoderConfig,
+    options: &PlanarOptions,
+    stop: &(impl Stop + ?Sized),
+) -> Result<PlanarPixels> {
+    let mut decoder = ManagedAvifDecoder::from_slice(data, config)?;
+    decoder.decode_planar(options, stop)
+}
+
+/// Probe an AVIF file's metadata without decoding pixels
+///
+/// Parses the container and AV1 headers in place, without copying `data`
+/// or constructing an AV1 decoder, and returns the same [`ImageInfo`] as
+/// [`ManagedAvifDecoder::probe_info`].
+///
+/// # Example
+///
+/// ```no_run
+/// let data = std::fs::read("image.avif").unwrap();
+/// let info = zenavif::probe(&data).unwrap();
+/// println!("{}x{}, {} bit", info.width, info.height, info.bit_depth);
+/// ```
+pub fn probe(data: &[u8]) -> Result<ImageInfo> {
+    decoder_managed::probe_bytes(data)
+}
+
+/// Probe metadata for many AVIF files without decoding pixels
+///
+/// Reads only the container and AV1 headers, and never constructs an AV1
+/// decoder, so it suits indexing jobs that need dimensions and metadata for
+/// large collections. Results come back in input order.
+///
+/// `threads` spreads the inputs over that many scoped threads: 0 uses the
+/// available parallelism, 1 probes on the calling thread.
+///
+/// # Example
+///
+/// ```no_run
+/// let files: Vec<Vec<u8>> = ["a.avif", "b.avif"]
+///     .iter()
+///     .map(|p| std::fs::read(p).unwrap())
+///     .collect();
+/// for info in zenavif::probe_batch(&files, 0) {
+///     match info {
+///         Ok(info) => println!("{}x{}", info.width, info.height),
+///         Err(e) => eprintln!("{e:?}"),
+///     }
+/// }
+/// ```
+pub fn probe_batch<D: AsRef<[u8]> + Sync>(inputs: &[D], threads: usize) -> Vec<Result<ImageInfo>> {
+    let threads = match threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .min(inputs.len());
+
+    if threads <= 1 {
+        return inputs
+            .iter()
+            .map(|data| decoder_managed::probe_bytes(data.as_ref()))
+            .collect();
+    }
+
+    let chunk_len = inputs.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = inputs
+            .chunks(chunk_len)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|data| decoder_managed::probe_bytes(data.as_ref()))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("probe worker panicked"))
+            .collect()
+    })
+}
+
 /// Decode an animated AVIF with default settings
 ///
 /// Returns all frames with timing info, or [`Error::Unsupported`] if the
@@ -205,14 +502,13 @@ pub fn decode_animation_with(
     config: &DecoderConfig,
     stop: &(impl Stop + ?Sized),
 ) -> Result<DecodedAnimation> {
-    let mut decoder = ManagedAvifDecoder::new(data, config)?;
+    let mut decoder = ManagedAvifDecoder::from_slice(data, config)?;
     decoder.decode_animation(stop)
 }
 
 /// Encode a decoded image to AVIF with default settings
 ///
-/// Supports Rgb8, Rgba8, Rgb16, and Rgba16 pixel formats. Returns
-/// [`Error::Unsupported`] for grayscale inputs.
+/// Supports Rgb8, Rgba8, Rgb16, Rgba16, Gray8 and Gray16 pixel formats.
 ///
 /// # Example
 ///
@@ -231,10 +527,39 @@ pub fn encode(image: &PixelBuffer) -> Result<EncodedImage> {
     )
 }
 
+/// Encode a [`DecodedImage`], carrying its metadata into the output
+///
+/// CICP primaries and transfer, ICC profile, EXIF, XMP, rotation, mirror
+/// and HDR metadata from `image.info` fill in whatever `config` leaves
+/// unset; see [`EncoderConfig::with_image_info`]. Pixel formats are as for
+/// [`encode_with`].
+///
+/// # Example
+///
+/// ```no_run
+/// use zenavif::{EncoderConfig, decode_image, encode_image};
+///
+/// let avif_data = std::fs::read("image.avif").unwrap();
+/// let image = decode_image(&avif_data).unwrap();
+/// let stop = almost_enough::StopToken::new(enough::Unstoppable);
+/// let encoded = encode_image(&image, &EncoderConfig::new().quality(60.0), stop).unwrap();
+/// std::fs::write("output.avif", &encoded.avif_file).unwrap();
+/// ```
+#[cfg(feature = "encode")]
+pub fn encode_image(
+    image: &DecodedImage,
+    config: &EncoderConfig,
+    stop: almost_enough::StopToken,
+) -> Result<EncodedImage> {
+    let config = config.clone().with_image_info(&image.info);
+    encode_with(&image.pixels, &config, stop)
+}
+
 /// Encode a decoded image to AVIF with custom settings and cancellation
 ///
-/// Supports Rgb8, Rgba8, Rgb16, and Rgba16 pixel formats. Returns
-/// [`Error::Unsupported`] for grayscale inputs.
+/// Supports Rgb8, Rgba8, Rgb16, Rgba16, Gray8 and Gray16 pixel formats;
+/// gray goes through [`encode_gray8`] and [`encode_gray16`]. Images with a side longer
+/// than [`EncoderConfig::grid_threshold`] are encoded as a grid of tiles.
 #[cfg(feature = "encode")]
 pub fn encode_with(
     image: &PixelBuffer,
@@ -243,6 +568,11 @@ pub fn encode_with(
 ) -> Result<EncodedImage> {
     use zenpixels::PixelDescriptor;
 
+    if let Some(layout) =
+        grid_encode::grid_layout(image.width(), image.height(), config.grid_threshold)?
+    {
+        return grid_encode::encode_grid(image, layout, config, stop);
+    }
     let desc = image.descriptor();
     if desc.layout_compatible(PixelDescriptor::RGB8) {
         let img = image.try_as_imgref::<rgb::Rgb<u8>>().unwrap();
@@ -256,9 +586,136 @@ pub fn encode_with(
     } else if desc.layout_compatible(PixelDescriptor::RGBA16) {
         let img = image.try_as_imgref::<rgb::Rgba<u16>>().unwrap();
         encode_rgba16(img, config, stop)
+    } else if desc.layout_compatible(PixelDescriptor::GRAY8) {
+        let img = image.try_as_imgref::<rgb::Gray<u8>>().unwrap();
+        encode_gray8(img, config, stop)
+    } else if desc.layout_compatible(PixelDescriptor::GRAY16) {
+        let img = image.try_as_imgref::<rgb::Gray<u16>>().unwrap();
+        encode_gray16(img, config, stop)
     } else {
         Err(at!(Error::Unsupported(
-            "only RGB/RGBA 8/16-bit encoding is supported",
+            "only RGB/RGBA/gray 8/16-bit encoding is supported",
         )))
     }
 }
+
+/// Define `*_unstoppable` twins of functions whose last parameter is a stop
+/// token, passing a token that never fires.
+macro_rules! unstoppable {
+    ($stop:expr; $($name:ident => $wrapper:ident($($arg:ident: $ty:ty),*) -> $ret:ty;)*) => {
+        $(
+            #[doc = concat!("[`", stringify!($name), "`] without cancellation.")]
+            pub fn $wrapper($($arg: $ty),*) -> $ret {
+                $name($($arg,)* $stop)
+            }
+        )*
+    };
+}
+
+unstoppable! {
+    &Unstoppable;
+    decode_with => decode_with_unstoppable(
+        data: &[u8],
+        config: &DecoderConfig
+    ) -> Result<PixelBuffer>;
+    decode_image_with => decode_image_with_unstoppable(
+        data: &[u8],
+        config: &DecoderConfig
+    ) -> Result<DecodedImage>;
+    decode_animation_with => decode_animation_with_unstoppable(
+        data: &[u8],
+        config: &DecoderConfig
+    ) -> Result<DecodedAnimation>;
+    decode_with_gainmap => decode_with_gainmap_unstoppable(
+        data: &[u8],
+        config: &DecoderConfig
+    ) -> Result<GainMapImage>;
+    decode_planar => decode_planar_unstoppable(
+        data: &[u8],
+        config: &DecoderConfig,
+        options: &PlanarOptions
+    ) -> Result<PlanarPixels>;
+}
+
+#[cfg(feature = "encode")]
+unstoppable! {
+    almost_enough::StopToken::new(Unstoppable);
+    encode_with => encode_with_unstoppable(
+        image: &PixelBuffer,
+        config: &EncoderConfig
+    ) -> Result<EncodedImage>;
+    encode_image => encode_image_unstoppable(
+        image: &DecodedImage,
+        config: &EncoderConfig
+    ) -> Result<EncodedImage>;
+    encode_with_gainmap => encode_with_gainmap_unstoppable(
+        base: &PixelBuffer,
+        gain_map: &PixelBuffer,
+        metadata: &GainMapMetadata,
+        config: &EncoderConfig
+    ) -> Result<EncodedImage>;
+    encode_rgb8 => encode_rgb8_unstoppable(
+        img: imgref::ImgRef<'_, rgb::Rgb<u8>>,
+        config: &EncoderConfig
+    ) -> Result<EncodedImage>;
+    encode_rgba8 => encode_rgba8_unstoppable(
+        img: imgref::ImgRef<'_, rgb::Rgba<u8>>,
+        config: &EncoderConfig
+    ) -> Result<EncodedImage>;
+    encode_rgb16 => encode_rgb16_unstoppable(
+        img: imgref::ImgRef<'_, rgb::Rgb<u16>>,
+        config: &EncoderConfig
+    ) -> Result<EncodedImage>;
+    encode_rgba16 => encode_rgba16_unstoppable(
+        img: imgref::ImgRef<'_, rgb::Rgba<u16>>,
+        config: &EncoderConfig
+    ) -> Result<EncodedImage>;
+    encode_gray8 => encode_gray8_unstoppable(
+        img: imgref::ImgRef<'_, rgb::Gray<u8>>,
+        config: &EncoderConfig
+    ) -> Result<EncodedImage>;
+    encode_gray16 => encode_gray16_unstoppable(
+        img: imgref::ImgRef<'_, rgb::Gray<u16>>,
+        config: &EncoderConfig
+    ) -> Result<EncodedImage>;
+    encode_rgb_with_alpha_plane => encode_rgb_with_alpha_plane_unstoppable(
+        color: imgref::ImgRef<'_, rgb::Rgb<u8>>,
+        alpha: imgref::ImgRef<'_, rgb::Gray<u8>>,
+        config: &EncoderConfig
+    ) -> Result<EncodedImage>;
+    encode_animation_rgb8 => encode_animation_rgb8_unstoppable(
+        frames: &[AnimationFrame],
+        config: &EncoderConfig
+    ) -> Result<EncodedAnimation>;
+    encode_animation_rgba8 => encode_animation_rgba8_unstoppable(
+        frames: &[AnimationFrameRgba],
+        config: &EncoderConfig
+    ) -> Result<EncodedAnimation>;
+    encode_animation_rgb16 => encode_animation_rgb16_unstoppable(
+        frames: &[AnimationFrame16],
+        config: &EncoderConfig
+    ) -> Result<EncodedAnimation>;
+    encode_animation_rgba16 => encode_animation_rgba16_unstoppable(
+        frames: &[AnimationFrameRgba16],
+        config: &EncoderConfig
+    ) -> Result<EncodedAnimation>;
+    encode_animation_yuv420 => encode_animation_yuv420_unstoppable(
+        frames: &[AnimationFrameYuv420<'_>],
+        cicp: YuvCicp,
+        config: &EncoderConfig
+    ) -> Result<EncodedAnimation>;
+    thumbnail => thumbnail_unstoppable(
+        data: &[u8],
+        max_dim: u32,
+        options: &ThumbnailOptions
+    ) -> Result<EncodedImage>;
+}
+
+/// [`encode_yuv`] without cancellation.
+#[cfg(feature = "encode")]
+pub fn encode_yuv_unstoppable<T: Copy + Into<u16>>(
+    planes: YuvPlanesRef<'_, T>,
+    config: &EncoderConfig,
+) -> Result<EncodedImage> {
+    encode_yuv(planes, config, almost_enough::StopToken::new(Unstoppable))
+}
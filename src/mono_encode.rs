@@ -0,0 +1,322 @@
+//! Monochrome (4:0:0) still-image encoding.
+//!
+//! zenravif codes 4:4:4 and 4:2:0 only, so gray images go to zenrav1e
+//! directly as a single luma plane and are wrapped by zenavif-serialize
+//! with the `monochrome` flag set. Rate control mirrors zenravif's, so a
+//! quality setting means the same quantizer for gray and color input.
+
+use crate::encoder::{
+    EncodePixelRange, EncodedImage, EncoderConfig, check_dimensions, cicp_to_color_primaries,
+    cicp_to_transfer_characteristics, resolve_bit_depth, threads_within_memory_limit,
+};
+use crate::error::{Error, Result};
+use almost_enough::Stop;
+use std::sync::Arc;
+use whereat::at;
+use zenavif_serialize::constants;
+use zenrav1e::prelude::{
+    ChromaSamplePosition, ChromaSampling, ColorDescription, Config, Context, EncoderStatus,
+    FrameType, MatrixCoefficients, Pixel, PixelRange, Rational, SceneDetectionSpeed, SpeedSettings,
+    Tune,
+};
+
+/// Encode a single-channel image from full-range samples of `bits` bits (8
+/// or 10), read once as the encoder consumes them.
+///
+/// 8-bit samples code at 8 bits unless the config asks for 10; 10-bit
+/// samples always code at 10. The matrix coefficients have no effect on a
+/// luma-only stream and are signalled as BT.601 unless the config names a
+/// YCbCr matrix.
+pub(crate) fn encode_luma(
+    width: usize,
+    height: usize,
+    luma: impl Iterator<Item = u16> + Send,
+    bits: u8,
+    config: &EncoderConfig,
+    stop: almost_enough::StopToken,
+) -> Result<EncodedImage> {
+    stop.check().map_err(|e| at!(Error::from(e)))?;
+    check_dimensions(width, height)?;
+    let wide = bits > 8;
+    let threads = threads_within_memory_limit(config, width, height, false, wide, false)?;
+    let depth = match resolve_bit_depth(config.bit_depth, wide) {
+        ravif::BitDepth::Eight if !wide => 8,
+        _ => 10,
+    };
+    let limited = config.pixel_range.unwrap_or_default() == EncodePixelRange::Limited;
+    let to_coded = coded_sample(bits, depth, limited);
+
+    let color = ColorDescription {
+        color_primaries: cicp_to_color_primaries(config.color_primaries.unwrap_or(1)),
+        transfer_characteristics: cicp_to_transfer_characteristics(
+            config.transfer_characteristics.unwrap_or(13),
+        ),
+        matrix_coefficients: luma_matrix(config.matrix_coefficients),
+    };
+    let rav1e = rav1e_config(width, height, depth, !limited, color, config, threads);
+    let av1 = if depth == 8 {
+        encode_plane::<u8>(&rav1e, width, height, luma.map(to_coded), stop)?
+    } else {
+        encode_plane::<u16>(&rav1e, width, height, luma.map(to_coded), stop)?
+    };
+
+    let mut aviffy = zenavif_serialize::Aviffy::new();
+    aviffy
+        .set_monochrome(true)
+        .set_chroma_subsampling((true, true))
+        .set_full_color_range(!limited)
+        .set_color_primaries(serialize_primaries(color.color_primaries as u8))
+        .set_transfer_characteristics(serialize_transfer(color.transfer_characteristics as u8))
+        .set_matrix_coefficients(serialize_matrix(color.matrix_coefficients as u8));
+    if let Some(exif) = &config.exif {
+        aviffy.set_exif(exif.clone());
+    }
+    if let Some(xmp) = &config.xmp {
+        aviffy.set_xmp(xmp.clone());
+    }
+    if let Some(icc) = &config.icc_profile {
+        aviffy.set_icc_profile(icc.clone());
+    }
+    if let Some(angle) = config.rotation {
+        aviffy.set_rotation(angle);
+    }
+    if let Some(axis) = config.mirror {
+        aviffy.set_mirror(axis);
+    }
+    if let Some((max_cll, max_fall)) = config.content_light_level {
+        aviffy.set_content_light_level(max_cll, max_fall);
+    }
+    if let Some(md) = config.mastering_display {
+        aviffy.set_mastering_display(
+            md.primaries,
+            md.white_point,
+            md.max_luminance,
+            md.min_luminance,
+        );
+    }
+    if let Some(gm) = &config.gain_map {
+        aviffy.set_gain_map(
+            gm.av1_data.clone(),
+            gm.width,
+            gm.height,
+            gm.bit_depth,
+            gm.metadata.clone(),
+        );
+    }
+    Ok(EncodedImage {
+        avif_file: aviffy.to_vec(&av1, None, width as u32, height as u32, depth),
+        color_byte_size: av1.len(),
+        alpha_byte_size: 0,
+    })
+}
+
+/// Map a full-range sample of `bits` bits to a coded sample of `depth`
+/// bits, widening 8-bit samples by bit replication.
+fn coded_sample(bits: u8, depth: u8, limited: bool) -> impl Fn(u16) -> u16 + use<> {
+    let step = f32::from(1u16 << (depth - 8));
+    let max = f32::from((1u16 << depth) - 1);
+    move |v| {
+        let v = if bits < depth { (v << 2) | (v >> 6) } else { v };
+        if limited {
+            (16.0 * step + f32::from(v) * (219.0 * step / max)).round() as u16
+        } else {
+            v
+        }
+    }
+}
+
+/// Matrix signalled for a luma-only stream: the configured YCbCr matrix,
+/// else BT.601. Identity describes G/B/R planes, which a gray stream
+/// doesn't have.
+fn luma_matrix(configured: Option<u8>) -> MatrixCoefficients {
+    match configured {
+        Some(1) => MatrixCoefficients::BT709,
+        Some(9) => MatrixCoefficients::BT2020NCL,
+        _ => MatrixCoefficients::BT601,
+    }
+}
+
+/// zenravif's quality to quantizer curve.
+fn quality_to_quantizer(quality: f32) -> u8 {
+    let q = quality.clamp(1.0, 100.0) / 100.0;
+    let x = if q >= 0.70 {
+        (1.0 - q) * 1.4
+    } else if q > 0.10 {
+        0.42 + (0.70 - q) * 0.85
+    } else {
+        0.93 + (0.10 - q) * 0.78
+    };
+    (x.min(1.0) * 255.0).round() as u8
+}
+
+/// Still-image rav1e settings for a 4:0:0 frame, as zenravif configures
+/// its alpha plane.
+fn rav1e_config(
+    width: usize,
+    height: usize,
+    depth: u8,
+    full_range: bool,
+    color: ColorDescription,
+    config: &EncoderConfig,
+    threads: Option<usize>,
+) -> Config {
+    #[cfg_attr(not(feature = "encode-imazen"), allow(unused_mut))]
+    let mut quantizer = quality_to_quantizer(config.quality);
+    #[cfg(feature = "encode-imazen")]
+    if config.lossless {
+        quantizer = 0;
+    }
+
+    let mut speed_settings = SpeedSettings::from_preset(config.speed);
+    speed_settings.multiref = false;
+    speed_settings.rdo_lookahead_frames = 1;
+    speed_settings.scene_detection_mode = SceneDetectionSpeed::None;
+    speed_settings.motion.include_near_mvs = false;
+
+    // Don't split into tiles smaller than zenravif would at this speed.
+    let min_tile_size: usize = match config.speed {
+        0 => 4096,
+        1 => 2048,
+        2 => 1024,
+        3 => 512,
+        4 => 256,
+        _ => 128,
+    };
+    let tiles = threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()))
+        .min(width * height / min_tile_size.pow(2));
+
+    #[cfg_attr(not(feature = "encode-imazen"), allow(unused_mut))]
+    let mut encoder = zenrav1e::prelude::EncoderConfig {
+        width,
+        height,
+        time_base: Rational::new(1, 1),
+        sample_aspect_ratio: Rational::new(1, 1),
+        bit_depth: depth.into(),
+        chroma_sampling: ChromaSampling::Cs400,
+        chroma_sample_position: ChromaSamplePosition::Unknown,
+        pixel_range: if full_range {
+            PixelRange::Full
+        } else {
+            PixelRange::Limited
+        },
+        color_description: Some(color),
+        still_picture: true,
+        min_key_frame_interval: 0,
+        max_key_frame_interval: 0,
+        quantizer: quantizer.into(),
+        min_quantizer: quantizer,
+        tune: Tune::Psychovisual,
+        tiles,
+        speed_settings,
+        ..zenrav1e::prelude::EncoderConfig::with_speed_preset(config.speed)
+    };
+    #[cfg(feature = "encode-imazen")]
+    {
+        encoder.enable_qm = config.enable_qm && !config.lossless;
+        encoder.enable_vaq = config.enable_vaq;
+        encoder.vaq_strength = config.vaq_strength;
+        if config.tune_still_image {
+            encoder.tune = Tune::StillImage;
+        }
+    }
+    let rav1e = Config::new().with_encoder_config(encoder);
+    match threads {
+        Some(threads) => rav1e.with_threads(threads),
+        None => rav1e,
+    }
+}
+
+/// Encode one frame from `samples` in raster order and return its key
+/// frame packet.
+fn encode_plane<P: Pixel>(
+    config: &Config,
+    width: usize,
+    height: usize,
+    samples: impl Iterator<Item = u16>,
+    stop: almost_enough::StopToken,
+) -> Result<Vec<u8>> {
+    let invalid = |e: zenrav1e::prelude::InvalidConfig| at!(Error::Encode(e.to_string()));
+    let mut ctx: Context<P> = config.new_context().map_err(invalid)?;
+    ctx.set_stop(Arc::new(stop.clone()));
+
+    let mut frame = ctx.new_frame();
+    let mut samples = samples.map(P::cast_from);
+    for row in frame.planes[0]
+        .mut_slice(Default::default())
+        .rows_iter_mut()
+        .take(height)
+    {
+        for (dst, src) in row[..width].iter_mut().zip(samples.by_ref()) {
+            *dst = src;
+        }
+    }
+    stop.check().map_err(|e| at!(Error::from(e)))?;
+
+    let failed = |e: EncoderStatus| at!(Error::Encode(e.to_string()));
+    ctx.send_frame(frame).map_err(failed)?;
+    ctx.flush();
+    let mut out = Vec::new();
+    loop {
+        match ctx.receive_packet() {
+            Ok(mut packet) if packet.frame_type == FrameType::KEY => out.append(&mut packet.data),
+            Ok(_) => {}
+            Err(EncoderStatus::Encoded | EncoderStatus::LimitReached) => break,
+            Err(e) => {
+                // A stop surfaces as `EncoderStatus::Cancelled`.
+                stop.check().map_err(|e| at!(Error::from(e)))?;
+                return Err(failed(e));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// CICP colour primaries as zenavif-serialize names them; code points it
+/// lacks are written as unspecified, as zenravif does.
+fn serialize_primaries(cp: u8) -> constants::ColorPrimaries {
+    use constants::ColorPrimaries as CP;
+    match cp {
+        1 => CP::Bt709,
+        6 => CP::Bt601,
+        9 => CP::Bt2020,
+        11 => CP::DciP3,
+        12 => CP::DisplayP3,
+        _ => CP::Unspecified,
+    }
+}
+
+/// CICP transfer characteristics as zenavif-serialize names them.
+#[allow(deprecated)]
+fn serialize_transfer(tc: u8) -> constants::TransferCharacteristics {
+    use constants::TransferCharacteristics as TC;
+    match tc {
+        1 => TC::Bt709,
+        4 => TC::Bt470M,
+        5 => TC::Bt470BG,
+        6 => TC::Bt601,
+        7 => TC::Smpte240,
+        8 => TC::Linear,
+        9 => TC::Log,
+        10 => TC::LogSqrt,
+        11 => TC::Iec61966,
+        12 => TC::Bt1361,
+        13 => TC::Srgb,
+        14 => TC::Bt2020_10,
+        15 => TC::Bt2020_12,
+        16 => TC::Smpte2084,
+        17 => TC::Smpte428,
+        18 => TC::Hlg,
+        _ => TC::Unspecified,
+    }
+}
+
+/// CICP matrix coefficients as zenavif-serialize names them.
+fn serialize_matrix(mc: u8) -> constants::MatrixCoefficients {
+    use constants::MatrixCoefficients as MC;
+    match mc {
+        1 => MC::Bt709,
+        9 => MC::Bt2020Ncl,
+        _ => MC::Bt601,
+    }
+}
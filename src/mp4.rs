@@ -0,0 +1,367 @@
+//! Remux AVIF image sequences into fragmented MP4.
+//!
+//! Animated AVIF playback is patchy in browsers while AV1 in MP4 plays in
+//! `<video>` almost everywhere. The color track's samples are copied as-is
+//! into a fragmented MP4 (ISO/IEC 14496-12 with AV1-ISOBMFF sample entries);
+//! nothing is re-encoded. A new fragment starts at every key frame.
+
+use crate::av1_header::{self, SequenceHeader};
+use crate::error::{Error, Result};
+use whereat::at;
+
+/// Movie and media timescale: frame durations are kept in milliseconds.
+pub(crate) const TIMESCALE: u32 = 1000;
+const TRACK_ID: u32 = 1;
+
+/// `sample_depends_on = 2`: decodable on its own.
+const SYNC_SAMPLE_FLAGS: u32 = 0x0200_0000;
+/// `sample_depends_on = 1` plus `sample_is_non_sync_sample`.
+const NON_SYNC_SAMPLE_FLAGS: u32 = 0x0101_0000;
+
+pub(crate) const UNITY_MATRIX: [u32; 9] = [0x0001_0000, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000];
+
+/// One AV1 temporal unit of the color track.
+struct Sample {
+    data: Vec<u8>,
+    duration: u32,
+    sync: bool,
+}
+
+/// Remux an animated AVIF's color track into a fragmented AV1 MP4.
+///
+/// Samples are copied without re-encoding, keeping frame durations at
+/// millisecond precision. The alpha track, loop count, and container
+/// metadata have no `<video>` equivalent and are dropped. Returns
+/// [`Error::Unsupported`] for still images.
+pub fn to_av1_mp4(data: &[u8]) -> Result<Vec<u8>> {
+    let parser = zenavif_parse::AvifParser::from_bytes(data).map_err(|e| at!(Error::from(e)))?;
+    let info = parser
+        .animation_info()
+        .ok_or_else(|| at!(Error::Unsupported("not an animated AVIF")))?;
+
+    let mut seq = None;
+    let mut samples = Vec::with_capacity(info.frame_count);
+    for i in 0..info.frame_count {
+        let frame = parser.frame(i).map_err(|e| at!(Error::from(e)))?;
+        let sync = av1_header::is_sync_sample(&frame.data, &mut seq)
+            .map_err(|_| at!(Error::Unsupported("malformed AV1 frame header")))?;
+        samples.push(Sample {
+            data: frame.data.to_vec(),
+            duration: frame.duration_ms,
+            sync,
+        });
+    }
+    let seq = seq.ok_or_else(|| at!(Error::Unsupported("no AV1 sequence header in track")))?;
+
+    Ok(write_mp4(&seq, &samples))
+}
+
+fn write_mp4(seq: &SequenceHeader, samples: &[Sample]) -> Vec<u8> {
+    let total_duration: u64 = samples.iter().map(|s| s.duration as u64).sum();
+    let payload_len: usize = samples.iter().map(|s| s.data.len()).sum();
+    let mut out = Vec::with_capacity(payload_len + 1024);
+
+    write_box(&mut out, b"ftyp", |b| {
+        b.extend_from_slice(b"iso6");
+        put_u32(b, 0);
+        for brand in [b"iso6", b"iso5", b"av01", b"mp41"] {
+            b.extend_from_slice(brand);
+        }
+    });
+    write_moov(&mut out, seq, total_duration);
+
+    let mut sequence_number = 1;
+    let mut decode_time = 0u64;
+    let mut start = 0;
+    while start < samples.len() {
+        let len = samples[start + 1..]
+            .iter()
+            .position(|s| s.sync)
+            .map_or(samples.len() - start, |n| n + 1);
+        let fragment = &samples[start..start + len];
+        write_fragment(&mut out, sequence_number, decode_time, fragment);
+        sequence_number += 1;
+        decode_time += fragment.iter().map(|s| s.duration as u64).sum::<u64>();
+        start += len;
+    }
+    out
+}
+
+fn write_moov(out: &mut Vec<u8>, seq: &SequenceHeader, total_duration: u64) {
+    let (width, height) = (seq.max_frame_width, seq.max_frame_height);
+    write_box(out, b"moov", |b| {
+        write_full_box(b, b"mvhd", 0, 0, |b| {
+            put_u32(b, 0); // creation_time
+            put_u32(b, 0); // modification_time
+            put_u32(b, TIMESCALE);
+            put_u32(b, 0); // duration: carried by mehd
+            put_u32(b, 0x0001_0000); // rate 1.0
+            put_u16(b, 0x0100); // volume 1.0
+            b.extend_from_slice(&[0; 10]);
+            UNITY_MATRIX.iter().for_each(|&v| put_u32(b, v));
+            b.extend_from_slice(&[0; 24]); // pre_defined
+            put_u32(b, TRACK_ID + 1); // next_track_ID
+        });
+        write_box(b, b"trak", |b| {
+            // flags: track_enabled | track_in_movie
+            write_full_box(b, b"tkhd", 0, 3, |b| {
+                put_u32(b, 0);
+                put_u32(b, 0);
+                put_u32(b, TRACK_ID);
+                put_u32(b, 0);
+                put_u32(b, 0); // duration
+                b.extend_from_slice(&[0; 16]); // reserved, layer, group, volume
+                UNITY_MATRIX.iter().for_each(|&v| put_u32(b, v));
+                put_u32(b, width << 16);
+                put_u32(b, height << 16);
+            });
+            write_box(b, b"mdia", |b| {
+                write_full_box(b, b"mdhd", 0, 0, |b| {
+                    put_u32(b, 0);
+                    put_u32(b, 0);
+                    put_u32(b, TIMESCALE);
+                    put_u32(b, 0);
+                    put_u16(b, 0x55C4); // language "und"
+                    put_u16(b, 0);
+                });
+                write_full_box(b, b"hdlr", 0, 0, |b| {
+                    put_u32(b, 0);
+                    b.extend_from_slice(b"vide");
+                    b.extend_from_slice(&[0; 12]);
+                    b.extend_from_slice(b"VideoHandler\0");
+                });
+                write_box(b, b"minf", |b| {
+                    write_full_box(b, b"vmhd", 0, 1, |b| b.extend_from_slice(&[0; 8]));
+                    write_dinf(b);
+                    write_stbl(b, seq);
+                });
+            });
+        });
+        write_box(b, b"mvex", |b| {
+            write_full_box(b, b"mehd", 1, 0, |b| put_u64(b, total_duration));
+            write_full_box(b, b"trex", 0, 0, |b| {
+                put_u32(b, TRACK_ID);
+                put_u32(b, 1); // default_sample_description_index
+                put_u32(b, 0);
+                put_u32(b, 0);
+                put_u32(b, 0);
+            });
+        });
+    });
+}
+
+/// Sample table with the `av01` sample entry; the samples themselves live
+/// in the fragments.
+fn write_stbl(out: &mut Vec<u8>, seq: &SequenceHeader) {
+    write_box(out, b"stbl", |b| {
+        write_stsd(b, seq, |b| write_colr(b, seq));
+        write_full_box(b, b"stts", 0, 0, |b| put_u32(b, 0));
+        write_full_box(b, b"stsc", 0, 0, |b| put_u32(b, 0));
+        write_full_box(b, b"stsz", 0, 0, |b| {
+            put_u32(b, 0);
+            put_u32(b, 0);
+        });
+        write_full_box(b, b"stco", 0, 0, |b| put_u32(b, 0));
+    });
+}
+
+/// `nclx` colour box repeating the sequence header's `color_config`, which
+/// players read from the sample entry rather than the bitstream.
+fn write_colr(out: &mut Vec<u8>, seq: &SequenceHeader) {
+    write_box(out, b"colr", |b| {
+        b.extend_from_slice(b"nclx");
+        put_u16(b, seq.color_primaries.into());
+        put_u16(b, seq.transfer_characteristics.into());
+        put_u16(b, seq.matrix_coefficients.into());
+        b.push((seq.full_range as u8) << 7);
+    });
+}
+
+/// One `moof` + `mdat` pair.
+fn write_fragment(out: &mut Vec<u8>, sequence_number: u32, decode_time: u64, samples: &[Sample]) {
+    let moof_start = out.len();
+    let mut data_offset_pos = 0;
+    write_box(out, b"moof", |b| {
+        write_full_box(b, b"mfhd", 0, 0, |b| put_u32(b, sequence_number));
+        write_box(b, b"traf", |b| {
+            // flags: default-base-is-moof
+            write_full_box(b, b"tfhd", 0, 0x02_0000, |b| put_u32(b, TRACK_ID));
+            write_full_box(b, b"tfdt", 1, 0, |b| put_u64(b, decode_time));
+            // flags: data offset, then per-sample duration, size and flags
+            write_full_box(b, b"trun", 0, 0x00_0701, |b| {
+                put_u32(b, samples.len() as u32);
+                data_offset_pos = b.len();
+                put_u32(b, 0);
+                for sample in samples {
+                    put_u32(b, sample.duration);
+                    put_u32(b, sample.data.len() as u32);
+                    put_u32(
+                        b,
+                        if sample.sync {
+                            SYNC_SAMPLE_FLAGS
+                        } else {
+                            NON_SYNC_SAMPLE_FLAGS
+                        },
+                    );
+                }
+            });
+        });
+    });
+    // Sample data starts right after the mdat header.
+    let data_offset = (out.len() - moof_start + 8) as u32;
+    out[data_offset_pos..data_offset_pos + 4].copy_from_slice(&data_offset.to_be_bytes());
+
+    write_box(out, b"mdat", |b| {
+        for sample in samples {
+            b.extend_from_slice(&sample.data);
+        }
+    });
+}
+
+/// Data reference table pointing at this file.
+pub(crate) fn write_dinf(out: &mut Vec<u8>) {
+    write_box(out, b"dinf", |b| {
+        write_full_box(b, b"dref", 0, 0, |b| {
+            put_u32(b, 1);
+            // flags: media data is in this file
+            write_full_box(b, b"url ", 0, 1, |_| {});
+        });
+    });
+}
+
+/// Sample description with a single `av01` entry. `extra` appends boxes
+/// after `av1C`.
+pub(crate) fn write_stsd(
+    out: &mut Vec<u8>,
+    seq: &SequenceHeader,
+    extra: impl FnOnce(&mut Vec<u8>),
+) {
+    write_full_box(out, b"stsd", 0, 0, |b| {
+        put_u32(b, 1);
+        write_box(b, b"av01", |b| {
+            b.extend_from_slice(&[0; 6]);
+            put_u16(b, 1); // data_reference_index
+            b.extend_from_slice(&[0; 16]); // pre_defined, reserved
+            put_u16(b, seq.max_frame_width as u16);
+            put_u16(b, seq.max_frame_height as u16);
+            put_u32(b, 0x0048_0000); // 72 dpi
+            put_u32(b, 0x0048_0000);
+            put_u32(b, 0);
+            put_u16(b, 1); // frame_count
+            let mut compressor = [0u8; 32];
+            compressor[0] = 7;
+            compressor[1..8].copy_from_slice(b"zenavif");
+            b.extend_from_slice(&compressor);
+            put_u16(b, 0x0018); // depth
+            put_u16(b, 0xFFFF); // pre_defined = -1
+            write_box(b, b"av1C", |b| b.extend_from_slice(&seq.av1c()));
+            extra(b);
+        });
+    });
+}
+
+pub(crate) fn write_box(out: &mut Vec<u8>, kind: &[u8; 4], body: impl FnOnce(&mut Vec<u8>)) {
+    let start = out.len();
+    put_u32(out, 0);
+    out.extend_from_slice(kind);
+    body(out);
+    let size = (out.len() - start) as u32;
+    out[start..start + 4].copy_from_slice(&size.to_be_bytes());
+}
+
+pub(crate) fn write_full_box(
+    out: &mut Vec<u8>,
+    kind: &[u8; 4],
+    version: u8,
+    flags: u32,
+    body: impl FnOnce(&mut Vec<u8>),
+) {
+    write_box(out, kind, |b| {
+        put_u32(b, ((version as u32) << 24) | flags);
+        body(b);
+    });
+}
+
+pub(crate) fn put_u16(out: &mut Vec<u8>, v: u16) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+pub(crate) fn put_u32(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+pub(crate) fn put_u64(out: &mut Vec<u8>, v: u64) {
+    out.extend_from_slice(&v.to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn top_level_boxes(data: &[u8]) -> Vec<(&[u8], &[u8])> {
+        let mut boxes = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            let size = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            boxes.push((&rest[4..8], &rest[8..size]));
+            rest = &rest[size..];
+        }
+        boxes
+    }
+
+    fn sample(data: &[u8], sync: bool) -> Sample {
+        Sample {
+            data: data.to_vec(),
+            duration: 40,
+            sync,
+        }
+    }
+
+    #[test]
+    fn fragments_start_at_key_frames() {
+        let seq = SequenceHeader::with_size(64, 48);
+        let samples = [
+            sample(&[1, 1], true),
+            sample(&[2], false),
+            sample(&[3, 3, 3], true),
+        ];
+        let mp4 = write_mp4(&seq, &samples);
+
+        let boxes = top_level_boxes(&mp4);
+        let kinds: Vec<&[u8]> = boxes.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(
+            kinds,
+            [b"ftyp", b"moov", b"moof", b"mdat", b"moof", b"mdat"]
+        );
+        assert_eq!(boxes[3].1, [1, 1, 2]);
+        assert_eq!(boxes[5].1, [3, 3, 3]);
+    }
+
+    #[test]
+    fn sample_entry_carries_sequence_colour() {
+        let mut seq = SequenceHeader::with_size(64, 48);
+        seq.color_primaries = 9;
+        seq.transfer_characteristics = 16;
+        seq.matrix_coefficients = 9;
+        seq.full_range = true;
+        let mp4 = write_mp4(&seq, &[sample(&[1], true)]);
+
+        let av1c = mp4.windows(4).position(|w| w == b"av1C").unwrap();
+        let colr = mp4.windows(4).position(|w| w == b"colr").unwrap();
+        assert!(colr > av1c, "colr should follow av1C in the av01 entry");
+        assert_eq!(mp4[colr + 4..colr + 15], *b"nclx\0\x09\0\x10\0\x09\x80");
+    }
+
+    #[test]
+    fn trun_data_offset_points_at_mdat_payload() {
+        let seq = SequenceHeader::default();
+        let mp4 = write_mp4(&seq, &[sample(&[9, 8, 7], true)]);
+
+        let moof_start = mp4.windows(4).position(|w| w == b"moof").unwrap() - 4;
+        let trun = mp4.windows(4).position(|w| w == b"trun").unwrap();
+        // size, type, version/flags, sample_count, then data_offset
+        let offset_pos = trun + 4 + 4 + 4;
+        let offset = u32::from_be_bytes(mp4[offset_pos..offset_pos + 4].try_into().unwrap());
+        assert_eq!(mp4[moof_start + offset as usize..], [9, 8, 7]);
+    }
+}
@@ -0,0 +1,379 @@
+//! Planar RGB output for ML preprocessing.
+//!
+//! Converts decoded YUV planes straight into separate R, G, B (and alpha)
+//! planes, one after the other (CHW), without building an interleaved image
+//! first. Tensor normalization and the HWC layout are applied in the same
+//! pass.
+
+use crate::decoder_managed::{to_our_yuv_matrix, to_our_yuv_range};
+use crate::image::{DecodedYuv, ImageInfo, MatrixCoefficients, YuvPlane};
+use crate::yuv_convert;
+
+/// Sample type of [`PlanarPixels`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlanarFormat {
+    /// 8-bit samples, 0–255 (default).
+    #[default]
+    U8,
+    /// `f32` samples normalized to `[0, 1]`, keeping the precision of
+    /// 10/12-bit sources.
+    F32,
+}
+
+/// Order of the samples in [`PlanarPixels::samples`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlanarLayout {
+    /// Channel after channel, each a full plane (default).
+    #[default]
+    Chw,
+    /// Pixel after pixel, with the channels of each pixel together.
+    Hwc,
+}
+
+/// Sample format, normalization and layout for
+/// [`decode_planar`](crate::decode_planar).
+///
+/// # Example
+///
+/// ```
+/// use zenavif::{PlanarLayout, PlanarOptions};
+///
+/// // ImageNet preprocessing.
+/// let options = PlanarOptions::new()
+///     .normalize([0.485, 0.456, 0.406], [0.229, 0.224, 0.225])
+///     .layout(PlanarLayout::Chw);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlanarOptions {
+    pub(crate) format: PlanarFormat,
+    pub(crate) mean: [f32; 3],
+    pub(crate) std: [f32; 3],
+    pub(crate) layout: PlanarLayout,
+}
+
+impl Default for PlanarOptions {
+    fn default() -> Self {
+        Self {
+            format: PlanarFormat::U8,
+            mean: [0.0; 3],
+            std: [1.0; 3],
+            layout: PlanarLayout::Chw,
+        }
+    }
+}
+
+impl PlanarOptions {
+    /// 8-bit CHW output without normalization.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the sample format.
+    pub fn format(mut self, format: PlanarFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Map each R, G, B sample `v` in `[0, 1]` to `(v - mean[c]) / std[c]`.
+    ///
+    /// Switches to [`PlanarFormat::F32`]; 8-bit output ignores `mean` and
+    /// `std`. Alpha is never normalized.
+    pub fn normalize(mut self, mean: [f32; 3], std: [f32; 3]) -> Self {
+        self.format = PlanarFormat::F32;
+        self.mean = mean;
+        self.std = std;
+        self
+    }
+
+    /// Set the sample layout.
+    pub fn layout(mut self, layout: PlanarLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+}
+
+/// Samples of all channels, in the order of [`PlanarPixels::layout`].
+#[derive(Debug, Clone)]
+pub enum PlanarSamples {
+    /// 8-bit samples.
+    U8(Vec<u8>),
+    /// Normalized `f32` samples.
+    F32(Vec<f32>),
+}
+
+/// A decoded image as separate channel planes.
+///
+/// Returned by [`decode_planar`](crate::decode_planar). Channels are in
+/// R, G, B, alpha order with no row padding. In the default
+/// [`PlanarLayout::Chw`] the planes are stored back to back, each
+/// `width` × `height` samples.
+#[derive(Debug, Clone)]
+pub struct PlanarPixels {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// 3 for RGB, 4 when the image has alpha.
+    pub channels: usize,
+    /// The samples, in `layout` order.
+    pub samples: PlanarSamples,
+    /// Sample order.
+    pub layout: PlanarLayout,
+    /// Bit depth, CICP and the rest of the metadata.
+    pub info: ImageInfo,
+}
+
+impl PlanarPixels {
+    /// Samples in one plane.
+    pub fn plane_len(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Plane `channel` (0 = R, 1 = G, 2 = B, 3 = alpha) of 8-bit output.
+    ///
+    /// `None` for `f32` or HWC output, or a channel the image doesn't have.
+    pub fn plane_u8(&self, channel: usize) -> Option<&[u8]> {
+        match &self.samples {
+            PlanarSamples::U8(samples)
+                if channel < self.channels && self.layout == PlanarLayout::Chw =>
+            {
+                Some(&samples[channel * self.plane_len()..][..self.plane_len()])
+            }
+            _ => None,
+        }
+    }
+
+    /// Plane `channel` (0 = R, 1 = G, 2 = B, 3 = alpha) of `f32` output.
+    ///
+    /// `None` for 8-bit or HWC output, or a channel the image doesn't have.
+    pub fn plane_f32(&self, channel: usize) -> Option<&[f32]> {
+        match &self.samples {
+            PlanarSamples::F32(samples)
+                if channel < self.channels && self.layout == PlanarLayout::Chw =>
+            {
+                Some(&samples[channel * self.plane_len()..][..self.plane_len()])
+            }
+            _ => None,
+        }
+    }
+}
+
+/// An output sample, built from a value nominally in `[0, 1]`.
+trait Sample: Copy + Default {
+    /// Store `v`, shifted by `mean` and scaled by `scale` (`1 / std`).
+    fn from_unit(v: f32, mean: f32, scale: f32) -> Self;
+}
+
+impl Sample for u8 {
+    #[inline(always)]
+    fn from_unit(v: f32, _mean: f32, _scale: f32) -> Self {
+        // Same rounding as the interleaved converters.
+        ((v * 255.0).clamp(0.0, 255.0) + 0.5) as u8
+    }
+}
+
+impl Sample for f32 {
+    #[inline(always)]
+    fn from_unit(v: f32, mean: f32, scale: f32) -> Self {
+        (v.clamp(0.0, 1.0) - mean) * scale
+    }
+}
+
+/// Convert `yuv` to planar RGB(A).
+pub(crate) fn from_yuv(yuv: DecodedYuv, options: &PlanarOptions) -> PlanarPixels {
+    let samples = match options.format {
+        PlanarFormat::U8 => PlanarSamples::U8(convert(&yuv, options)),
+        PlanarFormat::F32 => PlanarSamples::F32(convert(&yuv, options)),
+    };
+    PlanarPixels {
+        width: yuv.y.width() as u32,
+        height: yuv.y.height() as u32,
+        channels: 3 + usize::from(yuv.alpha.is_some()),
+        samples,
+        layout: options.layout,
+        info: yuv.info,
+    }
+}
+
+fn convert<T: Sample>(yuv: &DecodedYuv, options: &PlanarOptions) -> Vec<T> {
+    let (width, height) = (yuv.y.width(), yuv.y.height());
+    let len = width * height;
+    let channels = 3 + usize::from(yuv.alpha.is_some());
+    let mut out = vec![T::default(); len * channels];
+    // Distance between channels of a pixel, and between pixels.
+    let (channel_step, pixel_step) = match options.layout {
+        PlanarLayout::Chw => (len, 1),
+        PlanarLayout::Hwc => (1, channels),
+    };
+    let mean = options.mean;
+    let scale = options.std.map(|s| 1.0 / s);
+    let mut put = |pixel: usize, rgb: [f32; 3]| {
+        for c in 0..3 {
+            out[c * channel_step + pixel * pixel_step] = T::from_unit(rgb[c], mean[c], scale[c]);
+        }
+    };
+
+    let info = &yuv.info;
+    let c = yuv_convert::coefficients(
+        to_our_yuv_matrix(info.matrix_coefficients),
+        to_our_yuv_range(info.color_range),
+        info.bit_depth,
+    );
+    let identity = info.matrix_coefficients == MatrixCoefficients::IDENTITY;
+    let chroma = yuv.u.as_ref().zip(yuv.v.as_ref());
+    // Subsampled chroma is replicated to the luma grid.
+    let (shift_x, shift_y) = chroma.map_or((0, 0), |(u, _)| {
+        (
+            usize::from(u.width() < width),
+            usize::from(u.height() < height),
+        )
+    });
+
+    let mut y_row = vec![0.0; width];
+    let mut u_row = vec![0.0; width.div_ceil(1 << shift_x)];
+    let mut v_row = u_row.clone();
+    for row in 0..height {
+        read_row(&yuv.y, row, &mut y_row);
+        let luma = |s: f32| (s - c.y_offset) * c.y_scale;
+        let start = row * width;
+        let Some((u, v)) = chroma else {
+            for (x, &s) in y_row.iter().enumerate() {
+                let value = luma(s);
+                put(start + x, [value; 3]);
+            }
+            continue;
+        };
+        read_row(u, row >> shift_y, &mut u_row);
+        read_row(v, row >> shift_y, &mut v_row);
+        for (x, &s) in y_row.iter().enumerate() {
+            let (cu, cv) = (u_row[x >> shift_x], v_row[x >> shift_x]);
+            if identity {
+                // GBR: Y carries green, U blue and V red.
+                put(start + x, [luma(cv), luma(s), luma(cu)]);
+                continue;
+            }
+            let yn = luma(s);
+            let un = (cu - c.uv_center) * c.uv_scale;
+            let vn = (cv - c.uv_center) * c.uv_scale;
+            put(
+                start + x,
+                [
+                    vn.mul_add(c.vr, yn),
+                    vn.mul_add(c.vg, un.mul_add(c.ug, yn)),
+                    un.mul_add(c.ub, yn),
+                ],
+            );
+        }
+    }
+
+    if let Some(alpha) = &yuv.alpha {
+        let max = match alpha {
+            YuvPlane::U8(_) => 255.0,
+            YuvPlane::U16(_) => ((1u32 << info.bit_depth) - 1) as f32,
+        };
+        for row in 0..height {
+            read_row(alpha, row, &mut y_row);
+            for (x, &s) in y_row.iter().enumerate() {
+                out[3 * channel_step + (row * width + x) * pixel_step] =
+                    T::from_unit(s / max, 0.0, 1.0);
+            }
+        }
+    }
+    out
+}
+
+/// The first `out.len()` samples of `row` of `plane`, as `f32`.
+fn read_row(plane: &YuvPlane, row: usize, out: &mut [f32]) {
+    match plane {
+        YuvPlane::U8(img) => {
+            let start = row * img.stride();
+            for (o, &s) in out.iter_mut().zip(&img.buf()[start..start + img.width()]) {
+                *o = f32::from(s);
+            }
+        }
+        YuvPlane::U16(img) => {
+            let start = row * img.stride();
+            for (o, &s) in out.iter_mut().zip(&img.buf()[start..start + img.width()]) {
+                *o = f32::from(s);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::{ChromaSampling, ColorRange};
+    use imgref::ImgVec;
+
+    fn yuv(y: Vec<u8>, u: Vec<u8>, v: Vec<u8>, chroma: (usize, usize)) -> DecodedYuv {
+        let info = ImageInfo {
+            width: 2,
+            height: 2,
+            bit_depth: 8,
+            color_range: ColorRange::Full,
+            matrix_coefficients: MatrixCoefficients::BT709,
+            chroma_sampling: ChromaSampling::Cs420,
+            ..Default::default()
+        };
+        DecodedYuv {
+            y: YuvPlane::U8(ImgVec::new(y, 2, 2)),
+            u: Some(YuvPlane::U8(ImgVec::new(u, chroma.0, chroma.1))),
+            v: Some(YuvPlane::U8(ImgVec::new(v, chroma.0, chroma.1))),
+            alpha: None,
+            info,
+        }
+    }
+
+    #[test]
+    fn neutral_chroma_gives_gray_planes() {
+        let planar = from_yuv(
+            yuv(vec![0, 64, 128, 255], vec![128], vec![128], (1, 1)),
+            &PlanarOptions::new(),
+        );
+        assert_eq!(planar.channels, 3);
+        for channel in 0..3 {
+            assert_eq!(planar.plane_u8(channel).unwrap(), [0, 64, 128, 255]);
+        }
+        assert!(planar.plane_u8(3).is_none());
+        assert!(planar.plane_f32(0).is_none());
+    }
+
+    #[test]
+    fn f32_planes_are_normalized() {
+        // Full-range BT.709 red: Y 54, Cb 99, Cr 255.
+        let planar = from_yuv(
+            yuv(vec![54; 4], vec![99], vec![255], (1, 1)),
+            &PlanarOptions::new().format(PlanarFormat::F32),
+        );
+        let (r, g, b) = (
+            planar.plane_f32(0).unwrap(),
+            planar.plane_f32(1).unwrap(),
+            planar.plane_f32(2).unwrap(),
+        );
+        assert!(r.iter().all(|&v| v > 0.98), "{r:?}");
+        assert!(g.iter().chain(b).all(|&v| v < 0.03), "{g:?} {b:?}");
+    }
+
+    #[test]
+    fn hwc_layout_is_normalized_in_place() {
+        let options = PlanarOptions::new()
+            .normalize([0.5, 0.25, 0.0], [0.5, 0.25, 2.0])
+            .layout(PlanarLayout::Hwc);
+        let planar = from_yuv(
+            yuv(vec![0, 64, 128, 255], vec![128], vec![128], (1, 1)),
+            &options,
+        );
+        assert!(planar.plane_f32(0).is_none());
+        let PlanarSamples::F32(samples) = &planar.samples else {
+            panic!("normalize switches to f32");
+        };
+        // Pixel 0 is black and pixel 3 white: (v - mean) / std per channel.
+        let expected = [[-1.0, -1.0, 0.0], [1.0, 3.0, 0.5]];
+        for (pixel, expected) in [0, 3].into_iter().zip(expected) {
+            for (&got, want) in samples[pixel * 3..][..3].iter().zip(expected) {
+                assert!((got - want).abs() < 1e-5, "pixel {pixel}: {got} vs {want}");
+            }
+        }
+    }
+}
@@ -0,0 +1,329 @@
+//! Rectangle copies and fills for pixel buffers of any format.
+//!
+//! Rows are copied as byte slices, so one implementation covers RGB, RGBA
+//! and gray at 8 or 16 bits. Grid stitching, display cropping and the
+//! streaming sinks all go through [`copy_rect`]; damaged grid tiles are
+//! painted with [`fill_rect`].
+
+use crate::error::{Error, Result};
+use whereat::at;
+use zenpixels::{PixelBuffer, PixelDescriptor, PixelSlice, PixelSliceMut};
+
+/// A pixel rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    /// Left edge in pixels
+    pub x: u32,
+    /// Top edge in pixels
+    pub y: u32,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+}
+
+impl Rect {
+    /// Create a rectangle from its top-left corner and size.
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The part of `self` inside a `width` × `height` image.
+    pub(crate) fn clip(self, width: u32, height: u32) -> Self {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        Self {
+            x,
+            y,
+            width: self.width.min(width - x),
+            height: self.height.min(height - y),
+        }
+    }
+
+    /// Whether the pixel at (`x`, `y`) lies inside `self`.
+    pub(crate) fn contains(self, x: u32, y: u32) -> bool {
+        x >= self.x && y >= self.y && x - self.x < self.width && y - self.y < self.height
+    }
+}
+
+/// Crop and blit for [`PixelBuffer`]s, whatever their pixel format.
+pub trait PlaneCopy {
+    /// Copy the part of `rect` inside the image into a new buffer.
+    ///
+    /// The rectangle is clipped to the image; the result keeps the source
+    /// descriptor. Fails if nothing of `rect` lies inside the image.
+    fn crop(&self, rect: Rect) -> Result<PixelBuffer>;
+
+    /// Copy all of `src` into `self` with its top-left corner at `dst_pos`,
+    /// clipped to `self`.
+    ///
+    /// Both buffers must have the same pixel layout.
+    fn blit(&mut self, src: &PixelBuffer, dst_pos: (u32, u32)) -> Result<()>;
+}
+
+impl PlaneCopy for PixelBuffer {
+    fn crop(&self, rect: Rect) -> Result<PixelBuffer> {
+        let rect = rect.clip(self.width(), self.height());
+        if rect.width == 0 || rect.height == 0 {
+            return Err(at!(Error::Unsupported(
+                "crop rectangle lies outside the image"
+            )));
+        }
+        let mut out = new_buffer(rect.width, rect.height, self)?;
+        copy_rect(
+            &self.as_slice(),
+            (rect.x as usize, rect.y as usize),
+            &mut out.as_slice_mut(),
+            (0, 0),
+            rect.width as usize,
+            rect.height as usize,
+        );
+        Ok(out)
+    }
+
+    fn blit(&mut self, src: &PixelBuffer, dst_pos: (u32, u32)) -> Result<()> {
+        if !src.descriptor().layout_compatible(self.descriptor()) {
+            return Err(at!(Error::Unsupported(
+                "blit source and destination pixel layouts differ",
+            )));
+        }
+        let area = Rect::new(dst_pos.0, dst_pos.1, src.width(), src.height())
+            .clip(self.width(), self.height());
+        copy_rect(
+            &src.as_slice(),
+            (0, 0),
+            &mut self.as_slice_mut(),
+            (area.x as usize, area.y as usize),
+            area.width as usize,
+            area.height as usize,
+        );
+        Ok(())
+    }
+}
+
+/// Zeroed `width` × `height` buffer with the descriptor of `like`.
+pub(crate) fn new_buffer(width: u32, height: u32, like: &PixelBuffer) -> Result<PixelBuffer> {
+    let descriptor = like.descriptor();
+    let size = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(descriptor.bytes_per_pixel()))
+        .ok_or_else(|| at!(Error::OutOfMemory))?;
+    PixelBuffer::from_vec(vec![0u8; size], width, height, descriptor).map_err(|_| {
+        at!(Error::Decode {
+            code: -1,
+            msg: "failed to create output buffer",
+        })
+    })
+}
+
+/// Copy a `width` × `height` block from `src` at `src_pos` into `dst` at
+/// `dst_pos`, one row slice at a time.
+///
+/// The caller clips the block to both buffers, which must share a pixel
+/// layout.
+pub(crate) fn copy_rect(
+    src: &PixelSlice<'_>,
+    src_pos: (usize, usize),
+    dst: &mut PixelSliceMut<'_>,
+    dst_pos: (usize, usize),
+    width: usize,
+    height: usize,
+) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    let bpp = src.descriptor().bytes_per_pixel();
+    let bytes = width * bpp;
+    let src_start = src_pos.0 * bpp;
+    let dst_start = dst_pos.0 * bpp;
+    for y in 0..height {
+        let src_row = &src.row((src_pos.1 + y) as u32)[src_start..src_start + bytes];
+        dst.row_mut((dst_pos.1 + y) as u32)[dst_start..dst_start + bytes].copy_from_slice(src_row);
+    }
+}
+
+/// Fill the part of `rect` inside `image` with copies of `pixel`, the
+/// bytes of one pixel in the image's layout.
+pub(crate) fn fill_rect(image: &mut PixelBuffer, rect: Rect, pixel: &[u8]) {
+    if pixel.len() != image.descriptor().bytes_per_pixel() {
+        return;
+    }
+    let rect = rect.clip(image.width(), image.height());
+    let start = rect.x as usize * pixel.len();
+    let bytes = rect.width as usize * pixel.len();
+    let mut dst = image.as_slice_mut();
+    for y in rect.y..rect.y + rect.height {
+        for px in dst.row_mut(y)[start..start + bytes].chunks_exact_mut(pixel.len()) {
+            px.copy_from_slice(pixel);
+        }
+    }
+}
+
+/// Bytes of one pixel of `image`'s layout in the 8-bit RGBA `color`.
+///
+/// 16-bit layouts get the color scaled up, gray layouts its BT.709 luma.
+/// `None` for layouts other than 8/16-bit RGB, RGBA and gray.
+pub(crate) fn color_pixel(image: &PixelBuffer, color: [u8; 4]) -> Option<Vec<u8>> {
+    let (channels, size) = sample_layout(image)?;
+    let [r, g, b, a] = color.map(u16::from);
+    let samples = match channels {
+        1 => vec![(r * 54 + g * 183 + b * 19 + 128) >> 8],
+        3 => vec![r, g, b],
+        _ => vec![r, g, b, a],
+    };
+    Some(pixel_bytes(
+        samples.into_iter().map(|v| (v * 257) >> (8 * (2 - size))),
+        size,
+    ))
+}
+
+/// Average of the pixels just outside `rect` that lie inside `image` but
+/// in none of the `exclude` rectangles, as the bytes of one pixel.
+///
+/// `None` when no such pixel exists, or for layouts other than 8/16-bit
+/// RGB, RGBA and gray.
+pub(crate) fn border_average(image: &PixelBuffer, rect: Rect, exclude: &[Rect]) -> Option<Vec<u8>> {
+    let (channels, size) = sample_layout(image)?;
+    let (x0, y0) = (i64::from(rect.x) - 1, i64::from(rect.y) - 1);
+    let x1 = i64::from(rect.x) + i64::from(rect.width);
+    let y1 = i64::from(rect.y) + i64::from(rect.height);
+    let ring = (x0..=x1)
+        .flat_map(|x| [(x, y0), (x, y1)])
+        .chain((y0 + 1..y1).flat_map(|y| [(x0, y), (x1, y)]));
+
+    let src = image.as_slice();
+    let mut sums = vec![0u64; channels];
+    let mut count = 0u64;
+    for (x, y) in ring {
+        let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+            continue;
+        };
+        if x >= image.width() || y >= image.height() || exclude.iter().any(|r| r.contains(x, y)) {
+            continue;
+        }
+        let px = &src.row(y)[x as usize * channels * size..][..channels * size];
+        for (sum, sample) in sums.iter_mut().zip(px.chunks_exact(size)) {
+            *sum += match *sample {
+                [v] => u64::from(v),
+                [a, b] => u64::from(u16::from_ne_bytes([a, b])),
+                _ => 0,
+            };
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return None;
+    }
+    Some(pixel_bytes(
+        sums.into_iter()
+            .map(|sum| ((sum + count / 2) / count) as u16),
+        size,
+    ))
+}
+
+/// Samples per pixel and bytes per sample of `image`, for the layouts
+/// decoding produces.
+pub(crate) fn sample_layout(image: &PixelBuffer) -> Option<(usize, usize)> {
+    let desc = image.descriptor();
+    [
+        (PixelDescriptor::RGB8, 3, 1),
+        (PixelDescriptor::RGBA8, 4, 1),
+        (PixelDescriptor::GRAY8, 1, 1),
+        (PixelDescriptor::RGB16, 3, 2),
+        (PixelDescriptor::RGBA16, 4, 2),
+        (PixelDescriptor::GRAY16, 1, 2),
+    ]
+    .into_iter()
+    .find(|&(layout, ..)| desc.layout_compatible(layout))
+    .map(|(_, channels, size)| (channels, size))
+}
+
+/// Native-endian bytes of `samples`, each `size` bytes wide.
+fn pixel_bytes(samples: impl Iterator<Item = u16>, size: usize) -> Vec<u8> {
+    samples
+        .flat_map(|v| {
+            if size == 1 {
+                vec![v as u8]
+            } else {
+                v.to_ne_bytes().to_vec()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rgb::{Gray, Rgb};
+
+    fn rgb16(width: u32, height: u32) -> PixelBuffer {
+        let px: Vec<Rgb<u16>> = (1..=(width * height) as u16)
+            .map(|v| Rgb::new(v, v * 10, v * 100))
+            .collect();
+        PixelBuffer::from_pixels(px, width, height).unwrap().into()
+    }
+
+    #[test]
+    fn crop_copies_and_clips() {
+        let image = rgb16(4, 3);
+        let cropped = image.crop(Rect::new(2, 1, 5, 5)).unwrap();
+        assert_eq!((cropped.width(), cropped.height()), (2, 2));
+        let out = cropped.try_as_imgref::<Rgb<u16>>().unwrap();
+        let r: Vec<u16> = out.pixels().map(|p| p.r).collect();
+        assert_eq!(r, [7, 8, 11, 12]);
+        assert_eq!(out.buf()[0], Rgb::new(7, 70, 700));
+
+        assert!(image.crop(Rect::new(4, 0, 1, 1)).is_err());
+    }
+
+    #[test]
+    fn blit_places_and_clips() {
+        let tile = rgb16(2, 2);
+        let mut canvas: PixelBuffer = PixelBuffer::from_pixels(vec![Rgb::new(0u16, 0, 0); 9], 3, 3)
+            .unwrap()
+            .into();
+        canvas.blit(&tile, (2, 1)).unwrap();
+        let out = canvas.try_as_imgref::<Rgb<u16>>().unwrap();
+        let r: Vec<u16> = out.pixels().map(|p| p.r).collect();
+        assert_eq!(r, [0, 0, 0, 0, 0, 1, 0, 0, 3]);
+    }
+
+    #[test]
+    fn blit_rejects_mismatched_layouts() {
+        let gray: PixelBuffer = PixelBuffer::from_pixels(vec![Gray(1u8); 4], 2, 2)
+            .unwrap()
+            .into();
+        let mut canvas = rgb16(2, 2);
+        assert!(canvas.blit(&gray, (0, 0)).is_err());
+    }
+
+    #[test]
+    fn failed_tile_fills() {
+        let mut canvas: PixelBuffer = PixelBuffer::from_pixels(vec![Rgb::new(0u8, 0, 0); 9], 3, 3)
+            .unwrap()
+            .into();
+        fill_rect(&mut canvas, Rect::new(0, 0, 3, 1), &[30, 60, 90]);
+        let hole = Rect::new(1, 1, 5, 5);
+        let pixel = border_average(&canvas, hole, &[hole]).unwrap();
+        // Three of the five bordering pixels inside the image are painted.
+        assert_eq!(pixel, [18, 36, 54]);
+        fill_rect(&mut canvas, hole, &pixel);
+        let out = canvas.try_as_imgref::<Rgb<u8>>().unwrap();
+        assert_eq!(out.buf()[8], Rgb::new(18, 36, 54));
+        assert_eq!(out.buf()[3], Rgb::new(0, 0, 0));
+
+        let gray: PixelBuffer = PixelBuffer::from_pixels(vec![Gray(0u16); 1], 1, 1)
+            .unwrap()
+            .into();
+        assert_eq!(
+            color_pixel(&gray, [255, 255, 255, 0]).unwrap(),
+            65535u16.to_ne_bytes()
+        );
+        assert_eq!(color_pixel(&canvas, [1, 2, 3, 4]).unwrap(), [1, 2, 3]);
+    }
+}
@@ -0,0 +1,42 @@
+//! Progress reporting for long decodes.
+
+/// A decode stage, reported through
+/// [`ManagedAvifDecoder::on_progress`](crate::ManagedAvifDecoder::on_progress)
+/// or [`decode_with_progress`](crate::decode_with_progress) as it starts.
+///
+/// Indices are 0-based, so `Tile { index: count - 1, count }` is the last
+/// tile. Which events arrive depends on the image: stills report
+/// [`PrimaryDecode`](Self::PrimaryDecode), then
+/// [`AlphaDecode`](Self::AlphaDecode) if they have alpha, then
+/// [`ColorConversion`](Self::ColorConversion); grid images report each
+/// [`Tile`](Self::Tile) instead, and animations each [`Frame`](Self::Frame).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProgressEvent {
+    /// Parsing the container.
+    Parse,
+    /// Decoding the primary (color) item.
+    PrimaryDecode,
+    /// Decoding the alpha item. With more than one thread this runs
+    /// alongside the primary decode and is reported right after it.
+    AlphaDecode,
+    /// Decoding and converting grid tile `index` of `count`.
+    Tile {
+        /// Tiles started before this one, in row-major order.
+        index: usize,
+        /// Tiles to decode; fewer than the grid has when a crop skips some.
+        count: usize,
+    },
+    /// Converting YUV to the output pixel format.
+    ColorConversion,
+    /// Decoding and converting animation frame `index` of `count`.
+    Frame {
+        /// The frame.
+        index: usize,
+        /// Frames to decode, after any frame limits.
+        count: usize,
+    },
+}
+
+/// A progress callback borrowed for the decoder's lifetime.
+pub(crate) type ProgressFn<'a> = Box<dyn FnMut(ProgressEvent) + Send + 'a>;
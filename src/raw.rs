@@ -0,0 +1,115 @@
+//! Plain byte access to pixel buffers, for FFI and GPU uploads.
+
+use crate::error::{Error, Result};
+use whereat::at;
+use zenpixels::{PixelBuffer, PixelDescriptor};
+
+/// How the bytes from [`PixelBufferExt::into_raw_parts`] are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawLayout {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Bytes from the start of one row to the next; at least
+    /// `width * descriptor.bytes_per_pixel()`.
+    pub stride: usize,
+    /// Channel order, sample type and transfer of each pixel. Samples
+    /// wider than a byte are native-endian.
+    pub descriptor: PixelDescriptor,
+}
+
+impl RawLayout {
+    /// Layout of tightly packed rows.
+    pub fn packed(width: u32, height: u32, descriptor: PixelDescriptor) -> Self {
+        Self {
+            width,
+            height,
+            stride: width as usize * descriptor.bytes_per_pixel(),
+            descriptor,
+        }
+    }
+
+    /// Bytes of pixel data in each row, excluding padding.
+    pub fn row_bytes(&self) -> usize {
+        self.width as usize * self.descriptor.bytes_per_pixel()
+    }
+}
+
+/// Conversion between [`PixelBuffer`] and plain bytes plus a [`RawLayout`].
+pub trait PixelBufferExt: Sized {
+    /// The pixels as tightly packed rows of bytes, with their layout.
+    fn into_raw_parts(self) -> (Vec<u8>, RawLayout);
+
+    /// Wrap `bytes` laid out as `layout` into a buffer.
+    ///
+    /// Padding at the end of rows is dropped. Fails if `bytes` is too
+    /// short for `layout` or the stride is narrower than a row.
+    fn from_raw_parts(bytes: Vec<u8>, layout: RawLayout) -> Result<Self>;
+}
+
+impl PixelBufferExt for PixelBuffer {
+    fn into_raw_parts(self) -> (Vec<u8>, RawLayout) {
+        let layout = RawLayout::packed(self.width(), self.height(), self.descriptor());
+        let bytes = self.as_slice().contiguous_bytes().to_vec();
+        (bytes, layout)
+    }
+
+    fn from_raw_parts(bytes: Vec<u8>, layout: RawLayout) -> Result<Self> {
+        let mismatch = || at!(Error::Unsupported("raw pixel bytes do not fit the layout"));
+        let row_bytes = layout.row_bytes();
+        let needed = match layout.height {
+            0 => Some(0),
+            h => layout
+                .stride
+                .checked_mul(h as usize - 1)
+                .and_then(|n| n.checked_add(row_bytes)),
+        };
+        if layout.stride < row_bytes || needed.is_none_or(|n| bytes.len() < n) {
+            return Err(mismatch());
+        }
+        let bytes = if layout.stride == row_bytes {
+            let mut bytes = bytes;
+            bytes.truncate(row_bytes * layout.height as usize);
+            bytes
+        } else {
+            bytes
+                .chunks(layout.stride)
+                .take(layout.height as usize)
+                .flat_map(|row| &row[..row_bytes])
+                .copied()
+                .collect()
+        };
+        PixelBuffer::from_vec(bytes, layout.width, layout.height, layout.descriptor)
+            .map_err(|_| mismatch())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rgb::Rgb;
+
+    #[test]
+    fn raw_parts_roundtrip_drops_row_padding() {
+        let px: Vec<Rgb<u8>> = (0..6u8).map(|v| Rgb::new(v, v + 10, v + 20)).collect();
+        let image: PixelBuffer = PixelBuffer::from_pixels(px, 3, 2).unwrap().into();
+        let (bytes, layout) = image.into_raw_parts();
+        assert_eq!(layout, RawLayout::packed(3, 2, PixelDescriptor::RGB8));
+        assert_eq!(bytes[..6], [0, 10, 20, 1, 11, 21]);
+
+        let mut padded = Vec::new();
+        for row in bytes.chunks(layout.row_bytes()) {
+            padded.extend_from_slice(row);
+            padded.extend_from_slice(&[0xEE; 7]);
+        }
+        let strided = RawLayout {
+            stride: layout.row_bytes() + 7,
+            ..layout
+        };
+        let back = PixelBuffer::from_raw_parts(padded, strided).unwrap();
+        assert_eq!(back.into_raw_parts(), (bytes.clone(), layout));
+
+        assert!(PixelBuffer::from_raw_parts(bytes[..17].to_vec(), layout).is_err());
+    }
+}
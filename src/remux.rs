@@ -0,0 +1,1034 @@
+//! Add metadata items and properties to an already-encoded AVIF.
+//!
+//! A metadata item's payload goes into a new `mdat` at the end of the file.
+//! `meta` is rewritten with the extra `iinf`, `iloc` and `iref` entries, or
+//! for a property with the extra `ipco` and `ipma` entries, and every file
+//! offset that points past it is moved by however much it grew. All other
+//! boxes are copied unchanged.
+//!
+//! Animations get the same treatment: the track's chunk offsets move along
+//! with the item offsets, and [`rewrite_animation_metadata`] can also
+//! replace existing EXIF/XMP and patch the loop count in place, so the AV1
+//! samples stay byte for byte what they were.
+
+use crate::bmff::{
+    BoxSpan, ItemLocations, box_spans, boxes, item_locations, item_property_indices, malformed,
+    primary_item_id, read_u8, read_u16, read_u32, read_u64,
+};
+use crate::error::{Error, Result};
+use crate::mp4::{put_u16, put_u32, put_u64, write_box, write_full_box};
+use whereat::at;
+
+/// Metadata changes for [`rewrite_animation_metadata`].
+#[derive(Debug, Clone, Default)]
+pub struct MetadataEdit {
+    exif: Option<Vec<u8>>,
+    xmp: Option<Vec<u8>>,
+    loop_count: Option<u32>,
+}
+
+impl MetadataEdit {
+    /// Create an edit that changes nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the EXIF metadata, replacing any the file has.
+    ///
+    /// `exif` is the item payload, with the same offset prefix as
+    /// [`EncoderConfig::exif`](crate::EncoderConfig::exif).
+    pub fn exif(mut self, exif: Vec<u8>) -> Self {
+        self.exif = Some(exif);
+        self
+    }
+
+    /// Set the XMP metadata, replacing any the file has.
+    pub fn xmp(mut self, xmp: Vec<u8>) -> Self {
+        self.xmp = Some(xmp);
+        self
+    }
+
+    /// Set the number of times the animation plays (0 = infinite), as
+    /// [`MuxConfig::loop_count`](crate::MuxConfig::loop_count) writes it.
+    pub fn loop_count(mut self, count: u32) -> Self {
+        self.loop_count = Some(count);
+        self
+    }
+}
+
+/// Apply `edit` to an animated AVIF without touching its AV1 samples.
+///
+/// New metadata goes into an `mdat` appended to the file. Replaced
+/// metadata keeps its item ID and references; only its location changes,
+/// and the old bytes stay in the file unreferenced. The loop count is
+/// written into every track's edit list and duration. Decoding the result
+/// gives exactly the frames the input did.
+///
+/// Returns [`Error::Unsupported`] for still images and for tracks without an
+/// edit list when a loop count is set.
+pub fn rewrite_animation_metadata(data: &[u8], edit: &MetadataEdit) -> Result<Vec<u8>> {
+    let top = box_spans(data).collect::<Result<Vec<_>>>()?;
+    let Some(moov) = top.iter().find(|span| &span.kind == b"moov") else {
+        return Err(at!(Error::Unsupported("not an animated AVIF")));
+    };
+    let mut out = Vec::new();
+    out.try_reserve(data.len())
+        .map_err(|_| at!(Error::OutOfMemory))?;
+    out.extend_from_slice(data);
+    if let Some(count) = edit.loop_count {
+        set_loop_count(&mut out, moov, count)?;
+    }
+    if let Some(exif) = &edit.exif {
+        out = insert_metadata_item(&out, MetadataItem::Exif(exif), true)?;
+    }
+    if let Some(xmp) = &edit.xmp {
+        out = insert_metadata_item(&out, MetadataItem::Xmp(xmp), true)?;
+    }
+    Ok(out)
+}
+
+const XMP_CONTENT_TYPE: &[u8] = b"application/rdf+xml";
+
+/// A metadata item for [`add_metadata_item`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum MetadataItem<'a> {
+    /// Exif item payload, TIFF header offset included.
+    Exif(&'a [u8]),
+    /// Raw XMP packet.
+    Xmp(&'a [u8]),
+}
+
+impl<'a> MetadataItem<'a> {
+    fn item_type(self) -> &'static [u8; 4] {
+        match self {
+            Self::Exif(_) => b"Exif",
+            Self::Xmp(_) => b"mime",
+        }
+    }
+
+    fn payload(self) -> &'a [u8] {
+        match self {
+            Self::Exif(data) | Self::Xmp(data) => data,
+        }
+    }
+
+    fn is_same_kind(self, info: &ItemInfo<'_>) -> bool {
+        match self {
+            Self::Exif(_) => &info.item_type == b"Exif",
+            Self::Xmp(_) => &info.item_type == b"mime" && info.content_type == XMP_CONTENT_TYPE,
+        }
+    }
+}
+
+/// The parts of an `infe` box needed to tell items apart.
+struct ItemInfo<'a> {
+    id: u32,
+    item_type: [u8; 4],
+    content_type: &'a [u8],
+}
+
+/// Return `file` with `item` added as metadata of the primary item.
+///
+/// Fails if the file already has an item of the same kind, or if its last
+/// top-level box runs to the end of the file.
+pub(crate) fn add_metadata_item(file: &[u8], item: MetadataItem<'_>) -> Result<Vec<u8>> {
+    insert_metadata_item(file, item, false)
+}
+
+/// [`add_metadata_item`], or with `replace`, point an existing item of the
+/// same kind at the new payload instead of failing.
+fn insert_metadata_item(file: &[u8], item: MetadataItem<'_>, replace: bool) -> Result<Vec<u8>> {
+    let top = box_spans(file).collect::<Result<Vec<_>>>()?;
+    let meta = only_meta(&top)?;
+    if let Some(last) = top.last()
+        && read_u32(file, last.start)? == 0
+    {
+        return Err(at!(Error::Unsupported(
+            "cannot append after a box that runs to the end of the file",
+        )));
+    }
+    let payload = item.payload();
+    if payload.len() > (u32::MAX - 8) as usize {
+        return Err(at!(Error::Unsupported("metadata item is too large")));
+    }
+
+    let remux = MetaRemux::new(file, meta, item, replace)?;
+    let meta_len = (meta.end - meta.start) as i64;
+    for wide in [false, true] {
+        let Some(probe) = remux.write(0, 0, wide) else {
+            continue;
+        };
+        // The box layout only depends on `wide`, so the probe has the
+        // final length.
+        let delta = probe.len() as i64 - meta_len;
+        let item_offset = (file.len() as i64 + delta) as u64 + 8;
+        let Some(new_meta) = remux.write(delta, item_offset, wide) else {
+            continue;
+        };
+        let mut out = Vec::new();
+        out.try_reserve(file.len() + new_meta.len() - meta_len as usize + payload.len() + 8)
+            .map_err(|_| at!(Error::OutOfMemory))?;
+        out.extend_from_slice(&file[..meta.start]);
+        out.extend_from_slice(&new_meta);
+        out.extend_from_slice(&file[meta.end..]);
+        write_box(&mut out, b"mdat", |b| b.extend_from_slice(payload));
+        shift_chunk_offsets(&mut out, meta.end as u64, delta)?;
+        return Ok(out);
+    }
+    Err(malformed())
+}
+
+/// Return `file` with a `kind` property, a full box holding `payload`,
+/// associated with the primary item.
+///
+/// The property is appended to `ipco`; nothing is added outside `meta`.
+/// Fails if the primary item already has a `kind` property.
+pub(crate) fn add_primary_property(file: &[u8], kind: &[u8; 4], payload: &[u8]) -> Result<Vec<u8>> {
+    let top = box_spans(file).collect::<Result<Vec<_>>>()?;
+    let meta = only_meta(&top)?;
+    if payload.len() > (u32::MAX - 12) as usize {
+        return Err(at!(Error::Unsupported("item property is too large")));
+    }
+    let meta_payload = &file[meta.payload..meta.end];
+    let header = meta_payload.get(..4).ok_or_else(malformed)?;
+    let body = &meta_payload[4..];
+    let children = box_spans(body).collect::<Result<Vec<_>>>()?;
+    let find = |kind: &[u8; 4]| {
+        children
+            .iter()
+            .find(|span| &span.kind == kind)
+            .map(|span| &body[span.payload..span.end])
+            .ok_or_else(malformed)
+    };
+    let primary = primary_item_id(find(b"pitm")?)?;
+    let locations = item_locations(find(b"iloc")?)?;
+    let iprp = write_iprp(find(b"iprp")?, primary, kind, payload)?;
+
+    let meta_len = (meta.end - meta.start) as i64;
+    for wide in [false, true] {
+        let write = |delta| {
+            let iloc = write_iloc(&locations, meta.end as u64, delta, None, wide)?;
+            let mut out = Vec::new();
+            write_box(&mut out, b"meta", |b| {
+                b.extend_from_slice(header);
+                for span in &children {
+                    match &span.kind {
+                        b"iloc" => b.extend_from_slice(&iloc),
+                        b"iprp" => b.extend_from_slice(&iprp),
+                        _ => b.extend_from_slice(&body[span.start..span.end]),
+                    }
+                }
+            });
+            Some(out)
+        };
+        let Some(probe) = write(0) else {
+            continue;
+        };
+        let delta = probe.len() as i64 - meta_len;
+        let Some(new_meta) = write(delta) else {
+            continue;
+        };
+        let mut out = Vec::new();
+        out.try_reserve(file.len() + new_meta.len() - meta_len as usize)
+            .map_err(|_| at!(Error::OutOfMemory))?;
+        out.extend_from_slice(&file[..meta.start]);
+        out.extend_from_slice(&new_meta);
+        out.extend_from_slice(&file[meta.end..]);
+        shift_chunk_offsets(&mut out, meta.end as u64, delta)?;
+        return Ok(out);
+    }
+    Err(malformed())
+}
+
+/// Move every track chunk offset at or past `meta_end` by `delta`.
+///
+/// `meta` is the only box that changes size, so `moov` keeps its layout and
+/// the offsets are patched where they are.
+fn shift_chunk_offsets(file: &mut [u8], meta_end: u64, delta: i64) -> Result<()> {
+    let top = box_spans(file).collect::<Result<Vec<_>>>()?;
+    let Some(moov) = top.iter().find(|span| &span.kind == b"moov") else {
+        return Ok(());
+    };
+    let mut stbls = Vec::new();
+    for trak in children(file, moov, b"trak")? {
+        for mdia in children(file, &trak, b"mdia")? {
+            for minf in children(file, &mdia, b"minf")? {
+                stbls.extend(children(file, &minf, b"stbl")?);
+            }
+        }
+    }
+    for stbl in &stbls {
+        for (kind, size) in [(b"stco", 4), (b"co64", 8)] {
+            for table in children(file, stbl, kind)? {
+                let count = read_u32(file, table.payload + 4)? as usize;
+                let entries = table.payload + 8;
+                if table.end.saturating_sub(entries) / size < count {
+                    return Err(malformed());
+                }
+                for pos in (0..count).map(|i| entries + i * size) {
+                    let offset = match size {
+                        4 => u64::from(read_u32(file, pos)?),
+                        _ => read_u64(file, pos)?,
+                    };
+                    if offset < meta_end {
+                        continue;
+                    }
+                    let offset = offset.checked_add_signed(delta).ok_or_else(malformed)?;
+                    if size == 8 {
+                        file[pos..pos + 8].copy_from_slice(&offset.to_be_bytes());
+                        continue;
+                    }
+                    let offset = u32::try_from(offset).map_err(|_| {
+                        at!(Error::Unsupported(
+                            "chunk offsets no longer fit in the track's stco box",
+                        ))
+                    })?;
+                    file[pos..pos + 4].copy_from_slice(&offset.to_be_bytes());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write the loop count into each track's edit list and duration, and the
+/// longest track duration into `mvhd`, the way [`crate::MuxConfig`] lays
+/// them out. Nothing changes size.
+fn set_loop_count(file: &mut [u8], moov: &BoxSpan, count: u32) -> Result<()> {
+    let mut longest = 0u64;
+    for trak in children(file, moov, b"trak")? {
+        let Some(elst) = children(file, &trak, b"edts")?
+            .first()
+            .map(|edts| children(file, edts, b"elst"))
+            .transpose()?
+            .and_then(|elst| elst.first().copied())
+        else {
+            return Err(at!(Error::Unsupported(
+                "track has no edit list to carry the loop count",
+            )));
+        };
+        let wide = read_u8(file, elst.payload)? == 1;
+        let entry_len = if wide { 20 } else { 12 };
+        let mut media_duration = 0u64;
+        for i in 0..read_u32(file, elst.payload + 4)? as usize {
+            let pos = elst.payload + 8 + i * entry_len;
+            let segment = if wide {
+                read_u64(file, pos)?
+            } else {
+                u64::from(read_u32(file, pos)?)
+            };
+            media_duration = media_duration.saturating_add(segment);
+        }
+        // flags bit 0: repeat the edit list
+        let flags = elst.payload + 3;
+        file[flags] = file[flags] & !1 | u8::from(count != 1);
+
+        let tkhd = *children(file, &trak, b"tkhd")?
+            .first()
+            .ok_or_else(malformed)?;
+        let wide = read_u8(file, tkhd.payload)? == 1;
+        let pos = tkhd.payload + if wide { 28 } else { 20 };
+        let duration = repeated_duration(media_duration, count, wide);
+        put_duration(file, pos, wide, duration)?;
+        longest = longest.max(media_duration);
+    }
+
+    let mvhd = *children(file, moov, b"mvhd")?
+        .first()
+        .ok_or_else(malformed)?;
+    let wide = read_u8(file, mvhd.payload)? == 1;
+    let pos = mvhd.payload + if wide { 24 } else { 16 };
+    put_duration(file, pos, wide, repeated_duration(longest, count, wide))
+}
+
+/// `media_duration` played `count` times, all ones for 0 (indefinitely).
+fn repeated_duration(media_duration: u64, count: u32, wide: bool) -> u64 {
+    let max = if wide { u64::MAX } else { u64::from(u32::MAX) };
+    match count {
+        0 => max,
+        n => media_duration.saturating_mul(u64::from(n)).min(max - 1),
+    }
+}
+
+fn put_duration(file: &mut [u8], pos: usize, wide: bool, duration: u64) -> Result<()> {
+    if wide {
+        let slot = file.get_mut(pos..pos + 8).ok_or_else(malformed)?;
+        slot.copy_from_slice(&duration.to_be_bytes());
+    } else {
+        let slot = file.get_mut(pos..pos + 4).ok_or_else(malformed)?;
+        slot.copy_from_slice(&(duration as u32).to_be_bytes());
+    }
+    Ok(())
+}
+
+/// The `kind` children of the plain box `parent`, positioned within `file`.
+fn children(file: &[u8], parent: &BoxSpan, kind: &[u8; 4]) -> Result<Vec<BoxSpan>> {
+    let base = parent.payload;
+    let mut found = Vec::new();
+    for span in box_spans(&file[base..parent.end]) {
+        let span = span?;
+        if &span.kind == kind {
+            found.push(BoxSpan {
+                start: base + span.start,
+                payload: base + span.payload,
+                end: base + span.end,
+                ..span
+            });
+        }
+    }
+    Ok(found)
+}
+
+/// The one top-level `meta` box.
+fn only_meta(top: &[BoxSpan]) -> Result<BoxSpan> {
+    let mut metas = top.iter().filter(|span| &span.kind == b"meta");
+    let meta = *metas.next().ok_or_else(malformed)?;
+    if metas.next().is_some() {
+        return Err(malformed());
+    }
+    Ok(meta)
+}
+
+/// `iprp` with the new property appended to `ipco` and associated with
+/// `primary` in `ipma`.
+fn write_iprp(iprp: &[u8], primary: u32, kind: &[u8; 4], payload: &[u8]) -> Result<Vec<u8>> {
+    let children = box_spans(iprp).collect::<Result<Vec<_>>>()?;
+    let ipco = children
+        .iter()
+        .find(|span| &span.kind == b"ipco")
+        .ok_or_else(malformed)?;
+    let properties = boxes(&iprp[ipco.payload..ipco.end]).collect::<Result<Vec<_>>>()?;
+    for index in item_property_indices(iprp, primary)? {
+        if index
+            .checked_sub(1)
+            .and_then(|i| properties.get(i))
+            .is_some_and(|(found, _)| found == kind)
+        {
+            return Err(at!(Error::Unsupported(
+                "primary item already has this property",
+            )));
+        }
+    }
+    let index = u16::try_from(properties.len() + 1)
+        .ok()
+        .filter(|&index| index <= 0x7fff)
+        .ok_or_else(|| at!(Error::Unsupported("too many item properties")))?;
+
+    // The association goes into the `ipma` that already lists the primary
+    // item, since an item may only appear once.
+    let mut ipmas = Vec::new();
+    for span in children.iter().filter(|span| &span.kind == b"ipma") {
+        ipmas.push(parse_ipma(&iprp[span.payload..span.end])?);
+    }
+    let target = ipmas
+        .iter()
+        .position(|ipma| ipma.entries.iter().any(|(id, _)| *id == primary))
+        .unwrap_or(0);
+    let ipma = ipmas.get_mut(target).ok_or_else(malformed)?;
+    match ipma.entries.iter_mut().find(|(id, _)| *id == primary) {
+        Some((_, associations)) => associations.push(index),
+        None => ipma.entries.push((primary, vec![index])),
+    }
+    if ipma
+        .entries
+        .iter()
+        .any(|(_, associations)| associations.len() > 255)
+    {
+        return Err(at!(Error::Unsupported("too many item properties")));
+    }
+
+    let mut out = Vec::new();
+    write_box(&mut out, b"iprp", |b| {
+        let mut ipmas = ipmas.iter();
+        for span in &children {
+            match &span.kind {
+                b"ipco" => write_box(b, b"ipco", |b| {
+                    b.extend_from_slice(&iprp[span.payload..span.end]);
+                    write_full_box(b, kind, 0, 0, |b| b.extend_from_slice(payload));
+                }),
+                b"ipma" => ipmas.next().unwrap().write(b),
+                _ => b.extend_from_slice(&iprp[span.start..span.end]),
+            }
+        }
+    });
+    Ok(out)
+}
+
+/// A parsed `ipma` box.
+struct Ipma {
+    version: u8,
+    flags: u32,
+    /// Item IDs with their associations, essential flag in the top bit.
+    entries: Vec<(u32, Vec<u16>)>,
+}
+
+fn parse_ipma(ipma: &[u8]) -> Result<Ipma> {
+    let version = read_u8(ipma, 0)?;
+    let flags = read_u32(ipma, 0)? & 0xff_ffff;
+    let count = read_u32(ipma, 4)?;
+    let mut pos = 8;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let id = if version < 1 {
+            pos += 2;
+            u32::from(read_u16(ipma, pos - 2)?)
+        } else {
+            pos += 4;
+            read_u32(ipma, pos - 4)?
+        };
+        let count = read_u8(ipma, pos)?;
+        pos += 1;
+        let mut associations = Vec::with_capacity(count.into());
+        for _ in 0..count {
+            associations.push(if flags & 1 != 0 {
+                pos += 2;
+                read_u16(ipma, pos - 2)?
+            } else {
+                // Move the essential flag up to the wide position.
+                pos += 1;
+                let byte = u16::from(read_u8(ipma, pos - 1)?);
+                (byte & 0x80) << 8 | byte & 0x7f
+            });
+        }
+        entries.push((id, associations));
+    }
+    Ok(Ipma {
+        version,
+        flags,
+        entries,
+    })
+}
+
+impl Ipma {
+    /// Write the box, widening IDs and indices that no longer fit.
+    fn write(&self, out: &mut Vec<u8>) {
+        let version = if self.entries.iter().any(|(id, _)| *id > u32::from(u16::MAX)) {
+            self.version.max(1)
+        } else {
+            self.version
+        };
+        let wide_index = self
+            .entries
+            .iter()
+            .flat_map(|(_, associations)| associations)
+            .any(|&a| a & 0x7fff > 0x7f);
+        let flags = if wide_index {
+            self.flags | 1
+        } else {
+            self.flags
+        };
+        write_full_box(out, b"ipma", version, flags, |b| {
+            put_u32(b, self.entries.len() as u32);
+            for (id, associations) in &self.entries {
+                if version < 1 {
+                    put_u16(b, *id as u16);
+                } else {
+                    put_u32(b, *id);
+                }
+                b.push(associations.len() as u8);
+                for &a in associations {
+                    if flags & 1 != 0 {
+                        put_u16(b, a);
+                    } else {
+                        b.push(((a >> 8) & 0x80 | a & 0x7f) as u8);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// A parsed `meta` box and the item being added to it.
+struct MetaRemux<'a> {
+    /// Version and flags of `meta`.
+    header: &'a [u8],
+    /// Child boxes of `meta`, positioned within `body`.
+    body: &'a [u8],
+    children: Vec<BoxSpan>,
+    /// Where `meta` ends in the original file.
+    meta_end: u64,
+    primary: u32,
+    locations: ItemLocations,
+    item: MetadataItem<'a>,
+    item_id: u32,
+    item_len: u64,
+    /// `item_id` is an existing item being pointed at the new payload.
+    replaces: bool,
+}
+
+impl<'a> MetaRemux<'a> {
+    fn new(file: &'a [u8], meta: BoxSpan, item: MetadataItem<'a>, replace: bool) -> Result<Self> {
+        let payload = &file[meta.payload..meta.end];
+        let header = payload.get(..4).ok_or_else(malformed)?;
+        let body = &payload[4..];
+        let children = box_spans(body).collect::<Result<Vec<_>>>()?;
+        let find = |kind: &[u8; 4]| {
+            children
+                .iter()
+                .find(|span| &span.kind == kind)
+                .map(|span| &body[span.payload..span.end])
+                .ok_or_else(malformed)
+        };
+
+        let primary = primary_item_id(find(b"pitm")?)?;
+        let infos = item_infos(find(b"iinf")?)?;
+        let existing = infos.iter().find(|info| item.is_same_kind(info));
+        if existing.is_some() && !replace {
+            return Err(at!(Error::Unsupported(match item {
+                MetadataItem::Exif(_) => "image already has EXIF metadata",
+                MetadataItem::Xmp(_) => "image already has XMP metadata",
+            })));
+        }
+        let locations = item_locations(find(b"iloc")?)?;
+        let item_id = match existing {
+            Some(info) => info.id,
+            None => infos
+                .iter()
+                .map(|info| info.id)
+                .chain(locations.items.iter().map(|loc| loc.item_id))
+                .max()
+                .unwrap_or(0)
+                .checked_add(1)
+                .ok_or_else(malformed)?,
+        };
+        if existing.is_none()
+            && let Ok(iref) = find(b"iref")
+            && read_u32(iref, 0)? >> 24 == 0
+            && item_id.max(primary) > u32::from(u16::MAX)
+        {
+            return Err(at!(Error::Unsupported(
+                "item IDs exceed what the existing iref box can reference",
+            )));
+        }
+
+        Ok(Self {
+            header,
+            body,
+            children,
+            meta_end: meta.end as u64,
+            primary,
+            locations,
+            item,
+            item_id,
+            item_len: item.payload().len() as u64,
+            replaces: existing.is_some(),
+        })
+    }
+
+    /// The rewritten `meta` box, with file offsets behind the old one moved
+    /// by `delta` and the new item at `item_offset`.
+    ///
+    /// `wide` selects 8-byte `iloc` offsets and lengths; without it, `None`
+    /// means some value does not fit in 4 bytes.
+    fn write(&self, delta: i64, item_offset: u64, wide: bool) -> Option<Vec<u8>> {
+        let iloc = write_iloc(
+            &self.locations,
+            self.meta_end,
+            delta,
+            Some((self.item_id, item_offset, self.item_len)),
+            wide,
+        )?;
+        let has_iref = self.children.iter().any(|span| &span.kind == b"iref");
+        let mut out = Vec::new();
+        write_box(&mut out, b"meta", |b| {
+            b.extend_from_slice(self.header);
+            for span in &self.children {
+                let payload = &self.body[span.payload..span.end];
+                match &span.kind {
+                    b"iloc" => b.extend_from_slice(&iloc),
+                    _ if self.replaces => b.extend_from_slice(&self.body[span.start..span.end]),
+                    b"iinf" => {
+                        self.write_iinf(b, payload);
+                        if !has_iref {
+                            self.write_iref(b, None);
+                        }
+                    }
+                    b"iref" => self.write_iref(b, Some(payload)),
+                    _ => b.extend_from_slice(&self.body[span.start..span.end]),
+                }
+            }
+        });
+        Some(out)
+    }
+
+    fn write_iinf(&self, out: &mut Vec<u8>, iinf: &[u8]) {
+        let version = iinf[0];
+        let count_len = if version == 0 { 2 } else { 4 };
+        let count = if version == 0 {
+            u32::from(u16::from_be_bytes([iinf[4], iinf[5]]))
+        } else {
+            u32::from_be_bytes(iinf[4..8].try_into().unwrap())
+        } + 1;
+        let version = if count > u32::from(u16::MAX) {
+            version.max(1)
+        } else {
+            version
+        };
+        write_full_box(out, b"iinf", version, 0, |b| {
+            if version == 0 {
+                put_u16(b, count as u16);
+            } else {
+                put_u32(b, count);
+            }
+            b.extend_from_slice(&iinf[4 + count_len..]);
+            let short_id = self.item_id <= u32::from(u16::MAX);
+            write_full_box(b, b"infe", if short_id { 2 } else { 3 }, 0, |b| {
+                if short_id {
+                    put_u16(b, self.item_id as u16);
+                } else {
+                    put_u32(b, self.item_id);
+                }
+                put_u16(b, 0); // item_protection_index
+                b.extend_from_slice(self.item.item_type());
+                b.push(0); // item_name
+                if let MetadataItem::Xmp(_) = self.item {
+                    b.extend_from_slice(XMP_CONTENT_TYPE);
+                    b.push(0);
+                }
+            });
+        });
+    }
+
+    /// `iref` with a `cdsc` reference from the new item to the primary item
+    /// added to `existing`, if any.
+    fn write_iref(&self, out: &mut Vec<u8>, existing: Option<&[u8]>) {
+        let version = match existing {
+            Some(iref) => iref[0],
+            None if self.item_id > u32::from(u16::MAX) || self.primary > u32::from(u16::MAX) => 1,
+            None => 0,
+        };
+        write_full_box(out, b"iref", version, 0, |b| {
+            if let Some(iref) = existing {
+                b.extend_from_slice(&iref[4..]);
+            }
+            write_box(b, b"cdsc", |b| {
+                if version == 0 {
+                    put_u16(b, self.item_id as u16);
+                    put_u16(b, 1);
+                    put_u16(b, self.primary as u16);
+                } else {
+                    put_u32(b, self.item_id);
+                    put_u16(b, 1);
+                    put_u32(b, self.primary);
+                }
+            });
+        });
+    }
+}
+
+/// `iloc` for `locations`, with offsets behind `meta_end` moved by `delta`
+/// and `extra` (item ID, offset, length) appended, or replacing the entry
+/// of an item with that ID.
+///
+/// `wide` selects 8-byte offsets and lengths; without it, `None` means some
+/// value does not fit in 4 bytes.
+fn write_iloc(
+    locations: &ItemLocations,
+    meta_end: u64,
+    delta: i64,
+    extra: Option<(u32, u64, u64)>,
+    wide: bool,
+) -> Option<Vec<u8>> {
+    // (item_ID, construction_method, data_reference_index, extents)
+    let mut entries = Vec::with_capacity(locations.items.len() + 1);
+    for loc in &locations.items {
+        if extra.is_some_and(|(id, _, _)| id == loc.item_id) {
+            continue;
+        }
+        let mut extents = Vec::with_capacity(loc.extents.len());
+        for &(index, offset, length) in &loc.extents {
+            // Base offsets are folded into the extents.
+            let mut offset = loc.base_offset.checked_add(offset)?;
+            if loc.construction_method == 0 && loc.data_reference_index == 0 && offset >= meta_end {
+                offset = offset.checked_add_signed(delta)?;
+            }
+            extents.push((index, offset, length));
+        }
+        entries.push((
+            loc.item_id,
+            loc.construction_method,
+            loc.data_reference_index,
+            extents,
+        ));
+    }
+    if let Some((id, offset, length)) = extra {
+        entries.push((id, 0, 0, vec![(0, offset, length)]));
+    }
+
+    let fits = |v: u64| wide || v <= u64::from(u32::MAX);
+    if !entries
+        .iter()
+        .flat_map(|entry| &entry.3)
+        .all(|&(_, offset, length)| fits(offset) && fits(length))
+    {
+        return None;
+    }
+    let field_size = if wide { 8 } else { 4 };
+    let short_ids = entries.len() <= usize::from(u16::MAX)
+        && entries.iter().all(|entry| entry.0 <= u32::from(u16::MAX));
+    let index_size = locations.index_size;
+
+    let mut out = Vec::new();
+    write_full_box(&mut out, b"iloc", if short_ids { 1 } else { 2 }, 0, |b| {
+        b.push((field_size << 4 | field_size) as u8);
+        b.push(index_size as u8); // base_offset_size = 0
+        if short_ids {
+            put_u16(b, entries.len() as u16);
+        } else {
+            put_u32(b, entries.len() as u32);
+        }
+        for (id, construction_method, data_reference_index, extents) in &entries {
+            if short_ids {
+                put_u16(b, *id as u16);
+            } else {
+                put_u32(b, *id);
+            }
+            put_u16(b, u16::from(*construction_method));
+            put_u16(b, *data_reference_index);
+            put_u16(b, extents.len() as u16);
+            for &(index, offset, length) in extents {
+                put_uint(b, index, index_size);
+                put_uint(b, offset, field_size);
+                put_uint(b, length, field_size);
+            }
+        }
+    });
+    Some(out)
+}
+
+/// Items listed in the payload of an `iinf` box.
+fn item_infos(iinf: &[u8]) -> Result<Vec<ItemInfo<'_>>> {
+    let entries = if read_u8(iinf, 0)? == 0 { 6 } else { 8 };
+    let mut infos = Vec::new();
+    for span in box_spans(iinf.get(entries..).ok_or_else(malformed)?) {
+        let span = span?;
+        if &span.kind != b"infe" {
+            continue;
+        }
+        let infe = &iinf[entries + span.payload..entries + span.end];
+        let version = read_u8(infe, 0)?;
+        let (id, item_type, name) = match version {
+            0 | 1 => (u32::from(read_u16(infe, 4)?), *b"\0\0\0\0", 8),
+            2 => (u32::from(read_u16(infe, 4)?), read_fourcc(infe, 8)?, 12),
+            _ => (read_u32(infe, 4)?, read_fourcc(infe, 10)?, 14),
+        };
+        // item_name, then content_type for MIME items, as C strings.
+        let mut strings = infe.get(name..).unwrap_or_default().split(|&b| b == 0);
+        strings.next();
+        let content_type = if &item_type == b"mime" {
+            strings.next().unwrap_or_default()
+        } else {
+            &[]
+        };
+        infos.push(ItemInfo {
+            id,
+            item_type,
+            content_type,
+        });
+    }
+    Ok(infos)
+}
+
+fn read_fourcc(data: &[u8], pos: usize) -> Result<[u8; 4]> {
+    Ok(read_u32(data, pos)?.to_be_bytes())
+}
+
+/// Write `v` as a big-endian integer of `size` bytes (0, 4 or 8).
+fn put_uint(out: &mut Vec<u8>, v: u64, size: usize) {
+    match size {
+        4 => put_u32(out, v as u32),
+        8 => put_u64(out, v),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bmff::{boxes, child, item_data_end};
+
+    fn bx(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_box(&mut out, kind, |b| b.extend_from_slice(payload));
+        out
+    }
+
+    /// ftyp, meta with one image item, which has an essential `ispe`
+    /// property and whose data sits in the trailing mdat.
+    fn file() -> Vec<u8> {
+        let mut meta = Vec::new();
+        write_full_box(&mut meta, b"meta", 0, 0, |b| {
+            write_full_box(b, b"pitm", 0, 0, |b| put_u16(b, 1));
+            write_full_box(b, b"iinf", 0, 0, |b| {
+                put_u16(b, 1);
+                write_full_box(b, b"infe", 2, 0, |b| {
+                    put_u16(b, 1);
+                    put_u16(b, 0);
+                    b.extend_from_slice(b"av01\0");
+                });
+            });
+            write_box(b, b"iprp", |b| {
+                write_box(b, b"ipco", |b| {
+                    write_full_box(b, b"ispe", 0, 0, |b| b.extend_from_slice(&[0; 8]));
+                });
+                write_full_box(b, b"ipma", 0, 0, |b| {
+                    put_u32(b, 1);
+                    b.extend_from_slice(&[0, 1, 1, 0x81]);
+                });
+            });
+            write_full_box(b, b"iloc", 0, 0, |b| {
+                b.extend_from_slice(&[0x44, 0, 0, 1, 0, 1, 0, 0, 0, 1]);
+                put_u32(b, 0); // patched below
+                put_u32(b, 5);
+            });
+        });
+        let ftyp = bx(b"ftyp", b"avifmif1");
+        let offset = (ftyp.len() + meta.len() + 8) as u32;
+        let patch = meta.len() - 8;
+        meta[patch..patch + 4].copy_from_slice(&offset.to_be_bytes());
+        [ftyp, meta, bx(b"mdat", b"PIXEL")].concat()
+    }
+
+    /// [`file`] with a one-track `moov` after the mdat, whose only chunk
+    /// is the image data and whose edit list plays 100 ticks once.
+    fn animated_file() -> Vec<u8> {
+        let mut out = file();
+        let chunk = (out.len() - 5) as u32;
+        write_box(&mut out, b"moov", |b| {
+            write_full_box(b, b"mvhd", 0, 0, |b| {
+                b.extend_from_slice(&[0; 8]);
+                put_u32(b, 1000);
+                put_u32(b, 100);
+                b.extend_from_slice(&[0; 80]);
+            });
+            write_box(b, b"trak", |b| {
+                write_full_box(b, b"tkhd", 0, 3, |b| {
+                    b.extend_from_slice(&[0; 16]);
+                    put_u32(b, 100);
+                    b.extend_from_slice(&[0; 60]);
+                });
+                write_box(b, b"edts", |b| {
+                    write_full_box(b, b"elst", 0, 0, |b| {
+                        for v in [1, 100, 0, 0x1_0000] {
+                            put_u32(b, v);
+                        }
+                    });
+                });
+                write_box(b, b"mdia", |b| {
+                    write_box(b, b"minf", |b| {
+                        write_box(b, b"stbl", |b| {
+                            write_full_box(b, b"stco", 0, 0, |b| {
+                                put_u32(b, 1);
+                                put_u32(b, chunk);
+                            });
+                        });
+                    });
+                });
+            });
+        });
+        out
+    }
+
+    /// Payload of the box at `path` below the top level of `file`.
+    fn nested<'a>(file: &'a [u8], path: &[&[u8; 4]]) -> &'a [u8] {
+        path.iter()
+            .fold(file, |data, kind| child(data, kind).unwrap().unwrap())
+    }
+
+    /// Bytes of each extent of `item`.
+    fn item_data(file: &[u8], item: u32) -> Vec<u8> {
+        let meta = child(file, b"meta").unwrap().unwrap();
+        let iloc = child(&meta[4..], b"iloc").unwrap().unwrap();
+        let loc = item_locations(iloc)
+            .unwrap()
+            .items
+            .into_iter()
+            .find(|loc| loc.item_id == item)
+            .unwrap();
+        let (_, offset, length) = loc.extents[0];
+        let start = (loc.base_offset + offset) as usize;
+        file[start..start + length as usize].to_vec()
+    }
+
+    #[test]
+    fn appended_item_keeps_existing_data_reachable() {
+        let original = file();
+        let out = add_metadata_item(&original, MetadataItem::Xmp(b"<x:xmpmeta/>")).unwrap();
+        assert_eq!(item_data(&out, 1), b"PIXEL");
+        assert_eq!(item_data(&out, 2), b"<x:xmpmeta/>");
+
+        let meta = child(&out, b"meta").unwrap().unwrap();
+        let iref = child(&meta[4..], b"iref").unwrap().unwrap();
+        let (kind, cdsc) = boxes(&iref[4..]).next().unwrap().unwrap();
+        assert_eq!((&kind, cdsc), (b"cdsc", &[0, 2, 0, 1, 0, 1][..]));
+        assert_eq!(item_data_end(meta).unwrap(), Some(out.len() as u64));
+
+        let out = add_metadata_item(&out, MetadataItem::Exif(b"\0\0\0\0MM\0*")).unwrap();
+        assert_eq!(item_data(&out, 1), b"PIXEL");
+        assert_eq!(item_data(&out, 3), b"\0\0\0\0MM\0*");
+    }
+
+    #[test]
+    fn rejects_duplicate_kind() {
+        let out = add_metadata_item(&file(), MetadataItem::Exif(b"II*\0")).unwrap();
+        assert!(add_metadata_item(&out, MetadataItem::Exif(b"II*\0")).is_err());
+        assert!(add_metadata_item(&out, MetadataItem::Xmp(b"<x/>")).is_ok());
+    }
+
+    #[test]
+    fn animation_rewrite_keeps_samples_and_replaces_metadata() {
+        let original = animated_file();
+        let edit = MetadataEdit::new()
+            .xmp(b"<x:xmpmeta/>".to_vec())
+            .loop_count(3);
+        let out = rewrite_animation_metadata(&original, &edit).unwrap();
+        let stco = nested(
+            &out,
+            &[b"moov", b"trak", b"mdia", b"minf", b"stbl", b"stco"],
+        );
+        let chunk = read_u32(stco, 8).unwrap() as usize;
+        assert_eq!(&out[chunk..chunk + 5], b"PIXEL");
+        assert_eq!(item_data(&out, 1), b"PIXEL");
+        assert_eq!(item_data(&out, 2), b"<x:xmpmeta/>");
+
+        let elst = nested(&out, &[b"moov", b"trak", b"edts", b"elst"]);
+        assert_eq!(elst[3] & 1, 1);
+        let tkhd = nested(&out, &[b"moov", b"trak", b"tkhd"]);
+        assert_eq!(read_u32(tkhd, 20).unwrap(), 300);
+        let mvhd = nested(&out, &[b"moov", b"mvhd"]);
+        assert_eq!(read_u32(mvhd, 16).unwrap(), 300);
+
+        // Replacing keeps the item and its reference, and playing once
+        // clears the repeat flag.
+        let edit = MetadataEdit::new().xmp(b"<x/>".to_vec()).loop_count(1);
+        let out = rewrite_animation_metadata(&out, &edit).unwrap();
+        assert_eq!(item_data(&out, 2), b"<x/>");
+        let iinf = nested(&out, &[b"meta"]);
+        let iinf = child(&iinf[4..], b"iinf").unwrap().unwrap();
+        assert_eq!(read_u16(iinf, 4).unwrap(), 2);
+        assert_eq!(
+            nested(&out, &[b"moov", b"trak", b"edts", b"elst"])[3] & 1,
+            0
+        );
+        let tkhd = nested(&out, &[b"moov", b"trak", b"tkhd"]);
+        assert_eq!(read_u32(tkhd, 20).unwrap(), 100);
+
+        assert!(rewrite_animation_metadata(&file(), &MetadataEdit::new()).is_err());
+    }
+
+    #[test]
+    fn added_property_is_associated_with_primary_item() {
+        let original = file();
+        let t35 = [0xb5, 0, 0x3c, 0, 1, 4, 1, 0x40];
+        let out = add_primary_property(&original, b"it35", &t35).unwrap();
+        assert_eq!(item_data(&out, 1), b"PIXEL");
+        assert_eq!(crate::bmff::primary_item_it35(&out).unwrap().unwrap(), t35);
+
+        let meta = child(&out, b"meta").unwrap().unwrap();
+        let iprp = child(&meta[4..], b"iprp").unwrap().unwrap();
+        let ipma = child(iprp, b"ipma").unwrap().unwrap();
+        assert_eq!(&ipma[8..], &[0, 1, 2, 0x81, 0x02]);
+
+        assert!(add_primary_property(&out, b"it35", &t35).is_err());
+    }
+}
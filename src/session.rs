@@ -0,0 +1,155 @@
+//! Cancelling a group of decodes in one call.
+
+use crate::config::DecoderConfig;
+use crate::error::Result;
+use crate::image::DecodedImage;
+use crate::{decode_image_with, decode_with};
+use almost_enough::ChildStopper;
+use enough::{Stop, StopReason};
+use zenpixels::PixelBuffer;
+
+/// A decoder configuration plus a cancellation root shared by every decode
+/// started from it.
+///
+/// Each decode runs under its own [`CancelGroup`], a child of the session:
+/// cancelling the group stops that decode alone, and [`cancel`](Self::cancel)
+/// stops all of them, e.g. everything belonging to a closed tab. Groups can
+/// nest, so a page can own a group with one child per image, animation or
+/// grid tile. Pass a group wherever a decode function takes a stop token;
+/// decoders check it between frames, tiles and conversion strips.
+///
+/// Cancellation is permanent: groups created after [`cancel`](Self::cancel)
+/// start out cancelled. Clones share the same root.
+///
+/// # Example
+///
+/// ```no_run
+/// use zenavif::{DecodeSession, DecoderConfig};
+///
+/// let session = DecodeSession::new(DecoderConfig::new());
+/// let worker = {
+///     let session = session.clone();
+///     std::thread::spawn(move || {
+///         let data = std::fs::read("large.avif").unwrap();
+///         session.decode(&data)
+///     })
+/// };
+/// // The tab closed: stop the decode unless it has already finished.
+/// session.cancel();
+/// let _ = worker.join().unwrap();
+/// ```
+#[derive(Clone)]
+pub struct DecodeSession {
+    root: ChildStopper,
+    config: DecoderConfig,
+}
+
+impl DecodeSession {
+    /// Start a session decoding with `config`.
+    pub fn new(config: DecoderConfig) -> Self {
+        Self {
+            root: ChildStopper::new(),
+            config,
+        }
+    }
+
+    /// The configuration decodes in this session use.
+    pub fn config(&self) -> &DecoderConfig {
+        &self.config
+    }
+
+    /// A new group that stops when it or the session is cancelled.
+    pub fn group(&self) -> CancelGroup {
+        CancelGroup {
+            stop: self.root.child(),
+        }
+    }
+
+    /// Cancel every decode in the session, running or future.
+    pub fn cancel(&self) {
+        self.root.cancel();
+    }
+
+    /// `true` once [`cancel`](Self::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.root.is_cancelled()
+    }
+
+    /// [`decode_with`] under a new group of this session.
+    pub fn decode(&self, data: &[u8]) -> Result<PixelBuffer> {
+        decode_with(data, &self.config, &self.group())
+    }
+
+    /// [`decode_image_with`] under a new group of this session.
+    pub fn decode_image(&self, data: &[u8]) -> Result<DecodedImage> {
+        decode_image_with(data, &self.config, &self.group())
+    }
+}
+
+impl core::fmt::Debug for DecodeSession {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("DecodeSession")
+            .field("cancelled", &self.is_cancelled())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Stop`] token in a [`DecodeSession`]'s cancellation tree.
+///
+/// Fires when it, any group it was created from, or the session is
+/// cancelled. Cancelling a group never affects its parent or siblings.
+/// Clones share the same node.
+#[derive(Debug, Clone)]
+pub struct CancelGroup {
+    stop: ChildStopper,
+}
+
+impl CancelGroup {
+    /// A new group nested in this one.
+    pub fn group(&self) -> CancelGroup {
+        CancelGroup {
+            stop: self.stop.child(),
+        }
+    }
+
+    /// Cancel this group and every group nested in it.
+    pub fn cancel(&self) {
+        self.stop.cancel();
+    }
+
+    /// `true` once this group or one of its ancestors is cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.stop.is_cancelled()
+    }
+}
+
+impl Stop for CancelGroup {
+    fn check(&self) -> core::result::Result<(), StopReason> {
+        self.stop.check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancellation_flows_down_only() {
+        let session = DecodeSession::new(DecoderConfig::new());
+        let page = session.group();
+        let (image, frame) = (page.group(), page.group());
+        let other = session.group();
+
+        image.cancel();
+        assert!(image.check().is_err());
+        assert!(!frame.is_cancelled() && !page.is_cancelled());
+
+        page.cancel();
+        assert!(frame.is_cancelled());
+        assert!(!other.is_cancelled() && !session.is_cancelled());
+
+        session.cancel();
+        assert_eq!(other.check(), Err(StopReason::Cancelled));
+        assert!(session.group().is_cancelled());
+    }
+}
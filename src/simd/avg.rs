@@ -7,11 +7,17 @@
 //! them and packing the result back to 8-bit pixels.
 
 #[cfg(target_arch = "x86_64")]
-use archmage::{Desktop64, SimdToken, arcane};
+use archmage::{Desktop64, SimdToken, X64V2Token, arcane};
 
 #[cfg(target_arch = "x86_64")]
 use core::arch::x86_64::*;
 
+#[cfg(target_arch = "aarch64")]
+use archmage::{NeonToken, SimdToken, arcane};
+
+#[cfg(target_arch = "aarch64")]
+use core::arch::aarch64::*;
+
 #[cfg(target_arch = "wasm32")]
 use archmage::{SimdToken, Wasm128Token, arcane};
 
@@ -111,6 +117,64 @@ pub fn avg_8bpc_avx2(
     }
 }
 
+/// AVG operation using SSE4.1 — processes 16 pixels at a time
+///
+/// For x86-64 CPUs without AVX2. Same arithmetic as [`avg_8bpc_avx2`];
+/// 128-bit `packuswb` needs no lane fix-up.
+#[cfg(target_arch = "x86_64")]
+#[arcane]
+pub fn avg_8bpc_sse41(
+    _token: X64V2Token,
+    dst: &mut [u8],
+    dst_stride: usize,
+    tmp1: &[i16],
+    tmp2: &[i16],
+    w: usize,
+    h: usize,
+) {
+    debug_assert!(tmp1.len() >= w * h, "tmp1 too small");
+    debug_assert!(tmp2.len() >= w * h, "tmp2 too small");
+    debug_assert!(dst.len() >= (h - 1) * dst_stride + w, "dst too small");
+
+    let round = _mm_set1_epi16(PW_1024);
+
+    for row in 0..h {
+        let tmp1_row = &tmp1[row * w..][..w];
+        let tmp2_row = &tmp2[row * w..][..w];
+        let dst_row = &mut dst[row * dst_stride..][..w];
+
+        let mut col = 0;
+        while col + 16 <= w {
+            let t1_lo_arr: &[i16; 8] = tmp1_row[col..col + 8].try_into().unwrap();
+            let t1_hi_arr: &[i16; 8] = tmp1_row[col + 8..col + 16].try_into().unwrap();
+            let t2_lo_arr: &[i16; 8] = tmp2_row[col..col + 8].try_into().unwrap();
+            let t2_hi_arr: &[i16; 8] = tmp2_row[col + 8..col + 16].try_into().unwrap();
+
+            let t1_lo = safe_unaligned_simd::x86_64::_mm_loadu_si128(t1_lo_arr);
+            let t1_hi = safe_unaligned_simd::x86_64::_mm_loadu_si128(t1_hi_arr);
+            let t2_lo = safe_unaligned_simd::x86_64::_mm_loadu_si128(t2_lo_arr);
+            let t2_hi = safe_unaligned_simd::x86_64::_mm_loadu_si128(t2_hi_arr);
+
+            // (tmp1 + tmp2 + 1) >> 1 via pmulhrsw, as in the AVX2 version
+            let avg_lo = _mm_mulhrs_epi16(_mm_add_epi16(t1_lo, t2_lo), round);
+            let avg_hi = _mm_mulhrs_epi16(_mm_add_epi16(t1_hi, t2_hi), round);
+            let result = _mm_packus_epi16(avg_lo, avg_hi);
+
+            let dst_arr: &mut [u8; 16] = (&mut dst_row[col..col + 16]).try_into().unwrap();
+            safe_unaligned_simd::x86_64::_mm_storeu_si128(dst_arr, result);
+
+            col += 16;
+        }
+
+        while col < w {
+            let sum = tmp1_row[col].wrapping_add(tmp2_row[col]);
+            let avg = ((sum as i32 * 1024 + 16384) >> 15).clamp(0, 255) as u8;
+            dst_row[col] = avg;
+            col += 1;
+        }
+    }
+}
+
 /// AVG operation using wasm128 SIMD — processes 8 pixels at a time
 ///
 /// Synthesizes pmulhrsw from i32x4_extmul + add + shift + narrow since
@@ -189,6 +253,73 @@ pub fn avg_8bpc_wasm128(
     }
 }
 
+/// AVG operation using NEON — processes 16 pixels at a time
+///
+/// `vqrdmulhq_s16(a, b)` computes `(2 * a * b + (1 << 15)) >> 16`, which is
+/// exactly pmulhrsw's `(a * b + 16384) >> 15`; its saturation only kicks in
+/// for `i16::MIN * i16::MIN`, which a multiplier of 1024 never reaches.
+#[cfg(target_arch = "aarch64")]
+#[arcane]
+pub fn avg_8bpc_neon(
+    _token: NeonToken,
+    dst: &mut [u8],
+    dst_stride: usize,
+    tmp1: &[i16],
+    tmp2: &[i16],
+    w: usize,
+    h: usize,
+) {
+    debug_assert!(tmp1.len() >= w * h, "tmp1 too small");
+    debug_assert!(tmp2.len() >= w * h, "tmp2 too small");
+    debug_assert!(dst.len() >= (h - 1) * dst_stride + w, "dst too small");
+
+    let round = vdupq_n_s16(PW_1024);
+
+    for row in 0..h {
+        let tmp1_row = &tmp1[row * w..][..w];
+        let tmp2_row = &tmp2[row * w..][..w];
+        let dst_row = &mut dst[row * dst_stride..][..w];
+
+        let mut col = 0;
+        // Process 16 pixels at a time (2 x 8 i16 in, 16 u8 out)
+        while col + 16 <= w {
+            let t1_lo_arr: &[i16; 8] = tmp1_row[col..col + 8].try_into().unwrap();
+            let t1_hi_arr: &[i16; 8] = tmp1_row[col + 8..col + 16].try_into().unwrap();
+            let t2_lo_arr: &[i16; 8] = tmp2_row[col..col + 8].try_into().unwrap();
+            let t2_hi_arr: &[i16; 8] = tmp2_row[col + 8..col + 16].try_into().unwrap();
+
+            let t1_lo = safe_unaligned_simd::aarch64::vld1q_s16(t1_lo_arr);
+            let t1_hi = safe_unaligned_simd::aarch64::vld1q_s16(t1_hi_arr);
+            let t2_lo = safe_unaligned_simd::aarch64::vld1q_s16(t2_lo_arr);
+            let t2_hi = safe_unaligned_simd::aarch64::vld1q_s16(t2_hi_arr);
+
+            // Wrapping add, same as paddw
+            let sum_lo = vaddq_s16(t1_lo, t2_lo);
+            let sum_hi = vaddq_s16(t1_hi, t2_hi);
+
+            // (sum * 1024 + 16384) >> 15
+            let avg_lo = vqrdmulhq_s16(sum_lo, round);
+            let avg_hi = vqrdmulhq_s16(sum_hi, round);
+
+            // Narrow to u8 with unsigned saturation, like packuswb
+            let packed = vcombine_u8(vqmovun_s16(avg_lo), vqmovun_s16(avg_hi));
+
+            let dst_arr: &mut [u8; 16] = (&mut dst_row[col..col + 16]).try_into().unwrap();
+            safe_unaligned_simd::aarch64::vst1q_u8(dst_arr, packed);
+
+            col += 16;
+        }
+
+        // Scalar fallback for remaining pixels
+        while col < w {
+            let sum = tmp1_row[col].wrapping_add(tmp2_row[col]);
+            let avg = ((sum as i32 * 1024 + 16384) >> 15).clamp(0, 255) as u8;
+            dst_row[col] = avg;
+            col += 1;
+        }
+    }
+}
+
 /// Scalar fallback for AVG operation (for testing and non-AVX2 systems)
 pub fn avg_8bpc_scalar(
     dst: &mut [u8],
@@ -216,7 +347,8 @@ pub fn avg_8bpc_scalar(
 
 /// Runtime-dispatched AVG function
 ///
-/// Automatically selects AVX2, wasm128, or scalar implementation based on CPU features.
+/// Automatically selects AVX2, SSE4.1, NEON, RVV (with the `riscv-v` feature),
+/// wasm128, or scalar implementation based on CPU features.
 pub fn avg_8bpc(dst: &mut [u8], dst_stride: usize, tmp1: &[i16], tmp2: &[i16], w: usize, h: usize) {
     #[cfg(target_arch = "x86_64")]
     if let Some(token) = Desktop64::summon() {
@@ -224,6 +356,24 @@ pub fn avg_8bpc(dst: &mut [u8], dst_stride: usize, tmp1: &[i16], tmp2: &[i16], w
         return;
     }
 
+    #[cfg(target_arch = "x86_64")]
+    if let Some(token) = X64V2Token::summon() {
+        avg_8bpc_sse41(token, dst, dst_stride, tmp1, tmp2, w, h);
+        return;
+    }
+
+    #[cfg(all(target_arch = "riscv64", feature = "riscv-v"))]
+    if super::rvv_available() {
+        super::avg_8bpc_rvv(dst, dst_stride, tmp1, tmp2, w, h);
+        return;
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    if let Some(token) = NeonToken::summon() {
+        avg_8bpc_neon(token, dst, dst_stride, tmp1, tmp2, w, h);
+        return;
+    }
+
     #[cfg(target_arch = "wasm32")]
     if let Some(token) = Wasm128Token::summon() {
         avg_8bpc_wasm128(token, dst, dst_stride, tmp1, tmp2, w, h);
@@ -331,6 +481,26 @@ mod tests {
         );
     }
 
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_avg_sse41_matches_scalar() {
+        let Some(token) = X64V2Token::summon() else {
+            return;
+        };
+        // Not a multiple of 16, to cover the scalar tail.
+        let (w, h) = (40, 3);
+        let tmp1: Vec<i16> = (0..w * h).map(|i| ((i * 37) % 8192) as i16 - 512).collect();
+        let tmp2: Vec<i16> = (0..w * h)
+            .map(|i| ((i * 73 + 1000) % 8192) as i16)
+            .collect();
+
+        let mut dst_sse41 = vec![0u8; w * h];
+        let mut dst_scalar = vec![0u8; w * h];
+        avg_8bpc_scalar(&mut dst_scalar, w, &tmp1, &tmp2, w, h);
+        avg_8bpc_sse41(token, &mut dst_sse41, w, &tmp1, &tmp2, w, h);
+        assert_eq!(dst_sse41, dst_scalar);
+    }
+
     /// Test that the rounding is correct
     /// pmulhrsw(a, b) = (a * b + 16384) >> 15 (signed)
     #[test]
@@ -367,4 +537,53 @@ mod tests {
         avg_8bpc(&mut dst, w, &tmp1, &tmp2, w, h);
         assert_eq!(dst[0], 255, "sum=16384 should saturate to 255");
     }
+
+    /// Brute force: every i16 in `tmp1` against a spread of `tmp2` values,
+    /// at a width that leaves a scalar tail, must match the scalar reference.
+    #[cfg(target_arch = "aarch64")]
+    #[test]
+    fn test_avg_neon_matches_scalar_exhaustive() {
+        let Some(token) = NeonToken::summon() else {
+            return;
+        };
+        // All 65536 values, padded out to 262 rows of 251.
+        let (w, h) = (251, 262);
+        let mut tmp1: Vec<i16> = (i16::MIN..=i16::MAX).collect();
+        tmp1.resize(w * h, 0);
+
+        for v2 in [0i16, 1, -1, 255, 8192, -8192, i16::MIN, i16::MAX] {
+            let tmp2 = vec![v2; w * h];
+            let mut dst_neon = vec![0u8; w * h];
+            let mut dst_scalar = vec![0u8; w * h];
+
+            avg_8bpc_scalar(&mut dst_scalar, w, &tmp1, &tmp2, w, h);
+            avg_8bpc_neon(token, &mut dst_neon, w, &tmp1, &tmp2, w, h);
+
+            if let Some(i) = (0..w * h).find(|&i| dst_neon[i] != dst_scalar[i]) {
+                panic!(
+                    "Mismatch for v1={}, v2={v2}: neon={} scalar={}",
+                    tmp1[i], dst_neon[i], dst_scalar[i]
+                );
+            }
+        }
+    }
+
+    /// NEON must honor `dst_stride` and leave the padding between rows alone.
+    #[cfg(target_arch = "aarch64")]
+    #[test]
+    fn test_avg_neon_strided() {
+        let Some(token) = NeonToken::summon() else {
+            return;
+        };
+        let (w, h, stride) = (40, 3, 48);
+        let tmp1: Vec<i16> = (0..w * h).map(|i| (i * 97 % 9000) as i16 - 500).collect();
+        let tmp2: Vec<i16> = (0..w * h).map(|i| (i * 31 % 9000) as i16).collect();
+        let mut dst_neon = vec![0xAAu8; stride * h];
+        let mut dst_scalar = vec![0xAAu8; stride * h];
+
+        avg_8bpc_scalar(&mut dst_scalar, stride, &tmp1, &tmp2, w, h);
+        avg_8bpc_neon(token, &mut dst_neon, stride, &tmp1, &tmp2, w, h);
+
+        assert_eq!(dst_neon, dst_scalar);
+    }
 }
@@ -6,5 +6,16 @@
 #![allow(unused_imports)]
 
 mod avg;
+mod report;
+#[cfg(all(target_arch = "riscv64", feature = "riscv-v"))]
+#[allow(unsafe_code)]
+mod rvv;
+mod scale;
+mod swizzle;
 
 pub use avg::*;
+pub use report::*;
+#[cfg(all(target_arch = "riscv64", feature = "riscv-v"))]
+pub use rvv::*;
+pub use scale::*;
+pub use swizzle::*;
@@ -0,0 +1,174 @@
+//! Which SIMD tier the YUV conversion and pixel kernels run on
+//!
+//! Kernels summon archmage tokens per call and quietly fall back to scalar
+//! code when a token is unavailable, which is correct but can be several
+//! times slower. [`simd_report`] says which tier this process gets and why
+//! it isn't a faster one, and `warn_if_fallback` logs that once.
+
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "wasm32"
+))]
+use archmage::prelude::*;
+
+/// Instruction set tier, slowest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum SimdTier {
+    /// Portable scalar code.
+    Scalar,
+    /// 128-bit SSE4.1 kernels (x86-64-v2 CPUs without AVX2).
+    Sse41,
+    /// 128-bit wasm SIMD.
+    Wasm128,
+    /// 128-bit NEON.
+    Neon,
+    /// RISC-V vector extension at the hardware vector length, with the
+    /// `riscv-v` feature.
+    Rvv,
+    /// 256-bit AVX2 + FMA (x86-64-v3).
+    Avx2,
+    /// 512-bit AVX-512BW (x86-64-v4), with the `avx512` feature.
+    Avx512,
+}
+
+/// The SIMD tier available to this process. See [`simd_report`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SimdReport {
+    /// Best tier the kernels dispatch to on this CPU.
+    pub tier: SimdTier,
+    /// Best tier this build has kernels for on this architecture.
+    pub best_tier: SimdTier,
+    /// Why `tier` is below `best_tier`; `None` when it isn't.
+    pub fallback_reason: Option<&'static str>,
+}
+
+impl SimdReport {
+    /// `true` when the kernels run below the best tier of this build.
+    pub fn is_fallback(&self) -> bool {
+        self.tier < self.best_tier
+    }
+}
+
+/// Report the SIMD tier the YUV conversion and pixel kernels use here.
+///
+/// Tokens are summoned the same way the kernels do, so tokens disabled
+/// process-wide for testing show up as a fallback too. Individual
+/// converters may not have a kernel at every tier; those use the next tier
+/// down.
+#[cfg(target_arch = "x86_64")]
+pub fn simd_report() -> SimdReport {
+    #[cfg(feature = "avx512")]
+    {
+        if X64V4Token::summon().is_some() {
+            return report(SimdTier::Avx512, SimdTier::Avx512, None);
+        }
+        if Desktop64::summon().is_some() {
+            let reason = "CPU lacks AVX-512BW (x86-64-v4) or it is disabled";
+            return report(SimdTier::Avx2, SimdTier::Avx512, Some(reason));
+        }
+    }
+    let best_tier = if cfg!(feature = "avx512") {
+        SimdTier::Avx512
+    } else {
+        SimdTier::Avx2
+    };
+    if Desktop64::summon().is_some() {
+        return report(SimdTier::Avx2, best_tier, None);
+    }
+    if X64V2Token::summon().is_some() {
+        let reason = "CPU lacks AVX2/FMA (x86-64-v3) or it is disabled; using SSE4.1 kernels";
+        return report(SimdTier::Sse41, best_tier, Some(reason));
+    }
+    let reason = "CPU lacks SSE4.1 (x86-64-v2) or it is disabled; using scalar code";
+    report(SimdTier::Scalar, best_tier, Some(reason))
+}
+
+/// Report the SIMD tier the YUV conversion and pixel kernels use here.
+#[cfg(target_arch = "aarch64")]
+pub fn simd_report() -> SimdReport {
+    if NeonToken::summon().is_some() {
+        return report(SimdTier::Neon, SimdTier::Neon, None);
+    }
+    let reason = "NEON is disabled; using scalar code";
+    report(SimdTier::Scalar, SimdTier::Neon, Some(reason))
+}
+
+/// Report the SIMD tier the YUV conversion and pixel kernels use here.
+#[cfg(target_arch = "wasm32")]
+pub fn simd_report() -> SimdReport {
+    if Wasm128Token::summon().is_some() {
+        return report(SimdTier::Wasm128, SimdTier::Wasm128, None);
+    }
+    let reason = "built without simd128 (-C target-feature=+simd128); using scalar code";
+    report(SimdTier::Scalar, SimdTier::Wasm128, Some(reason))
+}
+
+/// Report the SIMD tier the YUV conversion and pixel kernels use here.
+#[cfg(all(target_arch = "riscv64", feature = "riscv-v"))]
+pub fn simd_report() -> SimdReport {
+    if super::rvv_available() {
+        return report(SimdTier::Rvv, SimdTier::Rvv, None);
+    }
+    let reason = "CPU lacks the V extension or the kernel doesn't enable it; using scalar code";
+    report(SimdTier::Scalar, SimdTier::Rvv, Some(reason))
+}
+
+/// Report the SIMD tier the YUV conversion and pixel kernels use here.
+///
+/// This architecture has scalar kernels only.
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "wasm32",
+    all(target_arch = "riscv64", feature = "riscv-v")
+)))]
+pub fn simd_report() -> SimdReport {
+    report(SimdTier::Scalar, SimdTier::Scalar, None)
+}
+
+fn report(
+    tier: SimdTier,
+    best_tier: SimdTier,
+    fallback_reason: Option<&'static str>,
+) -> SimdReport {
+    SimdReport {
+        tier,
+        best_tier,
+        fallback_reason,
+    }
+}
+
+/// Log a warning the first time a decoder is created on a CPU that
+/// falls back below AVX2, NEON, RVV or wasm SIMD128.
+///
+/// Missing AVX-512 alone isn't worth a warning; AVX2 is the tier the
+/// kernels are tuned for.
+pub(crate) fn warn_if_fallback() {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| {
+        let report = simd_report();
+        if report.tier < SimdTier::Avx2.min(report.best_tier)
+            && let Some(reason) = report.fallback_reason
+        {
+            log::warn!(
+                "zenavif: YUV conversion runs on the {:?} tier ({reason}); decoding will be slower",
+                report.tier
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reason_given_exactly_for_fallbacks() {
+        let report = simd_report();
+        assert!(report.tier <= report.best_tier);
+        assert_eq!(report.is_fallback(), report.fallback_reason.is_some());
+    }
+}
@@ -0,0 +1,212 @@
+//! RISC-V vector (RVV 1.0) kernels, behind the `riscv-v` feature
+//!
+//! Stable Rust has no RVV intrinsics, so these kernels are inline assembly.
+//! The `.option arch, +v` directive lets the assembler accept vector
+//! instructions without compiling the rest of the crate for `+v`; callers
+//! must check [`rvv_available`] first. Vector length is whatever the
+//! hardware offers: each loop asks `vsetvli` for as many elements as remain.
+//!
+//! Fixed-point rounding matches the x86 kernels: `vsmul` and `vssra` with
+//! `vxrm` set to round-to-nearest-up compute exactly what `pmulhrsw` does
+//! for the inputs reachable here.
+
+use core::arch::asm;
+use rgb::RGB8;
+
+/// `true` when the CPU and kernel support the V extension.
+pub fn rvv_available() -> bool {
+    std::arch::is_riscv_feature_detected!("v")
+}
+
+/// RVV version of [`avg_8bpc`](super::avg_8bpc).
+///
+/// Same arithmetic as [`avg_8bpc_scalar`](super::avg_8bpc_scalar): wrapping
+/// i16 sum, `(sum + 16) >> 5` with rounding, clamped to `[0, 255]`.
+///
+/// # Panics
+///
+/// If `rvv_available()` is false, or the buffers are smaller than `w`×`h`.
+pub fn avg_8bpc_rvv(
+    dst: &mut [u8],
+    dst_stride: usize,
+    tmp1: &[i16],
+    tmp2: &[i16],
+    w: usize,
+    h: usize,
+) {
+    assert!(rvv_available(), "RVV is not available on this CPU");
+    for row in 0..h {
+        let tmp1_row = &tmp1[row * w..][..w];
+        let tmp2_row = &tmp2[row * w..][..w];
+        let dst_row = &mut dst[row * dst_stride..][..w];
+        // SAFETY: V is available (checked above). Each iteration reads and
+        // writes `vl` elements, `vl <= remaining`, so every access stays
+        // within the three row slices, which hold `w` elements.
+        unsafe {
+            asm!(
+                ".option push",
+                ".option arch, +v",
+                "csrwi vxrm, 0",
+                "2:",
+                "vsetvli {vl}, {n}, e16, m2, ta, ma",
+                "vle16.v v8, ({a})",
+                "vle16.v v12, ({b})",
+                "vadd.vv v8, v8, v12",
+                "vssra.vi v8, v8, 5",
+                "vmax.vx v8, v8, zero",
+                "vsetvli zero, zero, e8, m1, ta, ma",
+                "vnclipu.wi v16, v8, 0",
+                "vse8.v v16, ({d})",
+                "slli {t}, {vl}, 1",
+                "add {a}, {a}, {t}",
+                "add {b}, {b}, {t}",
+                "add {d}, {d}, {vl}",
+                "sub {n}, {n}, {vl}",
+                "bnez {n}, 2b",
+                ".option pop",
+                a = inout(reg) tmp1_row.as_ptr() => _,
+                b = inout(reg) tmp2_row.as_ptr() => _,
+                d = inout(reg) dst_row.as_mut_ptr() => _,
+                n = inout(reg) w => _,
+                vl = out(reg) _,
+                t = out(reg) _,
+                out("v8") _, out("v9") _, out("v12") _, out("v13") _, out("v16") _,
+                options(nostack),
+            );
+        }
+    }
+}
+
+/// Fixed-point constants for [`yuv_to_rgb8_row_rvv`], in the order luma
+/// bias, 10-bit chroma bias, Y, Cr, Cb, G from V and G from U.
+pub type FixedCoefficients = [i16; 7];
+
+/// Convert one row of full-resolution Y, U and V samples to RGB8 with the
+/// fast path's fixed-point contract: samples widened to 10 bits by byte
+/// replication, luma and chroma biases subtracted, `pmulhrsw` products
+/// summed with wrapping i16 arithmetic and saturated to `[0, 255]`.
+///
+/// # Panics
+///
+/// If `rvv_available()` is false, or `u`, `v` or `out` is shorter than `y`.
+pub fn yuv_to_rgb8_row_rvv(
+    y: &[u8],
+    u: &[u8],
+    v: &[u8],
+    out: &mut [RGB8],
+    coefs: FixedCoefficients,
+) {
+    assert!(rvv_available(), "RVV is not available on this CPU");
+    let n = y.len();
+    let (u, v, out) = (&u[..n], &v[..n], &mut out[..n]);
+    let [
+        y_bias,
+        uv_bias,
+        y_coef,
+        cr_coef,
+        cb_coef,
+        g_coef_1,
+        g_coef_2,
+    ] = coefs.map(|c| c as isize);
+    // SAFETY: V is available (checked above). Each iteration reads `vl`
+    // samples from each plane and writes `vl` RGB8 pixels (`3 * vl` bytes),
+    // `vl <= remaining`, so every access stays within the slices, which all
+    // hold `n` elements. RGB8 is three packed bytes.
+    unsafe {
+        asm!(
+            ".option push",
+            ".option arch, +v",
+            "csrwi vxrm, 0",
+            "2:",
+            "vsetvli {vl}, {n}, e8, m1, ta, ma",
+            "vle8.v v0, ({y})",
+            "vle8.v v1, ({u})",
+            "vle8.v v2, ({v})",
+            "vssubu.vx v0, v0, {y_bias}",
+            "vsetvli zero, zero, e16, m2, ta, ma",
+            // Widen to 10 bits: ((s << 8) | s) >> 6.
+            "vzext.vf2 v4, v0",
+            "vzext.vf2 v6, v1",
+            "vzext.vf2 v8, v2",
+            "vsll.vi v10, v4, 8",
+            "vor.vv v4, v4, v10",
+            "vsrl.vi v4, v4, 6",
+            "vsll.vi v10, v6, 8",
+            "vor.vv v6, v6, v10",
+            "vsrl.vi v6, v6, 6",
+            "vsll.vi v10, v8, 8",
+            "vor.vv v8, v8, v10",
+            "vsrl.vi v8, v8, 6",
+            "vsub.vx v6, v6, {uv_bias}",
+            "vsub.vx v8, v8, {uv_bias}",
+            // y_scaled, then r = y + v*cr, b = y + u*cb, g = y - (v*g1 + u*g2).
+            "vsmul.vx v10, v4, {y_coef}",
+            "vsmul.vx v12, v8, {cr_coef}",
+            "vadd.vv v12, v10, v12",
+            "vsmul.vx v14, v6, {cb_coef}",
+            "vadd.vv v14, v10, v14",
+            "vsmul.vx v16, v8, {g_coef_1}",
+            "vsmul.vx v18, v6, {g_coef_2}",
+            "vadd.vv v16, v16, v18",
+            "vsub.vv v16, v10, v16",
+            "vmax.vx v12, v12, zero",
+            "vmax.vx v14, v14, zero",
+            "vmax.vx v16, v16, zero",
+            "vsetvli zero, zero, e8, m1, ta, ma",
+            "vnclipu.wi v20, v12, 0",
+            "vnclipu.wi v21, v16, 0",
+            "vnclipu.wi v22, v14, 0",
+            "vsseg3e8.v v20, ({out})",
+            "add {y}, {y}, {vl}",
+            "add {u}, {u}, {vl}",
+            "add {v}, {v}, {vl}",
+            "slli {t}, {vl}, 1",
+            "add {t}, {t}, {vl}",
+            "add {out}, {out}, {t}",
+            "sub {n}, {n}, {vl}",
+            "bnez {n}, 2b",
+            ".option pop",
+            y = inout(reg) y.as_ptr() => _,
+            u = inout(reg) u.as_ptr() => _,
+            v = inout(reg) v.as_ptr() => _,
+            out = inout(reg) out.as_mut_ptr() => _,
+            n = inout(reg) n => _,
+            vl = out(reg) _,
+            t = out(reg) _,
+            y_bias = in(reg) y_bias,
+            uv_bias = in(reg) uv_bias,
+            y_coef = in(reg) y_coef,
+            cr_coef = in(reg) cr_coef,
+            cb_coef = in(reg) cb_coef,
+            g_coef_1 = in(reg) g_coef_1,
+            g_coef_2 = in(reg) g_coef_2,
+            out("v0") _, out("v1") _, out("v2") _,
+            out("v4") _, out("v5") _, out("v6") _, out("v7") _,
+            out("v8") _, out("v9") _, out("v10") _, out("v11") _,
+            out("v12") _, out("v13") _, out("v14") _, out("v15") _,
+            out("v16") _, out("v17") _, out("v18") _, out("v19") _,
+            out("v20") _, out("v21") _, out("v22") _,
+            options(nostack),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn avg_matches_scalar() {
+        if !rvv_available() {
+            return;
+        }
+        let (w, h) = (77, 3);
+        let tmp1: Vec<i16> = (0..w * h).map(|i| (i as i16).wrapping_mul(331)).collect();
+        let tmp2: Vec<i16> = (0..w * h).map(|i| (i as i16).wrapping_mul(-97)).collect();
+        let mut expected = vec![0; w * h];
+        crate::simd::avg_8bpc_scalar(&mut expected, w, &tmp1, &tmp2, w, h);
+        let mut actual = vec![0; w * h];
+        avg_8bpc_rvv(&mut actual, w, &tmp1, &tmp2, w, h);
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,182 @@
+//! Bit depth scaling of decoded 10/12-bit samples to full u16 range
+//!
+//! Every sample of a high bit depth decode goes through this, so the
+//! per-lane kernels matter. All tiers use LSB replication,
+//! `(v << shift) | (v >> (bit_depth - shift))` with `shift = 16 - bit_depth`,
+//! which maps 0 to 0 and the native maximum to 65535 exactly and gives
+//! bit-identical output across architectures.
+
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "wasm32"
+))]
+use archmage::prelude::*;
+
+/// Scale native bit depth samples to full u16 range in place.
+///
+/// Dispatches to AVX2, SSE4.1, NEON or wasm SIMD128 when available. `bit_depth`
+/// must be at least 8; depths of 16 and above are left untouched.
+pub fn scale_samples_to_u16(samples: &mut [u16], bit_depth: u8) {
+    debug_assert!(bit_depth >= 8, "bit depth {bit_depth} below 8");
+    if bit_depth >= 16 {
+        return;
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    if let Some(token) = Desktop64::summon() {
+        scale_samples_to_u16_avx2(token, samples, bit_depth);
+        return;
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    if let Some(token) = X64V2Token::summon() {
+        scale_samples_to_u16_sse41(token, samples, bit_depth);
+        return;
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    if let Some(token) = NeonToken::summon() {
+        scale_samples_to_u16_neon(token, samples, bit_depth);
+        return;
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    if let Some(token) = Wasm128Token::summon() {
+        scale_samples_to_u16_wasm128(token, samples, bit_depth);
+        return;
+    }
+
+    scale_samples_to_u16_scalar(samples, bit_depth);
+}
+
+/// Scalar reference for [`scale_samples_to_u16`].
+pub fn scale_samples_to_u16_scalar(samples: &mut [u16], bit_depth: u8) {
+    let shift = 16 - u32::from(bit_depth);
+    let back = u32::from(bit_depth) - shift;
+    for v in samples {
+        *v = (*v << shift) | (*v >> back);
+    }
+}
+
+/// AVX2 version of [`scale_samples_to_u16`], 16 samples per iteration.
+#[cfg(target_arch = "x86_64")]
+#[arcane]
+pub fn scale_samples_to_u16_avx2(_token: Desktop64, samples: &mut [u16], bit_depth: u8) {
+    let shift = 16 - i64::from(bit_depth);
+    let back = i64::from(bit_depth) - shift;
+    let left = _mm_cvtsi64_si128(shift);
+    let right = _mm_cvtsi64_si128(back);
+
+    let mut chunks = samples.chunks_exact_mut(16);
+    for chunk in &mut chunks {
+        let chunk: &mut [u16; 16] = chunk.try_into().unwrap();
+        let v = _mm256_loadu_si256(&*chunk);
+        let scaled = _mm256_or_si256(_mm256_sll_epi16(v, left), _mm256_srl_epi16(v, right));
+        _mm256_storeu_si256(chunk, scaled);
+    }
+    scale_samples_to_u16_scalar(chunks.into_remainder(), bit_depth);
+}
+
+/// SSE4.1 version of [`scale_samples_to_u16`], 8 samples per iteration.
+#[cfg(target_arch = "x86_64")]
+#[arcane]
+pub fn scale_samples_to_u16_sse41(_token: X64V2Token, samples: &mut [u16], bit_depth: u8) {
+    let shift = 16 - i64::from(bit_depth);
+    let back = i64::from(bit_depth) - shift;
+    let left = _mm_cvtsi64_si128(shift);
+    let right = _mm_cvtsi64_si128(back);
+
+    let mut chunks = samples.chunks_exact_mut(8);
+    for chunk in &mut chunks {
+        let chunk: &mut [u16; 8] = chunk.try_into().unwrap();
+        let v = _mm_loadu_si128(&*chunk);
+        let scaled = _mm_or_si128(_mm_sll_epi16(v, left), _mm_srl_epi16(v, right));
+        _mm_storeu_si128(chunk, scaled);
+    }
+    scale_samples_to_u16_scalar(chunks.into_remainder(), bit_depth);
+}
+
+/// NEON version of [`scale_samples_to_u16`], 8 samples per iteration.
+#[cfg(target_arch = "aarch64")]
+#[arcane]
+pub fn scale_samples_to_u16_neon(_token: NeonToken, samples: &mut [u16], bit_depth: u8) {
+    let shift = 16 - i16::from(bit_depth);
+    let back = i16::from(bit_depth) - shift;
+    // vshlq shifts right for negative counts.
+    let left = vdupq_n_s16(shift);
+    let right = vdupq_n_s16(-back);
+
+    let mut chunks = samples.chunks_exact_mut(8);
+    for chunk in &mut chunks {
+        let chunk: &mut [u16; 8] = chunk.try_into().unwrap();
+        let v = vld1q_u16(&*chunk);
+        let scaled = vorrq_u16(vshlq_u16(v, left), vshlq_u16(v, right));
+        vst1q_u16(chunk, scaled);
+    }
+    scale_samples_to_u16_scalar(chunks.into_remainder(), bit_depth);
+}
+
+/// wasm SIMD128 version of [`scale_samples_to_u16`], 8 samples per iteration.
+#[cfg(target_arch = "wasm32")]
+#[arcane]
+pub fn scale_samples_to_u16_wasm128(_token: Wasm128Token, samples: &mut [u16], bit_depth: u8) {
+    let shift = 16 - u32::from(bit_depth);
+    let back = u32::from(bit_depth) - shift;
+
+    let mut chunks = samples.chunks_exact_mut(8);
+    for chunk in &mut chunks {
+        let chunk: &mut [u16; 8] = chunk.try_into().unwrap();
+        let v = safe_unaligned_simd::wasm32::v128_load(&*chunk);
+        let scaled = v128_or(u16x8_shl(v, shift), u16x8_shr(v, back));
+        safe_unaligned_simd::wasm32::v128_store(chunk, scaled);
+    }
+    scale_samples_to_u16_scalar(chunks.into_remainder(), bit_depth);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simd_matches_scalar() {
+        for depth in [9, 10, 12] {
+            let max = (1u32 << depth) as usize;
+            for len in [0, 1, 7, 16, 37, 1000] {
+                let input: Vec<u16> = (0..len).map(|i| ((i * 131) % max) as u16).collect();
+                let mut expected = input.clone();
+                scale_samples_to_u16_scalar(&mut expected, depth);
+                let mut actual = input;
+                scale_samples_to_u16(&mut actual, depth);
+                assert_eq!(actual, expected, "depth {depth}, len {len}");
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn sse41_matches_scalar() {
+        let Some(token) = X64V2Token::summon() else {
+            return;
+        };
+        for depth in [10, 12] {
+            let input: Vec<u16> = (0..37).map(|i| (i * 97) % (1 << depth)).collect();
+            let mut expected = input.clone();
+            scale_samples_to_u16_scalar(&mut expected, depth);
+            let mut actual = input;
+            scale_samples_to_u16_sse41(token, &mut actual, depth);
+            assert_eq!(actual, expected, "depth {depth}");
+        }
+    }
+
+    #[test]
+    fn endpoints_map_exactly() {
+        for depth in [10, 12] {
+            let mut samples = vec![0, (1 << depth) - 1];
+            samples.resize(40, (1 << depth) - 1);
+            scale_samples_to_u16(&mut samples, depth);
+            assert_eq!(samples[0], 0);
+            assert!(samples[1..].iter().all(|&v| v == 65535), "depth {depth}");
+        }
+    }
+}
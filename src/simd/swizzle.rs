@@ -0,0 +1,171 @@
+//! Channel reordering of 4-byte pixels (RGBA to BGRA, ARGB, ABGR)
+//!
+//! Each output byte `i` of a pixel takes source byte `order[i]`, so one
+//! byte shuffle per register handles 4 (NEON, wasm) or 8 (AVX2) pixels.
+
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "wasm32"
+))]
+use archmage::prelude::*;
+
+/// Shuffle control for 16 bytes: pixel `p`, byte `i` reads `4p + order[i]`.
+fn shuffle_mask(order: [u8; 4]) -> [u8; 16] {
+    core::array::from_fn(|i| (i & !3) as u8 + order[i & 3])
+}
+
+/// Reorder the bytes of each 4-byte pixel of `src` into `dst`.
+///
+/// `dst[4p + i] = src[4p + order[i]]`; both slices must be the same
+/// whole number of pixels long. Dispatches to AVX2, SSE4.1, NEON or wasm
+/// SIMD128 when available.
+pub fn swizzle4(src: &[u8], dst: &mut [u8], order: [u8; 4]) {
+    assert_eq!(src.len(), dst.len());
+    debug_assert!(src.len().is_multiple_of(4) && order.iter().all(|&i| i < 4));
+
+    #[cfg(target_arch = "x86_64")]
+    if let Some(token) = Desktop64::summon() {
+        swizzle4_avx2(token, src, dst, order);
+        return;
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    if let Some(token) = X64V2Token::summon() {
+        swizzle4_sse41(token, src, dst, order);
+        return;
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    if let Some(token) = NeonToken::summon() {
+        swizzle4_neon(token, src, dst, order);
+        return;
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    if let Some(token) = Wasm128Token::summon() {
+        swizzle4_wasm128(token, src, dst, order);
+        return;
+    }
+
+    swizzle4_scalar(src, dst, order);
+}
+
+/// Scalar reference for [`swizzle4`].
+pub fn swizzle4_scalar(src: &[u8], dst: &mut [u8], order: [u8; 4]) {
+    for (s, d) in src.chunks_exact(4).zip(dst.chunks_exact_mut(4)) {
+        for (d, &i) in d.iter_mut().zip(&order) {
+            *d = s[i as usize];
+        }
+    }
+}
+
+/// AVX2 version of [`swizzle4`], 8 pixels per iteration.
+#[cfg(target_arch = "x86_64")]
+#[arcane]
+pub fn swizzle4_avx2(_token: Desktop64, src: &[u8], dst: &mut [u8], order: [u8; 4]) {
+    // vpshufb shuffles within each 128-bit lane, so both lanes share a mask.
+    let half = shuffle_mask(order);
+    let mut mask = [0u8; 32];
+    mask[..16].copy_from_slice(&half);
+    mask[16..].copy_from_slice(&half);
+    let mask = _mm256_loadu_si256(&mask);
+
+    let mut src_chunks = src.chunks_exact(32);
+    let mut dst_chunks = dst.chunks_exact_mut(32);
+    for (s, d) in (&mut src_chunks).zip(&mut dst_chunks) {
+        let s: &[u8; 32] = s.try_into().unwrap();
+        let d: &mut [u8; 32] = d.try_into().unwrap();
+        _mm256_storeu_si256(d, _mm256_shuffle_epi8(_mm256_loadu_si256(s), mask));
+    }
+    swizzle4_scalar(src_chunks.remainder(), dst_chunks.into_remainder(), order);
+}
+
+/// SSE4.1 version of [`swizzle4`], 4 pixels per iteration.
+#[cfg(target_arch = "x86_64")]
+#[arcane]
+pub fn swizzle4_sse41(_token: X64V2Token, src: &[u8], dst: &mut [u8], order: [u8; 4]) {
+    let mask = _mm_loadu_si128(&shuffle_mask(order));
+
+    let mut src_chunks = src.chunks_exact(16);
+    let mut dst_chunks = dst.chunks_exact_mut(16);
+    for (s, d) in (&mut src_chunks).zip(&mut dst_chunks) {
+        let s: &[u8; 16] = s.try_into().unwrap();
+        let d: &mut [u8; 16] = d.try_into().unwrap();
+        _mm_storeu_si128(d, _mm_shuffle_epi8(_mm_loadu_si128(s), mask));
+    }
+    swizzle4_scalar(src_chunks.remainder(), dst_chunks.into_remainder(), order);
+}
+
+/// NEON version of [`swizzle4`], 4 pixels per iteration.
+#[cfg(target_arch = "aarch64")]
+#[arcane]
+pub fn swizzle4_neon(_token: NeonToken, src: &[u8], dst: &mut [u8], order: [u8; 4]) {
+    let mask = vld1q_u8(&shuffle_mask(order));
+
+    let mut src_chunks = src.chunks_exact(16);
+    let mut dst_chunks = dst.chunks_exact_mut(16);
+    for (s, d) in (&mut src_chunks).zip(&mut dst_chunks) {
+        let s: &[u8; 16] = s.try_into().unwrap();
+        let d: &mut [u8; 16] = d.try_into().unwrap();
+        vst1q_u8(d, vqtbl1q_u8(vld1q_u8(s), mask));
+    }
+    swizzle4_scalar(src_chunks.remainder(), dst_chunks.into_remainder(), order);
+}
+
+/// wasm SIMD128 version of [`swizzle4`], 4 pixels per iteration.
+#[cfg(target_arch = "wasm32")]
+#[arcane]
+pub fn swizzle4_wasm128(_token: Wasm128Token, src: &[u8], dst: &mut [u8], order: [u8; 4]) {
+    let mask = safe_unaligned_simd::wasm32::v128_load(&shuffle_mask(order));
+
+    let mut src_chunks = src.chunks_exact(16);
+    let mut dst_chunks = dst.chunks_exact_mut(16);
+    for (s, d) in (&mut src_chunks).zip(&mut dst_chunks) {
+        let s: &[u8; 16] = s.try_into().unwrap();
+        let d: &mut [u8; 16] = d.try_into().unwrap();
+        let v = safe_unaligned_simd::wasm32::v128_load(s);
+        safe_unaligned_simd::wasm32::v128_store(d, i8x16_swizzle(v, mask));
+    }
+    swizzle4_scalar(src_chunks.remainder(), dst_chunks.into_remainder(), order);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simd_matches_scalar() {
+        for order in [[2, 1, 0, 3], [3, 0, 1, 2], [3, 2, 1, 0]] {
+            for pixels in [0, 1, 3, 4, 9, 16, 37] {
+                let src: Vec<u8> = (0..pixels * 4).map(|i| (i * 7) as u8).collect();
+                let mut expected = vec![0; src.len()];
+                swizzle4_scalar(&src, &mut expected, order);
+                let mut actual = vec![0; src.len()];
+                swizzle4(&src, &mut actual, order);
+                assert_eq!(actual, expected, "{order:?} x{pixels}");
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn sse41_matches_scalar() {
+        let Some(token) = X64V2Token::summon() else {
+            return;
+        };
+        let src: Vec<u8> = (0..37 * 4).map(|i| (i * 7) as u8).collect();
+        let mut expected = vec![0; src.len()];
+        swizzle4_scalar(&src, &mut expected, [3, 0, 1, 2]);
+        let mut actual = vec![0; src.len()];
+        swizzle4_sse41(token, &src, &mut actual, [3, 0, 1, 2]);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn rgba_to_bgra() {
+        let mut out = [0; 8];
+        swizzle4(&[1, 2, 3, 4, 5, 6, 7, 8], &mut out, [2, 1, 0, 3]);
+        assert_eq!(out, [3, 2, 1, 4, 7, 6, 5, 8]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Decoding from data that arrives in pieces.
+//!
+//! [`StreamingAvifDecoder`] tracks top-level boxes as bytes are pushed and
+//! reads `iloc` out of `meta` as soon as it is complete. Once every item's
+//! data has arrived, the image decodes without waiting for whatever follows
+//! it in the file, such as trailing metadata or padding.
+
+use crate::bmff::{item_data_end, malformed, read_u32, read_u64};
+use crate::config::DecoderConfig;
+use crate::error::{Error, Result};
+use enough::Stop;
+use std::io::Read;
+use whereat::at;
+use zenpixels::PixelBuffer;
+
+/// Bytes requested per `read` call by [`StreamingAvifDecoder::decode_reader`].
+const READ_CHUNK: usize = 64 * 1024;
+
+/// Progress of a [`StreamingAvifDecoder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamStatus {
+    /// The container header or the image data is still incomplete.
+    NeedMoreData,
+    /// Everything the image needs has arrived; decoding does not have to
+    /// wait for the rest of the file.
+    Ready,
+}
+
+/// Where the item data referenced by `meta` ends.
+#[derive(Debug, Clone, Copy)]
+enum ItemData {
+    /// `meta` has not arrived yet.
+    Unknown,
+    /// Item data ends at this file offset.
+    Until(u64),
+    /// Some item runs to the end of the file.
+    ToEnd,
+}
+
+/// AVIF decoder fed incrementally, from `push_bytes` calls or an
+/// [`io::Read`](std::io::Read).
+///
+/// The container is scanned as data arrives, so decoding can start as soon
+/// as the image's own data is buffered instead of after the whole transfer.
+/// AV1 decoding itself needs each item's complete payload.
+///
+/// # Example
+///
+/// ```no_run
+/// use zenavif::{DecoderConfig, StreamStatus, StreamingAvifDecoder};
+/// use enough::Unstoppable;
+///
+/// let mut decoder = StreamingAvifDecoder::new(&DecoderConfig::new());
+/// # let chunks: Vec<Vec<u8>> = Vec::new();
+/// for chunk in chunks {
+///     if decoder.push_bytes(&chunk).unwrap() == StreamStatus::Ready {
+///         break;
+///     }
+/// }
+/// let image = decoder.decode(&Unstoppable).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct StreamingAvifDecoder {
+    config: DecoderConfig,
+    buffer: Vec<u8>,
+    /// End of the last complete top-level box.
+    complete: usize,
+    item_data: ItemData,
+}
+
+impl StreamingAvifDecoder {
+    /// Create a decoder with no data buffered yet.
+    pub fn new(config: &DecoderConfig) -> Self {
+        Self {
+            config: config.clone(),
+            buffer: Vec::new(),
+            complete: 0,
+            item_data: ItemData::Unknown,
+        }
+    }
+
+    /// Append the next bytes of the file.
+    ///
+    /// Fails if a top-level box header or the `iloc` table is malformed.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Result<StreamStatus> {
+        self.buffer
+            .try_reserve(bytes.len())
+            .map_err(|_| at!(Error::OutOfMemory))?;
+        self.buffer.extend_from_slice(bytes);
+        while let Some((kind, header, size)) = self.next_box()? {
+            if &kind == b"meta" {
+                let payload = &self.buffer[self.complete + header..self.complete + size];
+                self.item_data = match item_data_end(payload)? {
+                    Some(end) => ItemData::Until(end),
+                    None => ItemData::ToEnd,
+                };
+            }
+            self.complete += size;
+        }
+        Ok(self.status())
+    }
+
+    /// Whether the buffered data is enough to decode the image.
+    pub fn status(&self) -> StreamStatus {
+        match self.item_data {
+            ItemData::Until(end) if end <= self.complete as u64 => StreamStatus::Ready,
+            _ => StreamStatus::NeedMoreData,
+        }
+    }
+
+    /// Bytes buffered so far.
+    pub fn buffered_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Decode from the complete boxes buffered so far.
+    ///
+    /// Fails with [`Error::Unsupported`] until [`status`](Self::status) is
+    /// [`StreamStatus::Ready`]; use [`finish`](Self::finish) at end of input.
+    pub fn decode(&self, stop: &(impl Stop + ?Sized)) -> Result<PixelBuffer> {
+        if self.status() != StreamStatus::Ready {
+            return Err(at!(Error::Unsupported(
+                "image data has not been fully received yet",
+            )));
+        }
+        crate::decode_with(&self.buffer[..self.complete], &self.config, stop)
+    }
+
+    /// Decode everything received, for use once the input has ended.
+    ///
+    /// Unlike [`decode`](Self::decode) this also covers items that run to
+    /// the end of the file; a truncated file fails in the container parser.
+    /// Once the image is [`Ready`](StreamStatus::Ready), a partly received
+    /// box after it is ignored.
+    pub fn finish(self, stop: &(impl Stop + ?Sized)) -> Result<PixelBuffer> {
+        if self.status() == StreamStatus::Ready {
+            return self.decode(stop);
+        }
+        crate::decode_with(&self.buffer, &self.config, stop)
+    }
+
+    /// Read from `reader` until the image can be decoded, then decode it.
+    ///
+    /// Stops reading as soon as the image data is complete, leaving the rest
+    /// of the stream unread. The stop token is checked between reads.
+    /// Read failures surface as [`Error::Io`].
+    pub fn decode_reader(
+        mut reader: impl Read,
+        config: &DecoderConfig,
+        stop: &(impl Stop + ?Sized),
+    ) -> Result<PixelBuffer> {
+        let mut decoder = Self::new(config);
+        let mut chunk = vec![0u8; READ_CHUNK];
+        loop {
+            stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+            let read = match reader.read(&mut chunk) {
+                Ok(0) => return decoder.finish(stop),
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(at!(Error::Io(e))),
+            };
+            if decoder.push_bytes(&chunk[..read])? == StreamStatus::Ready {
+                return decoder.decode(stop);
+            }
+        }
+    }
+
+    /// `(type, header length, size)` of the box at `self.complete` if it has
+    /// fully arrived.
+    fn next_box(&self) -> Result<Option<([u8; 4], usize, usize)>> {
+        let rest = &self.buffer[self.complete..];
+        if rest.len() < 8 {
+            return Ok(None);
+        }
+        let kind: [u8; 4] = rest[4..8].try_into().unwrap();
+        let (header, size) = match read_u32(rest, 0)? {
+            // Runs to the end of the file, which only `finish` knows.
+            0 => return Ok(None),
+            1 if rest.len() < 16 => return Ok(None),
+            1 => (16, read_u64(rest, 8)?),
+            n => (8, u64::from(n)),
+        };
+        if size < header as u64 {
+            return Err(malformed());
+        }
+        match usize::try_from(size) {
+            Ok(size) if size <= rest.len() => Ok(Some((kind, header, size))),
+            _ => Ok(None),
+        }
+    }
+}
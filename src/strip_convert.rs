@@ -14,7 +14,7 @@
 #![deny(unsafe_code)]
 
 use crate::error::{Error, Result};
-use crate::image::{ChromaSampling, ColorRange};
+use crate::image::{ChromaSamplePosition, ChromaSampling, ColorRange};
 use crate::yuv_convert::{self, YuvMatrix, YuvRange};
 use rgb::{Rgb, Rgba};
 use whereat::at;
@@ -45,6 +45,7 @@ enum ConversionState {
         primary: Frame,
         alpha: Option<Frame>,
         chroma_sampling: ChromaSampling,
+        chroma_position: ChromaSamplePosition,
         yuv_range: YuvRange,
         yuv_matrix: YuvMatrix,
         alpha_range: ColorRange,
@@ -68,6 +69,7 @@ impl StripConverter {
         primary: Frame,
         alpha: Option<Frame>,
         chroma_sampling: ChromaSampling,
+        chroma_position: ChromaSamplePosition,
         yuv_range: YuvRange,
         yuv_matrix: YuvMatrix,
         alpha_range: ColorRange,
@@ -86,6 +88,7 @@ impl StripConverter {
                     primary,
                     alpha,
                     chroma_sampling,
+                    chroma_position,
                     yuv_range,
                     yuv_matrix,
                     alpha_range,
@@ -194,6 +197,7 @@ impl StripConverter {
                 primary,
                 alpha,
                 chroma_sampling,
+                chroma_position,
                 yuv_range,
                 yuv_matrix,
                 alpha_range,
@@ -204,6 +208,7 @@ impl StripConverter {
                 primary,
                 alpha.as_ref(),
                 *chroma_sampling,
+                *chroma_position,
                 *yuv_range,
                 *yuv_matrix,
                 *alpha_range,
@@ -236,6 +241,7 @@ impl StripConverter {
         primary: &Frame,
         alpha: Option<&Frame>,
         chroma_sampling: ChromaSampling,
+        chroma_position: ChromaSamplePosition,
         yuv_range: YuvRange,
         yuv_matrix: YuvMatrix,
         alpha_range: ColorRange,
@@ -278,7 +284,7 @@ impl StripConverter {
             })?;
 
             match chroma_sampling {
-                ChromaSampling::Cs420 => yuv_convert::yuv420_to_rgba8_strip(
+                ChromaSampling::Cs420 => yuv_convert::yuv420_strip(
                     y_view.as_slice(),
                     y_view.stride(),
                     u_view.as_slice(),
@@ -291,6 +297,7 @@ impl StripConverter {
                     strip_height,
                     yuv_range,
                     yuv_matrix,
+                    chroma_position,
                     out_rgba,
                 ),
                 ChromaSampling::Cs422 => yuv_convert::yuv422_to_rgba8_strip(
@@ -380,7 +387,7 @@ impl StripConverter {
             })?;
 
             match chroma_sampling {
-                ChromaSampling::Cs420 => yuv_convert::yuv420_to_rgb8_strip(
+                ChromaSampling::Cs420 => yuv_convert::yuv420_strip(
                     y_view.as_slice(),
                     y_view.stride(),
                     u_view.as_slice(),
@@ -393,6 +400,7 @@ impl StripConverter {
                     strip_height,
                     yuv_range,
                     yuv_matrix,
+                    chroma_position,
                     out_rgb,
                 ),
                 ChromaSampling::Cs422 => yuv_convert::yuv422_to_rgb8_strip(
@@ -0,0 +1,183 @@
+//! Synthetic test patterns.
+//!
+//! Deterministic RGB images for validating encoders, decoders and display
+//! chains, and for exercising the YUV converters with content that solid
+//! fixtures can't: gradients show banding, color bars check matrix and
+//! range handling, zone plates expose chroma siting and upsampling bugs,
+//! and noise defeats prediction.
+//!
+//! Every generator returns RGB8 for `depth` 8 and RGB16 otherwise, with
+//! samples quantized to `depth` bits and scaled to the full `u16` range the
+//! 16-bit encoders expect.
+//!
+//! # Example
+//!
+//! ```
+//! let bars = zenavif::testimg::color_bars(320, 240, 10).unwrap();
+//! assert_eq!((bars.width(), bars.height()), (320, 240));
+//! ```
+
+use crate::error::{Error, Result};
+use core::f32::consts::PI;
+use rgb::Rgb;
+use whereat::at;
+use zenpixels::PixelBuffer;
+
+/// Horizontal ramp in red, vertical ramp in green, diagonal ramp in blue.
+pub fn gradient(width: u32, height: u32, depth: u8) -> Result<PixelBuffer> {
+    let (w, h) = (span(width), span(height));
+    render(width, height, depth, |x, y| {
+        [x / w, y / h, 1.0 - (x + y) / (w + h).max(1.0)]
+    })
+}
+
+/// Eight vertical 75% bars: white, yellow, cyan, green, magenta, red, blue
+/// and black.
+pub fn color_bars(width: u32, height: u32, depth: u8) -> Result<PixelBuffer> {
+    const BARS: [[f32; 3]; 8] = [
+        [0.75, 0.75, 0.75],
+        [0.75, 0.75, 0.0],
+        [0.0, 0.75, 0.75],
+        [0.0, 0.75, 0.0],
+        [0.75, 0.0, 0.75],
+        [0.75, 0.0, 0.0],
+        [0.0, 0.0, 0.75],
+        [0.0, 0.0, 0.0],
+    ];
+    let bar_width = width as f32 / 8.0;
+    render(width, height, depth, |x, _| {
+        BARS[((x / bar_width) as usize).min(7)]
+    })
+}
+
+/// Circular zone plate whose frequency rises from zero at the center to
+/// Nyquist at the corners.
+///
+/// Blue runs in opposition to red and green, so the chroma planes carry the
+/// pattern too and misplaced or blurred chroma shows up as color fringes.
+pub fn zone_plate(width: u32, height: u32, depth: u8) -> Result<PixelBuffer> {
+    let (cx, cy) = (span(width) / 2.0, span(height) / 2.0);
+    let max_radius = (cx * cx + cy * cy).sqrt().max(1.0);
+    // Phase k·r² has local frequency k·r/π cycles per pixel; 0.5 at the corner.
+    let k = PI / (2.0 * max_radius);
+    render(width, height, depth, |x, y| {
+        let r2 = (x - cx) * (x - cx) + (y - cy) * (y - cy);
+        let v = 0.5 + 0.5 * (k * r2).cos();
+        [v, v, 1.0 - v]
+    })
+}
+
+/// Uniform white noise, independent per channel and identical from run to
+/// run.
+pub fn noise(width: u32, height: u32, depth: u8) -> Result<PixelBuffer> {
+    render(width, height, depth, |x, y| {
+        let seed = (((y as u64) << 32) | x as u64).wrapping_mul(3);
+        [0, 1, 2].map(|c| (mix(seed + c) >> 40) as f32 / (1u64 << 24) as f32)
+    })
+}
+
+/// Distance from the first to the last pixel along an axis of `len` pixels.
+fn span(len: u32) -> f32 {
+    len.saturating_sub(1).max(1) as f32
+}
+
+/// SplitMix64 finalizer.
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Fill an image from `pattern`, which maps pixel coordinates to RGB in
+/// `[0, 1]`.
+fn render(
+    width: u32,
+    height: u32,
+    depth: u8,
+    pattern: impl Fn(f32, f32) -> [f32; 3],
+) -> Result<PixelBuffer> {
+    if !matches!(depth, 8 | 10 | 12 | 16) {
+        return Err(at!(Error::Unsupported(
+            "test pattern depth must be 8, 10, 12 or 16"
+        )));
+    }
+    let max = ((1u32 << depth) - 1) as f32;
+    let coords = (0..height).flat_map(|y| (0..width).map(move |x| (x as f32, y as f32)));
+    let quantize = |v: f32| (v.clamp(0.0, 1.0) * max).round() as u32;
+    let buffer: PixelBuffer = if depth == 8 {
+        let pixels: Vec<Rgb<u8>> = coords
+            .map(|(x, y)| {
+                let [r, g, b] = pattern(x, y).map(|v| quantize(v) as u8);
+                Rgb { r, g, b }
+            })
+            .collect();
+        PixelBuffer::from_pixels(pixels, width, height)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    } else {
+        // Bit replication spreads `depth`-bit codes over the full range.
+        let widen = |v: u32| ((v << (16 - depth)) | (v >> (2 * depth - 16))) as u16;
+        let pixels: Vec<Rgb<u16>> = coords
+            .map(|(x, y)| {
+                let [r, g, b] = pattern(x, y).map(|v| widen(quantize(v)));
+                Rgb { r, g, b }
+            })
+            .collect();
+        PixelBuffer::from_pixels(pixels, width, height)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    };
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depth_selects_format_and_range() {
+        let img = gradient(16, 8, 8).unwrap();
+        let img = img.try_as_imgref::<Rgb<u8>>().unwrap();
+        assert_eq!(img.buf()[0], Rgb::new(0, 0, 255));
+        assert_eq!(img.buf()[15].r, 255);
+
+        let img = gradient(16, 8, 10).unwrap();
+        let img = img.try_as_imgref::<Rgb<u16>>().unwrap();
+        assert_eq!(img.buf()[0], Rgb::new(0, 0, 65535));
+        // Every sample is a 10-bit code widened by replication.
+        assert!(img.buf().iter().all(|p| p.g & 0x3f == p.g >> 10));
+
+        assert!(gradient(16, 8, 9).is_err());
+    }
+
+    #[test]
+    fn color_bars_cover_the_width() {
+        let img = color_bars(80, 2, 8).unwrap();
+        let img = img.try_as_imgref::<Rgb<u8>>().unwrap();
+        let row: Vec<_> = (0..8).map(|bar| img.buf()[bar * 10 + 5]).collect();
+        assert_eq!(row[0], Rgb::new(191, 191, 191));
+        assert_eq!(row[5], Rgb::new(191, 0, 0));
+        assert_eq!(row[7], Rgb::new(0, 0, 0));
+    }
+
+    #[test]
+    fn zone_plate_is_white_at_the_center() {
+        let img = zone_plate(33, 33, 8).unwrap();
+        let img = img.try_as_imgref::<Rgb<u8>>().unwrap();
+        assert_eq!(img.buf()[16 * 33 + 16], Rgb::new(255, 255, 0));
+    }
+
+    #[test]
+    fn noise_is_deterministic_and_spread() {
+        let a = noise(32, 32, 8).unwrap();
+        let b = noise(32, 32, 8).unwrap();
+        let (a, b) = (
+            a.try_as_imgref::<Rgb<u8>>().unwrap(),
+            b.try_as_imgref::<Rgb<u8>>().unwrap(),
+        );
+        assert_eq!(a.buf(), b.buf());
+        let mean = a.buf().iter().map(|p| f64::from(p.r)).sum::<f64>() / 1024.0;
+        assert!((100.0..156.0).contains(&mean), "mean {mean}");
+    }
+}
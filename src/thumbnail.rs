@@ -0,0 +1,269 @@
+//! One-call thumbnailing: decode reduced, resize, orient, re-encode.
+
+use crate::config::DecoderConfig;
+use crate::decoder_managed::ManagedAvifDecoder;
+use crate::encoder::{EncodeBitDepth, EncodedImage, EncoderConfig};
+use crate::error::{Error, Result};
+use almost_enough::Stop;
+use imgref::ImgVec;
+use rgb::{Rgb, Rgba};
+use whereat::at;
+use zenpixels::PixelBuffer;
+use zenpixels_convert::PixelBufferConvertTypedExt as _;
+
+/// Settings for [`thumbnail`].
+#[derive(Debug, Clone)]
+pub struct ThumbnailOptions {
+    pub(crate) quality: f32,
+    pub(crate) speed: u8,
+    pub(crate) keep_metadata: bool,
+    pub(crate) threads: u32,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        Self {
+            quality: 60.0,
+            speed: 8,
+            keep_metadata: false,
+            threads: 0,
+        }
+    }
+}
+
+impl ThumbnailOptions {
+    /// Defaults: quality 60, speed 8, metadata stripped, automatic threads.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encoding quality of the thumbnail (1.0 = worst, 100.0 = best).
+    pub fn quality(mut self, quality: f32) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    /// Encoding speed of the thumbnail (1 = slowest, 10 = fastest).
+    pub fn speed(mut self, speed: u8) -> Self {
+        self.speed = speed.clamp(1, 10);
+        self
+    }
+
+    /// Carry EXIF and XMP over into the thumbnail.
+    ///
+    /// Default: `false`. Color information is always kept.
+    pub fn keep_metadata(mut self, keep: bool) -> Self {
+        self.keep_metadata = keep;
+        self
+    }
+
+    /// Threads for decoding (0 = auto).
+    pub fn threads(mut self, threads: u32) -> Self {
+        self.threads = threads;
+        self
+    }
+}
+
+/// Make an AVIF thumbnail that fits within `max_dim` × `max_dim`
+///
+/// Decodes with [`DecoderConfig::downscale_after_decode`] so large images
+/// are box-filtered close to size, area-averages the result the rest of the
+/// way, applies the `irot`/`imir` orientation to the pixels, and encodes
+/// 8-bit output. The ICC profile or CICP tags stay attached; with the `cms`
+/// feature the pixels are converted to sRGB instead. Images already within
+/// `max_dim` are only re-encoded. Only the first frame of animations is used.
+///
+/// # Example
+///
+/// ```no_run
+/// use zenavif::{ThumbnailOptions, thumbnail_unstoppable};
+///
+/// let avif_data = std::fs::read("photo.avif").unwrap();
+/// let thumb = thumbnail_unstoppable(&avif_data, 256, &ThumbnailOptions::new()).unwrap();
+/// std::fs::write("thumb.avif", &thumb.avif_file).unwrap();
+/// ```
+pub fn thumbnail(
+    data: &[u8],
+    max_dim: u32,
+    options: &ThumbnailOptions,
+    stop: almost_enough::StopToken,
+) -> Result<EncodedImage> {
+    if max_dim == 0 {
+        return Err(at!(Error::Unsupported("thumbnail size must be non-zero")));
+    }
+    let config = DecoderConfig::new()
+        .threads(options.threads)
+        .downscale_after_decode(max_dim, max_dim);
+    #[cfg(feature = "cms")]
+    let config = config.color_manage(crate::config::Destination::Srgb);
+
+    let mut decoder = ManagedAvifDecoder::from_slice(data, &config)?;
+    let (pixels, mut info) = decoder.decode_full(&stop)?;
+    stop.check().map_err(|e| at!(Error::Cancelled(e)))?;
+
+    let has_alpha = pixels.has_alpha();
+    let rgba = pixels.to_rgba8();
+    let rgba = rgba.as_imgref();
+    let (width, height) = (rgba.width(), rgba.height());
+    let scale = (max_dim as f64 / width.max(height) as f64).min(1.0);
+    let size = |v: usize| ((v as f64 * scale).round() as usize).max(1);
+    let small = area_resize(rgba, size(width), size(height));
+    let quarter_turns = info.rotation.take().map_or(0, |r| (r.angle / 90) as usize);
+    let small = orient(small, quarter_turns, info.mirror.take().map(|m| m.axis));
+
+    if !options.keep_metadata {
+        info.exif = None;
+        info.xmp = None;
+    }
+    let encoder = EncoderConfig::new()
+        .quality(options.quality)
+        .speed(options.speed)
+        .bit_depth(EncodeBitDepth::Eight)
+        .with_image_info(&info);
+    let (w, h) = (small.width(), small.height());
+    let pixels: PixelBuffer = if has_alpha {
+        PixelBuffer::from_pixels(small.into_buf(), w as u32, h as u32)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    } else {
+        let rgb: Vec<Rgb<u8>> = small
+            .into_buf()
+            .into_iter()
+            .map(|p| Rgb::new(p.r, p.g, p.b))
+            .collect();
+        PixelBuffer::from_pixels(rgb, w as u32, h as u32)
+            .map_err(|_| at!(Error::OutOfMemory))?
+            .into()
+    };
+    crate::encode_with(&pixels, &encoder, stop)
+}
+
+/// Shrink `src` to `width` × `height` by averaging the source area each
+/// output pixel covers, weighting color by alpha.
+fn area_resize(src: imgref::ImgRef<'_, Rgba<u8>>, width: usize, height: usize) -> ImgVec<Rgba<u8>> {
+    let (sw, sh) = (src.width(), src.height());
+    if (sw, sh) == (width, height) {
+        return ImgVec::new(src.pixels().collect(), sw, sh);
+    }
+    let rows: Vec<&[Rgba<u8>]> = src.rows().collect();
+    let span = |i: usize, out: usize, size: usize| (i * size / out, ((i + 1) * size).div_ceil(out));
+    let mut out = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let (y0, y1) = span(y, height, sh);
+        for x in 0..width {
+            let (x0, x1) = span(x, width, sw);
+            let mut sum = [0u64; 4];
+            for row in &rows[y0..y1] {
+                for p in &row[x0..x1] {
+                    let a = u64::from(p.a);
+                    sum[0] += u64::from(p.r) * a;
+                    sum[1] += u64::from(p.g) * a;
+                    sum[2] += u64::from(p.b) * a;
+                    sum[3] += a;
+                }
+            }
+            let count = ((x1 - x0) * (y1 - y0)) as u64;
+            let color = |c: u64| match sum[3] {
+                0 => 0,
+                a => ((c + a / 2) / a) as u8,
+            };
+            out.push(Rgba::new(
+                color(sum[0]),
+                color(sum[1]),
+                color(sum[2]),
+                ((sum[3] + count / 2) / count) as u8,
+            ));
+        }
+    }
+    ImgVec::new(out, width, height)
+}
+
+/// Apply the `imir` mirror axis, then `irot`'s counter-clockwise quarter
+/// turns, to the pixels.
+fn orient(
+    img: ImgVec<Rgba<u8>>,
+    quarter_turns: usize,
+    mirror_axis: Option<u8>,
+) -> ImgVec<Rgba<u8>> {
+    let (w, h) = (img.width(), img.height());
+    let mut px = img.into_buf();
+    match mirror_axis {
+        // Vertical axis: left and right swap.
+        Some(0) => px.chunks_exact_mut(w).for_each(|row| row.reverse()),
+        Some(_) => {
+            let rows: Vec<_> = px.chunks_exact(w).rev().flatten().copied().collect();
+            px = rows;
+        }
+        None => {}
+    }
+    let at = |x: usize, y: usize| px[y * w + x];
+    match quarter_turns % 4 {
+        1 => ImgVec::new(
+            (0..w)
+                .flat_map(|y| (0..h).map(move |x| (w - 1 - y, x)))
+                .map(|(x, y)| at(x, y))
+                .collect(),
+            h,
+            w,
+        ),
+        2 => ImgVec::new(px.iter().rev().copied().collect(), w, h),
+        3 => ImgVec::new(
+            (0..w)
+                .flat_map(|y| (0..h).map(move |x| (y, h - 1 - x)))
+                .map(|(x, y)| at(x, y))
+                .collect(),
+            h,
+            w,
+        ),
+        _ => ImgVec::new(px, w, h),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(w: usize, h: usize) -> ImgVec<Rgba<u8>> {
+        ImgVec::new(
+            (0..w * h).map(|i| Rgba::new(i as u8, 0, 0, 255)).collect(),
+            w,
+            h,
+        )
+    }
+
+    fn reds(img: &ImgVec<Rgba<u8>>) -> Vec<u8> {
+        img.pixels().map(|p| p.r).collect()
+    }
+
+    #[test]
+    fn rotation_is_counter_clockwise() {
+        // 0 1 2
+        // 3 4 5
+        let img = numbered(3, 2);
+        let ccw = orient(img.clone(), 1, None);
+        assert_eq!((ccw.width(), ccw.height()), (2, 3));
+        assert_eq!(reds(&ccw), [2, 5, 1, 4, 0, 3]);
+        let cw = orient(img.clone(), 3, None);
+        assert_eq!(reds(&cw), [3, 0, 4, 1, 5, 2]);
+        let half = orient(img.clone(), 2, None);
+        assert_eq!(reds(&half), [5, 4, 3, 2, 1, 0]);
+        let flipped = orient(img, 0, Some(1));
+        assert_eq!(reds(&flipped), [3, 4, 5, 0, 1, 2]);
+    }
+
+    #[test]
+    fn area_resize_weights_color_by_alpha() {
+        let img = ImgVec::new(
+            vec![
+                Rgba::new(200, 0, 0, 255),
+                Rgba::new(0, 200, 0, 0),
+                Rgba::new(100, 0, 0, 255),
+                Rgba::new(0, 0, 0, 255),
+            ],
+            2,
+            2,
+        );
+        let out = area_resize(img.as_ref(), 1, 1);
+        assert_eq!(out.buf()[0], Rgba::new(100, 0, 0, 191));
+    }
+}
@@ -0,0 +1,284 @@
+//! Linear-light conversion driven by CICP code points.
+//!
+//! Float outputs are linear light, so the image's own transfer function has
+//! to be undone, and luma weights follow its primaries. Unknown or
+//! unspecified code points fall back to sRGB / BT.709, as AVIF readers do
+//! for untagged images.
+
+use imgref::ImgVec;
+use rgb::{Rgb, Rgba};
+use zenpixels::{PixelBuffer, PixelDescriptor};
+use zenpixels_convert::PixelBufferConvertTypedExt as _;
+
+/// Convert a non-linear signal value in `[0, 1]` to linear light for a CICP
+/// `transfer_characteristics` code point (ITU-T H.273).
+///
+/// SDR curves map reference white to 1.0. PQ maps 1.0 to 10 000 cd/m².
+/// HLG applies the inverse OETF only, giving normalised scene light.
+pub(crate) fn to_linear(v: f32, transfer: u8) -> f32 {
+    match transfer {
+        // BT.709, BT.601, BT.2020 share the BT.709 OETF.
+        1 | 6 | 12 | 14 | 15 => bt709_inverse_oetf(v),
+        4 => v.max(0.0).powf(2.2),
+        5 => v.max(0.0).powf(2.8),
+        7 => {
+            if v < 4.0 * 0.0228 {
+                v / 4.0
+            } else {
+                ((v + 0.1115) / 1.1115).powf(1.0 / 0.45)
+            }
+        }
+        8 => v,
+        9 => {
+            if v <= 0.0 {
+                0.0
+            } else {
+                10f32.powf(2.0 * (v - 1.0))
+            }
+        }
+        10 => {
+            if v <= 0.0 {
+                0.0
+            } else {
+                10f32.powf(2.5 * (v - 1.0))
+            }
+        }
+        // xvYCC: the BT.709 curve mirrored for negative values.
+        11 => bt709_inverse_oetf(v.abs()).copysign(v),
+        16 => pq_eotf(v),
+        17 => (52.37 / 48.0) * v.max(0.0).powf(2.6),
+        18 => hlg_inverse_oetf(v),
+        _ => srgb_eotf(v),
+    }
+}
+
+fn srgb_eotf(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn bt709_inverse_oetf(v: f32) -> f32 {
+    const ALPHA: f32 = 1.099_296_8;
+    const BETA: f32 = 0.018_053_97;
+    if v < 4.5 * BETA {
+        v / 4.5
+    } else {
+        ((v + (ALPHA - 1.0)) / ALPHA).powf(1.0 / 0.45)
+    }
+}
+
+/// SMPTE ST 2084.
+fn pq_eotf(v: f32) -> f32 {
+    const M1: f32 = 2610.0 / 16384.0;
+    const M2: f32 = 2523.0 / 4096.0 * 128.0;
+    const C1: f32 = 3424.0 / 4096.0;
+    const C2: f32 = 2413.0 / 4096.0 * 32.0;
+    const C3: f32 = 2392.0 / 4096.0 * 32.0;
+    let p = v.max(0.0).powf(1.0 / M2);
+    ((p - C1).max(0.0) / (C2 - C3 * p)).powf(1.0 / M1)
+}
+
+/// ARIB STD-B67 / BT.2100 HLG.
+fn hlg_inverse_oetf(v: f32) -> f32 {
+    const A: f32 = 0.178_832_77;
+    const B: f32 = 0.284_668_92;
+    const C: f32 = 0.559_910_7;
+    if v <= 0.5 {
+        v * v / 3.0
+    } else {
+        (((v - C) / A).exp() + B) / 12.0
+    }
+}
+
+/// Relative luminance weights `[R, G, B]` for a CICP `color_primaries` code
+/// point, derived from the primaries' and white point's chromaticities.
+#[cfg_attr(not(feature = "zencodec"), allow(dead_code))]
+pub(crate) fn luminance_weights(primaries: u8) -> [f32; 3] {
+    const D65: (f64, f64) = (0.3127, 0.3290);
+    const C: (f64, f64) = (0.310, 0.316);
+    let (rgb, white) = match primaries {
+        4 => ([(0.67, 0.33), (0.21, 0.71), (0.14, 0.08)], C),
+        5 => ([(0.64, 0.33), (0.29, 0.60), (0.15, 0.06)], D65),
+        6 | 7 => ([(0.630, 0.340), (0.310, 0.595), (0.155, 0.070)], D65),
+        8 => ([(0.681, 0.319), (0.243, 0.692), (0.145, 0.049)], C),
+        9 => ([(0.708, 0.292), (0.170, 0.797), (0.131, 0.046)], D65),
+        // CIE XYZ: the G channel already is luminance.
+        10 => return [0.0, 1.0, 0.0],
+        11 => (
+            [(0.680, 0.320), (0.265, 0.690), (0.150, 0.060)],
+            (0.314, 0.351),
+        ),
+        12 => ([(0.680, 0.320), (0.265, 0.690), (0.150, 0.060)], D65),
+        22 => ([(0.630, 0.340), (0.295, 0.605), (0.155, 0.077)], D65),
+        _ => ([(0.64, 0.33), (0.30, 0.60), (0.15, 0.06)], D65),
+    };
+    // Columns of the RGB→XYZ matrix up to scale: XYZ of each primary with Y = 1.
+    let xyz = |(x, y): (f64, f64)| [x / y, 1.0, (1.0 - x - y) / y];
+    let [r, g, b] = rgb.map(xyz);
+    let w = xyz(white);
+    // Scale each column so R + G + B = white; the scales are the Y row.
+    let det = |a: [f64; 3], b: [f64; 3], c: [f64; 3]| {
+        a[0] * (b[1] * c[2] - b[2] * c[1]) - b[0] * (a[1] * c[2] - a[2] * c[1])
+            + c[0] * (a[1] * b[2] - a[2] * b[1])
+    };
+    let d = det(r, g, b);
+    [
+        (det(w, g, b) / d) as f32,
+        (det(r, w, b) / d) as f32,
+        (det(r, g, w) / d) as f32,
+    ]
+}
+
+/// Linear-light RGBA from a decoded RGB(A) 8- or 16-bit buffer.
+///
+/// Colour channels go through [`to_linear`] via a lookup table; alpha is
+/// scaled to `[0, 1]` unchanged. With `video_range`, colour samples are
+/// expanded from video range first, without clipping: sub-blacks become
+/// negative and super-whites exceed 1.0, with the curve mirrored below zero.
+pub(crate) fn linear_rgba(
+    pixels: &PixelBuffer,
+    transfer: u8,
+    video_range: bool,
+) -> ImgVec<Rgba<f32>> {
+    linear_rgba_as(pixels, transfer, video_range, |v| v)
+}
+
+/// [`linear_rgba`] with each sample passed through `convert`, e.g. to `f16`.
+///
+/// The lookup table holds converted values, so no `f32` image is built.
+pub(crate) fn linear_rgba_as<T: Copy>(
+    pixels: &PixelBuffer,
+    transfer: u8,
+    video_range: bool,
+    convert: impl Fn(f32) -> T,
+) -> ImgVec<Rgba<T>> {
+    let desc = pixels.descriptor();
+    let (w, h) = (pixels.width() as usize, pixels.height() as usize);
+    let lut = |bits: u32| -> Vec<T> {
+        let (black, range) = if video_range {
+            ((16u32 << (bits - 8)) as f32, (219u32 << (bits - 8)) as f32)
+        } else {
+            (0.0, ((1u32 << bits) - 1) as f32)
+        };
+        (0..1u32 << bits)
+            .map(|v| {
+                let v = (v as f32 - black) / range;
+                convert(to_linear(v.abs(), transfer).copysign(v))
+            })
+            .collect()
+    };
+    let out: Vec<Rgba<T>> = if desc.layout_compatible(PixelDescriptor::RGB16) {
+        let lut = lut(16);
+        let opaque = convert(1.0);
+        let src = pixels.try_as_imgref::<Rgb<u16>>().unwrap();
+        src.pixels()
+            .map(|p| Rgba {
+                r: lut[p.r as usize],
+                g: lut[p.g as usize],
+                b: lut[p.b as usize],
+                a: opaque,
+            })
+            .collect()
+    } else if desc.layout_compatible(PixelDescriptor::RGBA16) {
+        let lut = lut(16);
+        let src = pixels.try_as_imgref::<Rgba<u16>>().unwrap();
+        src.pixels()
+            .map(|p| Rgba {
+                r: lut[p.r as usize],
+                g: lut[p.g as usize],
+                b: lut[p.b as usize],
+                a: convert(p.a as f32 / 65535.0),
+            })
+            .collect()
+    } else {
+        let lut = lut(8);
+        let alpha: Vec<T> = (0..256u32).map(|a| convert(a as f32 / 255.0)).collect();
+        let src = pixels.to_rgba8();
+        src.as_imgref()
+            .pixels()
+            .map(|p| Rgba {
+                r: lut[p.r as usize],
+                g: lut[p.g as usize],
+                b: lut[p.b as usize],
+                a: alpha[p.a as usize],
+            })
+            .collect()
+    };
+    ImgVec::new(out, w, h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32, tolerance: f32) -> bool {
+        (a - b).abs() <= tolerance
+    }
+
+    #[test]
+    fn srgb_reference_values() {
+        assert_eq!(to_linear(0.0, 13), 0.0);
+        assert!(close(to_linear(1.0, 13), 1.0, 1e-6));
+        assert!(close(to_linear(0.5, 13), 0.214_041, 1e-5));
+        // unspecified falls back to sRGB
+        assert_eq!(to_linear(0.5, 2), to_linear(0.5, 13));
+    }
+
+    #[test]
+    fn bt709_reference_values() {
+        assert!(close(to_linear(0.5, 1), 0.259_719, 1e-5));
+        assert!(close(to_linear(0.04, 1), 0.04 / 4.5, 1e-7));
+        assert!(close(to_linear(1.0, 1), 1.0, 1e-5));
+    }
+
+    #[test]
+    fn pq_reference_values() {
+        // 100 cd/m² sits at PQ code value 0.508 (of 10 000 cd/m²).
+        assert!(close(to_linear(0.508_078, 16), 0.01, 1e-5));
+        assert!(close(to_linear(1.0, 16), 1.0, 1e-5));
+        assert_eq!(to_linear(0.0, 16), 0.0);
+    }
+
+    #[test]
+    fn hlg_reference_values() {
+        assert!(close(to_linear(0.5, 18), 1.0 / 12.0, 1e-6));
+        assert!(close(to_linear(1.0, 18), 1.0, 1e-5));
+        assert!(close(to_linear(0.25, 18), 0.0625 / 3.0, 1e-7));
+    }
+
+    #[test]
+    fn video_range_expands_without_clipping() {
+        let px = [4096u16, 60160, 64000, 1280].map(|v| Rgb { r: v, g: v, b: v });
+        let pixels = PixelBuffer::from_pixels(px.to_vec(), 4, 1).unwrap().into();
+        let out = linear_rgba(&pixels, 8, true);
+        let r: Vec<f32> = out.pixels().map(|p| p.r).collect();
+        assert_eq!(r[0], 0.0);
+        assert!(close(r[1], 1.0, 1e-6), "{r:?}");
+        assert!(r[2] > 1.0, "{r:?}");
+        assert!(r[3] < 0.0, "{r:?}");
+        // Sub-blacks mirror the curve rather than running it on a negative
+        // signal.
+        let srgb = linear_rgba(&pixels, 13, true);
+        let below = srgb.pixels().nth(3).unwrap().r;
+        assert!(close(below, -to_linear(2816.0 / 56064.0, 13), 1e-6));
+    }
+
+    #[test]
+    fn luminance_weights_match_published_coefficients() {
+        let bt709 = luminance_weights(1);
+        for (w, r) in bt709.iter().zip([0.2126, 0.7152, 0.0722]) {
+            assert!(close(*w, r, 1e-4), "{bt709:?}");
+        }
+        let bt2020 = luminance_weights(9);
+        for (w, r) in bt2020.iter().zip([0.2627, 0.6780, 0.0593]) {
+            assert!(close(*w, r, 1e-4), "{bt2020:?}");
+        }
+        let p3 = luminance_weights(12);
+        for (w, r) in p3.iter().zip([0.2290, 0.6917, 0.0793]) {
+            assert!(close(*w, r, 1e-4), "{p3:?}");
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Trial encodes for picking encoder settings empirically.
+
+use crate::config::{DecoderConfig, OutputDepth};
+use crate::decoder_managed::ManagedAvifDecoder;
+use crate::encoder::{EncoderConfig, encode_rgb8};
+use crate::error::{Error, Result};
+use enough::Unstoppable;
+use imgref::ImgRef;
+use rgb::Rgb;
+use std::time::{Duration, Instant};
+use whereat::at;
+
+/// Longest side of the probe [`EncoderConfig::trial_encode`] encodes.
+pub const TRIAL_PROBE_SIDE: usize = 256;
+
+/// Outcome of one trial encode. See [`EncoderConfig::trial_encode`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct TrialResult {
+    /// Encoder speed used.
+    pub speed: u8,
+    /// Encoder quality used.
+    pub quality: f32,
+    /// Probe width and height in pixels.
+    pub probe_size: (u32, u32),
+    /// Size of the encoded probe in bytes.
+    pub bytes: usize,
+    /// Wall-clock encode time of the probe.
+    pub encode_time: Duration,
+    /// PSNR of the decoded probe against the source, in dB over RGB.
+    /// `f64::INFINITY` when they are identical.
+    pub psnr: f64,
+}
+
+impl TrialResult {
+    /// Encoded bits per probe pixel.
+    pub fn bits_per_pixel(&self) -> f64 {
+        let pixels = f64::from(self.probe_size.0) * f64::from(self.probe_size.1);
+        self.bytes as f64 * 8.0 / pixels
+    }
+}
+
+impl EncoderConfig {
+    /// Encode a probe of `img` once per entry of `speeds` and report size,
+    /// time and quality of each.
+    ///
+    /// The probe is the central [`TRIAL_PROBE_SIDE`]-square crop, or the
+    /// whole image when it is smaller; cropping rather than downscaling keeps
+    /// texture at the scale the real encode sees. Every other setting comes
+    /// from `self`. Results are in the order of `speeds`; byte counts scale
+    /// roughly with area, times less predictably with thread count.
+    pub fn trial_encode(
+        &self,
+        img: ImgRef<'_, Rgb<u8>>,
+        speeds: &[u8],
+    ) -> Result<Vec<TrialResult>> {
+        if img.width() == 0 || img.height() == 0 {
+            return Err(at!(Error::Unsupported("trial encode of an empty image")));
+        }
+        let (width, height) = (
+            img.width().min(TRIAL_PROBE_SIDE),
+            img.height().min(TRIAL_PROBE_SIDE),
+        );
+        let probe = img.sub_image(
+            (img.width() - width) / 2,
+            (img.height() - height) / 2,
+            width,
+            height,
+        );
+        let decoder_config = DecoderConfig::new().output_depth(OutputDepth::Force8);
+
+        speeds
+            .iter()
+            .map(|&speed| {
+                let config = self.clone().speed(speed);
+                let start = Instant::now();
+                let encoded =
+                    encode_rgb8(probe, &config, almost_enough::StopToken::new(Unstoppable))?;
+                let encode_time = start.elapsed();
+
+                let mut decoder = ManagedAvifDecoder::new(&encoded.avif_file, &decoder_config)?;
+                let (pixels, _) = decoder.decode_full(&Unstoppable)?;
+                let decoded = pixels.try_as_imgref::<Rgb<u8>>().ok_or_else(|| {
+                    at!(Error::Decode {
+                        code: -1,
+                        msg: "trial encode did not decode to RGB8",
+                    })
+                })?;
+                Ok(TrialResult {
+                    speed,
+                    quality: self.quality,
+                    probe_size: (width as u32, height as u32),
+                    bytes: encoded.avif_file.len(),
+                    encode_time,
+                    psnr: psnr(probe, decoded),
+                })
+            })
+            .collect()
+    }
+}
+
+/// PSNR in dB over all RGB samples of two same-sized images.
+fn psnr(a: ImgRef<'_, Rgb<u8>>, b: ImgRef<'_, Rgb<u8>>) -> f64 {
+    let (mut sum, mut count) = (0u64, 0u64);
+    for (pa, pb) in a.pixels().zip(b.pixels()) {
+        for (ca, cb) in [(pa.r, pb.r), (pa.g, pb.g), (pa.b, pb.b)] {
+            sum += u64::from(ca.abs_diff(cb)).pow(2);
+            count += 1;
+        }
+    }
+    if sum == 0 {
+        return f64::INFINITY;
+    }
+    let mse = sum as f64 / count as f64;
+    10.0 * (255.0 * 255.0 / mse).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use imgref::Img;
+
+    #[test]
+    fn psnr_of_identical_images_is_infinite() {
+        let img = Img::new(vec![Rgb { r: 1u8, g: 2, b: 3 }; 4], 2, 2);
+        assert_eq!(psnr(img.as_ref(), img.as_ref()), f64::INFINITY);
+        let off = Img::new(vec![Rgb { r: 2u8, g: 2, b: 3 }; 4], 2, 2);
+        let expected = 10.0 * (255.0f64 * 255.0 * 3.0).log10();
+        assert!((psnr(img.as_ref(), off.as_ref()) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn trial_encode_reports_each_speed() {
+        let pixels: Vec<Rgb<u8>> = (0..300 * 40)
+            .map(|i| Rgb {
+                r: (i % 300) as u8,
+                g: (i / 300 * 6) as u8,
+                b: 90,
+            })
+            .collect();
+        let img = Img::new(pixels, 300, 40);
+        let results = EncoderConfig::new()
+            .quality(70.0)
+            .trial_encode(img.as_ref(), &[10, 9])
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        for (result, speed) in results.iter().zip([10, 9]) {
+            assert_eq!(result.speed, speed);
+            assert_eq!(result.probe_size, (256, 40));
+            assert!(result.bytes > 0);
+            assert!(result.psnr > 25.0, "{result:?}");
+            assert!(result.bits_per_pixel() > 0.0);
+        }
+    }
+}
@@ -1,24 +1,40 @@
 //! YUV to RGB color space conversion
 //!
 //! Implements standard color space conversions for AVIF/AV1 images.
-//! Includes SIMD-optimized paths for x86 (AVX2/FMA), aarch64 (NEON),
-//! and wasm32 via magetypes generic dispatch.
+//! Includes SIMD-optimized paths for x86 (AVX2/FMA, with an SSE4.1
+//! fallback), aarch64 (NEON), and wasm32 via magetypes generic dispatch.
+//!
+//! # Rounding contract
+//!
+//! All float paths normalize with the same reciprocal constants, evaluate
+//! the matrix with fused multiply-adds in the same order, clamp to
+//! `[0, 255]`, and round half-up by adding `0.5` and truncating. Truncation
+//! behaves identically everywhere, whereas "round to nearest" differs between
+//! `f32::round` (ties away from zero) and SIMD rounding instructions (ties to
+//! even). The SSE4.1 strip path has no FMA either; it evaluates the
+//! multiply-adds in `f64`, which matches the fused rounding except in rare
+//! double-rounding cases. The wasm128 strip path has no FMA and may differ by one code
+//! value at exact rounding boundaries.
 //!
 //! References:
 //! - ITU-R BT.601 (SD video)
 //! - ITU-R BT.709 (HD video)
 //! - ITU-R BT.2020 (UHD video)
+//! - ITU-T H.273 (YCgCo, YCgCo-Re and YCgCo-Ro)
 
 // YUV conversion functions naturally require plane/stride/dimension/matrix/range parameters.
 #![allow(clippy::too_many_arguments)]
 #![allow(dead_code)]
 
+use crate::chroma_upsample::Sample;
+use crate::image::{ChromaSamplePosition, MatrixCoefficients};
 use archmage::prelude::*;
 use imgref::ImgVec;
 #[cfg(target_arch = "x86_64")]
 use magetypes::simd::f32x8;
 use magetypes::simd::generic::f32x8 as GenericF32x8;
 use rgb::{RGB8, Rgba};
+use std::sync::OnceLock;
 
 /// YUV color range
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -38,6 +54,33 @@ pub enum YuvMatrix {
     Bt709,
     /// ITU-R BT.2020 (UHD video, HDR)
     Bt2020,
+    /// SMPTE 240M (1990s HDTV)
+    Smpte240,
+    /// FCC Title 47 (US NTSC, nearly BT.601)
+    Fcc,
+}
+
+/// YCgCo matrices, which have no Kr/Kb form and bypass [`YuvMatrix`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YCgCo {
+    /// YCgCo (H.273 matrix coefficients 8): RGB at the coded bit depth.
+    Standard,
+    /// YCgCo-Re (16): lossless, RGB two bits shallower than the samples.
+    Re,
+    /// YCgCo-Ro (17): lossless, RGB one bit shallower than the samples.
+    Ro,
+}
+
+impl YCgCo {
+    /// The YCgCo matrix `mc` signals, if it is one.
+    pub fn from_matrix(mc: MatrixCoefficients) -> Option<Self> {
+        match mc {
+            MatrixCoefficients::YCGCO => Some(Self::Standard),
+            MatrixCoefficients::YCGCO_RE => Some(Self::Re),
+            MatrixCoefficients::YCGCO_RO => Some(Self::Ro),
+            _ => None,
+        }
+    }
 }
 
 /// Chroma subsampling format
@@ -51,6 +94,29 @@ pub enum ChromaSubsampling {
     Cs420,
 }
 
+/// Chroma plane column that luma column `x` of 4:2:0 content maps to.
+///
+/// Unknown siting is taken as centered between two luma columns; both AV1
+/// siting codes put chroma on the even luma columns.
+#[inline(always)]
+fn chroma_x_coord(x: usize, siting: ChromaSamplePosition) -> f32 {
+    match siting {
+        ChromaSamplePosition::Unknown => (x as f32 + 0.5) * 0.5 - 0.5,
+        ChromaSamplePosition::Vertical | ChromaSamplePosition::Colocated => x as f32 * 0.5,
+    }
+}
+
+/// Chroma plane row that luma row `y` of 4:2:0 content maps to.
+///
+/// Only colocated siting puts chroma on the even luma rows.
+#[inline(always)]
+fn chroma_y_coord(y: usize, siting: ChromaSamplePosition) -> f32 {
+    match siting {
+        ChromaSamplePosition::Colocated => y as f32 * 0.5,
+        _ => (y as f32 + 0.5) * 0.5 - 0.5,
+    }
+}
+
 /// Convert YUV420 to RGB8 with bilinear chroma upsampling
 ///
 /// Automatically dispatches to the best SIMD path available:
@@ -79,14 +145,117 @@ pub fn yuv420_to_rgb8(
     range: YuvRange,
     matrix: YuvMatrix,
 ) -> ImgVec<RGB8> {
+    yuv420_to_rgb8_into(
+        vec![RGB8::default(); width * height],
+        y_plane,
+        y_stride,
+        u_plane,
+        u_stride,
+        v_plane,
+        v_stride,
+        width,
+        height,
+        range,
+        matrix,
+        ChromaSamplePosition::Unknown,
+    )
+}
+
+/// [`yuv420_to_rgb8`] writing into `out`, which must hold `width * height`
+/// pixels.
+pub(crate) fn yuv420_to_rgb8_into(
+    out: Vec<RGB8>,
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    range: YuvRange,
+    matrix: YuvMatrix,
+    siting: ChromaSamplePosition,
+) -> ImgVec<RGB8> {
+    debug_assert_eq!(out.len(), width * height);
+    // magetypes has no 128-bit x86 tier; below AVX2 the SSE4.1 strip
+    // kernel converts the whole image instead.
+    #[cfg(target_arch = "x86_64")]
+    if Desktop64::summon().is_none()
+        && let Some(token) = X64V2Token::summon()
+    {
+        let mut out = out;
+        yuv420_strip_sse41(
+            token, y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, height, 0,
+            height, range, matrix, siting, &mut out,
+        );
+        return ImgVec::new(out, width, height);
+    }
     incant!(
         yuv420_to_rgb8_inner(
-            y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, height, range, matrix
+            out, y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, height, range,
+            matrix, siting
         ),
         [v3, neon, wasm128, scalar]
     )
 }
 
+/// Convert YUV420 to RGBA8 with alpha set to 255.
+///
+/// Same colors as [`yuv420_to_rgb8`], written straight into RGBA so images
+/// with an alpha plane skip the RGB intermediate and the widening pass.
+pub fn yuv420_to_rgba8(
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    range: YuvRange,
+    matrix: YuvMatrix,
+) -> ImgVec<Rgba<u8>> {
+    yuv420_to_rgba8_into(
+        vec![Rgba::default(); width * height],
+        y_plane,
+        y_stride,
+        u_plane,
+        u_stride,
+        v_plane,
+        v_stride,
+        width,
+        height,
+        range,
+        matrix,
+        ChromaSamplePosition::Unknown,
+    )
+}
+
+/// [`yuv420_to_rgba8`] writing into `out`, which must hold `width * height`
+/// pixels.
+pub(crate) fn yuv420_to_rgba8_into(
+    mut out: Vec<Rgba<u8>>,
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    range: YuvRange,
+    matrix: YuvMatrix,
+    siting: ChromaSamplePosition,
+) -> ImgVec<Rgba<u8>> {
+    debug_assert_eq!(out.len(), width * height);
+    yuv420_strip(
+        y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, height, 0, height, range,
+        matrix, siting, &mut out,
+    );
+    ImgVec::new(out, width, height)
+}
+
 /// Generic SIMD YUV420 to RGB8 with bilinear chroma upsampling.
 ///
 /// Uses `GenericF32x8` which maps to native 256-bit on x86 (AVX2/FMA),
@@ -96,6 +265,7 @@ pub fn yuv420_to_rgb8(
 #[magetypes(v3, neon, wasm128, scalar)]
 fn yuv420_to_rgb8_inner(
     token: Token,
+    out: Vec<RGB8>,
     y_plane: &[u8],
     y_stride: usize,
     u_plane: &[u8],
@@ -106,48 +276,38 @@ fn yuv420_to_rgb8_inner(
     height: usize,
     range: YuvRange,
     matrix: YuvMatrix,
+    siting: ChromaSamplePosition,
 ) -> ImgVec<RGB8> {
+    let mut out = out;
     #[allow(non_camel_case_types)]
     type f32x8 = GenericF32x8<Token>;
     const LANES: usize = 8;
 
-    let mut out = vec![RGB8::default(); width * height];
-
-    let (kr, kb) = matrix_coefficients(matrix);
-    let kg = 1.0 - kr - kb;
+    let c = coefficients(matrix, range, 8);
 
     let chroma_width = width.div_ceil(2);
     let chroma_height = height.div_ceil(2);
 
-    // Precompute SIMD constants
-    let vr = 2.0 * (1.0 - kr);
-    let ug = -2.0 * kb * (1.0 - kb) / kg;
-    let vg = -2.0 * kr * (1.0 - kr) / kg;
-    let ub = 2.0 * (1.0 - kb);
-
-    let vr_vec = f32x8::splat(token, vr);
-    let ug_vec = f32x8::splat(token, ug);
-    let vg_vec = f32x8::splat(token, vg);
-    let ub_vec = f32x8::splat(token, ub);
+    let vr_vec = f32x8::splat(token, c.vr);
+    let ug_vec = f32x8::splat(token, c.ug);
+    let vg_vec = f32x8::splat(token, c.vg);
+    let ub_vec = f32x8::splat(token, c.ub);
     let scale_255 = f32x8::splat(token, 255.0);
     let zero = f32x8::zero(token);
     let max_val = f32x8::splat(token, 255.0);
+    let half = f32x8::splat(token, 0.5);
 
     // Range normalization constants
-    let (y_offset, y_scale, uv_center, uv_scale) = match range {
-        YuvRange::Full => (0.0f32, 1.0 / 255.0, 128.0, 1.0 / 255.0),
-        YuvRange::Limited => (16.0, 1.0 / 219.0, 128.0, 1.0 / 224.0),
-    };
-    let y_off = f32x8::splat(token, y_offset);
-    let y_sc = f32x8::splat(token, y_scale);
-    let uv_cen = f32x8::splat(token, uv_center);
-    let uv_sc = f32x8::splat(token, uv_scale);
+    let y_off = f32x8::splat(token, c.y_offset);
+    let y_sc = f32x8::splat(token, c.y_scale);
+    let uv_cen = f32x8::splat(token, c.uv_center);
+    let uv_sc = f32x8::splat(token, c.uv_scale);
 
     for y_pos in 0..height {
         let row_start = y_pos * width;
 
         // Chroma y position (same for all pixels in this row)
-        let chroma_y_raw = (y_pos as f32 + 0.5) * 0.5 - 0.5;
+        let chroma_y_raw = chroma_y_coord(y_pos, siting);
         let chroma_y = chroma_y_raw.max(0.0).min(chroma_height as f32 - 1.0);
         let cy0 = chroma_y.floor() as usize;
         let cy1 = (cy0 + 1).min(chroma_height - 1);
@@ -167,7 +327,7 @@ fn yuv420_to_rgb8_inner(
             let mut v_vals = [0f32; 8];
             for i in 0..LANES {
                 let x = x_pos + i;
-                let chroma_x_raw = (x as f32 + 0.5) * 0.5 - 0.5;
+                let chroma_x_raw = chroma_x_coord(x, siting);
                 let chroma_x = chroma_x_raw.max(0.0).min(chroma_width as f32 - 1.0);
                 let cx0 = chroma_x.floor() as usize;
                 let cx1 = (cx0 + 1).min(chroma_width - 1);
@@ -202,10 +362,10 @@ fn yuv420_to_rgb8_inner(
             let g = v_norm.mul_add(vg_vec, u_norm.mul_add(ug_vec, y_norm));
             let b = u_norm.mul_add(ub_vec, y_norm);
 
-            // Scale, clamp, round
-            let r_out = (r * scale_255).max(zero).min(max_val).round();
-            let g_out = (g * scale_255).max(zero).min(max_val).round();
-            let b_out = (b * scale_255).max(zero).min(max_val).round();
+            // Scale, clamp, round half-up (truncated by the `as u8` below)
+            let r_out = (r * scale_255).max(zero).min(max_val) + half;
+            let g_out = (g * scale_255).max(zero).min(max_val) + half;
+            let b_out = (b * scale_255).max(zero).min(max_val) + half;
 
             // Store
             let r_arr = r_out.to_array();
@@ -226,7 +386,7 @@ fn yuv420_to_rgb8_inner(
         while x_pos < width {
             let y_val = y_plane[y_pos * y_stride + x_pos] as f32;
 
-            let chroma_x_raw = (x_pos as f32 + 0.5) * 0.5 - 0.5;
+            let chroma_x_raw = chroma_x_coord(x_pos, siting);
             let chroma_x = chroma_x_raw.max(0.0).min(chroma_width as f32 - 1.0);
             let cx0 = chroma_x.floor() as usize;
             let cx1 = (cx0 + 1).min(chroma_width - 1);
@@ -246,7 +406,7 @@ fn yuv420_to_rgb8_inner(
             let v11 = v_plane[cy1 * v_stride + cx1] as f32;
             let v_val = v00 * fx1 * fy1 + v01 * fx * fy1 + v10 * fx1 * fy + v11 * fx * fy;
 
-            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, kr, kg, kb, range);
+            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, c);
             out[row_start + x_pos] = RGB8 { r, g, b };
 
             x_pos += 1;
@@ -271,9 +431,10 @@ fn bilinear_chroma_sample_x8(
     u_stride: usize,
     v_plane: &[u8],
     v_stride: usize,
+    siting: ChromaSamplePosition,
 ) -> (f32x8, f32x8) {
     // Calculate chroma y position (same for all 8 pixels in this row)
-    let chroma_y_raw = (y as f32 + 0.5) * 0.5 - 0.5;
+    let chroma_y_raw = chroma_y_coord(y, siting);
     let chroma_y = chroma_y_raw.max(0.0).min(chroma_height as f32 - 1.0);
     let cy0 = chroma_y.floor() as usize;
     let cy1 = (cy0 + 1).min(chroma_height - 1);
@@ -294,7 +455,7 @@ fn bilinear_chroma_sample_x8(
         let x = x_start + i;
 
         // Calculate chroma x position
-        let chroma_x_raw = (x as f32 + 0.5) * 0.5 - 0.5;
+        let chroma_x_raw = chroma_x_coord(x, siting);
         let chroma_x = chroma_x_raw.max(0.0).min(chroma_width as f32 - 1.0);
         let cx0 = chroma_x.floor() as usize;
         let cx1 = (cx0 + 1).min(chroma_width - 1);
@@ -329,14 +490,12 @@ fn bilinear_chroma_sample_x8(
     let fy_vec = f32x8::splat(token, fy);
     let fy1_vec = f32x8::splat(token, 1.0 - fy);
 
-    // Bilinear interpolation using FMA
-    let u_top = u01.mul_add(fx, u00 * fx1);
-    let u_bot = u11.mul_add(fx, u10 * fx1);
-    let u_result = u_bot.mul_add(fy_vec, u_top * fy1_vec);
-
-    let v_top = v01.mul_add(fx, v00 * fx1);
-    let v_bot = v11.mul_add(fx, v10 * fx1);
-    let v_result = v_bot.mul_add(fy_vec, v_top * fy1_vec);
+    // Bilinear interpolation, evaluated in the same order as the scalar
+    // path so both produce identical chroma (no FMA here on purpose).
+    let u_result =
+        u00 * fx1 * fy1_vec + u01 * fx * fy1_vec + u10 * fx1 * fy_vec + u11 * fx * fy_vec;
+    let v_result =
+        v00 * fx1 * fy1_vec + v01 * fx * fy1_vec + v10 * fx1 * fy_vec + v11 * fx * fy_vec;
 
     (u_result, v_result)
 }
@@ -354,9 +513,10 @@ fn bilinear_chroma_sample(
     u_stride: usize,
     v_plane: &[u8],
     v_stride: usize,
+    siting: ChromaSamplePosition,
 ) -> (f32, f32) {
-    let chroma_x_raw = (x as f32 + 0.5) * 0.5 - 0.5;
-    let chroma_y_raw = (y as f32 + 0.5) * 0.5 - 0.5;
+    let chroma_x_raw = chroma_x_coord(x, siting);
+    let chroma_y_raw = chroma_y_coord(y, siting);
     let chroma_x = chroma_x_raw.max(0.0).min(chroma_width as f32 - 1.0);
     let chroma_y = chroma_y_raw.max(0.0).min(chroma_height as f32 - 1.0);
 
@@ -394,43 +554,23 @@ fn yuv_to_rgb_simd(
     y: f32x8,
     u: f32x8,
     v: f32x8,
-    kr: f32,
-    kg: f32,
-    kb: f32,
-    range: YuvRange,
+    c: &YuvCoefficients,
 ) -> (f32x8, f32x8, f32x8) {
-    let (y_norm, u_norm, v_norm) = match range {
-        YuvRange::Full => {
-            let scale = f32x8::splat(token, 1.0 / 255.0);
-            let center = f32x8::splat(token, 128.0);
-            (y * scale, (u - center) * scale, (v - center) * scale)
-        }
-        YuvRange::Limited => {
-            let y_offset = f32x8::splat(token, 16.0);
-            let uv_center = f32x8::splat(token, 128.0);
-            let y_scale = f32x8::splat(token, 1.0 / 219.0);
-            let uv_scale = f32x8::splat(token, 1.0 / 224.0);
-            (
-                (y - y_offset) * y_scale,
-                (u - uv_center) * uv_scale,
-                (v - uv_center) * uv_scale,
-            )
-        }
-    };
-
-    let vr = 2.0 * (1.0 - kr);
-    let ug = -2.0 * kb * (1.0 - kb) / kg;
-    let vg = -2.0 * kr * (1.0 - kr) / kg;
-    let ub = 2.0 * (1.0 - kb);
-
-    let vr_vec = f32x8::splat(token, vr);
-    let ug_vec = f32x8::splat(token, ug);
-    let vg_vec = f32x8::splat(token, vg);
-    let ub_vec = f32x8::splat(token, ub);
+    let y_offset = f32x8::splat(token, c.y_offset);
+    let y_scale = f32x8::splat(token, c.y_scale);
+    let uv_center = f32x8::splat(token, c.uv_center);
+    let uv_scale = f32x8::splat(token, c.uv_scale);
+    let y_norm = (y - y_offset) * y_scale;
+    let u_norm = (u - uv_center) * uv_scale;
+    let v_norm = (v - uv_center) * uv_scale;
+
+    let vr_vec = f32x8::splat(token, c.vr);
+    let ug_vec = f32x8::splat(token, c.ug);
+    let vg_vec = f32x8::splat(token, c.vg);
+    let ub_vec = f32x8::splat(token, c.ub);
 
     let r = v_norm.mul_add(vr_vec, y_norm);
-    let g_temp = u_norm * ug_vec;
-    let g = v_norm.mul_add(vg_vec, g_temp + y_norm);
+    let g = v_norm.mul_add(vg_vec, u_norm.mul_add(ug_vec, y_norm));
     let b = u_norm.mul_add(ub_vec, y_norm);
 
     let scale_255 = f32x8::splat(token, 255.0);
@@ -438,7 +578,6 @@ fn yuv_to_rgb_simd(
 }
 
 /// Convert YUV422 to RGB8
-#[autoversion]
 pub fn yuv422_to_rgb8(
     y_plane: &[u8],
     y_stride: usize,
@@ -451,10 +590,41 @@ pub fn yuv422_to_rgb8(
     range: YuvRange,
     matrix: YuvMatrix,
 ) -> ImgVec<RGB8> {
-    let mut out = vec![RGB8::default(); width * height];
+    yuv422_to_rgb8_into(
+        vec![RGB8::default(); width * height],
+        y_plane,
+        y_stride,
+        u_plane,
+        u_stride,
+        v_plane,
+        v_stride,
+        width,
+        height,
+        range,
+        matrix,
+    )
+}
 
-    let (kr, kb) = matrix_coefficients(matrix);
-    let kg = 1.0 - kr - kb;
+/// [`yuv422_to_rgb8`] writing into `out`, which must hold `width * height`
+/// pixels.
+#[autoversion]
+pub(crate) fn yuv422_to_rgb8_into(
+    out: Vec<RGB8>,
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    range: YuvRange,
+    matrix: YuvMatrix,
+) -> ImgVec<RGB8> {
+    let mut out = out;
+    debug_assert_eq!(out.len(), width * height);
+
+    let c = coefficients(matrix, range, 8);
 
     for y in 0..height {
         for x in 0..width {
@@ -463,7 +633,7 @@ pub fn yuv422_to_rgb8(
             let u_val = u_plane[y * u_stride + u_x] as f32;
             let v_val = v_plane[y * v_stride + u_x] as f32;
 
-            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, kr, kg, kb, range);
+            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, c);
             out[y * width + x] = RGB8 { r, g, b };
         }
     }
@@ -471,8 +641,61 @@ pub fn yuv422_to_rgb8(
     ImgVec::new(out, width, height)
 }
 
+/// Convert YUV422 to RGBA8 with alpha set to 255.
+///
+/// Same colors as [`yuv422_to_rgb8`], written straight into RGBA so images
+/// with an alpha plane skip the RGB intermediate and the widening pass.
+pub fn yuv422_to_rgba8(
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    range: YuvRange,
+    matrix: YuvMatrix,
+) -> ImgVec<Rgba<u8>> {
+    yuv422_to_rgba8_into(
+        vec![Rgba::default(); width * height],
+        y_plane,
+        y_stride,
+        u_plane,
+        u_stride,
+        v_plane,
+        v_stride,
+        width,
+        height,
+        range,
+        matrix,
+    )
+}
+
+/// [`yuv422_to_rgba8`] writing into `out`, which must hold `width * height`
+/// pixels.
+pub(crate) fn yuv422_to_rgba8_into(
+    mut out: Vec<Rgba<u8>>,
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    range: YuvRange,
+    matrix: YuvMatrix,
+) -> ImgVec<Rgba<u8>> {
+    debug_assert_eq!(out.len(), width * height);
+    yuv422_strip_scalar(
+        y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, 0, height, range, matrix,
+        &mut out,
+    );
+    ImgVec::new(out, width, height)
+}
+
 /// Convert YUV444 to RGB8
-#[autoversion]
 pub fn yuv444_to_rgb8(
     y_plane: &[u8],
     y_stride: usize,
@@ -485,10 +708,41 @@ pub fn yuv444_to_rgb8(
     range: YuvRange,
     matrix: YuvMatrix,
 ) -> ImgVec<RGB8> {
-    let mut out = vec![RGB8::default(); width * height];
+    yuv444_to_rgb8_into(
+        vec![RGB8::default(); width * height],
+        y_plane,
+        y_stride,
+        u_plane,
+        u_stride,
+        v_plane,
+        v_stride,
+        width,
+        height,
+        range,
+        matrix,
+    )
+}
 
-    let (kr, kb) = matrix_coefficients(matrix);
-    let kg = 1.0 - kr - kb;
+/// [`yuv444_to_rgb8`] writing into `out`, which must hold `width * height`
+/// pixels.
+#[autoversion]
+pub(crate) fn yuv444_to_rgb8_into(
+    out: Vec<RGB8>,
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    range: YuvRange,
+    matrix: YuvMatrix,
+) -> ImgVec<RGB8> {
+    let mut out = out;
+    debug_assert_eq!(out.len(), width * height);
+
+    let c = coefficients(matrix, range, 8);
 
     for y in 0..height {
         for x in 0..width {
@@ -496,7 +750,7 @@ pub fn yuv444_to_rgb8(
             let u_val = u_plane[y * u_stride + x] as f32;
             let v_val = v_plane[y * v_stride + x] as f32;
 
-            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, kr, kg, kb, range);
+            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, c);
             out[y * width + x] = RGB8 { r, g, b };
         }
     }
@@ -504,16 +758,272 @@ pub fn yuv444_to_rgb8(
     ImgVec::new(out, width, height)
 }
 
+/// Convert YUV444 to RGBA8 with alpha set to 255.
+///
+/// Same colors as [`yuv444_to_rgb8`], written straight into RGBA so images
+/// with an alpha plane skip the RGB intermediate and the widening pass.
+pub fn yuv444_to_rgba8(
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    range: YuvRange,
+    matrix: YuvMatrix,
+) -> ImgVec<Rgba<u8>> {
+    yuv444_to_rgba8_into(
+        vec![Rgba::default(); width * height],
+        y_plane,
+        y_stride,
+        u_plane,
+        u_stride,
+        v_plane,
+        v_stride,
+        width,
+        height,
+        range,
+        matrix,
+    )
+}
+
+/// [`yuv444_to_rgba8`] writing into `out`, which must hold `width * height`
+/// pixels.
+pub(crate) fn yuv444_to_rgba8_into(
+    mut out: Vec<Rgba<u8>>,
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    range: YuvRange,
+    matrix: YuvMatrix,
+) -> ImgVec<Rgba<u8>> {
+    debug_assert_eq!(out.len(), width * height);
+    yuv444_strip_scalar(
+        y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, 0, height, range, matrix,
+        &mut out,
+    );
+    ImgVec::new(out, width, height)
+}
+
+/// Convert identity-matrix (GBR) samples to RGB (`channels == 3`) or
+/// opaque RGBA (`channels == 4`), written at the source bit depth.
+///
+/// Y carries green, U blue and V red; full-range samples are copied
+/// unchanged, so lossless RGB encodes come back exactly. Limited-range
+/// samples are expanded with the luma range, as libavif does. Subsampled
+/// planes (`chroma_shift` as for [`ycgco_to_rgb`]) are replicated.
+pub(crate) fn gbr_to_rgb<T: Sample>(
+    y_plane: &[T],
+    y_stride: usize,
+    u_plane: &[T],
+    u_stride: usize,
+    v_plane: &[T],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    chroma_shift: (u8, u8),
+    bit_depth: u8,
+    range: YuvRange,
+    channels: usize,
+    out: &mut [T],
+) {
+    let max = ((1u32 << bit_depth) - 1) as f32;
+    let shift = bit_depth.saturating_sub(8);
+    let (offset, scale) = match range {
+        YuvRange::Full => (0.0, 1.0),
+        YuvRange::Limited => ((16u32 << shift) as f32, max / (219u32 << shift) as f32),
+    };
+    let expand = |s: T| T::from_f32((s.to_f32() - offset) * scale, max);
+
+    for (row, out_row) in out
+        .chunks_exact_mut(width * channels)
+        .take(height)
+        .enumerate()
+    {
+        let g_row = &y_plane[row * y_stride..][..width];
+        let chroma_row = row >> chroma_shift.1;
+        let b_row = &u_plane[chroma_row * u_stride..];
+        let r_row = &v_plane[chroma_row * v_stride..];
+        for (x, px) in out_row.chunks_exact_mut(channels).enumerate() {
+            let cx = x >> chroma_shift.0;
+            px[0] = expand(r_row[cx]);
+            px[1] = expand(g_row[x]);
+            px[2] = expand(b_row[cx]);
+            if channels == 4 {
+                px[3] = T::from_f32(max, max);
+            }
+        }
+    }
+}
+
+/// Convert YCgCo samples to RGB (`channels == 3`) or opaque RGBA
+/// (`channels == 4`), written at the source bit depth like
+/// [`yuv_to_rgb16`](crate::yuv_convert_16bit::yuv_to_rgb16).
+///
+/// `chroma_shift` is the subsampling shift, `(1, 1)` for 4:2:0; chroma is
+/// replicated. YCgCo-Re and YCgCo-Ro are reversible integer transforms,
+/// defined for full range only: the RGB they recover is 2 or 1 bits
+/// shallower than the samples and is rescaled to the source bit depth, so
+/// an 8-bit image coded as 10-bit YCgCo-Re comes back exactly once reduced
+/// to 8 bits.
+pub(crate) fn ycgco_to_rgb<T: Sample>(
+    y_plane: &[T],
+    y_stride: usize,
+    u_plane: &[T],
+    u_stride: usize,
+    v_plane: &[T],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    chroma_shift: (u8, u8),
+    bit_depth: u8,
+    range: YuvRange,
+    variant: YCgCo,
+    channels: usize,
+    out: &mut [T],
+) {
+    let max = ((1u32 << bit_depth) - 1) as f32;
+    let half = (1i32 << (bit_depth - 1)) as f32;
+    let shift = bit_depth.saturating_sub(8);
+    let (y_offset, y_scale, uv_scale) = match range {
+        YuvRange::Full => (0.0, 1.0, 1.0),
+        YuvRange::Limited => (
+            (16u32 << shift) as f32,
+            max / (219u32 << shift) as f32,
+            max / (224u32 << shift) as f32,
+        ),
+    };
+    let rgb_max = match variant {
+        YCgCo::Standard => max,
+        YCgCo::Re => ((1u32 << (bit_depth - 2)) - 1) as f32,
+        YCgCo::Ro => ((1u32 << (bit_depth - 1)) - 1) as f32,
+    };
+
+    for (row, out_row) in out
+        .chunks_exact_mut(width * channels)
+        .take(height)
+        .enumerate()
+    {
+        let y_row = &y_plane[row * y_stride..][..width];
+        let chroma_row = row >> chroma_shift.1;
+        let u_row = &u_plane[chroma_row * u_stride..];
+        let v_row = &v_plane[chroma_row * v_stride..];
+        for (x, px) in out_row.chunks_exact_mut(channels).enumerate() {
+            let cx = x >> chroma_shift.0;
+            let (y, cg, co) = (
+                y_row[x].to_f32(),
+                u_row[cx].to_f32() - half,
+                v_row[cx].to_f32() - half,
+            );
+            let (r, g, b) = match variant {
+                YCgCo::Standard => {
+                    let (y, cg, co) = ((y - y_offset) * y_scale, cg * uv_scale, co * uv_scale);
+                    let t = y - cg;
+                    (t + co, y + cg, t - co)
+                }
+                YCgCo::Re | YCgCo::Ro => {
+                    // Samples are integers, so this is exact in f32.
+                    let (y, cg, co) = (y as i32, cg as i32, co as i32);
+                    let t = y - (cg >> 1);
+                    let b = t - (co >> 1);
+                    let rescale = |v: i32| v.clamp(0, rgb_max as i32) as f32 * max / rgb_max;
+                    (rescale(b + co), rescale(t + cg), rescale(b))
+                }
+            };
+            px[0] = T::from_f32(r, max);
+            px[1] = T::from_f32(g, max);
+            px[2] = T::from_f32(b, max);
+            if channels == 4 {
+                px[3] = T::from_f32(max, max);
+            }
+        }
+    }
+}
+
 /// Get matrix coefficients (Kr, Kb) for the specified color space.
 pub(crate) fn matrix_coefficients(matrix: YuvMatrix) -> (f32, f32) {
     match matrix {
         YuvMatrix::Bt601 => (0.299, 0.114),
         YuvMatrix::Bt709 => (0.2126, 0.0722),
         YuvMatrix::Bt2020 => (0.2627, 0.0593),
+        YuvMatrix::Smpte240 => (0.212, 0.087),
+        YuvMatrix::Fcc => (0.30, 0.11),
     }
 }
 
-/// Convert YUV to RGB using the given matrix coefficients
+/// Conversion constants for one matrix, range and bit depth.
+///
+/// `y_offset`/`y_scale` and `uv_center`/`uv_scale` normalise samples to
+/// `[0, 1]` luma and `[-0.5, 0.5]` chroma; `vr`, `ug`, `vg`, `ub` are the
+/// matrix terms of the formula documented on `yuv_to_rgb`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YuvCoefficients {
+    pub vr: f32,
+    pub ug: f32,
+    pub vg: f32,
+    pub ub: f32,
+    pub y_offset: f32,
+    pub y_scale: f32,
+    pub uv_center: f32,
+    pub uv_scale: f32,
+}
+
+impl YuvCoefficients {
+    /// Derive the constants from scratch. Prefer [`coefficients`], which
+    /// caches the result.
+    pub fn derive(matrix: YuvMatrix, range: YuvRange, bit_depth: u8) -> Self {
+        let (kr, kb) = matrix_coefficients(matrix);
+        let kg = 1.0 - kr - kb;
+        let shift = bit_depth - 8;
+        let (y_offset, y_scale, uv_center, uv_scale) = match range {
+            YuvRange::Full => {
+                let max = ((1u32 << bit_depth) - 1) as f32;
+                (0.0, 1.0 / max, (128u32 << shift) as f32, 1.0 / max)
+            }
+            YuvRange::Limited => (
+                (16u32 << shift) as f32,
+                1.0 / (219u32 << shift) as f32,
+                (128u32 << shift) as f32,
+                1.0 / (224u32 << shift) as f32,
+            ),
+        };
+        Self {
+            vr: 2.0 * (1.0 - kr),
+            ug: -2.0 * kb * (1.0 - kb) / kg,
+            vg: -2.0 * kr * (1.0 - kr) / kg,
+            ub: 2.0 * (1.0 - kb),
+            y_offset,
+            y_scale,
+            uv_center,
+            uv_scale,
+        }
+    }
+}
+
+/// Cached [`YuvCoefficients`] for `matrix`, `range` and `bit_depth` (8–16).
+///
+/// Each combination is derived once per process. Strip conversion asks for
+/// them once per strip, and batch decodes of many small images once per
+/// image, so setup no longer scales with the call count.
+pub fn coefficients(matrix: YuvMatrix, range: YuvRange, bit_depth: u8) -> &'static YuvCoefficients {
+    const MATRICES: usize = 5;
+    const DEPTHS: usize = 9;
+    static CACHE: [OnceLock<YuvCoefficients>; MATRICES * 2 * DEPTHS] =
+        [const { OnceLock::new() }; MATRICES * 2 * DEPTHS];
+    debug_assert!((8..=16).contains(&bit_depth), "bit depth {bit_depth}");
+    let depth = bit_depth.clamp(8, 16);
+    let index = (matrix as usize * 2 + range as usize) * DEPTHS + (depth - 8) as usize;
+    CACHE[index].get_or_init(|| YuvCoefficients::derive(matrix, range, depth))
+}
+
+/// Convert one 8-bit YUV sample to RGB with cached coefficients
 ///
 /// Formula for Full range:
 /// ```text
@@ -527,36 +1037,29 @@ pub(crate) fn matrix_coefficients(matrix: YuvMatrix) -> (f32, f32) {
 /// Vg = -2 * Kr * (1 - Kr) / Kg
 /// Ub = 2 * (1 - Kb)
 /// ```
-fn yuv_to_rgb(y: f32, u: f32, v: f32, kr: f32, kg: f32, kb: f32, range: YuvRange) -> (u8, u8, u8) {
-    let (y_norm, u_norm, v_norm) = match range {
-        YuvRange::Full => {
-            let y = y / 255.0;
-            let u = (u - 128.0) / 255.0;
-            let v = (v - 128.0) / 255.0;
-            (y, u, v)
-        }
-        YuvRange::Limited => {
-            let y = (y - 16.0) / 219.0;
-            let u = (u - 128.0) / 224.0;
-            let v = (v - 128.0) / 224.0;
-            (y, u, v)
-        }
-    };
-
-    let vr = 2.0 * (1.0 - kr);
-    let ug = -2.0 * kb * (1.0 - kb) / kg;
-    let vg = -2.0 * kr * (1.0 - kr) / kg;
-    let ub = 2.0 * (1.0 - kb);
-
-    let r = y_norm + vr * v_norm;
-    let g = y_norm + ug * u_norm + vg * v_norm;
-    let b = y_norm + ub * u_norm;
-
-    let r = (r * 255.0).round().clamp(0.0, 255.0) as u8;
-    let g = (g * 255.0).round().clamp(0.0, 255.0) as u8;
-    let b = (b * 255.0).round().clamp(0.0, 255.0) as u8;
+#[inline(always)]
+fn yuv_to_rgb(y: f32, u: f32, v: f32, c: &YuvCoefficients) -> (u8, u8, u8) {
+    // Same reciprocal constants and FMA order as the SIMD paths; see the
+    // module-level rounding contract.
+    let y_norm = (y - c.y_offset) * c.y_scale;
+    let u_norm = (u - c.uv_center) * c.uv_scale;
+    let v_norm = (v - c.uv_center) * c.uv_scale;
+
+    let r = v_norm.mul_add(c.vr, y_norm);
+    let g = v_norm.mul_add(c.vg, u_norm.mul_add(c.ug, y_norm));
+    let b = u_norm.mul_add(c.ub, y_norm);
+
+    (
+        round_half_up(r * 255.0),
+        round_half_up(g * 255.0),
+        round_half_up(b * 255.0),
+    )
+}
 
-    (r, g, b)
+/// Clamp to `[0, 255]` and round half-up, matching the SIMD `+ 0.5` / truncate.
+#[inline(always)]
+fn round_half_up(v: f32) -> u8 {
+    (v.clamp(0.0, 255.0) + 0.5) as u8
 }
 
 // ── Strip-oriented conversion ───────────────────────────────────────────────
@@ -604,47 +1107,7 @@ pub fn yuv420_to_rgb8_strip(
     matrix: YuvMatrix,
     out: &mut [RGB8],
 ) {
-    #[cfg(target_arch = "x86_64")]
-    if let Some(token) = Desktop64::summon() {
-        return yuv420_strip_simd(
-            token,
-            y_plane,
-            y_stride,
-            u_plane,
-            u_stride,
-            v_plane,
-            v_stride,
-            width,
-            total_height,
-            y_start,
-            strip_height,
-            range,
-            matrix,
-            out,
-        );
-    }
-
-    #[cfg(target_arch = "wasm32")]
-    if let Some(token) = Wasm128Token::summon() {
-        return yuv420_strip_wasm128(
-            token,
-            y_plane,
-            y_stride,
-            u_plane,
-            u_stride,
-            v_plane,
-            v_stride,
-            width,
-            total_height,
-            y_start,
-            strip_height,
-            range,
-            matrix,
-            out,
-        );
-    }
-
-    yuv420_strip_scalar(
+    yuv420_strip(
         y_plane,
         y_stride,
         u_plane,
@@ -657,6 +1120,7 @@ pub fn yuv420_to_rgb8_strip(
         strip_height,
         range,
         matrix,
+        ChromaSamplePosition::Unknown,
         out,
     );
 }
@@ -676,6 +1140,42 @@ pub fn yuv420_to_rgba8_strip(
     range: YuvRange,
     matrix: YuvMatrix,
     out: &mut [Rgba<u8>],
+) {
+    yuv420_strip(
+        y_plane,
+        y_stride,
+        u_plane,
+        u_stride,
+        v_plane,
+        v_stride,
+        width,
+        total_height,
+        y_start,
+        strip_height,
+        range,
+        matrix,
+        ChromaSamplePosition::Unknown,
+        out,
+    );
+}
+
+/// YUV420 strip conversion with chroma placed per `siting`, dispatching to
+/// the best available implementation.
+pub(crate) fn yuv420_strip<P: StripPixel>(
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    total_height: usize,
+    y_start: usize,
+    strip_height: usize,
+    range: YuvRange,
+    matrix: YuvMatrix,
+    siting: ChromaSamplePosition,
+    out: &mut [P],
 ) {
     #[cfg(target_arch = "x86_64")]
     if let Some(token) = Desktop64::summon() {
@@ -693,6 +1193,28 @@ pub fn yuv420_to_rgba8_strip(
             strip_height,
             range,
             matrix,
+            siting,
+            out,
+        );
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    if let Some(token) = X64V2Token::summon() {
+        return yuv420_strip_sse41(
+            token,
+            y_plane,
+            y_stride,
+            u_plane,
+            u_stride,
+            v_plane,
+            v_stride,
+            width,
+            total_height,
+            y_start,
+            strip_height,
+            range,
+            matrix,
+            siting,
             out,
         );
     }
@@ -713,6 +1235,7 @@ pub fn yuv420_to_rgba8_strip(
             strip_height,
             range,
             matrix,
+            siting,
             out,
         );
     }
@@ -730,6 +1253,7 @@ pub fn yuv420_to_rgba8_strip(
         strip_height,
         range,
         matrix,
+        siting,
         out,
     );
 }
@@ -877,15 +1401,16 @@ fn yuv420_strip_simd<P: StripPixel>(
     strip_height: usize,
     range: YuvRange,
     matrix: YuvMatrix,
+    siting: ChromaSamplePosition,
     out: &mut [P],
 ) {
-    let (kr, kb) = matrix_coefficients(matrix);
-    let kg = 1.0 - kr - kb;
+    let c = coefficients(matrix, range, 8);
     let chroma_width = width.div_ceil(2);
     let chroma_height = total_height.div_ceil(2);
 
     let zero = f32x8::splat(token, 0.0);
     let max_val = f32x8::splat(token, 255.0);
+    let half = f32x8::splat(token, 0.5);
 
     for row in 0..strip_height {
         let y_pos = y_start + row;
@@ -909,15 +1434,15 @@ fn yuv420_strip_simd<P: StripPixel>(
                 u_stride,
                 v_plane,
                 v_stride,
+                siting,
             );
 
             let y_vec = f32x8::from_array(token, y_vals);
-            let (r_vec, g_vec, b_vec) =
-                yuv_to_rgb_simd(token, y_vec, u_vec, v_vec, kr, kg, kb, range);
+            let (r_vec, g_vec, b_vec) = yuv_to_rgb_simd(token, y_vec, u_vec, v_vec, c);
 
-            let r_clamped = r_vec.clamp(zero, max_val).round();
-            let g_clamped = g_vec.clamp(zero, max_val).round();
-            let b_clamped = b_vec.clamp(zero, max_val).round();
+            let r_clamped = r_vec.clamp(zero, max_val) + half;
+            let g_clamped = g_vec.clamp(zero, max_val) + half;
+            let b_clamped = b_vec.clamp(zero, max_val) + half;
 
             let r_vals = r_clamped.to_array();
             let g_vals = g_clamped.to_array();
@@ -944,14 +1469,147 @@ fn yuv420_strip_simd<P: StripPixel>(
                 u_stride,
                 v_plane,
                 v_stride,
+                siting,
             );
-            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, kr, kg, kb, range);
+            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, c);
             out[out_start + x_pos] = P::from_rgb(r, g, b);
             x_pos += 1;
         }
     }
 }
 
+/// SSE4.1 strip conversion for YUV420, 4 pixels per iteration, for x86-64
+/// CPUs without AVX2. Generic over output pixel type.
+#[cfg(target_arch = "x86_64")]
+#[arcane]
+fn yuv420_strip_sse41<P: StripPixel>(
+    token: X64V2Token,
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    total_height: usize,
+    y_start: usize,
+    strip_height: usize,
+    range: YuvRange,
+    matrix: YuvMatrix,
+    siting: ChromaSamplePosition,
+    out: &mut [P],
+) {
+    let c = coefficients(matrix, range, 8);
+    let chroma_width = width.div_ceil(2);
+    let chroma_height = total_height.div_ceil(2);
+
+    let y_offset = _mm_set1_ps(c.y_offset);
+    let y_scale = _mm_set1_ps(c.y_scale);
+    let uv_center = _mm_set1_ps(c.uv_center);
+    let uv_scale = _mm_set1_ps(c.uv_scale);
+    let (vr, ug, vg, ub) = (
+        _mm_set1_ps(c.vr),
+        _mm_set1_ps(c.ug),
+        _mm_set1_ps(c.vg),
+        _mm_set1_ps(c.ub),
+    );
+    let scale_255 = _mm_set1_ps(255.0);
+    let zero = _mm_setzero_ps();
+    let half = _mm_set1_ps(0.5);
+
+    for row in 0..strip_height {
+        let y_pos = y_start + row;
+        let out_start = row * width;
+
+        let chroma_y = chroma_y_coord(y_pos, siting)
+            .max(0.0)
+            .min(chroma_height as f32 - 1.0);
+        let cy0 = chroma_y.floor() as usize;
+        let cy1 = (cy0 + 1).min(chroma_height - 1);
+        let fy = chroma_y - cy0 as f32;
+        let fy1 = 1.0 - fy;
+
+        // Same expression order as the scalar path, so chroma is identical.
+        let bilinear = |x: usize| {
+            let chroma_x = chroma_x_coord(x, siting)
+                .max(0.0)
+                .min(chroma_width as f32 - 1.0);
+            let cx0 = chroma_x.floor() as usize;
+            let cx1 = (cx0 + 1).min(chroma_width - 1);
+            let fx = chroma_x - cx0 as f32;
+            let fx1 = 1.0 - fx;
+            let sample = |plane: &[u8], stride: usize| {
+                let p00 = plane[cy0 * stride + cx0] as f32;
+                let p01 = plane[cy0 * stride + cx1] as f32;
+                let p10 = plane[cy1 * stride + cx0] as f32;
+                let p11 = plane[cy1 * stride + cx1] as f32;
+                p00 * fx1 * fy1 + p01 * fx * fy1 + p10 * fx1 * fy + p11 * fx * fy
+            };
+            (sample(u_plane, u_stride), sample(v_plane, v_stride))
+        };
+
+        let mut x_pos = 0;
+        while x_pos + 4 <= width {
+            let y_idx = y_pos * y_stride + x_pos;
+            let mut y_vals = [0f32; 4];
+            let mut u_vals = [0f32; 4];
+            let mut v_vals = [0f32; 4];
+            for i in 0..4 {
+                y_vals[i] = y_plane[y_idx + i] as f32;
+                (u_vals[i], v_vals[i]) = bilinear(x_pos + i);
+            }
+
+            let y_norm = _mm_mul_ps(_mm_sub_ps(_mm_loadu_ps(&y_vals), y_offset), y_scale);
+            let u_norm = _mm_mul_ps(_mm_sub_ps(_mm_loadu_ps(&u_vals), uv_center), uv_scale);
+            let v_norm = _mm_mul_ps(_mm_sub_ps(_mm_loadu_ps(&v_vals), uv_center), uv_scale);
+
+            let r = mul_add_sse41(token, v_norm, vr, y_norm);
+            let g = mul_add_sse41(token, v_norm, vg, mul_add_sse41(token, u_norm, ug, y_norm));
+            let b = mul_add_sse41(token, u_norm, ub, y_norm);
+
+            let finish = |v: __m128| {
+                let v = _mm_min_ps(_mm_max_ps(_mm_mul_ps(v, scale_255), zero), scale_255);
+                let mut lanes = [0f32; 4];
+                _mm_storeu_ps(&mut lanes, _mm_add_ps(v, half));
+                lanes
+            };
+            let (r_vals, g_vals, b_vals) = (finish(r), finish(g), finish(b));
+
+            for i in 0..4 {
+                out[out_start + x_pos + i] =
+                    P::from_rgb(r_vals[i] as u8, g_vals[i] as u8, b_vals[i] as u8);
+            }
+
+            x_pos += 4;
+        }
+
+        // Scalar remainder
+        while x_pos < width {
+            let y_val = y_plane[y_pos * y_stride + x_pos] as f32;
+            let (u_val, v_val) = bilinear(x_pos);
+            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, c);
+            out[out_start + x_pos] = P::from_rgb(r, g, b);
+            x_pos += 1;
+        }
+    }
+}
+
+/// `a * b + c` per lane without FMA hardware.
+///
+/// The product of two `f32`s is exact in `f64`, so the only roundings are
+/// the `f64` sum and the narrowing. Those agree with a single fused
+/// rounding unless the sum needs more than 53 bits and lands on an `f32`
+/// tie.
+#[cfg(target_arch = "x86_64")]
+#[rite]
+fn mul_add_sse41(_token: X64V2Token, a: __m128, b: __m128, c: __m128) -> __m128 {
+    let wide = |v: __m128| (_mm_cvtps_pd(v), _mm_cvtps_pd(_mm_movehl_ps(v, v)));
+    let ((a_lo, a_hi), (b_lo, b_hi), (c_lo, c_hi)) = (wide(a), wide(b), wide(c));
+    let lo = _mm_add_pd(_mm_mul_pd(a_lo, b_lo), c_lo);
+    let hi = _mm_add_pd(_mm_mul_pd(a_hi, b_hi), c_hi);
+    _mm_movelh_ps(_mm_cvtpd_ps(lo), _mm_cvtpd_ps(hi))
+}
+
 /// wasm128 SIMD strip conversion for YUV420.
 #[cfg(target_arch = "wasm32")]
 #[arcane]
@@ -969,46 +1627,32 @@ fn yuv420_strip_wasm128<P: StripPixel>(
     strip_height: usize,
     range: YuvRange,
     matrix: YuvMatrix,
+    siting: ChromaSamplePosition,
     out: &mut [P],
 ) {
-    let (kr, kb) = matrix_coefficients(matrix);
-    let kg = 1.0 - kr - kb;
+    let c = coefficients(matrix, range, 8);
     let chroma_width = width.div_ceil(2);
     let chroma_height = total_height.div_ceil(2);
 
-    let vr = 2.0 * (1.0 - kr);
-    let ug = -2.0 * kb * (1.0 - kb) / kg;
-    let vg = -2.0 * kr * (1.0 - kr) / kg;
-    let ub = 2.0 * (1.0 - kb);
-
-    let vr_vec = f32x4_splat(vr);
-    let ug_vec = f32x4_splat(ug);
-    let vg_vec = f32x4_splat(vg);
-    let ub_vec = f32x4_splat(ub);
+    let vr_vec = f32x4_splat(c.vr);
+    let ug_vec = f32x4_splat(c.ug);
+    let vg_vec = f32x4_splat(c.vg);
+    let ub_vec = f32x4_splat(c.ub);
     let scale_255 = f32x4_splat(255.0);
     let zero_v = f32x4_splat(0.0);
     let max_255 = f32x4_splat(255.0);
+    let half = f32x4_splat(0.5);
 
-    let (y_offset, y_scale, uv_center, uv_scale) = match range {
-        YuvRange::Full => (
-            f32x4_splat(0.0),
-            f32x4_splat(1.0 / 255.0),
-            f32x4_splat(128.0),
-            f32x4_splat(1.0 / 255.0),
-        ),
-        YuvRange::Limited => (
-            f32x4_splat(16.0),
-            f32x4_splat(1.0 / 219.0),
-            f32x4_splat(128.0),
-            f32x4_splat(1.0 / 224.0),
-        ),
-    };
+    let y_offset = f32x4_splat(c.y_offset);
+    let y_scale = f32x4_splat(c.y_scale);
+    let uv_center = f32x4_splat(c.uv_center);
+    let uv_scale = f32x4_splat(c.uv_scale);
 
     for row in 0..strip_height {
         let y_pos = y_start + row;
         let out_start = row * width;
 
-        let chroma_y_raw = (y_pos as f32 + 0.5) * 0.5 - 0.5;
+        let chroma_y_raw = chroma_y_coord(y_pos, siting);
         let chroma_y = chroma_y_raw.max(0.0).min(chroma_height as f32 - 1.0);
         let cy0 = chroma_y.floor() as usize;
         let cy1 = (cy0 + 1).min(chroma_height - 1);
@@ -1029,7 +1673,7 @@ fn yuv420_strip_wasm128<P: StripPixel>(
             let mut v_vals = [0f32; 4];
             for i in 0..4 {
                 let x = x_pos + i;
-                let chroma_x_raw = (x as f32 + 0.5) * 0.5 - 0.5;
+                let chroma_x_raw = chroma_x_coord(x, siting);
                 let chroma_x = chroma_x_raw.max(0.0).min(chroma_width as f32 - 1.0);
                 let cx0 = chroma_x.floor() as usize;
                 let cx1 = (cx0 + 1).min(chroma_width - 1);
@@ -1064,18 +1708,18 @@ fn yuv420_strip_wasm128<P: StripPixel>(
             );
             let b = f32x4_add(y_norm, f32x4_mul(u_norm, ub_vec));
 
-            let r_scaled = f32x4_nearest(f32x4_max(
-                f32x4_min(f32x4_mul(r, scale_255), max_255),
-                zero_v,
-            ));
-            let g_scaled = f32x4_nearest(f32x4_max(
-                f32x4_min(f32x4_mul(g, scale_255), max_255),
-                zero_v,
-            ));
-            let b_scaled = f32x4_nearest(f32x4_max(
-                f32x4_min(f32x4_mul(b, scale_255), max_255),
-                zero_v,
-            ));
+            let r_scaled = f32x4_add(
+                f32x4_max(f32x4_min(f32x4_mul(r, scale_255), max_255), zero_v),
+                half,
+            );
+            let g_scaled = f32x4_add(
+                f32x4_max(f32x4_min(f32x4_mul(g, scale_255), max_255), zero_v),
+                half,
+            );
+            let b_scaled = f32x4_add(
+                f32x4_max(f32x4_min(f32x4_mul(b, scale_255), max_255), zero_v),
+                half,
+            );
 
             for (i, lane) in [0u8, 1, 2, 3].iter().enumerate() {
                 let ri = match lane {
@@ -1105,7 +1749,7 @@ fn yuv420_strip_wasm128<P: StripPixel>(
         // Scalar remainder
         while x_pos < width {
             let y_val = y_plane[y_pos * y_stride + x_pos] as f32;
-            let chroma_x_raw = (x_pos as f32 + 0.5) * 0.5 - 0.5;
+            let chroma_x_raw = chroma_x_coord(x_pos, siting);
             let chroma_x = chroma_x_raw.max(0.0).min(chroma_width as f32 - 1.0);
             let cx0 = chroma_x.floor() as usize;
             let cx1 = (cx0 + 1).min(chroma_width - 1);
@@ -1125,7 +1769,7 @@ fn yuv420_strip_wasm128<P: StripPixel>(
             let v11 = v_plane[cy1 * v_stride + cx1] as f32;
             let v_val = v00 * fx1 * fy1 + v01 * fx * fy1 + v10 * fx1 * fy + v11 * fx * fy;
 
-            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, kr, kg, kb, range);
+            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, c);
             out[out_start + x_pos] = P::from_rgb(r, g, b);
             x_pos += 1;
         }
@@ -1148,10 +1792,10 @@ fn yuv420_strip_scalar<P: StripPixel>(
     strip_height: usize,
     range: YuvRange,
     matrix: YuvMatrix,
+    siting: ChromaSamplePosition,
     out: &mut [P],
 ) {
-    let (kr, kb) = matrix_coefficients(matrix);
-    let kg = 1.0 - kr - kb;
+    let c = coefficients(matrix, range, 8);
     let chroma_width = width.div_ceil(2);
     let chroma_height = total_height.div_ceil(2);
 
@@ -1162,8 +1806,8 @@ fn yuv420_strip_scalar<P: StripPixel>(
         for x in 0..width {
             let y_val = y_plane[y_pos * y_stride + x] as f32;
 
-            let chroma_x_raw = (x as f32 + 0.5) * 0.5 - 0.5;
-            let chroma_y_raw = (y_pos as f32 + 0.5) * 0.5 - 0.5;
+            let chroma_x_raw = chroma_x_coord(x, siting);
+            let chroma_y_raw = chroma_y_coord(y_pos, siting);
             let chroma_x = chroma_x_raw.max(0.0).min(chroma_width as f32 - 1.0);
             let chroma_y = chroma_y_raw.max(0.0).min(chroma_height as f32 - 1.0);
 
@@ -1189,7 +1833,7 @@ fn yuv420_strip_scalar<P: StripPixel>(
             let v11 = v_plane[cy1 * v_stride + cx1] as f32;
             let v_val = v00 * fx1 * fy1 + v01 * fx * fy1 + v10 * fx1 * fy + v11 * fx * fy;
 
-            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, kr, kg, kb, range);
+            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, c);
             out[out_start + x] = P::from_rgb(r, g, b);
         }
     }
@@ -1210,8 +1854,7 @@ fn yuv422_strip_scalar<P: StripPixel>(
     matrix: YuvMatrix,
     out: &mut [P],
 ) {
-    let (kr, kb) = matrix_coefficients(matrix);
-    let kg = 1.0 - kr - kb;
+    let c = coefficients(matrix, range, 8);
 
     for row in 0..strip_height {
         let y_pos = y_start + row;
@@ -1223,7 +1866,7 @@ fn yuv422_strip_scalar<P: StripPixel>(
             let u_val = u_plane[y_pos * u_stride + u_x] as f32;
             let v_val = v_plane[y_pos * v_stride + u_x] as f32;
 
-            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, kr, kg, kb, range);
+            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, c);
             out[out_start + x] = P::from_rgb(r, g, b);
         }
     }
@@ -1244,8 +1887,7 @@ fn yuv444_strip_scalar<P: StripPixel>(
     matrix: YuvMatrix,
     out: &mut [P],
 ) {
-    let (kr, kb) = matrix_coefficients(matrix);
-    let kg = 1.0 - kr - kb;
+    let c = coefficients(matrix, range, 8);
 
     for row in 0..strip_height {
         let y_pos = y_start + row;
@@ -1256,7 +1898,7 @@ fn yuv444_strip_scalar<P: StripPixel>(
             let u_val = u_plane[y_pos * u_stride + x] as f32;
             let v_val = v_plane[y_pos * v_stride + x] as f32;
 
-            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, kr, kg, kb, range);
+            let (r, g, b) = yuv_to_rgb(y_val, u_val, v_val, c);
             out[out_start + x] = P::from_rgb(r, g, b);
         }
     }
@@ -1269,25 +1911,467 @@ mod tests {
     #[test]
     fn test_yuv_to_rgb_gray() {
         // YUV (128, 128, 128) should be gray (128, 128, 128)
-        let (r, g, b) = yuv_to_rgb(128.0, 128.0, 128.0, 0.299, 0.587, 0.114, YuvRange::Full);
+        let (r, g, b) = yuv_to_rgb(128.0, 128.0, 128.0, bt601_full());
         assert_eq!(r, 128);
         assert_eq!(g, 128);
         assert_eq!(b, 128);
     }
 
+    fn bt601_full() -> &'static YuvCoefficients {
+        coefficients(YuvMatrix::Bt601, YuvRange::Full, 8)
+    }
+
+    #[test]
+    fn coefficients_are_cached_per_combination() {
+        let a = coefficients(YuvMatrix::Bt709, YuvRange::Limited, 10);
+        let b = coefficients(YuvMatrix::Bt709, YuvRange::Limited, 10);
+        assert!(core::ptr::eq(a, b));
+        assert_eq!(
+            *a,
+            YuvCoefficients::derive(YuvMatrix::Bt709, YuvRange::Limited, 10)
+        );
+        assert!(!core::ptr::eq(
+            a,
+            coefficients(YuvMatrix::Bt709, YuvRange::Full, 10)
+        ));
+        assert!(!core::ptr::eq(
+            a,
+            coefficients(YuvMatrix::Bt2020, YuvRange::Limited, 10)
+        ));
+
+        // 10-bit limited range scales the 8-bit offsets by four.
+        assert_eq!((a.y_offset, a.uv_center), (64.0, 512.0));
+        assert_eq!(a.y_scale, 1.0 / 876.0);
+
+        // 8-bit values match the constants the converters used to inline.
+        let full = bt601_full();
+        assert_eq!((full.y_offset, full.uv_center), (0.0, 128.0));
+        assert_eq!((full.y_scale, full.uv_scale), (1.0 / 255.0, 1.0 / 255.0));
+        let limited = coefficients(YuvMatrix::Bt601, YuvRange::Limited, 8);
+        assert_eq!(
+            (limited.y_scale, limited.uv_scale),
+            (1.0 / 219.0, 1.0 / 224.0)
+        );
+    }
+
     #[test]
     fn test_yuv_to_rgb_black() {
         // YUV (0, 128, 128) should be black (0, 0, 0)
-        let (r, g, b) = yuv_to_rgb(0.0, 128.0, 128.0, 0.299, 0.587, 0.114, YuvRange::Full);
+        let (r, g, b) = yuv_to_rgb(0.0, 128.0, 128.0, bt601_full());
         assert_eq!(r, 0);
         assert_eq!(g, 0);
         assert_eq!(b, 0);
     }
 
+    /// Deterministic pseudo-random planes covering the full u8 range.
+    fn planes(width: usize, height: usize, seed: u32) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 24) as u8
+        };
+        let cw = width.div_ceil(2);
+        let ch = height.div_ceil(2);
+        let y = (0..width * height).map(|_| next()).collect();
+        let u = (0..cw * ch).map(|_| next()).collect();
+        let v = (0..cw * ch).map(|_| next()).collect();
+        (y, u, v)
+    }
+
+    /// Whatever tier dispatches on this CPU, whole-image and strip output
+    /// must match the scalar implementation exactly.
+    #[test]
+    fn test_yuv420_tiers_match_scalar() {
+        for (width, height) in [(1, 1), (7, 3), (8, 2), (17, 5), (33, 9), (64, 4)] {
+            let (y, u, v) = planes(width, height, (width * 131 + height) as u32);
+            let cw = width.div_ceil(2);
+            for range in [YuvRange::Full, YuvRange::Limited] {
+                for matrix in [
+                    YuvMatrix::Bt601,
+                    YuvMatrix::Bt709,
+                    YuvMatrix::Bt2020,
+                    YuvMatrix::Smpte240,
+                    YuvMatrix::Fcc,
+                ] {
+                    let mut reference = vec![RGB8::default(); width * height];
+                    yuv420_strip_scalar(
+                        &y,
+                        width,
+                        &u,
+                        cw,
+                        &v,
+                        cw,
+                        width,
+                        height,
+                        0,
+                        height,
+                        range,
+                        matrix,
+                        ChromaSamplePosition::Unknown,
+                        &mut reference,
+                    );
+
+                    let whole =
+                        yuv420_to_rgb8(&y, width, &u, cw, &v, cw, width, height, range, matrix);
+                    assert_eq!(
+                        whole.buf(),
+                        &reference,
+                        "{width}x{height} {range:?} {matrix:?}"
+                    );
+
+                    let mut strip = vec![RGB8::default(); width * height];
+                    yuv420_to_rgb8_strip(
+                        &y, width, &u, cw, &v, cw, width, height, 0, height, range, matrix,
+                        &mut strip,
+                    );
+                    assert_eq!(
+                        strip, reference,
+                        "strip {width}x{height} {range:?} {matrix:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rgba_output_matches_rgb() {
+        let (width, height) = (19, 6);
+        let (y, u, v) = planes(width, height, 11);
+        let (range, matrix) = (YuvRange::Limited, YuvMatrix::Bt709);
+        let cw = width.div_ceil(2);
+        // Full-size chroma planes serve 4:2:2 and 4:4:4 alike.
+        let u444: Vec<u8> = y.iter().map(|&s| s.wrapping_mul(7)).collect();
+        let v444: Vec<u8> = y.iter().map(|&s| s.wrapping_mul(13)).collect();
+        let (u422, v422) = (&u444, &v444);
+
+        let pairs = [
+            (
+                yuv420_to_rgb8(&y, width, &u, cw, &v, cw, width, height, range, matrix),
+                yuv420_to_rgba8(&y, width, &u, cw, &v, cw, width, height, range, matrix),
+            ),
+            (
+                yuv422_to_rgb8(
+                    &y, width, u422, width, v422, width, width, height, range, matrix,
+                ),
+                yuv422_to_rgba8(
+                    &y, width, u422, width, v422, width, width, height, range, matrix,
+                ),
+            ),
+            (
+                yuv444_to_rgb8(
+                    &y, width, &u444, width, &v444, width, width, height, range, matrix,
+                ),
+                yuv444_to_rgba8(
+                    &y, width, &u444, width, &v444, width, width, height, range, matrix,
+                ),
+            ),
+        ];
+        for (i, (rgb, rgba)) in pairs.iter().enumerate() {
+            for (a, b) in rgb.buf().iter().zip(rgba.buf()) {
+                assert_eq!(
+                    *b,
+                    Rgba {
+                        r: a.r,
+                        g: a.g,
+                        b: a.b,
+                        a: 255
+                    },
+                    "case {i}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_yuv420_sited_tiers_match_scalar() {
+        let (width, height) = (17, 5);
+        let (y, u, v) = planes(width, height, 7);
+        let cw = width.div_ceil(2);
+        for siting in [
+            ChromaSamplePosition::Vertical,
+            ChromaSamplePosition::Colocated,
+        ] {
+            let (range, matrix) = (YuvRange::Limited, YuvMatrix::Bt709);
+            let mut reference = vec![RGB8::default(); width * height];
+            yuv420_strip_scalar(
+                &y,
+                width,
+                &u,
+                cw,
+                &v,
+                cw,
+                width,
+                height,
+                0,
+                height,
+                range,
+                matrix,
+                siting,
+                &mut reference,
+            );
+
+            let whole = yuv420_to_rgb8_into(
+                vec![RGB8::default(); width * height],
+                &y,
+                width,
+                &u,
+                cw,
+                &v,
+                cw,
+                width,
+                height,
+                range,
+                matrix,
+                siting,
+            );
+            assert_eq!(whole.buf(), &reference, "{siting:?}");
+
+            let mut strip = vec![RGB8::default(); width * height];
+            yuv420_strip(
+                &y, width, &u, cw, &v, cw, width, height, 0, height, range, matrix, siting,
+                &mut strip,
+            );
+            assert_eq!(strip, reference, "strip {siting:?}");
+        }
+    }
+
+    /// The SSE4.1 tier only dispatches without AVX2; check it directly.
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_yuv420_sse41_matches_scalar() {
+        let Some(token) = X64V2Token::summon() else {
+            return;
+        };
+        for (width, height) in [(1, 1), (7, 3), (17, 5), (33, 9)] {
+            let (y, u, v) = planes(width, height, (width * 17 + height) as u32);
+            let cw = width.div_ceil(2);
+            for siting in [
+                ChromaSamplePosition::Unknown,
+                ChromaSamplePosition::Colocated,
+            ] {
+                for matrix in [YuvMatrix::Bt601, YuvMatrix::Bt709, YuvMatrix::Bt2020] {
+                    let mut reference = vec![RGB8::default(); width * height];
+                    yuv420_strip_scalar(
+                        &y,
+                        width,
+                        &u,
+                        cw,
+                        &v,
+                        cw,
+                        width,
+                        height,
+                        0,
+                        height,
+                        YuvRange::Limited,
+                        matrix,
+                        siting,
+                        &mut reference,
+                    );
+                    let mut sse41 = vec![RGB8::default(); width * height];
+                    yuv420_strip_sse41(
+                        token,
+                        &y,
+                        width,
+                        &u,
+                        cw,
+                        &v,
+                        cw,
+                        width,
+                        height,
+                        0,
+                        height,
+                        YuvRange::Limited,
+                        matrix,
+                        siting,
+                        &mut sse41,
+                    );
+                    assert_eq!(sse41, reference, "{width}x{height} {siting:?} {matrix:?}");
+                }
+            }
+        }
+    }
+
+    /// Luma sample (2, 2) lies on chroma sample (1, 1) only when colocated;
+    /// the other sitings blend it with its neighbours.
+    #[test]
+    fn test_yuv420_chroma_siting_offsets() {
+        let y = vec![128u8; 16];
+        let u = vec![0u8, 60, 120, 180];
+        let v = vec![128u8; 4];
+        let blue_at_2_2 = |siting| {
+            let img = yuv420_to_rgb8_into(
+                vec![RGB8::default(); 16],
+                &y,
+                4,
+                &u,
+                2,
+                &v,
+                2,
+                4,
+                4,
+                YuvRange::Full,
+                YuvMatrix::Bt601,
+                siting,
+            );
+            img.buf()[2 * 4 + 2].b
+        };
+        let expected = |u: f32| yuv_to_rgb(128.0, u, 128.0, bt601_full()).2;
+
+        assert_eq!(
+            blue_at_2_2(ChromaSamplePosition::Colocated),
+            expected(180.0)
+        );
+        // Co-sited horizontally, a quarter of the way back up vertically.
+        assert_eq!(
+            blue_at_2_2(ChromaSamplePosition::Vertical),
+            expected(0.25 * 60.0 + 0.75 * 180.0)
+        );
+        // Centered on both axes: (0.75, 0.75) in chroma coordinates.
+        let centered = 0.0625 * 0.0 + 0.1875 * 60.0 + 0.1875 * 120.0 + 0.5625 * 180.0;
+        assert_eq!(
+            blue_at_2_2(ChromaSamplePosition::Unknown),
+            expected(centered)
+        );
+    }
+
+    #[test]
+    fn test_gbr_copies_full_range_planes() {
+        let (g, b, r) = ([0u16, 1023, 512], [7u16, 8, 9], [1000u16, 1, 300]);
+        let mut out = [0u16; 9];
+        gbr_to_rgb(
+            &g,
+            3,
+            &b,
+            3,
+            &r,
+            3,
+            3,
+            1,
+            (0, 0),
+            10,
+            YuvRange::Full,
+            3,
+            &mut out,
+        );
+        assert_eq!(out, [1000, 0, 7, 1, 1023, 8, 300, 512, 9]);
+
+        let mut out = [0u8; 3];
+        gbr_to_rgb(
+            &[235u8],
+            1,
+            &[16u8],
+            1,
+            &[126u8],
+            1,
+            1,
+            1,
+            (0, 0),
+            8,
+            YuvRange::Limited,
+            3,
+            &mut out,
+        );
+        assert_eq!(out, [128, 255, 0]);
+    }
+
+    /// SMPTE 240M and FCC use their own Kr/Kb rather than BT.709's and
+    /// BT.601's, so their primaries come back exactly.
+    #[test]
+    fn test_smpte240_and_fcc_primaries() {
+        for matrix in [YuvMatrix::Smpte240, YuvMatrix::Fcc] {
+            let (kr, kb) = matrix_coefficients(matrix);
+            let kg = 1.0 - kr - kb;
+            let c = coefficients(matrix, YuvRange::Full, 8);
+            for rgb in [(255, 0, 0), (0, 255, 0), (0, 0, 255), (200, 100, 40)] {
+                let (r, g, b) = (rgb.0 as f32, rgb.1 as f32, rgb.2 as f32);
+                let y = kr * r + kg * g + kb * b;
+                let u = (b - y) / (2.0 * (1.0 - kb)) + 128.0;
+                let v = (r - y) / (2.0 * (1.0 - kr)) + 128.0;
+                assert_eq!(yuv_to_rgb(y, u, v, c), rgb, "{matrix:?}");
+            }
+        }
+        assert_ne!(
+            coefficients(YuvMatrix::Fcc, YuvRange::Full, 8),
+            coefficients(YuvMatrix::Bt601, YuvRange::Full, 8)
+        );
+    }
+
+    #[test]
+    fn test_ycgco_matches_h273() {
+        // R, G, B = 200, 100, 40: Y = G/2 + (R + B)/4, Cg = G/2 - (R + B)/4,
+        // Co = (R - B)/2, chroma offset by 128.
+        let (y, u, v) = ([110u8], [118u8], [208u8]);
+        let mut out = [0u8; 3];
+        ycgco_to_rgb(
+            &y,
+            1,
+            &u,
+            1,
+            &v,
+            1,
+            1,
+            1,
+            (0, 0),
+            8,
+            YuvRange::Full,
+            YCgCo::Standard,
+            3,
+            &mut out,
+        );
+        assert_eq!(out, [200, 100, 40]);
+    }
+
+    #[test]
+    fn test_ycgco_re_is_lossless() {
+        // Forward YCgCo-Re (H.273 equations 58-61) of 8-bit RGB into 10 bits.
+        let forward = |r: i32, g: i32, b: i32| {
+            let co = r - b;
+            let t = b + (co >> 1);
+            let cg = g - t;
+            let y = t + (cg >> 1);
+            [y as u16, (cg + 512) as u16, (co + 512) as u16]
+        };
+        for (r, g, b) in [(0, 0, 0), (255, 255, 255), (255, 0, 128), (3, 250, 77)] {
+            let [y, u, v] = forward(r, g, b);
+            let mut out = [0u16; 4];
+            ycgco_to_rgb(
+                &[y],
+                1,
+                &[u],
+                1,
+                &[v],
+                1,
+                1,
+                1,
+                (0, 0),
+                10,
+                YuvRange::Full,
+                YCgCo::Re,
+                4,
+                &mut out,
+            );
+            let to8 = |v: u16| (u32::from(v) * 255 + 511) / 1023;
+            assert_eq!(
+                [to8(out[0]), to8(out[1]), to8(out[2])],
+                [r as u32, g as u32, b as u32]
+            );
+            assert_eq!(out[3], 1023);
+        }
+    }
+
+    #[test]
+    fn test_round_half_up() {
+        assert_eq!(round_half_up(0.5), 1);
+        assert_eq!(round_half_up(1.5), 2);
+        assert_eq!(round_half_up(2.5), 3);
+        assert_eq!(round_half_up(-3.0), 0);
+        assert_eq!(round_half_up(300.0), 255);
+    }
+
     #[test]
     fn test_yuv_to_rgb_white() {
         // YUV (255, 128, 128) should be white (255, 255, 255)
-        let (r, g, b) = yuv_to_rgb(255.0, 128.0, 128.0, 0.299, 0.587, 0.114, YuvRange::Full);
+        let (r, g, b) = yuv_to_rgb(255.0, 128.0, 128.0, bt601_full());
         assert_eq!(r, 255);
         assert_eq!(g, 255);
         assert_eq!(b, 255);
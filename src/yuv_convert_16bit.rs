@@ -0,0 +1,612 @@
+//! Integer YUV to RGB conversion for 10/12-bit content
+//!
+//! Converts 4:2:0, 4:2:2 and 4:4:4 planes (i010/i210/i410 and their 12-bit
+//! counterparts) to interleaved RGB or RGBA at the source bit depth. AVX2
+//! and NEON kernels and the scalar fallback share the same Q14 fixed-point
+//! math, so every tier produces identical output.
+//!
+//! Safety: All intrinsics are protected by archmage's token system.
+//! Desktop64 proves AVX2 and NeonToken proves NEON. This module uses
+//! #![forbid(unsafe_code)] - all SIMD is safe via #[arcane].
+
+#![forbid(unsafe_code)]
+#![allow(clippy::too_many_arguments)]
+
+use crate::yuv_convert::{YuvMatrix, YuvRange, coefficients};
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+use archmage::prelude::*;
+
+/// Fractional bits of the fixed-point constants.
+const SHIFT: i32 = 14;
+
+/// Fixed-point constants for one matrix, range and bit depth.
+///
+/// Each color channel is
+/// `((Y - y_offset) * y_mul + (U - uv_center) * u_term + (V - uv_center) * v_term) >> 14`,
+/// rounded by folding half a step into the luma term. Every product fits
+/// in an `i32` for depths up to 12 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Constants16 {
+    y_offset: i32,
+    y_mul: i32,
+    uv_center: i32,
+    r_v: i32,
+    g_u: i32,
+    g_v: i32,
+    b_u: i32,
+    max: i32,
+}
+
+impl Constants16 {
+    /// Quantize the float coefficients for `bit_depth` (9–12).
+    pub fn new(matrix: YuvMatrix, range: YuvRange, bit_depth: u8) -> Self {
+        debug_assert!((9..=12).contains(&bit_depth), "bit depth {bit_depth}");
+        let c = coefficients(matrix, range, bit_depth);
+        let max = ((1u32 << bit_depth) - 1) as f32;
+        let q = |v: f32| (v * max * (1 << SHIFT) as f32).round() as i32;
+        Self {
+            y_offset: c.y_offset as i32,
+            y_mul: q(c.y_scale),
+            uv_center: c.uv_center as i32,
+            r_v: q(c.vr * c.uv_scale),
+            g_u: q(c.ug * c.uv_scale),
+            g_v: q(c.vg * c.uv_scale),
+            b_u: q(c.ub * c.uv_scale),
+            max: max as i32,
+        }
+    }
+}
+
+/// Convert one pixel to `[r, g, b]` at the source bit depth.
+#[inline(always)]
+fn pixel(y: u16, u: u16, v: u16, c: &Constants16) -> [u16; 3] {
+    let y = (i32::from(y) - c.y_offset) * c.y_mul + (1 << (SHIFT - 1));
+    let (u, v) = (i32::from(u) - c.uv_center, i32::from(v) - c.uv_center);
+    let clamp = |x: i32| (x >> SHIFT).clamp(0, c.max) as u16;
+    [
+        clamp(y + v * c.r_v),
+        clamp(y + u * c.g_u + v * c.g_v),
+        clamp(y + u * c.b_u),
+    ]
+}
+
+/// Write converted pixels into an interleaved row, adding opaque alpha
+/// when `channels` is 4.
+#[inline(always)]
+fn interleave(out: &mut [u16], channels: usize, max: u16, rgb: impl Iterator<Item = [u16; 3]>) {
+    for (dst, [r, g, b]) in out.chunks_exact_mut(channels).zip(rgb) {
+        dst[0] = r;
+        dst[1] = g;
+        dst[2] = b;
+        if channels == 4 {
+            dst[3] = max;
+        }
+    }
+}
+
+/// Convert the pixels of one row from column `x` on with scalar math.
+#[inline(always)]
+fn row_tail(
+    y_row: &[u16],
+    u_row: &[u16],
+    v_row: &[u16],
+    x: usize,
+    shift_x: u8,
+    c: &Constants16,
+    channels: usize,
+    out_row: &mut [u16],
+) {
+    let pixels = (x..y_row.len()).map(|col| {
+        let cx = col >> shift_x;
+        pixel(y_row[col], u_row[cx], v_row[cx], c)
+    });
+    interleave(&mut out_row[x * channels..], channels, c.max as u16, pixels);
+}
+
+/// Convert 10/12-bit YUV to RGB (`channels == 3`) or opaque RGBA
+/// (`channels == 4`) at the source bit depth.
+///
+/// `chroma_shift` is the horizontal and vertical subsampling shift: `(1, 1)`
+/// for 4:2:0, `(1, 0)` for 4:2:2 and `(0, 0)` for 4:4:4. `out` holds
+/// `width * height * channels` samples, rows packed. Uses AVX2 or NEON when
+/// available.
+pub fn yuv_to_rgb16(
+    y_plane: &[u16],
+    y_stride: usize,
+    u_plane: &[u16],
+    u_stride: usize,
+    v_plane: &[u16],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    chroma_shift: (u8, u8),
+    bit_depth: u8,
+    range: YuvRange,
+    matrix: YuvMatrix,
+    channels: usize,
+    out: &mut [u16],
+) {
+    let c = Constants16::new(matrix, range, bit_depth);
+
+    #[cfg(target_arch = "x86_64")]
+    if let Some(token) = Desktop64::summon() {
+        yuv_to_rgb16_avx2(
+            token,
+            y_plane,
+            y_stride,
+            u_plane,
+            u_stride,
+            v_plane,
+            v_stride,
+            width,
+            height,
+            chroma_shift,
+            &c,
+            channels,
+            out,
+        );
+        return;
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    if let Some(token) = NeonToken::summon() {
+        yuv_to_rgb16_neon(
+            token,
+            y_plane,
+            y_stride,
+            u_plane,
+            u_stride,
+            v_plane,
+            v_stride,
+            width,
+            height,
+            chroma_shift,
+            &c,
+            channels,
+            out,
+        );
+        return;
+    }
+
+    yuv_to_rgb16_scalar(
+        y_plane,
+        y_stride,
+        u_plane,
+        u_stride,
+        v_plane,
+        v_stride,
+        width,
+        height,
+        chroma_shift,
+        &c,
+        channels,
+        out,
+    );
+}
+
+/// Scalar reference for [`yuv_to_rgb16`].
+pub fn yuv_to_rgb16_scalar(
+    y_plane: &[u16],
+    y_stride: usize,
+    u_plane: &[u16],
+    u_stride: usize,
+    v_plane: &[u16],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    chroma_shift: (u8, u8),
+    c: &Constants16,
+    channels: usize,
+    out: &mut [u16],
+) {
+    for (row, out_row) in out
+        .chunks_exact_mut(width * channels)
+        .take(height)
+        .enumerate()
+    {
+        let cy = row >> chroma_shift.1;
+        row_tail(
+            &y_plane[row * y_stride..][..width],
+            &u_plane[cy * u_stride..],
+            &v_plane[cy * v_stride..],
+            0,
+            chroma_shift.0,
+            c,
+            channels,
+            out_row,
+        );
+    }
+}
+
+// ============================================================================
+// AVX2 implementation
+// ============================================================================
+
+/// Convert with AVX2, 16 pixels per iteration.
+///
+/// Safety: Token-gated via #[arcane] - all SIMD operations are safe
+#[cfg(target_arch = "x86_64")]
+#[arcane]
+pub fn yuv_to_rgb16_avx2(
+    token: Desktop64,
+    y_plane: &[u16],
+    y_stride: usize,
+    u_plane: &[u16],
+    u_stride: usize,
+    v_plane: &[u16],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    chroma_shift: (u8, u8),
+    c: &Constants16,
+    channels: usize,
+    out: &mut [u16],
+) {
+    let subsampled = chroma_shift.0 == 1;
+    for (row, out_row) in out
+        .chunks_exact_mut(width * channels)
+        .take(height)
+        .enumerate()
+    {
+        let cy = row >> chroma_shift.1;
+        let y_row = &y_plane[row * y_stride..][..width];
+        let u_row = &u_plane[cy * u_stride..];
+        let v_row = &v_plane[cy * v_stride..];
+
+        let mut x = 0;
+        while x + 16 <= width {
+            let cx = x >> chroma_shift.0;
+            let [r, g, b] = process_16_pixels_avx2(
+                token,
+                y_row[x..x + 16].try_into().unwrap(),
+                &u_row[cx..],
+                &v_row[cx..],
+                subsampled,
+                c,
+            );
+            let pixels = (0..16).map(|i| [r[i], g[i], b[i]]);
+            interleave(
+                &mut out_row[x * channels..(x + 16) * channels],
+                channels,
+                c.max as u16,
+                pixels,
+            );
+            x += 16;
+        }
+        row_tail(y_row, u_row, v_row, x, chroma_shift.0, c, channels, out_row);
+    }
+}
+
+/// Load 16 chroma samples as two i32x8 halves, duplicating each of the
+/// first eight when `subsampled`.
+#[cfg(target_arch = "x86_64")]
+#[rite]
+fn load_chroma_avx2(_token: Desktop64, chroma: &[u16], subsampled: bool) -> (__m256i, __m256i) {
+    let lo: &[u16; 8] = chroma[..8].try_into().unwrap();
+    let lo = _mm_loadu_si128(lo);
+    if subsampled {
+        (
+            _mm256_cvtepu16_epi32(_mm_unpacklo_epi16(lo, lo)),
+            _mm256_cvtepu16_epi32(_mm_unpackhi_epi16(lo, lo)),
+        )
+    } else {
+        let hi: &[u16; 8] = chroma[8..16].try_into().unwrap();
+        (
+            _mm256_cvtepu16_epi32(lo),
+            _mm256_cvtepu16_epi32(_mm_loadu_si128(hi)),
+        )
+    }
+}
+
+/// Convert 16 pixels, returning the R, G and B planes.
+///
+/// Safety: Token proves AVX2 is available. #[rite] enables target_feature,
+/// making all intrinsics safe to call without unsafe blocks (Rust 1.85+).
+#[cfg(target_arch = "x86_64")]
+#[rite]
+fn process_16_pixels_avx2(
+    token: Desktop64,
+    y: &[u16; 16],
+    u: &[u16],
+    v: &[u16],
+    subsampled: bool,
+    c: &Constants16,
+) -> [[u16; 16]; 3] {
+    let y_lo: &[u16; 8] = y[..8].try_into().unwrap();
+    let y_hi: &[u16; 8] = y[8..].try_into().unwrap();
+    let ys = [
+        _mm256_cvtepu16_epi32(_mm_loadu_si128(y_lo)),
+        _mm256_cvtepu16_epi32(_mm_loadu_si128(y_hi)),
+    ];
+    let (u_lo, u_hi) = load_chroma_avx2(token, u, subsampled);
+    let (v_lo, v_hi) = load_chroma_avx2(token, v, subsampled);
+
+    let y_offset = _mm256_set1_epi32(c.y_offset);
+    let y_mul = _mm256_set1_epi32(c.y_mul);
+    let round = _mm256_set1_epi32(1 << (SHIFT - 1));
+    let center = _mm256_set1_epi32(c.uv_center);
+    let (r_v, g_u) = (_mm256_set1_epi32(c.r_v), _mm256_set1_epi32(c.g_u));
+    let (g_v, b_u) = (_mm256_set1_epi32(c.g_v), _mm256_set1_epi32(c.b_u));
+    let zero = _mm256_setzero_si256();
+    let max = _mm256_set1_epi32(c.max);
+
+    let mut rgb = [[zero; 2]; 3];
+    for (half, (y, u, v)) in [(ys[0], u_lo, v_lo), (ys[1], u_hi, v_hi)]
+        .into_iter()
+        .enumerate()
+    {
+        let y = _mm256_add_epi32(
+            _mm256_mullo_epi32(_mm256_sub_epi32(y, y_offset), y_mul),
+            round,
+        );
+        let u = _mm256_sub_epi32(u, center);
+        let v = _mm256_sub_epi32(v, center);
+        let channels = [
+            _mm256_add_epi32(y, _mm256_mullo_epi32(v, r_v)),
+            _mm256_add_epi32(
+                y,
+                _mm256_add_epi32(_mm256_mullo_epi32(u, g_u), _mm256_mullo_epi32(v, g_v)),
+            ),
+            _mm256_add_epi32(y, _mm256_mullo_epi32(u, b_u)),
+        ];
+        for (out, x) in rgb.iter_mut().zip(channels) {
+            let x = _mm256_srai_epi32::<SHIFT>(x);
+            out[half] = _mm256_min_epi32(_mm256_max_epi32(x, zero), max);
+        }
+    }
+
+    rgb.map(|[lo, hi]| {
+        // packus interleaves the 128-bit lanes; restore pixel order.
+        let packed = _mm256_permute4x64_epi64::<0b11_01_10_00>(_mm256_packus_epi32(lo, hi));
+        let mut samples = [0u16; 16];
+        _mm256_storeu_si256(&mut samples, packed);
+        samples
+    })
+}
+
+// ============================================================================
+// NEON (aarch64) implementation
+// ============================================================================
+
+/// Convert with NEON, 8 pixels per iteration, storing with interleaving
+/// `vst3q`/`vst4q`.
+///
+/// Safety: Token-gated via #[arcane] - all SIMD operations are safe
+#[cfg(target_arch = "aarch64")]
+#[arcane]
+pub fn yuv_to_rgb16_neon(
+    token: NeonToken,
+    y_plane: &[u16],
+    y_stride: usize,
+    u_plane: &[u16],
+    u_stride: usize,
+    v_plane: &[u16],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    chroma_shift: (u8, u8),
+    c: &Constants16,
+    channels: usize,
+    out: &mut [u16],
+) {
+    let subsampled = chroma_shift.0 == 1;
+    let alpha = vdupq_n_u16(c.max as u16);
+    for (row, out_row) in out
+        .chunks_exact_mut(width * channels)
+        .take(height)
+        .enumerate()
+    {
+        let cy = row >> chroma_shift.1;
+        let y_row = &y_plane[row * y_stride..][..width];
+        let u_row = &u_plane[cy * u_stride..];
+        let v_row = &v_plane[cy * v_stride..];
+
+        let mut x = 0;
+        while x + 8 <= width {
+            let cx = x >> chroma_shift.0;
+            let (r, g, b) = process_8_pixels_neon(
+                token,
+                y_row[x..x + 8].try_into().unwrap(),
+                &u_row[cx..],
+                &v_row[cx..],
+                subsampled,
+                c,
+            );
+            let dst = &mut out_row[x * channels..(x + 8) * channels];
+            if channels == 4 {
+                vst4q_u16(dst.try_into().unwrap(), uint16x8x4_t(r, g, b, alpha));
+            } else {
+                vst3q_u16(dst.try_into().unwrap(), uint16x8x3_t(r, g, b));
+            }
+            x += 8;
+        }
+        row_tail(y_row, u_row, v_row, x, chroma_shift.0, c, channels, out_row);
+    }
+}
+
+/// Load 8 chroma samples as two i32x4 halves, duplicating each of the
+/// first four when `subsampled`.
+#[cfg(target_arch = "aarch64")]
+#[rite]
+fn load_chroma_neon(_token: NeonToken, chroma: &[u16], subsampled: bool) -> (int32x4_t, int32x4_t) {
+    let (lo, hi) = if subsampled {
+        let quad: &[u16; 4] = chroma[..4].try_into().unwrap();
+        let quad = vld1_u16(quad);
+        (vzip1_u16(quad, quad), vzip2_u16(quad, quad))
+    } else {
+        let oct: &[u16; 8] = chroma[..8].try_into().unwrap();
+        let oct = vld1q_u16(oct);
+        (vget_low_u16(oct), vget_high_u16(oct))
+    };
+    (
+        vreinterpretq_s32_u32(vmovl_u16(lo)),
+        vreinterpretq_s32_u32(vmovl_u16(hi)),
+    )
+}
+
+/// Convert 8 pixels, returning the R, G and B vectors.
+///
+/// Safety: Token proves NEON is available. #[rite] enables target_feature,
+/// making all intrinsics safe to call without unsafe blocks.
+#[cfg(target_arch = "aarch64")]
+#[rite]
+fn process_8_pixels_neon(
+    token: NeonToken,
+    y: &[u16; 8],
+    u: &[u16],
+    v: &[u16],
+    subsampled: bool,
+    c: &Constants16,
+) -> (uint16x8_t, uint16x8_t, uint16x8_t) {
+    let y = vld1q_u16(y);
+    let ys = [
+        vreinterpretq_s32_u32(vmovl_u16(vget_low_u16(y))),
+        vreinterpretq_s32_u32(vmovl_u16(vget_high_u16(y))),
+    ];
+    let (u_lo, u_hi) = load_chroma_neon(token, u, subsampled);
+    let (v_lo, v_hi) = load_chroma_neon(token, v, subsampled);
+
+    let y_offset = vdupq_n_s32(c.y_offset);
+    let y_mul = vdupq_n_s32(c.y_mul);
+    let round = vdupq_n_s32(1 << (SHIFT - 1));
+    let center = vdupq_n_s32(c.uv_center);
+    let max = vdupq_n_s32(c.max);
+
+    let mut rgb = [[vdup_n_u16(0); 2]; 3];
+    for (half, (y, u, v)) in [(ys[0], u_lo, v_lo), (ys[1], u_hi, v_hi)]
+        .into_iter()
+        .enumerate()
+    {
+        let y = vmlaq_s32(round, vsubq_s32(y, y_offset), y_mul);
+        let u = vsubq_s32(u, center);
+        let v = vsubq_s32(v, center);
+        let channels = [
+            vmlaq_n_s32(y, v, c.r_v),
+            vmlaq_n_s32(vmlaq_n_s32(y, u, c.g_u), v, c.g_v),
+            vmlaq_n_s32(y, u, c.b_u),
+        ];
+        for (out, x) in rgb.iter_mut().zip(channels) {
+            // The saturating narrow clamps below zero.
+            out[half] = vqmovun_s32(vminq_s32(vshrq_n_s32::<SHIFT>(x), max));
+        }
+    }
+
+    let [r, g, b] = rgb.map(|[lo, hi]| vcombine_u16(lo, hi));
+    (r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Planes with every sample drawn from a fixed pseudo-random sequence.
+    fn planes(width: usize, height: usize, depth: u8) -> [Vec<u16>; 3] {
+        let mut state = 0x2545_f491u32;
+        let mut plane = |len: usize| {
+            (0..len)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 17;
+                    state ^= state << 5;
+                    (state >> 8) as u16 & ((1 << depth) - 1)
+                })
+                .collect::<Vec<_>>()
+        };
+        [
+            plane(width * height),
+            plane(width * height),
+            plane(width * height),
+        ]
+    }
+
+    /// Every tier must match the scalar math exactly, for every subsampling,
+    /// channel count and row tail length.
+    #[test]
+    fn simd_matches_scalar() {
+        let height = 5;
+        for width in [1, 15, 16, 33, 67] {
+            for depth in [10, 12] {
+                let [y, u, v] = planes(width, height, depth);
+                for shift in [(1, 1), (1, 0), (0, 0)] {
+                    for range in [YuvRange::Full, YuvRange::Limited] {
+                        let c = Constants16::new(YuvMatrix::Bt709, range, depth);
+                        for channels in [3, 4] {
+                            let mut expected = vec![0u16; width * height * channels];
+                            yuv_to_rgb16_scalar(
+                                &y,
+                                width,
+                                &u,
+                                width,
+                                &v,
+                                width,
+                                width,
+                                height,
+                                shift,
+                                &c,
+                                channels,
+                                &mut expected,
+                            );
+                            let mut actual = vec![0u16; width * height * channels];
+                            yuv_to_rgb16(
+                                &y,
+                                width,
+                                &u,
+                                width,
+                                &v,
+                                width,
+                                width,
+                                height,
+                                shift,
+                                depth,
+                                range,
+                                YuvMatrix::Bt709,
+                                channels,
+                                &mut actual,
+                            );
+                            assert_eq!(actual, expected, "{width}x{height} {shift:?} {range:?}");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The fixed-point math stays within one code value of the float
+    /// coefficients.
+    #[test]
+    fn scalar_matches_float_reference() {
+        for matrix in [
+            YuvMatrix::Bt601,
+            YuvMatrix::Bt709,
+            YuvMatrix::Bt2020,
+            YuvMatrix::Smpte240,
+            YuvMatrix::Fcc,
+        ] {
+            for range in [YuvRange::Full, YuvRange::Limited] {
+                for depth in [10u8, 12] {
+                    let c = Constants16::new(matrix, range, depth);
+                    let f = coefficients(matrix, range, depth);
+                    let max = ((1u32 << depth) - 1) as f32;
+                    let step = (1u16 << depth) / 16;
+                    for (y, u, v) in (0..16)
+                        .flat_map(|i| (0..16).flat_map(move |j| (0..16).map(move |k| (i, j, k))))
+                    {
+                        let (y, u, v) = (y * step + 3, u * step + 1, v * step + 7);
+                        let yn = (f32::from(y) - f.y_offset) * f.y_scale;
+                        let un = (f32::from(u) - f.uv_center) * f.uv_scale;
+                        let vn = (f32::from(v) - f.uv_center) * f.uv_scale;
+                        let float = [yn + f.vr * vn, yn + f.ug * un + f.vg * vn, yn + f.ub * un]
+                            .map(|x| (x * max).round().clamp(0.0, max));
+                        let fixed = pixel(y, u, v, &c);
+                        for (a, b) in fixed.into_iter().zip(float) {
+                            assert!(
+                                (f32::from(a) - b).abs() <= 1.0,
+                                "{matrix:?} {range:?} {depth} ({y}, {u}, {v}): {fixed:?} vs {float:?}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
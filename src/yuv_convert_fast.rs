@@ -2,9 +2,20 @@
 //!
 //! Key optimizations:
 //! - Fixed-point integer math (much faster than float)
-//! - Process 32 pixels at once (AVX2) or 16 pixels (NEON)
+//! - Process 64 pixels at once (AVX-512BW), 32 (AVX2), 16 (SSE4.1, NEON)
+//!   or a hardware-sized vector (RVV)
 //! - Process 2 rows simultaneously for YUV420
-//! - Use AVX2/NEON intrinsics for proper SIMD vectorization
+//! - Use AVX2/SSE4.1/NEON intrinsics for proper SIMD vectorization
+//!
+//! # Rounding contract
+//!
+//! Every tier produces bit-identical output. Samples are widened to 10 bits
+//! by byte replication (`(v << 2) | (v >> 6)`), multiplied with
+//! `pmulhrsw` semantics — `(a * b + 0x4000) >> 15` — summed with wrapping
+//! i16 arithmetic and saturated to `[0, 255]`. NEON's `vqrdmulhq_s16` is the
+//! same operation for all inputs reachable here. Scalar code (remainder
+//! columns and [`yuv420_to_rgb8_fast_scalar`]) goes through
+//! [`yuv_to_rgb_fixed`], which implements exactly these steps.
 
 // These unsafe fn helpers use SIMD intrinsics that are safe within target_feature context.
 #![allow(unsafe_op_in_unsafe_fn)]
@@ -15,6 +26,137 @@ use archmage::prelude::*;
 use imgref::ImgVec;
 use rgb::RGB8;
 
+// BT.709 coefficients in fixed-point (Q13 format: 8192 = 1.0), shared by
+// every tier so they cannot drift apart.
+const Y_COEF: i16 = 9539; // 1.164 * 8192
+const CR_COEF: i16 = 13075; // 1.596 * 8192
+const CB_COEF: i16 = 16525; // 2.018 * 8192
+const G_COEF_1: i16 = 6660; // For V component (formula subtracts this)
+const G_COEF_2: i16 = 3209; // For U component (formula subtracts this)
+const Y_BIAS: i16 = 16;
+const UV_BIAS: i16 = 128;
+
+/// Scalar `pmulhrsw`: rounding multiply-high of two Q15 values.
+#[inline(always)]
+fn mulhrs(a: i16, b: i16) -> i16 {
+    ((a as i32 * b as i32 + 0x4000) >> 15) as i16
+}
+
+/// Widen an 8-bit sample to the 10-bit representation used by the SIMD paths.
+#[inline(always)]
+fn expand_to_10bit(v: u8) -> i16 {
+    (((v as u16) << 8 | v as u16) >> 6) as i16
+}
+
+/// Convert one pixel following the fast-path rounding contract.
+///
+/// Bit-exact with the AVX2, SSE4.1, NEON and RVV kernels for every input.
+#[inline(always)]
+pub(crate) fn yuv_to_rgb_fixed(y: u8, u: u8, v: u8) -> RGB8 {
+    let uv_corr = (UV_BIAS << 2) | (UV_BIAS >> 6);
+    let y = expand_to_10bit(y.saturating_sub(Y_BIAS as u8));
+    let u = expand_to_10bit(u).wrapping_sub(uv_corr);
+    let v = expand_to_10bit(v).wrapping_sub(uv_corr);
+
+    let y_scaled = mulhrs(y, Y_COEF);
+    let r = y_scaled.wrapping_add(mulhrs(v, CR_COEF));
+    let b = y_scaled.wrapping_add(mulhrs(u, CB_COEF));
+    let g = y_scaled.wrapping_sub(mulhrs(v, G_COEF_1).wrapping_add(mulhrs(u, G_COEF_2)));
+
+    RGB8 {
+        r: r.clamp(0, 255) as u8,
+        g: g.clamp(0, 255) as u8,
+        b: b.clamp(0, 255) as u8,
+    }
+}
+
+/// Fast YUV420 to RGB8, portable scalar reference.
+///
+/// Produces the same bytes as [`yuv420_to_rgb8_fast`],
+/// [`yuv420_to_rgb8_fast_sse41`] and [`yuv420_to_rgb8_fast_neon`]; used on
+/// CPUs without those tiers and as the oracle for tier-equivalence tests.
+pub fn yuv420_to_rgb8_fast_scalar(
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+) -> ImgVec<RGB8> {
+    let mut out = vec![RGB8::default(); width * height];
+    for row in 0..height {
+        let chroma_row = row / 2;
+        for px in 0..width {
+            let chroma_x = px / 2;
+            out[row * width + px] = yuv_to_rgb_fixed(
+                y_plane[row * y_stride + px],
+                u_plane[chroma_row * u_stride + chroma_x],
+                v_plane[chroma_row * v_stride + chroma_x],
+            );
+        }
+    }
+    ImgVec::new(out, width, height)
+}
+
+/// Fast YUV420 to RGB8 on the best tier this CPU supports.
+///
+/// Picks AVX-512BW (with the `avx512` feature), AVX2 or SSE4.1 on x86_64,
+/// NEON on aarch64, RVV on riscv64 (with the `riscv-v` feature) and the
+/// scalar reference elsewhere, so callers get the
+/// fixed-point speedup on Apple Silicon and Graviton without summoning
+/// tokens themselves. All tiers produce the same bytes.
+pub fn yuv420_to_rgb8_fast_auto(
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+) -> ImgVec<RGB8> {
+    #[cfg(all(target_arch = "x86_64", feature = "avx512"))]
+    if let Some(token) = X64V4Token::summon() {
+        return yuv420_to_rgb8_fast_avx512(
+            token, y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, height,
+        );
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    if let Some(token) = Desktop64::summon() {
+        return yuv420_to_rgb8_fast(
+            token, y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, height,
+        );
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    if let Some(token) = X64V2Token::summon() {
+        return yuv420_to_rgb8_fast_sse41(
+            token, y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, height,
+        );
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    if let Some(token) = NeonToken::summon() {
+        return yuv420_to_rgb8_fast_neon(
+            token, y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, height,
+        );
+    }
+
+    #[cfg(all(target_arch = "riscv64", feature = "riscv-v"))]
+    if crate::simd::rvv_available() {
+        return yuv420_to_rgb8_fast_rvv(
+            y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, height,
+        );
+    }
+
+    yuv420_to_rgb8_fast_scalar(
+        y_plane, y_stride, u_plane, u_stride, v_plane, v_stride, width, height,
+    )
+}
+
 /// Fast YUV420 to RGB8 using integer arithmetic (AVX2 path)
 #[cfg(target_arch = "x86_64")]
 #[arcane]
@@ -31,17 +173,9 @@ pub fn yuv420_to_rgb8_fast(
 ) -> ImgVec<RGB8> {
     let mut out = vec![RGB8::default(); width * height];
 
-    // BT.709 coefficients in fixed-point (Q13 format: 8192 = 1.0)
-    // Values from yuv crate for BT.709 full range
-    let y_coef: i16 = 9539; // 1.164 * 8192
-    let cr_coef: i16 = 13075; // 1.596 * 8192
-    let cb_coef: i16 = 16525; // 2.018 * 8192
-    let g_coef_1: i16 = 6660; // For U component (formula subtracts this)
-    let g_coef_2: i16 = 3209; // For V component (formula subtracts this)
-
-    // Bias values
-    let y_bias: i16 = 16;
-    let uv_bias: i16 = 128;
+    let (y_coef, cr_coef, cb_coef) = (Y_COEF, CR_COEF, CB_COEF);
+    let (g_coef_1, g_coef_2) = (G_COEF_1, G_COEF_2);
+    let (y_bias, uv_bias) = (Y_BIAS, UV_BIAS);
 
     // Process 2 rows at a time for YUV420
     for y in (0..height).step_by(2) {
@@ -53,7 +187,9 @@ pub fn yuv420_to_rgb8_fast(
         for x in (0..width).step_by(32) {
             let pixels_remaining = (width - x).min(32);
 
-            if pixels_remaining < 32 {
+            // A trailing odd row has no partner row to pair with; the scalar
+            // path handles it without splitting the output buffer.
+            if pixels_remaining < 32 || y1_row == y0_row {
                 // Handle remaining pixels with scalar code
                 for i in 0..pixels_remaining {
                     for row in [y0_row, y1_row] {
@@ -63,23 +199,11 @@ pub fn yuv420_to_rgb8_fast(
                         let px = x + i;
                         let chroma_x = px / 2;
 
-                        let y_val = y_plane[row * y_stride + px] as i32 - y_bias as i32;
-                        let u_val =
-                            u_plane[chroma_row * u_stride + chroma_x] as i32 - uv_bias as i32;
-                        let v_val =
-                            v_plane[chroma_row * v_stride + chroma_x] as i32 - uv_bias as i32;
-
-                        let y_scaled = (y_val * y_coef as i32) >> 13;
-                        let r = y_scaled + ((v_val * cr_coef as i32) >> 13);
-                        let g =
-                            y_scaled - ((v_val * g_coef_1 as i32 + u_val * g_coef_2 as i32) >> 13);
-                        let b = y_scaled + ((u_val * cb_coef as i32) >> 13);
-
-                        out[row * width + px] = RGB8 {
-                            r: r.clamp(0, 255) as u8,
-                            g: g.clamp(0, 255) as u8,
-                            b: b.clamp(0, 255) as u8,
-                        };
+                        out[row * width + px] = yuv_to_rgb_fixed(
+                            y_plane[row * y_stride + px],
+                            u_plane[chroma_row * u_stride + chroma_x],
+                            v_plane[chroma_row * v_stride + chroma_x],
+                        );
                     }
                 }
                 continue;
@@ -354,6 +478,296 @@ unsafe fn interleave_rgb_avx2(r: __m256i, g: __m256i, b: __m256i) -> (__m256i, _
     (rgb0, rgb1, rgb2)
 }
 
+// ============================================================================
+// AVX-512BW implementation
+// ============================================================================
+
+/// Fast YUV420 to RGB8 using integer arithmetic (AVX-512BW path)
+///
+/// Same math as the AVX2 path on 512-bit registers: two rows of 64 pixels
+/// per iteration. Worth it on Ice Lake, Sapphire Rapids and Zen 4, where
+/// 512-bit integer ops don't lower the clock.
+#[cfg(all(target_arch = "x86_64", feature = "avx512"))]
+#[arcane]
+pub fn yuv420_to_rgb8_fast_avx512(
+    token: X64V4Token,
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+) -> ImgVec<RGB8> {
+    let mut out = vec![RGB8::default(); width * height];
+
+    for y in (0..height).step_by(2) {
+        let y0_row = y;
+        let y1_row = (y + 1).min(height - 1);
+        let chroma_row = y / 2;
+
+        let mut x = 0;
+        // A trailing odd row has no partner row; leave it to the scalar loop.
+        if y1_row != y0_row {
+            while x + 64 <= width {
+                let (top_rows, bottom_rows) = out.split_at_mut(y1_row * width);
+                process_64_pixels_420_avx512(
+                    token,
+                    &y_plane[y0_row * y_stride + x..][..64],
+                    &y_plane[y1_row * y_stride + x..][..64],
+                    &u_plane[chroma_row * u_stride + x / 2..][..32],
+                    &v_plane[chroma_row * v_stride + x / 2..][..32],
+                    &mut top_rows[y0_row * width + x..][..64],
+                    &mut bottom_rows[x..][..64],
+                );
+                x += 64;
+            }
+        }
+
+        for px in x..width {
+            for row in [y0_row, y1_row] {
+                out[row * width + px] = yuv_to_rgb_fixed(
+                    y_plane[row * y_stride + px],
+                    u_plane[chroma_row * u_stride + px / 2],
+                    v_plane[chroma_row * v_stride + px / 2],
+                );
+            }
+        }
+    }
+
+    ImgVec::new(out, width, height)
+}
+
+/// Convert 64 pixels from each of two rows sharing one chroma row.
+///
+/// `y0` and `y1` hold 64 luma samples, `u` and `v` 32 chroma samples.
+#[cfg(all(target_arch = "x86_64", feature = "avx512"))]
+#[rite]
+fn process_64_pixels_420_avx512(
+    _token: X64V4Token,
+    y0: &[u8],
+    y1: &[u8],
+    u: &[u8],
+    v: &[u8],
+    out0: &mut [RGB8],
+    out1: &mut [RGB8],
+) {
+    let y0_arr: &[u8; 64] = y0.try_into().unwrap();
+    let y1_arr: &[u8; 64] = y1.try_into().unwrap();
+    let u_arr: &[u8; 32] = u.try_into().unwrap();
+    let v_arr: &[u8; 32] = v.try_into().unwrap();
+    let y0_vals = safe_unaligned_simd::x86_64::_mm512_loadu_si512(y0_arr);
+    let y1_vals = safe_unaligned_simd::x86_64::_mm512_loadu_si512(y1_arr);
+    let u_vals = safe_unaligned_simd::x86_64::_mm256_loadu_si256(u_arr);
+    let v_vals = safe_unaligned_simd::x86_64::_mm256_loadu_si256(v_arr);
+
+    let y_corr = _mm512_set1_epi8(Y_BIAS as i8);
+    let uv_corr = _mm512_set1_epi16((UV_BIAS << 2) | (UV_BIAS >> 6));
+    let y_coef = _mm512_set1_epi16(Y_COEF);
+    let cr_coef = _mm512_set1_epi16(CR_COEF);
+    let cb_coef = _mm512_set1_epi16(CB_COEF);
+    let g_coef_1 = _mm512_set1_epi16(G_COEF_1);
+    let g_coef_2 = _mm512_set1_epi16(G_COEF_2);
+
+    let y0_sub = _mm512_subs_epu8(y0_vals, y_corr);
+    let y1_sub = _mm512_subs_epu8(y1_vals, y_corr);
+
+    // Each chroma byte zero-extended to a word and copied into its high byte
+    // gives the 64 chroma bytes in pixel order, one per luma sample.
+    let dup = |c: __m256i| {
+        let wide = _mm512_cvtepu8_epi16(c);
+        _mm512_or_si512(wide, _mm512_slli_epi16::<8>(wide))
+    };
+    let u_expanded = dup(u_vals);
+    let v_expanded = dup(v_vals);
+
+    // Byte replication to 10 bits. unpacklo/hi work per 128-bit lane, and
+    // packus below undoes the same permutation.
+    let widen_lo = |v: __m512i| _mm512_srli_epi16::<6>(_mm512_unpacklo_epi8(v, v));
+    let widen_hi = |v: __m512i| _mm512_srli_epi16::<6>(_mm512_unpackhi_epi8(v, v));
+
+    let u_lo = _mm512_sub_epi16(widen_lo(u_expanded), uv_corr);
+    let u_hi = _mm512_sub_epi16(widen_hi(u_expanded), uv_corr);
+    let v_lo = _mm512_sub_epi16(widen_lo(v_expanded), uv_corr);
+    let v_hi = _mm512_sub_epi16(widen_hi(v_expanded), uv_corr);
+
+    let convert = |y: __m512i, u: __m512i, v: __m512i| {
+        let y_scaled = _mm512_mulhrs_epi16(y, y_coef);
+        let r = _mm512_add_epi16(y_scaled, _mm512_mulhrs_epi16(v, cr_coef));
+        let b = _mm512_add_epi16(y_scaled, _mm512_mulhrs_epi16(u, cb_coef));
+        let g = _mm512_sub_epi16(
+            y_scaled,
+            _mm512_add_epi16(
+                _mm512_mulhrs_epi16(v, g_coef_1),
+                _mm512_mulhrs_epi16(u, g_coef_2),
+            ),
+        );
+        (r, g, b)
+    };
+
+    for (y_sub, out) in [(y0_sub, out0), (y1_sub, out1)] {
+        let (r_lo, g_lo, b_lo) = convert(widen_lo(y_sub), u_lo, v_lo);
+        let (r_hi, g_hi, b_hi) = convert(widen_hi(y_sub), u_hi, v_hi);
+
+        let mut r = [0u8; 64];
+        let mut g = [0u8; 64];
+        let mut b = [0u8; 64];
+        safe_unaligned_simd::x86_64::_mm512_storeu_si512(&mut r, _mm512_packus_epi16(r_lo, r_hi));
+        safe_unaligned_simd::x86_64::_mm512_storeu_si512(&mut g, _mm512_packus_epi16(g_lo, g_hi));
+        safe_unaligned_simd::x86_64::_mm512_storeu_si512(&mut b, _mm512_packus_epi16(b_lo, b_hi));
+
+        for (i, px) in out.iter_mut().enumerate() {
+            *px = RGB8 {
+                r: r[i],
+                g: g[i],
+                b: b[i],
+            };
+        }
+    }
+}
+
+// ============================================================================
+// SSE4.1 implementation
+// ============================================================================
+
+/// Fast YUV420 to RGB8 using integer arithmetic (SSE4.1 path)
+///
+/// Same math as the AVX2 path on 128-bit registers, two rows of 16 pixels
+/// per iteration, for x86-64 CPUs without AVX2. `pmulhrsw` is SSSE3, so
+/// every instruction here is in the x86-64-v2 baseline.
+#[cfg(target_arch = "x86_64")]
+#[arcane]
+pub fn yuv420_to_rgb8_fast_sse41(
+    token: X64V2Token,
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+) -> ImgVec<RGB8> {
+    let mut out = vec![RGB8::default(); width * height];
+
+    for y in (0..height).step_by(2) {
+        let y0_row = y;
+        let y1_row = (y + 1).min(height - 1);
+        let chroma_row = y / 2;
+
+        let mut x = 0;
+        // A trailing odd row has no partner row; leave it to the scalar loop.
+        if y1_row != y0_row {
+            while x + 16 <= width {
+                let (top_rows, bottom_rows) = out.split_at_mut(y1_row * width);
+                process_16_pixels_420_sse41(
+                    token,
+                    &y_plane[y0_row * y_stride + x..][..16],
+                    &y_plane[y1_row * y_stride + x..][..16],
+                    &u_plane[chroma_row * u_stride + x / 2..][..8],
+                    &v_plane[chroma_row * v_stride + x / 2..][..8],
+                    &mut top_rows[y0_row * width + x..][..16],
+                    &mut bottom_rows[x..][..16],
+                );
+                x += 16;
+            }
+        }
+
+        for px in x..width {
+            for row in [y0_row, y1_row] {
+                out[row * width + px] = yuv_to_rgb_fixed(
+                    y_plane[row * y_stride + px],
+                    u_plane[chroma_row * u_stride + px / 2],
+                    v_plane[chroma_row * v_stride + px / 2],
+                );
+            }
+        }
+    }
+
+    ImgVec::new(out, width, height)
+}
+
+/// Convert 16 pixels from each of two rows sharing one chroma row.
+///
+/// `y0` and `y1` hold 16 luma samples, `u` and `v` 8 chroma samples.
+#[cfg(target_arch = "x86_64")]
+#[rite]
+fn process_16_pixels_420_sse41(
+    _token: X64V2Token,
+    y0: &[u8],
+    y1: &[u8],
+    u: &[u8],
+    v: &[u8],
+    out0: &mut [RGB8],
+    out1: &mut [RGB8],
+) {
+    let y0_vals = _mm_loadu_si128(<&[u8; 16]>::try_from(y0).unwrap());
+    let y1_vals = _mm_loadu_si128(<&[u8; 16]>::try_from(y1).unwrap());
+    // Only the low 8 bytes of the chroma registers are used.
+    let mut u_arr = [0u8; 16];
+    let mut v_arr = [0u8; 16];
+    u_arr[..8].copy_from_slice(u);
+    v_arr[..8].copy_from_slice(v);
+    let u_vals = _mm_loadu_si128(&u_arr);
+    let v_vals = _mm_loadu_si128(&v_arr);
+
+    let y_corr = _mm_set1_epi8(Y_BIAS as i8);
+    let uv_corr = _mm_set1_epi16((UV_BIAS << 2) | (UV_BIAS >> 6));
+    let y_coef = _mm_set1_epi16(Y_COEF);
+    let cr_coef = _mm_set1_epi16(CR_COEF);
+    let cb_coef = _mm_set1_epi16(CB_COEF);
+    let g_coef_1 = _mm_set1_epi16(G_COEF_1);
+    let g_coef_2 = _mm_set1_epi16(G_COEF_2);
+
+    let y0_sub = _mm_subs_epu8(y0_vals, y_corr);
+    let y1_sub = _mm_subs_epu8(y1_vals, y_corr);
+
+    // Duplicate each chroma byte to cover its two luma columns.
+    let u_expanded = _mm_unpacklo_epi8(u_vals, u_vals);
+    let v_expanded = _mm_unpacklo_epi8(v_vals, v_vals);
+
+    // Byte replication to 10 bits.
+    let widen_lo = |v: __m128i| _mm_srli_epi16::<6>(_mm_unpacklo_epi8(v, v));
+    let widen_hi = |v: __m128i| _mm_srli_epi16::<6>(_mm_unpackhi_epi8(v, v));
+
+    let u_lo = _mm_sub_epi16(widen_lo(u_expanded), uv_corr);
+    let u_hi = _mm_sub_epi16(widen_hi(u_expanded), uv_corr);
+    let v_lo = _mm_sub_epi16(widen_lo(v_expanded), uv_corr);
+    let v_hi = _mm_sub_epi16(widen_hi(v_expanded), uv_corr);
+
+    let convert = |y: __m128i, u: __m128i, v: __m128i| {
+        let y_scaled = _mm_mulhrs_epi16(y, y_coef);
+        let r = _mm_add_epi16(y_scaled, _mm_mulhrs_epi16(v, cr_coef));
+        let b = _mm_add_epi16(y_scaled, _mm_mulhrs_epi16(u, cb_coef));
+        let g = _mm_sub_epi16(
+            y_scaled,
+            _mm_add_epi16(_mm_mulhrs_epi16(v, g_coef_1), _mm_mulhrs_epi16(u, g_coef_2)),
+        );
+        (r, g, b)
+    };
+
+    for (y_sub, out) in [(y0_sub, out0), (y1_sub, out1)] {
+        let (r_lo, g_lo, b_lo) = convert(widen_lo(y_sub), u_lo, v_lo);
+        let (r_hi, g_hi, b_hi) = convert(widen_hi(y_sub), u_hi, v_hi);
+
+        let mut r = [0u8; 16];
+        let mut g = [0u8; 16];
+        let mut b = [0u8; 16];
+        _mm_storeu_si128(&mut r, _mm_packus_epi16(r_lo, r_hi));
+        _mm_storeu_si128(&mut g, _mm_packus_epi16(g_lo, g_hi));
+        _mm_storeu_si128(&mut b, _mm_packus_epi16(b_lo, b_hi));
+
+        for (i, px) in out.iter_mut().enumerate() {
+            *px = RGB8 {
+                r: r[i],
+                g: g[i],
+                b: b[i],
+            };
+        }
+    }
+}
+
 // ============================================================================
 // NEON (aarch64) implementation
 // ============================================================================
@@ -377,16 +791,9 @@ pub fn yuv420_to_rgb8_fast_neon(
 ) -> ImgVec<RGB8> {
     let mut out = vec![RGB8::default(); width * height];
 
-    // BT.709 coefficients in fixed-point (Q13 format: 8192 = 1.0)
-    let y_coef: i16 = 9539; // 1.164 * 8192
-    let cr_coef: i16 = 13075; // 1.596 * 8192
-    let cb_coef: i16 = 16525; // 2.018 * 8192
-    let g_coef_1: i16 = 6660; // For V component (formula subtracts this)
-    let g_coef_2: i16 = 3209; // For U component (formula subtracts this)
-
-    // Bias values
-    let y_bias: i16 = 16;
-    let uv_bias: i16 = 128;
+    let (y_coef, cr_coef, cb_coef) = (Y_COEF, CR_COEF, CB_COEF);
+    let (g_coef_1, g_coef_2) = (G_COEF_1, G_COEF_2);
+    let (y_bias, uv_bias) = (Y_BIAS, UV_BIAS);
 
     // Process 2 rows at a time for YUV420
     for y in (0..height).step_by(2) {
@@ -398,7 +805,9 @@ pub fn yuv420_to_rgb8_fast_neon(
         for x in (0..width).step_by(16) {
             let pixels_remaining = (width - x).min(16);
 
-            if pixels_remaining < 16 {
+            // A trailing odd row has no partner row to pair with; the scalar
+            // path handles it without splitting the output buffer.
+            if pixels_remaining < 16 || y1_row == y0_row {
                 // Handle remaining pixels with scalar code
                 for i in 0..pixels_remaining {
                     for row in [y0_row, y1_row] {
@@ -408,23 +817,11 @@ pub fn yuv420_to_rgb8_fast_neon(
                         let px = x + i;
                         let chroma_x = px / 2;
 
-                        let y_val = y_plane[row * y_stride + px] as i32 - y_bias as i32;
-                        let u_val =
-                            u_plane[chroma_row * u_stride + chroma_x] as i32 - uv_bias as i32;
-                        let v_val =
-                            v_plane[chroma_row * v_stride + chroma_x] as i32 - uv_bias as i32;
-
-                        let y_scaled = (y_val * y_coef as i32) >> 13;
-                        let r = y_scaled + ((v_val * cr_coef as i32) >> 13);
-                        let g =
-                            y_scaled - ((v_val * g_coef_1 as i32 + u_val * g_coef_2 as i32) >> 13);
-                        let b = y_scaled + ((u_val * cb_coef as i32) >> 13);
-
-                        out[row * width + px] = RGB8 {
-                            r: r.clamp(0, 255) as u8,
-                            g: g.clamp(0, 255) as u8,
-                            b: b.clamp(0, 255) as u8,
-                        };
+                        out[row * width + px] = yuv_to_rgb_fixed(
+                            y_plane[row * y_stride + px],
+                            u_plane[chroma_row * u_stride + chroma_x],
+                            v_plane[chroma_row * v_stride + chroma_x],
+                        );
                     }
                 }
                 continue;
@@ -643,3 +1040,154 @@ fn store_rgb_row_neon(out: &mut [RGB8], r: uint8x16_t, g: uint8x16_t, b: uint8x1
         };
     }
 }
+
+// ============================================================================
+// RISC-V vector implementation
+// ============================================================================
+
+/// Fast YUV420 to RGB8 using integer arithmetic (RVV path)
+///
+/// Duplicates each chroma row to full width once, shared by its two luma
+/// rows, then converts whole rows at the hardware vector length.
+///
+/// # Panics
+///
+/// If [`crate::simd::rvv_available`] is false.
+#[cfg(all(target_arch = "riscv64", feature = "riscv-v"))]
+pub fn yuv420_to_rgb8_fast_rvv(
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+) -> ImgVec<RGB8> {
+    let mut out = vec![RGB8::default(); width * height];
+    let coefs = [
+        Y_BIAS,
+        (UV_BIAS << 2) | (UV_BIAS >> 6),
+        Y_COEF,
+        CR_COEF,
+        CB_COEF,
+        G_COEF_1,
+        G_COEF_2,
+    ];
+    let mut u_row = vec![0u8; width];
+    let mut v_row = vec![0u8; width];
+
+    for (row, out_row) in out.chunks_exact_mut(width.max(1)).enumerate().take(height) {
+        let chroma_row = row / 2;
+        if row % 2 == 0 {
+            for px in 0..width {
+                u_row[px] = u_plane[chroma_row * u_stride + px / 2];
+                v_row[px] = v_plane[chroma_row * v_stride + px / 2];
+            }
+        }
+        crate::simd::yuv_to_rgb8_row_rvv(
+            &y_plane[row * y_stride..][..width],
+            &u_row,
+            &v_row,
+            out_row,
+            coefs,
+        );
+    }
+
+    ImgVec::new(out, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random planes covering the full u8 range.
+    fn planes(width: usize, height: usize, seed: u32) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (state >> 24) as u8
+        };
+        let cw = width.div_ceil(2);
+        let ch = height.div_ceil(2);
+        let y = (0..width * height).map(|_| next()).collect();
+        let u = (0..cw * ch).map(|_| next()).collect();
+        let v = (0..cw * ch).map(|_| next()).collect();
+        (y, u, v)
+    }
+
+    #[test]
+    fn mulhrs_matches_pmulhrsw_definition() {
+        assert_eq!(mulhrs(0, Y_COEF), 0);
+        assert_eq!(mulhrs(1, 0x4000), 1); // 0.5 rounds up
+        assert_eq!(mulhrs(-1, 0x4000), 0); // -0.5 rounds up
+        assert_eq!(mulhrs(i16::MAX, i16::MAX), 32766);
+    }
+
+    #[test]
+    fn fixed_point_endpoints() {
+        assert_eq!(yuv_to_rgb_fixed(16, 128, 128), RGB8::new(0, 0, 0));
+        assert_eq!(yuv_to_rgb_fixed(235, 128, 128), RGB8::new(255, 255, 255));
+    }
+
+    /// Every SIMD tier available on this CPU must match the scalar reference
+    /// byte for byte, across widths that exercise the remainder columns and
+    /// heights that exercise the trailing odd row.
+    #[test]
+    fn tiers_match_scalar_reference() {
+        for width in [1, 2, 15, 16, 17, 31, 32, 33, 63, 64, 65, 100, 128, 130] {
+            for height in [1, 2, 3, 4, 7] {
+                let (y, u, v) = planes(width, height, (width * 31 + height) as u32);
+                let cw = width.div_ceil(2);
+                // SIMD loads read a full register of chroma; pad the planes.
+                let mut u_pad = u.clone();
+                let mut v_pad = v.clone();
+                u_pad.resize(u.len() + 32, 128);
+                v_pad.resize(v.len() + 32, 128);
+                let reference =
+                    yuv420_to_rgb8_fast_scalar(&y, width, &u, cw, &v, cw, width, height);
+
+                #[cfg(target_arch = "x86_64")]
+                if let Some(token) = Desktop64::summon() {
+                    let simd = yuv420_to_rgb8_fast(
+                        token, &y, width, &u_pad, cw, &v_pad, cw, width, height,
+                    );
+                    assert_eq!(simd.buf(), reference.buf(), "AVX2 {width}x{height}");
+                }
+
+                #[cfg(target_arch = "x86_64")]
+                if let Some(token) = X64V2Token::summon() {
+                    let simd =
+                        yuv420_to_rgb8_fast_sse41(token, &y, width, &u, cw, &v, cw, width, height);
+                    assert_eq!(simd.buf(), reference.buf(), "SSE4.1 {width}x{height}");
+                }
+
+                #[cfg(all(target_arch = "x86_64", feature = "avx512"))]
+                if let Some(token) = X64V4Token::summon() {
+                    let simd =
+                        yuv420_to_rgb8_fast_avx512(token, &y, width, &u, cw, &v, cw, width, height);
+                    assert_eq!(simd.buf(), reference.buf(), "AVX-512 {width}x{height}");
+                }
+
+                #[cfg(all(target_arch = "riscv64", feature = "riscv-v"))]
+                if crate::simd::rvv_available() {
+                    let simd = yuv420_to_rgb8_fast_rvv(&y, width, &u, cw, &v, cw, width, height);
+                    assert_eq!(simd.buf(), reference.buf(), "RVV {width}x{height}");
+                }
+
+                #[cfg(target_arch = "aarch64")]
+                if let Some(token) = NeonToken::summon() {
+                    let simd = yuv420_to_rgb8_fast_neon(
+                        token, &y, width, &u_pad, cw, &v_pad, cw, width, height,
+                    );
+                    assert_eq!(simd.buf(), reference.buf(), "NEON {width}x{height}");
+                }
+
+                let auto = yuv420_to_rgb8_fast_auto(&y, width, &u, cw, &v, cw, width, height);
+                assert_eq!(auto.buf(), reference.buf(), "auto {width}x{height}");
+
+                let _ = (&u_pad, &v_pad, &reference);
+            }
+        }
+    }
+}
@@ -7,25 +7,33 @@
 #![allow(dead_code)]
 
 use crate::yuv_convert::{YuvMatrix, YuvRange};
-#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "wasm32"
+))]
 use crate::yuv_convert_libyuv_simd;
-#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+#[cfg(any(
+    target_arch = "x86_64",
+    target_arch = "aarch64",
+    target_arch = "wasm32"
+))]
 use archmage::prelude::*;
 use imgref::ImgVec;
 use rgb::RGB8;
 
 /// YUV conversion constants for different matrix/range combinations
 #[allow(dead_code)]
-struct YuvConstants {
-    yg: i32,
-    ygb: i32,
-    ub: i32,
-    ug: i32,
-    vg: i32,
-    vr: i32,
-    bb: i32,
-    bg: i32,
-    br: i32,
+pub(crate) struct YuvConstants {
+    pub(crate) yg: i32,
+    pub(crate) ygb: i32,
+    pub(crate) ub: i32,
+    pub(crate) ug: i32,
+    pub(crate) vg: i32,
+    pub(crate) vr: i32,
+    pub(crate) bb: i32,
+    pub(crate) bg: i32,
+    pub(crate) br: i32,
 }
 
 impl YuvConstants {
@@ -69,11 +77,21 @@ impl YuvConstants {
     };
 
     /// BT.601 Limited Range
-    const BT601_LIMITED: Self = Self::BT601_FULL; // Same as full for now
+    const BT601_LIMITED: Self = Self {
+        yg: 18997,
+        ygb: -1160,
+        ub: -128,                          // -2.018 * 64, clamped to i8
+        ug: 25,                            // 0.391 * 64
+        vg: 52,                            // 0.813 * 64
+        vr: -102,                          // -1.596 * 64
+        bb: -128 * 128 + (-1160),          // -17544
+        bg: 25 * 128 + 52 * 128 + (-1160), // 8696
+        br: -102 * 128 + (-1160),          // -14216
+    };
 }
 
 /// Get constants for the given matrix and range
-fn get_constants(matrix: YuvMatrix, range: YuvRange) -> Option<&'static YuvConstants> {
+pub(crate) fn get_constants(matrix: YuvMatrix, range: YuvRange) -> Option<&'static YuvConstants> {
     match (matrix, range) {
         (YuvMatrix::Bt709, YuvRange::Full) => Some(&YuvConstants::BT709_FULL),
         (YuvMatrix::Bt709, YuvRange::Limited) => Some(&YuvConstants::BT709_LIMITED),
@@ -85,7 +103,7 @@ fn get_constants(matrix: YuvMatrix, range: YuvRange) -> Option<&'static YuvConst
 
 /// Convert single YUV pixel to RGB
 #[inline(always)]
-fn yuv_pixel_with_constants(y: u8, u: u8, v: u8, c: &YuvConstants) -> RGB8 {
+pub(crate) fn yuv_pixel_with_constants(y: u8, u: u8, v: u8, c: &YuvConstants) -> RGB8 {
     let y1 = ((y as u32) * 0x0101 * (c.yg as u32)) >> 16;
     let y1 = y1 as i32;
 
@@ -102,7 +120,9 @@ fn yuv_pixel_with_constants(y: u8, u: u8, v: u8, c: &YuvConstants) -> RGB8 {
 
 /// Convert YUV420 to RGB8 using exact libyuv math
 ///
-/// Uses SIMD when available (2.77x faster), falls back to scalar
+/// Uses SIMD when available (2.77x faster), falls back to scalar. AVX-512
+/// (with the `avx512` feature) and wasm SIMD128 cover every supported
+/// matrix and range.
 pub fn yuv420_to_rgb8(
     y_plane: &[u8],
     y_stride: usize,
@@ -115,6 +135,24 @@ pub fn yuv420_to_rgb8(
     range: YuvRange,
     matrix: YuvMatrix,
 ) -> Option<ImgVec<RGB8>> {
+    #[cfg(all(target_arch = "x86_64", feature = "avx512"))]
+    if let Some(token) = X64V4Token::summon() {
+        let c = get_constants(matrix, range)?;
+        return Some(yuv_convert_libyuv_simd::yuv_to_rgb8_avx512(
+            token,
+            y_plane,
+            y_stride,
+            u_plane,
+            u_stride,
+            v_plane,
+            v_stride,
+            width,
+            height,
+            (1, 1),
+            c,
+        ));
+    }
+
     // Try SIMD first for BT.709 Full Range (most common)
     #[cfg(target_arch = "x86_64")]
     #[allow(clippy::collapsible_if)]
@@ -138,9 +176,26 @@ pub fn yuv420_to_rgb8(
         }
     }
 
-    // Scalar fallback for all matrix/range combinations
     let c = get_constants(matrix, range)?;
 
+    #[cfg(target_arch = "wasm32")]
+    if let Some(token) = Wasm128Token::summon() {
+        return Some(yuv_convert_libyuv_simd::yuv_to_rgb8_wasm128(
+            token,
+            y_plane,
+            y_stride,
+            u_plane,
+            u_stride,
+            v_plane,
+            v_stride,
+            width,
+            height,
+            (1, 1),
+            c,
+        ));
+    }
+
+    // Scalar fallback for all matrix/range combinations
     let mut out = vec![RGB8::default(); width * height];
 
     for y in 0..height {
@@ -160,6 +215,9 @@ pub fn yuv420_to_rgb8(
 }
 
 /// Convert YUV422 to RGB8
+///
+/// Uses AVX-512 (with the `avx512` feature) or SIMD128 on wasm32, scalar
+/// elsewhere.
 pub fn yuv422_to_rgb8(
     y_plane: &[u8],
     y_stride: usize,
@@ -173,6 +231,41 @@ pub fn yuv422_to_rgb8(
     matrix: YuvMatrix,
 ) -> Option<ImgVec<RGB8>> {
     let c = get_constants(matrix, range)?;
+
+    #[cfg(all(target_arch = "x86_64", feature = "avx512"))]
+    if let Some(token) = X64V4Token::summon() {
+        return Some(yuv_convert_libyuv_simd::yuv_to_rgb8_avx512(
+            token,
+            y_plane,
+            y_stride,
+            u_plane,
+            u_stride,
+            v_plane,
+            v_stride,
+            width,
+            height,
+            (1, 0),
+            c,
+        ));
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    if let Some(token) = Wasm128Token::summon() {
+        return Some(yuv_convert_libyuv_simd::yuv_to_rgb8_wasm128(
+            token,
+            y_plane,
+            y_stride,
+            u_plane,
+            u_stride,
+            v_plane,
+            v_stride,
+            width,
+            height,
+            (1, 0),
+            c,
+        ));
+    }
+
     let mut out = vec![RGB8::default(); width * height];
 
     for y in 0..height {
@@ -191,6 +284,9 @@ pub fn yuv422_to_rgb8(
 }
 
 /// Convert YUV444 to RGB8
+///
+/// Uses AVX-512 (with the `avx512` feature) or SIMD128 on wasm32, scalar
+/// elsewhere.
 pub fn yuv444_to_rgb8(
     y_plane: &[u8],
     y_stride: usize,
@@ -204,6 +300,41 @@ pub fn yuv444_to_rgb8(
     matrix: YuvMatrix,
 ) -> Option<ImgVec<RGB8>> {
     let c = get_constants(matrix, range)?;
+
+    #[cfg(all(target_arch = "x86_64", feature = "avx512"))]
+    if let Some(token) = X64V4Token::summon() {
+        return Some(yuv_convert_libyuv_simd::yuv_to_rgb8_avx512(
+            token,
+            y_plane,
+            y_stride,
+            u_plane,
+            u_stride,
+            v_plane,
+            v_stride,
+            width,
+            height,
+            (0, 0),
+            c,
+        ));
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    if let Some(token) = Wasm128Token::summon() {
+        return Some(yuv_convert_libyuv_simd::yuv_to_rgb8_wasm128(
+            token,
+            y_plane,
+            y_stride,
+            u_plane,
+            u_stride,
+            v_plane,
+            v_stride,
+            width,
+            height,
+            (0, 0),
+            c,
+        ));
+    }
+
     let mut out = vec![RGB8::default(); width * height];
 
     for y in 0..height {
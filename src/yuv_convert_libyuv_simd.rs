@@ -1,7 +1,9 @@
-//! SIMD-optimized libyuv YUV to RGB conversion using AVX2 and NEON
+//! SIMD-optimized libyuv YUV to RGB conversion using AVX-512, AVX2, NEON and
+//! wasm SIMD128
 //!
 //! Safety: All intrinsics are protected by archmage's token system.
-//! The Desktop64 token proves AVX2 is available, and NeonToken proves NEON is available.
+//! The X64V4Token proves AVX-512 is available, Desktop64 proves AVX2,
+//! NeonToken proves NEON, and Wasm128Token proves SIMD128.
 //! This module uses #![forbid(unsafe_code)] - all SIMD is safe via #[arcane].
 
 #![forbid(unsafe_code)]
@@ -9,6 +11,11 @@
 #![allow(dead_code)]
 
 use crate::yuv_convert::{YuvMatrix, YuvRange};
+#[cfg(any(
+    target_arch = "wasm32",
+    all(target_arch = "x86_64", feature = "avx512")
+))]
+use crate::yuv_convert_libyuv::{YuvConstants, yuv_pixel_with_constants};
 use archmage::prelude::*; // Includes core::arch and safe_unaligned_simd
 use imgref::ImgVec;
 use rgb::RGB8;
@@ -218,6 +225,148 @@ fn process_8_pixels_avx2(
     }
 }
 
+// ============================================================================
+// AVX-512 implementation
+// ============================================================================
+
+/// Convert 8-bit YUV to RGB8 using AVX-512, for any libyuv constants
+///
+/// Handles 64 pixels per iteration as four batches of sixteen i32 lanes.
+/// `chroma_shift` is as for [`yuv_to_rgb8_wasm128`]; output matches
+/// [`yuv_pixel_with_constants`] exactly.
+///
+/// Safety: Token-gated via #[arcane] - all SIMD operations are safe
+#[cfg(all(target_arch = "x86_64", feature = "avx512"))]
+#[arcane]
+pub(crate) fn yuv_to_rgb8_avx512(
+    token: X64V4Token,
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    chroma_shift: (u32, u32),
+    c: &YuvConstants,
+) -> ImgVec<RGB8> {
+    let (shift_x, shift_y) = chroma_shift;
+    let mut out = vec![RGB8::default(); width * height];
+
+    for row in 0..height {
+        let y_row = &y_plane[row * y_stride..];
+        let u_row = &u_plane[(row >> shift_y) * u_stride..];
+        let v_row = &v_plane[(row >> shift_y) * v_stride..];
+        let out_row = &mut out[row * width..][..width];
+
+        let mut x = 0;
+        while x + 64 <= width {
+            let cx = x >> shift_x;
+            let chroma_len = 64 >> shift_x;
+            process_64_pixels_avx512(
+                token,
+                &y_row[x..x + 64],
+                &u_row[cx..cx + chroma_len],
+                &v_row[cx..cx + chroma_len],
+                c,
+                &mut out_row[x..x + 64],
+            );
+            x += 64;
+        }
+
+        // Scalar tail
+        for (x, px) in out_row.iter_mut().enumerate().skip(x) {
+            *px = yuv_pixel_with_constants(y_row[x], u_row[x >> shift_x], v_row[x >> shift_x], c);
+        }
+    }
+
+    ImgVec::new(out, width, height)
+}
+
+/// Process 64 pixels using AVX-512
+///
+/// `u` and `v` hold 32 samples each for horizontally subsampled chroma, or
+/// 64 for 4:4:4. Each batch of 16 pixels widens to i32 lanes and follows
+/// [`yuv_pixel_with_constants`] step for step.
+#[cfg(all(target_arch = "x86_64", feature = "avx512"))]
+#[rite]
+fn process_64_pixels_avx512(
+    _token: X64V4Token,
+    y: &[u8],
+    u: &[u8],
+    v: &[u8],
+    c: &YuvConstants,
+    out: &mut [RGB8],
+) {
+    let yg = _mm512_set1_epi32(c.yg);
+    let ub = _mm512_set1_epi32(c.ub);
+    let ug = _mm512_set1_epi32(c.ug);
+    let vg = _mm512_set1_epi32(c.vg);
+    let vr = _mm512_set1_epi32(c.vr);
+    let bb = _mm512_set1_epi32(c.bb);
+    let bg = _mm512_set1_epi32(c.bg);
+    let br = _mm512_set1_epi32(c.br);
+    let c0x0101 = _mm512_set1_epi32(0x0101);
+    let zero = _mm512_setzero_si512();
+    let max = _mm512_set1_epi32(255);
+
+    // Chroma samples per batch of 16 pixels: 8 when subsampled, else 16.
+    let chroma_step = u.len() / 4;
+    let load16 = |src: &[u8]| {
+        let mut padded = [0u8; 16];
+        padded[..src.len()].copy_from_slice(src);
+        let vals = safe_unaligned_simd::x86_64::_mm_loadu_si128(&padded);
+        if src.len() == 8 {
+            // Duplicate each chroma sample: [c0,c0,c1,c1,...,c7,c7]
+            _mm512_cvtepu8_epi32(_mm_unpacklo_epi8(vals, vals))
+        } else {
+            _mm512_cvtepu8_epi32(vals)
+        }
+    };
+    // Clamp to [0, 255] and truncate each lane to a byte
+    let to_bytes = |vals: __m512i| {
+        let mut bytes = [0u8; 16];
+        let clamped = _mm512_min_epi32(_mm512_max_epi32(vals, zero), max);
+        safe_unaligned_simd::x86_64::_mm_storeu_si128(&mut bytes, _mm512_cvtepi32_epi8(clamped));
+        bytes
+    };
+
+    for (batch, out) in out[..64].chunks_exact_mut(16).enumerate() {
+        let y_i32 = load16(&y[batch * 16..][..16]);
+        let u_i32 = load16(&u[batch * chroma_step..][..chroma_step]);
+        let v_i32 = load16(&v[batch * chroma_step..][..chroma_step]);
+
+        // y1 = (y * 0x0101 * YG) >> 16; the product stays below 2^31.
+        let y1 =
+            _mm512_srai_epi32::<16>(_mm512_mullo_epi32(_mm512_mullo_epi32(y_i32, c0x0101), yg));
+        let b = _mm512_srai_epi32::<6>(_mm512_add_epi32(
+            _mm512_sub_epi32(y1, _mm512_mullo_epi32(u_i32, ub)),
+            bb,
+        ));
+        let g = _mm512_srai_epi32::<6>(_mm512_add_epi32(
+            _mm512_sub_epi32(
+                y1,
+                _mm512_add_epi32(_mm512_mullo_epi32(u_i32, ug), _mm512_mullo_epi32(v_i32, vg)),
+            ),
+            bg,
+        ));
+        let r = _mm512_srai_epi32::<6>(_mm512_add_epi32(
+            _mm512_sub_epi32(y1, _mm512_mullo_epi32(v_i32, vr)),
+            br,
+        ));
+
+        let (r, g, b) = (to_bytes(r), to_bytes(g), to_bytes(b));
+        for (i, px) in out.iter_mut().enumerate() {
+            *px = RGB8 {
+                r: r[i],
+                g: g[i],
+                b: b[i],
+            };
+        }
+    }
+}
+
 // ============================================================================
 // NEON (aarch64) implementation
 // ============================================================================
@@ -436,6 +585,144 @@ fn yuv_to_rgb_neon_i32(
     (r, g, b)
 }
 
+// ============================================================================
+// wasm32 SIMD128 implementation
+// ============================================================================
+
+/// Convert 8-bit YUV to RGB8 using wasm SIMD128, for any libyuv constants
+///
+/// `chroma_shift` is the horizontal and vertical subsampling shift: `(1, 1)`
+/// for 4:2:0, `(1, 0)` for 4:2:2 and `(0, 0)` for 4:4:4. Output matches
+/// [`yuv_pixel_with_constants`] exactly.
+///
+/// Safety: Token-gated via #[arcane] - all SIMD operations are safe
+#[cfg(target_arch = "wasm32")]
+#[arcane]
+pub(crate) fn yuv_to_rgb8_wasm128(
+    token: Wasm128Token,
+    y_plane: &[u8],
+    y_stride: usize,
+    u_plane: &[u8],
+    u_stride: usize,
+    v_plane: &[u8],
+    v_stride: usize,
+    width: usize,
+    height: usize,
+    chroma_shift: (u32, u32),
+    c: &YuvConstants,
+) -> ImgVec<RGB8> {
+    let (shift_x, shift_y) = chroma_shift;
+    let mut out = vec![RGB8::default(); width * height];
+
+    for row in 0..height {
+        let y_row = &y_plane[row * y_stride..];
+        let u_row = &u_plane[(row >> shift_y) * u_stride..];
+        let v_row = &v_plane[(row >> shift_y) * v_stride..];
+        let out_row = &mut out[row * width..][..width];
+
+        let mut x = 0;
+        while x + 8 <= width {
+            let cx = x >> shift_x;
+            let chroma_len = 8 >> shift_x;
+            process_8_pixels_wasm128(
+                token,
+                &y_row[x..x + 8],
+                &u_row[cx..cx + chroma_len],
+                &v_row[cx..cx + chroma_len],
+                c,
+                &mut out_row[x..x + 8],
+            );
+            x += 8;
+        }
+
+        // Scalar tail
+        for (x, px) in out_row.iter_mut().enumerate().skip(x) {
+            *px = yuv_pixel_with_constants(y_row[x], u_row[x >> shift_x], v_row[x >> shift_x], c);
+        }
+    }
+
+    ImgVec::new(out, width, height)
+}
+
+/// Process 8 pixels using wasm SIMD128
+///
+/// `u` and `v` hold 4 samples each for horizontally subsampled chroma, which
+/// are duplicated to 8, or 8 samples for 4:4:4. Math runs in two i32x4
+/// halves, exactly as [`yuv_pixel_with_constants`].
+#[cfg(target_arch = "wasm32")]
+#[rite]
+fn process_8_pixels_wasm128(
+    _token: Wasm128Token,
+    y: &[u8],
+    u: &[u8],
+    v: &[u8],
+    c: &YuvConstants,
+    out: &mut [RGB8],
+) {
+    let load8 = |src: &[u8]| {
+        let mut padded = [0u8; 16];
+        padded[..src.len()].copy_from_slice(src);
+        let vals = safe_unaligned_simd::wasm32::v128_load(&padded);
+        if src.len() == 4 {
+            // Duplicate each chroma sample: [c0,c0,c1,c1,c2,c2,c3,c3]
+            u8x16_shuffle::<0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7>(vals, vals)
+        } else {
+            vals
+        }
+    };
+    let widen = |vals: v128| {
+        let wide = u16x8_extend_low_u8x16(vals);
+        (u32x4_extend_low_u16x8(wide), u32x4_extend_high_u16x8(wide))
+    };
+    let (y_lo, y_hi) = widen(load8(y));
+    let (u_lo, u_hi) = widen(load8(u));
+    let (v_lo, v_hi) = widen(load8(v));
+
+    let yg = i32x4_splat(c.yg);
+    let ub = i32x4_splat(c.ub);
+    let ug = i32x4_splat(c.ug);
+    let vg = i32x4_splat(c.vg);
+    let vr = i32x4_splat(c.vr);
+    let bb = i32x4_splat(c.bb);
+    let bg = i32x4_splat(c.bg);
+    let br = i32x4_splat(c.br);
+    let c0x0101 = i32x4_splat(0x0101);
+
+    let convert = |y: v128, u: v128, v: v128| {
+        // y1 = (y * 0x0101 * YG) >> 16; the product stays below 2^31.
+        let y1 = i32x4_shr(i32x4_mul(i32x4_mul(y, c0x0101), yg), 16);
+        let b = i32x4_shr(i32x4_add(i32x4_sub(y1, i32x4_mul(u, ub)), bb), 6);
+        let g = i32x4_shr(
+            i32x4_add(
+                i32x4_sub(y1, i32x4_add(i32x4_mul(u, ug), i32x4_mul(v, vg))),
+                bg,
+            ),
+            6,
+        );
+        let r = i32x4_shr(i32x4_add(i32x4_sub(y1, i32x4_mul(v, vr)), br), 6);
+        (r, g, b)
+    };
+    let (r_lo, g_lo, b_lo) = convert(y_lo, u_lo, v_lo);
+    let (r_hi, g_hi, b_hi) = convert(y_hi, u_hi, v_hi);
+
+    // Saturating narrow i32 -> i16 -> u8 clamps to [0, 255]
+    let pack = |lo: v128, hi: v128| {
+        let mut bytes = [0u8; 16];
+        let narrowed = i16x8_narrow_i32x4(lo, hi);
+        safe_unaligned_simd::wasm32::v128_store(&mut bytes, u8x16_narrow_i16x8(narrowed, narrowed));
+        bytes
+    };
+    let (r, g, b) = (pack(r_lo, r_hi), pack(g_lo, g_hi), pack(b_lo, b_hi));
+
+    for (i, px) in out[..8].iter_mut().enumerate() {
+        *px = RGB8 {
+            r: r[i],
+            g: g[i],
+            b: b[i],
+        };
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -504,4 +791,73 @@ mod tests {
             }
         }
     }
+
+    /// The AVX-512 kernel must match the scalar libyuv math for every
+    /// subsampling and matrix, including the scalar tail.
+    #[cfg(all(target_arch = "x86_64", feature = "avx512"))]
+    #[test]
+    fn avx512_matches_scalar() {
+        let Some(token) = X64V4Token::summon() else {
+            return;
+        };
+        let (width, height): (usize, usize) = (133, 5);
+        for (matrix, range) in [
+            (YuvMatrix::Bt709, YuvRange::Full),
+            (YuvMatrix::Bt601, YuvRange::Limited),
+        ] {
+            let c = crate::yuv_convert_libyuv::get_constants(matrix, range).unwrap();
+            for shift in [(1, 1), (1, 0), (0, 0)] {
+                let (cw, ch) = (width.div_ceil(1 << shift.0), height.div_ceil(1 << shift.1));
+                let y: Vec<u8> = (0..width * height).map(|i| (i * 37 % 256) as u8).collect();
+                let u: Vec<u8> = (0..cw * ch).map(|i| (i * 53 % 256) as u8).collect();
+                let v: Vec<u8> = (0..cw * ch).map(|i| (255 - i * 29 % 256) as u8).collect();
+                let simd =
+                    yuv_to_rgb8_avx512(token, &y, width, &u, cw, &v, cw, width, height, shift, c);
+                for row in 0..height {
+                    for x in 0..width {
+                        let ci = (row >> shift.1) * cw + (x >> shift.0);
+                        let expected =
+                            yuv_pixel_with_constants(y[row * width + x], u[ci], v[ci], c);
+                        assert_eq!(
+                            simd.buf()[row * width + x],
+                            expected,
+                            "{matrix:?} {range:?} {shift:?} ({x}, {row})"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// The SIMD128 kernel must match the scalar libyuv math for every
+    /// subsampling, including the scalar tail.
+    #[cfg(target_arch = "wasm32")]
+    #[test]
+    fn wasm128_matches_scalar() {
+        let Some(token) = Wasm128Token::summon() else {
+            return;
+        };
+        let c =
+            crate::yuv_convert_libyuv::get_constants(YuvMatrix::Bt601, YuvRange::Limited).unwrap();
+        let (width, height) = (21, 5);
+        for shift in [(1, 1), (1, 0), (0, 0)] {
+            let (cw, ch) = (width.div_ceil(1 << shift.0), height.div_ceil(1 << shift.1));
+            let y: Vec<u8> = (0..width * height).map(|i| (i * 37 % 256) as u8).collect();
+            let u: Vec<u8> = (0..cw * ch).map(|i| (i * 53 % 256) as u8).collect();
+            let v: Vec<u8> = (0..cw * ch).map(|i| (255 - i * 29 % 256) as u8).collect();
+            let simd =
+                yuv_to_rgb8_wasm128(token, &y, width, &u, cw, &v, cw, width, height, shift, c);
+            for row in 0..height {
+                for x in 0..width {
+                    let ci = (row >> shift.1) * cw + (x >> shift.0);
+                    let expected = yuv_pixel_with_constants(y[row * width + x], u[ci], v[ci], c);
+                    assert_eq!(
+                        simd.buf()[row * width + x],
+                        expected,
+                        "{shift:?} ({x}, {row})"
+                    );
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,99 @@
+//! Allocation regression tests.
+//!
+//! Guards against per-row or per-pixel `Vec` churn creeping into the decode
+//! path. The bounds are loose on purpose: they leave room for decoder
+//! internals to change, but fail on anything that allocates per row.
+//!
+//! Run with: cargo test --features alloc-stats --test alloc_stats
+
+#![cfg(feature = "alloc-stats")]
+
+use enough::Unstoppable;
+use zenavif::alloc_stats::{self, AllocStats, CountingAllocator};
+use zenavif::{DecoderConfig, ManagedAvifDecoder, decode_with};
+
+#[global_allocator]
+static ALLOC: CountingAllocator = CountingAllocator::new(std::alloc::System);
+
+const KODIM03: &str = "tests/vectors/libavif/kodim03_yuv420_8bpc.avif";
+
+fn load_vector(path: &str) -> Option<Vec<u8>> {
+    match std::fs::read(path) {
+        Ok(data) => Some(data),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("skipping: {path} not found (download with: just download-vectors)");
+            None
+        }
+        Err(e) => panic!("Failed to read {path}: {e}"),
+    }
+}
+
+fn measure_decode(data: &[u8]) -> (AllocStats, u32) {
+    let config = DecoderConfig::new().threads(1);
+    let before = alloc_stats::snapshot();
+    let image = decode_with(data, &config, &Unstoppable).unwrap();
+    let height = image.height();
+    drop(image);
+    (alloc_stats::snapshot().since(&before), height)
+}
+
+/// Decode after `preallocate`, counting only the decode itself.
+fn measure_preallocated_decode(data: &[u8], width: u32, height: u32) -> AllocStats {
+    let config = DecoderConfig::new().threads(1);
+    let mut decoder = ManagedAvifDecoder::new(data, &config).unwrap();
+    decoder.preallocate(width, height, 8).unwrap();
+    let before = alloc_stats::snapshot();
+    let image = decoder.decode(&Unstoppable).unwrap();
+    let stats = alloc_stats::snapshot().since(&before);
+    drop(image);
+    stats
+}
+
+// The counters are process-wide, so everything is measured from a single
+// test to keep other test threads out of the numbers.
+#[test]
+fn decode_allocations_are_bounded_and_freed() {
+    let Some(data) = load_vector(KODIM03) else {
+        return;
+    };
+    // Warm up one-time initialisation (CPU detection, lazy tables).
+    measure_decode(&data);
+    let (stats, height) = measure_decode(&data);
+    eprintln!("{stats:#?}");
+
+    assert!(
+        stats.parse.allocations < 256,
+        "parse: {} allocations",
+        stats.parse.allocations
+    );
+    assert!(
+        stats.decode.allocations < height as u64,
+        "decode: {} allocations for {height} rows",
+        stats.decode.allocations
+    );
+    assert!(
+        stats.convert.allocations < 64,
+        "convert: {} allocations",
+        stats.convert.allocations
+    );
+    assert_eq!(stats.live_bytes, 0, "bytes still live after decode");
+
+    // With storage reserved up front, the output buffer is not allocated
+    // during the decode.
+    let width = decode_with(&data, &DecoderConfig::new(), &Unstoppable)
+        .unwrap()
+        .width();
+    let preallocated = measure_preallocated_decode(&data, width, height);
+    eprintln!("{preallocated:#?}");
+    let output_bytes = width as u64 * height as u64 * 3;
+    assert!(
+        stats.convert.bytes >= output_bytes,
+        "convert: {} bytes",
+        stats.convert.bytes
+    );
+    assert!(
+        preallocated.convert.bytes < output_bytes / 4,
+        "convert after preallocate: {} bytes",
+        preallocated.convert.bytes
+    );
+}
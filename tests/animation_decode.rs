@@ -6,7 +6,9 @@
 use almost_enough::StopExt;
 use almost_enough::Unstoppable;
 use std::fs;
-use zenavif::{AnimationDecoder, DecoderConfig, decode_animation, decode_animation_with};
+use zenavif::{
+    AnimationDecoder, AnimationLimitPolicy, DecoderConfig, decode_animation, decode_animation_with,
+};
 
 /// Load a test vector, returning None if the file doesn't exist (CI without vectors).
 fn load_vector(path: &str) -> Option<Vec<u8>> {
@@ -131,6 +133,100 @@ fn decode_8bpc_audio_track_skipped() {
     );
 }
 
+#[test]
+fn audio_track_is_extractable() {
+    let data = require_vector!(animated_vector("colors-animated-8bpc-audio.avif"));
+    assert!(zenavif::has_audio(&data));
+
+    let track = zenavif::extract_audio_track(&data)
+        .unwrap()
+        .expect("audio track should be present");
+    assert!(track.codec.iter().all(u8::is_ascii_graphic));
+    assert!(track.timescale > 0);
+    assert!(!track.samples.is_empty());
+    assert!(track.samples.iter().all(|s| !s.data.is_empty()));
+
+    let silent = require_vector!(animated_vector("colors-animated-8bpc.avif"));
+    assert!(!zenavif::has_audio(&silent));
+    assert!(zenavif::extract_audio_track(&silent).unwrap().is_none());
+}
+
+#[test]
+fn remux_to_fragmented_mp4() {
+    let data = require_vector!(animated_vector("colors-animated-8bpc.avif"));
+    let mp4 = zenavif::to_av1_mp4(&data).unwrap();
+
+    assert_eq!(&mp4[4..8], b"ftyp");
+    assert!(mp4.windows(4).any(|w| w == b"av1C"));
+    let fragments = mp4.windows(4).filter(|w| *w == b"moof").count();
+    assert!(fragments >= 1, "expected at least one fragment");
+}
+
+#[test]
+fn mux_av1_stream_roundtrip() {
+    let data = require_vector!(animated_vector("colors-animated-8bpc.avif"));
+    let parser = zenavif_parse::AvifParser::from_bytes(&data).unwrap();
+    let count = parser.animation_info().unwrap().frame_count;
+    let frames: Vec<_> = (0..count).map(|i| parser.frame(i).unwrap()).collect();
+    let stream: Vec<(&[u8], u32)> = frames
+        .iter()
+        .map(|f| (&f.data[..], f.duration_ms))
+        .collect();
+
+    let config = zenavif::MuxConfig::new().loop_count(2);
+    let avis = zenavif::from_av1_stream(&stream, &config).unwrap();
+    let original = decode_animation(&data).unwrap();
+    let muxed = decode_animation(&avis).unwrap();
+
+    assert_eq!(muxed.frames.len(), original.frames.len());
+    assert_eq!(muxed.info.loop_count, 2);
+    for (a, b) in muxed.frames.iter().zip(&original.frames) {
+        assert_eq!(a.duration_ms, b.duration_ms);
+        assert_eq!(a.pixels.width(), b.pixels.width());
+        assert_eq!(a.pixels.height(), b.pixels.height());
+    }
+}
+
+/// Frames of both animations match byte for byte.
+#[cfg(feature = "encode")]
+fn assert_same_frames(a: &[u8], b: &[u8]) {
+    let (a, b) = (decode_animation(a).unwrap(), decode_animation(b).unwrap());
+    assert_eq!(a.frames.len(), b.frames.len());
+    for (a, b) in a.frames.iter().zip(&b.frames) {
+        assert_eq!(a.duration_ms, b.duration_ms);
+        assert_eq!(
+            a.pixels.as_slice().contiguous_bytes(),
+            b.pixels.as_slice().contiguous_bytes()
+        );
+    }
+}
+
+#[cfg(feature = "encode")]
+#[test]
+fn rewrite_animation_metadata_keeps_frames() {
+    let data = require_vector!(animated_vector("colors-animated-8bpc-alpha-exif-xmp.avif"));
+    let exif = b"\0\0\0\0MM\0*\0\0\0\x08\0\0".to_vec();
+    let edit = zenavif::MetadataEdit::new().exif(exif.clone());
+    let edited = zenavif::rewrite_animation_metadata(&data, &edit).unwrap();
+    assert_same_frames(&data, &edited);
+    assert_eq!(zenavif::probe(&edited).unwrap().exif.unwrap(), exif[4..]);
+
+    let parser = zenavif_parse::AvifParser::from_bytes(&data).unwrap();
+    let count = parser.animation_info().unwrap().frame_count;
+    let frames: Vec<_> = (0..count).map(|i| parser.frame(i).unwrap()).collect();
+    let stream: Vec<(&[u8], u32)> = frames
+        .iter()
+        .map(|f| (&f.data[..], f.duration_ms))
+        .collect();
+    let avis = zenavif::from_av1_stream(&stream, &zenavif::MuxConfig::new().loop_count(1)).unwrap();
+    let edit = zenavif::MetadataEdit::new()
+        .xmp(b"<x:xmpmeta/>".to_vec())
+        .loop_count(0);
+    let edited = zenavif::rewrite_animation_metadata(&avis, &edit).unwrap();
+    assert_same_frames(&avis, &edited);
+    assert_eq!(decode_animation(&edited).unwrap().info.loop_count, 0);
+}
+
 #[test]
 fn decode_8bpc_depth() {
     let data = require_vector!(animated_vector("colors-animated-8bpc-depth-exif-xmp.avif"));
@@ -144,6 +240,33 @@ fn decode_8bpc_depth() {
     );
 }
 
+#[test]
+fn decode_depth_auxiliary() {
+    let data = require_vector!(animated_vector("colors-animated-8bpc-depth-exif-xmp.avif"));
+    let mut decoder = zenavif::ManagedAvifDecoder::new(&data, &DecoderConfig::new()).unwrap();
+    assert!(decoder.probe_info().unwrap().has_depth());
+
+    let depth = decoder
+        .decode_auxiliary(zenavif::AuxiliaryType::Depth, &Unstoppable)
+        .unwrap();
+    assert!(
+        depth
+            .descriptor()
+            .layout_compatible(zenpixels::PixelDescriptor::GRAY8)
+    );
+    assert!(depth.width() > 0 && depth.height() > 0);
+
+    // Files without a depth image report it up front and refuse the decode.
+    let data = require_vector!(animated_vector("colors-animated-8bpc.avif"));
+    let mut decoder = zenavif::ManagedAvifDecoder::new(&data, &DecoderConfig::new()).unwrap();
+    assert!(!decoder.probe_info().unwrap().has_depth());
+    assert!(
+        decoder
+            .decode_auxiliary(zenavif::AuxiliaryType::Depth, &Unstoppable)
+            .is_err()
+    );
+}
+
 #[test]
 fn still_image_returns_unsupported() {
     // A non-animated AVIF should return Error::Unsupported
@@ -179,6 +302,66 @@ fn frame_durations_sum_positive() {
     );
 }
 
+#[test]
+fn frame_and_duration_limits() {
+    let data = require_vector!(animated_vector("colors-animated-8bpc.avif"));
+    let full = decode_animation(&data).unwrap();
+
+    let err = decode_animation_with(&data, &DecoderConfig::new().max_frames(1), &Unstoppable)
+        .unwrap_err();
+    assert!(
+        matches!(err.error(), zenavif::Error::ResourceLimit(_)),
+        "{err}"
+    );
+
+    let truncated = DecoderConfig::new()
+        .max_frames(1)
+        .animation_limit_policy(AnimationLimitPolicy::Truncate);
+    let anim = decode_animation_with(&data, &truncated, &Unstoppable).unwrap();
+    assert_eq!(anim.frames.len(), 1);
+    assert_eq!(anim.info.frame_count, 1);
+    let mut decoder = AnimationDecoder::new(&data, &truncated).unwrap();
+    assert_eq!(decoder.info().frame_count, 1);
+    let first = decoder.next_frame(&Unstoppable).unwrap().unwrap();
+    assert_eq!(
+        first.pixels.as_slice().row(0),
+        anim.frames[0].pixels.as_slice().row(0)
+    );
+    assert!(decoder.next_frame(&Unstoppable).unwrap().is_none());
+
+    // Room for exactly the first frame.
+    let first_ms = u64::from(full.frames[0].duration_ms);
+    let by_duration = DecoderConfig::new()
+        .max_total_duration_ms(first_ms)
+        .animation_limit_policy(AnimationLimitPolicy::Truncate);
+    let anim = decode_animation_with(&data, &by_duration, &Unstoppable).unwrap();
+    assert_eq!(anim.frames.len(), 1);
+    let strict = DecoderConfig::new().max_total_duration_ms(first_ms);
+    assert!(decode_animation_with(&data, &strict, &Unstoppable).is_err());
+}
+
+#[test]
+fn frame_durations_are_sanitized() {
+    let data = require_vector!(animated_vector("colors-animated-8bpc.avif"));
+    let full = decode_animation(&data).unwrap();
+    let longest = full.frames.iter().map(|f| f.duration_ms).max().unwrap();
+
+    let floor = DecoderConfig::new().min_frame_duration_ms(longest + 1);
+    let anim = decode_animation_with(&data, &floor, &Unstoppable).unwrap();
+    assert!(anim.frames.iter().all(|f| f.duration_ms == longest + 1));
+
+    let capped = DecoderConfig::new().max_frame_duration_ms(1);
+    let err = decode_animation_with(&data, &capped, &Unstoppable).unwrap_err();
+    assert!(
+        matches!(err.error(), zenavif::Error::ResourceLimit(_)),
+        "{err}"
+    );
+    let capped = capped.animation_limit_policy(AnimationLimitPolicy::Truncate);
+    let anim = decode_animation_with(&data, &capped, &Unstoppable).unwrap();
+    assert_eq!(anim.frames.len(), full.frames.len());
+    assert!(anim.frames.iter().all(|f| f.duration_ms == 1));
+}
+
 #[test]
 fn decode_12bpc_produces_16bit_with_full_range() {
     let data = require_vector!(animated_vector(
@@ -290,6 +473,40 @@ fn animation_encode_decode_roundtrip_rgb8() {
     }
 }
 
+#[cfg(feature = "encode")]
+#[test]
+fn remuxed_mp4_signals_sequence_colour() {
+    use imgref::ImgVec;
+    use rgb::RGB8;
+    use zenavif::{AnimationFrame, EncoderConfig, encode_animation_rgb8};
+
+    let frames: Vec<AnimationFrame> = [0u8, 255]
+        .iter()
+        .map(|&v| AnimationFrame {
+            pixels: ImgVec::new(vec![RGB8 { r: v, g: 128, b: 0 }; 32 * 32], 32, 32),
+            duration_ms: 100,
+        })
+        .collect();
+    let config = EncoderConfig::new().speed(10);
+    let encoded = encode_animation_rgb8(&frames, &config, Unstoppable.into_token()).unwrap();
+    let info = zenavif::probe(&encoded.avif_file).unwrap();
+    let mp4 = zenavif::to_av1_mp4(&encoded.avif_file).unwrap();
+
+    let colr = mp4.windows(8).position(|w| w == b"colrnclx").unwrap() + 8;
+    assert_eq!(
+        mp4[colr..colr + 7],
+        [
+            0,
+            info.color_primaries.0,
+            0,
+            info.transfer_characteristics.0,
+            0,
+            info.matrix_coefficients.0,
+            u8::from(info.color_range == zenavif::ColorRange::Full) << 7,
+        ]
+    );
+}
+
 #[cfg(feature = "encode")]
 #[test]
 fn animation_encode_decode_roundtrip_rgba8() {
@@ -357,6 +574,67 @@ fn animation_encode_decode_roundtrip_rgba8() {
     }
 }
 
+#[cfg(feature = "encode")]
+#[test]
+fn pipelined_animation_matches_sequential() {
+    use enough::StopReason;
+    use imgref::ImgVec;
+    use rgb::RGBA8;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use zenavif::{AnimationFrameRgba, EncoderConfig, encode_animation_rgba8};
+
+    let frames: Vec<AnimationFrameRgba> = (0..5u8)
+        .map(|i| AnimationFrameRgba {
+            pixels: ImgVec::new(
+                (0..32 * 32)
+                    .map(|p| RGBA8::new(i * 50, (p % 32 * 8) as u8, (p / 32 * 8) as u8, 100 + i))
+                    .collect(),
+                32,
+                32,
+            ),
+            duration_ms: 40 + u32::from(i),
+        })
+        .collect();
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_animation_rgba8(&frames, &config, Unstoppable.into_token()).unwrap();
+
+    let sequential = decode_animation(&encoded.avif_file).unwrap();
+    let pipelined = decode_animation_with(
+        &encoded.avif_file,
+        &DecoderConfig::new().pipelined_animation(true),
+        &Unstoppable,
+    )
+    .unwrap();
+    assert_eq!(pipelined.frames.len(), 5);
+    for (a, b) in sequential.frames.iter().zip(&pipelined.frames) {
+        assert_eq!(a.duration_ms, b.duration_ms);
+        assert_eq!(
+            a.pixels.as_slice().contiguous_bytes(),
+            b.pixels.as_slice().contiguous_bytes()
+        );
+    }
+
+    struct StopAfter(AtomicUsize);
+
+    impl enough::Stop for StopAfter {
+        fn check(&self) -> std::result::Result<(), StopReason> {
+            if self.0.fetch_add(1, Ordering::Relaxed) >= 4 {
+                Err(StopReason::Cancelled)
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    let err = decode_animation_with(
+        &encoded.avif_file,
+        &DecoderConfig::new().pipelined_animation(true),
+        &StopAfter(AtomicUsize::new(0)),
+    )
+    .unwrap_err();
+    assert!(matches!(err.error(), zenavif::Error::Cancelled(_)));
+}
+
 // ---- AnimationDecoder (frame-by-frame) tests ----
 
 #[test]
@@ -433,6 +711,58 @@ fn frame_by_frame_12bpc() {
     eprintln!("frame-by-frame 12bpc: decoded {decoded_count} frames");
 }
 
+#[test]
+fn seek_restarts_from_keyframes() {
+    let data = require_vector!(animated_vector(
+        "colors-animated-12bpc-keyframes-0-2-3.avif"
+    ));
+    let config = DecoderConfig::new().threads(1);
+    let batch = decode_animation_with(&data, &config, &Unstoppable).unwrap();
+
+    let mut decoder = AnimationDecoder::new(&data, &config).unwrap();
+    assert_eq!(decoder.keyframe_at_or_before(1).unwrap(), 0);
+    assert_eq!(decoder.keyframe_at_or_before(2).unwrap(), 2);
+    assert_eq!(decoder.keyframe_at_or_before(3).unwrap(), 3);
+
+    let total = decoder.info().frame_count;
+    for index in [total - 1, 1, 2, 0, total - 1] {
+        decoder.seek(index, &Unstoppable).unwrap();
+        let frame = decoder.next_frame(&Unstoppable).unwrap().unwrap();
+        assert_eq!(
+            frame.pixels.as_slice().contiguous_bytes(),
+            batch.frames[index].pixels.as_slice().contiguous_bytes(),
+            "frame {index} differs after seek"
+        );
+    }
+    assert!(decoder.seek(total, &Unstoppable).is_err());
+}
+
+#[test]
+fn cached_animation_evicts_least_recently_used() {
+    let data = require_vector!(animated_vector("colors-animated-8bpc.avif"));
+    let config = DecoderConfig::new().threads(1);
+    let batch = decode_animation_with(&data, &config, &Unstoppable).unwrap();
+    let first = &batch.frames[0].pixels;
+    let frame_bytes =
+        first.width() as usize * first.height() as usize * first.descriptor().bytes_per_pixel();
+
+    let mut cache = zenavif::CachedAnimation::new(&data, &config, 2 * frame_bytes).unwrap();
+    let last = cache.info().frame_count - 1;
+    for index in [last, 0, last, 1, 0] {
+        let frame = cache.frame(index, &Unstoppable).unwrap();
+        assert_eq!(
+            frame.pixels.as_slice().contiguous_bytes(),
+            batch.frames[index].pixels.as_slice().contiguous_bytes(),
+            "frame {index} differs"
+        );
+        assert!(cache.cached_frames() <= 2);
+    }
+    assert_eq!(cache.cached_bytes(), 2 * frame_bytes);
+    assert!(cache.frame(last + 1, &Unstoppable).is_err());
+    cache.clear();
+    assert_eq!(cache.cached_frames(), 0);
+}
+
 #[test]
 fn frame_by_frame_cancellation() {
     use enough::StopReason;
@@ -631,3 +961,72 @@ fn animation_encode_decode_roundtrip_rgba16() {
         assert!(is_rgba16, "frame {i} should be RGBA16 for 10-bit source");
     }
 }
+
+#[cfg(feature = "encode")]
+#[test]
+fn animation_encode_yuv420_frames() {
+    use imgref::Img;
+    use zenavif::{
+        AnimationFrameYuv420, ColorPrimaries, ColorRange, EncoderConfig, MatrixCoefficients,
+        TransferCharacteristics, YuvCicp, encode_animation_yuv420,
+    };
+
+    // Odd dimensions: chroma planes round up to 17x9.
+    let (w, h) = (33, 17);
+    // BT.709 limited-range red and blue.
+    let planes = [(63u8, 102u8, 240u8), (32, 240, 118)];
+    let stored: Vec<_> = planes
+        .iter()
+        .map(|&(y, cb, cr)| (vec![y; w * h], vec![cb; 17 * 9], vec![cr; 17 * 9]))
+        .collect();
+    let frames: Vec<AnimationFrameYuv420> = stored
+        .iter()
+        .map(|(y, cb, cr)| AnimationFrameYuv420 {
+            y: Img::new(&y[..], w, h),
+            cb: Img::new(&cb[..], 17, 9),
+            cr: Img::new(&cr[..], 17, 9),
+            duration_ms: 40,
+        })
+        .collect();
+    let cicp = YuvCicp {
+        color_primaries: ColorPrimaries::BT709,
+        transfer_characteristics: TransferCharacteristics::BT709,
+        matrix_coefficients: MatrixCoefficients::BT709,
+        range: ColorRange::Limited,
+    };
+
+    let config = EncoderConfig::new().quality(90.0).speed(10);
+    let encoded =
+        encode_animation_yuv420(&frames, cicp, &config, Unstoppable.into_token()).unwrap();
+    assert_eq!(encoded.frame_count, 2);
+    assert_eq!(encoded.total_duration_ms, 80);
+
+    let decoded = decode_animation(&encoded.avif_file).unwrap();
+    assert_eq!(decoded.info.frame_count, 2);
+    // zenravif writes no colr box for animations, so the CICP is only in
+    // the AV1 sequence header, which a decode reads and a probe doesn't.
+    let (_, info) = zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &DecoderConfig::new())
+        .unwrap()
+        .decode_full(&Unstoppable)
+        .unwrap();
+    assert_eq!(info.transfer_characteristics, cicp.transfer_characteristics);
+    assert_eq!(info.color_primaries, cicp.color_primaries);
+    for (i, frame) in decoded.frames.iter().enumerate() {
+        // 10-bit content decodes to 16-bit samples.
+        let img = frame.pixels.try_as_imgref::<rgb::Rgb<u16>>().unwrap();
+        let p = img.buf()[img.stride() * 8 + 16];
+        let (hi, lo) = if i == 0 {
+            (p.r, p.g.max(p.b))
+        } else {
+            (p.b, p.r.max(p.g))
+        };
+        assert!(lo < hi / 8, "frame {i}: {p:?}");
+    }
+
+    // A 4:2:0 chroma plane at luma size is rejected.
+    let bad = AnimationFrameYuv420 {
+        cb: Img::new(&stored[0].0[..], w, h),
+        ..frames[0]
+    };
+    assert!(encode_animation_yuv420(&[bad], cicp, &config, Unstoppable.into_token()).is_err());
+}
@@ -0,0 +1,45 @@
+//! Walking a directory of AVIF files with batch::walk
+
+#![cfg(all(feature = "fs", feature = "encode"))]
+
+use almost_enough::{StopToken, Unstoppable};
+use imgref::Img;
+use rgb::Rgb;
+use zenavif::{DecoderConfig, EncoderConfig, encode_rgb8};
+
+#[test]
+fn walk_probes_avif_files_in_name_order() {
+    let pixels = vec![Rgb::new(200u8, 40, 90); 24 * 16];
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgb8(
+        Img::new(pixels.as_slice(), 24, 16),
+        &config,
+        StopToken::new(Unstoppable),
+    )
+    .unwrap();
+
+    let root = std::env::temp_dir().join(format!("zenavif-walk-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("sub")).unwrap();
+    std::fs::write(root.join("a.avif"), &encoded.avif_file).unwrap();
+    std::fs::write(root.join("broken.AVIF"), b"not an avif").unwrap();
+    std::fs::write(root.join("sub/b.avif"), &encoded.avif_file).unwrap();
+    std::fs::write(root.join("sub/notes.txt"), b"skipped").unwrap();
+
+    let entries: Vec<_> = zenavif::batch::walk(&root)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["a.avif", "broken.AVIF", "sub/b.avif"]);
+
+    let info = entries[0].info.as_ref().unwrap();
+    assert_eq!((info.width, info.height), (24, 16));
+    assert!(entries[1].info.is_err());
+    let decoded = entries[2]
+        .decode(&DecoderConfig::new(), &Unstoppable)
+        .unwrap();
+    assert_eq!(decoded.pixels.width(), 24);
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
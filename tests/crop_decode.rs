@@ -0,0 +1,71 @@
+//! Region-of-interest decoding through DecoderConfig::crop
+
+use almost_enough::Unstoppable;
+use zenavif::{DecoderConfig, PlaneCopy, Rect, decode_with};
+
+/// `decode_with(config.crop(rect))` must match a full decode cropped
+/// afterwards.
+fn assert_crop_matches(data: &[u8], rect: Rect) {
+    let full = decode_with(data, &DecoderConfig::new(), &Unstoppable).unwrap();
+    let config = DecoderConfig::new().crop(rect.x, rect.y, rect.width, rect.height);
+    let cropped = decode_with(data, &config, &Unstoppable).unwrap();
+    let expected = full.crop(rect).unwrap();
+    assert_eq!(
+        (cropped.width(), cropped.height()),
+        (expected.width(), expected.height())
+    );
+    assert_eq!(
+        cropped.as_slice().contiguous_bytes(),
+        expected.as_slice().contiguous_bytes()
+    );
+}
+
+#[test]
+fn grid_crop_matches_full_decode() {
+    let path = "tests/vectors/libavif/sofa_grid1x5_420.avif";
+    let Ok(data) = std::fs::read(path) else {
+        eprintln!("skipping: {path} not found (download with: just download-vectors)");
+        return;
+    };
+    let full = decode_with(&data, &DecoderConfig::new(), &Unstoppable).unwrap();
+    let (width, height) = (full.width(), full.height());
+
+    // Inside one tile, across a tile boundary, and running off the edge.
+    assert_crop_matches(&data, Rect::new(3, 2, 10, 10));
+    assert_crop_matches(
+        &data,
+        Rect::new(width / 3, height / 4, width / 3, height / 2),
+    );
+    assert_crop_matches(&data, Rect::new(width - 7, height - 5, 100, 100));
+
+    let config = DecoderConfig::new().crop(width, 0, 4, 4);
+    assert!(decode_with(&data, &config, &Unstoppable).is_err());
+}
+
+#[cfg(feature = "encode")]
+#[test]
+fn still_crop_matches_full_decode() {
+    use almost_enough::StopToken;
+    use imgref::Img;
+    use rgb::Rgba;
+
+    let pixels: Vec<Rgba<u8>> = (0..40 * 30)
+        .map(|i| {
+            Rgba::new(
+                (i % 40 * 6) as u8,
+                (i / 40 * 8) as u8,
+                50,
+                (i % 7 * 30) as u8,
+            )
+        })
+        .collect();
+    let encoded = zenavif::encode_rgba8(
+        Img::new(pixels.as_slice(), 40, 30),
+        &zenavif::EncoderConfig::new().quality(80.0).speed(10),
+        StopToken::new(Unstoppable),
+    )
+    .unwrap();
+
+    assert_crop_matches(&encoded.avif_file, Rect::new(5, 7, 20, 10));
+    assert_crop_matches(&encoded.avif_file, Rect::new(30, 20, 50, 50));
+}
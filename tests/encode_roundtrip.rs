@@ -6,9 +6,11 @@ use almost_enough::{StopToken, Unstoppable};
 use imgref::Img;
 use rgb::{Rgb, Rgba};
 use zenavif::{
-    EncodeBitDepth, EncodeColorModel, EncoderConfig, PixelBuffer, encode, encode_rgb8,
+    ChromaSampling, ColorRange, EncodeBitDepth, EncodeColorModel, EncodePixelRange, EncoderConfig,
+    MatrixCoefficients, PixelBuffer, YuvPlane, encode, encode_rgb_with_alpha_plane, encode_rgb8,
     encode_rgb16, encode_rgba8, encode_rgba16, encode_with,
 };
+use zenpixels_convert::PixelBufferConvertTypedExt as _;
 
 fn stop() -> StopToken {
     StopToken::new(Unstoppable)
@@ -79,6 +81,47 @@ fn roundtrip_rgba8() {
     assert!(decoded.has_alpha());
 }
 
+#[test]
+fn straight_alpha_is_not_transformed() {
+    let img = make_rgba8_image();
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgba8(img.as_ref(), &config, stop()).expect("encode should succeed");
+    let mut decoder =
+        zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &zenavif::DecoderConfig::new())
+            .unwrap();
+    let (pixels, info) = decoder.decode_full(&Unstoppable).unwrap();
+    assert!(pixels.has_alpha());
+    assert!(!info.premultiplied_alpha);
+    assert_eq!(info.alpha_transformed, None);
+}
+
+#[test]
+fn decode_alpha_only_matches_full_decode() {
+    let img = make_rgba8_image();
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgba8(img.as_ref(), &config, stop()).expect("encode should succeed");
+    let decoder_config = zenavif::DecoderConfig::new();
+
+    let mut decoder =
+        zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &decoder_config).unwrap();
+    let alpha = decoder.decode_alpha_only(&Unstoppable).unwrap();
+    let alpha = alpha
+        .try_as_imgref::<rgb::Gray<u8>>()
+        .expect("8-bit alpha decodes to Gray8");
+    assert_eq!((alpha.width(), alpha.height()), (16, 16));
+
+    let full = zenavif::decode(&encoded.avif_file).unwrap();
+    let full = full.try_as_imgref::<Rgba<u8>>().unwrap();
+    for (a, p) in alpha.pixels().map(|a| a.value()).zip(full.pixels()) {
+        assert_eq!(a, p.a);
+        assert!(a.abs_diff(200) <= 2, "alpha {a}");
+    }
+
+    let opaque = encode_rgb8(make_rgb8_image().as_ref(), &config, stop()).unwrap();
+    let mut decoder = zenavif::ManagedAvifDecoder::new(&opaque.avif_file, &decoder_config).unwrap();
+    assert!(decoder.decode_alpha_only(&Unstoppable).is_err());
+}
+
 #[test]
 fn convenience_encode_rgb8() {
     let img = make_rgb8_image();
@@ -104,6 +147,133 @@ fn convenience_encode_rgba8() {
     assert!(roundtrip.has_alpha());
 }
 
+#[test]
+fn unstoppable_variants_match_cancellable_path() {
+    let img = make_rgb8_image();
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded =
+        zenavif::encode_rgb8_unstoppable(img.as_ref(), &config).expect("encode should succeed");
+    let with_stop = encode_rgb8(img.as_ref(), &config, stop()).expect("encode should succeed");
+    assert_eq!(encoded.avif_file, with_stop.avif_file);
+
+    let decoder_config = zenavif::DecoderConfig::new();
+    let decoded = zenavif::decode_with_unstoppable(&encoded.avif_file, &decoder_config)
+        .expect("decode should succeed");
+    let image = zenavif::decode_image_with_unstoppable(&encoded.avif_file, &decoder_config)
+        .expect("decode should succeed");
+    assert_eq!(
+        decoded.as_slice().contiguous_bytes(),
+        image.pixels.as_slice().contiguous_bytes()
+    );
+
+    let options = zenavif::PlanarOptions::new();
+    let planar =
+        zenavif::decode_planar_unstoppable(&encoded.avif_file, &decoder_config, &options).unwrap();
+    let planar_with_stop =
+        zenavif::decode_planar(&encoded.avif_file, &decoder_config, &options, &stop()).unwrap();
+    assert_eq!(planar.plane_u8(0), planar_with_stop.plane_u8(0));
+}
+
+/// A flat gray still whose stream asks the decoder to synthesize film grain;
+/// zenavif's encoder doesn't write grain, so it goes to zenrav1e directly.
+fn grainy_avif() -> Vec<u8> {
+    use zenrav1e::prelude::*;
+
+    let (width, height) = (64, 64);
+    let grain = generate_photon_noise_params(
+        0,
+        u64::MAX,
+        NoiseGenArgs {
+            iso_setting: 6400,
+            width: width as u32,
+            height: height as u32,
+            transfer_function: TransferFunction::BT1886,
+            chroma_grain: false,
+            random_seed: None,
+        },
+    );
+    let encoder = zenrav1e::prelude::EncoderConfig {
+        width,
+        height,
+        chroma_sampling: ChromaSampling::Cs400,
+        pixel_range: PixelRange::Full,
+        still_picture: true,
+        quantizer: 100,
+        film_grain_params: Some(vec![grain]),
+        ..zenrav1e::prelude::EncoderConfig::with_speed_preset(10)
+    };
+    let mut ctx: Context<u8> = Config::new()
+        .with_encoder_config(encoder)
+        .new_context()
+        .unwrap();
+    let mut frame = ctx.new_frame();
+    frame.planes[0].data.fill(128);
+    ctx.send_frame(frame).unwrap();
+    ctx.flush();
+    let mut av1 = Vec::new();
+    while let Ok(mut packet) = ctx.receive_packet() {
+        av1.append(&mut packet.data);
+    }
+
+    let mut aviffy = zenavif_serialize::Aviffy::new();
+    aviffy
+        .set_monochrome(true)
+        .set_chroma_subsampling((true, true))
+        .set_full_color_range(true);
+    aviffy.to_vec(&av1, None, width as u32, height as u32, 8)
+}
+
+#[test]
+fn deterministic_decode_matches_threaded() {
+    let avif = grainy_avif();
+    let decode = |config: zenavif::DecoderConfig| {
+        zenavif::decode_with(&avif, &config, &Unstoppable)
+            .expect("decode should succeed")
+            .as_slice()
+            .contiguous_bytes()
+            .to_vec()
+    };
+    let threaded = decode(zenavif::DecoderConfig::new().threads(4));
+    assert_ne!(
+        threaded,
+        decode(zenavif::DecoderConfig::new().apply_grain(false)),
+        "the stream should carry film grain"
+    );
+
+    let deterministic = zenavif::DecoderConfig::new().threads(4).deterministic(true);
+    assert_eq!(decode(deterministic.clone()), threaded);
+    assert_eq!(decode(deterministic.clone()), decode(deterministic.clone()));
+
+    let seeded = decode(deterministic.clone().grain_seed_override(7));
+    assert_eq!(decode(deterministic.clone().grain_seed_override(7)), seeded);
+    assert_ne!(seeded, threaded, "seed 7 should replace the stream's seed");
+    assert_ne!(decode(deterministic.grain_seed_override(8)), seeded);
+}
+
+#[test]
+fn oversized_image_fails_before_encoding() {
+    let config = EncoderConfig::new().speed(10);
+    let wide = Img::new(vec![Rgb { r: 0u8, g: 0, b: 0 }; 70_000], 70_000, 1);
+    let err = encode_rgb8(wide.as_ref(), &config, stop()).unwrap_err();
+    match err.error() {
+        zenavif::Error::Encode(msg) => assert!(msg.contains("grid"), "{msg}"),
+        other => panic!("unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn empty_image_fails_before_encoding() {
+    let config = EncoderConfig::new().speed(10);
+    for (w, h) in [(16, 0), (0, 16)] {
+        let empty: Img<Vec<Rgba<u8>>> = Img::new_stride(Vec::new(), w, h, 16);
+        let err = encode_rgba8(empty.as_ref(), &config, stop()).unwrap_err();
+        match err.error() {
+            zenavif::Error::Encode(msg) => assert!(msg.contains("nonzero"), "{msg}"),
+            other => panic!("unexpected error for {w}x{h}: {other:?}"),
+        }
+    }
+}
+
 #[test]
 fn encoder_config_builder_chains() {
     let config = EncoderConfig::new()
@@ -198,18 +368,38 @@ fn convenience_encode_rgb16() {
 }
 
 #[test]
-fn unsupported_grayscale_input() {
-    let pixels: Vec<rgb::Gray<u8>> = vec![rgb::Gray::new(128); 4];
-    let img = Img::new(pixels, 2, 2);
+fn grayscale_input_decodes_to_gray() {
+    let pixels: Vec<rgb::Gray<u8>> = (0..64u8).map(|i| rgb::Gray::new(i * 4)).collect();
+    let img = Img::new(pixels, 8, 8);
     let pb: PixelBuffer = zenpixels::PixelBuffer::from_imgvec(img).into();
+    let config = EncoderConfig::new().quality(100.0).speed(10);
 
-    let result = encode(&pb);
-    assert!(result.is_err());
-    let err = result.unwrap_err();
-    assert!(
-        err.to_string().contains("encoding is supported"),
-        "error should mention grayscale: {err}"
-    );
+    let encoded = encode_with(&pb, &config, stop()).expect("gray encode should succeed");
+    let info = zenavif::probe(&encoded.avif_file).unwrap();
+    assert_eq!(info.bit_depth, 8);
+    assert_eq!(info.chroma_sampling, ChromaSampling::Monochrome);
+    let decoder_config = zenavif::DecoderConfig::new().output_depth(zenavif::OutputDepth::Force8);
+    let mut decoder =
+        zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &decoder_config).unwrap();
+    let (decoded, _) = decoder.decode_full(&Unstoppable).unwrap();
+    let rgb = decoded.try_as_imgref::<Rgb<u8>>().expect("decodes to RGB8");
+    for (i, px) in rgb.pixels().enumerate() {
+        assert!(
+            px.r.abs_diff(px.g) <= 1 && px.g.abs_diff(px.b) <= 1,
+            "{px:?}"
+        );
+        assert!(px.g.abs_diff(i as u8 * 4) <= 12, "pixel {i}: {px:?}");
+    }
+
+    let gray16: Vec<rgb::Gray<u16>> = (0..64u16).map(|i| rgb::Gray::new(i * 1024)).collect();
+    let encoded = zenavif::encode_gray16(Img::new(gray16, 8, 8).as_ref(), &config, stop()).unwrap();
+    let info = zenavif::probe(&encoded.avif_file).unwrap();
+    assert_eq!(info.bit_depth, 10);
+    assert_eq!(info.chroma_sampling, ChromaSampling::Monochrome);
+
+    let gray16 = Img::new(vec![rgb::Gray::new(40000u16); 64], 8, 8);
+    let encoded = zenavif::encode_gray16(gray16.as_ref(), &config, stop()).unwrap();
+    assert_eq!(zenavif::probe(&encoded.avif_file).unwrap().bit_depth, 10);
 }
 
 #[test]
@@ -221,3 +411,987 @@ fn encode_with_custom_config() {
     let encoded = encode_with(&pb, &config, stop()).expect("encode_with should succeed");
     assert!(!encoded.avif_file.is_empty());
 }
+
+#[test]
+fn grid_threshold_splits_large_images_into_tiles() {
+    // 160x100 at a 64-pixel threshold: a 3x2 grid of 64x64 tiles, with the
+    // last column and row padded.
+    let pixels: Vec<Rgb<u8>> = (0..100u32)
+        .flat_map(|y| {
+            (0..160u32).map(move |x| Rgb {
+                r: (x * 255 / 159) as u8,
+                g: (y * 255 / 99) as u8,
+                b: 96,
+            })
+        })
+        .collect();
+    let original = Img::new(pixels, 160, 100);
+    let pb: PixelBuffer = zenpixels::PixelBuffer::from_imgvec(original.clone()).into();
+    let config = EncoderConfig::new()
+        .quality(95.0)
+        .speed(10)
+        .grid_threshold(64);
+    let encoded = encode_with(&pb, &config, stop()).expect("grid encode should succeed");
+    assert!(encoded.avif_file.windows(4).any(|w| w == b"grid"));
+
+    let decoded = zenavif::decode(&encoded.avif_file).expect("decode should succeed");
+    assert_eq!((decoded.width(), decoded.height()), (160, 100));
+    let decoded = decoded.to_rgba8();
+    let mut total_error = 0u64;
+    for (a, b) in original.pixels().zip(decoded.as_imgref().pixels()) {
+        total_error += u64::from(a.r.abs_diff(b.r))
+            + u64::from(a.g.abs_diff(b.g))
+            + u64::from(a.b.abs_diff(b.b));
+    }
+    let mean_error = total_error as f64 / (160.0 * 100.0 * 3.0);
+    assert!(mean_error < 4.0, "mean error {mean_error}");
+}
+
+#[test]
+fn opaque_tiles_of_translucent_grid_keep_full_alpha() {
+    // The left tile is translucent, the right one fully opaque.
+    let pixels: Vec<Rgba<u8>> = (0..128 * 64)
+        .map(|i| Rgba {
+            r: (i % 128) as u8,
+            g: (i / 128 * 4) as u8,
+            b: 90,
+            a: if i % 128 < 64 { 128 } else { 255 },
+        })
+        .collect();
+    let pb: PixelBuffer = zenpixels::PixelBuffer::from_imgvec(Img::new(pixels, 128, 64)).into();
+    let config = EncoderConfig::new().speed(10).grid_threshold(64);
+    let encoded = encode_with(&pb, &config, stop()).unwrap();
+
+    let decoded = zenavif::decode(&encoded.avif_file).unwrap().to_rgba8();
+    for (i, px) in decoded.as_imgref().pixels().enumerate() {
+        if i % 128 >= 64 {
+            assert_eq!(px.a, 255, "pixel {i}");
+        }
+    }
+}
+
+#[test]
+fn test_patterns_roundtrip_through_encode_with() {
+    use zenavif::testimg;
+
+    type Pattern = fn(u32, u32, u8) -> zenavif::Result<PixelBuffer>;
+    let config = EncoderConfig::new().quality(90.0).speed(10);
+    for depth in [8, 10] {
+        let patterns: [(&str, Pattern); 4] = [
+            ("gradient", testimg::gradient),
+            ("color_bars", testimg::color_bars),
+            ("zone_plate", testimg::zone_plate),
+            ("noise", testimg::noise),
+        ];
+        for (name, pattern) in patterns {
+            let image = pattern(64, 48, depth).unwrap();
+            let encoded = encode_with(&image, &config, stop())
+                .unwrap_or_else(|e| panic!("{name} at {depth} bits: {e}"));
+            let decoded = zenavif::decode(&encoded.avif_file).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (64, 48), "{name}");
+        }
+    }
+
+    // The white bar survives the round trip at 75%.
+    let bars = testimg::color_bars(64, 48, 8).unwrap();
+    let encoded = encode_with(&bars, &config, stop()).unwrap();
+    let decoded = zenavif::decode(&encoded.avif_file).unwrap().to_rgba8();
+    let white = decoded.as_imgref().buf()[24 * 64 + 4];
+    for channel in [white.r, white.g, white.b] {
+        assert!(channel.abs_diff(191) <= 4, "{white:?}");
+    }
+}
+
+#[test]
+fn decode_with_progress_reports_stages() {
+    use zenavif::ProgressEvent;
+
+    let decode = |avif: &[u8]| {
+        let mut events = Vec::new();
+        zenavif::decode_with_progress(
+            avif,
+            &zenavif::DecoderConfig::new(),
+            &Unstoppable,
+            |event| events.push(event),
+        )
+        .unwrap();
+        events
+    };
+
+    let config = EncoderConfig::new().speed(10);
+    let still = encode_rgba8(make_rgba8_image().as_ref(), &config, stop()).unwrap();
+    assert_eq!(
+        decode(&still.avif_file),
+        [
+            ProgressEvent::Parse,
+            ProgressEvent::PrimaryDecode,
+            ProgressEvent::AlphaDecode,
+            ProgressEvent::ColorConversion,
+        ]
+    );
+
+    let bars = zenavif::testimg::color_bars(160, 100, 8).unwrap();
+    let grid = encode_with(&bars, &config.grid_threshold(64), stop()).unwrap();
+    let mut expected = vec![ProgressEvent::Parse];
+    expected.extend((0..6).map(|index| ProgressEvent::Tile { index, count: 6 }));
+    assert_eq!(decode(&grid.avif_file), expected);
+}
+
+#[test]
+fn decoder_max_memory_bytes_fails_before_decoding() {
+    let img = make_rgba8_image();
+    let config = EncoderConfig::new().speed(10);
+    let encoded = encode_rgba8(img.as_ref(), &config, stop()).unwrap();
+
+    let tight = zenavif::DecoderConfig::new().max_memory_bytes(1024);
+    let Err(err) = zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &tight) else {
+        panic!("1 KiB cannot hold a 16x16 RGBA decode");
+    };
+    match err.error() {
+        zenavif::Error::ResourceLimitExceeded { estimated, limit } => {
+            assert_eq!(*limit, 1024);
+            assert!(*estimated > 16 * 16 * 4, "estimate {estimated}");
+        }
+        other => panic!("unexpected error: {other:?}"),
+    }
+
+    let generous = zenavif::DecoderConfig::new().max_memory_bytes(1 << 20);
+    let decoded = zenavif::decode_with(&encoded.avif_file, &generous, &Unstoppable).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (16, 16));
+}
+
+#[test]
+fn max_memory_bytes_rejects_oversized_encode() {
+    let img = make_rgb8_image();
+    let config = EncoderConfig::new().speed(10).max_memory_bytes(1024);
+    let err = encode_rgb8(img.as_ref(), &config, stop()).expect_err("1 KiB cannot fit");
+    assert!(
+        matches!(err.error(), zenavif::Error::ResourceLimit(_)),
+        "expected ResourceLimit, got {err}"
+    );
+}
+
+#[test]
+fn max_memory_bytes_generous_limit_encodes() {
+    let pixels: Vec<Rgba<u8>> = (0..512 * 512)
+        .map(|i| Rgba {
+            r: (i % 512 / 2) as u8,
+            g: (i / 512 / 2) as u8,
+            b: 128,
+            a: 255,
+        })
+        .collect();
+    let img = Img::new(pixels, 512, 512);
+    let config = EncoderConfig::new()
+        .speed(10)
+        .threads(Some(64))
+        .max_memory_bytes(64 << 20);
+    // 64 threads would split the image into 16 tiles whose state exceeds
+    // 64 MiB; the encoder drops threads instead of failing.
+    let encoded = encode_rgba8(img.as_ref(), &config, stop()).expect("encode should succeed");
+    assert!(!encoded.avif_file.is_empty());
+}
+
+#[test]
+fn rgb_with_separate_alpha_plane_matches_rgba() {
+    let rgba = make_rgba8_image();
+    let color: Vec<Rgb<u8>> = rgba
+        .pixels()
+        .map(|p| Rgb {
+            r: p.r,
+            g: p.g,
+            b: p.b,
+        })
+        .collect();
+    let alpha: Vec<rgb::Gray<u8>> = rgba.pixels().map(|p| rgb::Gray::new(p.a)).collect();
+    let color = Img::new(color, 16, 16);
+    let alpha = Img::new(alpha, 16, 16);
+    let config = EncoderConfig::new()
+        .quality(80.0)
+        .speed(10)
+        .threads(Some(1));
+
+    let split = encode_rgb_with_alpha_plane(color.as_ref(), alpha.as_ref(), &config, stop())
+        .expect("encode should succeed");
+    let joined = encode_rgba8(rgba.as_ref(), &config, stop()).expect("encode should succeed");
+    assert_eq!(split.avif_file, joined.avif_file);
+    assert!(split.alpha_byte_size > 0);
+}
+
+#[test]
+fn rgb_with_alpha_plane_rejects_size_mismatch() {
+    let color = make_rgb8_image();
+    let alpha = Img::new(vec![rgb::Gray::new(255u8); 4], 2, 2);
+    let result = encode_rgb_with_alpha_plane(
+        color.as_ref(),
+        alpha.as_ref(),
+        &EncoderConfig::new(),
+        stop(),
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn post_process_hook_runs_before_return() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let img = make_rgb8_image();
+    let encoded = encode_rgb8(img.as_ref(), &EncoderConfig::new().speed(10), stop()).unwrap();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let seen = Arc::clone(&calls);
+    let config = zenavif::DecoderConfig::new().post_process(move |pixels, info| {
+        assert_eq!(pixels.width(), info.width);
+        seen.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    });
+    let decoded = zenavif::decode_with(&encoded.avif_file, &config, &Unstoppable).unwrap();
+    assert_eq!(decoded.width(), 16);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let failing = zenavif::DecoderConfig::new()
+        .post_process(|_, _| Err(zenavif::Error::Unsupported("rejected by hook")));
+    let err = zenavif::decode_with(&encoded.avif_file, &failing, &Unstoppable).unwrap_err();
+    assert!(matches!(err.error(), zenavif::Error::Unsupported(_)));
+}
+
+#[test]
+fn probe_batch_keeps_input_order() {
+    let config = EncoderConfig::new().speed(10);
+    let rgb = encode_rgb8(make_rgb8_image().as_ref(), &config, stop()).unwrap();
+    let rgba = encode_rgba8(make_rgba8_image().as_ref(), &config, stop()).unwrap();
+    let inputs = [rgb.avif_file, b"not an avif file".to_vec(), rgba.avif_file];
+
+    for threads in [1, 2, 0] {
+        let results = zenavif::probe_batch(&inputs, threads);
+        assert_eq!(results.len(), 3);
+        let first = results[0].as_ref().unwrap();
+        assert_eq!((first.width, first.height), (16, 16));
+        assert!(!first.has_alpha);
+        assert!(results[1].is_err());
+        assert!(results[2].as_ref().unwrap().has_alpha);
+    }
+}
+
+#[test]
+fn probe_matches_decoder_probe_info() {
+    let config = EncoderConfig::new().speed(10);
+    let encoded = encode_rgba8(make_rgba8_image().as_ref(), &config, stop()).unwrap();
+    let info = zenavif::probe(&encoded.avif_file).unwrap();
+    let decoder =
+        zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &zenavif::DecoderConfig::new())
+            .unwrap();
+    let expected = decoder.probe_info().unwrap();
+    assert_eq!((info.width, info.height), (expected.width, expected.height));
+    assert_eq!(info.bit_depth, expected.bit_depth);
+    assert_eq!(info.has_alpha, expected.has_alpha);
+    assert_eq!(info.chroma_sampling, expected.chroma_sampling);
+    assert!(zenavif::probe(b"not an avif file").is_err());
+}
+
+#[test]
+fn borrowed_decoder_matches_owned() {
+    let config = EncoderConfig::new().speed(10);
+    let encoded = encode_rgba8(make_rgba8_image().as_ref(), &config, stop()).unwrap();
+    let decoder_config = zenavif::DecoderConfig::new();
+
+    let mut owned = zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &decoder_config).unwrap();
+    let mut borrowed =
+        zenavif::ManagedAvifDecoder::from_slice(&encoded.avif_file, &decoder_config).unwrap();
+    let owned = owned.decode(&Unstoppable).unwrap();
+    let borrowed = borrowed.decode(&Unstoppable).unwrap();
+    assert_eq!(
+        owned.as_slice().contiguous_bytes(),
+        borrowed.as_slice().contiguous_bytes()
+    );
+}
+
+#[test]
+fn output_range_preserves_video_range() {
+    let black = Img::new(vec![Rgb { r: 0u8, g: 0, b: 0 }; 16 * 16], 16, 16);
+    let config = EncoderConfig::new()
+        .bit_depth(EncodeBitDepth::Eight)
+        .pixel_range(EncodePixelRange::Limited)
+        .quality(100.0)
+        .speed(10);
+    let encoded = encode_rgb8(black.as_ref(), &config, stop()).unwrap();
+
+    let decode = |range| {
+        let config = zenavif::DecoderConfig::new().output_range(range);
+        let mut decoder = zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &config).unwrap();
+        let (pixels, info) = decoder.decode_full(&Unstoppable).unwrap();
+        assert_eq!(info.color_range, ColorRange::Limited);
+        pixels.try_as_imgref::<Rgb<u8>>().unwrap().buf()[8 * 16 + 8]
+    };
+    let full = decode(zenavif::OutputRange::Full);
+    let limited = decode(zenavif::OutputRange::PreserveLimited);
+    assert!(full.g <= 2, "{full:?}");
+    assert!((15..=18).contains(&limited.g), "{limited:?}");
+}
+
+#[test]
+fn encoder_output_range_is_signalled() {
+    let img = make_rgb8_image();
+    for range in [ColorRange::Full, ColorRange::Limited] {
+        let config = EncoderConfig::new()
+            .bit_depth(EncodeBitDepth::Eight)
+            .output_range(range)
+            .speed(10);
+        let encoded = encode_rgb8(img.as_ref(), &config, stop()).unwrap();
+        assert_eq!(
+            zenavif::probe(&encoded.avif_file).unwrap().color_range,
+            range
+        );
+    }
+}
+
+#[test]
+fn encoder_chroma_choice_is_honored_or_rejected() {
+    let img = make_rgb8_image();
+    let config = EncoderConfig::new().chroma(ChromaSampling::Cs444).speed(10);
+    let encoded = encode_rgb8(img.as_ref(), &config, stop()).unwrap();
+    let info = zenavif::probe(&encoded.avif_file).unwrap();
+    assert_eq!(info.chroma_sampling, ChromaSampling::Cs444);
+
+    for sampling in [ChromaSampling::Cs422, ChromaSampling::Monochrome] {
+        let config = EncoderConfig::new().chroma(sampling);
+        assert_ne!(config.cache_key(), EncoderConfig::new().cache_key());
+        let err = encode_rgb8(img.as_ref(), &config, stop()).unwrap_err();
+        assert!(matches!(err.error(), zenavif::Error::Unsupported(_)));
+    }
+}
+
+#[test]
+fn cs420_roundtrips_as_subsampled_stream() {
+    let img = make_rgb8_image();
+    let config = EncoderConfig::new().chroma(ChromaSampling::Cs420).speed(10);
+    let encoded = encode_rgb8(img.as_ref(), &config, stop()).unwrap();
+    let info = zenavif::probe(&encoded.avif_file).unwrap();
+    assert_eq!(info.chroma_sampling, ChromaSampling::Cs420);
+    let decoded = zenavif::decode(&encoded.avif_file).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (16, 16));
+
+    let identity = config.color_model(EncodeColorModel::Rgb);
+    let err = encode_rgb8(img.as_ref(), &identity, stop()).unwrap_err();
+    assert!(matches!(err.error(), zenavif::Error::Unsupported(_)));
+}
+
+#[test]
+fn forced_8bit_output_reports_lossy_conversion() {
+    let gray = Img::new(
+        vec![
+            Rgb {
+                r: 128u8,
+                g: 128,
+                b: 128
+            };
+            16 * 16
+        ],
+        16,
+        16,
+    );
+    let config = EncoderConfig::new()
+        .bit_depth(EncodeBitDepth::Ten)
+        .quality(90.0)
+        .speed(10);
+    let encoded = encode_rgb8(gray.as_ref(), &config, stop()).unwrap();
+
+    for (depth, lossy) in [
+        (zenavif::OutputDepth::Auto, false),
+        (zenavif::OutputDepth::Force8, true),
+    ] {
+        let config = zenavif::DecoderConfig::new()
+            .output_depth(depth)
+            .dither(true);
+        let mut decoder = zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &config).unwrap();
+        let (_, info) = decoder.decode_full(&Unstoppable).unwrap();
+        assert_eq!(info.bit_depth, 10);
+        assert_eq!(info.lossy_conversion, lossy, "{depth:?}");
+    }
+}
+
+#[test]
+fn rgb16_signal_format_keeps_colors() {
+    let red = Img::new(
+        vec![
+            Rgb {
+                r: 60000u16,
+                g: 8000,
+                b: 8000,
+            };
+            16 * 16
+        ],
+        16,
+        16,
+    );
+    let configs = [
+        EncoderConfig::new(),
+        EncoderConfig::new()
+            .matrix_coefficients(1)
+            .pixel_range(EncodePixelRange::Limited),
+        EncoderConfig::new().color_model(EncodeColorModel::Rgb),
+    ];
+    for (config, (matrix, range)) in configs.iter().zip([
+        (MatrixCoefficients::BT601, ColorRange::Full),
+        (MatrixCoefficients::BT709, ColorRange::Limited),
+        (MatrixCoefficients::IDENTITY, ColorRange::Full),
+    ]) {
+        let config = config.clone().quality(95.0).speed(10);
+        let encoded = encode_rgb16(red.as_ref(), &config, stop()).unwrap();
+        let mut decoder =
+            zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &Default::default()).unwrap();
+        let (pixels, info) = decoder.decode_full(&Unstoppable).unwrap();
+        assert_eq!(
+            (info.matrix_coefficients, info.color_range),
+            (matrix, range)
+        );
+
+        let img = pixels.try_as_imgref::<Rgb<u16>>().unwrap();
+        let px = img.buf()[8 * 16 + 8];
+        assert!(
+            px.r > 50000 && px.g < 16000 && px.b < 16000,
+            "{px:?} for {matrix:?}"
+        );
+    }
+}
+
+#[test]
+fn contradictory_signal_format_is_rejected() {
+    let img = make_rgb16_image();
+    let config = EncoderConfig::new()
+        .color_model(EncodeColorModel::Rgb)
+        .matrix_coefficients(1);
+    let err = encode_rgb16(img.as_ref(), &config, stop()).unwrap_err();
+    assert!(matches!(err.error(), zenavif::Error::Unsupported(_)));
+}
+
+#[test]
+fn rgb8_keeps_matrices_ravif_cannot_convert() {
+    // ravif converts 8-bit input with BT.601 only; BT.2020 takes the
+    // planar path instead.
+    let config = EncoderConfig::new().speed(10).matrix_coefficients(9);
+    let encoded = encode_rgb8(make_rgb8_image().as_ref(), &config, stop()).unwrap();
+    let info = zenavif::probe(&encoded.avif_file).unwrap();
+    assert_eq!(info.matrix_coefficients, MatrixCoefficients::BT2020_NCL);
+}
+
+#[test]
+fn decode_yuv_returns_coded_planes() {
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgba8(make_rgba8_image().as_ref(), &config, stop()).unwrap();
+    let mut decoder =
+        zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &Default::default()).unwrap();
+    let yuv = decoder.decode_yuv(&Unstoppable).unwrap();
+
+    assert_eq!(yuv.info.chroma_sampling, ChromaSampling::Cs444);
+    assert_eq!(yuv.info.bit_depth, 8);
+    for plane in [
+        Some(&yuv.y),
+        yuv.u.as_ref(),
+        yuv.v.as_ref(),
+        yuv.alpha.as_ref(),
+    ] {
+        let plane = plane.expect("all four planes should be present");
+        assert!(matches!(plane, YuvPlane::U8(_)));
+        assert_eq!(
+            (plane.width(), plane.height(), plane.stride()),
+            (16, 16, 16)
+        );
+    }
+    let YuvPlane::U8(alpha) = yuv.alpha.unwrap() else {
+        unreachable!()
+    };
+    assert!(alpha.buf().iter().all(|&a| a.abs_diff(200) <= 2));
+}
+
+#[test]
+fn decode_planar_matches_interleaved_decode() {
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgba8(make_rgba8_image().as_ref(), &config, stop()).unwrap();
+    let decoder_config = zenavif::DecoderConfig::new();
+    let planar = zenavif::decode_planar(
+        &encoded.avif_file,
+        &decoder_config,
+        &zenavif::PlanarOptions::new(),
+        &Unstoppable,
+    )
+    .unwrap();
+    assert_eq!((planar.width, planar.height, planar.channels), (16, 16, 4));
+
+    let full = zenavif::decode(&encoded.avif_file).unwrap();
+    let full = full.try_as_imgref::<Rgba<u8>>().unwrap();
+    let planes: Vec<&[u8]> = (0..4).map(|c| planar.plane_u8(c).unwrap()).collect();
+    for (i, p) in full.pixels().enumerate() {
+        let expected = [p.r, p.g, p.b, p.a];
+        for (c, plane) in planes.iter().enumerate() {
+            assert!(
+                plane[i].abs_diff(expected[c]) <= 3,
+                "pixel {i} channel {c}: planar {} vs interleaved {}",
+                plane[i],
+                expected[c]
+            );
+        }
+    }
+
+    let float = zenavif::decode_planar(
+        &encoded.avif_file,
+        &decoder_config,
+        &zenavif::PlanarOptions::new().format(zenavif::PlanarFormat::F32),
+        &Unstoppable,
+    )
+    .unwrap();
+    for (c, plane) in planes.iter().enumerate() {
+        for (&f, &u) in float.plane_f32(c).unwrap().iter().zip(*plane) {
+            assert!((f * 255.0 - f32::from(u)).abs() <= 0.5 + 1e-3);
+        }
+    }
+
+    let hwc = zenavif::decode_planar(
+        &encoded.avif_file,
+        &decoder_config,
+        &zenavif::PlanarOptions::new().layout(zenavif::PlanarLayout::Hwc),
+        &Unstoppable,
+    )
+    .unwrap();
+    let zenavif::PlanarSamples::U8(interleaved) = &hwc.samples else {
+        panic!("expected 8-bit samples");
+    };
+    for (i, pixel) in interleaved.chunks_exact(4).enumerate() {
+        for (c, plane) in planes.iter().enumerate() {
+            assert_eq!(pixel[c], plane[i], "pixel {i} channel {c}");
+        }
+    }
+}
+
+#[test]
+fn downscale_after_decode_reduces_by_whole_factor() {
+    let pixels: Vec<Rgb<u8>> = (0..64 * 48)
+        .map(|i| Rgb::new((i % 64 * 4) as u8, (i / 64 * 5) as u8, 80))
+        .collect();
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgb8(Img::new(pixels.as_slice(), 64, 48), &config, stop()).unwrap();
+
+    let decode = |config: &zenavif::DecoderConfig| {
+        zenavif::decode_image_with(&encoded.avif_file, config, &Unstoppable).unwrap()
+    };
+    // min(64 / 16, 48 / 16) = 3
+    let thumb = decode(&zenavif::DecoderConfig::new().downscale_after_decode(16, 16));
+    assert_eq!((thumb.pixels.width(), thumb.pixels.height()), (22, 16));
+    assert_eq!((thumb.info.width, thumb.info.height), (64, 48));
+
+    let full = decode(&zenavif::DecoderConfig::new().downscale_after_decode(40, 40));
+    assert_eq!((full.pixels.width(), full.pixels.height()), (64, 48));
+}
+
+#[test]
+fn rgba_decodes_alike_with_and_without_parallel_alpha() {
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgba8(make_rgba8_image().as_ref(), &config, stop()).unwrap();
+
+    let decode = |config: &zenavif::DecoderConfig| {
+        zenavif::decode_with(&encoded.avif_file, config, &Unstoppable).unwrap()
+    };
+    // A single thread decodes color and alpha one after the other.
+    let sequential = decode(&zenavif::DecoderConfig::new().threads(1));
+    let parallel = decode(&zenavif::DecoderConfig::new().threads(4));
+    assert!(parallel.has_alpha());
+    assert_eq!(
+        sequential.as_slice().contiguous_bytes(),
+        parallel.as_slice().contiguous_bytes()
+    );
+}
+
+#[test]
+fn output_layouts_reorder_channels() {
+    use zenavif::OutputLayout;
+
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgba8(make_rgba8_image().as_ref(), &config, stop()).unwrap();
+    let decoder = |layout| {
+        let config = zenavif::DecoderConfig::new().output_layout(layout);
+        zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &config).unwrap()
+    };
+
+    let rgba = decoder(OutputLayout::Rgb).decode(&Unstoppable).unwrap();
+    let rgba = rgba.as_slice().contiguous_bytes().to_vec();
+
+    let bgra = decoder(OutputLayout::Bgra8).decode(&Unstoppable).unwrap();
+    assert!(
+        bgra.descriptor()
+            .layout_compatible(zenpixels::PixelDescriptor::BGRA8_SRGB)
+    );
+    let bgra = bgra.as_slice().contiguous_bytes().to_vec();
+    for (s, d) in rgba.chunks_exact(4).zip(bgra.chunks_exact(4)) {
+        assert_eq!([s[2], s[1], s[0], s[3]], d);
+    }
+
+    let mut argb = vec![0; 16 * 16 * 4];
+    decoder(OutputLayout::Argb8)
+        .decode_into_layout(&mut argb, 16 * 4, &Unstoppable)
+        .unwrap();
+    for (s, d) in rgba.chunks_exact(4).zip(argb.chunks_exact(4)) {
+        assert_eq!([s[3], s[0], s[1], s[2]], d);
+    }
+
+    // Padded rows; alpha is dropped.
+    let stride = 16 * 3 + 5;
+    let mut bgr = vec![0; stride * 16];
+    decoder(OutputLayout::Bgr8)
+        .decode_into_layout(&mut bgr, stride, &Unstoppable)
+        .unwrap();
+    for (y, row) in bgr.chunks(stride).enumerate() {
+        let src = &rgba[y * 16 * 4..][..16 * 4];
+        for (s, d) in src.chunks_exact(4).zip(row[..16 * 3].chunks_exact(3)) {
+            assert_eq!([s[2], s[1], s[0]], d);
+        }
+    }
+
+    assert!(decoder(OutputLayout::Bgr8).decode(&Unstoppable).is_err());
+}
+
+#[test]
+fn alpha_quality_follows_alpha_complexity() {
+    let with_alpha = |alpha: fn(usize, usize) -> u8| {
+        let pixels = (0..32 * 32)
+            .map(|i| Rgba::new(90u8, 140, 30, alpha(i % 32, i / 32)))
+            .collect();
+        Img::new(pixels, 32, 32)
+    };
+    let mask = with_alpha(|x, y| if x + y < 32 { 255 } else { 0 });
+    let ramp = with_alpha(|x, y| (x * 4 + y * 3) as u8);
+
+    let config = EncoderConfig::new()
+        .quality(60.0)
+        .speed(10)
+        .threads(Some(1));
+    let encode = |img: &Img<Vec<Rgba<u8>>>, config: &EncoderConfig| {
+        encode_rgba8(img.as_ref(), config, stop())
+            .unwrap()
+            .avif_file
+    };
+    // Masks are coded near-losslessly; gradients halfway to 100.
+    assert_eq!(
+        encode(&mask, &config),
+        encode(&mask, &config.clone().alpha_quality(100.0))
+    );
+    assert_eq!(
+        encode(&ramp, &config),
+        encode(&ramp, &config.clone().alpha_quality(80.0))
+    );
+    assert_ne!(
+        encode(&ramp, &config),
+        encode(&ramp, &config.clone().alpha_quality(60.0))
+    );
+}
+
+#[test]
+fn auto_alpha_mode_keeps_colors_behind_soft_edges() {
+    use zenavif::EncodeAlphaMode;
+
+    let with_alpha = |alpha: fn(usize) -> u8| {
+        let pixels = (0..32 * 32)
+            .map(|i| {
+                let (x, y) = (i % 32, i / 32);
+                Rgba::new((x * 37 + y * 91) as u8, (y * 13) as u8, 200, alpha(x))
+            })
+            .collect();
+        Img::new(pixels, 32, 32)
+    };
+    let cut_out = with_alpha(|x| if x < 16 { 0 } else { 255 });
+    let soft = with_alpha(|x| (x.saturating_sub(8) * 40).min(255) as u8);
+
+    let config = EncoderConfig::new()
+        .quality(70.0)
+        .speed(10)
+        .threads(Some(1));
+    let encode = |img: &Img<Vec<Rgba<u8>>>, mode| {
+        let config = config.clone().alpha_color_mode(mode);
+        encode_rgba8(img.as_ref(), &config, stop())
+            .unwrap()
+            .avif_file
+    };
+    assert_eq!(
+        encode(&cut_out, EncodeAlphaMode::Auto),
+        encode(&cut_out, EncodeAlphaMode::UnassociatedClean)
+    );
+    assert_eq!(
+        encode(&soft, EncodeAlphaMode::Auto),
+        encode(&soft, EncodeAlphaMode::UnassociatedDirty)
+    );
+}
+
+#[test]
+fn decode_semi_planar_interleaves_chroma() {
+    use zenavif::{
+        AnimationFrameYuv420, ColorPrimaries, SemiPlanarOrder, TransferCharacteristics, YuvCicp,
+        encode_animation_yuv420,
+    };
+
+    // Odd size: chroma rounds up to 17x9. rav1e's sequence encoder needs
+    // at least 16 pixels in each direction.
+    let (w, h) = (33, 17);
+    let y: Vec<u8> = (0..w * h).map(|i| (i * 3) as u8).collect();
+    let cb: Vec<u8> = (0..17 * 9).map(|i| 60 + i as u8).collect();
+    let cr: Vec<u8> = (0..17 * 9).map(|i| 200 - i as u8).collect();
+    let frame = AnimationFrameYuv420 {
+        y: Img::new(&y[..], w, h),
+        cb: Img::new(&cb[..], 17, 9),
+        cr: Img::new(&cr[..], 17, 9),
+        duration_ms: 40,
+    };
+    let cicp = YuvCicp {
+        color_primaries: ColorPrimaries::BT709,
+        transfer_characteristics: TransferCharacteristics::BT709,
+        matrix_coefficients: MatrixCoefficients::BT709,
+        range: ColorRange::Limited,
+    };
+    let config = EncoderConfig::new().quality(90.0).speed(10);
+    let encoded = encode_animation_yuv420(&[frame], cicp, &config, stop()).unwrap();
+    let decoder =
+        || zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &Default::default()).unwrap();
+
+    let yuv = decoder().decode_yuv(&Unstoppable).unwrap();
+    assert_eq!(yuv.info.chroma_sampling, ChromaSampling::Cs420);
+    let samples = |plane: &YuvPlane| match plane {
+        YuvPlane::U8(img) => img.pixels().map(u16::from).collect::<Vec<_>>(),
+        YuvPlane::U16(img) => img.pixels().collect(),
+    };
+    let (u, v) = (samples(&yuv.u.unwrap()), samples(&yuv.v.unwrap()));
+
+    for order in [SemiPlanarOrder::Nv12, SemiPlanarOrder::Nv21] {
+        let nv = decoder().decode_semi_planar(order, &Unstoppable).unwrap();
+        assert_eq!(nv.order, order);
+        assert_eq!(samples(&nv.y), samples(&yuv.y));
+        assert_eq!((nv.uv.width(), nv.uv.height()), (34, 9));
+        let uv = samples(&nv.uv);
+        let (first, second) = match order {
+            SemiPlanarOrder::Nv12 => (&u, &v),
+            SemiPlanarOrder::Nv21 => (&v, &u),
+        };
+        for (i, pair) in uv.chunks_exact(2).enumerate() {
+            assert_eq!([pair[0], pair[1]], [first[i], second[i]]);
+        }
+    }
+
+    // 4:4:4 images have no NV12 form.
+    let encoded = encode_rgb8(make_rgb8_image().as_ref(), &config, stop()).unwrap();
+    let mut decoder =
+        zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &Default::default()).unwrap();
+    assert!(
+        decoder
+            .decode_semi_planar(SemiPlanarOrder::Nv12, &Unstoppable)
+            .is_err()
+    );
+}
+
+#[test]
+fn conversion_backends_agree_closely() {
+    // Limited range, so the libyuv-exact converters take it.
+    let config = EncoderConfig::new()
+        .bit_depth(EncodeBitDepth::Eight)
+        .pixel_range(EncodePixelRange::Limited)
+        .quality(90.0)
+        .speed(10);
+    let rgb = encode_rgb8(make_rgb8_image().as_ref(), &config, stop()).unwrap();
+    let rgba = encode_rgba8(make_rgba8_image().as_ref(), &config, stop()).unwrap();
+
+    for (encoded, channels) in [(&rgb, 3), (&rgba, 4)] {
+        let decode = |backend| {
+            let config = zenavif::DecoderConfig::new().conversion_backend(backend);
+            let mut decoder =
+                zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &config).unwrap();
+            let (pixels, info) = decoder.decode_full(&Unstoppable).unwrap();
+            assert_eq!(info.bit_depth, 8);
+            assert_eq!(pixels.has_alpha(), channels == 4);
+            pixels.as_slice().contiguous_bytes().to_vec()
+        };
+        let reference = decode(zenavif::ConversionBackend::Reference);
+        assert_eq!(reference.len(), 16 * 16 * channels);
+        for backend in [
+            zenavif::ConversionBackend::Exact,
+            zenavif::ConversionBackend::Fast,
+        ] {
+            let other = decode(backend);
+            assert_eq!(other.len(), reference.len(), "{backend:?}");
+            for (i, (&a, &b)) in reference.iter().zip(&other).enumerate() {
+                if channels == 4 && i % 4 == 3 {
+                    assert_eq!(a, b, "{backend:?} alpha at {i}");
+                } else {
+                    assert!(a.abs_diff(b) <= 4, "{backend:?} at {i}: {a} vs {b}");
+                }
+            }
+        }
+    }
+    assert!(["rav1d-safe", "rav1d-asm"].contains(&zenavif::CODEC_BACKEND));
+}
+
+#[test]
+fn identity_matrix_decodes_planes_as_gbr() {
+    let config = EncoderConfig::new()
+        .color_model(EncodeColorModel::Rgb)
+        .bit_depth(EncodeBitDepth::Eight)
+        .quality(100.0)
+        .speed(10);
+    let source = make_rgb8_image();
+    let encoded = encode_rgb8(source.as_ref(), &config, stop()).unwrap();
+    let mut decoder =
+        zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &Default::default()).unwrap();
+    let (pixels, info) = decoder.decode_full(&Unstoppable).unwrap();
+    assert_eq!(info.matrix_coefficients, MatrixCoefficients::IDENTITY);
+
+    let decoded = pixels.try_as_imgref::<Rgb<u8>>().unwrap();
+    for (a, b) in source.pixels().zip(decoded.pixels()) {
+        let diff =
+            a.r.abs_diff(b.r)
+                .max(a.g.abs_diff(b.g))
+                .max(a.b.abs_diff(b.b));
+        assert!(diff <= 4, "{a:?} decoded as {b:?}");
+    }
+}
+
+#[test]
+fn encode_yuv_keeps_planes_and_signalling() {
+    let (width, height) = (16, 16);
+    let y: Vec<u8> = (0..width * height)
+        .map(|i| (16 + i % width * 12 + i / width) as u8)
+        .collect();
+    let cb = [100u8; 8 * 8];
+    let cr = [150u8; 8 * 8];
+    let planes = zenavif::YuvPlanesRef {
+        y: Img::new(&y[..], width, height),
+        cb: Img::new(&cb[..], 8, 8),
+        cr: Img::new(&cr[..], 8, 8),
+        bit_depth: 8,
+        cicp: zenavif::YuvCicp {
+            color_primaries: zenavif::ColorPrimaries::BT709,
+            transfer_characteristics: zenavif::TransferCharacteristics::BT709,
+            matrix_coefficients: MatrixCoefficients::BT709,
+            range: ColorRange::Limited,
+        },
+    };
+    let config = EncoderConfig::new().quality(100.0).speed(10);
+    let encoded = zenavif::encode_yuv(planes, &config, stop()).unwrap();
+
+    let mut decoder =
+        zenavif::ManagedAvifDecoder::new(&encoded.avif_file, &Default::default()).unwrap();
+    let yuv = decoder.decode_yuv(&Unstoppable).unwrap();
+    assert_eq!(yuv.info.matrix_coefficients, MatrixCoefficients::BT709);
+    assert_eq!(yuv.info.color_range, ColorRange::Limited);
+    let YuvPlane::U16(luma) = yuv.y else {
+        panic!("expected 10-bit planes");
+    };
+    for (&a, b) in y.iter().zip(luma.pixels()) {
+        let diff = (u16::from(a) << 2).abs_diff(b);
+        assert!(diff <= 12, "luma {a} decoded as {b}");
+    }
+
+    let mismatched = zenavif::YuvPlanesRef {
+        cb: Img::new(&cb[..], 8, 4),
+        ..planes
+    };
+    assert!(zenavif::encode_yuv(mismatched, &config, stop()).is_err());
+}
+
+/// A flat 10-bit limited-range super-white (Y = 1000, nominal white 940).
+fn super_white_avif(config: &EncoderConfig) -> Vec<u8> {
+    let y = [1000u16; 16 * 16];
+    let chroma = [512u16; 8 * 8];
+    let planes = zenavif::YuvPlanesRef {
+        y: Img::new(&y[..], 16, 16),
+        cb: Img::new(&chroma[..], 8, 8),
+        cr: Img::new(&chroma[..], 8, 8),
+        bit_depth: 10,
+        cicp: zenavif::YuvCicp {
+            color_primaries: zenavif::ColorPrimaries::BT709,
+            transfer_characteristics: zenavif::TransferCharacteristics::BT709,
+            matrix_coefficients: MatrixCoefficients::BT709,
+            range: ColorRange::Limited,
+        },
+    };
+    zenavif::encode_yuv(planes, config, stop())
+        .unwrap()
+        .avif_file
+}
+
+#[test]
+fn preserve_overshoot_keeps_super_white_in_16_bit_video_range() {
+    let avif = super_white_avif(&EncoderConfig::new().quality(100.0).speed(10));
+    let decode = |clipping| {
+        let config = zenavif::DecoderConfig::new().range_clipping(clipping);
+        let pixels = zenavif::decode_with(&avif, &config, &Unstoppable).unwrap();
+        pixels.try_as_imgref::<Rgb<u16>>().unwrap().buf()[0]
+    };
+    assert_eq!(
+        decode(zenavif::RangeClipping::Clip),
+        Rgb::new(65535, 65535, 65535)
+    );
+    // 1000 << 6, above nominal white at 235 << 8
+    let kept = decode(zenavif::RangeClipping::PreserveOvershoot);
+    for c in [kept.r, kept.g, kept.b] {
+        assert!(c.abs_diff(64000) <= 2 << 6, "{kept:?}");
+    }
+}
+
+#[cfg(feature = "zencodec")]
+#[test]
+fn preserve_overshoot_carries_super_white_into_float_output() {
+    let avif = super_white_avif(&EncoderConfig::new().quality(100.0).speed(10));
+    let decode = |clipping| {
+        let mut config = zenavif::AvifDecoderConfig::new();
+        *config.inner_mut() = zenavif::DecoderConfig::new().range_clipping(clipping);
+        let mut out = Img::new(vec![Rgb::new(0.0f32, 0.0, 0.0); 16 * 16], 16, 16);
+        config.decode_into_rgb_f32(&avif, out.as_mut()).unwrap();
+        out.buf()[0]
+    };
+    let clipped = decode(zenavif::RangeClipping::Clip);
+    assert!((clipped.r - 1.0).abs() < 1e-3, "{clipped:?}");
+    let kept = decode(zenavif::RangeClipping::PreserveOvershoot);
+    assert!(kept.r > 1.05 && kept.g > 1.05 && kept.b > 1.05, "{kept:?}");
+}
+
+#[cfg(feature = "cms")]
+#[test]
+fn preserve_overshoot_skips_color_management() {
+    let icc = moxcms::ColorProfile::new_display_p3().encode().unwrap();
+    let avif = super_white_avif(
+        &EncoderConfig::new()
+            .quality(100.0)
+            .speed(10)
+            .icc_profile(icc),
+    );
+    let decode = |clipping| {
+        let config = zenavif::DecoderConfig::new()
+            .range_clipping(clipping)
+            .color_manage(zenavif::Destination::Srgb);
+        let mut decoder = zenavif::ManagedAvifDecoder::new(&avif, &config).unwrap();
+        decoder.decode_full(&Unstoppable).unwrap()
+    };
+    // Converted to sRGB, which carries no ICC profile
+    let (_, info) = decode(zenavif::RangeClipping::Clip);
+    assert!(info.icc_profile.is_none());
+    // Left in the source space and described as such
+    let (pixels, info) = decode(zenavif::RangeClipping::PreserveOvershoot);
+    assert!(info.icc_profile.is_some());
+    let kept = pixels.try_as_imgref::<Rgb<u16>>().unwrap().buf()[0];
+    assert!(kept.r.abs_diff(64000) <= 2 << 6, "{kept:?}");
+}
+
+#[test]
+fn decode_session_cancels_all_its_decodes() {
+    let img = make_rgb8_image();
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgb8(img.as_ref(), &config, stop()).unwrap();
+
+    let session = zenavif::DecodeSession::new(zenavif::DecoderConfig::new());
+    assert_eq!(session.decode(&encoded.avif_file).unwrap().width(), 16);
+    let group = session.group();
+    group.cancel();
+    let err = zenavif::decode_with(&encoded.avif_file, session.config(), &group).unwrap_err();
+    assert!(matches!(err.error(), zenavif::Error::Cancelled(_)));
+    assert!(session.decode_image(&encoded.avif_file).is_ok());
+
+    session.cancel();
+    let err = session.decode(&encoded.avif_file).unwrap_err();
+    assert!(matches!(err.error(), zenavif::Error::Cancelled(_)));
+}
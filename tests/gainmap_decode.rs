@@ -157,6 +157,43 @@ fn decode_full_no_gain_map() {
     );
 }
 
+// ============================================================================
+// Decoding and applying the gain map image
+// ============================================================================
+
+#[test]
+fn decode_with_gainmap_decodes_both_images() {
+    let data = require_vector!(load_vector(SEINE_SDR_GAINMAP));
+    let image = zenavif::decode_with_gainmap(&data, &DecoderConfig::default(), &Unstoppable)
+        .expect("decode should succeed");
+    assert!(image.gain_map.width() > 0 && image.gain_map.height() > 0);
+    assert!(image.gain_map.width() <= image.base.width());
+    assert!(image.metadata.is_multichannel);
+    assert!(image.alt_color_info.is_some());
+
+    // No headroom: the base rendition, which is SDR and stays within [0, 1].
+    let sdr = image.apply(0.0).expect("apply should succeed");
+    assert_eq!(sdr.width(), image.base.width() as usize);
+    assert!(sdr.pixels().all(|p| p.r <= 1.0 + 1e-3 && p.g <= 1.0 + 1e-3));
+
+    // Full headroom: highlights go above SDR white.
+    let hdr = image.apply(1.3).expect("apply should succeed");
+    let peak = |img: &imgref::ImgVec<rgb::Rgba<f32>>| {
+        img.pixels()
+            .map(|p| p.r.max(p.g).max(p.b))
+            .fold(0.0f32, f32::max)
+    };
+    assert!(peak(&hdr) > 1.0, "HDR peak {}", peak(&hdr));
+    assert!(peak(&hdr) > peak(&sdr));
+}
+
+#[test]
+fn decode_with_gainmap_requires_gain_map() {
+    let data = require_vector!(load_vector(WHITE_1X1));
+    let result = zenavif::decode_with_gainmap(&data, &DecoderConfig::default(), &Unstoppable);
+    assert!(result.is_err(), "image without gain map should be rejected");
+}
+
 // ============================================================================
 // Gain map metadata field validation
 // ============================================================================
@@ -204,3 +204,61 @@ fn encode_gain_map_with_exif_and_icc() {
     // EXIF also present
     assert!(parser.exif().is_some());
 }
+
+#[test]
+fn encode_with_gainmap_roundtrips_pixels_and_metadata() {
+    use zenavif::{DecoderConfig, PixelBuffer};
+
+    // Metadata as the parser reports it, from a file with a raw tmap payload.
+    let config = EncoderConfig::new().quality(80.0).speed(10).with_gain_map(
+        vec![0x12, 0x00, 0x0A, 0x0A, 0x00, 0x00, 0x00, 0x04, 0x2C, 0xC6],
+        4,
+        4,
+        8,
+        make_test_tmap_metadata(),
+    );
+    let with_raw = encode_rgb8(make_rgb8_image().as_ref(), &config, stop()).unwrap();
+    let metadata = zenavif_parse::AvifParser::from_bytes(&with_raw.avif_file)
+        .unwrap()
+        .gain_map_metadata()
+        .expect("gain map metadata should be present")
+        .clone();
+
+    let base: PixelBuffer = zenpixels::PixelBuffer::from_imgvec(make_rgb8_image()).into();
+    let gain: Vec<rgb::Gray<u8>> = (0..8 * 8).map(|i| rgb::Gray((i * 4) as u8)).collect();
+    let gain: PixelBuffer = zenpixels::PixelBuffer::from_pixels(gain, 8, 8)
+        .unwrap()
+        .into();
+    let encoded = zenavif::encode_with_gainmap(
+        &base,
+        &gain,
+        &metadata,
+        &EncoderConfig::new().quality(90.0).speed(10),
+        stop(),
+    )
+    .expect("encode with gain map should succeed");
+
+    let decoded =
+        zenavif::decode_with_gainmap(&encoded.avif_file, &DecoderConfig::new(), &Unstoppable)
+            .expect("decode with gain map should succeed");
+    assert_eq!((decoded.base.width(), decoded.base.height()), (16, 16));
+    assert_eq!(
+        (decoded.gain_map.width(), decoded.gain_map.height()),
+        (8, 8)
+    );
+    assert_eq!(decoded.metadata.is_multichannel, metadata.is_multichannel);
+    assert_eq!(
+        decoded.metadata.alternate_hdr_headroom_n,
+        metadata.alternate_hdr_headroom_n
+    );
+    assert_eq!(
+        decoded.metadata.channels[0].gain_map_max_n,
+        metadata.channels[0].gain_map_max_n
+    );
+
+    // The gain map ramps up, so full headroom brightens the last rows most.
+    let hdr = decoded.apply(1.0).expect("apply should succeed");
+    let sdr = decoded.apply(0.0).expect("apply should succeed");
+    let ratio = hdr.buf()[15 * 16 + 8].g / sdr.buf()[15 * 16 + 8].g.max(1e-6);
+    assert!(ratio > 1.5, "gain ratio {ratio}");
+}
@@ -0,0 +1,38 @@
+//! Layer selection for scalable AV1 through DecoderConfig::all_layers and
+//! DecoderConfig::prefer_lowest_resolution
+
+use almost_enough::Unstoppable;
+use zenavif::{DecoderConfig, ManagedAvifDecoder};
+
+#[test]
+fn layered_still_decodes_to_highest_layer() {
+    let path = "tests/vectors/libavif/tiger_3layer_3res.avif";
+    let Ok(data) = std::fs::read(path) else {
+        eprintln!("skipping: {path} not found (download with: just download-vectors)");
+        return;
+    };
+    let decode = |config: &DecoderConfig| {
+        let mut decoder = ManagedAvifDecoder::new(&data, config).unwrap();
+        let info = decoder.probe_info().unwrap();
+        let pixels = decoder.decode(&Unstoppable).unwrap();
+        (info, pixels)
+    };
+
+    let (info, highest) = decode(&DecoderConfig::new());
+    assert_eq!(
+        (highest.width(), highest.height()),
+        (info.width, info.height)
+    );
+
+    // Every layer is output, and the last one is the same highest layer.
+    let (_, all) = decode(&DecoderConfig::new().all_layers(true));
+    assert_eq!(
+        all.as_slice().contiguous_bytes(),
+        highest.as_slice().contiguous_bytes()
+    );
+
+    // The base layer is coded at a lower resolution.
+    let (_, lowest) = decode(&DecoderConfig::new().prefer_lowest_resolution(true));
+    assert!(lowest.width() < highest.width());
+    assert!(lowest.height() < highest.height());
+}
@@ -95,6 +95,61 @@ fn xmp_metadata_roundtrip() {
     );
 }
 
+#[test]
+fn metadata_added_after_encode() {
+    let img = make_test_image();
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgb8(img.as_ref(), &config, stop()).expect("encode should succeed");
+    let pixels = zenavif::decode(&encoded.avif_file).expect("decode should succeed");
+
+    let xmp = b"<x:xmpmeta xmlns:x='adobe:ns:meta/'/>";
+    let edited = encoded
+        .with_exif(&make_avif_exif())
+        .expect("EXIF should be added")
+        .with_xmp(xmp)
+        .expect("XMP should be added");
+    let info = ManagedAvifDecoder::new(&edited.avif_file, &DecoderConfig::default())
+        .expect("decoder should open")
+        .probe_info()
+        .expect("probe should succeed");
+    assert!(
+        info.exif
+            .expect("EXIF should be present")
+            .starts_with(b"II*\0")
+    );
+    assert_eq!(info.xmp.as_deref(), Some(&xmp[..]));
+
+    let repixels = zenavif::decode(&edited.avif_file).expect("decode should succeed");
+    assert_eq!(
+        repixels.as_slice().contiguous_bytes(),
+        pixels.as_slice().contiguous_bytes()
+    );
+    assert!(edited.with_exif(&make_avif_exif()).is_err());
+}
+
+#[test]
+fn hdr10_plus_added_after_encode() {
+    let img = make_test_image();
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgb8(img.as_ref(), &config, stop()).expect("encode should succeed");
+    let pixels = zenavif::decode(&encoded.avif_file).expect("decode should succeed");
+
+    // T.35 header for HDR10+, application version 1, then payload bytes.
+    let t35 = [0xb5, 0x00, 0x3c, 0x00, 0x01, 0x04, 0x01, 0x40, 0x00, 0x0f];
+    assert!(encoded.clone().with_hdr10_plus(&t35[..5]).is_err());
+    let edited = encoded
+        .with_hdr10_plus(&t35)
+        .expect("HDR10+ should be added");
+
+    let decoded = zenavif::decode_image(&edited.avif_file).expect("decode should succeed");
+    assert_eq!(decoded.info.hdr10_plus.as_deref(), Some(&t35[..]));
+    assert_eq!(
+        decoded.pixels.as_slice().contiguous_bytes(),
+        pixels.as_slice().contiguous_bytes()
+    );
+    assert!(edited.with_hdr10_plus(&t35).is_err());
+}
+
 #[test]
 fn cicp_color_primaries_roundtrip() {
     // BT.2020 primaries (9), PQ transfer (16)
@@ -300,6 +355,84 @@ fn decode_full_returns_metadata() {
     assert_eq!(info.transfer_characteristics.0, 13, "sRGB transfer");
 }
 
+#[test]
+fn decoded_image_reencode_keeps_metadata() {
+    let img = make_test_image();
+    let config = EncoderConfig::new()
+        .quality(80.0)
+        .speed(10)
+        .exif(make_avif_exif())
+        .color_primaries(12) // Display P3
+        .transfer_characteristics(16) // PQ
+        .rotation(1)
+        .content_light_level(1000, 400);
+    let encoded = encode_rgb8(img.as_ref(), &config, stop()).expect("encode should succeed");
+
+    let image = zenavif::decode_image(&encoded.avif_file).expect("decode should succeed");
+    assert_eq!(image.pixels.width(), image.info.width);
+
+    // Nothing set on the re-encode config: everything comes from the image.
+    let reencoded = zenavif::encode_image(&image, &EncoderConfig::new().speed(10), stop())
+        .expect("re-encode should succeed");
+    let info = ManagedAvifDecoder::new(&reencoded.avif_file, &DecoderConfig::default())
+        .expect("decoder should open")
+        .probe_info()
+        .expect("probe should succeed");
+    assert_eq!(info.color_primaries.0, 12);
+    assert_eq!(info.transfer_characteristics.0, 16);
+    assert!(info.exif.is_some(), "EXIF should be carried over");
+    assert_eq!(info.rotation.map(|r| r.angle), Some(90));
+    assert_eq!(
+        info.content_light_level.map(|c| c.max_content_light_level),
+        Some(1000)
+    );
+
+    // Explicit settings win over the image's metadata.
+    let overridden = zenavif::encode_image(
+        &image,
+        &EncoderConfig::new().speed(10).transfer_characteristics(13),
+        stop(),
+    )
+    .expect("re-encode should succeed");
+    let info = ManagedAvifDecoder::new(&overridden.avif_file, &DecoderConfig::default())
+        .expect("decoder should open")
+        .probe_info()
+        .expect("probe should succeed");
+    assert_eq!(info.transfer_characteristics.0, 13);
+    assert_eq!(info.color_primaries.0, 12);
+}
+
+#[test]
+fn thumbnail_applies_orientation_and_strips_metadata() {
+    let pixels: Vec<Rgb<u8>> = (0..64 * 32)
+        .map(|i| Rgb::new((i % 64 * 4) as u8, (i / 64 * 8) as u8, 64))
+        .collect();
+    let config = EncoderConfig::new()
+        .quality(80.0)
+        .speed(10)
+        .exif(make_avif_exif())
+        .color_primaries(12)
+        .rotation(1);
+    let encoded = encode_rgb8(Img::new(pixels.as_slice(), 64, 32), &config, stop())
+        .expect("encode should succeed");
+
+    let options = zenavif::ThumbnailOptions::new().speed(10);
+    let thumb =
+        zenavif::thumbnail_unstoppable(&encoded.avif_file, 16, &options).expect("thumbnail");
+    let image = zenavif::decode_image(&thumb.avif_file).expect("decode should succeed");
+    // 64×32 shrinks to 16×8, then a quarter turn makes it 8×16.
+    assert_eq!((image.pixels.width(), image.pixels.height()), (8, 16));
+    assert!(image.info.rotation.is_none());
+    assert!(image.info.exif.is_none());
+    assert_eq!(image.info.color_primaries.0, 12);
+
+    let keep = options.keep_metadata(true);
+    let thumb = zenavif::thumbnail_unstoppable(&encoded.avif_file, 100, &keep).expect("thumbnail");
+    let info = zenavif::decode_image(&thumb.avif_file).unwrap().info;
+    assert_eq!((info.width, info.height), (32, 64));
+    assert!(info.exif.is_some());
+}
+
 #[test]
 fn cancellation_during_encode() {
     use enough::StopReason;
@@ -317,3 +450,76 @@ fn cancellation_during_encode() {
     let result = encode_rgb8(img.as_ref(), &config, StopToken::new(AlreadyStopped));
     assert!(result.is_err(), "encoding with cancelled token should fail");
 }
+
+#[cfg(feature = "zencodec")]
+#[test]
+fn linear_f32_decode_follows_transfer_characteristics() {
+    let gray = Img::new(
+        vec![
+            Rgb {
+                r: 128,
+                g: 128,
+                b: 128
+            };
+            16 * 16
+        ],
+        16,
+        16,
+    );
+    let decode_mid_gray = |tc: u8| {
+        let config = EncoderConfig::new()
+            .quality(95.0)
+            .speed(10)
+            .transfer_characteristics(tc);
+        let encoded = encode_rgb8(gray.as_ref(), &config, stop()).expect("encode should succeed");
+        let mut out = Img::new(vec![Rgb::new(0.0f32, 0.0, 0.0); 16 * 16], 16, 16);
+        zenavif::AvifDecoderConfig::new()
+            .decode_into_rgb_f32(&encoded.avif_file, out.as_mut())
+            .expect("decode should succeed");
+        out.buf()[8 * 16 + 8].g
+    };
+
+    // Code value 128/255: sRGB ≈ 0.216, PQ ≈ 0.0094 (94 cd/m² of 10 000),
+    // HLG ≈ 0.084 (inverse OETF).
+    let srgb = decode_mid_gray(13);
+    let pq = decode_mid_gray(16);
+    let hlg = decode_mid_gray(18);
+    assert!((srgb - 0.216).abs() < 0.01, "sRGB mid gray: {srgb}");
+    assert!((pq - 0.0094).abs() < 0.001, "PQ mid gray: {pq}");
+    assert!((hlg - 0.084).abs() < 0.01, "HLG mid gray: {hlg}");
+}
+
+#[test]
+fn pixi_mismatch_is_reported_and_rejected_when_strict() {
+    let img = make_test_image();
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let mut avif = encode_rgb8(img.as_ref(), &config, stop())
+        .expect("encode should succeed")
+        .avif_file;
+
+    let info = zenavif::decode_image(&avif)
+        .expect("decode should succeed")
+        .info;
+    let depth = info.bit_depth;
+    assert_eq!(info.declared_bits_per_channel, Some(vec![depth; 3]));
+    assert!(!info.bit_depth_mismatch());
+
+    // Relabel the primary item's pixi with a different depth.
+    let pos = avif
+        .windows(4)
+        .position(|w| w == b"pixi")
+        .expect("encoder should write pixi");
+    let wrong = if depth == 8 { 10 } else { 8 };
+    avif[pos + 9..pos + 12].fill(wrong);
+
+    let info = ManagedAvifDecoder::new(&avif, &DecoderConfig::default())
+        .expect("lenient decoder should open")
+        .probe_info()
+        .expect("probe should succeed");
+    assert_eq!(info.bit_depth, depth, "AV1 depth stays authoritative");
+    assert_eq!(info.declared_bits_per_channel, Some(vec![wrong; 3]));
+    assert!(info.bit_depth_mismatch());
+
+    let strict = DecoderConfig::new().strict_pixi(true);
+    assert!(ManagedAvifDecoder::new(&avif, &strict).is_err());
+}
@@ -0,0 +1,71 @@
+//! Grid decoding with corrupt tiles through DecoderConfig::error_resilient
+
+use almost_enough::Unstoppable;
+use rgb::Rgb;
+use zenavif::{DecoderConfig, ManagedAvifDecoder, TileFill};
+
+/// The sofa grid with every byte of tile `index` overwritten, so its first
+/// OBU header is invalid.
+fn corrupt_tile(data: &[u8], index: usize) -> Vec<u8> {
+    let parser = zenavif_parse::AvifParser::from_bytes(data).unwrap();
+    let tile = parser.tile_data(index).unwrap();
+    let start = data
+        .windows(tile.len())
+        .position(|window| window == &tile[..])
+        .unwrap();
+    let mut out = data.to_vec();
+    out[start..start + tile.len()].fill(0xff);
+    out
+}
+
+fn decode(data: &[u8], config: &DecoderConfig) -> zenavif::Result<zenpixels::PixelBuffer> {
+    ManagedAvifDecoder::new(data, config)?.decode(&Unstoppable)
+}
+
+#[test]
+fn failed_tile_is_filled_and_reported() {
+    let path = "tests/vectors/libavif/sofa_grid1x5_420.avif";
+    let Ok(data) = std::fs::read(path) else {
+        eprintln!("skipping: {path} not found (download with: just download-vectors)");
+        return;
+    };
+    let damaged = corrupt_tile(&data, 2);
+    let config = DecoderConfig::new().prefer_8bit(true);
+    assert!(decode(&damaged, &config).is_err());
+
+    let full = decode(&data, &config).unwrap();
+    let full = full.try_as_imgref::<Rgb<u8>>().unwrap();
+    let resilient = config
+        .clone()
+        .error_resilient(true)
+        .tile_fill(TileFill::Color([255, 0, 255, 255]));
+    let mut decoder = ManagedAvifDecoder::new(&damaged, &resilient).unwrap();
+    let (pixels, info) = decoder.decode_full(&Unstoppable).unwrap();
+    assert_eq!(info.failed_tiles, [2]);
+    assert_eq!(decoder.failed_tiles(), [2]);
+
+    // Only the damaged tile differs from the undamaged file.
+    let image = pixels.try_as_imgref::<Rgb<u8>>().unwrap();
+    let magenta = Rgb::new(255, 0, 255);
+    let mut painted = 0;
+    for (px, expected) in image.pixels().zip(full.pixels()) {
+        if px != expected {
+            assert_eq!(px, magenta);
+            painted += 1;
+        }
+    }
+    assert!(painted >= image.width() * image.height() / 10);
+
+    let averaged = config
+        .error_resilient(true)
+        .tile_fill(TileFill::NeighborAverage);
+    let pixels = decode(&damaged, &averaged).unwrap();
+    let image = pixels.try_as_imgref::<Rgb<u8>>().unwrap();
+    let mut filled = image
+        .pixels()
+        .zip(full.pixels())
+        .filter(|(px, expected)| px != expected)
+        .map(|(px, _)| px);
+    let first = filled.next().unwrap();
+    assert!(filled.all(|px| px == first));
+}
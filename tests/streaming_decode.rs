@@ -0,0 +1,98 @@
+//! Incremental decoding through StreamingAvifDecoder
+
+#![cfg(feature = "encode")]
+
+use almost_enough::{StopToken, Unstoppable};
+use imgref::Img;
+use rgb::Rgb;
+use std::io::Read;
+use zenavif::{DecoderConfig, EncoderConfig, StreamStatus, StreamingAvifDecoder, encode_rgb8};
+
+/// A small encoded image followed by a `free` box the decoder never needs.
+fn avif_with_trailer() -> (Vec<u8>, usize) {
+    let pixels: Vec<Rgb<u8>> = (0..32 * 24)
+        .map(|i| Rgb::new((i % 32 * 8) as u8, (i / 32 * 10) as u8, 96))
+        .collect();
+    let config = EncoderConfig::new().quality(80.0).speed(10);
+    let encoded = encode_rgb8(
+        Img::new(pixels.as_slice(), 32, 24),
+        &config,
+        StopToken::new(Unstoppable),
+    )
+    .unwrap();
+    let mut file = encoded.avif_file;
+    let image_len = file.len();
+    let trailer = 4096u32;
+    file.extend_from_slice(&trailer.to_be_bytes());
+    file.extend_from_slice(b"free");
+    file.resize(image_len + trailer as usize, 0);
+    (file, image_len)
+}
+
+#[test]
+fn push_bytes_becomes_ready_before_trailing_boxes() {
+    let (file, image_len) = avif_with_trailer();
+    let expected = zenavif::decode(&file).unwrap();
+
+    let mut decoder = StreamingAvifDecoder::new(&DecoderConfig::new());
+    let mut pushed = 0;
+    for chunk in file.chunks(100) {
+        if decoder.push_bytes(chunk).unwrap() == StreamStatus::Ready {
+            break;
+        }
+        assert!(decoder.decode(&Unstoppable).is_err());
+        pushed += chunk.len();
+    }
+    assert_eq!(decoder.status(), StreamStatus::Ready);
+    assert!(pushed < image_len);
+    assert!(decoder.buffered_len() <= image_len + 100);
+
+    let image = decoder.decode(&Unstoppable).unwrap();
+    assert_eq!(
+        image.as_slice().contiguous_bytes(),
+        expected.as_slice().contiguous_bytes()
+    );
+    let finished = decoder.finish(&Unstoppable).unwrap();
+    assert_eq!((finished.width(), finished.height()), (32, 24));
+}
+
+/// Counts how many bytes have been handed out.
+struct CountingReader<'a> {
+    data: &'a [u8],
+    read: usize,
+}
+
+impl Read for CountingReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(self.data.len() - self.read).min(512);
+        buf[..n].copy_from_slice(&self.data[self.read..self.read + n]);
+        self.read += n;
+        Ok(n)
+    }
+}
+
+#[test]
+fn decode_reader_stops_once_image_is_complete() {
+    let (file, image_len) = avif_with_trailer();
+    let mut reader = CountingReader {
+        data: &file,
+        read: 0,
+    };
+    let image =
+        StreamingAvifDecoder::decode_reader(&mut reader, &DecoderConfig::new(), &Unstoppable)
+            .unwrap();
+    assert_eq!((image.width(), image.height()), (32, 24));
+    assert!(reader.read < file.len());
+    assert!(reader.read <= image_len + 512);
+}
+
+#[test]
+fn truncated_input_fails_on_finish() {
+    let (file, image_len) = avif_with_trailer();
+    let mut decoder = StreamingAvifDecoder::new(&DecoderConfig::new());
+    assert_eq!(
+        decoder.push_bytes(&file[..image_len / 2]).unwrap(),
+        StreamStatus::NeedMoreData
+    );
+    assert!(decoder.finish(&Unstoppable).is_err());
+}